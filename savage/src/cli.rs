@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::str::FromStr;
+
+/// Command line option.
+pub struct Flag {
+    pub short: char,
+    pub long: &'static str,
+    /// Name of the value that the option takes, if any.
+    pub value: Option<&'static str>,
+    pub description: &'static str,
+}
+
+/// Command line subcommand.
+pub struct Subcommand {
+    pub name: &'static str,
+    /// Name and possible values of the argument that the subcommand takes.
+    pub argument: (&'static str, &'static [&'static str]),
+    pub description: &'static str,
+}
+
+pub const FLAGS: [Flag; 3] = [
+    Flag {
+        short: 'e',
+        long: "evaluate",
+        value: Some("EXPRESSION"),
+        description: "Evaluate an expression, print the result, and exit",
+    },
+    Flag {
+        short: 'h',
+        long: "help",
+        value: None,
+        description: "Print help information and exit",
+    },
+    Flag {
+        short: 'V',
+        long: "version",
+        value: None,
+        description: "Print version information and exit",
+    },
+];
+
+pub const SUBCOMMANDS: [Subcommand; 1] = [Subcommand {
+    name: "completions",
+    argument: ("SHELL", &["bash", "zsh", "fish"]),
+    description: "Print a completion script for the given shell",
+}];
+
+/// Shell for which a completion script can be generated.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!("unsupported shell '{}'", string)),
+        }
+    }
+}
+
+/// Action requested by the command line arguments.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Arguments {
+    Repl,
+    Evaluate(String),
+    Help,
+    Version,
+    Completions(Shell),
+}
+
+/// Returns the action requested by the given command line arguments
+/// (excluding the program name), or an error message if the arguments are invalid.
+pub fn parse(arguments: &[String]) -> Result<Arguments, String> {
+    let value = |index: usize| {
+        arguments
+            .get(index)
+            .cloned()
+            .ok_or_else(|| format!("missing value for '{}'", arguments[index - 1]))
+    };
+
+    let result = match arguments.first().map(String::as_str) {
+        None => Arguments::Repl,
+        Some("-e" | "--evaluate") => Arguments::Evaluate(value(1)?),
+        Some("-h" | "--help") => Arguments::Help,
+        Some("-V" | "--version") => Arguments::Version,
+        Some("completions") => Arguments::Completions(value(1)?.parse()?),
+        Some(argument) => return Err(format!("unexpected argument '{}'", argument)),
+    };
+
+    let expected_length = match result {
+        Arguments::Evaluate(_) | Arguments::Completions(_) => 2,
+        _ => arguments.len().min(1),
+    };
+
+    match arguments.get(expected_length) {
+        Some(argument) => Err(format!("unexpected argument '{}'", argument)),
+        None => Ok(result),
+    }
+}
+
+/// Returns the help text describing the command line arguments.
+pub fn help() -> String {
+    let mut lines = vec![
+        "Usage: savage [OPTIONS]".to_owned(),
+        "       savage <SUBCOMMAND>".to_owned(),
+        String::new(),
+        "Starts the REPL if no arguments are given.".to_owned(),
+        String::new(),
+        "Options:".to_owned(),
+    ];
+
+    let options = FLAGS
+        .iter()
+        .map(|flag| {
+            (
+                format!(
+                    "-{}, --{}{}",
+                    flag.short,
+                    flag.long,
+                    flag.value.map(|v| format!(" <{}>", v)).unwrap_or_default(),
+                ),
+                flag.description,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let subcommands = SUBCOMMANDS
+        .iter()
+        .map(|subcommand| {
+            (
+                format!("{} <{}>", subcommand.name, subcommand.argument.0),
+                subcommand.description,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let width = options
+        .iter()
+        .chain(&subcommands)
+        .map(|(usage, _)| usage.len())
+        .max()
+        .unwrap();
+
+    let format = |(usage, description): &(String, &str)| {
+        format!("  {:<width$}  {}", usage, description, width = width)
+    };
+
+    lines.extend(options.iter().map(format));
+    lines.push(String::new());
+    lines.push("Subcommands:".to_owned());
+    lines.extend(subcommands.iter().map(format));
+
+    lines.join("\n")
+}
+
+/// Returns a script that sets up completions of the command line arguments for the given shell.
+pub fn completions(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => {
+            let words = SUBCOMMANDS
+                .iter()
+                .map(|subcommand| subcommand.name.to_owned())
+                .chain(
+                    FLAGS
+                        .iter()
+                        .flat_map(|flag| [format!("-{}", flag.short), format!("--{}", flag.long)]),
+                )
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let mut cases = SUBCOMMANDS
+                .iter()
+                .map(|subcommand| {
+                    format!(
+                        "        {})\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            return\n            ;;\n",
+                        subcommand.name,
+                        subcommand.argument.1.join(" "),
+                    )
+                })
+                .collect::<String>();
+
+            for flag in FLAGS.iter().filter(|flag| flag.value.is_some()) {
+                cases.push_str(&format!(
+                    "        -{}|--{})\n            return\n            ;;\n",
+                    flag.short, flag.long,
+                ));
+            }
+
+            format!(
+                "_savage() {{
+    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+
+    case \"$prev\" in
+{}    esac
+
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))
+    fi
+}}
+
+complete -F _savage savage
+",
+                cases, words,
+            )
+        }
+        Shell::Zsh => {
+            let mut specs = FLAGS
+                .iter()
+                .map(|flag| {
+                    format!(
+                        "'(- *)'{{-{},--{}}}'[{}]{}'",
+                        flag.short,
+                        flag.long,
+                        flag.description,
+                        flag.value
+                            .map(|v| format!(":{}:", v.to_lowercase()))
+                            .unwrap_or_default(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            specs.push(format!(
+                "'1:subcommand:(({}))'",
+                SUBCOMMANDS
+                    .iter()
+                    .map(|subcommand| format!(
+                        "{}\\:\"{}\"",
+                        subcommand.name, subcommand.description,
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ));
+
+            for subcommand in &SUBCOMMANDS {
+                specs.push(format!(
+                    "'2:{}:({})'",
+                    subcommand.argument.0.to_lowercase(),
+                    subcommand.argument.1.join(" "),
+                ));
+            }
+
+            format!(
+                "#compdef savage
+
+_savage() {{
+    _arguments \\
+        {}
+}}
+
+_savage \"$@\"
+",
+                specs.join(" \\\n        "),
+            )
+        }
+        Shell::Fish => {
+            let mut lines = Vec::new();
+
+            for subcommand in &SUBCOMMANDS {
+                lines.push(format!(
+                    "complete -c savage -f -n __fish_use_subcommand -a {} -d '{}'",
+                    subcommand.name, subcommand.description,
+                ));
+
+                lines.push(format!(
+                    "complete -c savage -f -n '__fish_seen_subcommand_from {}' -a '{}'",
+                    subcommand.name,
+                    subcommand.argument.1.join(" "),
+                ));
+            }
+
+            for flag in &FLAGS {
+                lines.push(format!(
+                    "complete -c savage -f -n __fish_use_subcommand -s {} -l {}{} -d '{}'",
+                    flag.short,
+                    flag.long,
+                    if flag.value.is_some() { " -r" } else { "" },
+                    flag.description,
+                ));
+            }
+
+            lines.join("\n") + "\n"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{completions, parse, Arguments::*, Shell::*};
+
+    #[test]
+    fn arguments() {
+        let p = |arguments: &[&str]| {
+            parse(
+                &arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(p(&[]), Ok(Repl));
+        assert_eq!(p(&["-e", "1 + 2"]), Ok(Evaluate("1 + 2".to_owned())));
+        assert_eq!(p(&["--evaluate", "x"]), Ok(Evaluate("x".to_owned())));
+        assert_eq!(p(&["--help"]), Ok(Help));
+        assert_eq!(p(&["-V"]), Ok(Version));
+        assert_eq!(p(&["completions", "zsh"]), Ok(Completions(Zsh)));
+
+        assert_eq!(p(&["-e"]), Err("missing value for '-e'".to_owned()));
+        assert_eq!(
+            p(&["completions", "csh"]),
+            Err("unsupported shell 'csh'".to_owned())
+        );
+        assert_eq!(
+            p(&["--help", "x"]),
+            Err("unexpected argument 'x'".to_owned())
+        );
+        assert_eq!(p(&["run"]), Err("unexpected argument 'run'".to_owned()));
+    }
+
+    #[test]
+    fn completion_scripts() {
+        assert!(completions(Bash)
+            .contains("compgen -W \"completions -e --evaluate -h --help -V --version\""));
+        assert!(completions(Bash).contains("compgen -W \"bash zsh fish\""));
+        assert!(completions(Zsh).starts_with("#compdef savage\n"));
+        assert!(completions(Zsh).contains("'(- *)'{-e,--evaluate}'[Evaluate an expression, print the result, and exit]:expression:'"));
+        assert!(completions(Fish).contains(
+            "complete -c savage -f -n '__fish_seen_subcommand_from completions' -a 'bash zsh fish'"
+        ));
+    }
+}
@@ -4,6 +4,8 @@
 use std::str::FromStr;
 
 use chumsky::prelude::*;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
 use savage_core::{
     expression::Expression,
     parse::{parser as expression, Error},
@@ -13,8 +15,18 @@ use savage_core::{
 pub enum Command {
     EvaluateExpression(Expression),
     DefineVariable(String, Expression),
+    BindVariable(String, Expression),
     DefineFunction(String, Vec<String>, Expression),
     ShowHelp(Option<String>),
+    ShowVariables,
+    ShowFull,
+    Export(String),
+    Save(String),
+    Load(String),
+    Seed(u64),
+    Undo,
+    Redo,
+    Plot(Vec<Expression>, String, Expression, Expression),
 }
 
 fn parser() -> impl Parser<char, Command, Error = Error> {
@@ -23,6 +35,11 @@ fn parser() -> impl Parser<char, Command, Error = Error> {
         .then_ignore(just('='))
         .then(expression())
         .map(|(identifier, expression)| Command::DefineVariable(identifier, expression))
+        .or(text::ident()
+            .padded()
+            .then_ignore(just(":="))
+            .then(expression())
+            .map(|(identifier, expression)| Command::BindVariable(identifier, expression)))
         .or(text::ident()
             .padded()
             .then(
@@ -38,11 +55,84 @@ fn parser() -> impl Parser<char, Command, Error = Error> {
             .map(|((identifier, argument_identifiers), expression)| {
                 Command::DefineFunction(identifier, argument_identifiers, expression)
             }))
+        .or(text::keyword("plot")
+            .padded()
+            .ignore_then(
+                expression()
+                    .then_ignore(just(','))
+                    .then(text::ident().padded())
+                    .then_ignore(just(','))
+                    .then(expression())
+                    .then_ignore(just(','))
+                    .then(expression())
+                    .delimited_by(just('('), just(')')),
+            )
+            .padded()
+            .map(|(((functions, variable), a), b)| {
+                // A vector of functions is plotted as multiple series.
+                let functions = match functions {
+                    Expression::Vector(v) => v.iter().cloned().collect(),
+                    function => vec![function],
+                };
+
+                Command::Plot(functions, variable, a, b)
+            }))
         .or(expression().map(Command::EvaluateExpression))
         .or(just('?')
             .padded()
             .ignore_then(text::ident().padded().or_not())
             .map(Command::ShowHelp))
+        .or(just(':')
+            .padded()
+            .ignore_then(choice((
+                text::keyword("vars").to(Command::ShowVariables),
+                text::keyword("show")
+                    .then(text::keyword("full").padded())
+                    .to(Command::ShowFull),
+                text::keyword("export")
+                    .ignore_then(filter(|_| true).repeated().at_least(1).collect::<String>())
+                    .map(|path| Command::Export(path.trim().to_owned())),
+                text::keyword("save")
+                    .ignore_then(filter(|_| true).repeated().at_least(1).collect::<String>())
+                    .map(|path| Command::Save(path.trim().to_owned())),
+                text::keyword("load")
+                    .ignore_then(filter(|_| true).repeated().at_least(1).collect::<String>())
+                    .map(|path| Command::Load(path.trim().to_owned())),
+                text::keyword("seed")
+                    .ignore_then(text::int(10).padded())
+                    .try_map(|seed: String, span| {
+                        seed.parse()
+                            .map(Command::Seed)
+                            .map_err(|_| Simple::custom(span, "seed must be less than 2^64"))
+                    }),
+                text::keyword("undo").to(Command::Undo),
+                text::keyword("redo").to(Command::Redo),
+            )))
+            .padded())
+}
+
+/// Returns the given input with references to previous outputs replaced
+/// by the corresponding elements of the `Out` vector, given the number
+/// of previous outputs: `%n` refers to `Out[n]`, and `%` refers to the
+/// last output. `%` is treated as a reference only where an operand is
+/// expected, so that it continues to work as the remainder operator.
+pub fn expand_references(input: &str, output_count: usize) -> String {
+    lazy_static! {
+        static ref REGEX: Regex =
+            Regex::new(r"(^\s*|[(\[,+\-*/%^!=<>&|·×÷−¬∧∨√≤≥≠]\s*)%([0-9]*)").unwrap();
+    }
+
+    REGEX
+        .replace_all(input, |captures: &Captures| {
+            let index = if captures[2].is_empty() {
+                output_count.saturating_sub(1).to_string()
+            } else {
+                captures[2].to_owned()
+            };
+
+            format!("{}Out[{}]", &captures[1], index)
+        })
+        .into_owned()
 }
 
 impl FromStr for Command {
@@ -57,13 +147,29 @@ impl FromStr for Command {
 mod tests {
     use savage_core::helpers::*;
 
-    use crate::command::{Command, Command::*};
+    use crate::command::{expand_references, Command, Command::*};
 
     #[track_caller]
     fn t(string: &str, command: Command) {
         assert_eq!(string.parse(), Ok(command));
     }
 
+    #[test]
+    fn references() {
+        let e = |input: &str| expand_references(input, 5);
+
+        assert_eq!(e("%"), "Out[4]");
+        assert_eq!(e("%2 + %"), "Out[2] + Out[4]");
+        assert_eq!(e(" %2^2"), " Out[2]^2");
+        assert_eq!(e("f(%1, %3)"), "f(Out[1], Out[3])");
+        assert_eq!(e("a = % * 2"), "a = Out[4] * 2");
+        assert_eq!(e("7 % 3"), "7 % 3");
+        assert_eq!(e("7 %3"), "7 %3");
+        assert_eq!(e("7 % %3"), "7 % Out[3]");
+        assert_eq!(e("7 %%3"), "7 %Out[3]");
+        assert_eq!(e("(x) % 2"), "(x) % 2");
+    }
+
     #[test]
     fn parse() {
         t("   a ", EvaluateExpression(var("a")));
@@ -75,6 +181,12 @@ mod tests {
             DefineVariable("a".to_owned(), eq(var("b"), var("c"))),
         );
 
+        t(
+            "c := a + b",
+            BindVariable("c".to_owned(), var("a") + var("b")),
+        );
+        t(" c:=1 ", BindVariable("c".to_owned(), int(1)));
+
         t("f(  )= 1", DefineFunction("f".to_owned(), vec![], int(1)));
         t(
             " f (x) =x ^ 2",
@@ -93,8 +205,47 @@ mod tests {
             ),
         );
 
+        t(
+            "plot(x ^ 2, x, 0 - 1, 1)",
+            Plot(
+                vec![pow(var("x"), int(2))],
+                "x".to_owned(),
+                int(0) - int(1),
+                int(1),
+            ),
+        );
+        t(
+            " plot ( [x, 1 / x], x,1,2 ) ",
+            Plot(
+                vec![var("x"), int(1) / var("x")],
+                "x".to_owned(),
+                int(1),
+                int(2),
+            ),
+        );
+        t("plot", EvaluateExpression(var("plot")));
+        t(
+            "plotted(x)",
+            EvaluateExpression(fun(var("plotted"), [var("x")])),
+        );
+
         t(" ?  ", ShowHelp(None));
         t("?is_prime  ", ShowHelp(Some("is_prime".to_owned())));
         t("?  is_prime", ShowHelp(Some("is_prime".to_owned())));
+
+        t(":vars", ShowVariables);
+        t(" : vars ", ShowVariables);
+        t(":show   full", ShowFull);
+        t(":export  report.md ", Export("report.md".to_owned()));
+        t(
+            ":export my session.tex",
+            Export("my session.tex".to_owned()),
+        );
+        t(":save session.txt", Save("session.txt".to_owned()));
+        t(" :load  session.txt ", Load("session.txt".to_owned()));
+        t(":seed 42", Seed(42));
+        t(":seed 18446744073709551615 ", Seed(u64::MAX));
+        t(":undo", Undo);
+        t(":redo ", Redo);
     }
 }
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::str::FromStr;
+
+use rustyline::{EditMode, KeyCode, KeyEvent, Modifiers};
+
+/// Action that can be bound to a key at the prompt.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Action {
+    /// Insert the last result at the cursor position.
+    InsertPreviousResult,
+    /// Delete the entire line.
+    ClearLine,
+}
+
+/// REPL configuration, read from the configuration file.
+///
+/// The file consists of lines of the form `key = value`:
+///
+/// ```text
+/// # Editing mode at the prompt ("emacs" or "vi")
+/// editing_mode = vi
+///
+/// # Key bindings
+/// bind Ctrl-O = insert_previous_result
+/// bind F2 = clear_line
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Config {
+    pub editing_mode: EditMode,
+    /// Key bindings, in the order in which they were defined.
+    /// Later bindings for the same key override earlier ones.
+    pub key_bindings: Vec<(KeyEvent, Action)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            editing_mode: EditMode::Emacs,
+            key_bindings: vec![
+                (KeyEvent::alt('p'), Action::InsertPreviousResult),
+                (KeyEvent::alt('k'), Action::ClearLine),
+            ],
+        }
+    }
+}
+
+/// Returns the key event described by the given string, e.g. `Ctrl-O`, `Alt-Shift-Left`, or `F2`.
+fn parse_key(string: &str) -> Result<KeyEvent, String> {
+    let mut parts = string.split('-').collect::<Vec<_>>();
+    let key = parts.pop().unwrap();
+
+    let mut modifiers = Modifiers::NONE;
+
+    for modifier in parts {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" => Modifiers::CTRL,
+            "alt" => Modifiers::ALT,
+            "shift" => Modifiers::SHIFT,
+            _ => return Err(format!("unknown modifier '{}'", modifier)),
+        };
+    }
+
+    let mut characters = key.chars();
+
+    if let (Some(character), None) = (characters.next(), characters.next()) {
+        return Ok(KeyEvent::new(character, modifiers));
+    }
+
+    let code = match key.to_lowercase().as_str() {
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "down" => KeyCode::Down,
+        "end" => KeyCode::End,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "home" => KeyCode::Home,
+        "insert" => KeyCode::Insert,
+        "left" => KeyCode::Left,
+        "pagedown" => KeyCode::PageDown,
+        "pageup" => KeyCode::PageUp,
+        "right" => KeyCode::Right,
+        "tab" => KeyCode::Tab,
+        "up" => KeyCode::Up,
+        name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            Some(n @ 1..=12) => KeyCode::F(n),
+            _ => return Err(format!("unknown key '{}'", key)),
+        },
+    };
+
+    Ok(KeyEvent(code, modifiers))
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut config = Config::default();
+
+        for (index, line) in string.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+
+            let (key, value) = line
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| error("expected 'key = value'".to_owned()))?;
+
+            if key == "editing_mode" {
+                config.editing_mode = match value {
+                    "emacs" => EditMode::Emacs,
+                    "vi" => EditMode::Vi,
+                    _ => return Err(error(format!("unknown editing mode '{}'", value))),
+                };
+            } else if let Some(key) = key.strip_prefix("bind ") {
+                let key = parse_key(key.trim()).map_err(error)?;
+
+                let action = match value {
+                    "insert_previous_result" => Action::InsertPreviousResult,
+                    "clear_line" => Action::ClearLine,
+                    _ => return Err(error(format!("unknown action '{}'", value))),
+                };
+
+                config.key_bindings.push((key, action));
+            } else {
+                return Err(error(format!("unknown setting '{}'", key)));
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustyline::{EditMode, KeyCode, KeyEvent, Modifiers};
+
+    use crate::config::{Action::*, Config};
+
+    #[test]
+    fn parse() {
+        assert_eq!("".parse(), Ok(Config::default()));
+
+        let config = "
+            # Comment
+            editing_mode = vi
+
+            bind Ctrl-o = insert_previous_result
+            bind  Alt-Shift-Left =clear_line
+            bind F2 = clear_line
+        "
+        .parse::<Config>()
+        .unwrap();
+
+        assert_eq!(config.editing_mode, EditMode::Vi);
+        assert_eq!(
+            config.key_bindings[2..],
+            [
+                (KeyEvent::ctrl('o'), InsertPreviousResult),
+                (KeyEvent(KeyCode::Left, Modifiers::ALT_SHIFT), ClearLine),
+                (KeyEvent(KeyCode::F(2), Modifiers::NONE), ClearLine),
+            ],
+        );
+
+        let e = |string: &str| string.parse::<Config>().unwrap_err();
+
+        assert_eq!(e("editing_mode"), "line 1: expected 'key = value'");
+        assert_eq!(
+            e("\nediting_mode = ed"),
+            "line 2: unknown editing mode 'ed'"
+        );
+        assert_eq!(
+            e("bind Meta-x = clear_line"),
+            "line 1: unknown modifier 'Meta'"
+        );
+        assert_eq!(e("bind F13 = clear_line"), "line 1: unknown key 'F13'");
+        assert_eq!(e("bind x = quit"), "line 1: unknown action 'quit'");
+        assert_eq!(e("color = red"), "line 1: unknown setting 'color'");
+    }
+}
@@ -7,11 +7,12 @@ use ansi_term::Style;
 use lazy_static::lazy_static;
 use regex::Regex;
 use rustyline::{
+    completion::{Completer, Pair},
     highlight::Highlighter,
     validate::{ValidationContext, ValidationResult, Validator},
-    Result,
+    Context, Result,
 };
-use rustyline_derive::{Completer, Helper, Hinter};
+use rustyline_derive::{Helper, Hinter};
 use savage_core::{expression::Expression, parse::ErrorReason};
 
 enum TokenType {
@@ -31,8 +32,8 @@ fn tokenize(input: &str) -> Vec<(String, TokenType)> {
         static ref REGEX: Regex = Regex::new(
             &[
                 r"(?P<literal>[0-9]+(?:\.[0-9]+)?|true|false)",
-                r"(?P<variable>[a-zA-Z_][a-zA-Z0-9_]*)",
-                r"(?P<operator>[+\-*/%^!=<>&|]+)",
+                r"(?P<operator>[+\-*/%^!=<>&|·×÷−¬∧∨√≤≥≠]+|\bmod\b)",
+                r"(?P<variable>[a-zA-Z_][a-zA-Z0-9_]*|π)",
                 r"(?P<bracket>[()\[\]])",
                 r"(?P<separator>,)",
                 r"(?P<whitespace>\s+)",
@@ -81,9 +82,70 @@ fn tokenize(input: &str) -> Vec<(String, TokenType)> {
     tokens
 }
 
-#[derive(Completer, Helper, Hinter)]
+/// Abbreviations that are replaced with Unicode characters when
+/// typed after a backslash and completed using the Tab key.
+const ABBREVIATIONS: [(&str, &str); 18] = [
+    ("and", "∧"),
+    ("cdot", "·"),
+    ("div", "÷"),
+    ("ge", "≥"),
+    ("geq", "≥"),
+    ("le", "≤"),
+    ("leq", "≤"),
+    ("minus", "−"),
+    ("ne", "≠"),
+    ("neg", "¬"),
+    ("neq", "≠"),
+    ("not", "¬"),
+    ("or", "∨"),
+    ("pi", "π"),
+    ("sqrt", "√"),
+    ("times", "×"),
+    ("vee", "∨"),
+    ("wedge", "∧"),
+];
+
+#[derive(Helper, Hinter)]
 pub struct InputHelper {}
 
+impl Completer for InputHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context) -> Result<(usize, Vec<Pair>)> {
+        lazy_static! {
+            static ref REGEX: Regex = Regex::new(r"\\[a-zA-Z]*$").unwrap();
+        }
+
+        let abbreviation = match REGEX.find(&line[..pos]) {
+            Some(abbreviation) => abbreviation,
+            None => return Ok((pos, Vec::new())),
+        };
+
+        let name = &abbreviation.as_str()[1..];
+
+        // An exact match is replaced immediately, even if it is
+        // also a prefix of other abbreviations (e.g. "le" and "leq").
+        let candidates = match ABBREVIATIONS.iter().find(|(n, _)| *n == name) {
+            Some(candidate) => vec![candidate],
+            None => ABBREVIATIONS
+                .iter()
+                .filter(|(n, _)| n.starts_with(name))
+                .collect(),
+        };
+
+        Ok((
+            abbreviation.start(),
+            candidates
+                .into_iter()
+                .map(|(name, replacement)| Pair {
+                    display: format!("\\{} {}", name, replacement),
+                    replacement: (*replacement).to_owned(),
+                })
+                .collect(),
+        ))
+    }
+}
+
 impl Highlighter for InputHelper {
     fn highlight_prompt<'b, 's: 'b, 'p: 'b>(
         &'s self,
@@ -177,9 +239,9 @@ impl Validator for InputHelper {
             return Ok(ValidationResult::Valid(None));
         }
 
-        if let Err(errors) = input.parse::<Expression>() {
-            for error in errors {
-                if error.reason() == &ErrorReason::Unexpected && error.found() == None {
+        if let Err(error) = input.parse::<Expression>() {
+            for error in error.errors {
+                if error.reason() == &ErrorReason::Unexpected && error.found().is_none() {
                     return Ok(ValidationResult::Incomplete);
                 }
             }
@@ -188,3 +250,46 @@ impl Validator for InputHelper {
         Ok(ValidationResult::Valid(None))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rustyline::{completion::Completer, history::History, Context};
+
+    use crate::input::InputHelper;
+
+    #[test]
+    fn abbreviations() {
+        let history = History::new();
+        let context = Context::new(&history);
+
+        let c = |line: &str| {
+            let (start, candidates) = InputHelper {}.complete(line, line.len(), &context).unwrap();
+
+            (
+                start,
+                candidates
+                    .into_iter()
+                    .map(|candidate| candidate.replacement)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(c("2 * \\pi"), (4, vec!["π".to_owned()]));
+        assert_eq!(c("a \\le"), (2, vec!["≤".to_owned()]));
+        assert_eq!(c("\\sq"), (0, vec!["√".to_owned()]));
+        assert_eq!(
+            c("\\n"),
+            (
+                0,
+                vec![
+                    "≠".to_owned(),
+                    "¬".to_owned(),
+                    "≠".to_owned(),
+                    "¬".to_owned()
+                ]
+            )
+        );
+        assert_eq!(c("\\x"), (0, vec![]));
+        assert_eq!(c("pi"), (2, vec![]));
+    }
+}
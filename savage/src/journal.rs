@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use savage_core::{engine::Engine, expression::Expression};
+
+/// Change of the value of a variable in the session.
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct Definition {
+    identifier: String,
+    /// Value of the variable before the definition, if it had one.
+    previous_value: Option<Expression>,
+    value: Expression,
+}
+
+/// Journal of the definitions made in a session, which allows
+/// undoing definitions and redoing undone definitions.
+#[derive(Default, Debug)]
+pub struct Journal {
+    done: Vec<Definition>,
+    undone: Vec<Definition>,
+}
+
+impl Journal {
+    /// Sets the variable with the given identifier to the given value in the session,
+    /// recording the definition. Previously undone definitions can no longer be redone.
+    pub fn define(&mut self, engine: &mut Engine, identifier: String, value: Expression) {
+        let previous_value = engine.set(identifier.clone(), value.clone());
+
+        self.done.push(Definition {
+            identifier,
+            previous_value,
+            value,
+        });
+
+        self.undone.clear();
+    }
+
+    /// Reverts the last definition in the session, restoring the previous value
+    /// of the variable (if any). Returns the identifier and the restored value,
+    /// or `None` if there is no definition to undo.
+    pub fn undo(&mut self, engine: &mut Engine) -> Option<(String, Option<Expression>)> {
+        let definition = self.done.pop()?;
+
+        match &definition.previous_value {
+            Some(value) => engine.set(definition.identifier.clone(), value.clone()),
+            None => engine.unset(&definition.identifier),
+        };
+
+        let result = (
+            definition.identifier.clone(),
+            definition.previous_value.clone(),
+        );
+
+        self.undone.push(definition);
+
+        Some(result)
+    }
+
+    /// Reapplies the last undone definition in the session. Returns the identifier
+    /// and the value of the variable, or `None` if there is no definition to redo.
+    pub fn redo(&mut self, engine: &mut Engine) -> Option<(String, Expression)> {
+        let definition = self.undone.pop()?;
+
+        engine.set(definition.identifier.clone(), definition.value.clone());
+
+        let result = (definition.identifier.clone(), definition.value.clone());
+
+        self.done.push(definition);
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use savage_core::{engine::Engine, helpers::*};
+
+    use crate::journal::Journal;
+
+    #[test]
+    fn undo_redo() {
+        let mut journal = Journal::default();
+        let mut engine = Engine::new();
+
+        journal.define(&mut engine, "a".to_owned(), int(1));
+        journal.define(&mut engine, "b".to_owned(), int(2));
+        journal.define(&mut engine, "a".to_owned(), int(3));
+        assert_eq!(engine.get("a"), Some(&int(3)));
+
+        assert_eq!(
+            journal.undo(&mut engine),
+            Some(("a".to_owned(), Some(int(1))))
+        );
+        assert_eq!(engine.get("a"), Some(&int(1)));
+
+        assert_eq!(journal.undo(&mut engine), Some(("b".to_owned(), None)));
+        assert_eq!(engine.get("b"), None);
+
+        assert_eq!(journal.redo(&mut engine), Some(("b".to_owned(), int(2))));
+        assert_eq!(engine.get("b"), Some(&int(2)));
+
+        assert_eq!(journal.undo(&mut engine), Some(("b".to_owned(), None)));
+        assert_eq!(journal.undo(&mut engine), Some(("a".to_owned(), None)));
+        assert_eq!(journal.undo(&mut engine), None);
+        assert!(engine.variables().is_empty());
+
+        journal.redo(&mut engine);
+        journal.define(&mut engine, "c".to_owned(), int(4));
+        assert_eq!(journal.redo(&mut engine), None);
+        assert_eq!(engine.variables().len(), 2);
+    }
+}
@@ -1,23 +1,37 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
+mod cli;
 mod command;
+mod config;
 mod input;
+mod journal;
+mod plot;
+mod report;
 
-use std::{collections::HashMap, fs};
+use std::{collections::HashMap, fs, fs::File, process};
 
 use ansi_term::Style;
 use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
 use directories::ProjectDirs;
-use rustyline::{error::ReadlineError, highlight::Highlighter, Editor};
+use rustyline::{error::ReadlineError, highlight::Highlighter, Cmd, Editor, Movement};
 use savage_core::{
+    engine::Engine,
+    evaluate::Error as EvaluationError,
     expression::{Expression, Vector},
-    parse::{Error, ErrorReason},
+    parse::{error_code, error_suggestion, Error, ErrorReason},
 };
 
-use crate::{command::Command, input::InputHelper};
+use crate::{
+    cli::Arguments,
+    command::{expand_references, Command},
+    config::{Action, Config},
+    input::InputHelper,
+    journal::Journal,
+    report::{Entry, Output},
+};
 
-fn format_parse_error(error: Error) -> Report {
+fn format_parse_error(error: Error, input: &str) -> Report {
     // Heavily based on https://github.com/zesterer/chumsky/blob/463226372cf293d45bd5df52bf25d5028243066e/examples/json.rs#L114-L173
     let message = if let ErrorReason::Custom(message) = error.reason() {
         message.clone()
@@ -45,6 +59,7 @@ fn format_parse_error(error: Error) -> Report {
     };
 
     let report = Report::build(ReportKind::Error, (), error.span().start)
+        .with_code(error_code(&error))
         .with_message(message)
         .with_label(
             Label::new(error.span())
@@ -74,18 +89,309 @@ fn format_parse_error(error: Error) -> Report {
         ErrorReason::Custom(_) => report,
     };
 
+    let report = match error_suggestion(&error, input) {
+        Some(suggestion) => report.with_help(suggestion),
+        None => report,
+    };
+
     report.finish()
 }
 
+/// Formats an evaluation result for display. Matrices are printed with
+/// one row per line and aligned columns, indented by `indentation` characters.
+fn format_output(output: &Expression, indentation: usize) -> String {
+    if let Expression::Matrix(matrix) = output {
+        if matrix.nrows() > 1 {
+            let elements = matrix
+                .row_iter()
+                .map(|row| row.iter().map(|e| e.to_string()).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+
+            let widths = (0..matrix.ncols())
+                .map(|j| {
+                    elements
+                        .iter()
+                        .map(|row| row[j].chars().count())
+                        .max()
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+
+            let rows = elements
+                .iter()
+                .map(|row| {
+                    format!(
+                        "[{}]",
+                        row.iter()
+                            .zip(&widths)
+                            .map(|(element, width)| format!("{:>width$}", element, width = width))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            return format!(
+                "[{}]",
+                rows.join(&format!(",\n{}", " ".repeat(indentation + 1))),
+            );
+        }
+    }
+
+    output.to_string()
+}
+
+/// Returns the given text with the middle of lines that are wider than `width`
+/// characters and the middle lines of text that is higher than `height` lines
+/// replaced by ellipses, or `None` if the text fits within those dimensions.
+/// The first line is assumed to be preceded by `indentation` other characters.
+fn elide(text: &str, indentation: usize, width: usize, height: usize) -> Option<String> {
+    let mut elided = false;
+
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let width = if i == 0 {
+                width.saturating_sub(indentation)
+            } else {
+                width
+            };
+
+            let length = line.chars().count();
+
+            if length > width {
+                elided = true;
+
+                let head = width.saturating_sub(1) / 2;
+                let tail = width.saturating_sub(1) - head;
+
+                line.chars()
+                    .take(head)
+                    .chain(std::iter::once('…'))
+                    .chain(line.chars().skip(length - tail))
+                    .collect()
+            } else {
+                line.to_owned()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if lines.len() > height {
+        elided = true;
+
+        let head = height.saturating_sub(1) / 2;
+        let tail = height.saturating_sub(1) - head;
+
+        lines.splice(
+            head..(lines.len() - tail),
+            std::iter::once(format!("{}⋮", " ".repeat(indentation))),
+        );
+    }
+
+    if elided {
+        Some(lines.join("\n"))
+    } else {
+        None
+    }
+}
+
+/// Prints the given value after the given prompt. If the dimensions
+/// (width and height) of the terminal are given, parts of the value
+/// that don't fit into the terminal are elided.
+fn print_value(
+    editor: &Editor<InputHelper>,
+    prompt: &str,
+    value: &Expression,
+    dimensions: Option<(usize, usize)>,
+) {
+    let indentation = prompt.chars().count();
+
+    let output = format_output(value, indentation);
+
+    // Some lines are reserved for the prompt and the hint below the output.
+    let elided_output = dimensions
+        .and_then(|(width, height)| elide(&output, indentation, width, height.saturating_sub(4)));
+
+    println!(
+        "{}{}",
+        Style::new().bold().paint(prompt),
+        editor
+            .helper()
+            .unwrap()
+            .highlight(elided_output.as_ref().unwrap_or(&output), usize::MAX),
+    );
+
+    if elided_output.is_some() {
+        println!(
+            "(output elided, enter {} to see the full result)",
+            Style::new().bold().paint(":show full"),
+        );
+    }
+}
+
+/// Maximum number of characters of a value shown in the variable listing.
+const PREVIEW_LENGTH: usize = 40;
+
+/// Identifiers of the vector of previous outputs, which is set by the REPL
+/// rather than by the user. `Out` matches the numbering of the outputs.
+const OUTPUT_VARIABLES: [&str; 2] = ["out", "Out"];
+
+/// Sets the variables holding the vector of previous outputs.
+fn set_outputs(engine: &mut Engine, outputs: &[Expression]) {
+    for identifier in OUTPUT_VARIABLES {
+        engine.set(
+            identifier,
+            Expression::Vector(Vector::from_vec(outputs.to_vec())),
+        );
+    }
+}
+
+/// Formats a listing of the given variables, showing the type
+/// and a possibly truncated preview of the value of each variable.
+fn format_variables(context: &HashMap<String, Expression>) -> String {
+    let mut identifiers = context
+        .keys()
+        .filter(|identifier| !OUTPUT_VARIABLES.contains(&identifier.as_str()))
+        .collect::<Vec<_>>();
+
+    if identifiers.is_empty() {
+        return "No variables defined".to_owned();
+    }
+
+    identifiers.sort();
+
+    let rows = identifiers
+        .into_iter()
+        .map(|identifier| {
+            let value = &context[identifier];
+
+            let mut preview = value.to_string();
+
+            if preview.chars().count() > PREVIEW_LENGTH {
+                preview = preview.chars().take(PREVIEW_LENGTH - 1).collect();
+                preview.push('…');
+            }
+
+            (identifier, value.type_name(), preview)
+        })
+        .collect::<Vec<_>>();
+
+    let identifier_width = rows.iter().map(|row| row.0.chars().count()).max().unwrap();
+    let type_width = rows.iter().map(|row| row.1.chars().count()).max().unwrap();
+
+    rows.iter()
+        .map(|(identifier, type_name, preview)| {
+            format!(
+                "{}  {:<width$}  {}",
+                Style::new().bold().paint(format!(
+                    "{:<width$}",
+                    identifier,
+                    width = identifier_width,
+                )),
+                type_name,
+                preview,
+                width = type_width,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn print_evaluation_error(error: &EvaluationError) {
+    println!("Error[{}]: {}", error.code(), error);
+
+    if let Some(suggestion) = error.suggestion() {
+        println!("help: {}", suggestion);
+    }
+}
+
+/// Binds the keys from the configuration to their actions, with `previous_result`
+/// being the result inserted by the `InsertPreviousResult` action.
+fn bind_keys(
+    editor: &mut Editor<InputHelper>,
+    config: &Config,
+    previous_result: Option<&Expression>,
+) {
+    for (key, action) in &config.key_bindings {
+        let command = match action {
+            Action::InsertPreviousResult => match previous_result {
+                Some(result) => Cmd::Insert(1, result.to_string()),
+                None => Cmd::Noop,
+            },
+            Action::ClearLine => Cmd::Kill(Movement::WholeLine),
+        };
+
+        editor.bind_sequence(*key, command);
+    }
+}
+
+/// Evaluates the given input, and prints the result or an error message.
+/// Returns the exit code for the process.
+fn evaluate(input: &str) -> i32 {
+    match savage_core::eval(input) {
+        Ok(value) => {
+            println!("{}", format_output(&value, 0));
+            0
+        }
+        Err(error) => {
+            eprintln!("Error[{}]: {}", error.code(), error);
+            1
+        }
+    }
+}
+
 fn main() {
     use crate::command::Command::*;
 
-    let history_path = ProjectDirs::from("com.worldwidemann", "", "Savage")
-        .expect("unable to locate data directory")
-        .data_dir()
-        .join("history");
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
 
-    let mut editor = Editor::new();
+    match cli::parse(&arguments) {
+        Ok(Arguments::Repl) => {}
+        Ok(Arguments::Evaluate(input)) => process::exit(evaluate(&input)),
+        Ok(Arguments::Help) => {
+            println!("{}", cli::help());
+            return;
+        }
+        Ok(Arguments::Version) => {
+            println!("savage {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Ok(Arguments::Completions(shell)) => {
+            print!("{}", cli::completions(shell));
+            return;
+        }
+        Err(message) => {
+            eprintln!("Error: {}\n\n{}", message, cli::help());
+            process::exit(2);
+        }
+    }
+
+    let project_dirs = ProjectDirs::from("com.worldwidemann", "", "Savage")
+        .expect("unable to locate data directory");
+
+    let history_path = project_dirs.data_dir().join("history");
+
+    let config_path = project_dirs.config_dir().join("config");
+
+    let config = match fs::read_to_string(&config_path) {
+        Ok(string) => string.parse().unwrap_or_else(|message| {
+            println!(
+                "Error in configuration file {}: {}",
+                config_path.display(),
+                message,
+            );
+
+            Config::default()
+        }),
+        Err(_) => Config::default(),
+    };
+
+    let mut editor = Editor::with_config(
+        rustyline::Config::builder()
+            .edit_mode(config.editing_mode)
+            .build(),
+    );
 
     editor.set_helper(Some(InputHelper {}));
 
@@ -105,17 +411,29 @@ fn main() {
 
     let mut outputs = Vec::new();
 
-    let mut context = HashMap::new();
+    let mut engine = Engine::new();
 
-    context.insert(
-        "out".to_owned(),
-        Expression::Vector(Vector::from_vec(outputs.clone())),
-    );
+    let mut journal = Journal::default();
+
+    // Prompt and value of the last printed result, which can be shown
+    // in full with `:show full` if it was elided.
+    let mut last_value = None;
+
+    // Inputs and outputs of the session, for exporting as a report.
+    let mut entries = Vec::new();
+
+    set_outputs(&mut engine, &outputs);
 
     loop {
+        bind_keys(
+            &mut editor,
+            &config,
+            last_value.as_ref().map(|(_, value)| value),
+        );
+
         println!();
 
-        match editor.readline("in: ") {
+        match editor.readline(&format!("In[{}]: ", outputs.len())) {
             Ok(line) => {
                 let line = line.trim();
 
@@ -125,44 +443,111 @@ fn main() {
 
                 editor.add_history_entry(line);
 
-                match line.parse::<Command>() {
-                    Ok(EvaluateExpression(expression)) => {
-                        match expression.evaluate(context.clone()) {
-                            Ok(output) => {
-                                println!(
-                                    "{}{}",
-                                    Style::new()
-                                        .bold()
-                                        .paint(format!("out[{}]: ", outputs.len())),
-                                    editor
-                                        .helper()
-                                        .unwrap()
-                                        .highlight(&output.to_string(), usize::MAX),
-                                );
-
-                                outputs.push(output);
-
-                                context.insert(
-                                    "out".to_owned(),
-                                    Expression::Vector(Vector::from_vec(outputs.clone())),
-                                );
+                let expanded_line = expand_references(line, outputs.len());
+
+                match expanded_line.parse::<Command>() {
+                    Ok(EvaluateExpression(expression)) => match engine.evaluate(&expression) {
+                        Ok(output) => {
+                            let prompt = format!("Out[{}]: ", outputs.len());
+
+                            let dimensions = editor.dimensions();
+                            print_value(&editor, &prompt, &output, dimensions);
+
+                            last_value = Some((prompt, output.clone()));
+
+                            entries.push(Entry {
+                                input: line.to_owned(),
+                                output: Output::Value(output.clone()),
+                            });
+
+                            outputs.push(output);
+
+                            set_outputs(&mut engine, &outputs);
+                        }
+                        Err(error) => {
+                            print_evaluation_error(&error);
+
+                            entries.push(Entry {
+                                input: line.to_owned(),
+                                output: Output::Error(error.to_string()),
+                            });
+                        }
+                    },
+                    Ok(DefineVariable(identifier, expression)) => {
+                        match engine.evaluate(&expression) {
+                            Ok(value) => {
+                                let prompt = format!("{} = ", identifier);
+
+                                let dimensions = editor.dimensions();
+                                print_value(&editor, &prompt, &value, dimensions);
+
+                                last_value = Some((prompt, value.clone()));
+
+                                entries.push(Entry {
+                                    input: line.to_owned(),
+                                    output: Output::Definition(identifier.clone(), value.clone()),
+                                });
+
+                                journal.define(&mut engine, identifier, value);
+                            }
+                            Err(error) => {
+                                print_evaluation_error(&error);
+
+                                entries.push(Entry {
+                                    input: line.to_owned(),
+                                    output: Output::Error(error.to_string()),
+                                });
                             }
-                            Err(error) => println!("Error: {:#?}", error),
                         }
                     }
-                    Ok(DefineVariable(identifier, expression)) => {
-                        println!(
-                            "Define variable {} as {}: Not implemented yet.",
-                            identifier, expression,
-                        );
+                    Ok(BindVariable(identifier, definition)) => {
+                        match engine.bind(identifier.clone(), definition) {
+                            Ok(value) => {
+                                let prompt = format!("{} = ", identifier);
+
+                                let dimensions = editor.dimensions();
+                                print_value(&editor, &prompt, &value, dimensions);
+
+                                last_value = Some((prompt, value.clone()));
+
+                                entries.push(Entry {
+                                    input: line.to_owned(),
+                                    output: Output::Definition(identifier, value),
+                                });
+                            }
+                            Err(error) => {
+                                print_evaluation_error(&error);
+
+                                entries.push(Entry {
+                                    input: line.to_owned(),
+                                    output: Output::Error(error.to_string()),
+                                });
+                            }
+                        }
                     }
                     Ok(DefineFunction(identifier, argument_identifiers, expression)) => {
-                        println!(
-                            "Define function {} with arguments [{}] as {}: Not implemented yet.",
-                            identifier,
-                            argument_identifiers.join(", "),
-                            expression,
-                        );
+                        let signature =
+                            format!("{}({})", identifier, argument_identifiers.join(", "));
+
+                        println!("{} = {}", signature, expression);
+
+                        entries.push(Entry {
+                            input: line.to_owned(),
+                            output: Output::Definition(signature, expression.clone()),
+                        });
+
+                        // Functions are stored as lambda expressions rather than
+                        // as native functions, so that `:save` can write them.
+                        let function =
+                            Expression::Lambda(argument_identifiers, Box::new(expression));
+
+                        journal.define(&mut engine, identifier, function);
+                    }
+                    Ok(Plot(functions, variable, a, b)) => {
+                        match plot::plot(&functions, &variable, &a, &b, engine.variables()) {
+                            Ok(plot) => println!("{}", plot),
+                            Err(message) => println!("Error: {}", message),
+                        }
                     }
                     Ok(ShowHelp(function_name)) => {
                         println!(
@@ -170,14 +555,85 @@ fn main() {
                             function_name.unwrap_or_else(|| "all functions".to_owned()),
                         );
                     }
+                    Ok(ShowVariables) => {
+                        println!("{}", format_variables(engine.variables()));
+                    }
+                    Ok(Export(path)) => {
+                        let document = if path.ends_with(".tex") {
+                            report::to_latex(&entries)
+                        } else {
+                            report::to_markdown(&entries)
+                        };
+
+                        match fs::write(&path, document) {
+                            Ok(()) => println!("Session exported to {}", path),
+                            Err(error) => println!("Error: unable to write {}: {}", path, error),
+                        }
+                    }
+                    Ok(Save(path)) => {
+                        // Outputs belong to the session in which they were produced.
+                        let mut variables = engine.clone();
+                        for identifier in OUTPUT_VARIABLES {
+                            variables.unset(identifier);
+                        }
+
+                        match File::create(&path).and_then(|file| variables.save_to(file)) {
+                            Ok(()) => println!("Variables saved to {}", path),
+                            Err(error) => println!("Error: unable to write {}: {}", path, error),
+                        }
+                    }
+                    Ok(Load(path)) => {
+                        match File::open(&path).and_then(|file| engine.load_from(file)) {
+                            Ok(()) => println!("Variables loaded from {}", path),
+                            Err(error) => println!("Error: unable to read {}: {}", path, error),
+                        }
+                    }
+                    Ok(Seed(seed)) => {
+                        engine.set_seed(seed);
+                        println!("Random number generator seeded with {}", seed);
+                    }
+                    Ok(ShowFull) => match &last_value {
+                        Some((prompt, value)) => print_value(&editor, prompt, value, None),
+                        None => println!("No result to show"),
+                    },
+                    Ok(Undo) => match journal.undo(&mut engine) {
+                        Some((identifier, Some(value))) => {
+                            println!("Restored {} = {}", identifier, value);
+                        }
+                        Some((identifier, None)) => println!("Removed {}", identifier),
+                        None => println!("Nothing to undo"),
+                    },
+                    Ok(Redo) => match journal.redo(&mut engine) {
+                        Some((identifier, value)) => {
+                            println!("Restored {} = {}", identifier, value);
+                        }
+                        None => println!("Nothing to redo"),
+                    },
                     Err(errors) => {
                         for error in errors {
-                            format_parse_error(error)
-                                .print(Source::from(line))
+                            format_parse_error(error, &expanded_line)
+                                .print(Source::from(&expanded_line))
                                 .expect("unable to print parse error");
                         }
                     }
                 }
+
+                // Bound variables that were recomputed because
+                // a variable they depend on changed.
+                for update in engine.take_updates() {
+                    match update.result {
+                        Ok(value) => {
+                            let prompt = format!("{} = ", update.identifier);
+
+                            let dimensions = editor.dimensions();
+                            print_value(&editor, &prompt, &value, dimensions);
+                        }
+                        Err(error) => {
+                            println!("Unable to update {}:", update.identifier);
+                            print_evaluation_error(&error);
+                        }
+                    }
+                }
             }
             Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
                 break;
@@ -200,3 +656,23 @@ fn main() {
         .save_history(&history_path)
         .expect("unable to save input history");
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::elide;
+
+    #[test]
+    fn elision() {
+        assert_eq!(elide("12345", 0, 5, 1), None);
+        assert_eq!(elide("123456", 0, 5, 1), Some("12…56".to_owned()));
+        assert_eq!(elide("123456", 2, 5, 1), Some("1…6".to_owned()));
+        assert_eq!(
+            elide("[1],\n [2],\n [3],\n [4]", 0, 10, 3),
+            Some("[1],\n⋮\n [4]".to_owned()),
+        );
+        assert_eq!(
+            elide("[1, 2, 3],\n      [4, 5, 6]]", 5, 10, 2),
+            Some("[1…],\n    …, 6]]".to_owned()),
+        );
+    }
+}
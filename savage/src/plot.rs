@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::collections::HashMap;
+
+use ansi_term::Colour;
+use savage_core::expression::{Expression, Integer, Rational, RationalRepresentation};
+
+/// Width of the plot area, in characters.
+const WIDTH: usize = 60;
+
+/// Height of the plot area, in characters.
+const HEIGHT: usize = 16;
+
+/// Colors used for the individual series, in order.
+const COLORS: [Colour; 6] = [
+    Colour::Cyan,
+    Colour::Yellow,
+    Colour::Green,
+    Colour::Purple,
+    Colour::Blue,
+    Colour::Red,
+];
+
+/// Bits of the Braille pattern dots, indexed by row and column within a character cell.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Returns the value of the expression as a floating point number,
+/// or `None` if the expression is not a real number.
+fn to_f64(expression: &Expression) -> Option<f64> {
+    let x = Rational::try_from(expression.clone()).ok()?;
+
+    // Converting numerator and denominator separately overflows for large values
+    // that nevertheless have a representable quotient, so values are rescaled first.
+    let scale = x.numer().bits().max(x.denom().bits()).saturating_sub(1000);
+
+    let numerator = (x.numer() >> scale).to_string().parse::<f64>().ok()?;
+    let denominator = (x.denom() >> scale).to_string().parse::<f64>().ok()?;
+
+    Some(numerator / denominator).filter(|y| y.is_finite())
+}
+
+/// Returns the values of the given functions of `variable`, evaluated at
+/// equally spaced points from `a` to `b`, with `None` for points at which
+/// a function doesn't evaluate to a real number.
+fn sample(
+    functions: &[Expression],
+    variable: &str,
+    a: &Rational,
+    b: &Rational,
+    count: usize,
+    context: &HashMap<String, Expression>,
+) -> Vec<Vec<Option<f64>>> {
+    let points = (0..count)
+        .map(|i| a + (b - a) * Rational::new(Integer::from(i), Integer::from(count - 1)))
+        .collect::<Vec<_>>();
+
+    functions
+        .iter()
+        .map(|function| {
+            points
+                .iter()
+                .map(|x| {
+                    let mut context = context.clone();
+
+                    context.insert(
+                        variable.to_owned(),
+                        Expression::Rational(x.clone(), RationalRepresentation::Decimal),
+                    );
+
+                    function.evaluate(context).ok().and_then(|y| to_f64(&y))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Formats a number for use as an axis label.
+fn format_label(x: f64) -> String {
+    let string = format!("{:.4}", x);
+    let string = string.trim_end_matches('0').trim_end_matches('.');
+
+    if string == "-0" {
+        "0".to_owned()
+    } else {
+        string.to_owned()
+    }
+}
+
+/// Returns a plot of the given functions of `variable` over the interval
+/// from `a` to `b`, rendered using Braille characters, or an error message
+/// if the plot cannot be created.
+pub fn plot(
+    functions: &[Expression],
+    variable: &str,
+    a: &Expression,
+    b: &Expression,
+    context: &HashMap<String, Expression>,
+) -> Result<String, String> {
+    let [a, b] = [a, b].map(|x| {
+        x.evaluate(context.clone())
+            .ok()
+            .and_then(|x| Rational::try_from(x).ok())
+    });
+
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) if a < b => (a, b),
+        _ => return Err("Plot range must be given by real numbers a < b".to_owned()),
+    };
+
+    let series = sample(functions, variable, &a, &b, 2 * WIDTH, context);
+
+    let values = series.iter().flatten().flatten().copied();
+
+    let (mut y_min, mut y_max) = values
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| {
+            (min.min(y), max.max(y))
+        });
+
+    if y_min > y_max {
+        return Err("Functions do not evaluate to real numbers in the plot range".to_owned());
+    }
+
+    if y_min == y_max {
+        y_min -= 1.0;
+        y_max += 1.0;
+    }
+
+    let rows = 4 * HEIGHT;
+
+    let row = |y: f64| (((y_max - y) / (y_max - y_min)) * ((rows - 1) as f64)).round() as usize;
+
+    // Braille pattern bits and series index for each character cell.
+    let mut cells = vec![vec![(0, None); WIDTH]; HEIGHT];
+
+    for (index, values) in series.iter().enumerate() {
+        let mut previous_row = None;
+
+        for (column, y) in values.iter().enumerate() {
+            let current_row = y.map(row);
+
+            if let Some(current_row) = current_row {
+                // Connect consecutive points with a vertical line,
+                // so that steep sections of the graph remain visible.
+                let previous_row = previous_row.unwrap_or(current_row);
+                let (start, end) = if previous_row < current_row {
+                    (previous_row, current_row)
+                } else {
+                    (current_row, previous_row)
+                };
+
+                for row in start..=end {
+                    let cell = &mut cells[row / 4][column / 2];
+                    cell.0 |= BRAILLE_DOTS[row % 4][column % 2];
+                    cell.1 = Some(index);
+                }
+            }
+
+            previous_row = current_row;
+        }
+    }
+
+    let y_labels = [format_label(y_max), format_label(y_min)];
+    let label_width = y_labels.iter().map(String::len).max().unwrap();
+
+    let mut lines = Vec::new();
+
+    for (i, row) in cells.iter().enumerate() {
+        let label = if i == 0 {
+            &y_labels[0]
+        } else if i == HEIGHT - 1 {
+            &y_labels[1]
+        } else {
+            ""
+        };
+
+        let mut line = format!("{:>width$} │", label, width = label_width);
+
+        for (bits, index) in row {
+            let character = char::from_u32(0x2800 + bits).unwrap().to_string();
+
+            match index {
+                Some(index) => {
+                    line.push_str(&COLORS[index % COLORS.len()].paint(character).to_string())
+                }
+                None => line.push_str(&character),
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines.push(format!(
+        "{:>width$} └{}",
+        "",
+        "─".repeat(WIDTH),
+        width = label_width,
+    ));
+
+    let x_labels = [a, b].map(|x| format_label(to_f64(&x.into()).unwrap_or(f64::NAN)));
+
+    lines.push(format!(
+        "{:>width$}  {}{:>padding$}",
+        "",
+        x_labels[0],
+        x_labels[1],
+        width = label_width,
+        padding = WIDTH.saturating_sub(x_labels[0].len()),
+    ));
+
+    if functions.len() > 1 {
+        lines.push(String::new());
+
+        for (index, function) in functions.iter().enumerate() {
+            lines.push(format!(
+                "{} {}",
+                COLORS[index % COLORS.len()].paint("⣿"),
+                function,
+            ));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use savage_core::expression::Expression;
+
+/// Outcome of processing an input in the session.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Output {
+    /// Result of evaluating an expression.
+    Value(Expression),
+    /// Variable defined with the identifier and value.
+    Definition(String, Expression),
+    /// Error message.
+    Error(String),
+}
+
+/// Input and output that are recorded in the session report.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Entry {
+    pub input: String,
+    pub output: Output,
+}
+
+/// Returns the LaTeX representation of the given output, for use in math mode,
+/// or the error message if the output is an error.
+fn output_to_latex(output: &Output) -> Result<String, &str> {
+    match output {
+        Output::Value(value) => Ok(value.to_latex()),
+        Output::Definition(identifier, value) => Ok(format!(
+            "{} = {}",
+            Expression::Variable(identifier.clone()).to_latex(),
+            value.to_latex(),
+        )),
+        Output::Error(message) => Err(message),
+    }
+}
+
+/// Returns the given text with characters that have a special meaning in LaTeX escaped.
+fn escape_latex(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_owned(),
+            '~' => "\\textasciitilde{}".to_owned(),
+            '^' => "\\textasciicircum{}".to_owned(),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => format!("\\{}", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Returns a Markdown document showing the inputs of the given entries
+/// as code blocks, and the outputs as display math.
+pub fn to_markdown(entries: &[Entry]) -> String {
+    let mut document = "# Savage session\n".to_owned();
+
+    for entry in entries {
+        document.push_str(&format!("\n```\n{}\n```\n\n", entry.input));
+
+        match output_to_latex(&entry.output) {
+            Ok(latex) => document.push_str(&format!("$${}$$\n", latex)),
+            Err(message) => document.push_str(&format!("> Error: {}\n", message)),
+        }
+    }
+
+    document
+}
+
+/// Returns a LaTeX document showing the inputs of the given entries
+/// verbatim, and the outputs as display math.
+pub fn to_latex(entries: &[Entry]) -> String {
+    let mut document = [
+        "\\documentclass{article}",
+        "\\usepackage{amsmath}",
+        "\\begin{document}",
+        "\\section*{Savage session}",
+    ]
+    .join("\n");
+
+    document.push('\n');
+
+    for entry in entries {
+        document.push_str(&format!(
+            "\n\\begin{{verbatim}}\n{}\n\\end{{verbatim}}\n",
+            entry.input,
+        ));
+
+        match output_to_latex(&entry.output) {
+            Ok(latex) => document.push_str(&format!("\\[ {} \\]\n", latex)),
+            Err(message) => {
+                document.push_str(&format!("\\textit{{Error: {}}}\n", escape_latex(message),))
+            }
+        }
+    }
+
+    document.push_str("\n\\end{document}\n");
+
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use savage_core::helpers::*;
+
+    use crate::report::{to_latex, to_markdown, Entry, Output::*};
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                input: "a = 1/2".to_owned(),
+                output: Definition("a".to_owned(), rat(1, 2)),
+            },
+            Entry {
+                input: "a ^ 2".to_owned(),
+                output: Value(rat(1, 4)),
+            },
+            Entry {
+                input: "1 / 0".to_owned(),
+                output: Error("division by zero in 1 / 0 (0 is zero)".to_owned()),
+            },
+        ]
+    }
+
+    #[test]
+    fn markdown() {
+        assert_eq!(
+            to_markdown(&entries()),
+            "# Savage session
+
+```
+a = 1/2
+```
+
+$$a = \\frac{1}{2}$$
+
+```
+a ^ 2
+```
+
+$$\\frac{1}{4}$$
+
+```
+1 / 0
+```
+
+> Error: division by zero in 1 / 0 (0 is zero)
+",
+        );
+    }
+
+    #[test]
+    fn latex() {
+        assert_eq!(
+            to_latex(&entries()[1..]),
+            "\\documentclass{article}
+\\usepackage{amsmath}
+\\begin{document}
+\\section*{Savage session}
+
+\\begin{verbatim}
+a ^ 2
+\\end{verbatim}
+\\[ \\frac{1}{4} \\]
+
+\\begin{verbatim}
+1 / 0
+\\end{verbatim}
+\\textit{Error: division by zero in 1 / 0 (0 is zero)}
+
+\\end{document}
+",
+        );
+    }
+}
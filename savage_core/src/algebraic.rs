@@ -0,0 +1,920 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+//! Exact real algebraic numbers.
+
+use std::cmp::Ordering;
+
+use num::{One, Signed, ToPrimitive, Zero};
+
+use crate::{
+    bigfloat::BigFloat,
+    expression::{Integer, Rational},
+    factor::{derivative, div_rem, monic, primitive, value, Dense},
+    polynomial::Polynomial,
+};
+
+#[cfg(feature = "functions")]
+use crate::{
+    bigfloat::Rounding,
+    expression::{Expression, RationalRepresentation},
+    functions::function_expression,
+    helpers::*,
+    surd::Surd,
+};
+
+/// Maximum degree of the polynomial whose roots include the result of an operation
+/// on two algebraic numbers, which is the product of the degrees of the operands.
+const MAX_DEGREE: usize = 32;
+
+/// Maximum number of bisections of the intervals of the operands
+/// when isolating the result of an operation on two algebraic numbers.
+const MAX_REFINEMENTS: u32 = 1000;
+
+/// Square matrix with rational elements, given by its rows.
+type Matrix = Vec<Vec<Rational>>;
+
+/// Real algebraic number, i.e. a real root of a non-zero polynomial with rational
+/// coefficients, represented exactly by its minimal polynomial over the rational
+/// numbers and an interval that contains no other root of that polynomial.
+///
+/// The minimal polynomial is found by factoring, so for polynomials of high degree
+/// with large coefficients it might be reducible (see [`Polynomial::factor`]).
+/// In that case, equal numbers can have different representations.
+#[derive(Clone, Debug)]
+pub struct AlgebraicNumber {
+    /// Minimal polynomial, with coprime integer coefficients and
+    /// a positive leading coefficient, ordered by increasing degree.
+    polynomial: Dense,
+    /// Lower bound of the half-open interval `(lower, upper]` containing the number.
+    lower: Rational,
+    /// Upper bound of the half-open interval `(lower, upper]` containing the number.
+    upper: Rational,
+}
+
+/// Returns the Sturm sequence of the square-free polynomial.
+fn sturm_sequence(p: &[Rational]) -> Vec<Dense> {
+    let mut sequence = vec![p.to_vec(), derivative(p)];
+
+    while !sequence.last().unwrap().is_empty() {
+        let n = sequence.len();
+        let remainder = div_rem(&sequence[n - 2], &sequence[n - 1]).1;
+        sequence.push(remainder.into_iter().map(|c| -c).collect());
+    }
+
+    sequence.pop();
+    sequence
+}
+
+/// Returns the number of sign changes in the values of the polynomials at `x`.
+fn sign_changes(sequence: &[Dense], x: &Rational) -> usize {
+    let signs = sequence
+        .iter()
+        .map(|p| value(p, x))
+        .filter(|y| !y.is_zero())
+        .map(|y| y.is_positive())
+        .collect::<Vec<_>>();
+
+    signs.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+/// Returns the number of distinct roots in the half-open interval `(lower, upper]`
+/// of the square-free polynomial with the given Sturm sequence, provided that
+/// `lower` is not a root.
+fn count_roots(sequence: &[Dense], lower: &Rational, upper: &Rational) -> usize {
+    sign_changes(sequence, lower).saturating_sub(sign_changes(sequence, upper))
+}
+
+/// Returns a bound that is greater than the absolute values of all roots
+/// of the non-constant polynomial (Cauchy's bound).
+fn root_bound(p: &[Rational]) -> Rational {
+    let leading = p.last().unwrap().abs();
+
+    p[..p.len() - 1]
+        .iter()
+        .map(|c| c.abs() / &leading)
+        .max()
+        .unwrap_or_else(Rational::zero)
+        + Rational::one()
+}
+
+/// Returns disjoint half-open intervals `(lower, upper]`, in ascending order,
+/// that each contain exactly one of the real roots of the irreducible polynomial
+/// of degree at least 2, which has no rational roots.
+fn isolate_roots(p: &[Rational]) -> Vec<(Rational, Rational)> {
+    let sequence = sturm_sequence(p);
+    let bound = root_bound(p);
+
+    let mut result = Vec::new();
+    let mut intervals = vec![(-bound.clone(), bound)];
+
+    while let Some((lower, upper)) = intervals.pop() {
+        match count_roots(&sequence, &lower, &upper) {
+            0 => {}
+            1 => result.push((lower, upper)),
+            _ => {
+                let middle = (&lower + &upper) / Rational::from_integer(2.into());
+                intervals.push((middle.clone(), upper));
+                intervals.push((lower, middle));
+            }
+        }
+    }
+
+    result.sort();
+    result
+}
+
+/// Returns the irreducible factors of the non-zero polynomial over the rational
+/// numbers, or `None` if it cannot be factored.
+fn irreducible_factors(p: &[Rational]) -> Option<Vec<Dense>> {
+    let (_, factors) = Polynomial::from_coefficients("x", p).factor()?;
+
+    factors
+        .into_iter()
+        .map(|(factor, _)| factor.coefficients("x"))
+        .collect()
+}
+
+/// Returns the companion matrix of the monic polynomial,
+/// whose characteristic polynomial is that polynomial.
+fn companion_matrix(p: &[Rational]) -> Matrix {
+    let n = p.len() - 1;
+    let mut matrix = vec![vec![Rational::zero(); n]; n];
+
+    for i in 0..n {
+        if i > 0 {
+            matrix[i][i - 1] = Rational::one();
+        }
+
+        matrix[i][n - 1] = -p[i].clone();
+    }
+
+    matrix
+}
+
+/// Returns the product of the square matrices.
+fn matrix_product(a: &Matrix, b: &Matrix) -> Matrix {
+    let n = a.len();
+    let mut result = vec![vec![Rational::zero(); n]; n];
+
+    for i in 0..n {
+        for k in 0..n {
+            if a[i][k].is_zero() {
+                continue;
+            }
+
+            for j in 0..n {
+                result[i][j] += &a[i][k] * &b[k][j];
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the Kronecker product of the square matrices.
+fn kronecker_product(a: &Matrix, b: &Matrix) -> Matrix {
+    let (n, m) = (a.len(), b.len());
+    let mut result = vec![vec![Rational::zero(); n * m]; n * m];
+
+    for i in 0..n * m {
+        for j in 0..n * m {
+            result[i][j] = &a[i / m][j / m] * &b[i % m][j % m];
+        }
+    }
+
+    result
+}
+
+/// Returns the identity matrix of the given size.
+fn identity_matrix(n: usize) -> Matrix {
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| {
+                    if i == j {
+                        Rational::one()
+                    } else {
+                        Rational::zero()
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the characteristic polynomial of the square matrix,
+/// computed using the Faddeev-LeVerrier algorithm.
+fn characteristic_polynomial(a: &Matrix) -> Dense {
+    // https://en.wikipedia.org/wiki/Faddeev%E2%80%93LeVerrier_algorithm
+    let n = a.len();
+    let mut coefficients = vec![Rational::zero(); n + 1];
+    coefficients[n] = Rational::one();
+
+    let mut m = identity_matrix(n);
+
+    for k in 1..=n {
+        let am = matrix_product(a, &m);
+        let trace = (0..n).fold(Rational::zero(), |trace, i| trace + &am[i][i]);
+
+        coefficients[n - k] = -trace / Rational::from_integer(Integer::from(k));
+
+        m = am;
+
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] += &coefficients[n - k];
+        }
+    }
+
+    coefficients
+}
+
+/// Arithmetic operation on two algebraic numbers that is computed
+/// using the characteristic polynomial of a combined companion matrix.
+#[derive(Clone, Copy)]
+enum Operation {
+    Sum,
+    Product,
+}
+
+impl AlgebraicNumber {
+    /// Returns the algebraic number representing the rational number.
+    pub fn from_rational(x: Rational) -> Self {
+        Self {
+            polynomial: primitive(&[-x.clone(), Rational::one()]),
+            lower: &x - Rational::one(),
+            upper: x,
+        }
+    }
+
+    /// Returns the real root of the polynomial in (at most) one variable with
+    /// rational coefficients in the half-open interval `(lower, upper]`, or `None`
+    /// if the polynomial is zero, contains more than one variable, cannot be factored,
+    /// or does not have exactly one distinct root in that interval.
+    pub fn new(polynomial: &Polynomial, lower: Rational, upper: Rational) -> Option<Self> {
+        let mut result = None;
+
+        for root in Self::roots(polynomial)? {
+            if root.compare_rational(&lower) == Ordering::Greater
+                && root.compare_rational(&upper) != Ordering::Greater
+            {
+                if result.is_some() {
+                    return None;
+                }
+
+                result = Some(root);
+            }
+        }
+
+        result
+    }
+
+    /// Returns the distinct real roots of the polynomial in (at most) one variable
+    /// with rational coefficients, in ascending order, or `None` if the polynomial
+    /// is zero, contains more than one variable, or cannot be factored.
+    pub fn roots(polynomial: &Polynomial) -> Option<Vec<Self>> {
+        let variables = polynomial.variables();
+
+        if variables.len() > 1 || polynomial.is_zero() {
+            return None;
+        }
+
+        let identifier = variables.into_iter().next().unwrap_or_default();
+        let p = polynomial.coefficients(&identifier)?;
+
+        if p.len() < 2 {
+            return Some(Vec::new());
+        }
+
+        let mut roots = Vec::new();
+
+        for factor in irreducible_factors(&p)? {
+            if factor.len() == 2 {
+                roots.push(Self::from_rational(-&factor[0] / &factor[1]));
+            } else {
+                roots.extend(
+                    isolate_roots(&factor)
+                        .into_iter()
+                        .map(|(lower, upper)| Self {
+                            polynomial: factor.clone(),
+                            lower,
+                            upper,
+                        }),
+                );
+            }
+        }
+
+        roots.sort();
+
+        Some(roots)
+    }
+
+    /// Returns the minimal polynomial of the number in the variable with
+    /// the given identifier, with coprime integer coefficients and
+    /// a positive leading coefficient.
+    pub fn polynomial(&self, identifier: &str) -> Polynomial {
+        Polynomial::from_coefficients(identifier, &self.polynomial)
+    }
+
+    /// Returns the degree of the minimal polynomial of the number.
+    pub fn degree(&self) -> usize {
+        self.polynomial.len() - 1
+    }
+
+    /// Returns the number if it is rational.
+    pub fn to_rational(&self) -> Option<Rational> {
+        if self.degree() == 1 {
+            Some(-&self.polynomial[0] / &self.polynomial[1])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the position of the number among the real roots of its minimal
+    /// polynomial in ascending order, starting with 1.
+    pub fn index(&self) -> usize {
+        if self.degree() == 1 {
+            return 1;
+        }
+
+        count_roots(
+            &sturm_sequence(&self.polynomial),
+            &-root_bound(&self.polynomial),
+            &self.upper,
+        )
+    }
+
+    /// Halves the interval containing the number.
+    fn refine(&mut self) {
+        let middle = (&self.lower + &self.upper) / Rational::from_integer(2.into());
+
+        // If the polynomial is linear, the number is the upper bound. Otherwise,
+        // the polynomial has no rational roots, and the number is in the half
+        // at whose bounds the polynomial has different signs.
+        if self.degree() == 1
+            || value(&self.polynomial, &middle).is_positive()
+                != value(&self.polynomial, &self.upper).is_positive()
+        {
+            self.lower = middle;
+        } else {
+            self.upper = middle;
+        }
+    }
+
+    /// Returns the number rounded to the given number of decimal places.
+    pub fn approximate(&self, digits: u32) -> Rational {
+        let scale = Rational::from_integer(Integer::from(10).pow(digits));
+
+        if let Some(x) = self.to_rational() {
+            return (x * &scale).round() / scale;
+        }
+
+        // The number is irrational, so it is not halfway between two decimals,
+        // and eventually both bounds of its interval round to the same decimal.
+        let mut number = self.clone();
+
+        while (&number.lower * &scale).round() != (&number.upper * &scale).round() {
+            number.refine();
+        }
+
+        (number.upper * &scale).round() / scale
+    }
+
+    /// Returns the number as a floating point number.
+    pub fn to_f64(&self) -> Option<f64> {
+        self.approximate(17).to_f64()
+    }
+
+    /// Returns the number rounded to the given number of bits.
+    pub fn to_bigfloat(&self, precision: u32) -> BigFloat {
+        if let Some(x) = self.to_rational() {
+            return BigFloat::from_rational(&x, precision);
+        }
+
+        // As in `approximate`, both bounds eventually round to the same number.
+        let mut number = self.clone();
+
+        loop {
+            let lower = BigFloat::from_rational(&number.lower, precision);
+
+            if lower == BigFloat::from_rational(&number.upper, precision) {
+                return lower;
+            }
+
+            number.refine();
+        }
+    }
+
+    /// Returns the bounds of an interval containing the number, rounded outward
+    /// to the given number of bits, which are equal or adjacent at that precision.
+    #[cfg(feature = "functions")]
+    pub(crate) fn to_bigfloat_bounds(&self, precision: u32) -> (BigFloat, BigFloat) {
+        let mut number = self.clone();
+
+        if let Some(x) = self.to_rational() {
+            number.lower = x.clone();
+            number.upper = x;
+        }
+
+        // The isolating interval is refined until its bounds round to the same number.
+        while BigFloat::from_rational(&number.lower, precision)
+            != BigFloat::from_rational(&number.upper, precision)
+        {
+            number.refine();
+        }
+
+        (
+            BigFloat::from_rational_rounded(&number.lower, precision, Rounding::Down),
+            BigFloat::from_rational_rounded(&number.upper, precision, Rounding::Up),
+        )
+    }
+
+    /// Compares the number with the rational number.
+    fn compare_rational(&self, x: &Rational) -> Ordering {
+        if let Some(y) = self.to_rational() {
+            return y.cmp(x);
+        }
+
+        let mut number = self.clone();
+
+        loop {
+            if number.upper <= *x {
+                // The number is not equal to the upper bound because it is irrational.
+                return Ordering::Less;
+            } else if number.lower >= *x {
+                return Ordering::Greater;
+            }
+
+            number.refine();
+        }
+    }
+
+    /// Returns the negation of the number.
+    pub fn negate(&self) -> Self {
+        if let Some(x) = self.to_rational() {
+            return Self::from_rational(-x);
+        }
+
+        Self {
+            polynomial: primitive(
+                &self
+                    .polynomial
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| if i % 2 == 0 { c.clone() } else { -c })
+                    .collect::<Vec<_>>(),
+            ),
+            lower: -self.upper.clone(),
+            upper: -self.lower.clone(),
+        }
+    }
+
+    /// Returns the reciprocal of the number, or `None` if it is zero.
+    pub fn reciprocal(&self) -> Option<Self> {
+        if let Some(x) = self.to_rational() {
+            return if x.is_zero() {
+                None
+            } else {
+                Some(Self::from_rational(x.recip()))
+            };
+        }
+
+        let mut number = self.clone();
+
+        while !number.lower.is_positive() && !number.upper.is_negative() {
+            number.refine();
+        }
+
+        Some(Self {
+            polynomial: primitive(&number.polynomial.iter().rev().cloned().collect::<Vec<_>>()),
+            lower: number.upper.recip(),
+            upper: number.lower.recip(),
+        })
+    }
+
+    /// Returns the number raised to the integer power `n`,
+    /// or `None` if the number is zero and `n` is negative.
+    pub fn power(&self, n: i32) -> Option<Self> {
+        if n < 0 {
+            return self.reciprocal()?.power(-n);
+        }
+
+        if let Some(x) = self.to_rational() {
+            return Some(Self::from_rational(x.pow(n)));
+        }
+
+        // The number is a root of the characteristic polynomial
+        // of the `n`th power of the companion matrix.
+        let companion = companion_matrix(&monic(&self.polynomial));
+        let mut matrix = identity_matrix(companion.len());
+
+        for _ in 0..n {
+            matrix = matrix_product(&matrix, &companion);
+        }
+
+        let mut number = self.clone();
+
+        while !number.lower.is_positive() && !number.upper.is_negative() {
+            number.refine();
+        }
+
+        Self::isolate(&characteristic_polynomial(&matrix), || {
+            let (a, b) = (number.lower.pow(n), number.upper.pow(n));
+            let result = if a <= b { (a, b) } else { (b, a) };
+            number.refine();
+            result
+        })
+    }
+
+    /// Returns the sum of the numbers, or `None` if the degree of its minimal
+    /// polynomial could exceed the internal limit.
+    pub fn add(&self, other: &Self) -> Option<Self> {
+        self.combine(other, Operation::Sum)
+    }
+
+    /// Returns the product of the numbers, or `None` if the degree of its minimal
+    /// polynomial could exceed the internal limit.
+    pub fn multiply(&self, other: &Self) -> Option<Self> {
+        self.combine(other, Operation::Product)
+    }
+
+    /// Returns the result of the operation on the numbers, or `None`
+    /// if the degree of the result could exceed the internal limit.
+    fn combine(&self, other: &Self, operation: Operation) -> Option<Self> {
+        if let (Some(x), Some(y)) = (self.to_rational(), other.to_rational()) {
+            return Some(Self::from_rational(match operation {
+                Operation::Sum => x + y,
+                Operation::Product => x * y,
+            }));
+        }
+
+        if self.degree() * other.degree() > MAX_DEGREE {
+            return None;
+        }
+
+        // The sum and the product of the numbers are eigenvalues of the Kronecker sum
+        // and the Kronecker product of the companion matrices of their polynomials.
+        let a = companion_matrix(&monic(&self.polynomial));
+        let b = companion_matrix(&monic(&other.polynomial));
+
+        let matrix = match operation {
+            Operation::Sum => {
+                let mut sum = kronecker_product(&a, &identity_matrix(b.len()));
+
+                for (row, other_row) in sum
+                    .iter_mut()
+                    .zip(kronecker_product(&identity_matrix(a.len()), &b))
+                {
+                    for (x, y) in row.iter_mut().zip(other_row) {
+                        *x += y;
+                    }
+                }
+
+                sum
+            }
+            Operation::Product => kronecker_product(&a, &b),
+        };
+
+        let (mut x, mut y) = (self.clone(), other.clone());
+
+        Self::isolate(&characteristic_polynomial(&matrix), || {
+            let result = match operation {
+                Operation::Sum => (&x.lower + &y.lower, &x.upper + &y.upper),
+                Operation::Product => {
+                    let products = [
+                        &x.lower * &y.lower,
+                        &x.lower * &y.upper,
+                        &x.upper * &y.lower,
+                        &x.upper * &y.upper,
+                    ];
+
+                    (
+                        products.iter().min().unwrap().clone(),
+                        products.iter().max().unwrap().clone(),
+                    )
+                }
+            };
+
+            x.refine();
+            y.refine();
+            result
+        })
+    }
+
+    /// Returns the root of the polynomial that is the only root in the closed interval
+    /// returned by some call to `interval`, where the intervals are expected to shrink
+    /// towards that root, or `None` if no such interval is found.
+    fn isolate(p: &[Rational], mut interval: impl FnMut() -> (Rational, Rational)) -> Option<Self> {
+        let factors = irreducible_factors(p)?;
+        let sequences = factors
+            .iter()
+            .map(|factor| sturm_sequence(factor))
+            .collect::<Vec<_>>();
+
+        for _ in 0..MAX_REFINEMENTS {
+            let (lower, upper) = interval();
+            let mut candidates = Vec::new();
+
+            for (factor, sequence) in factors.iter().zip(&sequences) {
+                if factor.len() == 2 {
+                    let root = -&factor[0] / &factor[1];
+
+                    if lower <= root && root <= upper {
+                        candidates.push(Self::from_rational(root));
+                    }
+                } else {
+                    // The lower bound is not a root because the factor has no rational roots.
+                    for _ in 0..count_roots(sequence, &lower, &upper) {
+                        candidates.push(Self {
+                            polynomial: factor.clone(),
+                            lower: lower.clone(),
+                            upper: upper.clone(),
+                        });
+                    }
+                }
+            }
+
+            if candidates.len() == 1 {
+                return candidates.pop();
+            }
+        }
+
+        None
+    }
+}
+
+impl PartialEq for AlgebraicNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for AlgebraicNumber {}
+
+impl Ord for AlgebraicNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let Some(x) = other.to_rational() {
+            return self.compare_rational(&x);
+        } else if let Some(x) = self.to_rational() {
+            return other.compare_rational(&x).reverse();
+        }
+
+        let (mut x, mut y) = (self.clone(), other.clone());
+
+        loop {
+            if x.polynomial == y.polynomial {
+                // Both intervals contain exactly one root of the same polynomial,
+                // so the numbers are equal if and only if the intersection
+                // of the intervals contains a root.
+                let lower = (&x.lower).max(&y.lower);
+                let upper = (&x.upper).min(&y.upper);
+
+                if lower < upper && count_roots(&sturm_sequence(&x.polynomial), lower, upper) == 1 {
+                    return Ordering::Equal;
+                }
+            }
+
+            if x.upper <= y.lower {
+                return Ordering::Less;
+            } else if y.upper <= x.lower {
+                return Ordering::Greater;
+            }
+
+            x.refine();
+            y.refine();
+        }
+    }
+}
+
+impl PartialOrd for AlgebraicNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "functions")]
+impl AlgebraicNumber {
+    /// Returns the `k`th smallest real root of the polynomial expression with rational
+    /// coefficients in the variable with the given identifier, starting with 1,
+    /// or `None` if there is no such root or the expression is not such a polynomial.
+    pub(crate) fn root_of(p: &Expression, identifier: &str, k: usize) -> Option<Self> {
+        let polynomial = Polynomial::from_expression(p)?;
+
+        if polynomial.variables().iter().any(|x| x != identifier) {
+            return None;
+        }
+
+        Self::roots(&polynomial)?.into_iter().nth(k.checked_sub(1)?)
+    }
+
+    /// Returns the algebraic number represented by the expression, which can be
+    /// a rational number, a surd, or a call to `root_of`.
+    pub(crate) fn from_expression(expression: &Expression) -> Option<Self> {
+        if let Some(arguments) = root_of_arguments(expression) {
+            return match arguments {
+                [p, Expression::Variable(identifier), k] => Self::root_of(
+                    p,
+                    identifier,
+                    Integer::try_from(k.clone()).ok()?.to_usize()?,
+                ),
+                _ => None,
+            };
+        }
+
+        let surd = Surd::from_expression(expression)?;
+
+        if surd.is_rational() {
+            return Some(Self::from_rational(surd.coefficient().clone()));
+        }
+
+        // `c * r ^ (1/n)` is the only root of `x ^ n - c ^ n * r`
+        // between zero and `c * r` (because `r > 1`).
+        let (c, r, n) = (surd.coefficient(), surd.radicand(), surd.index());
+        let mut p = vec![Rational::zero(); n as usize + 1];
+        p[0] = -c.pow(n as i32) * Rational::from(r.clone());
+        p[n as usize] = Rational::one();
+
+        let p = Polynomial::from_coefficients("x", &p);
+        let bound = c * Rational::from(r.clone());
+
+        if c.is_positive() {
+            Self::new(&p, Rational::zero(), bound)
+        } else {
+            Self::new(&p, bound - Rational::one(), Rational::zero())
+        }
+    }
+
+    /// Returns the expression representing the number, which is a rational number
+    /// or a call to `root_of` with the minimal polynomial in the variable `x`.
+    pub(crate) fn to_expression(&self) -> Expression {
+        match self.to_rational() {
+            Some(x) => Expression::Complex(x.into(), RationalRepresentation::Fraction),
+            None => fun(
+                function_expression("root_of").unwrap(),
+                [
+                    self.polynomial("x").to_expression(),
+                    var("x"),
+                    Expression::Integer(self.index().into()),
+                ],
+            ),
+        }
+    }
+}
+
+/// Returns the arguments of the expression if it is a call to `root_of`.
+#[cfg(feature = "functions")]
+fn root_of_arguments(expression: &Expression) -> Option<&[Expression]> {
+    match expression {
+        Expression::FunctionValue(f, arguments) => match &**f {
+            Expression::Function(identifier, _) | Expression::Variable(identifier)
+                if identifier == "root_of" =>
+            {
+                Some(arguments)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the negation of the evaluated operand if it is a call to `root_of`
+/// representing an algebraic number, or `None` otherwise.
+#[cfg(feature = "functions")]
+pub(crate) fn negate(a: &Expression) -> Option<Expression> {
+    root_of_arguments(a)?;
+
+    Some(
+        AlgebraicNumber::from_expression(a)?
+            .negate()
+            .to_expression(),
+    )
+}
+
+/// Returns the result of the arithmetic operation or comparison (`operation`) on the
+/// evaluated operands if at least one of them is a call to `root_of` representing
+/// an algebraic number and the other one is a rational number, a surd, or such a call,
+/// or `None` otherwise.
+#[cfg(feature = "functions")]
+pub(crate) fn evaluate(
+    operation: &Expression,
+    a: &Expression,
+    b: &Expression,
+) -> Option<Expression> {
+    use crate::expression::Expression::*;
+
+    if root_of_arguments(a).is_none() && root_of_arguments(b).is_none() {
+        return None;
+    }
+
+    let x = AlgebraicNumber::from_expression(a)?;
+
+    if let Power(_, _) = operation {
+        let n = crate::expression::Integer::try_from(b.clone())
+            .ok()?
+            .to_i32()?;
+        return Some(x.power(n)?.to_expression());
+    }
+
+    let y = AlgebraicNumber::from_expression(b)?;
+
+    let result = match operation {
+        Sum(_, _) => x.add(&y)?,
+        Difference(_, _) => x.add(&y.negate())?,
+        Product(_, _) => x.multiply(&y)?,
+        Quotient(_, _) => x.multiply(&y.reciprocal()?)?,
+        Equal(_, _) => return Some(Boolean(x == y)),
+        NotEqual(_, _) => return Some(Boolean(x != y)),
+        LessThan(_, _) => return Some(Boolean(x < y)),
+        LessThanOrEqual(_, _) => return Some(Boolean(x <= y)),
+        GreaterThan(_, _) => return Some(Boolean(x > y)),
+        GreaterThanOrEqual(_, _) => return Some(Boolean(x >= y)),
+        _ => return None,
+    };
+
+    Some(result.to_expression())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use crate::{
+        algebraic::AlgebraicNumber,
+        expression::{Expression, Rational},
+        polynomial::Polynomial,
+    };
+
+    fn polynomial(p: &str) -> Polynomial {
+        Polynomial::from_expression(&p.parse::<Expression>().unwrap()).unwrap()
+    }
+
+    fn root(p: &str, k: usize) -> AlgebraicNumber {
+        AlgebraicNumber::roots(&polynomial(p)).unwrap()[k - 1].clone()
+    }
+
+    fn r(numerator: i64, denominator: i64) -> Rational {
+        Rational::new(numerator.into(), denominator.into())
+    }
+
+    #[test]
+    fn roots() {
+        let roots = AlgebraicNumber::roots(&polynomial("x^5 - 3*x^3 - x^2 + 3")).unwrap();
+
+        assert_eq!(roots.len(), 3);
+        assert_eq!(roots[1].to_rational(), Some(r(1, 1)));
+        assert_eq!(roots[0].approximate(6), r(-1_732_051, 1_000_000));
+        assert_eq!(roots[2].approximate(6), r(1_732_051, 1_000_000));
+        assert_eq!(roots[0].index(), 1);
+        assert_eq!(roots[2].index(), 2);
+
+        assert!(AlgebraicNumber::roots(&polynomial("x^2 + 1"))
+            .unwrap()
+            .is_empty());
+        assert!(AlgebraicNumber::roots(&polynomial("x * y")).is_none());
+    }
+
+    #[test]
+    fn new() {
+        let phi = AlgebraicNumber::new(&polynomial("x^2 - x - 1"), r(1, 1), r(2, 1)).unwrap();
+
+        assert_eq!(phi, root("x^2 - x - 1", 2));
+        assert_eq!(phi.approximate(10), r(16_180_339_887, 10_000_000_000));
+        assert_eq!(phi.polynomial("y"), polynomial("y^2 - y - 1"));
+
+        assert!(AlgebraicNumber::new(&polynomial("x^2 - x - 1"), r(-1, 1), r(2, 1)).is_none());
+        assert!(AlgebraicNumber::new(&polynomial("x^2 - x - 1"), r(2, 1), r(3, 1)).is_none());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let phi = root("x^2 - x - 1", 2);
+        let sqrt_2 = root("x^2 - 2", 2);
+        let cbrt_2 = root("x^3 - 2", 1);
+
+        // phi ^ 2 == phi + 1
+        assert_eq!(
+            phi.power(2).unwrap(),
+            phi.add(&AlgebraicNumber::from_rational(r(1, 1))).unwrap(),
+        );
+
+        let sum = sqrt_2.add(&cbrt_2).unwrap();
+        assert_eq!(sum.degree(), 6);
+        assert_eq!(sum.approximate(6), r(2_674_135, 1_000_000));
+
+        let product = sqrt_2.multiply(&sqrt_2).unwrap();
+        assert_eq!(product.to_rational(), Some(r(2, 1)));
+
+        let difference = sqrt_2.add(&sqrt_2.negate()).unwrap();
+        assert_eq!(difference.to_rational(), Some(r(0, 1)));
+
+        assert_eq!(
+            phi.reciprocal().unwrap(),
+            phi.add(&AlgebraicNumber::from_rational(r(-1, 1))).unwrap(),
+        );
+
+        assert_eq!(sqrt_2.cmp(&phi), Ordering::Less);
+        assert_eq!(phi.negate().cmp(&sqrt_2.negate()), Ordering::Less);
+        assert_eq!(cbrt_2.power(3).unwrap().to_rational(), Some(r(2, 1)));
+        assert_eq!(sqrt_2.power(-2).unwrap().to_rational(), Some(r(1, 2)));
+    }
+}
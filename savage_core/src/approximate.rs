@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{Signed, ToPrimitive, Zero};
+
+use crate::{
+    bigfloat::BigFloat,
+    evaluate::LimitExceeded,
+    expression::{Expression, Rational, RationalRepresentation},
+};
+
+#[cfg(feature = "functions")]
+use crate::{algebraic::AlgebraicNumber, evaluate::Options};
+
+/// Number of bits by which the precision of the computation exceeds
+/// the precision required for the requested number of digits.
+pub(crate) const GUARD_BITS: u32 = 32;
+
+/// Maximum number of times the precision of the computation is doubled
+/// when the results at two consecutive precisions differ.
+pub(crate) const MAX_DOUBLINGS: u32 = 4;
+
+/// Returns the identifier of the function applied by the given function value
+/// and its arguments, if any.
+pub(crate) fn function_value(expression: &Expression) -> Option<(&str, &[Expression])> {
+    match expression {
+        Expression::FunctionValue(function, arguments) => match &**function {
+            Expression::Variable(identifier) | Expression::Function(identifier, _) => {
+                Some((identifier, arguments))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the value of the expression rounded to the given number of bits
+/// (with rounding errors accumulating over the operations), or `None`
+/// if the expression is not a real number or contains an operation
+/// that cannot be approximated.
+fn value(expression: &Expression, precision: u32) -> Option<BigFloat> {
+    use crate::expression::Expression::*;
+
+    let value = |a: &Expression| value(a, precision);
+
+    match expression {
+        Integer(n) => Some(BigFloat::new(n.clone(), 0, precision)),
+        Rational(x, _) => Some(BigFloat::from_rational(x, precision)),
+        Complex(z, _) if z.im.is_zero() => Some(BigFloat::from_rational(&z.re, precision)),
+        Variable(identifier) if identifier == "pi" => Some(BigFloat::pi(precision)),
+        Variable(identifier) if identifier == "e" => Some(BigFloat::e(precision)),
+        Negation(a) => Some(-&value(a)?),
+        Sum(a, b) => Some(&value(a)? + &value(b)?),
+        Difference(a, b) => Some(&value(a)? - &value(b)?),
+        Product(a, b) => Some(&value(a)? * &value(b)?),
+        Quotient(a, b) => value(a)?.checked_div(&value(b)?),
+        Power(a, b) => match &**b {
+            Integer(n) => value(a)?.powi(n.to_i64()?),
+            _ => value(a)?.pow(&value(b)?),
+        },
+        _ => match function_value(expression)? {
+            ("sqrt", [a]) => value(a)?.sqrt(),
+            ("sin", [a]) => value(a)?.sin(),
+            ("cos", [a]) => value(a)?.cos(),
+            ("tan", [a]) => value(a)?.tan(),
+            ("exp", [a]) => value(a)?.exp(),
+            ("ln", [a]) => value(a)?.ln(),
+            ("log", [b, a]) => value(a)?.ln()?.checked_div(&value(b)?.ln()?),
+            ("gamma", [a]) => value(a)?.gamma(),
+            ("erf", [a]) => value(a)?.erf(),
+            ("erfc", [a]) => value(a)?.erfc(),
+            ("zeta", [a]) => value(a)?.zeta(),
+            ("Ei", [a]) => value(a)?.ei(),
+            #[cfg(feature = "functions")]
+            ("root_of", _) => {
+                AlgebraicNumber::from_expression(expression).map(|x| x.to_bigfloat(precision))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Returns the real and imaginary parts of the value of the expression rounded
+/// to the given number of bits, or `None` if the expression is not a complex number
+/// built from real numbers (see `value`) and complex numbers using sums, differences,
+/// products, quotients by real numbers, and powers, e.g. `(-1) ^ (1/3)` or `i ^ i`.
+#[cfg(feature = "complex")]
+fn complex_value(expression: &Expression, precision: u32) -> Option<(BigFloat, BigFloat)> {
+    use crate::expression::Expression::*;
+
+    if let Some(x) = value(expression, precision) {
+        return Some((x, BigFloat::zero(precision)));
+    }
+
+    let complex_value = |a: &Expression| complex_value(a, precision);
+
+    match expression {
+        Complex(z, _) => Some((
+            BigFloat::from_rational(&z.re, precision),
+            BigFloat::from_rational(&z.im, precision),
+        )),
+        Negation(a) => {
+            let (re, im) = complex_value(a)?;
+            Some((-&re, -&im))
+        }
+        Sum(a, b) => {
+            let ((a_re, a_im), (b_re, b_im)) = (complex_value(a)?, complex_value(b)?);
+            Some((&a_re + &b_re, &a_im + &b_im))
+        }
+        Difference(a, b) => {
+            let ((a_re, a_im), (b_re, b_im)) = (complex_value(a)?, complex_value(b)?);
+            Some((&a_re - &b_re, &a_im - &b_im))
+        }
+        Product(a, b) => {
+            let ((a_re, a_im), (b_re, b_im)) = (complex_value(a)?, complex_value(b)?);
+            Some((
+                &(&a_re * &b_re) - &(&a_im * &b_im),
+                &(&a_re * &b_im) + &(&a_im * &b_re),
+            ))
+        }
+        Quotient(a, b) => {
+            let (re, im) = complex_value(a)?;
+            let b = value(b, precision)?;
+            Some((re.checked_div(&b)?, im.checked_div(&b)?))
+        }
+        Power(a, b) => {
+            // Principal value: a ^ b = exp(b * (ln(|a|) + i * arg(a)))
+            let ((a_re, a_im), (b_re, b_im)) = (complex_value(a)?, complex_value(b)?);
+
+            let log_abs = (&(&a_re * &a_re) + &(&a_im * &a_im)).sqrt()?.ln()?;
+            let arg = a_im.atan2(&a_re)?;
+
+            let modulus = (&(&b_re * &log_abs) - &(&b_im * &arg)).exp()?;
+            let angle = &(&b_re * &arg) + &(&b_im * &log_abs);
+
+            Some((&modulus * &angle.cos()?, &modulus * &angle.sin()?))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the value of the expression rounded to the given number of significant
+/// decimal digits, or `None` if it cannot be approximated (see `value`), or the error
+/// returned by `check` for the initial approximation of the value.
+fn decimal_value(
+    expression: &Expression,
+    digits: u32,
+    check: &impl Fn(&BigFloat) -> Result<(), LimitExceeded>,
+) -> Result<Option<Rational>, LimitExceeded> {
+    converged_decimal(|precision| value(expression, precision), digits, check)
+}
+
+/// Returns the value computed by `value` for a given number of bits, rounded to
+/// the given number of significant decimal digits, or `None` if `value` returns `None`,
+/// or the error returned by `check` for the initial approximation of the value.
+///
+/// The value is computed with increasing precision until the rounded results
+/// at two consecutive precisions agree, which compensates for the loss
+/// of significant digits when subtracting nearly equal numbers. Results that
+/// are zero or do not agree are recomputed up to the maximum precision,
+/// and if they still do not agree but are smaller than the rounding error
+/// of the initial precision, the value is assumed to be zero.
+fn converged_decimal(
+    value: impl Fn(u32) -> Option<BigFloat>,
+    digits: u32,
+    check: &impl Fn(&BigFloat) -> Result<(), LimitExceeded>,
+) -> Result<Option<Rational>, LimitExceeded> {
+    let initial_precision =
+        (f64::from(digits) * std::f64::consts::LOG2_10).ceil() as u32 + GUARD_BITS;
+    let mut precision = initial_precision;
+
+    let mut result = match value(precision) {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+
+    check(&result)?;
+
+    for _ in 0..MAX_DOUBLINGS {
+        precision *= 2;
+
+        let next = match value(precision) {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+
+        if !next.is_zero() && next.to_decimal(digits) == result.to_decimal(digits) {
+            return Ok(Some(next.to_decimal(digits)));
+        }
+
+        result = next;
+    }
+
+    let epsilon = Rational::new(
+        1.into(),
+        crate::expression::Integer::from(1) << initial_precision as usize,
+    );
+
+    if result.to_rational().abs() < epsilon {
+        Ok(Some(Rational::zero()))
+    } else {
+        Ok(Some(result.to_decimal(digits)))
+    }
+}
+
+/// Returns the rational number rounded to the given number of significant decimal digits.
+fn round(x: &Rational, digits: u32) -> Rational {
+    BigFloat::from_rational(x, digits * 4 + GUARD_BITS).to_decimal(digits)
+}
+
+impl Expression {
+    /// Returns the value of the expression rounded to the given number of bits
+    /// (with rounding errors accumulating over the operations), or `None`
+    /// if the expression is not a real number or cannot be approximated.
+    #[cfg(feature = "functions")]
+    pub(crate) fn approximate_value(&self, precision: u32) -> Option<BigFloat> {
+        value(self, precision)
+    }
+
+    /// Returns the expression with all real numerical subexpressions (including
+    /// constants like `pi` and functions like `sqrt`, `sin`, and `ln` of numbers) replaced
+    /// with their values rounded to the given number of significant decimal digits,
+    /// which must be positive, as numbers in decimal representation. The real and
+    /// imaginary parts of complex numbers are rounded separately.
+    pub fn approximate(&self, digits: u32) -> Self {
+        match self.approximate_with(digits, &|_| Ok(())) {
+            Ok(result) => result,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the result of `approximate`, or an error if the decimal representation
+    /// of an approximated number would exceed the digit limits of the options,
+    /// e.g. because its magnitude is extremely large or small.
+    #[cfg(feature = "functions")]
+    pub(crate) fn approximate_within_limits(
+        &self,
+        digits: u32,
+        options: &Options,
+    ) -> Result<Self, LimitExceeded> {
+        self.approximate_with(digits, &|x| {
+            options.check_digits(
+                (x.magnitude().abs() as f64) * std::f64::consts::LOG10_2 + f64::from(digits),
+            )
+        })
+    }
+
+    /// Returns the result of `approximate`, or the error returned by `check`
+    /// for the initial approximation of a number.
+    fn approximate_with(
+        &self,
+        digits: u32,
+        check: &impl Fn(&BigFloat) -> Result<(), LimitExceeded>,
+    ) -> Result<Self, LimitExceeded> {
+        use crate::expression::Expression::*;
+
+        if let Complex(z, _) = self {
+            if !z.im.is_zero() {
+                return Ok(Complex(
+                    crate::expression::Complex::new(round(&z.re, digits), round(&z.im, digits)),
+                    RationalRepresentation::Decimal,
+                ));
+            }
+        }
+
+        if let Some(x) = decimal_value(self, digits, check)? {
+            return Ok(Rational(x, RationalRepresentation::Decimal));
+        }
+
+        #[cfg(feature = "complex")]
+        {
+            let part = |imaginary: bool| {
+                converged_decimal(
+                    |precision| {
+                        complex_value(self, precision)
+                            .map(|(re, im)| if imaginary { im } else { re })
+                    },
+                    digits,
+                    check,
+                )
+            };
+
+            if let (Some(re), Some(im)) = (part(false)?, part(true)?) {
+                return Ok(Complex(
+                    crate::expression::Complex::new(re, im),
+                    RationalRepresentation::Decimal,
+                ));
+            }
+        }
+
+        let mut error = None;
+
+        let result = self.map_children(|a| {
+            a.approximate_with(digits, check)
+                .unwrap_or_else(|limit_exceeded| {
+                    error.get_or_insert(limit_exceeded);
+                    a.clone()
+                })
+        });
+
+        match error {
+            Some(limit_exceeded) => Err(limit_exceeded),
+            None => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+
+    #[track_caller]
+    fn a(expression: &str, digits: u32, result: &str) {
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .approximate(digits)
+                .to_string(),
+            result,
+        );
+    }
+
+    #[test]
+    fn approximate() {
+        a(
+            "pi",
+            50,
+            "3.1415926535897932384626433832795028841971693993751",
+        );
+        a("e", 20, "2.7182818284590452354");
+        a("2 / 3", 5, "0.66667");
+        a("-sqrt(3) / 2", 8, "-0.8660254");
+        a("2 ^ 0.5 * 2 ^ (1/2)", 10, "2");
+        a("sqrt(10 ^ 40 + 1) - 10 ^ 20", 5, "0.000000000000000000005");
+        a("tan(pi / 4)", 10, "1");
+        a("ln(2)", 30, "0.693147180559945309417232121458");
+        a("exp(-1) * e", 20, "1");
+        a("log(10, 2)", 12, "0.301029995664");
+        a("gamma(1/3) * gamma(2/3)", 20, "3.6275987284684357012");
+        a("zeta(3) - erf(1/2)", 20, "0.68155702534654774772");
+        a("sin(x) + cos(pi)", 3, "sin(x) + -1");
+        #[cfg(feature = "matrices")]
+        a("[1/7, sqrt(-1)]", 3, "[0.143, sqrt(-1)]");
+        a("1/3 * i", 2, "0.33 * i");
+        a("sin(pi) + 1 - 1", 10, "0");
+        a("1 - 1", 10, "0");
+        #[cfg(feature = "complex")]
+        a("3 * (-1) ^ (1/3)", 10, "1.5 + 2.598076211*i");
+        #[cfg(feature = "complex")]
+        a("2 ^ (1/3) * (-1) ^ (2/3)", 8, "1.0911236*i - 0.62996052");
+        #[cfg(feature = "complex")]
+        a("(-1) ^ (1/3) - 1/2", 10, "0.8660254038*i");
+    }
+}
@@ -0,0 +1,1376 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+//! Arbitrary-precision binary floating point numbers.
+
+use std::{
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use num::{Integer as _, One, Signed, ToPrimitive, Zero};
+
+use crate::expression::{Expression, Integer, Rational, RationalRepresentation};
+
+/// Number of additional bits used in intermediate results
+/// of elementary functions to compensate for rounding errors.
+const GUARD_BITS: u32 = 32;
+
+/// Maximum binary magnitude (the number of bits of the integer part) of arguments
+/// of elementary functions whose results would be too large or too expensive to compute.
+const MAX_MAGNITUDE: i64 = 1 << 16;
+
+/// Direction in which numbers are rounded to the precision of a `BigFloat`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Rounding {
+    /// To the nearest number, with ties rounded away from zero.
+    Nearest,
+    /// Towards negative infinity.
+    Down,
+    /// Towards positive infinity.
+    Up,
+}
+
+/// Arbitrary-precision binary floating point number `mantissa * 2 ^ exponent`,
+/// whose mantissa is rounded to `precision` bits after every operation.
+///
+/// The results of arithmetic operations are correctly rounded. The results of
+/// elementary functions are computed with additional bits and then rounded,
+/// so their last bit might be incorrect.
+#[derive(Clone, Debug)]
+pub struct BigFloat {
+    mantissa: Integer,
+    exponent: i64,
+    precision: u32,
+}
+
+/// Returns `x` divided by `2 ^ shift`, rounded to the nearest integer
+/// (with ties rounded away from zero).
+fn shift_right_rounded(x: &Integer, shift: u64) -> Integer {
+    if shift == 0 {
+        return x.clone();
+    }
+
+    let magnitude = (x.abs() + (Integer::one() << (shift - 1) as usize)) >> shift as usize;
+
+    if x.is_negative() {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Returns `x` divided by `2 ^ shift`, rounded to an integer in the given direction.
+fn shift_right(x: &Integer, shift: u64, rounding: Rounding) -> Integer {
+    let divisor = Integer::one() << shift as usize;
+
+    match rounding {
+        Rounding::Nearest => shift_right_rounded(x, shift),
+        Rounding::Down => x.div_floor(&divisor),
+        Rounding::Up => -(-x).div_floor(&divisor),
+    }
+}
+
+/// Returns `2 ^ bits` as a fixed point number with `bits` fractional bits, i.e. `1`.
+fn fixed_one(bits: u32) -> Integer {
+    Integer::one() << bits as usize
+}
+
+/// Returns the product of the fixed point numbers with `bits` fractional bits.
+fn fixed_multiply(a: &Integer, b: &Integer, bits: u32) -> Integer {
+    shift_right_rounded(&(a * b), u64::from(bits))
+}
+
+/// Returns the inverse hyperbolic tangent of the fixed point number `z`
+/// with `bits` fractional bits, which must satisfy `|z| <= 1/2`.
+fn fixed_atanh(z: &Integer, bits: u32) -> Integer {
+    let z_squared = fixed_multiply(z, z, bits);
+    let mut term = z.clone();
+    let mut sum = Integer::zero();
+    let mut k = 1_u64;
+
+    while !term.is_zero() {
+        sum += &term / Integer::from(k);
+        term = fixed_multiply(&term, &z_squared, bits);
+        k += 2;
+    }
+
+    sum
+}
+
+/// Returns the arctangent of the fixed point number `z`
+/// with `bits` fractional bits, which must satisfy `|z| <= 1/2`.
+fn fixed_atan(z: &Integer, bits: u32) -> Integer {
+    let z_squared = fixed_multiply(z, z, bits);
+    let mut term = z.clone();
+    let mut sum = Integer::zero();
+    let mut k = 1_u64;
+
+    while !term.is_zero() {
+        let summand = &term / Integer::from(k);
+
+        if k % 4 == 1 {
+            sum += summand;
+        } else {
+            sum -= summand;
+        }
+
+        term = fixed_multiply(&term, &z_squared, bits);
+        k += 2;
+    }
+
+    sum
+}
+
+/// Returns the arctangent of `1/n` as a fixed point number with `bits` fractional bits.
+fn fixed_atan_reciprocal(n: u32, bits: u32) -> Integer {
+    let n_squared = Integer::from(n) * Integer::from(n);
+    let mut term = fixed_one(bits) / Integer::from(n);
+    let mut sum = Integer::zero();
+    let mut k = 1_u64;
+
+    while !term.is_zero() {
+        let summand = &term / Integer::from(k);
+
+        if k % 4 == 1 {
+            sum += summand;
+        } else {
+            sum -= summand;
+        }
+
+        term /= &n_squared;
+        k += 2;
+    }
+
+    sum
+}
+
+/// Returns `pi` as a fixed point number with `bits` fractional bits.
+fn fixed_pi(bits: u32) -> Integer {
+    // Machin's formula: pi = 16 * atan(1/5) - 4 * atan(1/239)
+    fixed_atan_reciprocal(5, bits) * Integer::from(16)
+        - fixed_atan_reciprocal(239, bits) * Integer::from(4)
+}
+
+/// Returns `ln(2)` as a fixed point number with `bits` fractional bits.
+fn fixed_ln_2(bits: u32) -> Integer {
+    // ln(2) = 2 * atanh(1/3)
+    fixed_atanh(&(fixed_one(bits) / Integer::from(3)), bits) * Integer::from(2)
+}
+
+/// Returns the tangent numbers `T(1)` to `T(n)` (the coefficients of `x ^ (2 * k - 1) / (2 * k - 1)!`
+/// in the Taylor series of `tan(x)`), computed with integer operations only.
+fn tangent_numbers(n: usize) -> Vec<Integer> {
+    // https://arxiv.org/abs/1108.0286, Algorithm TangentNumbers
+    let mut numbers = vec![Integer::one(); n];
+
+    for k in 1..n {
+        numbers[k] = &numbers[k - 1] * Integer::from(k);
+    }
+
+    for k in 1..n {
+        for j in k..n {
+            numbers[j] =
+                &numbers[j - 1] * Integer::from(j - k) + &numbers[j] * Integer::from(j - k + 2);
+        }
+    }
+
+    numbers
+}
+
+/// Returns Euler's constant `gamma` as a fixed point number with `bits` fractional bits.
+fn fixed_euler_gamma(bits: u32) -> Integer {
+    // Brent-McMillan algorithm (https://doi.org/10.1090/S0025-5718-1980-0551307-4,
+    // Algorithm B1), whose error is less than `pi * exp(-4 * n)`.
+    let n = u64::from(bits) / 5 + 2;
+    let n_squared = Integer::from(n) * Integer::from(n);
+
+    let mut a = -BigFloat::new(Integer::from(n), 0, bits)
+        .ln()
+        .unwrap()
+        .to_fixed(bits);
+    let mut b = fixed_one(bits);
+    let mut u = a.clone();
+    let mut v = b.clone();
+    let mut k = 1_u64;
+
+    while !a.is_zero() || !b.is_zero() {
+        b = b * &n_squared / Integer::from(k * k);
+        a = (a * &n_squared / Integer::from(k) + &b) / Integer::from(k);
+        u += &a;
+        v += &b;
+        k += 1;
+    }
+
+    (u << bits as usize) / v
+}
+
+impl BigFloat {
+    /// Returns the number `mantissa * 2 ^ exponent`, rounded to `precision` bits,
+    /// which must be positive.
+    pub fn new(mantissa: Integer, exponent: i64, precision: u32) -> Self {
+        Self::new_rounded(mantissa, exponent, precision, Rounding::Nearest)
+    }
+
+    /// Returns the number `mantissa * 2 ^ exponent`, rounded to `precision` bits,
+    /// which must be positive, in the given direction.
+    pub fn new_rounded(
+        mantissa: Integer,
+        exponent: i64,
+        precision: u32,
+        rounding: Rounding,
+    ) -> Self {
+        assert!(precision > 0, "precision must be positive");
+
+        let bits = mantissa.bits();
+
+        if bits > u64::from(precision) {
+            let shift = bits - u64::from(precision);
+            let mantissa = shift_right(&mantissa, shift, rounding);
+
+            // Rounding can carry into an additional bit (e.g. `0b111` to `0b1000`),
+            // in which case the result is normalized again.
+            Self::new_rounded(mantissa, exponent + shift as i64, precision, rounding)
+        } else if mantissa.is_zero() {
+            Self {
+                mantissa,
+                exponent: 0,
+                precision,
+            }
+        } else {
+            Self {
+                mantissa,
+                exponent,
+                precision,
+            }
+        }
+    }
+
+    /// Returns zero with the given precision.
+    pub fn zero(precision: u32) -> Self {
+        Self::new(Integer::zero(), 0, precision)
+    }
+
+    /// Returns the rational number rounded to `precision` bits.
+    pub fn from_rational(x: &Rational, precision: u32) -> Self {
+        Self::from_rational_rounded(x, precision, Rounding::Nearest)
+    }
+
+    /// Returns the rational number rounded to `precision` bits in the given direction.
+    pub fn from_rational_rounded(x: &Rational, precision: u32, rounding: Rounding) -> Self {
+        if x.is_zero() {
+            return Self::zero(precision);
+        }
+
+        // The quotient has at least `precision + 1` bits, so dividing is only
+        // followed by rounding, and the remainder decides ties correctly.
+        let shift = i64::from(precision) + 1 + x.denom().bits() as i64 - x.numer().bits() as i64;
+
+        let (numerator, denominator) = if shift >= 0 {
+            (x.numer() << shift as usize, x.denom().clone())
+        } else {
+            (x.numer().clone(), x.denom() << (-shift) as usize)
+        };
+
+        let (quotient, remainder) = (&numerator / &denominator, &numerator % &denominator);
+
+        // A non-zero remainder is represented by an additional bit below the quotient,
+        // which makes ties impossible and rounds in the correct direction.
+        let mantissa = (quotient << 1_usize)
+            + if remainder.is_zero() {
+                Integer::zero()
+            } else if numerator.is_negative() {
+                -Integer::one()
+            } else {
+                Integer::one()
+            };
+
+        Self::new_rounded(mantissa, -shift - 1, precision, rounding)
+    }
+
+    /// Returns the exact value of the number as a rational number.
+    pub fn to_rational(&self) -> Rational {
+        if self.exponent >= 0 {
+            Rational::from(&self.mantissa << self.exponent as usize)
+        } else {
+            Rational::new(
+                self.mantissa.clone(),
+                Integer::one() << (-self.exponent) as usize,
+            )
+        }
+    }
+
+    /// Returns the number rounded to the given number of significant decimal digits,
+    /// which must be positive, as a rational number.
+    pub fn to_decimal(&self, digits: u32) -> Rational {
+        self.to_decimal_rounded(digits, Rounding::Nearest)
+    }
+
+    /// Returns the number rounded to the given number of significant decimal digits,
+    /// which must be positive, in the given direction, as a rational number.
+    pub fn to_decimal_rounded(&self, digits: u32, rounding: Rounding) -> Rational {
+        let x = self.to_rational();
+
+        if x.is_zero() {
+            return x;
+        }
+
+        let ten = Rational::from(Integer::from(10));
+
+        // Estimate of the decimal exponent of the number, corrected below
+        // so that `10 ^ exponent <= |x| < 10 ^ (exponent + 1)`.
+        let mut exponent = ((self.magnitude() as f64) * std::f64::consts::LOG10_2).floor() as i32;
+
+        while ten.pow(exponent) > x.abs() {
+            exponent -= 1;
+        }
+
+        while ten.pow(exponent + 1) <= x.abs() {
+            exponent += 1;
+        }
+
+        let scale = ten.pow(digits as i32 - 1 - exponent);
+        let x = x * &scale;
+
+        let rounded = match rounding {
+            Rounding::Nearest => x.round(),
+            Rounding::Down => x.floor(),
+            Rounding::Up => x.ceil(),
+        };
+
+        rounded / scale
+    }
+
+    /// Returns the expression representing the number rounded to the given number
+    /// of significant decimal digits, as a rational number in decimal representation.
+    pub fn to_expression(&self, digits: u32) -> Expression {
+        Expression::Rational(self.to_decimal(digits), RationalRepresentation::Decimal)
+    }
+
+    /// Returns the number of bits of the mantissa.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Returns the number rounded to the given number of bits.
+    pub fn with_precision(&self, precision: u32) -> Self {
+        Self::new(self.mantissa.clone(), self.exponent, precision)
+    }
+
+    /// Returns whether the number is zero.
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    /// Returns whether the number is negative.
+    pub fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    /// Returns the number of bits of the integer part of the absolute value
+    /// of the number, which is negative if the number is less than 1/2.
+    pub(crate) fn magnitude(&self) -> i64 {
+        self.exponent + self.mantissa.bits() as i64
+    }
+
+    /// Returns the number as a fixed point number with `bits` fractional bits, rounded.
+    fn to_fixed(&self, bits: u32) -> Integer {
+        let shift = self.exponent + i64::from(bits);
+
+        if shift >= 0 {
+            &self.mantissa << shift as usize
+        } else {
+            shift_right_rounded(&self.mantissa, (-shift) as u64)
+        }
+    }
+
+    /// Returns the fixed point number with `bits` fractional bits,
+    /// rounded to `precision` bits.
+    fn from_fixed(value: Integer, bits: u32, precision: u32) -> Self {
+        Self::new(value, -i64::from(bits), precision)
+    }
+
+    /// Returns `pi` rounded to `precision` bits.
+    pub fn pi(precision: u32) -> Self {
+        let bits = precision + GUARD_BITS;
+        Self::from_fixed(fixed_pi(bits), bits, precision)
+    }
+
+    /// Returns Euler's number `e` rounded to `precision` bits.
+    pub fn e(precision: u32) -> Self {
+        Self::new(Integer::one(), 0, precision).exp().unwrap()
+    }
+
+    /// Returns Euler's constant `gamma` rounded to `precision` bits.
+    pub fn euler_gamma(precision: u32) -> Self {
+        let bits = precision + GUARD_BITS;
+        Self::from_fixed(fixed_euler_gamma(bits), bits, precision)
+    }
+
+    /// Returns the absolute value of the number.
+    pub fn abs(&self) -> Self {
+        Self {
+            mantissa: self.mantissa.abs(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the square root of the number, or `None` if the number is negative.
+    pub fn sqrt(&self) -> Option<Self> {
+        if self.is_negative() {
+            return None;
+        } else if self.is_zero() {
+            return Some(self.clone());
+        }
+
+        // The mantissa is shifted so that its integer square root has
+        // at least `precision + 2` bits, and the exponent becomes even.
+        let mut shift = (2 * i64::from(self.precision) + 4 - self.mantissa.bits() as i64).max(0);
+
+        if (self.exponent - shift) % 2 != 0 {
+            shift += 1;
+        }
+
+        let root = (&self.mantissa << shift as usize).sqrt();
+
+        Some(Self::new(root, (self.exponent - shift) / 2, self.precision))
+    }
+
+    /// Returns the number raised to the integer power `n`,
+    /// or `None` if the number is zero and `n` is negative.
+    pub fn powi(&self, n: i64) -> Option<Self> {
+        if n < 0 {
+            return Self::new(Integer::one(), 0, self.precision).checked_div(&self.powi(-n)?);
+        }
+
+        let precision = self.precision + GUARD_BITS + 64;
+        let mut base = self.with_precision(precision);
+        let mut result = Self::new(Integer::one(), 0, precision);
+        let mut n = n as u64;
+
+        while n > 0 {
+            if n % 2 == 1 {
+                result = &result * &base;
+            }
+
+            base = &base * &base;
+            n /= 2;
+        }
+
+        Some(result.with_precision(self.precision))
+    }
+
+    /// Returns the exponential function of the number, or `None` if the result
+    /// would be too large or too small.
+    pub fn exp(&self) -> Option<Self> {
+        if self.is_zero() {
+            return Some(Self::new(Integer::one(), 0, self.precision));
+        } else if self.magnitude() > 32 {
+            return None;
+        }
+
+        // The argument is divided by `2 ^ halvings` so that it is less than 2^-8,
+        // and the result of the Taylor series is squared `halvings` times, which
+        // multiplies the relative error by `2 ^ halvings`.
+        let halvings = (self.magnitude() + 8).max(0) as u32;
+        let bits = self.precision + GUARD_BITS + halvings;
+
+        let x = self.to_fixed(bits - halvings);
+        let mut term = fixed_one(bits);
+        let mut sum = term.clone();
+        let mut n = 1_u64;
+
+        while !term.is_zero() {
+            term = fixed_multiply(&term, &x, bits) / Integer::from(n);
+            sum += &term;
+            n += 1;
+        }
+
+        let mut result = Self::from_fixed(sum, bits, bits);
+
+        for _ in 0..halvings {
+            result = &result * &result;
+        }
+
+        Some(result.with_precision(self.precision))
+    }
+
+    /// Returns the natural logarithm of the number,
+    /// or `None` if the number is not positive.
+    pub fn ln(&self) -> Option<Self> {
+        if self.is_negative() || self.is_zero() {
+            return None;
+        }
+
+        // With `x = f * 2 ^ k` and `1/2 <= f < 1`, `ln(x) = k * ln(2) + ln(f)`,
+        // and `ln(f) = 2 * atanh((f - 1) / (f + 1))`, where `|(f - 1) / (f + 1)| <= 1/3`.
+        let k = self.magnitude();
+        let bits = self.precision + GUARD_BITS + 64;
+
+        let f = Self::new(self.mantissa.clone(), -(self.mantissa.bits() as i64), bits);
+        let one = Self::new(Integer::one(), 0, bits);
+        let z = (&(&f - &one) / &(&f + &one)).to_fixed(bits);
+
+        let result = fixed_ln_2(bits) * Integer::from(k) + fixed_atanh(&z, bits) * Integer::from(2);
+
+        Some(Self::from_fixed(result, bits, self.precision))
+    }
+
+    /// Returns the number raised to the power `exponent`, which is computed as
+    /// `exp(exponent * ln(self))`, or `None` if the number is not positive
+    /// or the result would be too large or too small.
+    pub fn pow(&self, exponent: &Self) -> Option<Self> {
+        let precision = self.precision.max(exponent.precision);
+        let guard = (self.magnitude().abs() + exponent.magnitude().max(0)).min(MAX_MAGNITUDE);
+        let working = precision + GUARD_BITS + guard as u32;
+
+        let logarithm = self.with_precision(working).ln()?;
+
+        (&exponent.with_precision(working) * &logarithm)
+            .exp()
+            .map(|result| result.with_precision(precision))
+    }
+
+    /// Returns the sine and the cosine of the number, or `None`
+    /// if the number is too large to reduce it modulo `2 * pi`.
+    fn sin_cos(&self) -> Option<(Self, Self)> {
+        if self.magnitude() > MAX_MAGNITUDE {
+            return None;
+        }
+
+        let bits = self.precision + GUARD_BITS + self.magnitude().max(0) as u32;
+
+        // The argument is reduced to `[-pi, pi]`.
+        let two_pi = fixed_pi(bits) * Integer::from(2);
+        let x = self.to_fixed(bits);
+        let k = shift_right_rounded(&((&x << 1_usize) / &two_pi), 1);
+        let x = x - k * two_pi;
+
+        let mut sin = Integer::zero();
+        let mut cos = Integer::zero();
+        let mut term = fixed_one(bits);
+        let mut n = 0_u64;
+
+        // The terms of the Taylor series alternate between those of
+        // the cosine (even powers) and those of the sine (odd powers).
+        while !term.is_zero() {
+            let sign = if n % 4 < 2 { 1 } else { -1 };
+
+            if n % 2 == 1 {
+                sin += &term * sign;
+            } else {
+                cos += &term * sign;
+            }
+
+            n += 1;
+            term = fixed_multiply(&term, &x, bits) / Integer::from(n);
+        }
+
+        Some((
+            Self::from_fixed(sin, bits, self.precision),
+            Self::from_fixed(cos, bits, self.precision),
+        ))
+    }
+
+    /// Returns the sine of the number, or `None` if the number is too large.
+    pub fn sin(&self) -> Option<Self> {
+        self.sin_cos().map(|(sin, _)| sin)
+    }
+
+    /// Returns the cosine of the number, or `None` if the number is too large.
+    pub fn cos(&self) -> Option<Self> {
+        self.sin_cos().map(|(_, cos)| cos)
+    }
+
+    /// Returns the tangent of the number, or `None` if the number is too large.
+    pub fn tan(&self) -> Option<Self> {
+        let precision = self.precision + GUARD_BITS;
+        let (sin, cos) = self.with_precision(precision).sin_cos()?;
+
+        sin.checked_div(&cos)
+            .map(|result| result.with_precision(self.precision))
+    }
+
+    /// Returns the angle (in the interval `(-pi, pi]`) of the point `(x, self)`
+    /// from the positive x-axis, or `None` if both numbers are zero.
+    pub fn atan2(&self, x: &Self) -> Option<Self> {
+        if self.is_zero() && x.is_zero() {
+            return None;
+        }
+
+        let precision = self.precision.max(x.precision);
+        let bits = precision + GUARD_BITS;
+
+        // The quotient of the smaller by the larger coordinate is in `[-1, 1]`,
+        // and `atan(z) = 2 * atan(z / (1 + sqrt(1 + z ^ 2)))`, where the argument
+        // on the right-hand side is in `[-tan(pi/8), tan(pi/8)]`.
+        let swapped = self.abs().to_rational() > x.abs().to_rational();
+        let (numerator, denominator) = if swapped { (x, self) } else { (self, x) };
+
+        let one = Self::new(Integer::one(), 0, bits);
+        let z = &numerator.with_precision(bits) / &denominator.with_precision(bits);
+        let z = &z / &(&one + &(&one + &(&z * &z)).sqrt()?);
+
+        let pi = fixed_pi(bits);
+        let mut angle = fixed_atan(&z.to_fixed(bits), bits) * Integer::from(2);
+
+        if swapped {
+            // atan2(y, x) = sign(y) * pi/2 - atan(x / y)
+            let half_pi = &pi / Integer::from(2);
+            angle = if self.is_negative() {
+                -half_pi
+            } else {
+                half_pi
+            } - angle;
+        } else if x.is_negative() {
+            if self.is_negative() {
+                angle -= pi;
+            } else {
+                angle += pi;
+            }
+        }
+
+        Some(Self::from_fixed(angle, bits, precision))
+    }
+
+    /// Returns the gamma function of the number, or `None` if the number
+    /// is a non-positive integer or the result would be too large or too small.
+    pub fn gamma(&self) -> Option<Self> {
+        let x = self.to_rational();
+
+        if (x.is_integer() && !x.is_positive()) || self.magnitude() > 24 {
+            return None;
+        }
+
+        let bits = self.precision + 2 * GUARD_BITS;
+        let one = Self::new(Integer::one(), 0, bits);
+        let x = self.with_precision(bits);
+
+        if x.to_rational() < Rational::new(Integer::one(), Integer::from(2)) {
+            // Reflection formula: gamma(x) = pi / (sin(pi * x) * gamma(1 - x))
+            let pi = Self::pi(bits);
+            let denominator = &(&pi * &x).sin()? * &(&one - &x).gamma()?;
+
+            return pi
+                .checked_div(&denominator)
+                .map(|result| result.with_precision(self.precision));
+        }
+
+        // The argument is shifted by `n` so that the terms of Stirling's series
+        // for `ln(gamma(x + n))` decrease rapidly, and the result is divided by
+        // `x * (x + 1) * ... * (x + n - 1)` afterwards.
+        let n = (i64::from(bits) - x.to_i64()?).max(0);
+        let z = &x + &Self::new(Integer::from(n), 0, bits);
+
+        let half = Self::new(Integer::one(), -1, bits);
+        let two_pi = &Self::pi(bits) * &Self::new(Integer::from(2), 0, bits);
+
+        // ln(gamma(z)) = (z - 1/2) * ln(z) - z + ln(2 * pi) / 2
+        //                + sum(B(2 * k) / (2 * k * (2 * k - 1) * z ^ (2 * k - 1)), k, 1, infinity),
+        // where B(2 * k) / (2 * k) = (-1) ^ (k - 1) * T(k) / (4 ^ k * (4 ^ k - 1)).
+        let mut logarithm = &(&(&(&z - &half) * &z.ln()?) - &z) + &(&two_pi.ln()? * &half);
+
+        let terms = bits as usize / 8 + 2;
+        let z_squared = &z * &z;
+        let mut power = z.clone();
+
+        for (k, tangent) in (1..).zip(tangent_numbers(terms)) {
+            let four_k = Integer::one() << (2 * k) as usize;
+            let denominator = Integer::from(2 * k - 1) * &four_k * (four_k - Integer::one());
+            let coefficient =
+                Rational::new(if k % 2 == 1 { tangent } else { -tangent }, denominator);
+
+            logarithm =
+                &logarithm + &Self::from_rational(&coefficient, bits).checked_div(&power)?;
+            power = &power * &z_squared;
+        }
+
+        let mut product = one.clone();
+
+        for i in 0..n {
+            product = &product * &(&x + &Self::new(Integer::from(i), 0, bits));
+        }
+
+        logarithm
+            .exp()?
+            .checked_div(&product)
+            .map(|result| result.with_precision(self.precision))
+    }
+
+    /// Returns the error function of the number.
+    pub fn erf(&self) -> Option<Self> {
+        let bits = self.precision + GUARD_BITS;
+        let x = self.with_precision(bits);
+        let x_squared = &x * &x;
+
+        // Since `erfc(x) < exp(-x ^ 2)` for positive `x`,
+        // the result rounds to `1` or `-1` if `x ^ 2 > bits`.
+        if x_squared.to_rational() > Rational::from(Integer::from(bits)) {
+            let one = Self::new(Integer::one(), 0, self.precision);
+            return Some(if self.is_negative() { -&one } else { one });
+        }
+
+        // erf(x) = 2 / sqrt(pi) * exp(-x ^ 2)
+        //          * sum(2 ^ n * x ^ (2 * n + 1) / (1 * 3 * ... * (2 * n + 1)), n, 0, infinity),
+        // whose terms all have the same sign, so no significant bits are lost.
+        let two_x_squared = &x_squared + &x_squared;
+        let mut term = x.clone();
+        let mut sum = x.clone();
+        let mut n = 0_u64;
+
+        while !term.is_zero() && term.magnitude() > sum.magnitude() - i64::from(bits) {
+            n += 1;
+            term = &(&term * &two_x_squared) / &Self::new(Integer::from(2 * n + 1), 0, bits);
+            sum = &sum + &term;
+        }
+
+        let two = Self::new(Integer::from(2), 0, bits);
+
+        (&(&two * &(-&x_squared).exp()?) * &sum)
+            .checked_div(&Self::pi(bits).sqrt()?)
+            .map(|result| result.with_precision(self.precision))
+    }
+
+    /// Returns the complementary error function `1 - erf(x)` of the number,
+    /// or `None` if the result would be too small.
+    pub fn erfc(&self) -> Option<Self> {
+        let bits = self.precision + GUARD_BITS;
+        let x_squared = self.to_rational().pow(2);
+
+        if self.is_negative() || x_squared <= Rational::from(Integer::from(bits)) {
+            // For positive `x`, subtracting `erf(x)` from `1` loses
+            // about `x ^ 2 * log2(e)` significant bits.
+            let extra = if self.is_negative() {
+                0
+            } else {
+                (x_squared.to_f64()? * std::f64::consts::LOG2_E).ceil() as u32
+            };
+
+            let bits = bits + extra;
+            let one = Self::new(Integer::one(), 0, bits);
+
+            return Some((&one - &self.with_precision(bits).erf()?).with_precision(self.precision));
+        }
+
+        // erfc(x) = exp(-x ^ 2) / (sqrt(pi) * f), where `f` is the continued fraction
+        // x + (1/2) / (x + 1 / (x + (3/2) / (x + 2 / (x + ...)))), evaluated
+        // with the modified Lentz algorithm.
+        let x = self.with_precision(bits);
+        let one = Self::new(Integer::one(), 0, bits);
+        let half = Self::new(Integer::one(), -1, bits);
+
+        let mut f = x.clone();
+        let mut c = x.clone();
+        let mut d = Self::zero(bits);
+        let mut k = 0_u64;
+
+        loop {
+            k += 1;
+
+            if k > 4 * u64::from(bits) {
+                return None;
+            }
+
+            let a = &half * &Self::new(Integer::from(k), 0, bits);
+            d = one.checked_div(&(&x + &(&a * &d)))?;
+            c = &x + &a.checked_div(&c)?;
+
+            let delta = &c * &d;
+            f = &f * &delta;
+
+            let error = &delta - &one;
+
+            if error.is_zero() || error.magnitude() < -i64::from(bits) {
+                break;
+            }
+        }
+
+        (-&(&x * &x))
+            .exp()?
+            .checked_div(&(&Self::pi(bits).sqrt()? * &f))
+            .map(|result| result.with_precision(self.precision))
+    }
+
+    /// Returns the exponential integral `Ei` of the number, or `None`
+    /// if the number is zero or the result would be too large or too small.
+    pub fn ei(&self) -> Option<Self> {
+        if self.is_zero() || self.magnitude() > 32 {
+            return None;
+        }
+
+        let bits = self.precision + GUARD_BITS;
+        let absolute_value = self.abs().to_f64()?;
+
+        if absolute_value > f64::from(bits) {
+            // Asymptotic series: Ei(x) ~ exp(x) / x * sum(k! / x ^ k, k, 0, infinity),
+            // whose terms decrease below `2 ^ -bits` before they start to increase
+            // (at `k = |x|`, where they are about `exp(-|x|)`).
+            let x = self.with_precision(bits);
+            let mut term = Self::new(Integer::one(), 0, bits);
+            let mut sum = term.clone();
+            let mut k = 0_u64;
+
+            loop {
+                k += 1;
+                term = &(&term * &Self::new(Integer::from(k), 0, bits)) / &x;
+
+                if term.is_zero() || term.magnitude() < -i64::from(bits) {
+                    break;
+                }
+
+                sum = &sum + &term;
+            }
+
+            return (&x.exp()? * &sum)
+                .checked_div(&x)
+                .map(|result| result.with_precision(self.precision));
+        }
+
+        // Ei(x) = gamma + ln(|x|) + sum(x ^ n / (n * n!), n, 1, infinity), where the terms
+        // for negative `x` have alternating signs, which loses about `|x| * log2(e)`
+        // significant bits.
+        let bits = if self.is_negative() {
+            bits + (absolute_value * std::f64::consts::LOG2_E).ceil() as u32
+        } else {
+            bits
+        };
+
+        let x = self.with_precision(bits);
+        let mut term = Self::new(Integer::one(), 0, bits);
+        let mut sum = &Self::euler_gamma(bits) + &self.abs().with_precision(bits).ln()?;
+        let mut n = 0_u64;
+
+        loop {
+            n += 1;
+
+            let n_float = Self::new(Integer::from(n), 0, bits);
+            term = &(&term * &x) / &n_float;
+
+            let summand = &term / &n_float;
+            sum = &sum + &summand;
+
+            if summand.is_zero()
+                || (n as f64 > absolute_value && summand.magnitude() < -i64::from(bits))
+            {
+                break;
+            }
+        }
+
+        Some(sum.with_precision(self.precision))
+    }
+
+    /// Returns the Riemann zeta function of the number,
+    /// or `None` if the number is `1` or too large.
+    pub fn zeta(&self) -> Option<Self> {
+        let s = self.to_rational();
+
+        // Since `zeta(s) - 1 < 2 ^ (1 - s)` for `s >= 2`, the result rounds to `1` for large `s`.
+        if s > Rational::from(Integer::from(self.precision + GUARD_BITS)) {
+            return Some(Self::new(Integer::one(), 0, self.precision));
+        } else if s.is_one() || self.magnitude() > 24 {
+            return None;
+        }
+
+        if s < Rational::new(Integer::one(), Integer::from(2)) {
+            // Functional equation:
+            // zeta(s) = 2 ^ s * pi ^ (s - 1) * sin(pi * s / 2) * gamma(1 - s) * zeta(1 - s)
+            let bits = self.precision + 2 * GUARD_BITS;
+            let s = self.with_precision(bits);
+            let one = Self::new(Integer::one(), 0, bits);
+            let two = Self::new(Integer::from(2), 0, bits);
+            let pi = Self::pi(bits);
+            let t = &one - &s;
+
+            let factor = &(&two.pow(&s)? * &pi.pow(&-&t)?) * &(&(&pi * &s) / &two).sin()?;
+
+            return Some((&(&factor * &t.gamma()?) * &t.zeta()?).with_precision(self.precision));
+        }
+
+        // Computing `1 - 2 ^ (1 - s)` loses about `-log2(|s - 1|)` significant bits.
+        let one = Self::new(Integer::one(), 0, self.precision);
+        let bits = self.precision + GUARD_BITS + (-(self - &one).magnitude()).max(0) as u32;
+        let s = self.with_precision(bits);
+        let one = one.with_precision(bits);
+
+        // Borwein's algorithm (https://doi.org/10.1090/S0025-5718-99-01032-7, Algorithm 2)
+        // for the alternating zeta function, whose error is less than `3 / (3 + sqrt(8)) ^ n`:
+        // zeta(s) = -1 / (d(n) * (1 - 2 ^ (1 - s))) * sum((-1) ^ k * (d(k) - d(n)) / (k + 1) ^ s, k, 0, n - 1),
+        // where d(k) = n * sum((n + i - 1)! * 4 ^ i / ((n - i)! * (2 * i)!), i, 0, k).
+        let n = u64::from(bits) * 2 / 5 + 2;
+        let mut term = Rational::one();
+        let mut d = vec![term.clone()];
+
+        for i in 1..=n {
+            term *= Rational::new(
+                Integer::from(4 * (n + i - 1) * (n - i + 1)),
+                Integer::from(2 * i * (2 * i - 1)),
+            );
+            d.push(&d[d.len() - 1] + &term);
+        }
+
+        let d_n = &d[n as usize];
+        let integer_exponent = if s.to_rational().is_integer() {
+            s.to_i64()
+        } else {
+            None
+        };
+        let mut sum = Self::zero(bits);
+
+        for (k, d_k) in d.iter().take(n as usize).enumerate() {
+            let base = Self::new(Integer::from(k + 1), 0, bits);
+            let power = match integer_exponent {
+                Some(exponent) => base.powi(exponent)?,
+                None => base.pow(&s)?,
+            };
+
+            let summand = Self::from_rational(&(d_k - d_n), bits).checked_div(&power)?;
+            sum = if k % 2 == 1 {
+                &sum - &summand
+            } else {
+                &sum + &summand
+            };
+        }
+
+        let two = Self::new(Integer::from(2), 0, bits);
+        let denominator = &Self::from_rational(d_n, bits) * &(&one - &two.pow(&(&one - &s))?);
+
+        (-&sum)
+            .checked_div(&denominator)
+            .map(|result| result.with_precision(self.precision))
+    }
+
+    /// Returns the sum of the numbers, rounded in the given direction.
+    pub fn add_rounded(&self, other: &Self, rounding: Rounding) -> Self {
+        let exponent = self.exponent.min(other.exponent);
+
+        Self::new_rounded(
+            (&self.mantissa << (self.exponent - exponent) as usize)
+                + (&other.mantissa << (other.exponent - exponent) as usize),
+            exponent,
+            self.precision.max(other.precision),
+            rounding,
+        )
+    }
+
+    /// Returns the product of the numbers, rounded in the given direction.
+    pub fn mul_rounded(&self, other: &Self, rounding: Rounding) -> Self {
+        Self::new_rounded(
+            &self.mantissa * &other.mantissa,
+            self.exponent + other.exponent,
+            self.precision.max(other.precision),
+            rounding,
+        )
+    }
+
+    /// Returns the quotient of the numbers, or `None` if `other` is zero.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        self.div_rounded(other, Rounding::Nearest)
+    }
+
+    /// Returns the quotient of the numbers, rounded in the given direction,
+    /// or `None` if `other` is zero.
+    pub fn div_rounded(&self, other: &Self, rounding: Rounding) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+
+        let precision = self.precision.max(other.precision);
+
+        Some(Self::from_rational_rounded(
+            &(self.to_rational() / other.to_rational()),
+            precision,
+            rounding,
+        ))
+    }
+}
+
+impl Neg for &BigFloat {
+    type Output = BigFloat;
+
+    fn neg(self) -> BigFloat {
+        BigFloat {
+            mantissa: -self.mantissa.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+impl Add for &BigFloat {
+    type Output = BigFloat;
+
+    fn add(self, other: &BigFloat) -> BigFloat {
+        self.add_rounded(other, Rounding::Nearest)
+    }
+}
+
+impl Sub for &BigFloat {
+    type Output = BigFloat;
+
+    fn sub(self, other: &BigFloat) -> BigFloat {
+        self + &-other
+    }
+}
+
+impl Mul for &BigFloat {
+    type Output = BigFloat;
+
+    fn mul(self, other: &BigFloat) -> BigFloat {
+        self.mul_rounded(other, Rounding::Nearest)
+    }
+}
+
+impl Div for &BigFloat {
+    type Output = BigFloat;
+
+    /// Returns the quotient of the numbers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero. Use [`BigFloat::checked_div`] to avoid this.
+    fn div(self, other: &BigFloat) -> BigFloat {
+        self.checked_div(other).expect("division by zero")
+    }
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_rational() == other.to_rational()
+    }
+}
+
+impl fmt::Display for BigFloat {
+    /// Formats the number in decimal representation with as many significant
+    /// digits as are accurately represented by the precision.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits =
+            ((f64::from(self.precision) * std::f64::consts::LOG10_2).floor() as u32).max(1);
+        write!(f, "{}", self.to_expression(digits))
+    }
+}
+
+impl ToPrimitive for BigFloat {
+    fn to_i64(&self) -> Option<i64> {
+        self.to_rational().to_integer().to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.to_rational().to_integer().to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        self.with_precision(53).to_rational().to_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{One, ToPrimitive};
+
+    use crate::{
+        bigfloat::{BigFloat, Rounding},
+        expression::{Integer, Rational},
+    };
+
+    const PI: &str = "3.14159265358979323846264338327950288419716939937510582097494459230781640628620899862803482534211706798214808651328230664709384460955058223172535940812848111745028410270193852110555964462294895493038196";
+    const E: &str = "2.71828182845904523536028747135266249775724709369995957496696762772407663035354759457138217852516642742746";
+    const SQRT_2: &str = "1.41421356237309504880168872420969807856967187537694807317667973799073247846210703885038753432764157273501384623";
+    const LN_2: &str = "0.69314718055994530941723212145817656807550013436025525412068000949339362196969471560586332699641868754200148102057";
+
+    /// Returns the first `digits` significant digits of the decimal string, rounded.
+    fn rounded(string: &str, digits: u32) -> String {
+        let x = string.trim_start_matches('-');
+        let x = x.parse::<crate::expression::Expression>().unwrap();
+        let x = Rational::try_from(x).unwrap();
+        let x = if string.starts_with('-') { -x } else { x };
+        let result = BigFloat::from_rational(&x, 2000).to_expression(digits);
+        result.to_string()
+    }
+
+    #[track_caller]
+    fn t(x: Option<BigFloat>, expected: &str, digits: u32) {
+        assert_eq!(
+            x.unwrap().to_expression(digits).to_string(),
+            rounded(expected, digits)
+        );
+    }
+
+    fn n(x: i64, precision: u32) -> BigFloat {
+        BigFloat::new(Integer::from(x), 0, precision)
+    }
+
+    #[test]
+    fn constants() {
+        t(Some(BigFloat::pi(700)), PI, 200);
+        t(Some(BigFloat::e(400)), E, 100);
+        t(Some(BigFloat::pi(60)), PI, 15);
+    }
+
+    #[test]
+    fn functions() {
+        t(n(2, 400).sqrt(), SQRT_2, 100);
+        t(n(2, 400).ln(), LN_2, 100);
+        t(n(1, 400).exp(), E, 100);
+        t(n(2, 400).exp().and_then(|x| x.ln()), "2", 100);
+        t(n(10, 400).ln().and_then(|x| x.exp()), "10", 100);
+        t(
+            n(2, 400).pow(&BigFloat::from_rational(
+                &Rational::new(1.into(), 2.into()),
+                400,
+            )),
+            SQRT_2,
+            100,
+        );
+        t(
+            n(6, 300).sin(),
+            "-0.279415498198925872811555446611894759627994864318204318483351369",
+            60,
+        );
+        t(
+            n(6, 300).cos(),
+            "0.9601702866503660205456522979229244054519376792110126981292864260535459",
+            60,
+        );
+        t(
+            n(1, 300).tan(),
+            "1.55740772465490223050697480745836017308725077238152003838394660",
+            60,
+        );
+        t(n(3, 100).powi(-2), "0.111111111111111111111111111111", 30);
+
+        t(
+            n(1, 300).atan2(&n(1, 300)),
+            "0.785398163397448309615660845819875721049292349843776455243736148",
+            60,
+        );
+        t(
+            n(2, 300).atan2(&n(-1, 300)),
+            "2.034443935795702735445577923100965844127121753973673174298405384874106",
+            60,
+        );
+        t(
+            n(-1, 300).atan2(&n(-3, 300)),
+            "-2.81984209919315104506123876892084156517641410381744962954214153295106",
+            60,
+        );
+        t(n(0, 300).atan2(&n(-1, 300)), PI, 60);
+
+        assert!(BigFloat::pi(300).sin().unwrap().to_f64().unwrap().abs() < 1e-85);
+        assert!(n(0, 100).atan2(&n(0, 100)).is_none());
+        assert!(n(-1, 100).sqrt().is_none());
+        assert!(n(0, 100).ln().is_none());
+        assert!(n(1 << 40, 100).exp().is_none());
+    }
+
+    #[test]
+    fn gamma() {
+        let r = |numerator: i64, denominator: i64, precision: u32| {
+            BigFloat::from_rational(
+                &Rational::new(numerator.into(), denominator.into()),
+                precision,
+            )
+        };
+
+        t(n(5, 100).gamma(), "24", 30);
+        t(n(100, 600).gamma(), "933262154439441526816992388562667004907159682643816214685929638952175999932299156089414639761565182862536979208272237582511852109168640000000000000000000000", 150);
+        t(
+            r(1, 2, 400).gamma(),
+            "1.7724538509055160272981674833411451827975494561223871282138077898529112845910321813749506567385446654162268236",
+            100,
+        );
+        t(
+            r(1, 3, 400).gamma(),
+            "2.6789385347077476336556929409746776441286893779573011009504283275904176101677438195409828890411887894191590492",
+            100,
+        );
+        t(
+            r(-5, 2, 400).gamma(),
+            "-0.94530872048294188122568932444861076415869304326527313504736415458821935178188383006664035026055715488865430593",
+            100,
+        );
+        t(
+            r(1, 1000, 200).gamma(),
+            "999.42377248459546611498220129964400046521761014561223246954217169139602381182840384524838777218984017556346123",
+            50,
+        );
+        t(
+            r(-61, 3, 200).gamma(),
+            "-0.00000000000000000054332363480849878561225959347488818442701205654349175609585714406888951925090231673311694304149174936345507466",
+            50,
+        );
+
+        assert!(n(0, 100).gamma().is_none());
+        assert!(n(-3, 100).gamma().is_none());
+    }
+
+    #[test]
+    fn special_functions() {
+        let r = |numerator: i64, denominator: i64, precision: u32| {
+            BigFloat::from_rational(
+                &Rational::new(numerator.into(), denominator.into()),
+                precision,
+            )
+        };
+        let power_of_10 = |exponent: u32, precision: u32| {
+            BigFloat::new(Integer::from(10).pow(exponent), 0, precision)
+        };
+
+        t(
+            Some(BigFloat::euler_gamma(400)),
+            "0.57721566490153286060651209008240243104215933593992359880576723488486772677766467093694706329174674951463144725",
+            100,
+        );
+        t(
+            n(1, 400).erf(),
+            "0.84270079294971486934122063508260925929606699796630290845993789783471725409601084126198332534814488845415826153",
+            100,
+        );
+        t(
+            r(-1, 2, 300).erf(),
+            "-0.52049987781304653768274665389196452873645157575796370005880572564719352171685357091478821873478775703296612439",
+            60,
+        );
+        t(
+            n(5, 300).erfc(),
+            "0.0000000000015374597944280348501883434853833788901180503147233799306879140559203913645586914647181438594641887693124296122",
+            60,
+        );
+        t(
+            n(30, 300).erfc().map(|x| &x * &power_of_10(393, 300)),
+            "2.5646562037561116000333972775014471465488897227786170541225995861842386947791973507574559246002319256975429913",
+            60,
+        );
+        t(
+            n(-2, 300).erfc(),
+            "1.9953222650189527341620692563672529286108917970400600767383523262004372807199951773676290080196806804879393287",
+            60,
+        );
+        t(
+            n(1, 400).ei(),
+            "1.8951178163559367554665209343316342690170605817327075916462284318825138345338041535489007101261389569718110953",
+            100,
+        );
+        t(
+            n(-50, 300).ei(),
+            "-0.0000000000000000000000037832640295504590186989678540212857803028931862511140475242885945040244214444537225373267034740493716570674698",
+            60,
+        );
+        t(
+            n(500, 200).ei().map(|x| &x / &power_of_10(214, 200)),
+            "2.8128213978862943374749315178964386976934867868985673914467070572774859432875754160537771257885841963546356095",
+            50,
+        );
+        t(
+            n(-500, 200).ei().map(|x| &x * &power_of_10(220, 200)),
+            "-1.422076782253638422098193936057278281607864840846107048335737710730609069099890259443206815051756226215933092",
+            50,
+        );
+        t(
+            n(3, 400).zeta(),
+            "1.2020569031595942853997381615114499907649862923404988817922715553418382057863130901864558736093352581461991578",
+            100,
+        );
+        t(
+            r(1, 2, 300).zeta(),
+            "-1.4603545088095868128894991525152980124672293310125814905428860878255305294745006252764193754633568195144963747",
+            60,
+        );
+        t(
+            r(-1, 2, 300).zeta(),
+            "-0.20788622497735456601730672539704930222626853128767253761011355710614729193229234048754326694073321564310997561",
+            60,
+        );
+        t(
+            r(-15, 2, 300).zeta(),
+            "0.0032690395726002200217173953164688431859117208917165424292059661691326340787155609108312582907008766133829305122",
+            60,
+        );
+        t(
+            r(999, 1000, 300).zeta(),
+            "-999.42285715578879000992076041969468145087260326376243630284179490143442765969438238557494383117831380198765257",
+            60,
+        );
+
+        assert!(n(0, 100).ei().is_none());
+        assert!(n(1, 100).zeta().is_none());
+        assert_eq!(
+            n(1 << 40, 100).zeta().unwrap().to_rational(),
+            Rational::one()
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let third = BigFloat::from_rational(&Rational::new(1.into(), 3.into()), 100);
+
+        assert_eq!(
+            (&(&third + &third) + &third).to_expression(30).to_string(),
+            "1",
+        );
+        assert_eq!((&third * &n(3, 100)).to_expression(20).to_string(), "1");
+        assert_eq!(
+            (&n(1, 100) / &n(8, 100)).to_rational(),
+            Rational::new(1.into(), 8.into())
+        );
+        assert_eq!(
+            (&n(1, 100) - &third).to_expression(5).to_string(),
+            "0.66667"
+        );
+        assert_eq!(
+            n(12345, 8).to_rational(),
+            Rational::from(Integer::from(12352))
+        );
+        assert_eq!(format!("{}", BigFloat::pi(50)), "3.14159265358979");
+        assert!(n(1, 10).checked_div(&n(0, 10)).is_none());
+    }
+
+    #[test]
+    fn directed_rounding() {
+        let third = Rational::new(1.into(), 3.into());
+
+        for x in [third.clone(), -third] {
+            let lower = BigFloat::from_rational_rounded(&x, 20, Rounding::Down);
+            let upper = BigFloat::from_rational_rounded(&x, 20, Rounding::Up);
+
+            assert!(lower.to_rational() < x && x < upper.to_rational());
+            assert_eq!(
+                upper.to_rational() - lower.to_rational(),
+                Rational::new(1.into(), Integer::one() << 21_usize),
+            );
+        }
+
+        assert_eq!(
+            BigFloat::new_rounded(Integer::from(12345), 0, 8, Rounding::Down).to_rational(),
+            Rational::from(Integer::from(12288))
+        );
+        assert_eq!(
+            BigFloat::new_rounded(Integer::from(-12345), 0, 8, Rounding::Down).to_rational(),
+            Rational::from(Integer::from(-12352))
+        );
+        assert_eq!(
+            BigFloat::new_rounded(Integer::from(12289), 0, 8, Rounding::Up).to_rational(),
+            Rational::from(Integer::from(12352))
+        );
+        assert_eq!(
+            n(1, 10)
+                .add_rounded(&BigFloat::new(Integer::one(), -20, 10), Rounding::Up)
+                .to_rational(),
+            Rational::new(513.into(), 512.into())
+        );
+        assert_eq!(
+            n(1, 10)
+                .div_rounded(&n(3, 10), Rounding::Down)
+                .map(|x| x.to_rational()),
+            Some(Rational::new(341.into(), 1024.into()))
+        );
+        assert_eq!(
+            n(1, 100)
+                .div_rounded(&n(3, 100), Rounding::Up)
+                .map(|x| x.to_decimal_rounded(3, Rounding::Up).to_string()),
+            Some("167/500".to_owned())
+        );
+        assert_eq!(
+            BigFloat::pi(100).to_decimal_rounded(5, Rounding::Down),
+            Rational::new(31415.into(), 10000.into())
+        );
+    }
+}
@@ -0,0 +1,606 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::collections::HashMap;
+
+use num::{Signed, Zero};
+
+use crate::{
+    evaluate::Options,
+    expression::{Expression, Type},
+    helpers::*,
+};
+
+/// Returns the identifier of the function that is applied
+/// by a function value with the given function expression.
+fn function_name(function: &Expression) -> Option<&str> {
+    match function {
+        Expression::Variable(identifier) | Expression::Function(identifier, _) => Some(identifier),
+        _ => None,
+    }
+}
+
+/// Returns the product of the derivative `outer` of a function at the inner
+/// expression and the derivative `inner` of the inner expression (chain rule).
+fn chain(outer: Expression, inner: Expression) -> Expression {
+    if inner == int(1) {
+        outer
+    } else {
+        outer * inner
+    }
+}
+
+/// Returns the sum of the given expressions, written as a difference
+/// if the second expression is a negation.
+fn sum(a: Expression, b: Expression) -> Expression {
+    match b {
+        Expression::Negation(b) => a - *b,
+        _ => a + b,
+    }
+}
+
+/// Returns the difference of the given expressions, written as a sum
+/// if the second expression is a negation.
+fn difference(a: Expression, b: Expression) -> Expression {
+    match b {
+        Expression::Negation(b) => a + *b,
+        _ => a - b,
+    }
+}
+
+/// Returns the constant `c` such that `a == c * b` if it can be determined
+/// by factoring out constants (expressions not containing `x`) from products.
+fn constant_ratio(a: &Expression, b: &Expression, x: &Expression) -> Option<Expression> {
+    use crate::expression::Expression::*;
+
+    if a == b {
+        return Some(int(1));
+    }
+
+    match (a, b) {
+        (Product(c, a), _) | (Product(a, c), _) if !c.contains(x) => {
+            Some(*c.clone() * constant_ratio(a, b, x)?)
+        }
+        (_, Product(c, b)) | (_, Product(b, c)) if !c.contains(x) => {
+            Some(constant_ratio(a, b, x)? / *c.clone())
+        }
+        (Negation(a), _) => Some(-constant_ratio(a, b, x)?),
+        (_, Negation(b)) => Some(-constant_ratio(a, b, x)?),
+        _ => None,
+    }
+}
+
+/// Returns whether the expression is a number.
+fn is_number(expression: &Expression) -> bool {
+    matches!(expression.typ(), crate::expression::Type::Number(_, _))
+}
+
+/// Returns the result of evaluating the expression if it evaluates
+/// to a number, and the expression itself otherwise.
+fn evaluate_number(expression: Expression, options: &Options) -> Expression {
+    match expression.evaluate_with_options(HashMap::new(), options) {
+        Ok(number) if is_number(&number) => number,
+        _ => expression,
+    }
+}
+
+/// Returns the product of the constant `c` and `g`, combining `c`
+/// with the numeric coefficient of `g` if both are numbers.
+fn scale(c: Expression, g: Expression, options: &Options) -> Expression {
+    let c = evaluate_number(c, options);
+
+    let (c, g) = match g {
+        Expression::Product(k, g) if is_number(&k) => {
+            if is_number(&c) {
+                (evaluate_number(c * *k, options), *g)
+            } else {
+                // The numeric coefficient is kept in front.
+                (*k, c * *g)
+            }
+        }
+        Expression::Negation(g) if is_number(&c) => (evaluate_number(-c, options), *g),
+        g => (c, g),
+    };
+
+    if c == int(1) {
+        g
+    } else if c == int(-1) {
+        -g
+    } else {
+        c * g
+    }
+}
+
+impl Expression {
+    /// Returns an antiderivative `g` of the expression, viewed as a function of
+    /// an inner expression `u` (e.g. `u ^ 3` or `sin(u)`), with respect to `u`,
+    /// together with `u`, or `None` if the expression has no such form.
+    fn outer_antiderivative(&self, x: &Self, options: &Options) -> Option<(Self, Self)> {
+        use crate::expression::Expression::*;
+
+        match self {
+            Power(u, n) if u.contains(x) && !n.contains(x) => {
+                let m = evaluate_number(*n.clone() + int(1), options);
+
+                Some((
+                    if m == int(0) {
+                        fun(var("ln"), [*u.clone()])
+                    } else if is_number(&m) {
+                        scale(int(1) / m.clone(), pow(*u.clone(), m), options)
+                    } else {
+                        pow(*u.clone(), m.clone()) / m
+                    },
+                    *u.clone(),
+                ))
+            }
+            Power(a, u) if **a == var("e") => Some((self.clone(), *u.clone())),
+            Power(a, u) if !a.contains(x) => {
+                Some((self.clone() / fun(var("ln"), [*a.clone()]), *u.clone()))
+            }
+            FunctionValue(function, arguments) => match arguments.as_slice() {
+                [u] => Some((
+                    match function_name(function)? {
+                        "sin" => -fun(var("cos"), [u.clone()]),
+                        "cos" => fun(var("sin"), [u.clone()]),
+                        "exp" => fun(var("exp"), [u.clone()]),
+                        "sqrt" => rat(2, 3) * pow(u.clone(), rat(3, 2)),
+                        _ => return None,
+                    },
+                    u.clone(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns an antiderivative of the product of the expression, viewed as
+    /// a function of an inner expression `u`, and `factor`, if `factor` is
+    /// a constant multiple of the derivative of `u` (integration by substitution).
+    fn substitution_antiderivative(
+        &self,
+        factor: &Self,
+        variable: &str,
+        options: &Options,
+    ) -> Option<Self> {
+        let x = Expression::Variable(variable.to_owned());
+
+        let (g, u) = self.outer_antiderivative(&x, options)?;
+
+        let evaluate = |expression: Self| {
+            expression
+                .evaluate_with_options(HashMap::new(), options)
+                .ok()
+        };
+
+        let du = evaluate(u.derivative(variable)?)?;
+        let factor = evaluate(factor.clone())?;
+
+        Some(scale(constant_ratio(&factor, &du, &x)?, g, options))
+    }
+
+    /// Returns an antiderivative of the expression with respect to the variable
+    /// with the given identifier, or `None` if none can be determined. Polynomials,
+    /// powers, exponentials, and the functions `sin`, `cos`, `exp`, and `sqrt`
+    /// of linear expressions are integrated, as are products that can be integrated
+    /// by substitution (e.g. `x * cos(x ^ 2)`). The options are used when simplifying
+    /// intermediate results. The antiderivative of a vector, matrix, or tensor
+    /// is taken elementwise. The result should be evaluated.
+    pub fn antiderivative(&self, variable: &str, options: &Options) -> Option<Self> {
+        use crate::expression::Expression::*;
+
+        match self {
+            #[cfg(feature = "matrices")]
+            Vector(v) => {
+                return v
+                    .iter()
+                    .map(|x| x.antiderivative(variable, options))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|elements| Vector(crate::expression::Vector::from_vec(elements)));
+            }
+            #[cfg(feature = "matrices")]
+            Matrix(m) => {
+                return m
+                    .iter()
+                    .map(|x| x.antiderivative(variable, options))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|elements| {
+                        Matrix(crate::expression::Matrix::from_vec(
+                            m.nrows(),
+                            m.ncols(),
+                            elements,
+                        ))
+                    });
+            }
+            #[cfg(feature = "matrices")]
+            Tensor(t) => {
+                return t
+                    .elements()
+                    .iter()
+                    .map(|x| x.antiderivative(variable, options))
+                    .collect::<Option<Vec<_>>>()
+                    .and_then(|elements| {
+                        crate::expression::Tensor::new(t.shape().to_vec(), elements)
+                    })
+                    .map(Tensor);
+            }
+            _ => (),
+        }
+
+        let x = Variable(variable.to_owned());
+
+        if !self.contains(&x) {
+            return match self.typ() {
+                crate::expression::Type::Boolean(_)
+                | crate::expression::Type::String(_)
+                | crate::expression::Type::Function(_, _) => None,
+                _ => Some(scale(self.clone(), x, options)),
+            };
+        }
+
+        let a = |a: &Self| a.antiderivative(variable, options);
+
+        // Reciprocal of the given expression, as a power of the inner expression
+        // if the expression is a power.
+        let reciprocal = |a: &Self| match a {
+            Power(a, n) => pow(*a.clone(), evaluate_number(-*n.clone(), options)),
+            _ => pow(a.clone(), int(-1)),
+        };
+
+        match self {
+            Variable(_) => Some(rat(1, 2) * pow(x, int(2))),
+            Negation(b) => Some(scale(int(-1), a(b)?, options)),
+            Sum(b, c) => Some(sum(a(b)?, a(c)?)),
+            Difference(b, c) => Some(difference(a(b)?, a(c)?)),
+            Product(b, c) if !b.contains(&x) => Some(scale(*b.clone(), a(c)?, options)),
+            Product(b, c) if !c.contains(&x) => Some(scale(*c.clone(), a(b)?, options)),
+            Product(b, c) => b
+                .substitution_antiderivative(c, variable, options)
+                .or_else(|| c.substitution_antiderivative(b, variable, options)),
+            Quotient(b, c) if !c.contains(&x) => Some(scale(int(1) / *c.clone(), a(b)?, options)),
+            Quotient(b, c) if !b.contains(&x) => {
+                Some(scale(*b.clone(), a(&reciprocal(c))?, options))
+            }
+            Quotient(b, c) => {
+                let c = reciprocal(c);
+
+                c.substitution_antiderivative(b, variable, options)
+                    .or_else(|| b.substitution_antiderivative(&c, variable, options))
+            }
+            _ => {
+                // Functions of linear expressions, whose derivatives are constant.
+                let (g, u) = self.outer_antiderivative(&x, options)?;
+                let du = u.derivative(variable)?;
+
+                if du.contains(&x) {
+                    None
+                } else {
+                    Some(scale(int(1) / du, g, options))
+                }
+            }
+        }
+    }
+
+    /// Returns the derivative of the expression with respect to the variable
+    /// with the given identifier, or `None` if the derivative cannot be determined
+    /// symbolically, e.g. because the expression contains an unknown function
+    /// of the variable. The derivative of a vector, matrix, or tensor is taken
+    /// elementwise. The result is not simplified, and should be evaluated.
+    pub fn derivative(&self, variable: &str) -> Option<Self> {
+        use crate::expression::Expression::*;
+
+        match self {
+            #[cfg(feature = "matrices")]
+            Vector(v) => {
+                return v
+                    .iter()
+                    .map(|x| x.derivative(variable))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|elements| Vector(crate::expression::Vector::from_vec(elements)));
+            }
+            #[cfg(feature = "matrices")]
+            Matrix(m) => {
+                return m
+                    .iter()
+                    .map(|x| x.derivative(variable))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|elements| {
+                        Matrix(crate::expression::Matrix::from_vec(
+                            m.nrows(),
+                            m.ncols(),
+                            elements,
+                        ))
+                    });
+            }
+            #[cfg(feature = "matrices")]
+            Tensor(t) => {
+                return t
+                    .elements()
+                    .iter()
+                    .map(|x| x.derivative(variable))
+                    .collect::<Option<Vec<_>>>()
+                    .and_then(|elements| {
+                        crate::expression::Tensor::new(t.shape().to_vec(), elements)
+                    })
+                    .map(Tensor);
+            }
+            _ => (),
+        }
+
+        let x = Variable(variable.to_owned());
+
+        if !self.contains(&x) {
+            return match self.typ() {
+                crate::expression::Type::Boolean(_)
+                | crate::expression::Type::String(_)
+                | crate::expression::Type::Function(_, _) => None,
+                _ => Some(int(0)),
+            };
+        }
+
+        match self {
+            Variable(_) => Some(int(1)),
+            FunctionValue(function, arguments) => match arguments.as_slice() {
+                [u] => {
+                    let du = u.derivative(variable)?;
+
+                    let outer = match function_name(function)? {
+                        "sin" => fun(var("cos"), [u.clone()]),
+                        "cos" => -fun(var("sin"), [u.clone()]),
+                        "tan" => int(1) + pow(fun(var("tan"), [u.clone()]), int(2)),
+                        "sqrt" => int(1) / (int(2) * fun(var("sqrt"), [u.clone()])),
+                        "exp" => fun(var("exp"), [u.clone()]),
+                        "ln" => int(1) / u.clone(),
+                        _ => return None,
+                    };
+
+                    Some(chain(outer, du))
+                }
+                _ => None,
+            },
+            Negation(a) => Some(-a.derivative(variable)?),
+            Sum(a, b) => Some(sum(a.derivative(variable)?, b.derivative(variable)?)),
+            Difference(a, b) => Some(difference(a.derivative(variable)?, b.derivative(variable)?)),
+            Product(a, b) => {
+                if !a.contains(&x) {
+                    Some(chain(*a.clone(), b.derivative(variable)?))
+                } else if !b.contains(&x) {
+                    Some(chain(*b.clone(), a.derivative(variable)?))
+                } else {
+                    Some(sum(
+                        a.derivative(variable)? * *b.clone(),
+                        *a.clone() * b.derivative(variable)?,
+                    ))
+                }
+            }
+            Quotient(a, b) => {
+                if !b.contains(&x) {
+                    Some(a.derivative(variable)? / *b.clone())
+                } else {
+                    Some(
+                        difference(
+                            a.derivative(variable)? * *b.clone(),
+                            *a.clone() * b.derivative(variable)?,
+                        ) / pow(*b.clone(), int(2)),
+                    )
+                }
+            }
+            Power(a, b) if **a == var("e") => Some(chain(self.clone(), b.derivative(variable)?)),
+            Power(a, b) if !b.contains(&x) => Some(chain(
+                *b.clone() * pow(*a.clone(), *b.clone() - int(1)),
+                a.derivative(variable)?,
+            )),
+            Power(a, b) if !a.contains(&x) => Some(chain(
+                self.clone() * fun(var("ln"), [*a.clone()]),
+                b.derivative(variable)?,
+            )),
+            // General power rule: d(u ^ v) = u ^ v * (v' * ln(u) + v * u' / u)
+            Power(a, b) => Some(
+                self.clone()
+                    * sum(
+                        b.derivative(variable)? * fun(var("ln"), [*a.clone()]),
+                        *b.clone() * a.derivative(variable)? / *a.clone(),
+                    ),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns the Taylor polynomial of the given order of the expression in
+    /// the variable with the given identifier around `point`, followed by the
+    /// order term `O((x - point) ^ (order + 1))`, or `None` if a derivative
+    /// cannot be determined or cannot be evaluated at `point`, or if computing
+    /// the derivatives exceeds the timeout in the options. The options are used
+    /// when evaluating the derivatives. The result should be evaluated.
+    pub fn series(
+        &self,
+        variable: &str,
+        point: &Self,
+        order: usize,
+        options: &Options,
+    ) -> Option<Self> {
+        let x = var(variable);
+        let context = HashMap::from([(variable.to_owned(), point.clone())]);
+
+        let h = match evaluate_number(point.clone(), options).typ() {
+            Type::Number(z, _) if z.is_zero() => x.clone(),
+            Type::Number(z, _) if z.im.is_zero() && z.re.is_negative() => {
+                x.clone() + evaluate_number(-point.clone(), options)
+            }
+            _ => x.clone() - point.clone(),
+        };
+
+        let mut derivative = self.clone();
+        let mut factorial = crate::expression::Integer::from(1);
+        let mut polynomial: Option<Self> = None;
+
+        for k in 0..=order {
+            options.check_time().ok()?;
+
+            if k > 0 {
+                derivative = derivative.derivative(variable)?;
+
+                // Evaluating simplifies the derivative, which otherwise
+                // grows exponentially with the order.
+                if let Ok(simplified) = derivative.evaluate_with_options(HashMap::new(), options) {
+                    derivative = simplified;
+                }
+
+                factorial *= k;
+            }
+
+            let value = derivative
+                .evaluate_with_options(context.clone(), options)
+                .ok()?;
+
+            if value.contains(&x) {
+                return None;
+            }
+
+            let coefficient = evaluate_number(value / int(factorial.clone()), options);
+
+            if coefficient == int(0) {
+                continue;
+            }
+
+            let term = |coefficient| match k {
+                0 => coefficient,
+                1 => scale(coefficient, h.clone(), options),
+                _ => scale(coefficient, pow(h.clone(), int(k)), options),
+            };
+
+            polynomial = Some(match (polynomial, coefficient.typ()) {
+                (Some(polynomial), Type::Number(z, _)) if z.im.is_zero() && z.re.is_negative() => {
+                    polynomial - term(evaluate_number(-coefficient, options))
+                }
+                (Some(polynomial), _) => sum(polynomial, term(coefficient)),
+                (None, _) => term(coefficient),
+            });
+        }
+
+        let order_term = fun(var("O"), [pow(h, int(order + 1))]);
+
+        Some(match polynomial {
+            Some(polynomial) => polynomial + order_term,
+            None => order_term,
+        })
+    }
+}
+
+#[cfg(test)]
+// Tests that use built-in functions are skipped if those are disabled.
+#[cfg_attr(not(feature = "functions"), allow(unused_imports, dead_code))]
+mod tests {
+    use std::collections::HashMap;
+
+    #[cfg(feature = "matrices")]
+    use nalgebra::dvector;
+
+    use crate::evaluate::Options;
+    use crate::expression::Expression;
+    #[cfg(feature = "matrices")]
+    use crate::helpers::*;
+
+    #[track_caller]
+    fn d(expression: &str, derivative: &str) {
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .derivative("x")
+                .map(|x| x.to_string()),
+            Some(derivative.to_owned()),
+        );
+    }
+
+    #[test]
+    fn derivative() {
+        d("y", "0");
+        d("x", "1");
+        d("3 * x", "3");
+        d("x ^ 3", "3 * x ^ (3 - 1)");
+        d("x * y + 1", "y + 0");
+        d("x - cos(x)", "1 + sin(x)");
+        d("x * sin(x)", "1 * sin(x) + x * cos(x)");
+        d("cos(2 * x)", "-sin(2 * x) * 2");
+        d("1 / x", "(0 * x - 1 * 1) / x ^ 2");
+        d("(x + 1) / 2", "(1 + 0) / 2");
+        d("-tan(x)", "-(1 + tan(x) ^ 2)");
+        d("sqrt(x)", "1 / (2 * sqrt(x))");
+        d("ln(x ^ 2)", "1 / x ^ 2 * 2 * x ^ (2 - 1)");
+        d("2 ^ x", "2 ^ x * ln(2)");
+        d("x ^ x", "x ^ x * (1 * ln(x) + x * 1 / x)");
+        d(
+            "x ^ sin(x)",
+            "x ^ sin(x) * (cos(x) * ln(x) + sin(x) * 1 / x)",
+        );
+
+        #[cfg(feature = "matrices")]
+        assert_eq!(
+            Expression::Vector(dvector![var("x"), var("y")]).derivative("x"),
+            Some(Expression::Vector(dvector![int(1), int(0)])),
+        );
+        assert_eq!("f(x)".parse::<Expression>().unwrap().derivative("x"), None);
+        assert_eq!("x < 1".parse::<Expression>().unwrap().derivative("x"), None);
+    }
+
+    #[track_caller]
+    fn a(expression: &str, antiderivative: Option<&str>) {
+        let options = Options::default();
+
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .antiderivative("x", &options)
+                .map(|x| x.evaluate(HashMap::new()).unwrap().to_string()),
+            antiderivative.map(ToOwned::to_owned),
+        );
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn antiderivative() {
+        a("y", Some("y * x"));
+        a("x", Some("1/2 * x ^ 2"));
+        a("x ^ 2 / 2 - 3", Some("1/6 * x ^ 3 - 3 * x"));
+        a("(1 - x) ^ 2", Some("-1/3 * (1 - x) ^ 3"));
+        a("1 / (2 * x + 1)", Some("1/2 * ln(2 * x + 1)"));
+        a("exp(-x)", Some("-e ^ (-x)"));
+        a("cos(x) / sin(x) ^ 2", Some("-sin(x) ^ (-1)"));
+        a("x * exp(x ^ 2)", Some("1/2 * e ^ x ^ 2"));
+        a("x ^ x", None);
+        a("exp(x ^ 2)", None);
+        a("x < 1", None);
+    }
+    #[track_caller]
+    fn s(expression: &str, point: &str, order: usize, series: Option<&str>) {
+        let options = Options::default();
+
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .series("x", &point.parse().unwrap(), order, &options)
+                .map(|x| x.evaluate(HashMap::new()).unwrap().to_string()),
+            series.map(ToOwned::to_owned),
+        );
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn series() {
+        s("x ^ 2 + 1", "0", 3, Some("1 + x ^ 2 + O(x ^ 4)"));
+        s("x ^ 2", "-1", 1, Some("1 - 2 * (x + 1) + O((x + 1) ^ 2)"));
+        s(
+            "cos(x)",
+            "0",
+            4,
+            Some("1 - 1/2 * x ^ 2 + 1/24 * x ^ 4 + O(x ^ 5)"),
+        );
+        s("1 / (1 + x)", "0", 2, Some("1 - x + x ^ 2 + O(x ^ 3)"));
+        s("y", "0", 1, Some("y + O(x ^ 2)"));
+        s("1 / x", "0", 1, None);
+        s("f(x)", "0", 1, None);
+    }
+}
@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::fmt;
+
+use num::{ToPrimitive, Zero};
+
+use crate::expression::{Expression, Rational};
+
+/// Error that occurred while trying to compile an expression to a numeric function.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum CompileError {
+    /// The expression contains a variable that is not one of the parameters.
+    UnknownVariable(String),
+    /// The expression contains a subexpression that has no floating point equivalent,
+    /// such as a boolean, a vector, or a function call other than `sin`, `cos`, and `tan`.
+    UnsupportedExpression(Expression),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::UnknownVariable(identifier) => {
+                write!(f, "unknown variable {}", identifier)
+            }
+            CompileError::UnsupportedExpression(expression) => {
+                write!(f, "cannot compile {} to a numeric function", expression)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// Operation on floating point numbers with one operand.
+#[derive(Clone, Copy, Debug)]
+enum UnaryOperation {
+    Negation,
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+}
+
+/// Operation on floating point numbers with two operands.
+#[derive(Clone, Copy, Debug)]
+enum BinaryOperation {
+    Sum,
+    Difference,
+    Product,
+    Quotient,
+    Remainder,
+    Power,
+}
+
+/// Expression tree restricted to floating point arithmetic,
+/// from which both closures and source code are generated.
+#[derive(Clone, Debug)]
+enum Node {
+    Constant(f64),
+    /// Parameter with the given index.
+    Parameter(usize),
+    Unary(UnaryOperation, Box<Node>),
+    Binary(BinaryOperation, Box<Node>, Box<Node>),
+    /// Power with a constant integer exponent, which is faster and
+    /// more accurate than the general power.
+    IntegerPower(Box<Node>, i32),
+}
+
+/// Closure computing a floating point number from the parameter values.
+type Closure = dyn Fn(&[f64]) -> f64;
+
+/// Returns the value of the given rational number as a floating point number.
+fn rational_to_f64(x: &Rational) -> f64 {
+    x.to_f64().unwrap_or(f64::NAN)
+}
+
+impl Node {
+    /// Returns a closure that computes the value of the node from the parameter values.
+    fn to_closure(&self) -> Box<Closure> {
+        use BinaryOperation::*;
+        use UnaryOperation::*;
+
+        match self {
+            Node::Constant(x) => {
+                let x = *x;
+                Box::new(move |_| x)
+            }
+            Node::Parameter(i) => {
+                let i = *i;
+                Box::new(move |x| x[i])
+            }
+            Node::Unary(operation, a) => {
+                let a = a.to_closure();
+
+                match operation {
+                    Negation => Box::new(move |x| -a(x)),
+                    Sin => Box::new(move |x| a(x).sin()),
+                    Cos => Box::new(move |x| a(x).cos()),
+                    Tan => Box::new(move |x| a(x).tan()),
+                    Exp => Box::new(move |x| a(x).exp()),
+                    Ln => Box::new(move |x| a(x).ln()),
+                }
+            }
+            Node::Binary(operation, a, b) => {
+                let (a, b) = (a.to_closure(), b.to_closure());
+
+                match operation {
+                    Sum => Box::new(move |x| a(x) + b(x)),
+                    Difference => Box::new(move |x| a(x) - b(x)),
+                    Product => Box::new(move |x| a(x) * b(x)),
+                    Quotient => Box::new(move |x| a(x) / b(x)),
+                    Remainder => Box::new(move |x| a(x) % b(x)),
+                    Power => Box::new(move |x| a(x).powf(b(x))),
+                }
+            }
+            Node::IntegerPower(a, n) => {
+                let (a, n) = (a.to_closure(), *n);
+                Box::new(move |x| a(x).powi(n))
+            }
+        }
+    }
+
+    /// Returns Rust source code for an expression of type `f64` that computes
+    /// the value of the node from the parameter values in the slice `parameter`.
+    fn to_source(&self, parameter: &str) -> String {
+        use BinaryOperation::*;
+        use UnaryOperation::*;
+
+        match self {
+            Node::Constant(x) => {
+                if x.is_nan() {
+                    "f64::NAN".to_owned()
+                } else if x.is_infinite() {
+                    format!("{}f64::INFINITY", if *x < 0.0 { "-" } else { "" })
+                } else {
+                    // The `Debug` format always includes a decimal point or an exponent,
+                    // so the literal is never mistaken for an integer literal.
+                    format!("({:?}_f64)", x)
+                }
+            }
+            Node::Parameter(i) => format!("{}[{}]", parameter, i),
+            Node::Unary(operation, a) => {
+                let a = a.to_source(parameter);
+
+                match operation {
+                    Negation => format!("(-{})", a),
+                    Sin => format!("{}.sin()", a),
+                    Cos => format!("{}.cos()", a),
+                    Tan => format!("{}.tan()", a),
+                    Exp => format!("{}.exp()", a),
+                    Ln => format!("{}.ln()", a),
+                }
+            }
+            Node::Binary(operation, a, b) => {
+                let (a, b) = (a.to_source(parameter), b.to_source(parameter));
+
+                match operation {
+                    Sum => format!("({} + {})", a, b),
+                    Difference => format!("({} - {})", a, b),
+                    Product => format!("({} * {})", a, b),
+                    Quotient => format!("({} / {})", a, b),
+                    Remainder => format!("({} % {})", a, b),
+                    Power => format!("{}.powf({})", a, b),
+                }
+            }
+            Node::IntegerPower(a, n) => format!("{}.powi({})", a.to_source(parameter), n),
+        }
+    }
+}
+
+impl Expression {
+    /// Returns the floating point expression tree equivalent to the expression,
+    /// with the given variables as parameters.
+    #[allow(clippy::result_large_err)]
+    fn to_node(&self, variables: &[&str]) -> Result<Node, CompileError> {
+        use crate::expression::Expression::*;
+
+        let unary = |operation, a: &Self| -> Result<Node, CompileError> {
+            Ok(Node::Unary(operation, Box::new(a.to_node(variables)?)))
+        };
+
+        let binary = |operation, a: &Self, b: &Self| -> Result<Node, CompileError> {
+            Ok(Node::Binary(
+                operation,
+                Box::new(a.to_node(variables)?),
+                Box::new(b.to_node(variables)?),
+            ))
+        };
+
+        match self {
+            Variable(identifier) => match variables.iter().position(|v| v == identifier) {
+                Some(i) => Ok(Node::Parameter(i)),
+                None if identifier == "pi" => Ok(Node::Constant(std::f64::consts::PI)),
+                None if identifier == "e" => Ok(Node::Constant(std::f64::consts::E)),
+                None => Err(CompileError::UnknownVariable(identifier.clone())),
+            },
+            FunctionValue(function, arguments) => {
+                let operation = match (&**function, arguments.as_slice()) {
+                    (Variable(identifier), [_]) | (Function(identifier, _), [_]) => {
+                        match identifier.as_str() {
+                            "sin" => Some(UnaryOperation::Sin),
+                            "cos" => Some(UnaryOperation::Cos),
+                            "tan" => Some(UnaryOperation::Tan),
+                            "exp" => Some(UnaryOperation::Exp),
+                            "ln" => Some(UnaryOperation::Ln),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+
+                match operation {
+                    Some(operation) => unary(operation, &arguments[0]),
+                    None => Err(CompileError::UnsupportedExpression(self.clone())),
+                }
+            }
+            Integer(n) => Ok(Node::Constant(n.to_f64().unwrap_or(f64::NAN))),
+            Rational(x, _) => Ok(Node::Constant(rational_to_f64(x))),
+            Complex(z, _) if z.im.is_zero() => Ok(Node::Constant(rational_to_f64(&z.re))),
+            Negation(a) => unary(UnaryOperation::Negation, a),
+            Sum(a, b) => binary(BinaryOperation::Sum, a, b),
+            Difference(a, b) => binary(BinaryOperation::Difference, a, b),
+            Product(a, b) => binary(BinaryOperation::Product, a, b),
+            Quotient(a, b) => binary(BinaryOperation::Quotient, a, b),
+            Remainder(a, b) => binary(BinaryOperation::Remainder, a, b),
+            Power(a, b) => match &**b {
+                Integer(n) if n.to_i32().is_some() => Ok(Node::IntegerPower(
+                    Box::new(a.to_node(variables)?),
+                    n.to_i32().unwrap(),
+                )),
+                _ => binary(BinaryOperation::Power, a, b),
+            },
+            _ => Err(CompileError::UnsupportedExpression(self.clone())),
+        }
+    }
+
+    /// Returns a closure that computes the value of the expression as a floating point number,
+    /// given the values of the variables in the order in which they are listed in `variables`.
+    /// This is much faster than repeatedly evaluating the expression, at the cost of exactness.
+    ///
+    /// Only real arithmetic and the functions `sin`, `cos`, and `tan` are supported.
+    /// Operations whose results are not real numbers produce `NaN`.
+    /// The closure panics if it is passed fewer values than there are variables.
+    #[allow(clippy::result_large_err)]
+    pub fn compile_f64(&self, variables: &[&str]) -> Result<impl Fn(&[f64]) -> f64, CompileError> {
+        let closure = self.to_node(variables)?.to_closure();
+
+        Ok(move |x: &[f64]| closure(x))
+    }
+
+    /// Returns the source code of a Rust closure of type `Fn(&[f64]) -> f64`
+    /// equivalent to the closure returned by [`Expression::compile_f64`],
+    /// for embedding the expression in generated code.
+    #[allow(clippy::result_large_err)]
+    pub fn compile_f64_to_source(&self, variables: &[&str]) -> Result<String, CompileError> {
+        Ok(format!(
+            "|x: &[f64]| -> f64 {{ {} }}",
+            self.to_node(variables)?.to_source("x"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::CompileError::*;
+    use crate::expression::Expression;
+    use crate::helpers::*;
+
+    #[track_caller]
+    fn c(string: &str, variables: &[&str], values: &[f64], value: f64) {
+        let expression = string.parse::<Expression>().unwrap();
+        let f = expression.compile_f64(variables).unwrap();
+
+        assert!(
+            (f(values) - value).abs() < 1e-12,
+            "{} != {}",
+            f(values),
+            value
+        );
+    }
+
+    #[test]
+    fn compile() {
+        c("1", &[], &[], 1.0);
+        c("x", &["x"], &[2.5], 2.5);
+        c("x ^ 2 + 2 * x * y - 1/4", &["x", "y"], &[3.0, 0.5], 11.75);
+        c("-x / (y - 1)", &["y", "x"], &[3.0, 1.0], -0.5);
+        c("7 % x", &["x"], &[4.0], 3.0);
+        c("x ^ (-2)", &["x"], &[2.0], 0.25);
+        c("x ^ 0.5", &["x"], &[2.25], 1.5);
+        c("sin(pi * x) + cos(0)", &["x"], &[0.5], 2.0);
+        c("tan(x)", &["x"], &[1.0], 1.0f64.tan());
+        c("x + pi", &["pi", "x"], &[1.0, 2.0], 3.0);
+        c("e ^ x", &["x"], &[2.0], std::f64::consts::E.powi(2));
+        c("ln(exp(x))", &["x"], &[2.0], 2.0);
+
+        let f = "(-8) ^ (1/3)".parse::<Expression>().unwrap();
+        assert!(f.compile_f64(&[]).unwrap()(&[]).is_nan());
+
+        let e = |string: &str, variables: &[&str]| {
+            string
+                .parse::<Expression>()
+                .unwrap()
+                .compile_f64(variables)
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(e("x + y", &["x"]), UnknownVariable("y".to_owned()));
+        assert_eq!(
+            e("x < 1", &["x"]),
+            UnsupportedExpression(lt(var("x"), int(1)))
+        );
+        assert_eq!(
+            e("f(x)", &["x"]),
+            UnsupportedExpression(fun(var("f"), [var("x")])),
+        );
+    }
+
+    #[test]
+    fn source() {
+        let s = |string: &str, variables: &[&str]| {
+            string
+                .parse::<Expression>()
+                .unwrap()
+                .compile_f64_to_source(variables)
+                .unwrap()
+        };
+
+        assert_eq!(
+            s("2 * x ^ 3 - sin(y) / 0.5", &["x", "y"]),
+            "|x: &[f64]| -> f64 { (((2.0_f64) * x[0].powi(3)) - (x[1].sin() / (0.5_f64))) }",
+        );
+        assert_eq!(
+            s("-x ^ y % 1", &["x", "y"]),
+            "|x: &[f64]| -> f64 { ((-x[0].powf(x[1])) % (1.0_f64)) }",
+        );
+    }
+}
@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+//! Reading and writing CSV data.
+
+use std::fmt;
+
+use num::{One, Zero};
+
+use crate::{
+    expression::{Expression, Integer, Matrix, Rational, RationalRepresentation, Vector},
+    print::FormatOptions,
+};
+
+/// Delimiters that are recognized when the delimiter is detected automatically,
+/// in order of preference.
+const DELIMITERS: [char; 3] = [',', ';', '\t'];
+
+/// Options controlling how CSV data is read.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct CsvOptions {
+    /// Character separating the cells of a row.
+    /// If `None`, the first of `,`, `;`, and tab that occurs
+    /// in the first row is used, defaulting to `,`.
+    pub delimiter: Option<char>,
+    /// Whether the first row is a header that is skipped.
+    /// If `None`, the first row is treated as a header
+    /// if and only if it contains a non-numeric cell.
+    pub has_header: Option<bool>,
+}
+
+/// Options controlling how CSV data is written.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct CsvFormatOptions {
+    /// Character separating the cells of a row.
+    pub delimiter: char,
+    /// Number of digits after the decimal point to which all numbers are rounded.
+    /// If `None`, numbers are written exactly, in their own representation,
+    /// so rational numbers without a finite decimal expansion are written as fractions.
+    pub precision: Option<usize>,
+}
+
+impl Default for CsvFormatOptions {
+    fn default() -> Self {
+        CsvFormatOptions {
+            delimiter: ',',
+            precision: None,
+        }
+    }
+}
+
+/// Error that occurred while trying to read CSV data.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum CsvError {
+    /// The cell in the given (1-based) line and column is not a number.
+    InvalidCell {
+        line: usize,
+        column: usize,
+        cell: String,
+    },
+    /// The row in the given (1-based) line has a different number of cells than the first row.
+    InconsistentRowLength {
+        line: usize,
+        expected_length: usize,
+        given_length: usize,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsvError::InvalidCell { line, column, cell } => write!(
+                f,
+                "cell '{}' in line {}, column {} is not a number",
+                cell, line, column,
+            ),
+            CsvError::InconsistentRowLength {
+                line,
+                expected_length,
+                given_length,
+            } => write!(
+                f,
+                "row in line {} has {} cells, but {} were expected",
+                line, given_length, expected_length,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Type that can be constructed from CSV data.
+pub trait FromCsv: Sized {
+    /// Reads a value from the given CSV data.
+    fn from_csv(text: &str, options: &CsvOptions) -> Result<Self, CsvError>;
+}
+
+/// Type that can be written as CSV data.
+pub trait ToCsv {
+    /// Returns the CSV data representing the value, with a line break after each row.
+    fn to_csv(&self, options: &CsvFormatOptions) -> String;
+}
+
+impl FromCsv for Matrix {
+    /// Reads a matrix of exact rational numbers from CSV data, with one matrix row per
+    /// non-empty line. Integer cells become integers, cells of the form `a/b` become
+    /// fractions, and cells with a decimal point or an exponent become rational numbers
+    /// in decimal representation, so they are printed the way they were written.
+    fn from_csv(text: &str, options: &CsvOptions) -> Result<Self, CsvError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim_end_matches('\r')))
+            .filter(|(_, line)| !line.trim().is_empty())
+            .peekable();
+
+        let delimiter = options.delimiter.unwrap_or_else(|| {
+            lines
+                .peek()
+                .and_then(|(_, line)| DELIMITERS.iter().find(|d| line.contains(**d)).copied())
+                .unwrap_or(DELIMITERS[0])
+        });
+
+        let has_header = options.has_header.unwrap_or_else(|| {
+            matches!(lines.peek(), Some((_, line))
+                if line.split(delimiter).any(|cell| parse_cell(cell).is_none()))
+        });
+
+        if has_header {
+            lines.next();
+        }
+
+        let mut components = Vec::new();
+        let mut row_length = None;
+        let mut row_count = 0;
+
+        for (line_number, line) in lines {
+            let cells = line.split(delimiter).collect::<Vec<_>>();
+
+            match row_length {
+                Some(expected_length) if cells.len() != expected_length => {
+                    return Err(CsvError::InconsistentRowLength {
+                        line: line_number,
+                        expected_length,
+                        given_length: cells.len(),
+                    });
+                }
+                _ => row_length = Some(cells.len()),
+            }
+
+            for (index, cell) in cells.into_iter().enumerate() {
+                components.push(parse_cell(cell).ok_or_else(|| CsvError::InvalidCell {
+                    line: line_number,
+                    column: index + 1,
+                    cell: cell.trim().to_owned(),
+                })?);
+            }
+
+            row_count += 1;
+        }
+
+        Ok(Matrix::from_row_slice(
+            row_count,
+            row_length.unwrap_or(0),
+            &components,
+        ))
+    }
+}
+
+/// Returns the number represented by the given CSV cell,
+/// or `None` if the cell does not contain a number.
+fn parse_cell(cell: &str) -> Option<Expression> {
+    let mut cell = cell.trim();
+
+    if cell.len() >= 2 && cell.starts_with('"') && cell.ends_with('"') {
+        cell = cell[1..cell.len() - 1].trim();
+    }
+
+    if let Some((numerator, denominator)) = cell.split_once('/') {
+        let numerator = parse_integer(numerator.trim())?;
+        let denominator = parse_integer(denominator.trim())?;
+
+        if denominator.is_zero() {
+            return None;
+        }
+
+        let x = Rational::new(numerator, denominator);
+
+        return Some(if x.denom().is_one() {
+            Expression::Integer(x.to_integer())
+        } else {
+            Expression::Rational(x, RationalRepresentation::Fraction)
+        });
+    }
+
+    let (mantissa, exponent) = match cell.find(['e', 'E']) {
+        Some(index) => (&cell[..index], Some(cell[index + 1..].parse::<i32>().ok()?)),
+        None => (cell, None),
+    };
+
+    let (integer_part, fractional_part) = match mantissa.split_once('.') {
+        Some((integer_part, fractional_part)) => (integer_part, Some(fractional_part)),
+        None => (mantissa, None),
+    };
+
+    let (negative, integer_part) = match integer_part.strip_prefix('-') {
+        Some(integer_part) => (true, integer_part),
+        None => (
+            false,
+            integer_part.strip_prefix('+').unwrap_or(integer_part),
+        ),
+    };
+
+    let fractional_part = fractional_part.unwrap_or("");
+
+    if integer_part.is_empty() && fractional_part.is_empty()
+        || !integer_part
+            .chars()
+            .chain(fractional_part.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let digits = format!("{}{}", integer_part, fractional_part);
+    let mut x = Rational::new(
+        digits.parse().ok()?,
+        num::pow(Integer::from(10), fractional_part.len()),
+    );
+
+    if let Some(exponent) = exponent {
+        let power = Rational::from_integer(num::pow(
+            Integer::from(10),
+            exponent.unsigned_abs() as usize,
+        ));
+
+        if exponent < 0 {
+            x /= power;
+        } else {
+            x *= power;
+        }
+    }
+
+    if negative {
+        x = -x;
+    }
+
+    Some(if exponent.is_none() && !mantissa.contains('.') {
+        Expression::Integer(x.to_integer())
+    } else {
+        Expression::Rational(x, RationalRepresentation::Decimal)
+    })
+}
+
+/// Returns the integer represented by the given string, which may have a sign.
+fn parse_integer(string: &str) -> Option<Integer> {
+    let digits = string
+        .strip_prefix('-')
+        .or_else(|| string.strip_prefix('+'))
+        .unwrap_or(string);
+
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    string.parse().ok()
+}
+
+impl ToCsv for Matrix {
+    /// Writes the matrix as CSV data, with one line per matrix row.
+    /// Components that are strings are written without the quotation marks
+    /// of string literals, and all other components are written as they are printed.
+    fn to_csv(&self, options: &CsvFormatOptions) -> String {
+        let format_options = FormatOptions {
+            rational_representation: options.precision.map(|_| RationalRepresentation::Decimal),
+            max_decimal_digits: options.precision,
+            ..FormatOptions::default()
+        };
+
+        let delimiter = options.delimiter.to_string();
+
+        self.row_iter()
+            .map(|row| {
+                row.iter()
+                    .map(|component| {
+                        let cell = match component {
+                            Expression::String(string) => string.clone(),
+                            _ => component.display_with(&format_options).to_string(),
+                        };
+
+                        quote_cell(&cell, options.delimiter)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(&delimiter)
+                    + "\n"
+            })
+            .collect()
+    }
+}
+
+impl ToCsv for Vector {
+    /// Writes the vector as CSV data, with one line per component.
+    fn to_csv(&self, options: &CsvFormatOptions) -> String {
+        Matrix::from_columns(std::slice::from_ref(self)).to_csv(options)
+    }
+}
+
+/// Returns the given cell enclosed in quotation marks if it contains
+/// the delimiter, a quotation mark, or a line break, and the cell itself otherwise.
+fn quote_cell(cell: &str, delimiter: char) -> String {
+    if cell.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use crate::csv::{CsvError::*, CsvFormatOptions, CsvOptions, FromCsv, ToCsv};
+    use crate::expression::{Matrix, Vector};
+    use crate::helpers::*;
+
+    #[track_caller]
+    fn m(text: &str, options: CsvOptions, matrix: Matrix) {
+        assert_eq!(Matrix::from_csv(text, &options), Ok(matrix));
+    }
+
+    #[test]
+    fn from_csv() {
+        let default = CsvOptions::default();
+
+        m(
+            "1,2\n3,4\n",
+            default,
+            dmatrix![int(1), int(2); int(3), int(4)],
+        );
+        m(
+            "x,y\r\n0.5, -1/3\r\n\r\n1e3,\"2.50\"\r\n",
+            default,
+            dmatrix![ratd(1, 2), rat(-1, 3); ratd(1000, 1), ratd(5, 2)],
+        );
+        m(
+            "1;2.5e-1\n-7;+0",
+            default,
+            dmatrix![int(1), ratd(1, 4); int(-7), int(0)],
+        );
+        m("1\t4/2", default, dmatrix![int(1), int(2)]);
+        m("", default, Matrix::from_element(0, 0, int(0)));
+
+        m(
+            "1|2\n3|4",
+            CsvOptions {
+                delimiter: Some('|'),
+                has_header: Some(true),
+            },
+            dmatrix![int(3), int(4)],
+        );
+
+        let e = |text: &str| {
+            Matrix::from_csv(text, &CsvOptions::default())
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(
+            e("1,2\n3,x"),
+            InvalidCell {
+                line: 2,
+                column: 2,
+                cell: "x".to_owned(),
+            },
+        );
+        assert_eq!(
+            e("1,2\n\n3"),
+            InconsistentRowLength {
+                line: 3,
+                expected_length: 2,
+                given_length: 1,
+            },
+        );
+        assert_eq!(
+            e("a,b\n1/0,1").to_string(),
+            "cell '1/0' in line 2, column 1 is not a number",
+        );
+    }
+
+    #[test]
+    fn to_csv() {
+        let default = CsvFormatOptions::default();
+
+        let matrix = dmatrix![
+            int(1), rat(1, 3), ratd(-5, 4);
+            var("x") + int(1), fun(var("f"), [var("a"), var("b")]), string("say \"hi\"");
+        ];
+
+        assert_eq!(
+            matrix.to_csv(&default),
+            "1,1/3,-1.25\nx + 1,\"f(a, b)\",\"say \"\"hi\"\"\"\n",
+        );
+        assert_eq!(
+            matrix.to_csv(&CsvFormatOptions {
+                delimiter: ';',
+                precision: Some(2),
+            }),
+            "1;0.33;-1.25\nx + 1;f(a, b);\"say \"\"hi\"\"\"\n",
+        );
+        assert_eq!(
+            Vector::from_vec(vec![int(1), ratd(1, 2)]).to_csv(&default),
+            "1\n0.5\n",
+        );
+
+        let text = "1,-1/3\n0.25,7\n";
+        assert_eq!(
+            Matrix::from_csv(text, &CsvOptions::default())
+                .unwrap()
+                .to_csv(&default),
+            text,
+        );
+    }
+}
@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+//! Certified enclosures of numerical values, computed using directed rounding.
+
+use num::{One, ToPrimitive, Zero};
+
+use crate::{
+    algebraic::AlgebraicNumber,
+    approximate::{function_value, GUARD_BITS, MAX_DOUBLINGS},
+    bigfloat::{BigFloat, Rounding},
+    evaluate::{LimitExceeded, Options},
+    expression::{Expression, Integer, Rational, RationalRepresentation},
+    interval::Interval,
+};
+
+/// Maximum binary magnitude of the bounds of an enclosure, beyond which
+/// the bounds would be too expensive to represent as rational numbers.
+const MAX_MAGNITUDE: i64 = 1 << 16;
+
+/// Returns the smallest interval whose bounds have `precision` bits that contains
+/// the interval, or `None` if the bounds are too large or too small.
+fn outward(interval: &Interval, precision: u32) -> Option<Interval> {
+    let lower = BigFloat::from_rational_rounded(interval.lower(), precision, Rounding::Down);
+    let upper = BigFloat::from_rational_rounded(interval.upper(), precision, Rounding::Up);
+
+    if lower.magnitude().abs() > MAX_MAGNITUDE || upper.magnitude().abs() > MAX_MAGNITUDE {
+        return None;
+    }
+
+    Interval::new(lower.to_rational(), upper.to_rational())
+}
+
+/// Returns the interval containing only the given number.
+fn point(x: Rational) -> Interval {
+    Interval::new(x.clone(), x).unwrap()
+}
+
+/// Returns an interval containing the exact value of a constant or function
+/// whose value rounded to `precision` bits is `x`. Such values are computed with
+/// guard bits (see `BigFloat`), so their error is bounded by two units in the last
+/// place plus an absolute error of `2 ^ -precision` for values computed
+/// in fixed point arithmetic that are much smaller than their arguments.
+fn widened(x: BigFloat, precision: u32) -> Interval {
+    let magnitude = x.magnitude();
+    let x = x.to_rational();
+
+    let error = Rational::new(Integer::one(), Integer::one() << precision as usize)
+        * (Rational::one()
+            + if magnitude >= 0 {
+                Rational::from_integer(Integer::one() << (magnitude + 1) as usize)
+            } else {
+                Rational::new(Integer::one(), Integer::one() << (-magnitude - 1) as usize)
+            });
+
+    Interval::new(&x - &error, x + error).unwrap()
+}
+
+/// Returns an interval containing the values of the non-decreasing function
+/// on the interval, or `None` if the function is not defined at one of its bounds.
+fn increasing(
+    f: fn(&BigFloat) -> Option<BigFloat>,
+    interval: &Interval,
+    precision: u32,
+) -> Option<Interval> {
+    let value = |x: &Rational| f(&BigFloat::from_rational(x, precision));
+
+    Interval::new(
+        widened(value(interval.lower())?, precision).lower().clone(),
+        widened(value(interval.upper())?, precision).upper().clone(),
+    )
+}
+
+/// Returns an interval containing the values of the function on the interval,
+/// where the function takes values in `[-1, 1]` and its derivative is bounded by 1
+/// in absolute value (e.g. sine and cosine), so its values differ from the value
+/// at the lower bound by at most the width of the interval.
+fn bounded(
+    f: fn(&BigFloat) -> Option<BigFloat>,
+    interval: &Interval,
+    precision: u32,
+) -> Option<Interval> {
+    let width = interval.upper() - interval.lower();
+    let value = widened(
+        f(&BigFloat::from_rational(interval.lower(), precision))?,
+        precision,
+    );
+
+    Interval::new(
+        (value.lower() - &width).max(-Rational::one()),
+        (value.upper() + width).min(Rational::one()),
+    )
+}
+
+/// Returns an interval containing the `n`th powers of the numbers in the interval,
+/// computed by repeated squaring, or `None` if `n` is negative and the interval
+/// contains zero, or the bounds become too large or too small.
+fn power(interval: &Interval, n: i64, precision: u32) -> Option<Interval> {
+    if n < 0 {
+        return outward(&power(interval, -n, precision)?.reciprocal()?, precision);
+    }
+
+    let mut base = interval.clone();
+    let mut result = point(Rational::one());
+    let mut n = n as u64;
+
+    while n > 0 {
+        if n % 2 == 1 {
+            result = outward(&result.multiply(&base), precision)?;
+        }
+
+        n /= 2;
+
+        if n > 0 {
+            base = outward(&base.power(2)?, precision)?;
+        }
+    }
+
+    Some(result)
+}
+
+/// Returns an interval containing the value of the expression, whose bounds
+/// have `precision` bits, or `None` if the expression is not a real number
+/// or contains an operation for which no enclosure can be computed.
+///
+/// Operations are performed on intervals with rational bounds, which are exact,
+/// and the results are rounded outward, so the enclosure is certified provided
+/// that the error bounds of the functions of `BigFloat` hold (see `widened`).
+fn enclosure(expression: &Expression, precision: u32) -> Option<Interval> {
+    use crate::expression::Expression::*;
+
+    let enclosure = |a: &Expression| enclosure(a, precision);
+    let ln = |a: &self::Interval| increasing(BigFloat::ln, a, precision);
+
+    let result = match expression {
+        Integer(n) => point(n.clone().into()),
+        Rational(x, _) => point(x.clone()),
+        Complex(z, _) if z.im.is_zero() => point(z.re.clone()),
+        Variable(identifier) if identifier == "pi" => widened(BigFloat::pi(precision), precision),
+        Variable(identifier) if identifier == "e" => widened(BigFloat::e(precision), precision),
+        Negation(a) => enclosure(a)?.negate(),
+        Sum(a, b) => enclosure(a)?.add(&enclosure(b)?),
+        Difference(a, b) => enclosure(a)?.subtract(&enclosure(b)?),
+        Product(a, b) => enclosure(a)?.multiply(&enclosure(b)?),
+        Quotient(a, b) => enclosure(a)?.divide(&enclosure(b)?)?,
+        Power(a, b) => match &**b {
+            Integer(n) => power(&enclosure(a)?, n.to_i64()?, precision)?,
+            // a ^ b = exp(b * ln(a)) for positive a
+            _ => {
+                let exponent = outward(&enclosure(b)?.multiply(&ln(&enclosure(a)?)?), precision)?;
+                increasing(BigFloat::exp, &exponent, precision)?
+            }
+        },
+        _ => match function_value(expression)? {
+            ("sqrt", [a]) => increasing(BigFloat::sqrt, &enclosure(a)?, precision)?,
+            ("exp", [a]) => increasing(BigFloat::exp, &enclosure(a)?, precision)?,
+            ("ln", [a]) => ln(&enclosure(a)?)?,
+            ("log", [b, a]) => ln(&enclosure(a)?)?.divide(&ln(&enclosure(b)?)?)?,
+            ("sin", [a]) => bounded(BigFloat::sin, &enclosure(a)?, precision)?,
+            ("cos", [a]) => bounded(BigFloat::cos, &enclosure(a)?, precision)?,
+            ("tan", [a]) => {
+                let a = enclosure(a)?;
+                let sin = bounded(BigFloat::sin, &a, precision)?;
+                let cos = bounded(BigFloat::cos, &a, precision)?;
+                sin.divide(&cos)?
+            }
+            ("root_of", _) => {
+                let (lower, upper) =
+                    AlgebraicNumber::from_expression(expression)?.to_bigfloat_bounds(precision);
+                self::Interval::new(lower.to_rational(), upper.to_rational())?
+            }
+            _ => return None,
+        },
+    };
+
+    outward(&result, precision)
+}
+
+impl Expression {
+    /// Returns an interval containing the value of the expression, whose bounds are
+    /// rounded outward to the given number of significant decimal digits, which must
+    /// be positive, or `None` if no enclosure can be computed (see `enclosure`),
+    /// or an error if the decimal representation of a bound would exceed the digit
+    /// limits of the options.
+    ///
+    /// As with `approximate`, the precision of the computation is doubled until
+    /// both bounds round to the same number, or the maximum precision is reached.
+    pub(crate) fn enclose(
+        &self,
+        digits: u32,
+        options: &Options,
+    ) -> Result<Option<Interval>, LimitExceeded> {
+        let mut precision =
+            (f64::from(digits) * std::f64::consts::LOG2_10).ceil() as u32 + GUARD_BITS;
+
+        let bounds = |interval: &Interval, precision: u32| {
+            (
+                BigFloat::from_rational(interval.lower(), precision),
+                BigFloat::from_rational(interval.upper(), precision),
+            )
+        };
+
+        let mut result = match enclosure(self, precision) {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+
+        for bound in [bounds(&result, precision).0, bounds(&result, precision).1] {
+            options.check_digits(
+                (bound.magnitude().abs() as f64) * std::f64::consts::LOG10_2 + f64::from(digits),
+            )?;
+        }
+
+        for _ in 0..MAX_DOUBLINGS {
+            let (lower, upper) = bounds(&result, precision);
+
+            if lower.to_decimal(digits) == upper.to_decimal(digits) {
+                break;
+            }
+
+            precision *= 2;
+
+            // A wider enclosure at a lower precision is still valid.
+            match enclosure(self, precision) {
+                Some(next) => result = next,
+                None => break,
+            }
+        }
+
+        let (lower, upper) = bounds(&result, precision);
+
+        Ok(Interval::new(
+            lower.to_decimal_rounded(digits, Rounding::Down),
+            upper.to_decimal_rounded(digits, Rounding::Up),
+        ))
+    }
+
+    /// Returns the expression with all real numerical subexpressions that can be
+    /// enclosed (see `enclose`), and that are the entire expression or operands
+    /// of arithmetic operations, replaced with intervals containing their values,
+    /// whose bounds are in decimal representation. Because interval arithmetic
+    /// is exact, evaluating the result yields an interval containing the value
+    /// of the expression for every value of its variables.
+    pub(crate) fn enclose_subexpressions(
+        &self,
+        digits: u32,
+        options: &Options,
+    ) -> Result<Self, LimitExceeded> {
+        use crate::expression::Expression::*;
+
+        if let Some(interval) = self.enclose(digits, options)? {
+            return Ok(interval.to_expression(RationalRepresentation::Decimal));
+        }
+
+        let enclose = |a: &Self| -> Result<Box<Self>, LimitExceeded> {
+            Ok(Box::new(a.enclose_subexpressions(digits, options)?))
+        };
+
+        Ok(match self {
+            Negation(a) => Negation(enclose(a)?),
+            Sum(a, b) => Sum(enclose(a)?, enclose(b)?),
+            Difference(a, b) => Difference(enclose(a)?, enclose(b)?),
+            Product(a, b) => Product(enclose(a)?, enclose(b)?),
+            Quotient(a, b) => Quotient(enclose(a)?, enclose(b)?),
+            // Exponents are left unchanged, since powers are only defined
+            // for intervals with integer exponents.
+            Power(a, b) => Power(enclose(a)?, b.clone()),
+            _ => self.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluate::Options,
+        expression::{Expression, RationalRepresentation},
+    };
+
+    #[track_caller]
+    fn e(expression: &str, digits: u32, result: Option<&str>) {
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .enclose(digits, &Options::default())
+                .unwrap()
+                .map(|interval| interval
+                    .to_expression(RationalRepresentation::Decimal)
+                    .to_string()),
+            result.map(str::to_owned),
+        );
+    }
+
+    #[test]
+    fn enclose() {
+        e("pi", 10, Some("interval(3.141592653, 3.141592654)"));
+        e("1/3", 3, Some("interval(0.333, 0.334)"));
+        e("2", 5, Some("interval(2, 2)"));
+        e("(-2) ^ 3", 5, Some("interval(-8, -8)"));
+        e("-sqrt(2)", 5, Some("interval(-1.4143, -1.4142)"));
+        e("2 ^ (1/3)", 10, Some("interval(1.259921049, 1.25992105)"));
+        e("e ^ 2 - 1/3", 8, Some("interval(7.0557227, 7.0557228)"));
+        e(
+            "ln(2)",
+            20,
+            Some("interval(0.69314718055994530941, 0.69314718055994530942)"),
+        );
+        e(
+            "log(10, 2)",
+            10,
+            Some("interval(0.3010299956, 0.3010299957)"),
+        );
+        e("tan(1)", 10, Some("interval(1.557407724, 1.557407725)"));
+        e(
+            "sin(10 ^ 30)",
+            10,
+            Some("interval(-0.09011690192, -0.09011690191)"),
+        );
+        e(
+            "exp(-100)",
+            5,
+            Some("interval(0.0000000000000000000000000000000000000000000372, 0.000000000000000000000000000000000000000000037201)"),
+        );
+        e(
+            "pi - 355/113",
+            5,
+            Some("interval(-0.00000026677, -0.00000026676)"),
+        );
+        e(
+            "root_of(x^5 - x - 1, x, 1)",
+            10,
+            Some("interval(1.167303978, 1.167303979)"),
+        );
+        e("x + 1", 5, None);
+        e("sqrt(-1)", 5, None);
+        e("tan(pi / 2)", 5, None);
+        e("1 / (1 - 1)", 5, None);
+        e("10 ^ 100000", 5, None);
+        e("gamma(1/3)", 5, None);
+    }
+}
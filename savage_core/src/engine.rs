@@ -0,0 +1,922 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+//! Evaluation sessions that keep variables and options across evaluations.
+
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, Read, Write},
+    rc::Rc,
+};
+
+use crate::{
+    evaluate::{complete_context, Error, Options},
+    expression::{Expression, TraversalOrder, Type},
+    SavageError,
+};
+
+/// Maximum number of results kept in the result cache of a session.
+/// The cache is cleared when it is full.
+const CACHE_CAPACITY: usize = 1024;
+
+/// Error returned by a native function registered with `Engine::register_fn`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum NativeError {
+    /// The function was invoked with an argument it cannot process.
+    InvalidArgument(Expression),
+    /// The function was invoked with a number of arguments outside the given range.
+    InvalidNumberOfArguments {
+        min_number: usize,
+        max_number: usize,
+    },
+    /// The function failed for another reason, described by the message.
+    Failed(String),
+}
+
+impl From<Expression> for NativeError {
+    fn from(argument: Expression) -> Self {
+        NativeError::InvalidArgument(argument)
+    }
+}
+
+impl From<String> for NativeError {
+    fn from(message: String) -> Self {
+        NativeError::Failed(message)
+    }
+}
+
+impl NativeError {
+    /// Returns the evaluation error corresponding to this error,
+    /// which occurred while evaluating the given function expression.
+    fn into_error(self, expression: &Expression, given_number: usize) -> Error {
+        match self {
+            NativeError::InvalidArgument(argument) => Error::InvalidArgument {
+                expression: expression.clone(),
+                argument,
+            },
+            NativeError::InvalidNumberOfArguments {
+                min_number,
+                max_number,
+            } => Error::InvalidNumberOfArguments {
+                expression: expression.clone(),
+                min_number,
+                max_number,
+                given_number,
+            },
+            NativeError::Failed(message) => Error::NativeFunctionFailed {
+                expression: expression.clone(),
+                message,
+            },
+        }
+    }
+}
+
+/// Definition of a bound variable, whose value is recomputed
+/// whenever a variable it depends on changes.
+#[derive(Clone, Debug)]
+struct Binding {
+    definition: Expression,
+    /// Variables that the definition depends on.
+    dependencies: BTreeSet<String>,
+}
+
+/// Change of the value of a bound variable, caused by
+/// a change of a variable that its definition depends on.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Update {
+    pub identifier: String,
+    /// New value of the variable, or the error that occurred while recomputing it
+    /// (in which case the variable has no value until it is recomputed successfully).
+    pub result: Result<Expression, Error>,
+}
+
+/// Result of a previous evaluation in a session.
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    expression: Expression,
+    /// Values of the variables that the expression depends on, directly
+    /// or through the values of other variables, at the time of evaluation.
+    bindings: BTreeMap<String, Expression>,
+    result: Expression,
+}
+
+/// Evaluation session owning the values of variables and the evaluation options
+/// that are used for all expressions evaluated in the session.
+#[derive(Clone, Debug, Default)]
+pub struct Engine {
+    context: HashMap<String, Expression>,
+    options: Options,
+    bindings: BTreeMap<String, Binding>,
+    /// Updates of bound variables that haven't been taken yet.
+    updates: Vec<Update>,
+    /// Results of previous evaluations, by the hashes of their cache keys.
+    cache: RefCell<HashMap<u64, CacheEntry>>,
+}
+
+impl Engine {
+    /// Returns a new session without any variables, using the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new session without any variables, using the given options.
+    pub fn with_options(options: Options) -> Self {
+        Engine {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the options used for evaluation.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Returns the options used for evaluation, for changing them.
+    pub fn options_mut(&mut self) -> &mut Options {
+        // Cached results might not be valid under the changed options.
+        self.cache.get_mut().clear();
+        &mut self.options
+    }
+
+    /// Seeds the generator behind the random built-in functions with the given value,
+    /// making the sequence of random numbers produced in the session reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.options_mut().set_seed(seed);
+    }
+
+    /// Returns the values of all variables set in the session, by their identifiers.
+    pub fn variables(&self) -> &HashMap<String, Expression> {
+        &self.context
+    }
+
+    /// Returns the value of the variable with the given identifier, if it is set.
+    pub fn get(&self, identifier: &str) -> Option<&Expression> {
+        self.context.get(identifier)
+    }
+
+    /// Sets the value of the variable with the given identifier,
+    /// returning the previous value if the variable was already set.
+    /// If the variable was bound, the binding is removed.
+    /// Bound variables that depend on the variable are recomputed.
+    pub fn set(
+        &mut self,
+        identifier: impl Into<String>,
+        value: impl Into<Expression>,
+    ) -> Option<Expression> {
+        let identifier = identifier.into();
+
+        self.bindings.remove(&identifier);
+        let previous_value = self.context.insert(identifier.clone(), value.into());
+        self.recompute_dependents(&identifier);
+
+        previous_value
+    }
+
+    /// Unsets the variable with the given identifier, returning its value if it was set.
+    /// If the variable was bound, the binding is removed.
+    /// Bound variables that depend on the variable are recomputed.
+    pub fn unset(&mut self, identifier: &str) -> Option<Expression> {
+        self.bindings.remove(identifier);
+        let previous_value = self.context.remove(identifier);
+        self.recompute_dependents(identifier);
+
+        previous_value
+    }
+
+    /// Binds the variable with the given identifier to the given definition.
+    /// The value of the variable is set to the result of evaluating the definition,
+    /// and is recomputed whenever a variable that the definition depends on changes.
+    /// Recomputations are performed in dependency order, and only for the bound
+    /// variables that are affected by the change. They are recorded as updates,
+    /// which can be retrieved with `take_updates`.
+    ///
+    /// Returns the value of the variable, or an error if the definition depends on
+    /// the variable itself or cannot be evaluated (in which case nothing is changed).
+    #[allow(clippy::result_large_err)]
+    pub fn bind(
+        &mut self,
+        identifier: impl Into<String>,
+        definition: Expression,
+    ) -> Result<Expression, Error> {
+        let identifier = identifier.into();
+
+        let dependencies = definition.free_variables();
+
+        if dependencies.contains(&identifier)
+            || dependencies
+                .iter()
+                .any(|dependency| self.dependencies(dependency).contains(&identifier))
+        {
+            return Err(Error::CyclicDefinition {
+                identifier,
+                definition,
+            });
+        }
+
+        let value = self.evaluate(&definition)?;
+
+        self.context.insert(identifier.clone(), value.clone());
+        self.bindings.insert(
+            identifier.clone(),
+            Binding {
+                definition,
+                dependencies,
+            },
+        );
+        self.recompute_dependents(&identifier);
+
+        Ok(value)
+    }
+
+    /// Returns the definitions of all bound variables, by their identifiers.
+    pub fn bindings(&self) -> HashMap<&str, &Expression> {
+        self.bindings
+            .iter()
+            .map(|(identifier, binding)| (identifier.as_str(), &binding.definition))
+            .collect()
+    }
+
+    /// Returns the updates of bound variables that occurred since
+    /// the last call to this function, in the order in which they occurred.
+    pub fn take_updates(&mut self) -> Vec<Update> {
+        std::mem::take(&mut self.updates)
+    }
+
+    /// Returns the identifiers of all variables that the variable with the given
+    /// identifier depends on, directly or through other bindings.
+    fn dependencies(&self, identifier: &str) -> HashSet<String> {
+        let mut dependencies = HashSet::new();
+        let mut stack = vec![identifier];
+
+        while let Some(identifier) = stack.pop() {
+            if let Some(binding) = self.bindings.get(identifier) {
+                for dependency in &binding.dependencies {
+                    if dependencies.insert(dependency.clone()) {
+                        stack.push(dependency);
+                    }
+                }
+            }
+        }
+
+        dependencies
+    }
+
+    /// Recomputes the values of all bound variables that depend on the variable
+    /// with the given identifier, directly or through other bindings,
+    /// such that each variable is recomputed after all variables it depends on.
+    fn recompute_dependents(&mut self, identifier: &str) {
+        fn visit(
+            bindings: &BTreeMap<String, Binding>,
+            identifier: &str,
+            visited: &mut HashSet<String>,
+            order: &mut Vec<String>,
+        ) {
+            for (dependent, binding) in bindings {
+                if binding.dependencies.contains(identifier) && visited.insert(dependent.clone()) {
+                    visit(bindings, dependent, visited, order);
+                    order.push(dependent.clone());
+                }
+            }
+        }
+
+        let mut order = Vec::new();
+        visit(&self.bindings, identifier, &mut HashSet::new(), &mut order);
+
+        // Reverse post-order is a topological order, in which every variable
+        // comes before the variables that depend on it.
+        for identifier in order.into_iter().rev() {
+            let result = self.evaluate(&self.bindings[&identifier].definition);
+
+            match &result {
+                Ok(value) => self.context.insert(identifier.clone(), value.clone()),
+                Err(_) => self.context.remove(&identifier),
+            };
+
+            self.updates.push(Update { identifier, result });
+        }
+    }
+
+    /// Registers the given closure as a function that can be called
+    /// from expressions using the given identifier.
+    ///
+    /// The closure is invoked with the evaluated arguments once all of them
+    /// have been evaluated to values. Function calls with symbolic arguments
+    /// (e.g. variables without a value) are left unevaluated, just like calls
+    /// to built-in functions.
+    ///
+    /// Returns the previous value of the identifier, if it was set.
+    #[allow(clippy::result_large_err)]
+    pub fn register_fn(
+        &mut self,
+        identifier: impl Into<String>,
+        function: impl Fn(&[Expression]) -> Result<Expression, NativeError> + 'static,
+    ) -> Option<Expression> {
+        let identifier = identifier.into();
+
+        self.set(
+            identifier.clone(),
+            Expression::Function(
+                identifier,
+                Rc::new(move |expression, arguments, _, _| {
+                    if arguments.iter().any(|argument| {
+                        matches!(
+                            argument.typ(),
+                            Type::Boolean(None) | Type::Arithmetic | Type::Unknown,
+                        )
+                    }) {
+                        return Ok(expression.clone());
+                    }
+
+                    function(arguments)
+                        .map_err(|error| error.into_error(expression, arguments.len()))
+                }),
+            ),
+        )
+    }
+
+    /// Writes the variables of the session to the given writer, in a text format
+    /// that can be read by `load_from`.
+    ///
+    /// Each variable is written on its own line as `identifier = value`,
+    /// or as `identifier := definition` if the variable is bound,
+    /// in alphabetical order of identifiers. Functions defined as lambda expressions
+    /// are written like other values, but variables whose values are native functions
+    /// (such as those registered with `register_fn`) cannot be written and are skipped.
+    pub fn save_to(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut identifiers = self.context.keys().collect::<Vec<_>>();
+        identifiers.extend(
+            self.bindings
+                .keys()
+                .filter(|identifier| !self.context.contains_key(*identifier)),
+        );
+        identifiers.sort();
+
+        for identifier in identifiers {
+            if let Some(binding) = self.bindings.get(identifier) {
+                writeln!(writer, "{} := {}", identifier, binding.definition)?;
+            } else {
+                let value = &self.context[identifier];
+
+                if !matches!(value, Expression::Function(_, _)) {
+                    writeln!(writer, "{} = {}", identifier, value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads variables written by `save_to` from the given reader, and sets
+    /// or binds them in the session. Empty lines and lines starting with `#`
+    /// are ignored.
+    ///
+    /// Returns an error of kind `InvalidData` describing the first invalid line
+    /// if the input is not valid, in which case no variables are changed.
+    pub fn load_from(&mut self, reader: impl Read) -> io::Result<()> {
+        let mut engine = self.clone();
+        let mut bindings = Vec::new();
+
+        for (index, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", index + 1, message),
+                )
+            };
+
+            let (identifier, value) = line
+                .split_once('=')
+                .map(|(identifier, value)| (identifier.trim(), value.trim()))
+                .ok_or_else(|| error("expected 'identifier = value'".to_owned()))?;
+
+            let (identifier, bound) = match identifier.strip_suffix(':') {
+                Some(identifier) => (identifier.trim(), true),
+                None => (identifier, false),
+            };
+
+            match identifier.parse() {
+                Ok(Expression::Variable(_)) => (),
+                _ => return Err(error(format!("invalid identifier '{}'", identifier))),
+            }
+
+            let value = value
+                .parse::<Expression>()
+                .map_err(|error_| error(error_.to_string()))?;
+
+            if bound {
+                // Bindings are applied once all values are set,
+                // so that definitions can use variables from later lines.
+                bindings.push((index, identifier.to_owned(), value));
+            } else {
+                // Values are evaluated without any variables set, which normalizes
+                // their representation without substituting other variables.
+                let value = value
+                    .evaluate_with_options(HashMap::new(), &self.options)
+                    .map_err(|error_| error(error_.to_string()))?;
+
+                engine.set(identifier, value);
+            }
+        }
+
+        for (index, identifier, definition) in bindings {
+            engine.bind(identifier, definition).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", index + 1, error),
+                )
+            })?;
+        }
+
+        *self = engine;
+
+        Ok(())
+    }
+
+    /// Returns the result of evaluating the expression using the variables
+    /// and options of the session, or an error if the expression cannot be evaluated.
+    ///
+    /// Results are cached, so evaluating the same expression again returns
+    /// immediately as long as none of the variables it depends on has changed.
+    /// Expressions calling random or input/output functions or native functions
+    /// are always evaluated, as is every expression if an observer is set.
+    #[allow(clippy::result_large_err)]
+    pub fn evaluate(&self, expression: &Expression) -> Result<Expression, Error> {
+        self.evaluate_cached(expression, || {
+            expression.evaluate_with_options(self.context.clone(), &self.options)
+        })
+    }
+
+    /// Returns the cached result of evaluating the expression if there is one,
+    /// and otherwise the result of calling `evaluate`, which is added to the cache
+    /// unless it is an error or the expression must not be cached.
+    #[allow(clippy::result_large_err)]
+    fn evaluate_cached(
+        &self,
+        expression: &Expression,
+        evaluate: impl FnOnce() -> Result<Expression, Error>,
+    ) -> Result<Expression, Error> {
+        let bindings = match self.cache_bindings(expression) {
+            Some(bindings) => bindings,
+            None => return evaluate(),
+        };
+
+        let mut hasher = DefaultHasher::new();
+        // The debug representation distinguishes all expressions that compare unequal,
+        // including numbers that only differ in their representation.
+        format!("{:?}", expression).hash(&mut hasher);
+        format!("{:?}", bindings).hash(&mut hasher);
+        let key = hasher.finish();
+
+        if let Some(entry) = self.cache.borrow().get(&key) {
+            if entry.expression == *expression && entry.bindings == bindings {
+                return Ok(entry.result.clone());
+            }
+        }
+
+        let result = evaluate()?;
+
+        let mut cache = self.cache.borrow_mut();
+
+        if cache.len() >= CACHE_CAPACITY {
+            cache.clear();
+        }
+
+        cache.insert(
+            key,
+            CacheEntry {
+                expression: expression.clone(),
+                bindings,
+                result: result.clone(),
+            },
+        );
+
+        Ok(result)
+    }
+
+    /// Returns the values of all variables that the result of evaluating
+    /// the expression depends on, directly or through the values of other variables,
+    /// or `None` if the result must not be cached.
+    fn cache_bindings(&self, expression: &Expression) -> Option<BTreeMap<String, Expression>> {
+        if self.options.observer.is_some() {
+            return None;
+        }
+
+        let mut bindings = BTreeMap::new();
+        let mut stack = vec![expression];
+
+        while let Some(expression) = stack.pop() {
+            for subexpression in expression.iter_subexpressions(TraversalOrder::PreOrder) {
+                if let Expression::Variable(identifier) = subexpression {
+                    match self.context.get(identifier) {
+                        // Native functions cannot be compared, and might not be pure.
+                        Some(Expression::Function(_, _)) => return None,
+                        Some(value) if !bindings.contains_key(identifier) => {
+                            bindings.insert(identifier.clone(), value.clone());
+                            stack.push(value);
+                        }
+                        Some(_) => (),
+                        // Results of impure built-in functions must not be reused.
+                        #[cfg(feature = "functions")]
+                        None if crate::functions::is_impure(identifier) => return None,
+                        None => (),
+                    }
+                }
+            }
+        }
+
+        Some(bindings)
+    }
+
+    /// Returns the results of evaluating the given expressions using the variables
+    /// and options of the session, in the same order as the expressions.
+    ///
+    /// This is more efficient than evaluating the expressions individually,
+    /// because the context in which they are evaluated is set up only once.
+    /// Each expression is evaluated separately, so an error in one expression
+    /// doesn't affect the others, and limits on evaluation steps and time apply
+    /// to each expression individually. Results are cached like those of `evaluate`,
+    /// so repeated expressions are evaluated only once. The expressions
+    /// are evaluated sequentially on the calling thread.
+    #[allow(clippy::result_large_err)]
+    pub fn evaluate_many(&self, expressions: &[Expression]) -> Vec<Result<Expression, Error>> {
+        let context = complete_context(self.context.clone(), &self.options);
+
+        expressions
+            .iter()
+            .map(|expression| {
+                self.evaluate_cached(expression, || {
+                    expression.evaluate_in_complete_context(&context, &self.options)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the result of parsing the given string as an expression and
+    /// evaluating it using the variables and options of the session,
+    /// or an error if either step fails.
+    #[allow(clippy::result_large_err)]
+    pub fn eval(&self, string: &str) -> Result<Expression, SavageError> {
+        Ok(self.evaluate(&string.parse()?)?)
+    }
+
+    /// Parses and evaluates the given string like `eval`, and sets the variable
+    /// with the given identifier to the result. Returns the result, or an error
+    /// (in which case the variable is left unchanged).
+    #[allow(clippy::result_large_err)]
+    pub fn define(
+        &mut self,
+        identifier: impl Into<String>,
+        string: &str,
+    ) -> Result<Expression, SavageError> {
+        let value = self.eval(string)?;
+        self.set(identifier, value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+// Tests that use built-in functions are skipped if those are disabled.
+#[cfg_attr(not(feature = "functions"), allow(unused_imports, dead_code))]
+mod tests {
+    use crate::{
+        engine::{Engine, NativeError},
+        evaluate::Error,
+        evaluate::{Options, ZeroPowerConvention},
+        expression::Integer,
+        helpers::*,
+        SavageError,
+    };
+
+    #[test]
+    fn session() {
+        let mut engine = Engine::new();
+
+        assert_eq!(engine.eval("a + 1"), Ok(var("a") + int(1)));
+
+        assert_eq!(engine.define("a", "1/3 + 1/6"), Ok(rat(1, 2)));
+        assert_eq!(engine.eval("a + 1"), Ok(rat(3, 2)));
+
+        assert_eq!(engine.define("b", "a * 4"), Ok(int(2)));
+        assert_eq!(engine.set("b", int(3)), Some(int(2)));
+        assert_eq!(engine.eval("a * b"), Ok(rat(3, 2)));
+
+        assert!(matches!(
+            engine.define("b", "b / 0"),
+            Err(SavageError::Evaluate(_)),
+        ));
+        assert_eq!(engine.get("b"), Some(&int(3)));
+
+        assert_eq!(engine.unset("a"), Some(rat(1, 2)));
+        assert_eq!(engine.variables().len(), 1);
+
+        assert!(engine.eval("0 ^ 0").is_err());
+
+        engine.options_mut().zero_to_the_power_of_zero = ZeroPowerConvention::One;
+        assert_eq!(engine.eval("0 ^ 0"), Ok(int(1)));
+
+        let engine = Engine::with_options(Options {
+            max_digits: 10,
+            ..Options::default()
+        });
+
+        assert!(engine.eval("2 ^ 100").is_err());
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn bindings() {
+        let mut engine = Engine::new();
+
+        let b = |engine: &mut Engine, identifier: &str, definition: &str| {
+            engine.bind(identifier, definition.parse().unwrap())
+        };
+
+        let updates = |engine: &mut Engine| {
+            engine
+                .take_updates()
+                .into_iter()
+                .map(|update| (update.identifier, update.result.map(|v| v.to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        engine.set("a", int(1));
+        assert_eq!(b(&mut engine, "b", "a + 1"), Ok(int(2)));
+        assert_eq!(b(&mut engine, "c", "b * 10 + a"), Ok(int(21)));
+        assert_eq!(b(&mut engine, "d", "x + 1"), Ok(var("x") + int(1)));
+        assert_eq!(updates(&mut engine), []);
+
+        engine.set("a", int(2));
+        assert_eq!(
+            updates(&mut engine),
+            [
+                ("b".to_owned(), Ok("3".to_owned())),
+                ("c".to_owned(), Ok("32".to_owned())),
+            ],
+        );
+        assert_eq!(engine.get("c"), Some(&int(32)));
+
+        assert_eq!(b(&mut engine, "b", "a - 2"), Ok(int(0)));
+        assert_eq!(updates(&mut engine), [("c".to_owned(), Ok("2".to_owned()))]);
+
+        assert!(matches!(
+            b(&mut engine, "a", "c + 1"),
+            Err(Error::CyclicDefinition { .. }),
+        ));
+        assert!(matches!(
+            b(&mut engine, "x", "x + 1"),
+            Err(Error::CyclicDefinition { .. }),
+        ));
+        assert_eq!(engine.get("a"), Some(&int(2)));
+
+        assert!(matches!(
+            b(&mut engine, "e", "1 / b"),
+            Err(Error::DivisionByZero { .. }),
+        ));
+        assert_eq!(engine.get("e"), None);
+
+        engine.unset("b");
+        assert_eq!(
+            updates(&mut engine),
+            [("c".to_owned(), Ok("b * 10 + 2".to_owned()))],
+        );
+
+        // Setting a bound variable removes its binding.
+        engine.set("c", int(0));
+        engine.set("a", int(3));
+        assert_eq!(updates(&mut engine), []);
+        assert_eq!(engine.bindings().keys().collect::<Vec<_>>(), [&"d"]);
+
+        assert_eq!(b(&mut engine, "f", "1 / a"), Ok(rat(1, 3)));
+        engine.set("a", int(0));
+
+        let update = engine.take_updates().pop().unwrap();
+        assert_eq!(update.identifier, "f");
+        assert!(update.result.is_err());
+        assert_eq!(engine.get("f"), None);
+    }
+
+    #[cfg(feature = "matrices")]
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn native_functions() {
+        let mut engine = Engine::new();
+
+        engine.register_fn("lookup", |arguments| match arguments {
+            [key] => match Integer::try_from(key.clone()) {
+                Ok(key) if key == 42.into() => {
+                    Ok(crate::expression::Vector::from_vec(vec![int(1), int(2)]).into())
+                }
+                Ok(key) => Err(NativeError::Failed(format!("no entry for key {}", key))),
+                Err(_) => Err(key.clone().into()),
+            },
+            _ => Err(NativeError::InvalidNumberOfArguments {
+                min_number: 1,
+                max_number: 1,
+            }),
+        });
+
+        assert_eq!(
+            engine.eval("lookup(42) * 2"),
+            "[2, 4]".parse().map_err(SavageError::from)
+        );
+        assert_eq!(engine.eval("lookup(k)"), Ok(fun(var("lookup"), [var("k")])),);
+
+        let e = |string: &str| engine.eval(string).unwrap_err().to_string();
+
+        assert_eq!(e("lookup(1)"), "lookup(1) failed: no entry for key 1");
+        assert_eq!(e("lookup(true)"), "invalid argument true in lookup(true)");
+        assert_eq!(
+            e("lookup(1, 2)"),
+            "lookup takes 1 argument, but 2 were given in lookup(1, 2)",
+        );
+        assert_eq!(engine.eval("lookup(1)").unwrap_err().code(), "E0211");
+    }
+
+    #[allow(clippy::result_large_err)]
+    #[cfg(feature = "functions")]
+    #[test]
+    fn batch_evaluation() {
+        let mut engine = Engine::new();
+        engine.set("a", int(2));
+
+        let expressions = ["a + 1", "a / 0", "[a, b] * 2", "factorial(a + 3)", "a + 1"]
+            .iter()
+            .map(|expression| expression.parse().unwrap())
+            .collect::<Vec<_>>();
+
+        let results = engine.evaluate_many(&expressions);
+
+        // Successful results are cached, and the repeated expression is evaluated once.
+        assert_eq!(engine.cache.borrow().len(), 3);
+
+        assert_eq!(
+            results,
+            expressions
+                .iter()
+                .map(|expression| engine.evaluate(expression))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(results[0], Ok(int(3)));
+        assert!(results[1].is_err());
+        assert_eq!(results[3], Ok(int(120)));
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn seed() {
+        let mut engine = Engine::new();
+
+        let values = |engine: &Engine| {
+            [
+                "random()",
+                "random()",
+                "random()",
+                "randint(1, 10^30)",
+                "randrational(100)",
+            ]
+            .iter()
+            .map(|expression| engine.eval(expression).unwrap())
+            .collect::<Vec<_>>()
+        };
+
+        engine.set_seed(42);
+        let a = values(&engine);
+        engine.set_seed(42);
+        let b = values(&engine);
+        engine.set_seed(43);
+        let c = values(&engine);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a[0], a[1]);
+    }
+
+    #[allow(clippy::result_large_err)]
+    #[cfg(feature = "functions")]
+    #[test]
+    fn caching() {
+        let mut engine = Engine::new();
+        let cached = |engine: &Engine| engine.cache.borrow().len();
+
+        assert_eq!(engine.eval("det([[1, 2], [3, 4]])"), Ok(int(-2)));
+        assert_eq!(engine.eval("det([[1, 2], [3, 4]])"), Ok(int(-2)));
+        assert_eq!(cached(&engine), 1);
+
+        engine.define("a", "2").unwrap();
+        engine.set("b", var("a") + var("c"));
+        assert_eq!(engine.eval("b * 2"), Ok((int(2) + var("c")) * int(2)));
+        assert_eq!(cached(&engine), 3);
+
+        // Changing a variable that the expression depends on,
+        // directly or indirectly, invalidates the cached result.
+        engine.set("c", int(1));
+        assert_eq!(engine.eval("b * 2"), Ok(int(6)));
+        engine.set("a", int(3));
+        assert_eq!(engine.eval("b * 2"), Ok(int(8)));
+        assert_eq!(cached(&engine), 5);
+
+        engine.set("d", int(0));
+        assert_eq!(engine.eval("b * 2"), Ok(int(8)));
+        assert_eq!(cached(&engine), 5);
+
+        // Results of random functions differ between evaluations.
+        assert_ne!(engine.eval("random()"), engine.eval("random()"));
+        assert_ne!(
+            engine.eval("randint(1, 10^30)"),
+            engine.eval("randint(1, 10^30)"),
+        );
+        assert_ne!(
+            engine.eval("randrational(100)"),
+            engine.eval("randrational(100)"),
+        );
+        engine.register_fn("f", |_| Ok(int(0)));
+        assert_eq!(engine.eval("f(1)"), Ok(int(0)));
+        assert_eq!(cached(&engine), 5);
+
+        engine.options_mut().zero_to_the_power_of_zero = ZeroPowerConvention::One;
+        assert_eq!(cached(&engine), 0);
+        assert_eq!(engine.eval("0 ^ 0"), Ok(int(1)));
+
+        engine.set_seed(42);
+        assert_eq!(cached(&engine), 0);
+    }
+
+    #[allow(clippy::result_large_err)]
+    #[cfg(feature = "functions")]
+    #[test]
+    fn persistence() {
+        let mut engine = Engine::new();
+
+        for (identifier, value) in [
+            ("a", "1/2"),
+            ("b", "-0.25"),
+            ("c", "[[1, 2 + i], [3, 4]]"),
+            ("d", "3 mod 7"),
+            ("e", "!true"),
+            ("f", "x ^ 2 + y"),
+            ("g", "quaternion(1, 2, 3, 4)"),
+            ("l", "(x, y) -> x ^ y + a"),
+            ("m", "() -> 1"),
+        ] {
+            engine.define(identifier, value).unwrap();
+        }
+
+        engine.register_fn("h", |_| Ok(int(0)));
+        engine.bind("k", "a * 4".parse().unwrap()).unwrap();
+
+        let mut data = Vec::new();
+        engine.save_to(&mut data).unwrap();
+
+        assert_eq!(
+            String::from_utf8(data.clone()).unwrap(),
+            "a = 1/2
+b = -0.25
+c = [[1, 2 + i], [3, 4]]
+d = 3 mod 7
+e = false
+f = x ^ 2 + y
+g = quaternion(1, 2, 3, 4)
+k := a * 4
+l = (x, y) -> x ^ y + a
+m = () -> 1
+",
+        );
+
+        let mut loaded = Engine::new();
+        loaded.load_from(&data[..]).unwrap();
+
+        engine.unset("h");
+        assert_eq!(loaded.variables(), engine.variables());
+        assert_eq!(loaded.bindings(), engine.bindings());
+
+        loaded.set("a", int(3));
+        assert_eq!(loaded.get("k"), Some(&int(12)));
+        assert_eq!(loaded.eval("l(2, 3)").unwrap(), int(11));
+        assert_eq!(loaded.eval("m()").unwrap(), int(1));
+
+        let e = |string: &str| {
+            Engine::new()
+                .load_from(string.as_bytes())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(e("# Comment\n\na"), "line 3: expected 'identifier = value'");
+        assert_eq!(e("a + b = 1"), "line 1: invalid identifier 'a + b'");
+        assert!(e("a = 1 +").starts_with("line 1: unexpected end of input"));
+        assert_eq!(
+            e("a = 1 / 0"),
+            "line 1: division by zero in 1 / 0 (0 is zero)"
+        );
+    }
+}
@@ -1,15 +1,31 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
-use std::collections::HashMap;
+use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
-use num::{One, ToPrimitive, Zero};
+use derivative::*;
+use num::{Integer as _, One, Signed, ToPrimitive, Zero};
 
 use crate::{
-    expression::{Complex, Expression, RationalRepresentation},
-    functions::functions,
+    expression::{Complex, Expression, Function, Integer, Rational, RationalRepresentation, Type},
+    helpers::{int, modulo, pow, quat},
+    print::FormatOptions,
+    random::Generator,
+    surd::{self, Surd},
 };
 
+#[cfg(feature = "functions")]
+use crate::{algebraic, functions::functions};
+
+#[cfg(feature = "matrices")]
+use crate::expression::Tensor;
+
 /// Error that occurred while trying to evaluate an expression.
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum Error {
@@ -24,6 +40,15 @@ pub enum Error {
         operand_1: Expression,
         operand_2: Expression,
     },
+    /// Operation on two vector or matrix expressions whose shapes
+    /// (given as numbers of rows and columns) are incompatible for the operation.
+    IncompatibleShapes {
+        expression: Expression,
+        operand_1: Expression,
+        operand_2: Expression,
+        shape_1: (usize, usize),
+        shape_2: (usize, usize),
+    },
     /// Division by an expression that evaluates to zero (undefined).
     DivisionByZero {
         expression: Expression,
@@ -37,6 +62,12 @@ pub enum Error {
         base: Expression,
         exponent: Expression,
     },
+    /// Operation requiring the multiplicative inverse of an expression that
+    /// evaluates to a residue class that is not invertible modulo its modulus.
+    NotInvertible {
+        expression: Expression,
+        operand: Expression,
+    },
     /// Vector or matrix expression indexed by an expression that evaluates to
     /// an integer outside the range of valid indices for that vector or matrix.
     IndexOutOfBounds {
@@ -45,7 +76,8 @@ pub enum Error {
         index: Expression,
     },
     /// Function expression evaluated with a number of arguments
-    /// that is invalid for the function.
+    /// that is invalid for the function. If the function takes
+    /// arbitrarily many arguments, `max_number` is `usize::MAX`.
     InvalidNumberOfArguments {
         expression: Expression,
         min_number: usize,
@@ -58,6 +90,914 @@ pub enum Error {
         expression: Expression,
         argument: Expression,
     },
+    /// Operation whose evaluation would require more of a resource
+    /// than the limit set in the evaluation options.
+    ResourceLimitExceeded {
+        expression: Expression,
+        resource: Resource,
+        limit: usize,
+        estimate: usize,
+    },
+    /// Function expression whose evaluation failed for a reason other than
+    /// an invalid argument, e.g. because a native function registered by the
+    /// embedding application reported a failure, or a file could not be read.
+    NativeFunctionFailed {
+        expression: Expression,
+        message: String,
+    },
+    /// Variable bound to a definition that depends on the variable itself,
+    /// directly or through other bindings.
+    CyclicDefinition {
+        identifier: String,
+        definition: Expression,
+    },
+    /// Conversion of a quantity to a unit of a different physical dimension.
+    /// The dimensions are given as products and quotients of SI base units.
+    IncompatibleDimensions {
+        expression: Expression,
+        dimension_1: Expression,
+        dimension_2: Expression,
+    },
+}
+
+/// Returns a description of the given expression for use in error messages,
+/// e.g. "the complex number 2 + 3*i". Expressions that are not values
+/// are returned as they are.
+fn describe(expression: &Expression) -> String {
+    use crate::expression::Type::*;
+
+    let kind = match expression.typ() {
+        Function(_, _) => "the function",
+        Number(z, _) => {
+            if !z.im.is_zero() {
+                "the complex number"
+            } else if z.re.is_integer() {
+                "the integer"
+            } else {
+                "the number"
+            }
+        }
+        #[cfg(feature = "matrices")]
+        Matrix(m) => {
+            if m.ncols() == 1 && matches!(expression, Expression::Vector(_)) {
+                "the vector"
+            } else {
+                "the matrix"
+            }
+        }
+        #[cfg(feature = "matrices")]
+        Tensor(_) => "the tensor",
+        Quaternion(_, _, _, _) => "the quaternion",
+        Mod(_, _) => "the residue class",
+        Interval(_, _) => "the interval",
+        String(_) => "the string",
+        Boolean(Some(_)) => "the boolean value",
+        Boolean(None) | Arithmetic | Unknown => return expression.to_string(),
+    };
+
+    format!("{} {}", kind, expression)
+}
+
+/// Returns the operator symbol of the given operator expression, if any.
+pub(crate) fn operator(expression: &Expression) -> Option<&'static str> {
+    use crate::expression::Expression::*;
+
+    Some(match expression {
+        Mod(_, _) => "mod",
+        Negation(_) => "-",
+        Not(_) => "!",
+        Sum(_, _) => "+",
+        Difference(_, _) => "-",
+        Product(_, _) => "*",
+        Quotient(_, _) => "/",
+        Remainder(_, _) => "%",
+        Power(_, _) => "^",
+        Equal(_, _) => "==",
+        NotEqual(_, _) => "!=",
+        LessThan(_, _) => "<",
+        LessThanOrEqual(_, _) => "<=",
+        GreaterThan(_, _) => ">",
+        GreaterThanOrEqual(_, _) => ">=",
+        And(_, _) => "&&",
+        Or(_, _) => "||",
+        _ => return None,
+    })
+}
+
+/// Returns a verb phrase describing the operation performed
+/// by the given expression on its operands, e.g. "add".
+fn action(expression: &Expression) -> String {
+    use crate::expression::Expression::*;
+
+    match expression {
+        FunctionValue(_, _) => "call".to_owned(),
+        #[cfg(feature = "matrices")]
+        VectorElement(_, _) | MatrixElement(_, _, _) => "index".to_owned(),
+        Quaternion(_, _, _, _) => "construct a quaternion from".to_owned(),
+        Mod(_, _) => "construct a residue class from".to_owned(),
+        Interval(_, _) => "construct an interval from".to_owned(),
+        Negation(_) => "negate".to_owned(),
+        Sum(_, _) => "add".to_owned(),
+        Difference(_, _) => "subtract".to_owned(),
+        Product(_, _) => "multiply".to_owned(),
+        Quotient(_, _) => "divide".to_owned(),
+        Remainder(_, _) => "compute the remainder of".to_owned(),
+        Power(_, _) => "exponentiate".to_owned(),
+        Equal(_, _)
+        | NotEqual(_, _)
+        | LessThan(_, _)
+        | LessThanOrEqual(_, _)
+        | GreaterThan(_, _)
+        | GreaterThanOrEqual(_, _) => format!("compare with {}", operator(expression).unwrap()),
+        Not(_) | And(_, _) | Or(_, _) => format!("apply {} to", operator(expression).unwrap()),
+        _ => "evaluate".to_owned(),
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+
+        match self {
+            InvalidOperand {
+                expression,
+                operand,
+            } => {
+                let action = action(expression);
+
+                // "cannot compare with < the number 1" reads better as
+                // "cannot compare the number 1 with <".
+                match action.split_once(' ') {
+                    Some((verb @ "compare", rest)) => write!(
+                        f,
+                        "cannot {} {} {} in {}",
+                        verb,
+                        describe(operand),
+                        rest,
+                        expression,
+                    ),
+                    _ => write!(
+                        f,
+                        "cannot {} {} in {}",
+                        action,
+                        describe(operand),
+                        expression,
+                    ),
+                }
+            }
+            IncompatibleOperands {
+                expression,
+                operand_1,
+                operand_2,
+            } => write!(
+                f,
+                "cannot {} {} and {} in {}",
+                action(expression),
+                describe(operand_1),
+                describe(operand_2),
+                expression,
+            ),
+            IncompatibleShapes {
+                expression,
+                shape_1,
+                shape_2,
+                ..
+            } => write!(
+                f,
+                "cannot {} a {}x{} matrix and a {}x{} matrix in {}",
+                action(expression),
+                shape_1.0,
+                shape_1.1,
+                shape_2.0,
+                shape_2.1,
+                expression,
+            ),
+            DivisionByZero {
+                expression,
+                divisor,
+                ..
+            } => write!(
+                f,
+                "division by zero in {} ({} {} zero)",
+                expression,
+                divisor,
+                if let Expression::Interval(_, _) = divisor {
+                    "contains"
+                } else {
+                    "is"
+                },
+            ),
+            ZeroToThePowerOfZero { expression, .. } => write!(
+                f,
+                "zero to the power of zero is undefined in {}",
+                expression,
+            ),
+            NotInvertible {
+                expression,
+                operand,
+            } => write!(
+                f,
+                "{} is not invertible in {}",
+                describe(operand),
+                expression,
+            ),
+            IndexOutOfBounds {
+                expression,
+                vector_or_matrix,
+                index,
+            } => write!(
+                f,
+                "index {} is out of bounds for {} in {}",
+                index,
+                describe(vector_or_matrix),
+                expression,
+            ),
+            InvalidNumberOfArguments {
+                expression,
+                min_number,
+                max_number,
+                given_number,
+            } => {
+                let function = match expression {
+                    Expression::FunctionValue(function, _) => function.to_string(),
+                    _ => expression.to_string(),
+                };
+
+                let plural = |n: usize| if n == 1 { "argument" } else { "arguments" };
+
+                if *max_number == usize::MAX {
+                    write!(
+                        f,
+                        "{} takes at least {} {}, but {} {} given in {}",
+                        function,
+                        min_number,
+                        plural(*min_number),
+                        given_number,
+                        if *given_number == 1 { "was" } else { "were" },
+                        expression,
+                    )
+                } else if min_number == max_number {
+                    write!(
+                        f,
+                        "{} takes {} {}, but {} {} given in {}",
+                        function,
+                        min_number,
+                        plural(*min_number),
+                        given_number,
+                        if *given_number == 1 { "was" } else { "were" },
+                        expression,
+                    )
+                } else {
+                    write!(
+                        f,
+                        "{} takes between {} and {} arguments, but {} {} given in {}",
+                        function,
+                        min_number,
+                        max_number,
+                        given_number,
+                        if *given_number == 1 { "was" } else { "were" },
+                        expression,
+                    )
+                }
+            }
+            InvalidArgument {
+                expression,
+                argument,
+            } => write!(f, "invalid argument {} in {}", argument, expression),
+            ResourceLimitExceeded {
+                expression,
+                resource: resource @ (Resource::Steps | Resource::Time),
+                limit,
+                ..
+            } => write!(
+                f,
+                "evaluating {} exceeded the limit of {} {}",
+                expression,
+                limit,
+                match resource {
+                    Resource::Steps => "evaluation steps",
+                    _ => "milliseconds",
+                },
+            ),
+            ResourceLimitExceeded {
+                expression,
+                resource,
+                limit,
+                estimate,
+            } => write!(
+                f,
+                "evaluating {} would require {} {}, exceeding the limit of {}",
+                expression,
+                // Estimates saturate at the largest `usize` value.
+                if *estimate == usize::MAX {
+                    format!("more than 2 ^ {}", usize::BITS)
+                } else {
+                    format!("about {}", estimate)
+                },
+                match resource {
+                    Resource::Digits => "digits",
+                    Resource::MatrixElements => "matrix elements",
+                    Resource::Memory => "bytes of memory",
+                    Resource::Steps | Resource::Time => unreachable!(),
+                },
+                limit,
+            ),
+            NativeFunctionFailed {
+                expression,
+                message,
+            } => write!(f, "{} failed: {}", expression, message),
+            CyclicDefinition {
+                identifier,
+                definition,
+            } => write!(
+                f,
+                "cannot bind {} to {}, which depends on {}",
+                identifier, definition, identifier,
+            ),
+            IncompatibleDimensions {
+                expression,
+                dimension_1,
+                dimension_2,
+            } => write!(
+                f,
+                "cannot convert a quantity of dimension {} to a unit of dimension {} in {}",
+                dimension_1, dimension_2, expression,
+            ),
+        }
+    }
+}
+
+impl Error {
+    /// Returns a stable, machine-readable code identifying the kind of the error.
+    pub fn code(&self) -> &'static str {
+        use Error::*;
+
+        match self {
+            InvalidOperand { .. } => "E0201",
+            IncompatibleOperands { .. } => "E0202",
+            IncompatibleShapes { .. } => "E0203",
+            DivisionByZero { .. } => "E0204",
+            ZeroToThePowerOfZero { .. } => "E0205",
+            NotInvertible { .. } => "E0206",
+            IndexOutOfBounds { .. } => "E0207",
+            InvalidNumberOfArguments { .. } => "E0208",
+            InvalidArgument { .. } => "E0209",
+            ResourceLimitExceeded { .. } => "E0210",
+            NativeFunctionFailed { .. } => "E0211",
+            CyclicDefinition { .. } => "E0212",
+            IncompatibleDimensions { .. } => "E0213",
+        }
+    }
+
+    /// Returns a suggestion for fixing the error, if one is available.
+    pub fn suggestion(&self) -> Option<String> {
+        use Error::*;
+
+        match self {
+            IncompatibleShapes { expression, .. } => match expression {
+                Expression::Product(_, _) => Some(
+                    "the number of columns of the first matrix must equal \
+                     the number of rows of the second matrix"
+                        .to_owned(),
+                ),
+                _ => Some("both matrices must have the same number of rows and columns".to_owned()),
+            },
+            ZeroToThePowerOfZero { .. } => Some(
+                "set `zero_to_the_power_of_zero` in the evaluation options \
+                 to `ZeroPowerConvention::One` to define zero to the power of zero as one"
+                    .to_owned(),
+            ),
+            NotInvertible { .. } => Some(
+                "a residue class is invertible only if its representative \
+                 is coprime to the modulus"
+                    .to_owned(),
+            ),
+            IndexOutOfBounds { .. } => Some("indices start at 0".to_owned()),
+            ResourceLimitExceeded { resource, .. } => Some(format!(
+                "increase `{}` in the evaluation options to allow this operation",
+                match resource {
+                    Resource::Digits => "max_digits",
+                    Resource::MatrixElements => "max_matrix_elements",
+                    Resource::Memory => "max_memory",
+                    Resource::Steps => "max_steps",
+                    Resource::Time => "timeout",
+                },
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Resource whose consumption by individual operations
+/// can be limited using the evaluation options.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Resource {
+    /// Number of decimal digits of the numbers in the result.
+    Digits,
+    /// Number of elements of a matrix in the result.
+    MatrixElements,
+    /// Memory, in bytes.
+    Memory,
+    /// Number of evaluation steps performed on subexpressions.
+    /// Limited for entire evaluations rather than individual operations.
+    Steps,
+    /// Wall-clock time, in milliseconds.
+    /// Limited for entire evaluations rather than individual operations.
+    Time,
+}
+
+/// Resource limit that an operation would exceed, without
+/// the expression that is required to construct an `Error`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(crate) struct LimitExceeded {
+    resource: Resource,
+    limit: usize,
+    estimate: usize,
+}
+
+impl LimitExceeded {
+    /// Returns the error for the given expression exceeding the limit.
+    pub(crate) fn into_error(self, expression: &Expression) -> Error {
+        Error::ResourceLimitExceeded {
+            expression: expression.clone(),
+            resource: self.resource,
+            limit: self.limit,
+            estimate: self.estimate,
+        }
+    }
+}
+
+/// Convention for the value of zero raised to the power of zero.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ZeroPowerConvention {
+    /// `0 ^ 0` is undefined, and evaluating it fails
+    /// with a `ZeroToThePowerOfZero` error.
+    Undefined,
+    /// `0 ^ 0 == 1`, as is customary in combinatorics and for power series.
+    One,
+}
+
+/// Sign that a real variable can be assumed to have.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Sign {
+    /// The variable is greater than zero.
+    Positive,
+    /// The variable is greater than or equal to zero.
+    NonNegative,
+    /// The variable is less than zero.
+    Negative,
+    /// The variable is less than or equal to zero.
+    NonPositive,
+}
+
+/// Event that occurred during evaluation, reported to the observer
+/// set in the evaluation options.
+#[derive(Clone, Copy, Debug)]
+pub enum Event<'a> {
+    /// An evaluation step is about to be performed on the expression.
+    Enter(&'a Expression),
+    /// An evaluation step rewrote the first expression into the second expression.
+    Rewrite(&'a Expression, &'a Expression),
+    /// Evaluation failed with the error.
+    Error(&'a Error),
+}
+
+/// Callback that is invoked for each event that occurs during evaluation.
+pub type Observer = dyn Fn(Event);
+
+/// Options controlling how expressions are evaluated.
+#[derive(Derivative)]
+#[derivative(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub struct Options {
+    /// Value of zero raised to the power of zero.
+    pub zero_to_the_power_of_zero: ZeroPowerConvention,
+    /// Maximum number of decimal digits of numbers produced
+    /// by powers, factorials, and matrix powers.
+    pub max_digits: usize,
+    /// Maximum number of elements of matrices produced by matrix products.
+    pub max_matrix_elements: usize,
+    /// Maximum estimated memory, in bytes, that a single operation may allocate.
+    pub max_memory: usize,
+    /// Maximum number of evaluation steps performed on subexpressions
+    /// while evaluating an expression.
+    pub max_steps: usize,
+    /// Maximum wall-clock time that evaluating an expression may take, if any.
+    /// The time is checked between evaluation steps and inside the loops of some
+    /// built-in functions, so other individual operations are not interrupted.
+    pub timeout: Option<Duration>,
+    /// Identifiers of the built-in functions that are available during evaluation,
+    /// or `None` if all built-in functions are available. Calls to other built-in
+    /// functions remain unevaluated.
+    pub allowed_functions: Option<BTreeSet<String>>,
+    /// Options used when built-in functions such as `format` turn values into text.
+    pub format_options: FormatOptions,
+    /// Signs that variables are assumed to have, by their identifiers. Built-in
+    /// functions such as `abs` and `sqrt` use them to simplify symbolic arguments,
+    /// e.g. `sqrt(x^2)` evaluates to `x` if `x` is assumed to be non-negative.
+    pub assumptions: HashMap<String, Sign>,
+    /// Callback for observing the progress of evaluation, e.g. for profiling,
+    /// debugging, or showing the individual steps of a computation.
+    #[derivative(PartialEq = "ignore", Debug = "ignore")]
+    pub observer: Option<Rc<Observer>>,
+    /// Generator behind the random built-in functions, shared by clones of the options.
+    #[derivative(PartialEq = "ignore", Debug = "ignore")]
+    pub(crate) generator: Rc<Generator>,
+    /// Resources consumed by the ongoing evaluation, if any.
+    #[derivative(PartialEq = "ignore", Debug = "ignore")]
+    pub(crate) budget: Option<Rc<Budget>>,
+}
+
+/// Steps and time consumed by an evaluation.
+pub(crate) struct Budget {
+    steps: Cell<usize>,
+    start: Instant,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            zero_to_the_power_of_zero: ZeroPowerConvention::Undefined,
+            max_digits: 100_000,
+            max_matrix_elements: 1_000_000,
+            max_memory: 1 << 26,
+            max_steps: usize::MAX,
+            timeout: None,
+            allowed_functions: None,
+            format_options: FormatOptions::default(),
+            assumptions: HashMap::new(),
+            observer: None,
+            generator: Rc::new(Generator::from_entropy()),
+            budget: None,
+        }
+    }
+}
+
+impl Options {
+    /// Returns options suitable for evaluating untrusted input, e.g. expressions
+    /// submitted to chat bots or web services. These options impose strict limits
+    /// on resource consumption, and make only built-in functions available
+    /// whose running time is bounded by those limits. Built-in functions that
+    /// loop internally (such as `roots`, `series`, and `nintegrate`) check the
+    /// timeout inside their loops, while functions that factor polynomials
+    /// (such as `factor` and `apart`) are not available because their running
+    /// time cannot be bounded.
+    pub fn sandboxed() -> Self {
+        Self {
+            max_digits: 1000,
+            max_matrix_elements: 10_000,
+            max_memory: 1 << 20,
+            max_steps: 100_000,
+            timeout: Some(Duration::from_secs(1)),
+            allowed_functions: Some(
+                [
+                    "and",
+                    "abs",
+                    "sqrt",
+                    "re",
+                    "im",
+                    "arg",
+                    "conj",
+                    "exp",
+                    "ln",
+                    "log",
+                    "lhs",
+                    "rhs",
+                    "swap",
+                    "solve",
+                    "linsolve",
+                    "roots",
+                    "together",
+                    "expand",
+                    "factorial",
+                    "binomial",
+                    "permutations",
+                    "multinomial",
+                    "gamma",
+                    "erf",
+                    "erfc",
+                    "zeta",
+                    "Ei",
+                    "shape",
+                    "reshape",
+                    "tensordot",
+                    "diff",
+                    "integrate",
+                    "nintegrate",
+                    "series",
+                    "div",
+                    "curl",
+                    "sum",
+                    "product",
+                    "subs",
+                    "is_probable_prime",
+                    "gcd",
+                    "lcm",
+                    "mod",
+                    "powmod",
+                    "invmod",
+                    "rationalize",
+                    "cfrac",
+                    "from_cfrac",
+                    "convergents",
+                    "to_base",
+                    "to_base_string",
+                    "from_base",
+                    "sin",
+                    "cos",
+                    "tan",
+                    "quaternion",
+                    "quaternion_conjugate",
+                    "quaternion_norm",
+                    "quaternion_inverse",
+                    "quaternion_to_matrix",
+                    "matrix_to_quaternion",
+                    "interval",
+                    "decimal",
+                    "fraction",
+                    "N",
+                    "N_interval",
+                    "N_down",
+                    "N_up",
+                    "round_down",
+                    "round_up",
+                    "round",
+                    "floor",
+                    "ceil",
+                    "percent_of",
+                    "format",
+                    "convert",
+                    "random",
+                    "randint",
+                    "randrational",
+                ]
+                .iter()
+                .map(|identifier| identifier.to_string())
+                .collect(),
+            ),
+            ..Self::default()
+        }
+    }
+
+    /// Seeds the generator behind the random built-in functions with the given value,
+    /// making the sequence of random numbers produced during evaluation reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.generator = Rc::new(Generator::new(seed));
+    }
+
+    /// Records an evaluation step on the given expression, returning
+    /// an error if the step exceeds the step limit or the timeout.
+    #[allow(clippy::result_large_err)]
+    fn record_step(&self, expression: &Expression) -> Result<(), Error> {
+        if let Some(budget) = &self.budget {
+            budget.steps.set(budget.steps.get() + 1);
+
+            if budget.steps.get() > self.max_steps {
+                return Err(LimitExceeded {
+                    resource: Resource::Steps,
+                    limit: self.max_steps,
+                    estimate: budget.steps.get(),
+                }
+                .into_error(expression));
+            }
+
+            self.check_time()
+                .map_err(|limit_exceeded| limit_exceeded.into_error(expression))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if the ongoing evaluation has exceeded the timeout.
+    /// Operations that may run for a long time without performing evaluation
+    /// steps call this function periodically to honor the timeout.
+    pub(crate) fn check_time(&self) -> Result<(), LimitExceeded> {
+        if let (Some(budget), Some(timeout)) = (&self.budget, self.timeout) {
+            let elapsed = budget.start.elapsed();
+
+            if elapsed > timeout {
+                return Err(LimitExceeded {
+                    resource: Resource::Time,
+                    limit: timeout.as_millis() as usize,
+                    estimate: elapsed.as_millis() as usize,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if the given estimate for the consumption
+    /// of the given resource exceeds the limit for that resource.
+    pub(crate) fn check(&self, resource: Resource, estimate: f64) -> Result<(), LimitExceeded> {
+        let limit = match resource {
+            Resource::Digits => self.max_digits,
+            Resource::MatrixElements => self.max_matrix_elements,
+            Resource::Memory => self.max_memory,
+            Resource::Steps => self.max_steps,
+            Resource::Time => self
+                .timeout
+                .map_or(usize::MAX, |timeout| timeout.as_millis() as usize),
+        };
+
+        // Casting saturates for values that are too large for the target type.
+        let estimate = estimate.ceil() as usize;
+
+        if estimate > limit {
+            Err(LimitExceeded {
+                resource,
+                limit,
+                estimate,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns an error if a number with the given number of decimal digits
+    /// (or the corresponding memory) exceeds the limits.
+    pub(crate) fn check_digits(&self, digits: f64) -> Result<(), LimitExceeded> {
+        self.check(Resource::Digits, digits)?;
+        self.check(Resource::Memory, digits * std::f64::consts::LOG2_10 / 8.0)
+    }
+}
+
+/// Returns the implementation of a function that evaluates to the given body
+/// with each parameter replaced by the corresponding argument.
+#[allow(clippy::result_large_err)]
+pub(crate) fn lambda(parameters: &[String], body: &Expression) -> Rc<Function> {
+    let parameters = parameters.to_vec();
+    let body = body.clone();
+
+    Rc::new(move |expression, arguments, _, _| {
+        if arguments.len() != parameters.len() {
+            return Err(Error::InvalidNumberOfArguments {
+                expression: expression.clone(),
+                min_number: parameters.len(),
+                max_number: parameters.len(),
+                given_number: arguments.len(),
+            });
+        }
+
+        let substitutions = parameters
+            .iter()
+            .map(String::as_str)
+            .zip(arguments)
+            .collect();
+
+        Ok(substitute_variables(&body, &substitutions))
+    })
+}
+
+/// Adds the identifiers of all variables in the expression that are not
+/// parameters of an enclosing lambda expression to `variables`.
+fn unbound_variables(expression: &Expression, variables: &mut BTreeSet<String>) {
+    match expression {
+        Expression::Variable(identifier) => {
+            variables.insert(identifier.clone());
+        }
+        Expression::Lambda(parameters, body) => {
+            let mut body_variables = BTreeSet::new();
+            unbound_variables(body, &mut body_variables);
+
+            variables.extend(
+                body_variables
+                    .into_iter()
+                    .filter(|identifier| !parameters.contains(identifier)),
+            );
+        }
+        _ => {
+            for child in expression.children() {
+                unbound_variables(child, variables);
+            }
+        }
+    }
+}
+
+/// Returns the expression with all unbound variables whose identifiers are keys
+/// of `substitutions` replaced by the corresponding values. The replacements
+/// are performed simultaneously, so variables in the values are not replaced.
+/// Parameters of lambda expressions are renamed where necessary to prevent
+/// variables in the values from being captured by them.
+pub(crate) fn substitute_variables(
+    expression: &Expression,
+    substitutions: &HashMap<&str, &Expression>,
+) -> Expression {
+    match expression {
+        Expression::Variable(identifier) => substitutions
+            .get(identifier.as_str())
+            .map_or_else(|| expression.clone(), |value| (*value).clone()),
+        Expression::Lambda(parameters, body) => {
+            // Parameters shadow variables with the same identifiers.
+            let substitutions = substitutions
+                .iter()
+                .filter(|(identifier, _)| !parameters.iter().any(|p| p == *identifier))
+                .map(|(identifier, value)| (*identifier, *value))
+                .collect::<HashMap<_, _>>();
+
+            let mut captured = BTreeSet::new();
+
+            for value in substitutions.values() {
+                unbound_variables(value, &mut captured);
+            }
+
+            let mut used = captured.clone();
+            unbound_variables(body, &mut used);
+            used.extend(parameters.iter().cloned());
+
+            let mut renamings = HashMap::new();
+
+            let parameters = parameters
+                .iter()
+                .map(|parameter| {
+                    if !captured.contains(parameter) {
+                        return parameter.clone();
+                    }
+
+                    let renamed = (1..)
+                        .map(|n| format!("{}{}", parameter, n))
+                        .find(|identifier| !used.contains(identifier))
+                        .unwrap();
+
+                    used.insert(renamed.clone());
+                    renamings.insert(parameter.as_str(), Expression::Variable(renamed.clone()));
+
+                    renamed
+                })
+                .collect();
+
+            let renamings = renamings
+                .iter()
+                .map(|(identifier, value)| (*identifier, value))
+                .collect();
+
+            Expression::Lambda(
+                parameters,
+                Box::new(substitute_variables(
+                    &substitute_variables(body, &renamings),
+                    &substitutions,
+                )),
+            )
+        }
+        _ => expression.map_children(|child| substitute_variables(child, substitutions)),
+    }
+}
+
+/// Identifiers of the mathematical constants `pi` and `e`, which are bound to themselves
+/// in the default context, so they are not free variables and remain exact when evaluated.
+pub(crate) const CONSTANTS: [&str; 2] = ["pi", "e"];
+
+/// Returns the variables and functions that are available
+/// when evaluating any expression with the given options, by their identifiers.
+#[cfg_attr(not(feature = "functions"), allow(unused_variables))]
+fn default_context(options: &Options) -> HashMap<String, Expression> {
+    let mut context = HashMap::new();
+
+    #[cfg(feature = "complex")]
+    context.insert(
+        "i".to_owned(),
+        Expression::Complex(Complex::i(), RationalRepresentation::Fraction),
+    );
+
+    for identifier in CONSTANTS {
+        context.insert(
+            identifier.to_owned(),
+            Expression::Variable(identifier.to_owned()),
+        );
+    }
+
+    #[cfg(feature = "functions")]
+    for function in functions() {
+        if let Some(allowed_functions) = &options.allowed_functions {
+            if !allowed_functions.contains(function.metadata.name) {
+                continue;
+            }
+        }
+
+        context.insert(
+            function.metadata.name.to_owned(),
+            Expression::Function(function.metadata.name.to_owned(), function.implementation),
+        );
+    }
+
+    context
+}
+
+/// Returns the variables and functions that are available when evaluating
+/// any expression with the given options, with the given variables added.
+pub(crate) fn complete_context(
+    context: HashMap<String, Expression>,
+    options: &Options,
+) -> HashMap<String, Expression> {
+    let mut complete_context = default_context(options);
+
+    for (identifier, expression) in context {
+        complete_context.insert(identifier, expression);
+    }
+
+    complete_context
 }
 
 impl Expression {
@@ -65,30 +1005,55 @@ impl Expression {
     /// the unary operator expression `self` with operand `a`, or an error
     /// if the expression cannot be evaluated. The `context` argument can be
     /// used to set the values of variables by their identifiers.
+    #[allow(clippy::result_large_err)]
     fn evaluate_step_unary(
         &self,
         a: &Self,
         context: &HashMap<String, Self>,
+        options: &Options,
     ) -> Result<Self, Error> {
         use crate::expression::Expression::*;
-        use crate::expression::Type::{Arithmetic, Boolean as Bool, Matrix as Mat, Number as Num};
+        use crate::expression::Type::{
+            Arithmetic, Boolean as Bool, Interval as Ival, Matrix as Mat, Mod as Residue,
+            Number as Num, Quaternion as Quat, String as Str, Tensor as Ten,
+        };
         use Error::*;
 
         let a_original = a;
 
-        let a = a.evaluate_step(context)?;
+        let a = a.evaluate_step(context, options)?;
 
         match (self, a.typ()) {
-            (Negation(_), Bool(_)) | (Not(_), Num(_, _) | Mat(_) | Arithmetic) => {
-                Err(InvalidOperand {
-                    expression: self.clone(),
-                    operand: a_original.clone(),
-                })
-            }
+            (Negation(_), Bool(None)) if matches!(a, Equal(_, _)) => match a {
+                Equal(lhs, rhs) => Ok(Equal(Box::new(-*lhs), Box::new(-*rhs))),
+                _ => unreachable!(),
+            },
+
+            (Negation(_), Bool(_) | Str(_))
+            | (
+                Not(_),
+                Num(_, _) | Mat(_) | Ten(_) | Quat(_, _, _, _) | Ival(_, _) | Arithmetic | Str(_),
+            ) => Err(InvalidOperand {
+                expression: self.clone(),
+                operand: a_original.clone(),
+            }),
 
             (Negation(_), Num(a, representation)) => Ok(Complex(-a, representation)),
+            #[cfg(feature = "matrices")]
             (Negation(_), Mat(a)) => Ok(Matrix(-a)),
-            (Negation(_), _) => Ok(Negation(Box::new(a))),
+            #[cfg(feature = "matrices")]
+            (Negation(_), Ten(a)) => Ok(Tensor(a.map(|x| -x.clone()))),
+            (Negation(_), Quat(a, b, c, d)) => Ok(quat(-*a, -*b, -*c, -*d)),
+            (Negation(_), Residue(n, m)) => Ok(modulo((-n).mod_floor(&m), m)),
+            (Negation(_), Ival(x, representation)) => Ok(x.negate().to_expression(representation)),
+            (Negation(_), _) => {
+                #[cfg(feature = "functions")]
+                if let Some(result) = algebraic::negate(&a) {
+                    return Ok(result);
+                }
+
+                Ok(surd::negate(&a).unwrap_or_else(|| Negation(Box::new(a))))
+            }
 
             (Not(_), Bool(Some(a))) => Ok(Boolean(!a)),
             (Not(_), _) => Ok(Not(Box::new(a))),
@@ -100,11 +1065,11 @@ impl Expression {
                 | Integer(_)
                 | Rational(_, _)
                 | Complex(_, _)
-                | Vector(_)
-                | VectorElement(_, _)
-                | Matrix(_)
-                | MatrixElement(_, _, _)
+                | Quaternion(_, _, _, _)
+                | Mod(_, _)
+                | Interval(_, _)
                 | Boolean(_)
+                | String(_)
                 | Sum(_, _)
                 | Difference(_, _)
                 | Product(_, _)
@@ -118,7 +1083,18 @@ impl Expression {
                 | GreaterThan(_, _)
                 | GreaterThanOrEqual(_, _)
                 | And(_, _)
-                | Or(_, _),
+                | Or(_, _)
+                | Lambda(_, _),
+                _,
+            ) => unreachable!(),
+            #[cfg(feature = "matrices")]
+            (
+                Vector(_)
+                | VectorElement(_, _)
+                | Matrix(_)
+                | MatrixElement(_, _, _)
+                | Tensor(_)
+                | TensorElement(_, _),
                 _,
             ) => unreachable!(),
         }
@@ -129,26 +1105,109 @@ impl Expression {
     /// or an error if the expression cannot be evaluated. The `context`
     /// argument can be used to set the values of variables by their
     /// identifiers.
+    #[allow(clippy::result_large_err)]
     fn evaluate_step_binary(
         &self,
         a: &Self,
         b: &Self,
         context: &HashMap<String, Self>,
+        options: &Options,
     ) -> Result<Self, Error> {
         use crate::expression::Expression::*;
-        use crate::expression::Type::{Arithmetic, Boolean as Bool, Matrix as Mat, Number as Num};
+        use crate::expression::Type::{
+            Arithmetic, Boolean as Bool, Function as Fun, Interval as Ival, Matrix as Mat,
+            Mod as Residue, Number as Num, Quaternion as Quat, String as Str, Tensor as Ten,
+            Unknown,
+        };
         use Error::*;
 
         let a_original = a;
         let b_original = b;
 
-        let a = a.evaluate_step(context)?;
-        let b = b.evaluate_step(context)?;
+        let a = a.evaluate_step(context, options)?;
+        let b = b.evaluate_step(context, options)?;
 
         let a_evaluated = &a;
         let b_evaluated = &b;
 
+        if let Some(result) = surd::evaluate(self, &a, &b) {
+            return Ok(result);
+        }
+
+        #[cfg(feature = "functions")]
+        if let Some(result) = algebraic::evaluate(self, &a, &b) {
+            return Ok(result);
+        }
+
         match (self, a.typ(), b.typ()) {
+            (Equal(_, _), Str(a), Str(b)) => Ok(Boolean(a == b)),
+            (NotEqual(_, _), Str(a), Str(b)) => Ok(Boolean(a != b)),
+
+            (
+                Equal(_, _) | NotEqual(_, _),
+                Str(_),
+                Fun(_, _) | Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Bool(Some(_)),
+            )
+            | (
+                Equal(_, _) | NotEqual(_, _),
+                Fun(_, _) | Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Bool(Some(_)),
+                Str(_),
+            ) => Err(IncompatibleOperands {
+                expression: self.clone(),
+                operand_1: a_original.clone(),
+                operand_2: b_original.clone(),
+            }),
+
+            // Strings can only be compared for equality.
+            (Equal(_, _) | NotEqual(_, _), _, _)
+                if a.as_str().is_some() || b.as_str().is_some() =>
+            {
+                Ok(match self {
+                    Equal(_, _) => Equal(Box::new(a), Box::new(b)),
+                    _ => NotEqual(Box::new(a), Box::new(b)),
+                })
+            }
+            (_, Str(_), _) => Err(InvalidOperand {
+                expression: self.clone(),
+                operand: a_original.clone(),
+            }),
+
+            // Arithmetic operations on equations are applied to both sides.
+            (Sum(_, _) | Difference(_, _) | Product(_, _) | Quotient(_, _) | Power(_, _), _, _)
+                if (matches!(a, Equal(_, _)) || matches!(b, Equal(_, _)))
+                    && [&a, &b].iter().all(|x| {
+                        matches!(x, Equal(_, _))
+                            || matches!(x.typ(), Num(_, _) | Arithmetic | Unknown)
+                    }) =>
+            {
+                let operation: fn(_, _) -> _ = match self {
+                    Sum(_, _) => Sum,
+                    Difference(_, _) => Difference,
+                    Product(_, _) => Product,
+                    Quotient(_, _) => Quotient,
+                    Power(_, _) => Power,
+                    _ => unreachable!(),
+                };
+
+                let sides = |x: Self| match x {
+                    Equal(lhs, rhs) => (lhs, rhs),
+                    x => (Box::new(x.clone()), Box::new(x)),
+                };
+
+                let (a_lhs, a_rhs) = sides(a);
+                let (b_lhs, b_rhs) = sides(b);
+
+                Ok(Equal(
+                    Box::new(operation(a_lhs, b_lhs)),
+                    Box::new(operation(a_rhs, b_rhs)),
+                ))
+            }
+
+            (_, _, Str(_)) => Err(InvalidOperand {
+                expression: self.clone(),
+                operand: b_original.clone(),
+            }),
+
             (
                 Sum(_, _)
                 | Difference(_, _)
@@ -159,15 +1218,20 @@ impl Expression {
                 Bool(_),
                 _,
             )
+            | (Remainder(_, _), Quat(_, _, _, _) | Residue(_, _) | Ival(_, _), _)
             | (
                 LessThan(_, _)
                 | LessThanOrEqual(_, _)
                 | GreaterThan(_, _)
                 | GreaterThanOrEqual(_, _),
-                Mat(_) | Bool(_),
+                Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Bool(_),
                 _,
             )
-            | (And(_, _) | Or(_, _), Num(_, _) | Mat(_) | Arithmetic, _) => Err(InvalidOperand {
+            | (
+                And(_, _) | Or(_, _),
+                Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Ival(_, _) | Arithmetic,
+                _,
+            ) => Err(InvalidOperand {
                 expression: self.clone(),
                 operand: a_original.clone(),
             }),
@@ -182,53 +1246,334 @@ impl Expression {
                 _,
                 Bool(_),
             )
+            | (Remainder(_, _) | Power(_, _), _, Quat(_, _, _, _) | Residue(_, _) | Ival(_, _))
             | (
                 LessThan(_, _)
                 | LessThanOrEqual(_, _)
                 | GreaterThan(_, _)
                 | GreaterThanOrEqual(_, _),
                 _,
-                Mat(_) | Bool(_),
+                Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Bool(_),
             )
-            | (And(_, _) | Or(_, _), _, Num(_, _) | Mat(_) | Arithmetic) => Err(InvalidOperand {
+            | (
+                And(_, _) | Or(_, _),
+                _,
+                Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Ival(_, _) | Arithmetic,
+            ) => Err(InvalidOperand {
                 expression: self.clone(),
                 operand: b_original.clone(),
             }),
 
-            (Sum(_, _) | Difference(_, _) | Equal(_, _) | NotEqual(_, _), Num(_, _), Mat(_))
-            | (Sum(_, _) | Difference(_, _) | Equal(_, _) | NotEqual(_, _), Mat(_), Num(_, _))
-            | (Equal(_, _) | NotEqual(_, _), Num(_, _) | Mat(_), Bool(_))
-            | (Equal(_, _) | NotEqual(_, _), Bool(_), Num(_, _) | Mat(_)) => {
-                Err(IncompatibleOperands {
-                    expression: self.clone(),
-                    operand_1: a_original.clone(),
-                    operand_2: b_original.clone(),
-                })
+            (
+                LessThan(_, _)
+                | LessThanOrEqual(_, _)
+                | GreaterThan(_, _)
+                | GreaterThanOrEqual(_, _)
+                | And(_, _)
+                | Or(_, _),
+                Ten(_),
+                _,
+            ) => Err(InvalidOperand {
+                expression: self.clone(),
+                operand: a_original.clone(),
+            }),
+            (
+                LessThan(_, _)
+                | LessThanOrEqual(_, _)
+                | GreaterThan(_, _)
+                | GreaterThanOrEqual(_, _)
+                | And(_, _)
+                | Or(_, _),
+                _,
+                Ten(_),
+            ) => Err(InvalidOperand {
+                expression: self.clone(),
+                operand: b_original.clone(),
+            }),
+
+            #[cfg(feature = "matrices")]
+            (Equal(_, _) | NotEqual(_, _), Ten(a), Ten(b)) if a.shape() == b.shape() => {
+                let (comparison, connective): (fn(_, _) -> _, fn(_, _) -> _) = match self {
+                    Equal(_, _) => (Equal, And),
+                    NotEqual(_, _) => (NotEqual, Or),
+                    _ => unreachable!(),
+                };
+
+                Ok(a.elements()
+                    .iter()
+                    .zip(b.elements())
+                    .map(|(x, y)| comparison(Box::new(x.clone()), Box::new(y.clone())))
+                    .reduce(|x, y| connective(Box::new(x), Box::new(y)))
+                    .unwrap_or(Boolean(matches!(self, Equal(_, _)))))
             }
 
+            // Arithmetic on tensors is performed elementwise, with scalars
+            // being combined with every element.
+            #[cfg(feature = "matrices")]
             (
                 Sum(_, _)
                 | Difference(_, _)
                 | Product(_, _)
                 | Quotient(_, _)
                 | Remainder(_, _)
-                | Power(_, _)
-                | Equal(_, _)
-                | NotEqual(_, _)
-                | LessThan(_, _)
-                | LessThanOrEqual(_, _)
-                | GreaterThan(_, _)
-                | GreaterThanOrEqual(_, _),
-                Num(a, a_representation),
-                Num(b, b_representation),
+                | Power(_, _),
+                Ten(_),
+                _,
+            )
+            | (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Remainder(_, _)
+                | Power(_, _),
+                _,
+                Ten(_),
             ) => {
-                let representation = a_representation.merge(b_representation);
+                let operation: fn(_, _) -> _ = match self {
+                    Sum(_, _) => Sum,
+                    Difference(_, _) => Difference,
+                    Product(_, _) => Product,
+                    Quotient(_, _) => Quotient,
+                    Remainder(_, _) => Remainder,
+                    Power(_, _) => Power,
+                    _ => unreachable!(),
+                };
 
-                match self {
-                    Sum(_, _) => Ok(Complex(a + b, representation)),
-                    Difference(_, _) => Ok(Complex(a - b, representation)),
-                    Product(_, _) => Ok(Complex(a * b, representation)),
-                    Quotient(_, _) | Remainder(_, _) => {
+                let apply =
+                    |x: &Self, y: &Self| operation(Box::new(x.clone()), Box::new(y.clone()));
+
+                match (a.typ(), b.typ()) {
+                    (Ten(a), Ten(b)) => a.zip_map(&b, apply),
+                    (Ten(a), Num(_, _) | Residue(_, _) | Ival(_, _) | Arithmetic | Unknown) => {
+                        Some(a.map(|x| apply(x, &b)))
+                    }
+                    (Num(_, _) | Residue(_, _) | Ival(_, _) | Arithmetic | Unknown, Ten(b)) => {
+                        Some(b.map(|y| apply(&a, y)))
+                    }
+                    _ => None,
+                }
+                .map(Tensor)
+                .ok_or_else(|| IncompatibleOperands {
+                    expression: self.clone(),
+                    operand_1: a_original.clone(),
+                    operand_2: b_original.clone(),
+                })
+            }
+
+            (
+                Equal(_, _) | NotEqual(_, _),
+                Ten(_),
+                Fun(_, _)
+                | Num(_, _)
+                | Mat(_)
+                | Ten(_)
+                | Quat(_, _, _, _)
+                | Residue(_, _)
+                | Bool(_),
+            )
+            | (
+                Equal(_, _) | NotEqual(_, _),
+                Fun(_, _) | Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Bool(_),
+                Ten(_),
+            )
+            | (
+                Sum(_, _) | Difference(_, _) | Equal(_, _) | NotEqual(_, _),
+                Num(_, _) | Quat(_, _, _, _) | Residue(_, _),
+                Mat(_),
+            )
+            | (
+                Sum(_, _) | Difference(_, _) | Equal(_, _) | NotEqual(_, _),
+                Mat(_),
+                Num(_, _) | Quat(_, _, _, _) | Residue(_, _),
+            )
+            | (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _),
+                Quat(_, _, _, _),
+                Residue(_, _),
+            )
+            | (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _),
+                Residue(_, _),
+                Quat(_, _, _, _),
+            )
+            | (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _),
+                Ival(_, _),
+                Mat(_) | Quat(_, _, _, _) | Residue(_, _),
+            )
+            | (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _),
+                Mat(_) | Quat(_, _, _, _) | Residue(_, _),
+                Ival(_, _),
+            )
+            | (
+                Equal(_, _) | NotEqual(_, _),
+                Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Ival(_, _),
+                Bool(_),
+            )
+            | (
+                Equal(_, _) | NotEqual(_, _),
+                Bool(_),
+                Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Ival(_, _),
+            ) => Err(IncompatibleOperands {
+                expression: self.clone(),
+                operand_1: a_original.clone(),
+                operand_2: b_original.clone(),
+            }),
+
+            (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Power(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _)
+                | LessThan(_, _)
+                | LessThanOrEqual(_, _)
+                | GreaterThan(_, _)
+                | GreaterThanOrEqual(_, _),
+                Ival(_, _),
+                Ival(_, _) | Num(_, _),
+            )
+            | (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _)
+                | LessThan(_, _)
+                | LessThanOrEqual(_, _)
+                | GreaterThan(_, _)
+                | GreaterThanOrEqual(_, _),
+                Num(_, _),
+                Ival(_, _),
+            ) => {
+                let (x, x_representation) = crate::interval::Interval::from_type(a.typ())
+                    .ok_or_else(|| InvalidOperand {
+                        expression: self.clone(),
+                        operand: a_original.clone(),
+                    })?;
+
+                let (y, y_representation) = crate::interval::Interval::from_type(b.typ())
+                    .ok_or_else(|| InvalidOperand {
+                        expression: self.clone(),
+                        operand: b_original.clone(),
+                    })?;
+
+                let representation = x_representation.merge(y_representation);
+
+                // Comparisons whose result depends on the numbers
+                // chosen from the intervals remain unevaluated.
+                let comparison = |result: Option<bool>, operation: fn(_, _) -> _| {
+                    Ok(result.map(Boolean).unwrap_or_else(|| {
+                        operation(Box::new(a_evaluated.clone()), Box::new(b_evaluated.clone()))
+                    }))
+                };
+
+                match self {
+                    Sum(_, _) => Ok(x.add(&y).to_expression(representation)),
+                    Difference(_, _) => Ok(x.subtract(&y).to_expression(representation)),
+                    Product(_, _) => Ok(x.multiply(&y).to_expression(representation)),
+                    Quotient(_, _) => x
+                        .divide(&y)
+                        .map(|result| result.to_expression(representation))
+                        .ok_or_else(|| DivisionByZero {
+                            expression: self.clone(),
+                            dividend: a_original.clone(),
+                            // The divisor interval contains zero, but isn't necessarily zero.
+                            divisor: match b.typ() {
+                                Ival(_, _) => b_evaluated.clone(),
+                                _ => b_original.clone(),
+                            },
+                        }),
+                    Power(_, _) => {
+                        let n = match b.typ() {
+                            Num(n, _) if n.im.is_zero() && n.re.is_integer() => {
+                                n.re.to_integer().to_i32()
+                            }
+                            _ => None,
+                        }
+                        .ok_or_else(|| InvalidOperand {
+                            expression: self.clone(),
+                            operand: b_original.clone(),
+                        })?;
+
+                        let bound = x.lower().abs().max(x.upper().abs());
+
+                        options
+                            .check_digits(
+                                magnitude_bits(&bound.into()) as f64
+                                    * f64::from(n).abs()
+                                    * std::f64::consts::LOG10_2,
+                            )
+                            .map_err(|e| e.into_error(self))?;
+
+                        x.power(n)
+                            .map(|result| result.to_expression(representation))
+                            .ok_or_else(|| DivisionByZero {
+                                expression: self.clone(),
+                                dividend: int(1),
+                                divisor: a_original.clone(),
+                            })
+                    }
+                    Equal(_, _) => Ok(Boolean(x == y)),
+                    NotEqual(_, _) => Ok(Boolean(x != y)),
+                    LessThan(_, _) => comparison(x.less_than(&y), LessThan),
+                    LessThanOrEqual(_, _) => comparison(x.less_than_or_equal(&y), LessThanOrEqual),
+                    GreaterThan(_, _) => comparison(y.less_than(&x), GreaterThan),
+                    GreaterThanOrEqual(_, _) => {
+                        comparison(y.less_than_or_equal(&x), GreaterThanOrEqual)
+                    }
+                    _ => unreachable!(),
+                }
+            }
+
+            (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Remainder(_, _)
+                | Power(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _)
+                | LessThan(_, _)
+                | LessThanOrEqual(_, _)
+                | GreaterThan(_, _)
+                | GreaterThanOrEqual(_, _),
+                Num(a, a_representation),
+                Num(b, b_representation),
+            ) => {
+                let representation = a_representation.merge(b_representation);
+
+                match self {
+                    Sum(_, _) => Ok(Complex(a + b, representation)),
+                    Difference(_, _) => Ok(Complex(a - b, representation)),
+                    Product(_, _) => Ok(Complex(a * b, representation)),
+                    Quotient(_, _) | Remainder(_, _) => {
                         if b.is_zero() {
                             Err(DivisionByZero {
                                 expression: self.clone(),
@@ -248,19 +1593,40 @@ impl Expression {
                     }
                     Power(_, _) => {
                         if a.is_zero() && b.is_zero() {
-                            Err(ZeroToThePowerOfZero {
-                                expression: self.clone(),
-                                base: a_original.clone(),
-                                exponent: b_original.clone(),
-                            })
-                        } else if let Some(b) = b.to_i32() {
-                            Ok(Complex(a.powi(b), representation))
+                            match options.zero_to_the_power_of_zero {
+                                ZeroPowerConvention::Undefined => Err(ZeroToThePowerOfZero {
+                                    expression: self.clone(),
+                                    base: a_original.clone(),
+                                    exponent: b_original.clone(),
+                                }),
+                                ZeroPowerConvention::One => {
+                                    Ok(Complex(num::Complex::one(), representation))
+                                }
+                            }
+                        } else if a.is_zero() {
+                            if b.re.is_positive() {
+                                Ok(Complex(a, representation))
+                            } else if b.re.is_negative() {
+                                Err(DivisionByZero {
+                                    expression: self.clone(),
+                                    dividend: int(1),
+                                    divisor: a_original.clone(),
+                                })
+                            } else {
+                                Err(InvalidOperand {
+                                    expression: self.clone(),
+                                    operand: b_original.clone(),
+                                })
+                            }
                         } else {
-                            // TODO
-                            Ok(Power(
-                                Box::new(a_evaluated.clone()),
-                                Box::new(b_evaluated.clone()),
-                            ))
+                            Ok(self
+                                .evaluate_number_power(&a, &b, representation, options)?
+                                .unwrap_or_else(|| {
+                                    Power(
+                                        Box::new(a_evaluated.clone()),
+                                        Box::new(b_evaluated.clone()),
+                                    )
+                                }))
                         }
                     }
                     Equal(_, _) => Ok(Boolean(a == b)),
@@ -296,6 +1662,171 @@ impl Expression {
                 }
             }
 
+            (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _),
+                Residue(_, _) | Num(_, _),
+                Residue(_, _) | Num(_, _),
+            ) => {
+                let m = match (a.typ(), b.typ()) {
+                    (Residue(_, m_a), Residue(_, m_b)) if m_a != m_b => {
+                        return Err(IncompatibleOperands {
+                            expression: self.clone(),
+                            operand_1: a_original.clone(),
+                            operand_2: b_original.clone(),
+                        });
+                    }
+                    (Residue(_, m), _) | (_, Residue(_, m)) => m,
+                    _ => unreachable!(),
+                };
+
+                let a = residue(a.typ(), &m).ok_or_else(|| InvalidOperand {
+                    expression: self.clone(),
+                    operand: a_original.clone(),
+                })?;
+
+                let b = residue(b.typ(), &m).ok_or_else(|| InvalidOperand {
+                    expression: self.clone(),
+                    operand: b_original.clone(),
+                })?;
+
+                match self {
+                    Sum(_, _) => Ok(modulo((a + b).mod_floor(&m), m)),
+                    Difference(_, _) => Ok(modulo((a - b).mod_floor(&m), m)),
+                    Product(_, _) => Ok(modulo((a * b).mod_floor(&m), m)),
+                    Quotient(_, _) => match modular_inverse(&b, &m) {
+                        Some(b) => Ok(modulo((a * b).mod_floor(&m), m)),
+                        None => Err(NotInvertible {
+                            expression: self.clone(),
+                            operand: b_original.clone(),
+                        }),
+                    },
+                    Equal(_, _) => Ok(Boolean(a == b)),
+                    NotEqual(_, _) => Ok(Boolean(a != b)),
+                    _ => unreachable!(),
+                }
+            }
+
+            (Power(_, _), Residue(n, m), Num(e, _)) => {
+                if !e.im.is_zero() || !e.re.is_integer() {
+                    Err(InvalidOperand {
+                        expression: self.clone(),
+                        operand: b_original.clone(),
+                    })
+                } else if n.is_zero()
+                    && e.is_zero()
+                    && options.zero_to_the_power_of_zero == ZeroPowerConvention::Undefined
+                {
+                    Err(ZeroToThePowerOfZero {
+                        expression: self.clone(),
+                        base: a_original.clone(),
+                        exponent: b_original.clone(),
+                    })
+                } else {
+                    let e = e.re.to_integer();
+
+                    let n = if e.is_negative() {
+                        modular_inverse(&n, &m).ok_or_else(|| NotInvertible {
+                            expression: self.clone(),
+                            operand: a_original.clone(),
+                        })?
+                    } else {
+                        n
+                    };
+
+                    Ok(modulo(n.modpow(&e.abs(), &m), m))
+                }
+            }
+
+            (
+                Sum(_, _)
+                | Difference(_, _)
+                | Product(_, _)
+                | Quotient(_, _)
+                | Equal(_, _)
+                | NotEqual(_, _),
+                Quat(_, _, _, _) | Num(_, _),
+                Quat(_, _, _, _) | Num(_, _),
+            ) => {
+                let [a1, b1, c1, d1] = quaternion_components(a.typ());
+                let [a2, b2, c2, d2] = quaternion_components(b.typ());
+
+                match self {
+                    Sum(_, _) => Ok(quat(a1 + a2, b1 + b2, c1 + c2, d1 + d2)),
+                    Difference(_, _) => Ok(quat(a1 - a2, b1 - b2, c1 - c2, d1 - d2)),
+                    Product(_, _) => Ok(hamilton_product([a1, b1, c1, d1], [a2, b2, c2, d2])),
+                    Quotient(_, _) => {
+                        if [&a2, &b2, &c2, &d2].iter().all(|x| is_zero(x)) {
+                            Err(DivisionByZero {
+                                expression: self.clone(),
+                                dividend: a_original.clone(),
+                                divisor: b_original.clone(),
+                            })
+                        } else {
+                            let norm_squared = a2.clone() * a2.clone()
+                                + b2.clone() * b2.clone()
+                                + c2.clone() * c2.clone()
+                                + d2.clone() * d2.clone();
+
+                            Ok(hamilton_product(
+                                [a1, b1, c1, d1],
+                                [
+                                    a2 / norm_squared.clone(),
+                                    -b2 / norm_squared.clone(),
+                                    -c2 / norm_squared.clone(),
+                                    -d2 / norm_squared,
+                                ],
+                            ))
+                        }
+                    }
+                    Equal(_, _) | NotEqual(_, _) => {
+                        let (comparison, connective): (fn(_, _) -> _, fn(_, _) -> _) = match self {
+                            Equal(_, _) => (Equal, And),
+                            NotEqual(_, _) => (NotEqual, Or),
+                            _ => unreachable!(),
+                        };
+
+                        Ok([(a1, a2), (b1, b2), (c1, c2), (d1, d2)]
+                            .iter()
+                            .map(|(x, y)| comparison(Box::new(x.clone()), Box::new(y.clone())))
+                            .reduce(|x, y| connective(Box::new(x), Box::new(y)))
+                            .unwrap())
+                    }
+                    _ => unreachable!(),
+                }
+            }
+
+            (Power(_, _), Quat(_, _, _, _), Num(n, _)) if n.im.is_zero() && n.re.is_integer() => {
+                let n = n.re.to_integer();
+
+                if n.is_zero() {
+                    if quaternion_components(a.typ()).iter().all(is_zero)
+                        && options.zero_to_the_power_of_zero == ZeroPowerConvention::Undefined
+                    {
+                        Err(ZeroToThePowerOfZero {
+                            expression: self.clone(),
+                            base: a_original.clone(),
+                            exponent: b_original.clone(),
+                        })
+                    } else {
+                        Ok(quat(int(1), int(0), int(0), int(0)))
+                    }
+                } else if n.is_negative() {
+                    Ok(pow(int(1) / a, Integer(-n)))
+                } else if n.is_one() {
+                    Ok(a)
+                } else if n.is_even() {
+                    Ok(pow(a.clone() * a, Integer(n / 2)))
+                } else {
+                    Ok(pow(a.clone(), Integer(n - 1)) * a)
+                }
+            }
+
+            #[cfg(feature = "matrices")]
             (Sum(_, _) | Difference(_, _), Mat(a), Mat(b)) => {
                 if a.shape() == b.shape() {
                     Ok(Matrix(match self {
@@ -304,40 +1835,184 @@ impl Expression {
                         _ => unreachable!(),
                     }))
                 } else {
-                    Err(IncompatibleOperands {
+                    Err(IncompatibleShapes {
+                        expression: self.clone(),
+                        operand_1: a_original.clone(),
+                        operand_2: b_original.clone(),
+                        shape_1: a.shape(),
+                        shape_2: b.shape(),
+                    })
+                }
+            }
+
+            #[cfg(feature = "matrices")]
+            (Equal(_, _) | NotEqual(_, _), Mat(a), Mat(b)) => {
+                // Vectors and matrices of different shapes are not considered unequal,
+                // but incomparable, consistent with comparing them to scalars.
+                if a.shape() == b.shape() {
+                    let (comparison, connective): (fn(_, _) -> _, fn(_, _) -> _) = match self {
+                        Equal(_, _) => (Equal, And),
+                        NotEqual(_, _) => (NotEqual, Or),
+                        _ => unreachable!(),
+                    };
+
+                    Ok(a.iter()
+                        .zip(b.iter())
+                        .map(|(x, y)| comparison(Box::new(x.clone()), Box::new(y.clone())))
+                        .reduce(|x, y| connective(Box::new(x), Box::new(y)))
+                        .unwrap_or(Boolean(matches!(self, Equal(_, _)))))
+                } else {
+                    Err(IncompatibleShapes {
                         expression: self.clone(),
                         operand_1: a_original.clone(),
                         operand_2: b_original.clone(),
+                        shape_1: a.shape(),
+                        shape_2: b.shape(),
                     })
                 }
             }
 
+            #[cfg(feature = "matrices")]
             (Product(_, _), Mat(a), Mat(b)) => {
                 if a.is_empty() && b.is_empty() {
                     Ok(Matrix(a))
                 } else if !a.is_empty() && !b.is_empty() && a.ncols() == b.nrows() {
-                    Ok(Matrix(crate::expression::Matrix::from_fn(
-                        a.nrows(),
-                        b.ncols(),
-                        |i, j| {
-                            (0..a.ncols())
-                                .map(|k| a[(i, k)].clone() * b[(k, j)].clone())
-                                .reduce(|a, b| a + b)
-                                .unwrap()
-                        },
-                    )))
+                    check_matrix_product(&a, &b, options).map_err(|e| e.into_error(self))?;
+
+                    Ok(Matrix(matrix_product(&a, &b)))
                 } else {
-                    Err(IncompatibleOperands {
+                    Err(IncompatibleShapes {
                         expression: self.clone(),
                         operand_1: a_original.clone(),
                         operand_2: b_original.clone(),
+                        shape_1: a.shape(),
+                        shape_2: b.shape(),
                     })
                 }
             }
 
+            #[cfg(feature = "matrices")]
             (Product(_, _), Mat(a), _) => Ok(Matrix(a.map(|element| element * b.clone()))),
+            #[cfg(feature = "matrices")]
             (Product(_, _), _, Mat(b)) => Ok(Matrix(b.map(|element| a.clone() * element))),
 
+            (
+                Quotient(_, _) | Remainder(_, _),
+                Num(_, _) | Mat(_) | Quat(_, _, _, _) | Residue(_, _),
+                Mat(_),
+            )
+            | (Remainder(_, _), Mat(_), Num(_, _))
+            | (Power(_, _), Num(_, _) | Mat(_), Mat(_)) => Err(IncompatibleOperands {
+                expression: self.clone(),
+                operand_1: a_original.clone(),
+                operand_2: b_original.clone(),
+            }),
+
+            #[cfg(feature = "matrices")]
+            (Quotient(_, _), Mat(a), _) => {
+                if is_zero(&b) {
+                    Err(DivisionByZero {
+                        expression: self.clone(),
+                        dividend: a_original.clone(),
+                        divisor: b_original.clone(),
+                    })
+                } else {
+                    Ok(Matrix(a.map(|element| element / b.clone())))
+                }
+            }
+
+            #[cfg(feature = "matrices")]
+            (Power(_, _), Mat(a), Num(n, _)) => {
+                if !n.im.is_zero() || !n.re.is_integer() {
+                    Err(InvalidOperand {
+                        expression: self.clone(),
+                        operand: b_original.clone(),
+                    })
+                } else if !a.is_square() {
+                    Err(InvalidOperand {
+                        expression: self.clone(),
+                        operand: a_original.clone(),
+                    })
+                } else {
+                    let n = n.re.to_integer();
+
+                    if n.is_zero() {
+                        Ok(Matrix(crate::expression::Matrix::from_fn(
+                            a.nrows(),
+                            a.ncols(),
+                            |i, j| int(if i == j { 1 } else { 0 }),
+                        )))
+                    } else if n.is_negative() {
+                        // TODO
+                        Ok(Power(
+                            Box::new(a_evaluated.clone()),
+                            Box::new(b_evaluated.clone()),
+                        ))
+                    } else {
+                        check_matrix_product(&a, &a, options).map_err(|e| e.into_error(self))?;
+
+                        // Only the growth of numeric elements can be estimated.
+                        let bits = a
+                            .iter()
+                            .map(|element| match element.typ() {
+                                Num(z, _) => Some(magnitude_bits(&z)),
+                                _ => None,
+                            })
+                            .collect::<Option<Vec<_>>>();
+
+                        if let (Some(bits), Some(exponent)) = (bits, n.to_f64()) {
+                            let bits = bits.into_iter().max().unwrap_or(0) as f64
+                                + (a.nrows() as f64).log2();
+                            let digits = exponent * bits * std::f64::consts::LOG10_2;
+
+                            options
+                                .check_digits(digits)
+                                .and_then(|_| {
+                                    options.check(
+                                        Resource::Memory,
+                                        (a.len() as f64) * digits * std::f64::consts::LOG2_10 / 8.0,
+                                    )
+                                })
+                                .map_err(|e| e.into_error(self))?;
+                        }
+
+                        // Exponentiation by squaring, with the intermediate results
+                        // evaluated to prevent the size of the elements from exploding.
+                        let evaluate = |m: crate::expression::Matrix| {
+                            Matrix(m)
+                                .evaluate_fully(context, options)
+                                .map(|m| match m.typ() {
+                                    Mat(m) => m,
+                                    _ => unreachable!(),
+                                })
+                        };
+
+                        let mut result = None;
+                        let mut base = a;
+                        let mut n = n;
+
+                        loop {
+                            if n.is_odd() {
+                                result = Some(match result {
+                                    Some(result) => evaluate(matrix_product(&result, &base))?,
+                                    None => base.clone(),
+                                });
+                            }
+
+                            n >>= 1;
+
+                            if n.is_zero() {
+                                break;
+                            }
+
+                            base = evaluate(matrix_product(&base, &base))?;
+                        }
+
+                        Ok(Matrix(result.unwrap()))
+                    }
+                }
+            }
+
             (Equal(_, _), Bool(Some(a)), Bool(Some(b))) => Ok(Boolean(a == b)),
             (NotEqual(_, _), Bool(Some(a)), Bool(Some(b))) => Ok(Boolean(a != b)),
             (And(_, _), Bool(Some(a)), Bool(Some(b))) => Ok(Boolean(a && b)),
@@ -365,45 +2040,168 @@ impl Expression {
                 | Integer(_)
                 | Rational(_, _)
                 | Complex(_, _)
-                | Vector(_)
+                | Quaternion(_, _, _, _)
+                | Mod(_, _)
+                | Interval(_, _)
+                | Boolean(_)
+                | String(_)
+                | Negation(_)
+                | Not(_)
+                | Lambda(_, _),
+                _,
+                _,
+            ) => unreachable!(),
+            #[cfg(feature = "matrices")]
+            (
+                Vector(_)
                 | VectorElement(_, _)
                 | Matrix(_)
                 | MatrixElement(_, _, _)
-                | Boolean(_)
-                | Negation(_)
-                | Not(_),
+                | Tensor(_)
+                | TensorElement(_, _),
                 _,
                 _,
             ) => unreachable!(),
         }
     }
 
-    /// Returns the result of performing a single evaluation step on the expression,
-    /// or an error if the expression cannot be evaluated. The `context` argument
-    /// can be used to set the values of variables by their identifiers.
-    fn evaluate_step(&self, context: &HashMap<String, Self>) -> Result<Self, Error> {
-        use crate::expression::Expression::*;
-        use crate::expression::Type::{
-            Boolean as Bool, Function as Fun, Matrix as Mat, Number as Num,
-        };
-        use Error::*;
+    /// Returns the result of raising the non-zero number `a` to the power `b`,
+    /// where `self` is the power expression being evaluated, `None` if the power
+    /// should be left unevaluated, or an error if the result would exceed the limits.
+    ///
+    /// This is a separate function rather than part of `evaluate_step_binary`
+    /// to keep the stack frame of that recursive function small.
+    #[allow(clippy::result_large_err)]
+    fn evaluate_number_power(
+        &self,
+        a: &Complex,
+        b: &Complex,
+        representation: RationalRepresentation,
+        options: &Options,
+    ) -> Result<Option<Self>, Error> {
+        if b.im.is_zero() && b.re.is_integer() {
+            let n = b.re.to_integer();
+
+            if is_unit(a) {
+                // Powers of 1, -1, i, and -i repeat with period 4.
+                let n = n
+                    .mod_floor(&crate::expression::Integer::from(4))
+                    .to_i32()
+                    .unwrap();
+                return Ok(Some(Self::Complex(a.powi(n), representation)));
+            }
 
-        let expression = self.simplify();
+            // Numbers other than units grow or shrink by at least one bit
+            // with each factor, so exponents beyond the range of `i32`
+            // always exceed the default limits.
+            options
+                .check_digits(
+                    magnitude_bits(a).max(1) as f64
+                        * n.abs().to_f64().unwrap_or(f64::INFINITY)
+                        * std::f64::consts::LOG10_2,
+                )
+                .map_err(|e| e.into_error(self))?;
+
+            Ok(Some(Self::Complex(integer_power(a, &n), representation)))
+        } else if let Some(root) = perfect_root(a, b) {
+            // a ^ (p/q) = (a ^ (1/q)) ^ p
+            let p = b.re.numer();
+
+            if !root.is_one() {
+                options
+                    .check_digits(
+                        magnitude_bits(&root).max(1) as f64
+                            * p.abs().to_f64().unwrap_or(f64::INFINITY)
+                            * std::f64::consts::LOG10_2,
+                    )
+                    .map_err(|e| e.into_error(self))?;
+            }
 
-        match &expression {
-            Variable(identifier) => context
-                .get(identifier)
-                .map_or_else(|| Ok(expression), |x| x.evaluate_step(context)),
-            Function(_, _) => Ok(expression),
-            FunctionValue(function, arguments) => {
-                let function_original = function;
+            Ok(Some(Self::Complex(integer_power(&root, p), representation)))
+        } else {
+            if (a.im.is_zero() || a.re.is_zero()) && b.im.is_zero() {
+                // The rational part of an exact power grows
+                // like an integer power with the same magnitude.
+                options
+                    .check_digits(
+                        magnitude_bits(a) as f64
+                            * b.re.abs().to_f64().unwrap_or(f64::INFINITY)
+                            * std::f64::consts::LOG10_2,
+                    )
+                    .map_err(|e| e.into_error(self))?;
+            }
 
-                let function = function.evaluate_step(context)?;
+            Ok(complex_power(a, b, representation))
+        }
+    }
+
+    /// Returns the result of performing a single evaluation step on the expression,
+    /// or an error if the expression cannot be evaluated. The `context` argument
+    /// can be used to set the values of variables by their identifiers.
+    /// Reports the step to the observer set in the options, if any.
+    #[allow(clippy::result_large_err)]
+    fn evaluate_step(
+        &self,
+        context: &HashMap<String, Self>,
+        options: &Options,
+    ) -> Result<Self, Error> {
+        options.record_step(self)?;
+
+        match &options.observer {
+            Some(observer) => {
+                observer(Event::Enter(self));
+
+                let result = self.evaluate_step_unobserved(context, options);
+
+                if let Ok(expression) = &result {
+                    if expression != self {
+                        observer(Event::Rewrite(self, expression));
+                    }
+                }
+
+                result
+            }
+            None => self.evaluate_step_unobserved(context, options),
+        }
+    }
+
+    /// Returns the result of performing a single evaluation step on the expression,
+    /// or an error if the expression cannot be evaluated, without reporting
+    /// the step to the observer. The `context` argument can be used to set
+    /// the values of variables by their identifiers.
+    #[allow(clippy::result_large_err)]
+    fn evaluate_step_unobserved(
+        &self,
+        context: &HashMap<String, Self>,
+        options: &Options,
+    ) -> Result<Self, Error> {
+        use crate::expression::Expression::*;
+        use crate::expression::Type::{
+            Arithmetic, Boolean as Bool, Function as Fun, Interval as Ival, Matrix as Mat,
+            Mod as Residue, Number as Num, Quaternion as Quat, String as Str, Tensor as Ten,
+            Unknown,
+        };
+        use Error::*;
+
+        let expression = self.simplify_step();
+
+        match &expression {
+            Variable(identifier) => match context.get(identifier) {
+                // Constants are bound to themselves.
+                Some(Variable(value)) if value == identifier => Ok(expression),
+                Some(x) => x.evaluate_step(context, options),
+                None => Ok(expression),
+            },
+            Function(_, _) | Lambda(_, _) => Ok(expression),
+            FunctionValue(function, arguments) => {
+                let function_original = function;
+
+                let function = function.evaluate_step(context, options)?;
 
                 let mut arguments_evaluated = Vec::new();
 
                 for argument in arguments {
-                    arguments_evaluated.push(argument.evaluate_step(context)?);
+                    arguments_evaluated.push(argument.evaluate_step(context, options)?);
                 }
 
                 match function.typ() {
@@ -412,7 +2210,7 @@ impl Expression {
                         operand: *function_original.clone(),
                     }),
 
-                    Fun(_, f) => f(&expression, &arguments_evaluated, context),
+                    Fun(_, f) => f(&expression, &arguments_evaluated, context, options),
 
                     _ => Ok(FunctionValue(Box::new(function), arguments_evaluated)),
                 }
@@ -428,21 +2226,23 @@ impl Expression {
             } else {
                 expression
             }),
+            #[cfg(feature = "matrices")]
             Vector(v) => {
                 let mut elements = Vec::new();
 
                 for element in v.iter() {
-                    elements.push(element.evaluate_step(context)?);
+                    elements.push(element.evaluate_step(context, options)?);
                 }
 
                 Ok(Vector(crate::expression::Vector::from_vec(elements)))
             }
+            #[cfg(feature = "matrices")]
             VectorElement(vector, i) => {
                 let vector_original = vector;
                 let i_original = i;
 
-                let vector = vector.evaluate_step(context)?;
-                let i = i.evaluate_step(context)?;
+                let vector = vector.evaluate_step(context, options)?;
+                let i = i.evaluate_step(context, options)?;
 
                 match (vector.typ(), i.typ()) {
                     (Num(_, _) | Bool(_), _) => Err(InvalidOperand {
@@ -455,6 +2255,10 @@ impl Expression {
                         operand: *i_original.clone(),
                     }),
 
+                    (Ten(tensor), Num(_, _)) => {
+                        tensor_element(&expression, vector_original, &tensor, &[(&**i_original, i)])
+                    }
+
                     (Mat(vector), Num(i, _)) => {
                         if vector.ncols() != 1 {
                             Err(InvalidOperand {
@@ -482,6 +2286,7 @@ impl Expression {
                     _ => Ok(VectorElement(Box::new(vector), Box::new(i))),
                 }
             }
+            #[cfg(feature = "matrices")]
             Matrix(m) => {
                 let mut columns = Vec::new();
 
@@ -489,7 +2294,7 @@ impl Expression {
                     let mut elements = Vec::new();
 
                     for element in column.iter() {
-                        elements.push(element.evaluate_step(context)?);
+                        elements.push(element.evaluate_step(context, options)?);
                     }
 
                     columns.push(crate::expression::Vector::from_vec(elements));
@@ -503,14 +2308,15 @@ impl Expression {
                     Matrix(crate::expression::Matrix::from_columns(&columns))
                 })
             }
+            #[cfg(feature = "matrices")]
             MatrixElement(matrix, i, j) => {
                 let matrix_original = matrix;
                 let i_original = i;
                 let j_original = j;
 
-                let matrix = matrix.evaluate_step(context)?;
-                let i = i.evaluate_step(context)?;
-                let j = j.evaluate_step(context)?;
+                let matrix = matrix.evaluate_step(context, options)?;
+                let i = i.evaluate_step(context, options)?;
+                let j = j.evaluate_step(context, options)?;
 
                 match (matrix.typ(), i.typ(), j.typ()) {
                     (Num(_, _) | Bool(_), _, _) => Err(InvalidOperand {
@@ -528,6 +2334,13 @@ impl Expression {
                         operand: *j_original.clone(),
                     }),
 
+                    (Ten(tensor), Num(_, _), Num(_, _)) => tensor_element(
+                        &expression,
+                        matrix_original,
+                        &tensor,
+                        &[(&**i_original, i), (&**j_original, j)],
+                    ),
+
                     (Mat(matrix), Num(i, _), Num(j, _)) => {
                         if let Some(i) = i.to_usize() {
                             if let Some(j) = j.to_usize() {
@@ -563,52 +2376,290 @@ impl Expression {
                     _ => Ok(MatrixElement(Box::new(matrix), Box::new(i), Box::new(j))),
                 }
             }
-            Boolean(_) => Ok(expression),
-            Negation(a) => expression.evaluate_step_unary(a, context),
-            Not(a) => expression.evaluate_step_unary(a, context),
-            Sum(a, b) => expression.evaluate_step_binary(a, b, context),
-            Difference(a, b) => expression.evaluate_step_binary(a, b, context),
-            Product(a, b) => expression.evaluate_step_binary(a, b, context),
-            Quotient(a, b) => expression.evaluate_step_binary(a, b, context),
-            Remainder(a, b) => expression.evaluate_step_binary(a, b, context),
-            Power(a, b) => expression.evaluate_step_binary(a, b, context),
-            Equal(a, b) => expression.evaluate_step_binary(a, b, context),
-            NotEqual(a, b) => expression.evaluate_step_binary(a, b, context),
-            LessThan(a, b) => expression.evaluate_step_binary(a, b, context),
-            LessThanOrEqual(a, b) => expression.evaluate_step_binary(a, b, context),
-            GreaterThan(a, b) => expression.evaluate_step_binary(a, b, context),
-            GreaterThanOrEqual(a, b) => expression.evaluate_step_binary(a, b, context),
-            And(a, b) => expression.evaluate_step_binary(a, b, context),
-            Or(a, b) => expression.evaluate_step_binary(a, b, context),
+            #[cfg(feature = "matrices")]
+            Tensor(t) => {
+                let mut elements = Vec::new();
+
+                for element in t.elements() {
+                    elements.push(element.evaluate_step(context, options)?);
+                }
+
+                Ok(Tensor(
+                    crate::expression::Tensor::new(t.shape().to_vec(), elements).unwrap(),
+                ))
+            }
+            #[cfg(feature = "matrices")]
+            TensorElement(tensor, indices) => {
+                let tensor_original = tensor;
+                let indices_original = indices;
+
+                let tensor = tensor.evaluate_step(context, options)?;
+
+                let mut indices = Vec::new();
+
+                for index in indices_original {
+                    indices.push(index.evaluate_step(context, options)?);
+                }
+
+                if let Some((index, _)) = indices_original
+                    .iter()
+                    .zip(&indices)
+                    .find(|(_, index)| matches!(index.typ(), Mat(_) | Ten(_) | Bool(_)))
+                {
+                    return Err(InvalidOperand {
+                        expression: expression.clone(),
+                        operand: index.clone(),
+                    });
+                }
+
+                match tensor.typ() {
+                    Ten(t) if indices.iter().all(|index| matches!(index.typ(), Num(_, _))) => {
+                        tensor_element(
+                            &expression,
+                            tensor_original,
+                            &t,
+                            &indices_original.iter().zip(indices).collect::<Vec<_>>(),
+                        )
+                    }
+
+                    Ten(_) | Unknown | Arithmetic => Ok(TensorElement(Box::new(tensor), indices)),
+
+                    _ => Err(InvalidOperand {
+                        expression: expression.clone(),
+                        operand: *tensor_original.clone(),
+                    }),
+                }
+            }
+            Quaternion(a, b, c, d) => Ok(quat(
+                a.evaluate_step(context, options)?,
+                b.evaluate_step(context, options)?,
+                c.evaluate_step(context, options)?,
+                d.evaluate_step(context, options)?,
+            )),
+            Mod(n, m) => {
+                let n_original = n;
+                let m_original = m;
+
+                let n = n.evaluate_step(context, options)?;
+                let m = m.evaluate_step(context, options)?;
+
+                match (n.typ(), m.typ()) {
+                    (_, Num(m, _))
+                        if !m.im.is_zero() || !m.re.is_integer() || !m.re.is_positive() =>
+                    {
+                        Err(InvalidOperand {
+                            expression: expression.clone(),
+                            operand: *m_original.clone(),
+                        })
+                    }
+
+                    (_, Mat(_) | Quat(_, _, _, _) | Residue(_, _) | Ival(_, _) | Bool(_))
+                    | (_, Fun(_, _)) => Err(InvalidOperand {
+                        expression: expression.clone(),
+                        operand: *m_original.clone(),
+                    }),
+
+                    (
+                        Mat(_)
+                        | Quat(_, _, _, _)
+                        | Residue(_, _)
+                        | Ival(_, _)
+                        | Bool(_)
+                        | Fun(_, _),
+                        _,
+                    ) => Err(InvalidOperand {
+                        expression: expression.clone(),
+                        operand: *n_original.clone(),
+                    }),
+
+                    (Num(n, representation), Num(m, _)) => {
+                        let m = m.re.to_integer();
+
+                        residue(Num(n, representation), &m)
+                            .map(|n| modulo(n, m))
+                            .ok_or_else(|| InvalidOperand {
+                                expression: expression.clone(),
+                                operand: *n_original.clone(),
+                            })
+                    }
+
+                    _ => Ok(Mod(Box::new(n), Box::new(m))),
+                }
+            }
+            Interval(a, b) => {
+                let a_original = a;
+                let b_original = b;
+
+                let a = a.evaluate_step(context, options)?;
+                let b = b.evaluate_step(context, options)?;
+
+                let is_real = |typ| match typ {
+                    Num(z, _) => z.im.is_zero(),
+                    Arithmetic | Unknown => true,
+                    Fun(_, _)
+                    | Mat(_)
+                    | Ten(_)
+                    | Quat(_, _, _, _)
+                    | Residue(_, _)
+                    | Ival(_, _)
+                    | Bool(_)
+                    | Str(_) => false,
+                };
+
+                if !is_real(a.typ()) {
+                    Err(InvalidOperand {
+                        expression: expression.clone(),
+                        operand: *a_original.clone(),
+                    })
+                } else if !is_real(b.typ()) {
+                    Err(InvalidOperand {
+                        expression: expression.clone(),
+                        operand: *b_original.clone(),
+                    })
+                } else {
+                    match (a.typ(), b.typ()) {
+                        // The bounds of an interval must be in ascending order.
+                        (Num(x, _), Num(y, _)) if x.re > y.re => Err(InvalidOperand {
+                            expression: expression.clone(),
+                            operand: *b_original.clone(),
+                        }),
+                        _ => Ok(Interval(Box::new(a), Box::new(b))),
+                    }
+                }
+            }
+            Boolean(_) | String(_) => Ok(expression),
+            Negation(a) => expression.evaluate_step_unary(a, context, options),
+            Not(a) => expression.evaluate_step_unary(a, context, options),
+            Sum(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            Difference(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            Product(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            Quotient(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            Remainder(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            Power(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            Equal(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            NotEqual(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            LessThan(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            LessThanOrEqual(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            GreaterThan(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            GreaterThanOrEqual(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            And(a, b) => expression.evaluate_step_binary(a, b, context, options),
+            Or(a, b) => expression.evaluate_step_binary(a, b, context, options),
         }
     }
 
     /// Returns the result of evaluating the expression, or an error
     /// if the expression cannot be evaluated. The `context` argument
     /// can be used to set the values of variables by their identifiers.
+    #[allow(clippy::result_large_err)]
     pub fn evaluate(&self, context: HashMap<String, Self>) -> Result<Self, Error> {
-        let mut default_context = HashMap::new();
+        self.evaluate_with_options(context, &Options::default())
+    }
 
-        default_context.insert(
-            "i".to_owned(),
-            Expression::Complex(Complex::i(), RationalRepresentation::Fraction),
-        );
+    /// Returns the result of evaluating the expression using the given options,
+    /// or an error if the expression cannot be evaluated. The `context` argument
+    /// can be used to set the values of variables by their identifiers.
+    #[allow(clippy::result_large_err)]
+    pub fn evaluate_with_options(
+        &self,
+        context: HashMap<String, Self>,
+        options: &Options,
+    ) -> Result<Self, Error> {
+        self.evaluate_in_complete_context(&complete_context(context, options), options)
+    }
 
-        for function in functions() {
-            default_context.insert(
-                function.metadata.name.to_owned(),
-                Expression::Function(function.metadata.name.to_owned(), function.implementation),
-            );
-        }
+    /// Returns the result of evaluating the expression using the given options,
+    /// or an error if the expression cannot be evaluated. Unlike `evaluate_with_options`,
+    /// this function expects a context that already contains the default variables
+    /// and functions, as returned by `complete_context`.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn evaluate_in_complete_context(
+        &self,
+        context: &HashMap<String, Self>,
+        options: &Options,
+    ) -> Result<Self, Error> {
+        // Nested evaluations (e.g. by built-in functions) consume
+        // the resources of the evaluation they are part of.
+        let options = &Options {
+            budget: Some(options.budget.clone().unwrap_or_else(|| {
+                Rc::new(Budget {
+                    steps: Cell::new(0),
+                    start: Instant::now(),
+                })
+            })),
+            ..options.clone()
+        };
 
-        for (identifier, expression) in context {
-            default_context.insert(identifier, expression);
+        let result = self
+            .evaluate_fully(context, options)
+            .map_err(|error| match error {
+                // Limits on entire evaluations are reported for the evaluated expression,
+                // rather than for the subexpression at which the limit was reached.
+                Error::ResourceLimitExceeded {
+                    resource: resource @ (Resource::Steps | Resource::Time),
+                    limit,
+                    estimate,
+                    ..
+                } => Error::ResourceLimitExceeded {
+                    expression: self.clone(),
+                    resource,
+                    limit,
+                    estimate,
+                },
+                error => error,
+            });
+
+        if let (Some(observer), Err(error)) = (&options.observer, &result) {
+            observer(Event::Error(error));
         }
 
+        result
+    }
+
+    /// Returns the identifiers of all variables in the expression
+    /// that are not bound by default (such as `i` and the names
+    /// of built-in functions), i.e. the variables whose values
+    /// must be provided in the context when evaluating the expression.
+    pub fn free_variables(&self) -> BTreeSet<String> {
+        let default_context = default_context(&Options::default());
+
+        let mut variables = BTreeSet::new();
+        unbound_variables(self, &mut variables);
+
+        variables
+            .into_iter()
+            .filter(|identifier| !default_context.contains_key(identifier))
+            .collect()
+    }
+
+    /// Returns a function with the given identifier and parameters that,
+    /// when called, evaluates to the body with each parameter replaced by
+    /// the corresponding argument. Setting a variable in the context to such
+    /// a function makes it callable from expressions, e.g. with `f` set to the
+    /// function with parameter `x` and body `x ^ 2`, `f(3)` evaluates to `9`.
+    /// Variables in the body that are not parameters are looked up in
+    /// the context in which the function is called.
+    pub fn user_function(
+        identifier: impl Into<String>,
+        parameters: Vec<String>,
+        body: Expression,
+    ) -> Self {
+        Expression::Function(identifier.into(), lambda(&parameters, &body))
+    }
+
+    /// Returns the result of performing evaluation steps on the expression
+    /// until it doesn't change anymore, or an error if the expression cannot
+    /// be evaluated. Unlike `evaluate`, this function doesn't add the default
+    /// variables and functions to the context.
+    #[allow(clippy::result_large_err)]
+    fn evaluate_fully(
+        &self,
+        context: &HashMap<String, Self>,
+        options: &Options,
+    ) -> Result<Self, Error> {
         let mut old_expression = self.clone();
 
         loop {
-            let new_expression = old_expression.evaluate_step(&default_context)?;
+            let new_expression = old_expression.evaluate_step(context, options)?;
 
             if new_expression == old_expression {
                 return Ok(new_expression);
@@ -619,181 +2670,1330 @@ impl Expression {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+/// Returns the element or subtensor of the given tensor at the given leading
+/// indices, each of which is given both as it appears in `expression` and
+/// as the number it evaluates to, or an error if an index is invalid.
+#[allow(clippy::result_large_err)]
+#[cfg(feature = "matrices")]
+fn tensor_element(
+    expression: &Expression,
+    tensor_original: &Expression,
+    tensor: &Tensor,
+    indices: &[(&Expression, Expression)],
+) -> Result<Expression, Error> {
+    use Error::*;
+
+    if indices.len() > tensor.rank() {
+        return Err(InvalidOperand {
+            expression: expression.clone(),
+            operand: tensor_original.clone(),
+        });
+    }
 
-    use crate::expression::Expression;
+    let mut numbers = Vec::new();
 
-    #[track_caller]
-    fn t(expression: &str, result: &str) {
-        assert_eq!(
-            expression
-                .parse::<Expression>()
-                .unwrap()
-                .evaluate(HashMap::new())
-                .unwrap()
-                .to_string(),
-            result,
-        );
+    for ((index_original, index), size) in indices.iter().zip(tensor.shape()) {
+        let number = match index.typ() {
+            Type::Number(z, _) if z.im.is_zero() && z.re.is_integer() => z.re.to_usize(),
+            _ => None,
+        }
+        .ok_or_else(|| InvalidOperand {
+            expression: expression.clone(),
+            operand: (*index_original).clone(),
+        })?;
+
+        if number >= *size {
+            return Err(IndexOutOfBounds {
+                expression: expression.clone(),
+                vector_or_matrix: tensor_original.clone(),
+                index: (*index_original).clone(),
+            });
+        }
+
+        numbers.push(number);
+    }
+
+    Ok(tensor.slice(&numbers).unwrap().into_expression())
+}
+
+/// Returns the given rational number as a floating point number,
+/// or `None` if it cannot be represented as a finite floating point number.
+fn to_f64(x: &Rational) -> Option<f64> {
+    x.to_f64().filter(|x| x.is_finite())
+}
+
+/// Returns the given finite floating point number as a rational number,
+/// rounded to 15 significant digits to avoid showing floating point noise.
+pub(crate) fn from_f64(x: f64) -> Rational {
+    let string = format!("{:.14e}", x);
+    let (mantissa, exponent) = string.split_once('e').unwrap();
+    let (integer_part, fractional_part) = mantissa.split_once('.').unwrap();
+
+    let mantissa = Rational::new(
+        format!("{}{}", integer_part, fractional_part)
+            .parse()
+            .unwrap(),
+        Integer::from(10).pow(fractional_part.len() as u32),
+    );
+
+    let exponent = exponent.parse::<i32>().unwrap();
+
+    mantissa * Rational::from_integer(Integer::from(10)).pow(exponent)
+}
+
+/// Returns the principal value of `a ^ b` for a non-zero complex number `a`
+/// and a non-integer complex number `b`, or `None` if the value is neither
+/// exactly representable nor numerically computable, in which case the power
+/// should be left unevaluated.
+///
+/// The principal value is `exp(b * log(a))`, where `log` is the principal
+/// branch of the complex logarithm, whose imaginary part (the argument of `a`)
+/// lies in the interval `(-pi, pi]`. The branch cut thus lies along the negative
+/// real axis, with negative real numbers having argument `pi`. Consequently,
+/// `(-1) ^ (1/2)` is `i` rather than `-i`.
+///
+/// Powers that are exact (rational powers of positive rational numbers
+/// that are perfect powers, and half-integer powers of negative rational
+/// numbers) are returned in exact form. Other rational powers of negative
+/// rational numbers in fraction representation are returned as products
+/// of surds and powers of -1, e.g. `(-27) ^ (1/3)` is `3 * (-1) ^ (1/3)`,
+/// which are approximated by `N`. Rational powers of positive rational
+/// numbers that are not perfect powers are returned as `None`, since they
+/// are best represented by the unevaluated power itself. Half-integer powers
+/// of imaginary rational numbers are returned in exact form as well, e.g.
+/// `i ^ (1/2)` is `(1/2 + 1/2 * i) * sqrt(2)`. All other powers of exact
+/// numbers are returned as `None` and left to `N` to approximate, while
+/// powers involving decimal numbers are approximated numerically. Without
+/// the `complex` feature, powers whose values are not real are returned
+/// as `None`.
+fn complex_power(
+    a: &Complex,
+    b: &Complex,
+    representation: RationalRepresentation,
+) -> Option<Expression> {
+    if cfg!(not(feature = "complex")) && !(a.im.is_zero() && b.im.is_zero() && !a.re.is_negative())
+    {
+        return None;
+    }
+
+    if b.im.is_zero() && a.im.is_zero() {
+        let p = b.re.numer().to_i32();
+        let q = b.re.denom().to_u32();
+
+        if let (Some(p), Some(q)) = (p, q) {
+            if a.re.is_positive() {
+                return Surd::root(&a.re, p, q)
+                    .filter(|root| {
+                        root.is_rational() || representation == RationalRepresentation::Fraction
+                    })
+                    .map(|root| root.to_expression(representation));
+            } else if q == 2 {
+                // (-x) ^ (p/2) = exp(i * pi * p/2) * x ^ (p/2) = i ^ p * x ^ (p/2)
+                let factor = Complex::i().powi(p);
+                let root = Surd::root(&-a.re.clone(), p, 2).filter(|root| {
+                    root.is_rational() || representation == RationalRepresentation::Fraction
+                })?;
+                let factor = factor * root.coefficient();
+
+                return Some(if root.is_rational() {
+                    Expression::Complex(factor, representation)
+                } else {
+                    Expression::Complex(factor, representation) * root.radical()
+                });
+            } else if representation == RationalRepresentation::Fraction {
+                // (-x) ^ (p/q) = (-1) ^ (p/q) * x ^ (p/q), where (-1) ^ (p/q) = exp(i * pi * p/q)
+                // has period 2 in p/q and changes its sign when p/q changes by 1,
+                // so p can be reduced to the range (0, q).
+                let q_signed = q as i32;
+                let (negative, p_reduced) = match p.rem_euclid(2 * q_signed) {
+                    p if p > q_signed => (true, p - q_signed),
+                    p => (false, p),
+                };
+
+                let root = Surd::root(&-a.re.clone(), p, q)?;
+                let root = if negative { root.negate() } else { root };
+
+                let unit = pow(
+                    Expression::Rational(-Rational::one(), representation),
+                    Expression::Rational(Rational::new(p_reduced.into(), q.into()), representation),
+                );
+
+                return Some(if root.is_rational() {
+                    let coefficient = root.coefficient();
+
+                    if coefficient.is_one() {
+                        unit
+                    } else if (-coefficient).is_one() {
+                        -unit
+                    } else {
+                        Expression::Complex(coefficient.clone().into(), representation) * unit
+                    }
+                } else {
+                    root.to_expression(representation) * unit
+                });
+            }
+        } else if a.re.is_positive() {
+            return None;
+        }
+    } else if b.im.is_zero()
+        && a.re.is_zero()
+        && b.re.denom() == &Integer::from(2)
+        && representation == RationalRepresentation::Fraction
+    {
+        // (c * i) ^ (p/2) = |c| ^ (p/2) * exp(i * pi * sign(c) * p/4),
+        // where p is odd, so the second factor is (±1 ± i) / sqrt(2).
+        if let Some(p) = b.re.numer().to_i32() {
+            let k = if a.im.is_positive() { p } else { -p };
+
+            let unit = match k.rem_euclid(8) {
+                1 => Complex::new(Rational::one(), Rational::one()),
+                3 => Complex::new(-Rational::one(), Rational::one()),
+                5 => Complex::new(-Rational::one(), -Rational::one()),
+                _ => Complex::new(Rational::one(), -Rational::one()),
+            };
+
+            let root = Surd::root(
+                &(a.im.abs().pow(p) / Rational::from_integer(2.into())),
+                1,
+                2,
+            )?;
+            let factor = unit * root.coefficient();
+
+            return Some(if root.is_rational() {
+                Expression::Complex(factor, representation)
+            } else {
+                Expression::Complex(factor, representation) * root.radical()
+            });
+        }
+    }
+
+    if representation == RationalRepresentation::Fraction {
+        return None;
+    }
+
+    let [a_re, a_im, b_re, b_im] = [&a.re, &a.im, &b.re, &b.im].map(to_f64);
+    let (a_re, a_im, b_re, b_im) = (a_re?, a_im?, b_re?, b_im?);
+
+    let log_abs = a_re.hypot(a_im).ln();
+    let arg = a_im.atan2(a_re);
+
+    let re = b_re * log_abs - b_im * arg;
+    let im = b_re * arg + b_im * log_abs;
+
+    let abs = re.exp();
+
+    if !abs.is_finite() || abs == 0.0 {
+        return None;
+    }
+
+    // Parts that are negligible relative to the absolute value
+    // are floating point noise (e.g. `sin(pi)`) and are set to zero.
+    let part = |x: f64| {
+        if x.abs() < abs * 1e-15 {
+            Rational::zero()
+        } else {
+            from_f64(x)
+        }
+    };
+
+    Some(Expression::Complex(
+        Complex::new(part(abs * im.cos()), part(abs * im.sin())),
+        RationalRepresentation::Decimal,
+    ))
+}
+
+/// Returns the number of bits by which the numerators and denominators
+/// of a power of the given number grow (at most) with each factor.
+fn magnitude_bits(z: &Complex) -> u64 {
+    let x = z.re.abs() + z.im.abs();
+
+    x.numer().bits().max(x.denom().bits()).saturating_sub(1)
+}
+
+/// Returns the `q`th root of the positive rational number `a` if `b` is a real
+/// number with denominator `q` and the numerator and denominator of `a` are perfect
+/// `q`th powers, or `None` otherwise. Unlike `Surd::root`, this works for exponents
+/// and numbers of any size.
+fn perfect_root(a: &Complex, b: &Complex) -> Option<Complex> {
+    if !a.im.is_zero() || !a.re.is_positive() || !b.im.is_zero() {
+        return None;
+    }
+
+    let q = b.re.denom().to_u32()?;
+
+    let root = |n: &Integer| {
+        let root = n.nth_root(q);
+        if root.pow(q) == *n {
+            Some(root)
+        } else {
+            None
+        }
+    };
+
+    Some(Rational::new(root(a.re.numer())?, root(a.re.denom())?).into())
+}
+
+/// Returns whether the number is 1, -1, i, or -i.
+fn is_unit(z: &Complex) -> bool {
+    (z.re.is_zero() || z.im.is_zero()) && z.norm_sqr().is_one()
+}
+
+/// Returns the number raised to the power of the integer `n`, which can be
+/// arbitrarily large. The number must not be zero if `n` is negative.
+fn integer_power(z: &Complex, n: &Integer) -> Complex {
+    let base = if n.is_negative() { z.inv() } else { z.clone() };
+    let n = n.magnitude();
+    let mut result = Complex::one();
+
+    for i in (0..n.bits()).rev() {
+        result = &result * &result;
+
+        if n.bit(i) {
+            result *= &base;
+        }
     }
 
+    result
+}
+
+/// Returns an error if the product of the given matrices
+/// would exceed the resource limits.
+#[cfg(feature = "matrices")]
+fn check_matrix_product(
+    a: &crate::expression::Matrix,
+    b: &crate::expression::Matrix,
+    options: &Options,
+) -> Result<(), LimitExceeded> {
+    let elements = (a.nrows() as f64) * (b.ncols() as f64);
+
+    options.check(Resource::MatrixElements, elements)?;
+
+    // Each element is a sum of products of elements of the factors,
+    // consisting of about `4 * a.ncols()` expression nodes.
+    options.check(
+        Resource::Memory,
+        elements * 4.0 * (a.ncols() as f64) * (std::mem::size_of::<Expression>() as f64),
+    )
+}
+
+/// Returns the product of the given matrices, which must be non-empty
+/// and have compatible shapes.
+#[cfg(feature = "matrices")]
+fn matrix_product(
+    a: &crate::expression::Matrix,
+    b: &crate::expression::Matrix,
+) -> crate::expression::Matrix {
+    crate::expression::Matrix::from_fn(a.nrows(), b.ncols(), |i, j| {
+        (0..a.ncols())
+            .map(|k| a[(i, k)].clone() * b[(k, j)].clone())
+            .reduce(|a, b| a + b)
+            .unwrap()
+    })
+}
+
+/// Returns the real part and `i`, `j`, and `k` components of
+/// the given quaternion or number type.
+fn quaternion_components(typ: Type) -> [Expression; 4] {
+    match typ {
+        Type::Quaternion(a, b, c, d) => [*a, *b, *c, *d],
+        Type::Number(z, representation) => [
+            Expression::Rational(z.re, representation),
+            Expression::Rational(z.im, representation),
+            int(0),
+            int(0),
+        ],
+        _ => unreachable!(),
+    }
+}
+
+/// Returns the Hamilton product of the quaternions with the given components.
+fn hamilton_product(p: [Expression; 4], q: [Expression; 4]) -> Expression {
+    let [a1, b1, c1, d1] = p;
+    let [a2, b2, c2, d2] = q;
+
+    quat(
+        a1.clone() * a2.clone()
+            - b1.clone() * b2.clone()
+            - c1.clone() * c2.clone()
+            - d1.clone() * d2.clone(),
+        a1.clone() * b2.clone() + b1.clone() * a2.clone() + c1.clone() * d2.clone()
+            - d1.clone() * c2.clone(),
+        a1.clone() * c2.clone() - b1.clone() * d2.clone()
+            + c1.clone() * a2.clone()
+            + d1.clone() * b2.clone(),
+        a1 * d2 + b1 * c2 - c1 * b2 + d1 * a2,
+    )
+}
+
+/// Returns the multiplicative inverse of `n` modulo `m` as an integer
+/// in the range `[0, m)`, or `None` if `n` is not invertible modulo `m`.
+pub(crate) fn modular_inverse(n: &Integer, m: &Integer) -> Option<Integer> {
+    let gcd = n.mod_floor(m).extended_gcd(m);
+
+    if gcd.gcd.is_one() {
+        Some(gcd.x.mod_floor(m))
+    } else {
+        None
+    }
+}
+
+/// Returns the representative in the range `[0, m)` of the residue class
+/// modulo `m` of the given residue class or number type, or `None` if the type
+/// has no such representative (e.g. because it is a rational number whose
+/// denominator is not invertible modulo `m`).
+fn residue(typ: Type, m: &Integer) -> Option<Integer> {
+    match typ {
+        Type::Mod(n, _) => Some(n),
+        Type::Number(z, _) if z.im.is_zero() => {
+            let inverse = modular_inverse(z.re.denom(), m)?;
+            Some((z.re.numer() * inverse).mod_floor(m))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if the expression is a number that is zero.
+fn is_zero(expression: &Expression) -> bool {
+    matches!(expression.typ(), Type::Number(z, _) if z.is_zero())
+}
+
+#[cfg(test)]
+// Tests that use built-in functions are skipped if those are disabled.
+#[cfg_attr(not(feature = "functions"), allow(unused_imports, dead_code))]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+
+    use super::{Error::*, Event, Options, Resource, ZeroPowerConvention};
+    use crate::{assert_evaluates_to, assert_evaluation_error, expression::Expression, helpers::*};
+
+    #[cfg(feature = "functions")]
     #[test]
     fn arithmetic() {
-        t("-(-1)", "1");
-        t("-0", "0");
+        assert_evaluates_to!("-(-1)", "1");
+        assert_evaluates_to!("-0", "0");
 
-        t("1 + 2", "3");
-        t("1 + -1", "0");
-        t("1/2 + 0.5", "1");
-        t(
+        assert_evaluates_to!("1 + 2", "3");
+        assert_evaluates_to!("1 + -1", "0");
+        assert_evaluates_to!("1/2 + 0.5", "1");
+        assert_evaluates_to!(
             "123456789987654321 + 987654321123456789",
             "1111111111111111110",
         );
 
-        t("1 - 2", "-1");
-        t("1 - -1", "2");
-        t("1/2 - 0.5", "0");
-        t(
+        assert_evaluates_to!("1 - 2", "-1");
+        assert_evaluates_to!("1 - -1", "2");
+        assert_evaluates_to!("1/2 - 0.5", "0");
+        assert_evaluates_to!(
             "123456789987654321 - 987654321123456789",
             "-864197531135802468",
         );
 
-        t("1 * 2", "2");
-        t("1 * -1", "-1");
-        t("1/2 * 0.5", "0.25");
-        t(
+        assert_evaluates_to!("1 * 2", "2");
+        assert_evaluates_to!("1 * -1", "-1");
+        assert_evaluates_to!("1/2 * 0.5", "0.25");
+        assert_evaluates_to!(
             "123456789987654321 * 987654321123456789",
             "121932632103337905662094193112635269",
         );
 
-        t("1 / 2", "1/2");
-        t("1 / -1", "-1");
-        t("1/2 / 0.5", "1");
-        t(
+        assert_evaluates_to!("1 / 2", "1/2");
+        assert_evaluates_to!("1 / -1", "-1");
+        assert_evaluates_to!("1/2 / 0.5", "1");
+        assert_evaluates_to!(
             "123456789987654321 / 987654321123456789",
             "101010101/808080809",
         );
 
-        t("4 % 2", "0");
-        t("0 % 2", "0");
-        t("5 % 2", "1");
-        t("-5 % 2", "-1");
-        t("-5 % -2", "-1");
-        t("0.75 % (1/4)", "0");
-        t("0.75 % (1/3)", "1/12");
-        t("987654321123456789 % 123456789987654321", "1222222221");
-
-        t("i ^ 2", "-1");
-        t("2 ^ 3", "8");
-        t("2 ^ (-3)", "1/8");
-        t("-2 ^ 4", "-16");
-        t("(-2) ^ 4", "16");
-        t("0.5 ^ 4", "0.0625");
-        t(
+        assert_evaluates_to!("4 % 2", "0");
+        assert_evaluates_to!("0 % 2", "0");
+        assert_evaluates_to!("5 % 2", "1");
+        assert_evaluates_to!("-5 % 2", "-1");
+        assert_evaluates_to!("-5 % -2", "-1");
+        assert_evaluates_to!("0.75 % (1/4)", "0");
+        assert_evaluates_to!("0.75 % (1/3)", "1/12");
+        assert_evaluates_to!("987654321123456789 % 123456789987654321", "1222222221");
+
+        assert_evaluates_to!("15%", "3/20");
+        assert_evaluates_to!("120 * (1 + 15%)", "138");
+        assert_evaluates_to!("10%-3", "1");
+        assert_evaluates_to!("0.5% * 10", "0.05");
+
+        assert_evaluates_to!("i ^ 2", "-1");
+        assert_evaluates_to!("2 ^ 3", "8");
+        assert_evaluates_to!("2 ^ (-3)", "1/8");
+        assert_evaluates_to!("(-1) ^ (10 ^ 20 + 1)", "-1");
+        assert_evaluates_to!("i ^ (10 ^ 20 + 3)", "-i");
+        assert_evaluates_to!("(-i) ^ (-10 ^ 25 - 1)", "i");
+        assert_evaluates_to!("(1/2 + 1/2 * i) ^ 4", "-1/4");
+        assert_evaluates_to!("8 ^ (1/3)", "2");
+        assert_evaluates_to!("16 ^ (3/4)", "8");
+        assert_evaluates_to!("(8/27) ^ (-2/3)", "9/4");
+        assert_evaluates_to!("(3 ^ 100) ^ (7/100)", "2187");
+        assert_evaluates_to!("(2 ^ 100) ^ (1/100)", "2");
+        assert_evaluates_to!("1 ^ ((10 ^ 20 + 1) / 2)", "1");
+        assert_evaluates_to!("-2 ^ 4", "-16");
+        assert_evaluates_to!("(-2) ^ 4", "16");
+        assert_evaluates_to!("0.5 ^ 4", "0.0625");
+        assert_evaluates_to!("2 ^ (1/2)", "sqrt(2)");
+        assert_evaluates_to!("8 ^ (1/2)", "2 * sqrt(2)");
+        assert_evaluates_to!("16 ^ (1/3)", "2 * 2 ^ (1/3)");
+        assert_evaluates_to!("2 ^ (-3/2)", "sqrt(2) / 4");
+        assert_evaluates_to!("2 ^ 0.5", "2 ^ 0.5");
+        assert_evaluates_to!("4 ^ (1/2)", "2");
+        assert_evaluates_to!("(4/9) ^ (-3/2)", "27/8");
+        assert_evaluates_to!("(-1) ^ (1/2)", "i");
+        assert_evaluates_to!("(-1) ^ (-1/2)", "-i");
+        assert_evaluates_to!("(-4) ^ (3/2)", "-8*i");
+        assert_evaluates_to!("(-2) ^ (1/2)", "i * sqrt(2)");
+        assert_evaluates_to!("(-8) ^ (1/3)", "2 * (-1) ^ (1/3)");
+        assert_evaluates_to!("(-27) ^ (1/3)", "3 * (-1) ^ (1/3)");
+        assert_evaluates_to!("(-2) ^ (1/3)", "2 ^ (1/3) * (-1) ^ (1/3)");
+        assert_evaluates_to!("(-8) ^ (4/3)", "-16 * (-1) ^ (1/3)");
+        assert_evaluates_to!("(-8) ^ (-1/3)", "-1/2 * (-1) ^ (2/3)");
+        assert_evaluates_to!("(-1) ^ (5/3)", "-(-1) ^ (2/3)");
+        assert_evaluates_to!("(-8) ^ 0.25", "1.18920711500272 + 1.18920711500272*i");
+        assert_evaluates_to!("i ^ (1/2)", "(1/2 + 1/2*i) * sqrt(2)");
+        assert_evaluates_to!("(-i) ^ (1/2)", "(1/2 - 1/2*i) * sqrt(2)");
+        assert_evaluates_to!("i ^ (-3/2)", "(-1/2 - 1/2*i) * sqrt(2)");
+        assert_evaluates_to!("(2 * i) ^ (1/2)", "1 + i");
+        assert_evaluates_to!("(-8 * i) ^ (3/2)", "-16 - 16*i");
+        assert_evaluates_to!("i ^ (1/3)", "i ^ (1/3)");
+        assert_evaluates_to!("i ^ i", "i ^ i");
+        assert_evaluates_to!("2 ^ (1 + i)", "2 ^ (1 + i)");
+        assert_evaluates_to!("i ^ 0.5", "0.707106781186548 + 0.707106781186547*i");
+        assert_evaluates_to!("0.5 ^ (1 + i)", "0.384619450681986 - 0.319480638156817*i");
+        assert_evaluates_to!("(1 - 1) ^ (1/2)", "0");
+        assert_evaluates_to!(
             "987654321123456789 ^ 5",
             "939777062588963894467852986656442266299580252508947542802086985660852317355013741720482949",
         );
-        t(
+        assert_evaluates_to!(
             "3 ^ 4 ^ 5",
             "373391848741020043532959754184866588225409776783734007750636931722079040617265251229993688938803977220468765065431475158108727054592160858581351336982809187314191748594262580938807019951956404285571818041046681288797402925517668012340617298396574731619152386723046235125934896058590588284654793540505936202376547807442730582144527058988756251452817793413352141920744623027518729185432862375737063985485319476416926263819972887006907013899256524297198527698749274196276811060702333710356481",
         );
     }
 
+    #[cfg(not(feature = "complex"))]
+    #[test]
+    fn real_numbers() {
+        assert_evaluates_to!("4 ^ (1/2)", "2");
+        assert_evaluates_to!("(-1) ^ 3", "-1");
+        assert_evaluates_to!("(-1) ^ (1/2)", "(-1) ^ (1/2)");
+        assert_evaluates_to!("(-8) ^ (1/3)", "(-8) ^ (1/3)");
+        assert_evaluates_to!("(-8) ^ 0.25", "(-8) ^ 0.25");
+        assert_evaluates_to!("i ^ 2", "i ^ 2");
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn error_messages() {
+        let e = |expression: &str| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(HashMap::new())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            e("(2 + 3 * i) < 1"),
+            "cannot compare 2 + 3 * i with < in 2 + 3 * i < 1"
+        );
+        assert_eq!(e("!3"), "cannot apply ! to the integer 3 in !3");
+        assert_eq!(
+            e("[1, 2] + 1"),
+            "cannot add the vector [1, 2] and the integer 1 in [1, 2] + 1",
+        );
+        assert_eq!(
+            e("[1, 2] + [1, 2, 3]"),
+            "cannot add a 2x1 matrix and a 3x1 matrix in [1, 2] + [1, 2, 3]",
+        );
+        assert_eq!(
+            e("1 / (1 - 1)"),
+            "division by zero in 1 / (1 - 1) (1 - 1 is zero)",
+        );
+        assert_eq!(
+            e("interval(1, 2) / interval(-1, 1)"),
+            "division by zero in interval(1, 2) / interval(-1, 1) (interval(-1, 1) contains zero)",
+        );
+        assert_eq!(
+            e("(2 mod 4) ^ (-1)"),
+            "the residue class 2 mod 4 is not invertible in (2 mod 4) ^ (-1)",
+        );
+        assert_eq!(
+            e("[1, 2][5]"),
+            "index 5 is out of bounds for the vector [1, 2] in [1, 2][5]",
+        );
+        assert_eq!(
+            e("factorial(1, 2)"),
+            "factorial takes 1 argument, but 2 were given in factorial(1, 2)",
+        );
+        assert_eq!(e("factorial(-1)"), "invalid argument -1 in factorial(-1)");
+        assert_eq!(
+            e("2 ^ 1000000"),
+            "evaluating 2 ^ 1000000 would require about 301030 digits, exceeding the limit of 100000",
+        );
+        assert_eq!(
+            e("2 ^ (10 ^ 30)"),
+            format!(
+                "evaluating 2 ^ 10 ^ 30 would require more than 2 ^ {} digits, exceeding the limit of 100000",
+                usize::BITS,
+            ),
+        );
+        assert_eq!(
+            e("convert(3 * J, W)"),
+            "cannot convert a quantity of dimension m ^ 2 * kg / s ^ 2 to a unit of dimension \
+             m ^ 2 * kg / s ^ 3 in convert(3 * J, W)",
+        );
+        assert_eq!(
+            e("convert(2 * m, x)"),
+            "cannot convert a quantity of dimension m to a unit of dimension 1 in convert(2 * m, x)",
+        );
+
+        let error: Box<dyn std::error::Error> = Box::new(
+            "1 / 0"
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(HashMap::new())
+                .unwrap_err(),
+        );
+
+        assert_eq!(error.to_string(), "division by zero in 1 / 0 (0 is zero)");
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn error_codes() {
+        let c = |expression: &str, code: &str, suggestion: Option<&str>| {
+            let error = expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(HashMap::new())
+                .unwrap_err();
+
+            assert_eq!(error.code(), code);
+            assert_eq!(error.suggestion().as_deref(), suggestion);
+        };
+
+        c("!3", "E0201", None);
+        c("[1, 2] + 1", "E0202", None);
+        c(
+            "[[1, 2]] * [[1, 2]]",
+            "E0203",
+            Some("the number of columns of the first matrix must equal the number of rows of the second matrix"),
+        );
+        c(
+            "[1, 2] + [1, 2, 3]",
+            "E0203",
+            Some("both matrices must have the same number of rows and columns"),
+        );
+        c("1 / 0", "E0204", None);
+        c(
+            "0 ^ 0",
+            "E0205",
+            Some("set `zero_to_the_power_of_zero` in the evaluation options to `ZeroPowerConvention::One` to define zero to the power of zero as one"),
+        );
+        c(
+            "(2 mod 4) ^ (-1)",
+            "E0206",
+            Some("a residue class is invertible only if its representative is coprime to the modulus"),
+        );
+        c("[1, 2][5]", "E0207", Some("indices start at 0"));
+        c("factorial(1, 2)", "E0208", None);
+        c("factorial(-1)", "E0209", None);
+        c(
+            "2 ^ 1000000",
+            "E0210",
+            Some("increase `max_digits` in the evaluation options to allow this operation"),
+        );
+        c("convert(1 * m, s)", "E0213", None);
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn free_variables() {
+        let f = |expression: &str| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .free_variables()
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(f("1 + 2"), Vec::<String>::new());
+        assert_eq!(f("i * x + y ^ x"), vec!["x", "y"]);
+        assert_eq!(f("det([[a, b], [c, d]])"), vec!["a", "b", "c", "d"]);
+        assert_eq!(f("f(x, is_prime(n))"), vec!["f", "n", "x"]);
+        assert_eq!(f("v[k] && !b"), vec!["b", "k", "v"]);
+        assert_eq!(f("2 * pi * r + e ^ x"), vec!["r", "x"]);
+    }
+
+    #[test]
+    fn lambdas() {
+        assert_evaluates_to!("(x -> x ^ 2)(3)", "9");
+        assert_evaluates_to!("((x, y) -> x - y)(5, 2)", "3");
+        assert_evaluates_to!("(() -> 1 + 1)()", "2");
+        assert_evaluates_to!("((x -> y -> x + y)(1))(2)", "3");
+        assert_evaluates_to!("(x -> y -> x + y)(y)", "y1 -> y + y1");
+        assert_evaluates_to!("(x -> x -> x)(1)", "x -> x");
+        assert_evaluates_to!("(f -> f(f(2)))(x -> x * 3)", "18");
+        assert_evaluates_to!("(x -> x ^ 2)(a + 1)", "(a + 1) ^ 2");
+
+        assert_evaluation_error!(
+            "(x -> x)(1, 2)",
+            InvalidNumberOfArguments {
+                min_number: 1,
+                max_number: 1,
+                given_number: 2,
+                ..
+            },
+        );
+
+        assert_eq!(
+            "x -> x + a"
+                .parse::<Expression>()
+                .unwrap()
+                .free_variables()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["a"],
+        );
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn user_functions() {
+        let function = |identifier: &str, parameters: &[&str], body: &str| {
+            (
+                identifier.to_owned(),
+                Expression::user_function(
+                    identifier,
+                    parameters.iter().map(|p| p.to_string()).collect(),
+                    body.parse().unwrap(),
+                ),
+            )
+        };
+
+        let context = HashMap::from([
+            function("f", &["x"], "x ^ 2"),
+            function("g", &["x", "y"], "x - y + c"),
+            function("h", &[], "f(c)"),
+            ("c".to_owned(), int(10)),
+        ]);
+
+        let e = |expression: &str| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(context.clone())
+                .map(|x| x.to_string())
+        };
+
+        assert_eq!(e("f(3)"), Ok("9".to_owned()));
+        assert_eq!(e("f(1/2) + f(2)"), Ok("17/4".to_owned()));
+        #[cfg(feature = "matrices")]
+        assert_eq!(e("f([[1, 1], [0, 1]])"), Ok("[[1, 2], [0, 1]]".to_owned()));
+        assert_eq!(e("g(5, 3)"), Ok("12".to_owned()));
+        assert_eq!(e("g(y, x)"), Ok("y - x + 10".to_owned()));
+        assert_eq!(e("f(f(a))"), Ok("(a ^ 2) ^ 2".to_owned()));
+        assert_eq!(e("h()"), Ok("100".to_owned()));
+        assert!(matches!(
+            e("f(1, 2)"),
+            Err(InvalidNumberOfArguments {
+                min_number: 1,
+                max_number: 1,
+                given_number: 2,
+                ..
+            }),
+        ));
+    }
+
+    #[allow(clippy::result_large_err)]
+    #[cfg(feature = "functions")]
+    #[test]
+    fn options() {
+        let options = Options {
+            zero_to_the_power_of_zero: ZeroPowerConvention::One,
+            ..Options::default()
+        };
+
+        let e = |expression: &str, options: &Options| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate_with_options(HashMap::new(), options)
+                .map(|result| result.to_string())
+        };
+
+        for expression in [
+            "0 ^ 0",
+            "(1 - 1) ^ (2 - 2)",
+            "(0 mod 5) ^ (1 - 1)",
+            "quaternion(0, 0, 0, 0) ^ (1 - 1)",
+        ] {
+            assert!(matches!(
+                e(expression, &Options::default()),
+                Err(ZeroToThePowerOfZero { .. }),
+            ));
+        }
+
+        assert_eq!(e("0 ^ 0", &options), Ok("1".to_owned()));
+        assert_eq!(e("(1 - 1) ^ (2 - 2)", &options), Ok("1".to_owned()));
+        assert_eq!(e("(0 mod 5) ^ (1 - 1)", &options), Ok("1 mod 5".to_owned()));
+        assert_eq!(
+            e("quaternion(0, 0, 0, 0) ^ (1 - 1)", &options),
+            Ok("quaternion(1, 0, 0, 0)".to_owned()),
+        );
+        assert_eq!(e("0.0 ^ 0", &options), Ok("1".to_owned()));
+    }
+
+    #[test]
+    fn observer() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let events_clone = events.clone();
+
+        let options = Options {
+            observer: Some(Rc::new(move |event| {
+                events_clone.borrow_mut().push(match event {
+                    Event::Enter(expression) => format!("enter {}", expression),
+                    Event::Rewrite(a, b) => format!("rewrite {} -> {}", a, b),
+                    Event::Error(error) => format!("error {}", error.code()),
+                })
+            })),
+            ..Options::default()
+        };
+
+        let e = |expression: &str| {
+            events.borrow_mut().clear();
+
+            let _ = expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate_with_options(HashMap::new(), &options);
+
+            events.borrow().clone()
+        };
+
+        assert_eq!(
+            e("(1 + 2) * 3"),
+            [
+                "enter (1 + 2) * 3",
+                "enter 1 + 2",
+                "enter 1",
+                "enter 2",
+                "rewrite 1 + 2 -> 3",
+                "enter 3",
+                "rewrite (1 + 2) * 3 -> 9",
+                // Rewrites also include changes in the internal representation
+                // of values, which don't affect their display form.
+                "enter 9",
+                "rewrite 9 -> 9",
+                "enter 9",
+                "rewrite 9 -> 9",
+                "enter 9",
+            ],
+        );
+        assert_eq!(
+            e("1 / 0"),
+            ["enter 1 / 0", "enter 1", "enter 0", "error E0204"]
+        );
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn sandbox() {
+        let e = |expression: &str, options: &Options| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate_with_options(HashMap::new(), options)
+                .map_err(|error| error.to_string())
+        };
+
+        let options = Options::sandboxed();
+
+        assert_eq!(
+            e("factorial(10) / 2 + sin(pi / 6)", &options),
+            e("factorial(10) / 2 + sin(pi / 6)", &Options::default()),
+        );
+        assert_eq!(
+            e("nth_prime(5)", &options),
+            Ok(fun(var("nth_prime"), [int(5)])),
+        );
+        assert!(e("factorial(1000)", &options).is_err());
+        assert_eq!(
+            e("factor(x^2 - 1)", &options),
+            Ok(fun(var("factor"), [pow(var("x"), int(2)) - int(1)])),
+        );
+
+        // The timeout stops built-in functions that loop internally.
+        let options = Options {
+            max_steps: usize::MAX,
+            timeout: Some(Duration::from_millis(100)),
+            ..Options::sandboxed()
+        };
+
+        assert_eq!(
+            e("sum(k, 1, 10^9, k)", &options),
+            Err(
+                "evaluating sum(k, 1, 10 ^ 9, k) exceeded the limit of 100 milliseconds".to_owned()
+            ),
+        );
+        assert_eq!(
+            e("series(exp(x), x, 0, 10^6)", &options),
+            Err(
+                "evaluating series(exp(x), x, 0, 10 ^ 6) exceeded the limit of 100 milliseconds"
+                    .to_owned()
+            ),
+        );
+
+        let options = Options {
+            max_steps: 10,
+            ..Options::default()
+        };
+
+        assert!(e("1 + 2 + 3", &options).is_ok());
+        assert_eq!(
+            e("1 + 2 + 3 + 4 + 5 + 6", &options),
+            Err(
+                "evaluating 1 + 2 + 3 + 4 + 5 + 6 exceeded the limit of 10 evaluation steps"
+                    .to_owned()
+            ),
+        );
+
+        let options = Options {
+            timeout: Some(Duration::ZERO),
+            ..Options::default()
+        };
+
+        assert_eq!(
+            e("[1, 2, 3] * [[4, 5, 6]]", &options),
+            Err(
+                "evaluating [1, 2, 3] * [[4, 5, 6]] exceeded the limit of 0 milliseconds"
+                    .to_owned()
+            ),
+        );
+    }
+
+    #[allow(clippy::result_large_err)]
+    #[cfg(feature = "functions")]
+    #[test]
+    fn resource_limits() {
+        let options = Options {
+            max_digits: 100,
+            max_matrix_elements: 100,
+            max_memory: 1 << 20,
+            ..Options::default()
+        };
+
+        let e = |expression: &str| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate_with_options(HashMap::new(), &options)
+        };
+
+        assert!(matches!(
+            e("2 ^ 1000"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                limit: 100,
+                estimate: 302,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("(1/3) ^ (-1000)"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("2 ^ (2 ^ 40)"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("binomial(10 ^ 6, 5 * 10 ^ 5)"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("2 ^ (1000001/2)"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("(2/3) ^ (-10 ^ 12)"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("factorial(100)"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                limit: 100,
+                estimate: 158,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("[[1, 1], [1, 0]] ^ 1000"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::Digits,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            e("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11] * [[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]]"),
+            Err(ResourceLimitExceeded {
+                resource: Resource::MatrixElements,
+                limit: 100,
+                estimate: 121,
+                ..
+            }),
+        ));
+
+        assert!(e("2 ^ 300").is_ok());
+        assert!(e("(-1) ^ 1000000").is_ok());
+        assert!(e("1 ^ (10 ^ 100)").is_ok());
+        assert!(e("factorial(60)").is_ok());
+        assert!(e("[[1, 1], [1, 0]] ^ 100").is_ok());
+        assert!(e("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10] * [[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]]").is_ok());
+    }
+
+    #[cfg(feature = "matrices")]
     #[test]
     fn linear_algebra() {
-        t("[1] + [2]", "[3]");
-        t("[1] - [2]", "[-1]");
-        t("[1] * [2]", "[2]");
-        t("[1] * 2", "[2]");
-        t("1 * [2]", "[2]");
-
-        t("[1, 2] + [3, 4]", "[4, 6]");
-        t("[1, 2] - [3, 4]", "[-2, -2]");
-        t("[1, 2] * [[3, 4]]", "[[3, 4], [6, 8]]");
-        t("[[1, 2]] * [3, 4]", "[11]");
-        t("2 * [3, 4]", "[6, 8]");
-        t("[2, 3] * 4", "[8, 12]");
-
-        t(
+        assert_evaluates_to!("[1] + [2]", "[3]");
+        assert_evaluates_to!("[1] - [2]", "[-1]");
+        assert_evaluates_to!("[1] * [2]", "[2]");
+        assert_evaluates_to!("[1] * 2", "[2]");
+        assert_evaluates_to!("1 * [2]", "[2]");
+
+        assert_evaluates_to!("[1, 2] + [3, 4]", "[4, 6]");
+        assert_evaluates_to!("[1, 2] - [3, 4]", "[-2, -2]");
+        assert_evaluates_to!("[1, 2] * [[3, 4]]", "[[3, 4], [6, 8]]");
+        assert_evaluates_to!("[[1, 2]] * [3, 4]", "[11]");
+        assert_evaluates_to!("2 * [3, 4]", "[6, 8]");
+        assert_evaluates_to!("[2, 3] * 4", "[8, 12]");
+
+        assert_evaluates_to!(
             "[[a, b], [c, d], [e, f]] * [[5, 6], [7, 8]]",
             "[[a * 5 + b * 7, a * 6 + b * 8], [c * 5 + d * 7, c * 6 + d * 8], [e * 5 + f * 7, e * 6 + f * 8]]",
         );
+
+        assert_evaluates_to!("[1, 2] / 2", "[1/2, 1]");
+        assert_evaluates_to!("[[2, 4], [6, 8]] / 2", "[[1, 2], [3, 4]]");
+        assert_evaluates_to!("[a, b] / c", "[a / c, b / c]");
+        assert_evaluates_to!("[1, 2] - [1, 2]", "[0, 0]");
+
+        assert_evaluates_to!("[[1, 2], [3, 4]] ^ 0", "[[1, 0], [0, 1]]");
+        assert_evaluates_to!("[[1, 2], [3, 4]] ^ 1", "[[1, 2], [3, 4]]");
+        assert_evaluates_to!("[[1, 2], [3, 4]] ^ 3", "[[37, 54], [81, 118]]");
+        assert_evaluates_to!(
+            "[[1, 1], [1, 0]] ^ 100",
+            "[[573147844013817084101, 354224848179261915075], [354224848179261915075, 218922995834555169026]]",
+        );
+        assert_evaluates_to!("[[1, 2], [3, 4]] * [[1, 2], [3, 4]]", "[[7, 10], [15, 22]]");
+
+        assert_evaluates_to!("[1, 2] == [1, 2]", "true");
+        assert_evaluates_to!("[1, 2] == [1, 3]", "false");
+        assert_evaluates_to!("[1, 2] != [1, 2]", "false");
+        assert_evaluates_to!("[1, 2] != [1, 3]", "true");
+        assert_evaluates_to!("[[1, 2], [3, 4]] == [[1, 2], [3, 2 + 2]]", "true");
+        assert_evaluates_to!("[a, 1] == [b, 1]", "a == b");
+        assert_evaluates_to!("[a, b] != [c, d]", "a != c || b != d");
+        assert_evaluates_to!("[1 / 2, 2] == [0.5, 2]", "true");
+
+        assert_evaluates_to!("[1 + 1, 2 * 3]", "[2, 6]");
+        assert_evaluates_to!("[[1 + 1, 2 ^ 3], [-(-4), 6 / 4]]", "[[2, 8], [4, 3/2]]");
+        assert_evaluates_to!("[[[1 + 1]], [[1 - 1]]]", "[[[2]], [[0]]]");
+        assert_evaluates_to!("[a + 0, b * 1]", "[a, b]");
+
+        let context = HashMap::from([("a".to_owned(), int(2)), ("b".to_owned(), rat(1, 2))]);
+
+        assert_eq!(
+            "[[a, a * b], [b, 1 + a]]"
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(context)
+                .map(|x| x.to_string()),
+            Ok("[[2, 1], [1/2, 3]]".to_owned()),
+        );
+    }
+
+    #[cfg(feature = "matrices")]
+    #[test]
+    fn linear_algebra_errors() {
+        assert_evaluation_error!(
+            "[1, 2] + [1, 2, 3]",
+            IncompatibleShapes {
+                shape_1: (2, 1),
+                shape_2: (3, 1),
+                ..
+            }
+        );
+        assert_evaluation_error!(
+            "[[1, 2]] * [[1, 2]]",
+            IncompatibleShapes {
+                shape_1: (1, 2),
+                shape_2: (1, 2),
+                ..
+            }
+        );
+        assert_evaluation_error!(
+            "[1, 2] == [1, 2, 3]",
+            IncompatibleShapes {
+                shape_1: (2, 1),
+                shape_2: (3, 1),
+                ..
+            }
+        );
+        assert_evaluation_error!("[1, 2] + 1", IncompatibleOperands);
+        assert_evaluation_error!("2 / [1, 2]", IncompatibleOperands);
+        assert_evaluation_error!("[1, 2] / [1, 2]", IncompatibleOperands);
+        assert_evaluation_error!("[1, 2] % 2", IncompatibleOperands);
+        assert_evaluation_error!("2 ^ [1, 2]", IncompatibleOperands);
+        assert_evaluation_error!("[1, 2] / 0", DivisionByZero);
+        assert_evaluation_error!("[1, 2] ^ 2", InvalidOperand);
+        assert_evaluation_error!("[[1, 2], [3, 4]] ^ 0.5", InvalidOperand);
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn quaternions() {
+        assert_evaluates_to!("-quaternion(1, 2, 3, 4)", "quaternion(-1, -2, -3, -4)");
+        assert_evaluates_to!("quaternion(1, 2, 3, 4) + 1", "quaternion(2, 2, 3, 4)");
+        assert_evaluates_to!(
+            "quaternion(1, 2, 3, 4) - quaternion(4, 3, 2, 1)",
+            "quaternion(-3, -1, 1, 3)",
+        );
+        assert_evaluates_to!(
+            "quaternion(0, 1, 0, 0) * quaternion(0, 1, 0, 0)",
+            "quaternion(-1, 0, 0, 0)",
+        );
+        assert_evaluates_to!("quaternion(1, 2, 3, 4) * i", "quaternion(-2, 1, 4, -3)");
+        assert_evaluates_to!("i * quaternion(1, 2, 3, 4)", "quaternion(-2, 1, -4, 3)");
+        assert_evaluates_to!("quaternion(2, 4, 6, 8) / 2", "quaternion(1, 2, 3, 4)");
+        assert_evaluates_to!(
+            "quaternion(1, 2, 3, 4) ^ 5",
+            "quaternion(3916, 1112, 1668, 2224)",
+        );
+        assert_evaluates_to!(
+            "quaternion(1, 1, 1, 1) ^ (0 - 1)",
+            "quaternion(1/4, -1/4, -1/4, -1/4)",
+        );
+        assert_evaluates_to!("quaternion(1, 2, 3, 4) == quaternion(1, 2, 3, 4)", "true");
+        assert_evaluates_to!("quaternion(1, 2, 3, 4) == quaternion(1, 2, 3, 5)", "false");
+        assert_evaluates_to!("quaternion(1, 0, 0, 0) != 1", "false");
     }
 
+    #[test]
+    fn residues() {
+        assert_evaluates_to!("5 mod 7", "5 mod 7");
+        assert_evaluates_to!("12 mod 7", "5 mod 7");
+        assert_evaluates_to!("-1 mod 7", "6 mod 7");
+        assert_evaluates_to!("1/3 mod 7", "5 mod 7");
+        assert_evaluates_to!("-(3 mod 7)", "4 mod 7");
+
+        assert_evaluates_to!("(3 mod 7) + 5", "1 mod 7");
+        assert_evaluates_to!("2 - (3 mod 7)", "6 mod 7");
+        assert_evaluates_to!("(3 mod 7) * (5 mod 7)", "1 mod 7");
+        assert_evaluates_to!("(3 mod 7) / (5 mod 7)", "2 mod 7");
+        assert_evaluates_to!("(3 mod 7) ^ (0 - 1)", "5 mod 7");
+        assert_evaluates_to!("(2 mod 1000000007) ^ 1000000006", "1 mod 1000000007");
+
+        assert_evaluates_to!("(3 mod 7) == 10", "true");
+        assert_evaluates_to!("(3 mod 7) != (4 mod 7)", "true");
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn intervals() {
+        assert_evaluates_to!("interval(1, 1 + 1)", "interval(1, 2)");
+        assert_evaluates_to!("-interval(1, 2)", "interval(-2, -1)");
+        assert_evaluates_to!("interval(1, 2) + 1/2", "interval(3/2, 5/2)");
+        assert_evaluates_to!("interval(-1, 2) * interval(-3, 1)", "interval(-6, 3)");
+        assert_evaluates_to!("interval(1, 2) / interval(-4, -2)", "interval(-1, -1/4)");
+        assert_evaluates_to!("interval(-3, -2) ^ 2", "interval(4, 9)");
+        assert_evaluates_to!("interval(1, 2) ^ (0 - 2)", "interval(1/4, 1)");
+        assert_evaluates_to!("interval(0.5, 1) + 1", "interval(1.5, 2)");
+        assert_evaluates_to!("[interval(1, 2), 3] * 2", "[interval(2, 4), 6]");
+
+        assert_evaluates_to!("interval(1, 2) == interval(1, 2)", "true");
+        assert_evaluates_to!("interval(1, 1) == 1", "true");
+        assert_evaluates_to!("interval(1, 2) != 1", "true");
+        assert_evaluates_to!("interval(1, 2) <= interval(2, 3)", "true");
+        assert_evaluates_to!("interval(2, 3) > 1", "true");
+        assert_evaluates_to!("interval(2, 3) >= 4", "false");
+        assert_evaluates_to!("interval(2, 3) > 5/2", "interval(2, 3) > 5/2");
+
+        assert_evaluation_error!("interval(2, 1)", InvalidArgument);
+        assert_evaluation_error!("interval(i, 1)", InvalidArgument);
+        assert_evaluation_error!("1 / interval(-1, 1)", DivisionByZero);
+        assert_evaluation_error!("interval(-1, 1) ^ (0 - 1)", DivisionByZero);
+        assert_evaluation_error!("2 ^ interval(1, 2)", InvalidOperand);
+        assert_evaluation_error!("interval(1, 2) ^ (1/2)", InvalidOperand);
+        assert_evaluation_error!("interval(1, 2) % 2", InvalidOperand);
+        assert_evaluation_error!("interval(1, 2) + (1 mod 3)", IncompatibleOperands);
+        assert_evaluation_error!("interval(1, 2) + i", InvalidOperand);
+    }
+
+    #[cfg(feature = "matrices")]
     #[test]
     fn indices() {
-        t("[a][0]", "a");
-        t("[a, b, c][2]", "c");
-        t("[1 + 2, 2 + 3, 3 + 4, 4 + 5][1 + 2]", "9");
+        assert_evaluates_to!("[a][0]", "a");
+        assert_evaluates_to!("[a, b, c][2]", "c");
+        assert_evaluates_to!("[1 + 2, 2 + 3, 3 + 4, 4 + 5][1 + 2]", "9");
 
-        t("[[a]][0, 0]", "a");
-        t("[[a, b, c], [d, e, f]][1, 2]", "f");
-        t("[[1 + 2, 2 + 3], [3 + 4, 4 + 5]][0 + 0, 0 + 1]", "5");
+        assert_evaluates_to!("[[a]][0, 0]", "a");
+        assert_evaluates_to!("[[a, b, c], [d, e, f]][1, 2]", "f");
+        assert_evaluates_to!("[[1 + 2, 2 + 3], [3 + 4, 4 + 5]][0 + 0, 0 + 1]", "5");
     }
 
+    #[cfg(feature = "matrices")]
     #[test]
-    fn logic() {
-        t("!true", "false");
-        t("!false", "true");
+    fn tensors() {
+        assert_evaluates_to!(
+            "[[[1, 2], [3, 4]], [[5, 6], [7, 8]]]",
+            "[[[1, 2], [3, 4]], [[5, 6], [7, 8]]]"
+        );
+        assert_evaluates_to!("-[[[1, 2]], [[3, 4]]]", "[[[-1, -2]], [[-3, -4]]]");
+        assert_evaluates_to!(
+            "[[[1, 2]], [[3, 4]]] + [[[a, b]], [[c, d]]]",
+            "[[[1 + a, 2 + b]], [[3 + c, 4 + d]]]"
+        );
+        assert_evaluates_to!(
+            "[[[1, 2]], [[3, 4]]] * [[[5, 6]], [[7, 8]]]",
+            "[[[5, 12]], [[21, 32]]]"
+        );
+        assert_evaluates_to!("[[[1, 2]], [[3, 4]]] / 2", "[[[1/2, 1]], [[3/2, 2]]]");
+        assert_evaluates_to!("10 - [[[1, 2]], [[3, 4]]]", "[[[9, 8]], [[7, 6]]]");
+        assert_evaluates_to!("2 ^ [[[1, 2]], [[3, 4]]]", "[[[2, 4]], [[8, 16]]]");
+        assert_evaluates_to!("[[[1, 2]], [[3, 4]]] == [[[1, 2]], [[3, 4]]]", "true");
+        assert_evaluates_to!("[[[1, 2]], [[3, 4]]] != [[[1, 2]], [[3, 4]]]", "false");
+
+        assert_evaluates_to!(
+            "[[[1, 2], [3, 4]], [[5, 6], [7, 8]]][1]",
+            "[[5, 6], [7, 8]]"
+        );
+        assert_evaluates_to!("[[[1, 2], [3, 4]], [[5, 6], [7, 8]]][1, 0]", "[5, 6]");
+        assert_evaluates_to!("[[[1, 2], [3, 4]], [[5, 6], [7, 8]]][1, 0, 1]", "6");
+        assert_evaluates_to!("[[[1, 2], [3, 4]], [[5, 6], [7, 8]]][0 + 1, 1, 1 - 1]", "7");
+        assert_evaluates_to!("[[[a]]][0, 0, 0]", "a");
+
+        assert_evaluation_error!("[[[1, 2]], [[3, 4]]][2]", IndexOutOfBounds);
+        assert_evaluation_error!("[[[1, 2]], [[3, 4]]][0, 0, 0, 0]", InvalidOperand);
+        assert_evaluation_error!("[[[1, 2]], [[3, 4]]][1/2, 0, 0]", InvalidOperand);
+        assert_evaluation_error!("[[1, 2], [3, 4]][0, 0, 0]", InvalidOperand);
+        assert_evaluation_error!("[[[1, 2]], [[3, 4]]] + [[[1, 2]]]", IncompatibleOperands);
+        assert_evaluation_error!("[[[1, 2]], [[3, 4]]] + [1, 2]", IncompatibleOperands);
+        assert_evaluation_error!("[[[1, 2]], [[3, 4]]] == 1", IncompatibleOperands);
+        assert_evaluation_error!("[[[1, 2]], [[3, 4]]] < 1", InvalidOperand);
+        assert_evaluation_error!("![[[1, 2]], [[3, 4]]]", InvalidOperand);
+    }
 
-        t("true && true", "true");
-        t("true && false", "false");
-        t("false && true", "false");
-        t("false && false", "false");
+    #[test]
+    fn equations() {
+        assert_evaluates_to!("(x == 3) + 2", "x + 2 == 5");
+        assert_evaluates_to!("3 * (x == 2)", "3 * x == 6");
+        assert_evaluates_to!("(x == 2) ^ 2", "x ^ 2 == 4");
+        assert_evaluates_to!("-(x == 3)", "-x == -3");
+        assert_evaluates_to!("(x == 3) + (y == 4)", "x + y == 7");
+        assert_evaluates_to!("(x == 3) - 3", "x - 3 == 0");
+
+        assert_evaluation_error!("(x == 2) + true", InvalidOperand);
+        assert_evaluation_error!("(x == 2) % 2", InvalidOperand);
+        assert_evaluation_error!("(x < 2) + 1", InvalidOperand);
+    }
 
-        t("true || true", "true");
-        t("true || false", "true");
-        t("false || true", "true");
-        t("false || false", "false");
+    #[test]
+    fn logic() {
+        assert_evaluates_to!("!true", "false");
+        assert_evaluates_to!("!false", "true");
+
+        assert_evaluates_to!("true && true", "true");
+        assert_evaluates_to!("true && false", "false");
+        assert_evaluates_to!("false && true", "false");
+        assert_evaluates_to!("false && false", "false");
+
+        assert_evaluates_to!("true || true", "true");
+        assert_evaluates_to!("true || false", "true");
+        assert_evaluates_to!("false || true", "true");
+        assert_evaluates_to!("false || false", "false");
     }
 
     #[test]
     fn comparisons() {
-        t("0 == 0", "true");
-        t("0 == 0.0", "true");
-        t("0.5 == 1/2", "true");
-        t("1/2 == 2/4", "true");
-        t("3 ^ 4 ^ 5 == 5 ^ 4 ^ 3", "false");
-
-        t("0 != 0", "false");
-        t("0 != 0.0", "false");
-        t("0.5 != 1/2", "false");
-        t("1/2 != 2/4", "false");
-        t("3 ^ 4 ^ 5 != 5 ^ 4 ^ 3", "true");
-
-        t("0 < 0", "false");
-        t("0 < 0.0", "false");
-        t("0.5 < 1/2", "false");
-        t("1/2 < 2/4", "false");
-        t("3 ^ 4 ^ 5 < 5 ^ 4 ^ 3", "false");
-
-        t("0 <= 0", "true");
-        t("0 <= 0.0", "true");
-        t("0.5 <= 1/2", "true");
-        t("1/2 <= 2/4", "true");
-        t("3 ^ 4 ^ 5 <= 5 ^ 4 ^ 3", "false");
-
-        t("0 > 0", "false");
-        t("0 > 0.0", "false");
-        t("0.5 > 1/2", "false");
-        t("1/2 > 2/4", "false");
-        t("3 ^ 4 ^ 5 > 5 ^ 4 ^ 3", "true");
-
-        t("0 >= 0", "true");
-        t("0 >= 0.0", "true");
-        t("0.5 >= 1/2", "true");
-        t("1/2 >= 2/4", "true");
-        t("3 ^ 4 ^ 5 >= 5 ^ 4 ^ 3", "true");
-
-        t("true == true", "true");
-        t("true == false", "false");
-        t("false == true", "false");
-        t("false == false", "true");
-
-        t("true != true", "false");
-        t("true != false", "true");
-        t("false != true", "true");
-        t("false != false", "false");
+        assert_evaluates_to!("0 == 0", "true");
+        assert_evaluates_to!("0 == 0.0", "true");
+        assert_evaluates_to!("0.5 == 1/2", "true");
+        assert_evaluates_to!("1/2 == 2/4", "true");
+        assert_evaluates_to!("3 ^ 4 ^ 5 == 5 ^ 4 ^ 3", "false");
+
+        assert_evaluates_to!("0 != 0", "false");
+        assert_evaluates_to!("0 != 0.0", "false");
+        assert_evaluates_to!("0.5 != 1/2", "false");
+        assert_evaluates_to!("1/2 != 2/4", "false");
+        assert_evaluates_to!("3 ^ 4 ^ 5 != 5 ^ 4 ^ 3", "true");
+
+        assert_evaluates_to!("0 < 0", "false");
+        assert_evaluates_to!("0 < 0.0", "false");
+        assert_evaluates_to!("0.5 < 1/2", "false");
+        assert_evaluates_to!("1/2 < 2/4", "false");
+        assert_evaluates_to!("3 ^ 4 ^ 5 < 5 ^ 4 ^ 3", "false");
+
+        assert_evaluates_to!("0 <= 0", "true");
+        assert_evaluates_to!("0 <= 0.0", "true");
+        assert_evaluates_to!("0.5 <= 1/2", "true");
+        assert_evaluates_to!("1/2 <= 2/4", "true");
+        assert_evaluates_to!("3 ^ 4 ^ 5 <= 5 ^ 4 ^ 3", "false");
+
+        assert_evaluates_to!("0 > 0", "false");
+        assert_evaluates_to!("0 > 0.0", "false");
+        assert_evaluates_to!("0.5 > 1/2", "false");
+        assert_evaluates_to!("1/2 > 2/4", "false");
+        assert_evaluates_to!("3 ^ 4 ^ 5 > 5 ^ 4 ^ 3", "true");
+
+        assert_evaluates_to!("0 >= 0", "true");
+        assert_evaluates_to!("0 >= 0.0", "true");
+        assert_evaluates_to!("0.5 >= 1/2", "true");
+        assert_evaluates_to!("1/2 >= 2/4", "true");
+        assert_evaluates_to!("3 ^ 4 ^ 5 >= 5 ^ 4 ^ 3", "true");
+
+        assert_evaluates_to!("true == true", "true");
+        assert_evaluates_to!("true == false", "false");
+        assert_evaluates_to!("false == true", "false");
+        assert_evaluates_to!("false == false", "true");
+
+        assert_evaluates_to!("true != true", "false");
+        assert_evaluates_to!("true != false", "true");
+        assert_evaluates_to!("false != true", "true");
+        assert_evaluates_to!("false != false", "false");
+    }
+
+    #[test]
+    fn strings() {
+        assert_evaluates_to!("\"a\"", "\"a\"");
+        assert_evaluates_to!("\"a\" == \"a\"", "true");
+        assert_evaluates_to!("\"a\" == \"b\"", "false");
+        assert_evaluates_to!("\"a\" != \"b\"", "true");
+        assert_evaluates_to!("\"a\" == x", "\"a\" == x");
+
+        assert_evaluation_error!("-\"a\"", InvalidOperand { .. });
+        assert_evaluation_error!("\"a\" + 1", InvalidOperand { .. });
+        assert_evaluation_error!("\"a\" == 1", IncompatibleOperands { .. });
     }
 }
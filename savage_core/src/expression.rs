@@ -4,13 +4,21 @@
 use std::{collections::HashMap, rc::Rc};
 
 use derivative::*;
-use num::{Signed, Zero};
+use num::{One, Signed, Zero};
 
-use crate::evaluate::Error;
+use crate::evaluate::{lambda, substitute_variables, Error, Options};
+use crate::interval::Interval;
+use crate::print::FormatOptions;
+#[cfg(feature = "matrices")]
+pub use crate::tensor::Tensor;
 
 /// Function implementation.
-pub type Function =
-    dyn Fn(&Expression, &[Expression], &HashMap<String, Expression>) -> Result<Expression, Error>;
+pub type Function = dyn Fn(
+    &Expression,
+    &[Expression],
+    &HashMap<String, Expression>,
+    &Options,
+) -> Result<Expression, Error>;
 
 /// Arbitrary-precision integer.
 pub type Integer = num::bigint::BigInt;
@@ -22,11 +30,25 @@ pub type Rational = num::rational::Ratio<Integer>;
 pub type Complex = num::complex::Complex<Rational>;
 
 /// Column vector with expressions as components.
+#[cfg(feature = "matrices")]
 pub type Vector = nalgebra::DVector<Expression>;
 
 /// Column-major matrix with expressions as components.
+#[cfg(feature = "matrices")]
 pub type Matrix = nalgebra::DMatrix<Expression>;
 
+/// Stand-in for matrices when the `matrices` feature is disabled.
+/// It has no values, so `Type::Matrix` never occurs.
+#[cfg(not(feature = "matrices"))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) enum Matrix {}
+
+/// Stand-in for tensors when the `matrices` feature is disabled.
+/// It has no values, so `Type::Tensor` never occurs.
+#[cfg(not(feature = "matrices"))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) enum Tensor {}
+
 /// Preferred representation when printing a rational number.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum RationalRepresentation {
@@ -71,15 +93,34 @@ pub enum Expression {
     /// Complex number with preferred representation for real and imaginary parts.
     Complex(Complex, RationalRepresentation),
     /// Column vector.
+    #[cfg(feature = "matrices")]
     Vector(Vector),
     /// Element of a column vector expression given by an index expression.
+    #[cfg(feature = "matrices")]
     VectorElement(Box<Self>, Box<Self>),
     /// Column-major matrix.
+    #[cfg(feature = "matrices")]
     Matrix(Matrix),
     /// Element of a column-major matrix expression given by row and column index expressions.
+    #[cfg(feature = "matrices")]
     MatrixElement(Box<Self>, Box<Self>, Box<Self>),
+    /// Array with more than two dimensions.
+    #[cfg(feature = "matrices")]
+    Tensor(Tensor),
+    /// Element or subtensor of a tensor expression given by index expressions
+    /// for the leading dimensions.
+    #[cfg(feature = "matrices")]
+    TensorElement(Box<Self>, Vec<Self>),
+    /// Quaternion with real part and `i`, `j`, and `k` components.
+    Quaternion(Box<Self>, Box<Self>, Box<Self>, Box<Self>),
+    /// Residue class of an integer expression modulo a positive integer expression.
+    Mod(Box<Self>, Box<Self>),
+    /// Closed interval of real numbers between a lower and an upper bound expression.
+    Interval(Box<Self>, Box<Self>),
     /// Boolean value.
     Boolean(bool),
+    /// String of text.
+    String(String),
     /// Arithmetic negation of an expression.
     Negation(Box<Self>),
     /// Logical negation (NOT) of an expression.
@@ -112,6 +153,8 @@ pub enum Expression {
     And(Box<Self>, Box<Self>),
     /// Logical disjunction (OR) of two expressions.
     Or(Box<Self>, Box<Self>),
+    /// Anonymous function with parameter identifiers and body.
+    Lambda(Vec<String>, Box<Self>),
 }
 
 /// Basic expression type designed to make evaluating expressions easier.
@@ -126,9 +169,26 @@ pub(crate) enum Type {
     /// Number with preferred representation for rational parts.
     Number(Complex, RationalRepresentation),
     /// Column-major matrix.
+    #[cfg_attr(not(feature = "matrices"), allow(dead_code))]
     Matrix(Matrix),
+    /// Array with more than two dimensions.
+    #[cfg_attr(not(feature = "matrices"), allow(dead_code))]
+    Tensor(Tensor),
+    /// Quaternion with real part and `i`, `j`, and `k` components.
+    Quaternion(
+        Box<Expression>,
+        Box<Expression>,
+        Box<Expression>,
+        Box<Expression>,
+    ),
+    /// Residue class of an integer modulo a positive integer.
+    Mod(Integer, Integer),
+    /// Closed interval with rational bounds with preferred representation.
+    Interval(Interval, RationalRepresentation),
     /// Boolean expression with value (if available).
     Boolean(Option<bool>),
+    /// String of text.
+    String(String),
     /// Arithmetic expression (in particular, this expression does *not* have a boolean value).
     Arithmetic,
     /// Expression that cannot be assigned to any of the above types with certainty.
@@ -147,14 +207,63 @@ pub(crate) enum Associativity {
     Associative,
 }
 
+/// Order in which the sub-expressions of an expression are visited.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TraversalOrder {
+    /// Every expression is visited before its sub-expressions.
+    PreOrder,
+    /// Every expression is visited after its sub-expressions.
+    PostOrder,
+}
+
+/// Iterator over an expression and all of its sub-expressions,
+/// created by `Expression::iter_subexpressions`.
+#[derive(Clone, Debug)]
+pub struct Subexpressions<'a> {
+    order: TraversalOrder,
+    /// Expressions that remain to be visited, together with
+    /// whether their sub-expressions have already been added.
+    stack: Vec<(&'a Expression, bool)>,
+}
+
+impl<'a> Iterator for Subexpressions<'a> {
+    type Item = &'a Expression;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (expression, expanded) = self.stack.pop()?;
+
+            if expanded {
+                return Some(expression);
+            }
+
+            if self.order == TraversalOrder::PostOrder {
+                self.stack.push((expression, true));
+            }
+
+            // Children are pushed in reverse so that they are visited from left to right.
+            for child in expression.children().into_iter().rev() {
+                self.stack.push((child, false));
+            }
+
+            if self.order == TraversalOrder::PreOrder {
+                return Some(expression);
+            }
+        }
+    }
+}
+
 impl Expression {
     /// Returns the basic type of the expression.
     pub(crate) fn typ(&self) -> Type {
         use Expression::*;
         use RationalRepresentation::*;
         use Type::{
-            Arithmetic, Boolean as Bool, Function as Fun, Matrix as Mat, Number as Num, Unknown,
+            Arithmetic, Boolean as Bool, Function as Fun, Mod as Residue, Number as Num,
+            Quaternion as Quat, String as Str, Unknown,
         };
+        #[cfg(feature = "matrices")]
+        use Type::{Matrix as Mat, Tensor as Ten};
 
         match self {
             Variable(_) => Unknown,
@@ -163,11 +272,38 @@ impl Expression {
             Integer(n) => Num(self::Rational::from_integer(n.clone()).into(), Fraction),
             Rational(x, representation) => Num(x.into(), *representation),
             Complex(z, representation) => Num(z.clone(), *representation),
-            Vector(v) => Mat(self::Matrix::from_columns(&[v.clone()])),
+            #[cfg(feature = "matrices")]
+            Vector(v) => Mat(self::Matrix::from_columns(std::slice::from_ref(v))),
+            #[cfg(feature = "matrices")]
             VectorElement(_, _) => Unknown,
+            #[cfg(feature = "matrices")]
             Matrix(m) => Mat(m.clone()),
+            #[cfg(feature = "matrices")]
             MatrixElement(_, _, _) => Unknown,
+            #[cfg(feature = "matrices")]
+            Tensor(t) => Ten(t.clone()),
+            #[cfg(feature = "matrices")]
+            TensorElement(_, _) => Unknown,
+            Quaternion(a, b, c, d) => Quat(a.clone(), b.clone(), c.clone(), d.clone()),
+            Mod(n, m) => match (&**n, &**m) {
+                (Integer(n), Integer(m)) => Residue(n.clone(), m.clone()),
+                _ => Unknown,
+            },
+            Interval(a, b) => match (a.typ(), b.typ()) {
+                (Num(a, a_representation), Num(b, b_representation))
+                    if a.im.is_zero() && b.im.is_zero() =>
+                {
+                    match self::Interval::new(a.re, b.re) {
+                        Some(interval) => {
+                            Type::Interval(interval, a_representation.merge(b_representation))
+                        }
+                        None => Unknown,
+                    }
+                }
+                _ => Unknown,
+            },
             Boolean(boolean) => Bool(Some(*boolean)),
+            String(string) => Str(string.clone()),
             Negation(_) => Arithmetic,
             Not(_) => Bool(None),
             Sum(_, _) => Arithmetic,
@@ -184,6 +320,51 @@ impl Expression {
             GreaterThanOrEqual(_, _) => Bool(None),
             And(_, _) => Bool(None),
             Or(_, _) => Bool(None),
+            Lambda(parameters, body) => Fun(self.to_string(), lambda(parameters, body)),
+        }
+    }
+
+    /// Returns a human-readable name for the type of the expression,
+    /// e.g. "integer", "boolean value", or "3×3 matrix". Expressions
+    /// that are not values are described as symbolic expressions.
+    pub fn type_name(&self) -> String {
+        use Type::*;
+
+        match self.typ() {
+            Function(_, _) => "function".to_owned(),
+            Number(z, _) => {
+                if !z.im.is_zero() {
+                    "complex number".to_owned()
+                } else if z.re.is_integer() {
+                    "integer".to_owned()
+                } else {
+                    "rational number".to_owned()
+                }
+            }
+            #[cfg(feature = "matrices")]
+            Matrix(m) => {
+                if matches!(self, Expression::Vector(_)) {
+                    format!("vector of length {}", m.nrows())
+                } else {
+                    format!("{}×{} matrix", m.nrows(), m.ncols())
+                }
+            }
+            #[cfg(feature = "matrices")]
+            Tensor(t) => format!(
+                "{} tensor",
+                t.shape()
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join("×"),
+            ),
+            Quaternion(_, _, _, _) => "quaternion".to_owned(),
+            Mod(_, _) => "residue class".to_owned(),
+            Interval(_, _) => "interval".to_owned(),
+            Boolean(Some(_)) => "boolean value".to_owned(),
+            Boolean(None) => "symbolic boolean expression".to_owned(),
+            String(_) => "string".to_owned(),
+            Arithmetic | Unknown => "symbolic expression".to_owned(),
         }
     }
 
@@ -192,6 +373,15 @@ impl Expression {
     /// expressions, to which the concept of associativity doesn't apply,
     /// `Associative` is returned.
     pub(crate) fn precedence_and_associativity(&self) -> (isize, Associativity) {
+        self.precedence_and_associativity_with(&FormatOptions::default())
+    }
+
+    /// Returns the precedence and associativity of the expression
+    /// when it is formatted with the given options.
+    pub(crate) fn precedence_and_associativity_with(
+        &self,
+        options: &FormatOptions,
+    ) -> (isize, Associativity) {
         use Associativity::*;
         use Expression::*;
 
@@ -207,7 +397,7 @@ impl Expression {
                 }
             }
             Rational(x, _) => {
-                if self.to_string().contains('/') {
+                if self.display_with(options).to_string().contains('/') {
                     (2, LeftAssociative)
                 } else if x.is_negative() {
                     (2, Associative)
@@ -217,24 +407,39 @@ impl Expression {
             }
             Complex(z, _) => {
                 if !z.re.is_zero() && !z.im.is_zero() {
-                    if self.to_string().contains('+') {
+                    if self.display_with(options).to_string().contains('+') {
                         (1, Associative)
                     } else {
                         (1, LeftAssociative)
                     }
-                } else if self.to_string().contains('/') {
+                } else if self.display_with(options).to_string().contains('/') {
                     (2, LeftAssociative)
+                } else if z.re.is_zero() && z.im.is_one() {
+                    // The imaginary unit is displayed as `i`, like a variable.
+                    (isize::MAX, Associative)
                 } else if z.re.is_negative() || !z.im.is_zero() {
                     (2, Associative)
                 } else {
                     (isize::MAX, Associative)
                 }
             }
+            #[cfg(feature = "matrices")]
             Vector(_) => (isize::MAX, Associative),
+            #[cfg(feature = "matrices")]
             VectorElement(_, _) => (5, Associative),
+            #[cfg(feature = "matrices")]
             Matrix(_) => (isize::MAX, Associative),
+            #[cfg(feature = "matrices")]
             MatrixElement(_, _, _) => (5, Associative),
+            #[cfg(feature = "matrices")]
+            Tensor(_) => (isize::MAX, Associative),
+            #[cfg(feature = "matrices")]
+            TensorElement(_, _) => (5, Associative),
+            Quaternion(_, _, _, _) => (5, Associative),
+            Mod(_, _) => (0, LeftAssociative),
+            Interval(_, _) => (5, Associative),
             Boolean(_) => (isize::MAX, Associative),
+            String(_) => (isize::MAX, Associative),
             Negation(_) => (3, Associative),
             Not(_) => (3, Associative),
             Sum(_, _) => (1, Associative),
@@ -243,14 +448,15 @@ impl Expression {
             Quotient(_, _) => (2, LeftAssociative),
             Remainder(_, _) => (2, LeftAssociative),
             Power(_, _) => (4, RightAssociative),
-            Equal(_, _) => (0, Associative),
-            NotEqual(_, _) => (0, Associative),
-            LessThan(_, _) => (0, Associative),
-            LessThanOrEqual(_, _) => (0, Associative),
-            GreaterThan(_, _) => (0, Associative),
-            GreaterThanOrEqual(_, _) => (0, Associative),
-            And(_, _) => (-1, Associative),
-            Or(_, _) => (-2, Associative),
+            Equal(_, _) => (-1, Associative),
+            NotEqual(_, _) => (-1, Associative),
+            LessThan(_, _) => (-1, Associative),
+            LessThanOrEqual(_, _) => (-1, Associative),
+            GreaterThan(_, _) => (-1, Associative),
+            GreaterThanOrEqual(_, _) => (-1, Associative),
+            And(_, _) => (-2, Associative),
+            Or(_, _) => (-3, Associative),
+            Lambda(_, _) => (-4, RightAssociative),
         }
     }
 
@@ -265,4 +471,256 @@ impl Expression {
     pub(crate) fn associativity(&self) -> Associativity {
         self.precedence_and_associativity().1
     }
+
+    /// Returns whether the expression is a vector, matrix, or tensor.
+    pub(crate) fn is_array(&self) -> bool {
+        match self {
+            #[cfg(feature = "matrices")]
+            Expression::Vector(_) | Expression::Matrix(_) | Expression::Tensor(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the immediate sub-expressions of the expression.
+    pub(crate) fn children(&self) -> Vec<&Self> {
+        use Expression::*;
+
+        match self {
+            Variable(_)
+            | Function(_, _)
+            | Integer(_)
+            | Rational(_, _)
+            | Complex(_, _)
+            | Boolean(_)
+            | String(_) => Vec::new(),
+            FunctionValue(function, arguments) => {
+                std::iter::once(&**function).chain(arguments).collect()
+            }
+            #[cfg(feature = "matrices")]
+            Vector(v) => v.iter().collect(),
+            #[cfg(feature = "matrices")]
+            Matrix(m) => m.iter().collect(),
+            #[cfg(feature = "matrices")]
+            VectorElement(a, b) => vec![&**a, &**b],
+            #[cfg(feature = "matrices")]
+            MatrixElement(a, b, c) => vec![&**a, &**b, &**c],
+            #[cfg(feature = "matrices")]
+            Tensor(t) => t.elements().iter().collect(),
+            #[cfg(feature = "matrices")]
+            TensorElement(tensor, indices) => std::iter::once(&**tensor).chain(indices).collect(),
+            Quaternion(a, b, c, d) => vec![&**a, &**b, &**c, &**d],
+            Negation(a) | Not(a) => vec![&**a],
+            Mod(a, b)
+            | Interval(a, b)
+            | Sum(a, b)
+            | Difference(a, b)
+            | Product(a, b)
+            | Quotient(a, b)
+            | Remainder(a, b)
+            | Power(a, b)
+            | Equal(a, b)
+            | NotEqual(a, b)
+            | LessThan(a, b)
+            | LessThanOrEqual(a, b)
+            | GreaterThan(a, b)
+            | GreaterThanOrEqual(a, b)
+            | And(a, b)
+            | Or(a, b) => vec![&**a, &**b],
+            Lambda(_, body) => vec![&**body],
+        }
+    }
+
+    /// Returns the expression obtained by replacing each immediate sub-expression
+    /// with the result of applying `f` to it.
+    pub(crate) fn map_children(&self, mut f: impl FnMut(&Self) -> Self) -> Self {
+        use Expression::*;
+
+        let mut g = |a: &Self| Box::new(f(a));
+
+        match self {
+            Variable(_)
+            | Function(_, _)
+            | Integer(_)
+            | Rational(_, _)
+            | Complex(_, _)
+            | Boolean(_)
+            | String(_) => self.clone(),
+            FunctionValue(function, arguments) => {
+                FunctionValue(g(function), arguments.iter().map(|a| *g(a)).collect())
+            }
+            #[cfg(feature = "matrices")]
+            Vector(v) => Vector(v.map(|a| *g(&a))),
+            #[cfg(feature = "matrices")]
+            Matrix(m) => Matrix(m.map(|a| *g(&a))),
+            #[cfg(feature = "matrices")]
+            VectorElement(a, b) => VectorElement(g(a), g(b)),
+            #[cfg(feature = "matrices")]
+            MatrixElement(a, b, c) => MatrixElement(g(a), g(b), g(c)),
+            #[cfg(feature = "matrices")]
+            Tensor(t) => Tensor(t.map(|a| *g(a))),
+            #[cfg(feature = "matrices")]
+            TensorElement(tensor, indices) => {
+                TensorElement(g(tensor), indices.iter().map(|a| *g(a)).collect())
+            }
+            Quaternion(a, b, c, d) => Quaternion(g(a), g(b), g(c), g(d)),
+            Negation(a) => Negation(g(a)),
+            Not(a) => Not(g(a)),
+            Mod(a, b) => Mod(g(a), g(b)),
+            Interval(a, b) => Interval(g(a), g(b)),
+            Sum(a, b) => Sum(g(a), g(b)),
+            Difference(a, b) => Difference(g(a), g(b)),
+            Product(a, b) => Product(g(a), g(b)),
+            Quotient(a, b) => Quotient(g(a), g(b)),
+            Remainder(a, b) => Remainder(g(a), g(b)),
+            Power(a, b) => Power(g(a), g(b)),
+            Equal(a, b) => Equal(g(a), g(b)),
+            NotEqual(a, b) => NotEqual(g(a), g(b)),
+            LessThan(a, b) => LessThan(g(a), g(b)),
+            LessThanOrEqual(a, b) => LessThanOrEqual(g(a), g(b)),
+            GreaterThan(a, b) => GreaterThan(g(a), g(b)),
+            GreaterThanOrEqual(a, b) => GreaterThanOrEqual(g(a), g(b)),
+            And(a, b) => And(g(a), g(b)),
+            Or(a, b) => Or(g(a), g(b)),
+            Lambda(parameters, body) => Lambda(parameters.clone(), g(body)),
+        }
+    }
+
+    /// Returns an iterator over the expression and all of its sub-expressions,
+    /// visited from left to right in the given order.
+    pub fn iter_subexpressions(&self, order: TraversalOrder) -> Subexpressions<'_> {
+        Subexpressions {
+            order,
+            stack: vec![(self, false)],
+        }
+    }
+
+    /// Returns whether the given expression is the expression itself
+    /// or one of its sub-expressions.
+    pub fn contains(&self, expression: &Self) -> bool {
+        self.iter_subexpressions(TraversalOrder::PreOrder)
+            .any(|subexpression| subexpression == expression)
+    }
+
+    /// Returns the expression with each occurrence of `target` replaced by `replacement`,
+    /// without evaluating it. Sub-expressions are compared structurally, so for example
+    /// `x + y` is found in `x + y + z`, which is parsed as `(x + y) + z`, but `y + z`
+    /// and `y + x` are not. If `target` is a variable, occurrences bound by lambda
+    /// expressions are not replaced, and parameters are renamed where necessary
+    /// to prevent variables in `replacement` from being captured by them.
+    pub fn substitute(&self, target: &Self, replacement: &Self) -> Self {
+        if let Self::Variable(identifier) = target {
+            return substitute_variables(
+                self,
+                &HashMap::from([(identifier.as_str(), replacement)]),
+            );
+        }
+
+        if self == target {
+            return replacement.clone();
+        }
+
+        self.map_children(|child| child.substitute(target, replacement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::{Expression, TraversalOrder::*};
+
+    #[test]
+    fn subexpressions() {
+        let expression = "f(a + 2, b) * -c".parse::<Expression>().unwrap();
+
+        let visit = |order| {
+            expression
+                .iter_subexpressions(order)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            visit(PreOrder),
+            [
+                "f(a + 2, b) * -c",
+                "f(a + 2, b)",
+                "f",
+                "a + 2",
+                "a",
+                "2",
+                "b",
+                "-c",
+                "c"
+            ],
+        );
+        assert_eq!(
+            visit(PostOrder),
+            [
+                "f",
+                "a",
+                "2",
+                "a + 2",
+                "b",
+                "f(a + 2, b)",
+                "c",
+                "-c",
+                "f(a + 2, b) * -c"
+            ],
+        );
+
+        let c = |string: &str| expression.contains(&string.parse().unwrap());
+
+        assert!(c("f(a + 2, b) * -c"));
+        assert!(c("a + 2"));
+        assert!(c("b"));
+        assert!(!c("2 + a"));
+        assert!(!c("d"));
+    }
+
+    #[test]
+    fn type_names() {
+        let t = |string: &str| string.parse::<Expression>().unwrap().type_name();
+
+        assert_eq!(t("12"), "integer");
+        assert_eq!(t("1.5"), "rational number");
+        assert_eq!(t("1 + i"), "symbolic expression");
+        assert_eq!(t("true"), "boolean value");
+        assert_eq!(t("a && b"), "symbolic boolean expression");
+        #[cfg(feature = "matrices")]
+        assert_eq!(t("[1, 2, 3]"), "vector of length 3");
+        #[cfg(feature = "matrices")]
+        assert_eq!(t("[[1, 2, 3], [4, 5, 6]]"), "2×3 matrix");
+        assert_eq!(t("3 mod 4"), "residue class");
+        assert_eq!(t("x ^ 2"), "symbolic expression");
+        #[cfg(feature = "complex")]
+        assert_eq!(
+            "1 + i"
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(Default::default())
+                .unwrap()
+                .type_name(),
+            "complex number",
+        );
+    }
+
+    #[test]
+    fn substitute() {
+        let s = |expression: &str, target: &str, replacement: &str| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .substitute(
+                    &target.parse::<Expression>().unwrap(),
+                    &replacement.parse::<Expression>().unwrap(),
+                )
+                .to_string()
+        };
+
+        assert_eq!(s("x ^ 2 + x", "x", "y + 1"), "(y + 1) ^ 2 + y + 1");
+        assert_eq!(s("f(x) * sin(x)", "sin(x)", "1"), "f(x) * 1");
+        assert_eq!(s("x + y + z", "x + y", "w"), "w + z");
+        assert_eq!(s("x + y + z", "y + z", "w"), "x + y + z");
+        assert_eq!(s("(x -> x + y)(x)", "x", "2"), "(x -> x + y)(2)");
+        assert_eq!(s("(x -> x + y)(1)", "y", "x"), "(x1 -> x1 + x)(1)");
+    }
 }
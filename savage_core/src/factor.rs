@@ -0,0 +1,1064 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::collections::BTreeSet;
+
+use num::{Integer as _, One, Signed, ToPrimitive, Zero};
+
+use crate::{
+    evaluate::modular_inverse,
+    expression::{Integer, Rational},
+    polynomial::Polynomial,
+};
+
+/// Number of primes modulo which a polynomial is factored to find the prime
+/// modulo which it has the fewest factors, which are then lifted and combined.
+const PRIME_CANDIDATES: usize = 5;
+
+/// Maximum number of combinations of lifted modular factors tested for dividing
+/// a polynomial, beyond which the remaining factors are not separated.
+const MAX_COMBINATIONS: usize = 100_000;
+
+/// Polynomial in one variable, given by its coefficients ordered by increasing degree,
+/// without trailing zeros (so the zero polynomial has no coefficients).
+pub(crate) type Dense = Vec<Rational>;
+
+/// Removes trailing zero coefficients from the polynomial.
+pub(crate) fn trim(mut a: Dense) -> Dense {
+    while matches!(a.last(), Some(c) if c.is_zero()) {
+        a.pop();
+    }
+
+    a
+}
+
+/// Returns the difference of the polynomials.
+fn subtract(a: &[Rational], b: &[Rational]) -> Dense {
+    let mut result = vec![Rational::zero(); a.len().max(b.len())];
+
+    for (i, c) in a.iter().enumerate() {
+        result[i] += c;
+    }
+
+    for (i, c) in b.iter().enumerate() {
+        result[i] -= c;
+    }
+
+    trim(result)
+}
+
+/// Returns the quotient and remainder of dividing `a` by the non-zero polynomial `b`.
+pub(crate) fn div_rem(a: &[Rational], b: &[Rational]) -> (Dense, Dense) {
+    let mut remainder = a.to_vec();
+
+    if a.len() < b.len() {
+        return (Vec::new(), remainder);
+    }
+
+    let mut quotient = vec![Rational::zero(); a.len() - b.len() + 1];
+    let leading = b.last().unwrap();
+
+    for i in (0..quotient.len()).rev() {
+        let factor = &remainder[i + b.len() - 1] / leading;
+
+        for (j, c) in b.iter().enumerate() {
+            remainder[i + j] -= &factor * c;
+        }
+
+        quotient[i] = factor;
+    }
+
+    (trim(quotient), trim(remainder))
+}
+
+/// Returns the polynomial divided by its leading coefficient.
+pub(crate) fn monic(a: &[Rational]) -> Dense {
+    match a.last() {
+        Some(leading) => a.iter().map(|c| c / leading).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns the pseudo-remainder of dividing `a` by the non-zero polynomial `b`,
+/// which is the remainder of dividing `lc(b) ^ (deg(a) - deg(b) + 1) * a` by `b`.
+fn pseudo_remainder(a: &[Rational], b: &[Rational]) -> Dense {
+    if a.len() < b.len() {
+        return a.to_vec();
+    }
+
+    let factor = b.last().unwrap().pow((a.len() - b.len() + 1) as i32);
+
+    div_rem(&a.iter().map(|c| c * &factor).collect::<Vec<_>>(), b).1
+}
+
+/// Returns the greatest common divisor of the rational numbers,
+/// which is the greatest rational number of which both are integer multiples.
+pub(crate) fn rational_gcd(a: &Rational, b: &Rational) -> Rational {
+    Rational::new(a.numer().gcd(b.numer()), a.denom().lcm(b.denom()))
+}
+
+/// Returns the content of the polynomial, which is the (non-negative) greatest
+/// common divisor of its coefficients.
+fn content(a: &[Rational]) -> Rational {
+    a.iter().fold(Rational::zero(), |g, c| rational_gcd(&g, c))
+}
+
+/// Returns the greatest common divisor of the polynomials, computed using the
+/// subresultant polynomial remainder sequence, which keeps the coefficients of
+/// intermediate results small without computing the content at every step.
+/// The result is the product of the greatest common divisor of the contents
+/// and a primitive polynomial with a positive leading coefficient.
+fn subresultant_gcd(a: &[Rational], b: &[Rational]) -> Dense {
+    let (mut a, mut b) = if a.len() >= b.len() {
+        (a.to_vec(), b.to_vec())
+    } else {
+        (b.to_vec(), a.to_vec())
+    };
+
+    if b.is_empty() {
+        return if a.is_empty() {
+            a
+        } else {
+            primitive(&a).iter().map(|c| c * content(&a)).collect()
+        };
+    }
+
+    let d = rational_gcd(&content(&a), &content(&b));
+
+    a = primitive(&a);
+    b = primitive(&b);
+
+    let mut g = Rational::one();
+    let mut h = Rational::one();
+
+    loop {
+        let delta = (a.len() - b.len()) as i32;
+        let r = pseudo_remainder(&a, &b);
+
+        if r.is_empty() {
+            break;
+        }
+
+        if r.len() == 1 {
+            b = vec![Rational::one()];
+            break;
+        }
+
+        let divisor = &g * h.pow(delta);
+
+        a = b;
+        b = r.iter().map(|c| c / &divisor).collect();
+        g = a.last().unwrap().clone();
+        h = h.pow(1 - delta) * g.pow(delta);
+    }
+
+    primitive(&b).iter().map(|c| c * &d).collect()
+}
+
+/// Returns the monic greatest common divisor of the polynomials.
+fn gcd(a: &[Rational], b: &[Rational]) -> Dense {
+    monic(&subresultant_gcd(a, b))
+}
+
+/// Returns the derivative of the polynomial.
+pub(crate) fn derivative(a: &[Rational]) -> Dense {
+    trim(
+        a.iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| c * Rational::from_integer(Integer::from(i)))
+            .collect(),
+    )
+}
+
+/// Returns the value of the polynomial at `x`.
+pub(crate) fn value(a: &[Rational], x: &Rational) -> Rational {
+    a.iter()
+        .rev()
+        .fold(Rational::zero(), |value, c| value * x + c)
+}
+
+/// Returns the rational multiple of the non-zero polynomial that has coprime
+/// integer coefficients and a positive leading coefficient.
+pub(crate) fn primitive(a: &[Rational]) -> Dense {
+    let denominator = a.iter().fold(Integer::one(), |m, c| m.lcm(c.denom()));
+    let numerator = a.iter().fold(Integer::zero(), |g, c| {
+        g.gcd(&(c * &denominator).to_integer())
+    });
+
+    let mut factor = Rational::new(denominator, numerator);
+
+    if a.last().unwrap().is_negative() {
+        factor = -factor;
+    }
+
+    a.iter().map(|c| c * &factor).collect()
+}
+
+/// Returns the square-free polynomials `a_i` such that the monic polynomial
+/// `a` is the product of the powers `a_i ^ i`, computed using Yun's algorithm,
+/// together with `i`, omitting factors that are constant.
+fn square_free_decomposition(a: &[Rational]) -> Vec<(Dense, u32)> {
+    let mut result = Vec::new();
+
+    let a_derivative = derivative(a);
+    let c = gcd(a, &a_derivative);
+    let mut w = div_rem(a, &c).0;
+    let mut y = div_rem(&a_derivative, &c).0;
+    let mut z = subtract(&y, &derivative(&w));
+    let mut i = 1;
+
+    while w.len() > 1 {
+        let g = gcd(&w, &z);
+        w = div_rem(&w, &g).0;
+        y = div_rem(&z, &g).0;
+        z = subtract(&y, &derivative(&w));
+
+        if g.len() > 1 {
+            result.push((g, i));
+        }
+
+        i += 1;
+    }
+
+    result
+}
+
+/// Polynomial with coefficients in the integers modulo a prime, given by its coefficients
+/// (in the range `[0, p)`) ordered by increasing degree, without trailing zeros.
+type Modular = Vec<u64>;
+
+/// Removes trailing zero coefficients from the polynomial modulo a prime.
+fn trim_modular(mut a: Modular) -> Modular {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+
+    a
+}
+
+/// Returns `a ^ e` modulo the prime `p`.
+fn power_modular(a: u64, e: u64, p: u64) -> u64 {
+    let (mut base, mut e, mut result) = (a % p, e, 1);
+
+    while e > 0 {
+        if e % 2 == 1 {
+            result = result * base % p;
+        }
+
+        base = base * base % p;
+        e /= 2;
+    }
+
+    result
+}
+
+/// Returns whether the integer `n`, which must be small, is prime.
+fn is_small_prime(n: u64) -> bool {
+    let mut d = 2;
+
+    while d * d <= n {
+        if num::Integer::is_multiple_of(&n, &d) {
+            return false;
+        }
+
+        d += 1;
+    }
+
+    n >= 2
+}
+
+/// Returns the multiplicative inverse of the non-zero residue `a` modulo the prime `p`.
+fn inverse_modular(a: u64, p: u64) -> u64 {
+    power_modular(a, p - 2, p)
+}
+
+/// Returns the polynomial with integer coefficients reduced modulo the prime `p`.
+fn reduce(a: &[Integer], p: u64) -> Modular {
+    let modulus = Integer::from(p);
+
+    trim_modular(
+        a.iter()
+            .map(|c| c.mod_floor(&modulus).to_u64().unwrap())
+            .collect(),
+    )
+}
+
+/// Returns the polynomial modulo a prime as a polynomial with integer coefficients.
+fn lift(a: &[u64]) -> Vec<Integer> {
+    a.iter().map(|c| Integer::from(*c)).collect()
+}
+
+/// Returns the difference of the polynomials modulo the prime `p`.
+fn subtract_modular(a: &[u64], b: &[u64], p: u64) -> Modular {
+    let mut result = vec![0; a.len().max(b.len())];
+
+    for (i, c) in a.iter().enumerate() {
+        result[i] = *c;
+    }
+
+    for (i, c) in b.iter().enumerate() {
+        result[i] = (result[i] + p - c) % p;
+    }
+
+    trim_modular(result)
+}
+
+/// Returns the product of the polynomials modulo the prime `p`.
+fn multiply_modular(a: &[u64], b: &[u64], p: u64) -> Modular {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+
+    let mut result = vec![0; a.len() + b.len() - 1];
+
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] = (result[i + j] + x * y) % p;
+        }
+    }
+
+    trim_modular(result)
+}
+
+/// Returns the quotient and remainder of dividing `a` by the non-zero polynomial `b`
+/// modulo the prime `p`.
+fn div_rem_modular(a: &[u64], b: &[u64], p: u64) -> (Modular, Modular) {
+    let mut remainder = a.to_vec();
+
+    if a.len() < b.len() {
+        return (Vec::new(), remainder);
+    }
+
+    let mut quotient = vec![0; a.len() - b.len() + 1];
+    let inverse = inverse_modular(*b.last().unwrap(), p);
+
+    for i in (0..quotient.len()).rev() {
+        let factor = remainder[i + b.len() - 1] * inverse % p;
+
+        for (j, c) in b.iter().enumerate() {
+            remainder[i + j] = (remainder[i + j] + p - factor * c % p) % p;
+        }
+
+        quotient[i] = factor;
+    }
+
+    (trim_modular(quotient), trim_modular(remainder))
+}
+
+/// Returns the polynomial modulo the prime `p` divided by its leading coefficient.
+fn monic_modular(a: &[u64], p: u64) -> Modular {
+    match a.last() {
+        Some(leading) => {
+            let inverse = inverse_modular(*leading, p);
+            a.iter().map(|c| c * inverse % p).collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Returns the monic greatest common divisor of the polynomials modulo the prime `p`.
+fn gcd_modular(a: &[u64], b: &[u64], p: u64) -> Modular {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+
+    while !b.is_empty() {
+        let remainder = div_rem_modular(&a, &b, p).1;
+        a = b;
+        b = remainder;
+    }
+
+    monic_modular(&a, p)
+}
+
+/// Returns polynomials `s` and `t` such that `s * a + t * b = 1` modulo the prime `p`,
+/// for polynomials `a` and `b` that are coprime modulo `p`.
+fn bezout_modular(a: &[u64], b: &[u64], p: u64) -> (Modular, Modular) {
+    let (mut r_0, mut r_1) = (a.to_vec(), b.to_vec());
+    let (mut s_0, mut s_1) = (vec![1], Vec::new());
+    let (mut t_0, mut t_1) = (Vec::new(), vec![1]);
+
+    while !r_1.is_empty() {
+        let (q, r) = div_rem_modular(&r_0, &r_1, p);
+        let s = subtract_modular(&s_0, &multiply_modular(&q, &s_1, p), p);
+        let t = subtract_modular(&t_0, &multiply_modular(&q, &t_1, p), p);
+
+        r_0 = std::mem::replace(&mut r_1, r);
+        s_0 = std::mem::replace(&mut s_1, s);
+        t_0 = std::mem::replace(&mut t_1, t);
+    }
+
+    // The last non-zero remainder is a non-zero constant.
+    let inverse = [inverse_modular(r_0[0], p)];
+
+    (
+        multiply_modular(&s_0, &inverse, p),
+        multiply_modular(&t_0, &inverse, p),
+    )
+}
+
+/// Returns a basis of the Berlekamp subalgebra of the monic square-free polynomial `f`
+/// modulo the prime `p`, which consists of the polynomials `v` of lower degree such
+/// that `v ^ p = v` modulo `f`. The number of basis elements equals the number of
+/// irreducible factors of `f`, and the first basis element is the constant `1`.
+fn berlekamp_basis(f: &[u64], p: u64) -> Vec<Modular> {
+    let n = f.len() - 1;
+
+    // The rows of the matrix are the coefficients of `x ^ (i * p) mod f`, minus `x ^ i`.
+    let mut x_p = vec![1];
+    let (mut base, mut e) = (div_rem_modular(&[0, 1], f, p).1, p);
+
+    while e > 0 {
+        if e % 2 == 1 {
+            x_p = div_rem_modular(&multiply_modular(&x_p, &base, p), f, p).1;
+        }
+
+        base = div_rem_modular(&multiply_modular(&base, &base, p), f, p).1;
+        e /= 2;
+    }
+
+    let mut rows = Vec::with_capacity(n);
+    let mut power = vec![1];
+
+    for i in 0..n {
+        let mut row = power.clone();
+        row.resize(n, 0);
+        row[i] = (row[i] + p - 1) % p;
+        rows.push(row);
+
+        power = div_rem_modular(&multiply_modular(&power, &x_p, p), f, p).1;
+    }
+
+    // The basis spans the left null space of the matrix, which is the
+    // null space of its transpose, found using Gauss-Jordan elimination.
+    let mut matrix = (0..n)
+        .map(|j| rows.iter().map(|row| row[j]).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let mut pivots = Vec::new();
+
+    for column in 0..n {
+        let row = pivots.len();
+
+        let pivot = match (row..n).find(|i| matrix[*i][column] != 0) {
+            Some(pivot) => pivot,
+            None => continue,
+        };
+
+        matrix.swap(row, pivot);
+
+        let inverse = inverse_modular(matrix[row][column], p);
+
+        for c in &mut matrix[row] {
+            *c = *c * inverse % p;
+        }
+
+        let pivot_row = matrix[row].clone();
+
+        for (i, other_row) in matrix.iter_mut().enumerate() {
+            let factor = other_row[column];
+
+            if i != row && factor != 0 {
+                for (c, d) in other_row.iter_mut().zip(&pivot_row) {
+                    *c = (*c + p - factor * d % p) % p;
+                }
+            }
+        }
+
+        pivots.push(column);
+    }
+
+    (0..n)
+        .filter(|column| !pivots.contains(column))
+        .map(|column| {
+            let mut v = vec![0; n];
+            v[column] = 1;
+
+            for (i, pivot) in pivots.iter().enumerate() {
+                v[*pivot] = (p - matrix[i][column]) % p;
+            }
+
+            trim_modular(v)
+        })
+        .collect()
+}
+
+/// Returns the monic irreducible factors of the monic square-free polynomial `f` modulo
+/// the prime `p`, found using Berlekamp's algorithm with the given Berlekamp basis.
+fn berlekamp_factor(f: &[u64], basis: &[Modular], p: u64) -> Vec<Modular> {
+    let mut factors = vec![f.to_vec()];
+
+    for v in basis.iter().skip(1) {
+        if factors.len() == basis.len() {
+            break;
+        }
+
+        // Each factor is the product of its greatest common divisors with `v - s`.
+        factors = factors
+            .into_iter()
+            .flat_map(|mut u| {
+                let mut parts = Vec::new();
+
+                for s in 0..p {
+                    if u.len() <= 2 {
+                        break;
+                    }
+
+                    let g = gcd_modular(&u, &subtract_modular(v, &[s], p), p);
+
+                    if g.len() > 1 {
+                        u = div_rem_modular(&u, &g, p).0;
+                        parts.push(g);
+                    }
+                }
+
+                if u.len() > 1 {
+                    parts.push(u);
+                }
+
+                parts
+            })
+            .collect();
+    }
+
+    factors
+}
+
+/// Returns the product of the polynomials with integer coefficients.
+fn multiply_integer(a: &[Integer], b: &[Integer]) -> Vec<Integer> {
+    let mut result = vec![Integer::zero(); a.len() + b.len() - 1];
+
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+
+    result
+}
+
+/// Returns polynomials `g` and `h` with integer coefficients such that `f = g * h` modulo
+/// `p ^ k`, where `g` is monic and congruent to `g_0` modulo the prime `p`, and `h` has
+/// the same leading coefficient as `f` and is congruent to `h_0` modulo `p`, found using
+/// Hensel lifting. `f` must be congruent to `g_0 * h_0` modulo `p`, where `g_0` is monic
+/// and coprime to `h_0` modulo `p`.
+fn hensel_lift(
+    f: &[Integer],
+    g_0: &[u64],
+    h_0: &[u64],
+    p: u64,
+    k: u32,
+) -> (Vec<Integer>, Vec<Integer>) {
+    let (s, t) = bezout_modular(g_0, h_0, p);
+
+    let mut g = lift(g_0);
+    let mut h = lift(h_0);
+    *h.last_mut().unwrap() = f.last().unwrap().clone();
+
+    let mut modulus = Integer::from(p);
+
+    for _ in 1..k {
+        // `f - g * h` is divisible by the modulus, and the quotient `e` is split into
+        // `sigma * g + tau * h` modulo `p`, with the degree of `tau` less than that of `g`.
+        let e = reduce(
+            &f.iter()
+                .zip(multiply_integer(&g, &h))
+                .map(|(c, d)| (c - d) / &modulus)
+                .collect::<Vec<_>>(),
+            p,
+        );
+
+        let (q, tau) = div_rem_modular(&multiply_modular(&t, &e, p), g_0, p);
+        let sigma = trim_modular(
+            multiply_modular(&s, &e, p)
+                .into_iter()
+                .chain(std::iter::repeat(0))
+                .zip(
+                    multiply_modular(&q, h_0, p)
+                        .into_iter()
+                        .chain(std::iter::repeat(0)),
+                )
+                .take(h.len())
+                .map(|(a, b)| (a + b) % p)
+                .collect(),
+        );
+
+        for (i, c) in tau.iter().enumerate() {
+            g[i] += &modulus * c;
+        }
+
+        for (i, c) in sigma.iter().enumerate() {
+            h[i] += &modulus * c;
+        }
+
+        modulus *= p;
+    }
+
+    (g, h)
+}
+
+/// Returns the quotient of the polynomials with integer coefficients if `b` divides `a`
+/// over the integers, and `None` otherwise. Division stops at the first coefficient
+/// of the quotient that is not an integer.
+fn divide_exactly(a: &[Integer], b: &[Integer]) -> Option<Vec<Integer>> {
+    if a.len() < b.len() {
+        return None;
+    }
+
+    let mut remainder = a.to_vec();
+    let mut quotient = vec![Integer::zero(); a.len() - b.len() + 1];
+    let leading = b.last().unwrap();
+
+    for i in (0..quotient.len()).rev() {
+        let (factor, rest) = remainder[i + b.len() - 1].div_rem(leading);
+
+        if !rest.is_zero() {
+            return None;
+        }
+
+        for (j, c) in b.iter().enumerate() {
+            remainder[i + j] -= &factor * c;
+        }
+
+        quotient[i] = factor;
+    }
+
+    if remainder.iter().all(|c| c.is_zero()) {
+        Some(quotient)
+    } else {
+        None
+    }
+}
+
+/// Returns the polynomial with each coefficient replaced by its representative
+/// modulo `m` in the range `(-m/2, m/2]`.
+fn symmetric(a: &[Integer], m: &Integer) -> Dense {
+    let half = m / 2;
+
+    trim(
+        a.iter()
+            .map(|c| {
+                let c = c.mod_floor(m);
+                Rational::from_integer(if c > half { c - m } else { c })
+            })
+            .collect(),
+    )
+}
+
+/// Returns the factor of the primitive polynomial `a` that corresponds to the given subset
+/// of the monic factors of `a` modulo `m`, together with the quotient of `a` and the factor,
+/// or `None` if the product of the factors in the subset does not correspond to a factor.
+fn recombine(
+    a: &[Rational],
+    factors: &[Vec<Integer>],
+    subset: &[usize],
+    m: &Integer,
+) -> Option<(Dense, Dense)> {
+    let leading = a.last().unwrap().to_integer();
+
+    // The constant term of a candidate that corresponds to a factor divides
+    // `leading * a(0)`, which rules out most subsets without computing the candidate.
+    let constant = symmetric(
+        &[subset
+            .iter()
+            .fold(leading.clone(), |product, i| product * &factors[*i][0])],
+        m,
+    );
+
+    match constant.first() {
+        Some(c) if (&leading * a[0].to_integer()).is_multiple_of(&c.to_integer()) => (),
+        _ => return None,
+    }
+
+    let candidate = primitive(&symmetric(
+        &subset.iter().fold(vec![leading], |product, i| {
+            multiply_integer(&product, &factors[*i])
+        }),
+        m,
+    ));
+
+    let quotient = divide_exactly(
+        &a.iter().map(|c| c.to_integer()).collect::<Vec<_>>(),
+        &candidate.iter().map(|c| c.to_integer()).collect::<Vec<_>>(),
+    )?;
+
+    Some((
+        candidate,
+        quotient.into_iter().map(Rational::from_integer).collect(),
+    ))
+}
+
+/// Returns the irreducible factors of the primitive square-free polynomial `a`
+/// with a non-zero constant term, found using the Zassenhaus algorithm: the
+/// polynomial is factored modulo a small prime using Berlekamp's algorithm,
+/// the factorization is lifted to a large power of the prime using Hensel
+/// lifting, and the lifted factors are combined into factors over the integers.
+/// If combining the factors would take too long, the factors that have not
+/// been found are kept together as a single (reducible) factor.
+fn zassenhaus(a: &[Rational]) -> Vec<Dense> {
+    let f = a.iter().map(|c| c.to_integer()).collect::<Vec<_>>();
+    let n = f.len() - 1;
+    let leading = f.last().unwrap().clone();
+
+    // Among the first few primes that do not divide the leading coefficient
+    // and modulo which the polynomial remains square-free, the one modulo
+    // which the polynomial has the fewest factors is chosen.
+    let (p, f_p, basis) = (3..)
+        .filter(|p| is_small_prime(*p))
+        .filter_map(|p| {
+            let f_p = reduce(&f, p);
+
+            if f_p.len() != f.len() {
+                return None;
+            }
+
+            let f_p = monic_modular(&f_p, p);
+            let derivative = trim_modular(
+                f_p.iter()
+                    .enumerate()
+                    .skip(1)
+                    .map(|(i, c)| i as u64 % p * c % p)
+                    .collect(),
+            );
+
+            if gcd_modular(&f_p, &derivative, p).len() != 1 {
+                return None;
+            }
+
+            let basis = berlekamp_basis(&f_p, p);
+
+            Some((p, f_p, basis))
+        })
+        .take(PRIME_CANDIDATES)
+        .min_by_key(|(_, _, basis)| basis.len())
+        .unwrap();
+
+    if basis.len() == 1 {
+        return vec![a.to_vec()];
+    }
+
+    let modular_factors = berlekamp_factor(&f_p, &basis, p);
+
+    // Any factor of `leading * a` has coefficients whose absolute values are less
+    // than `leading * 2 ^ n * (n + 1) * max |a_i|` (Mignotte's bound), and the modulus
+    // must exceed twice that to recover the coefficients from their residues.
+    let bound = leading.abs()
+        * (Integer::one() << n)
+        * (n + 1)
+        * f.iter().map(|c| c.abs()).max().unwrap()
+        * 2;
+
+    let mut k = 1;
+    let mut modulus = Integer::from(p);
+
+    while modulus <= bound {
+        k += 1;
+        modulus *= p;
+    }
+
+    // Lifting the modular factors one by one, the remaining product
+    // keeps the leading coefficient of the polynomial.
+    let mut lifted = Vec::new();
+    let mut rest = f.clone();
+
+    for (i, factor) in modular_factors.iter().enumerate() {
+        if i == modular_factors.len() - 1 {
+            let inverse = modular_inverse(&leading, &modulus).unwrap();
+            lifted.push(
+                rest.iter()
+                    .map(|c| (c * &inverse).mod_floor(&modulus))
+                    .collect(),
+            );
+            break;
+        }
+
+        let others = modular_factors[i + 1..].iter().fold(
+            reduce(std::slice::from_ref(&leading), p),
+            |product, factor| multiply_modular(&product, factor, p),
+        );
+
+        let (g, h) = hensel_lift(&rest, factor, &others, p, k);
+        lifted.push(g);
+        rest = h;
+    }
+
+    // Subsets of the lifted factors, in order of increasing size, are combined
+    // into candidate factors, which are kept if they divide the polynomial.
+    let mut factors = Vec::new();
+    let mut a = a.to_vec();
+    let mut size = 1;
+    let mut combinations = 0;
+
+    'sizes: while 2 * size <= lifted.len() {
+        let mut subset = (0..size).collect::<Vec<_>>();
+
+        loop {
+            combinations += 1;
+
+            if combinations > MAX_COMBINATIONS {
+                break 'sizes;
+            }
+
+            if let Some((factor, quotient)) = recombine(&a, &lifted, &subset, &modulus) {
+                factors.push(factor);
+                a = quotient;
+
+                for i in subset.iter().rev() {
+                    lifted.remove(*i);
+                }
+
+                continue 'sizes;
+            }
+
+            // Advance to the next subset in lexicographic order.
+            match (0..size)
+                .rev()
+                .find(|i| subset[*i] < lifted.len() - size + i)
+            {
+                Some(i) => {
+                    subset[i] += 1;
+
+                    for j in i + 1..size {
+                        subset[j] = subset[j - 1] + 1;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        size += 1;
+    }
+
+    factors.push(a);
+    factors
+}
+
+/// Returns the factors of the primitive square-free polynomial `a`.
+/// Factors that cannot be split further in a reasonable amount of time are kept.
+fn factor_square_free(a: &[Rational]) -> Vec<Dense> {
+    let mut factors = Vec::new();
+    let mut a = a.to_vec();
+
+    if a[0].is_zero() {
+        factors.push(vec![Rational::zero(), Rational::one()]);
+        a.remove(0);
+    }
+
+    if a.len() > 2 {
+        factors.extend(zassenhaus(&a));
+    } else {
+        factors.push(a);
+    }
+
+    factors
+        .into_iter()
+        .filter(|factor| factor.len() > 1)
+        .map(|factor| primitive(&factor))
+        .collect()
+}
+
+impl Polynomial {
+    /// Returns the factorization of the polynomial in one variable over the rational
+    /// numbers, consisting of a rational constant and the irreducible factors with their
+    /// multiplicities, or `None` if the polynomial is zero or contains more than one
+    /// variable. The factors have coprime integer coefficients and positive leading
+    /// coefficients, and are ordered by degree and then by their coefficients,
+    /// starting with the leading coefficient.
+    ///
+    /// The square-free factors are found using Yun's algorithm, and their irreducible
+    /// factors using the Zassenhaus algorithm. The factorization is partial only if
+    /// combining the modular factors found by the Zassenhaus algorithm would require
+    /// testing more than 100,000 combinations, in which case the factors that have
+    /// not been separated are kept together as a single reducible factor.
+    pub fn factor(&self) -> Option<(Rational, Vec<(Polynomial, u32)>)> {
+        let variables = self.variables();
+
+        let identifier = match variables.iter().next() {
+            Some(identifier) if variables.len() == 1 => identifier.clone(),
+            Some(_) => return None,
+            None => {
+                return self
+                    .constant_value()
+                    .filter(|c| !c.is_zero())
+                    .map(|c| (c, Vec::new()))
+            }
+        };
+
+        let a = self.coefficients(&identifier)?;
+
+        let mut factors = square_free_decomposition(&monic(&a))
+            .into_iter()
+            .flat_map(|(factor, multiplicity)| {
+                factor_square_free(&primitive(&factor))
+                    .into_iter()
+                    .map(move |factor| (factor, multiplicity))
+            })
+            .collect::<Vec<_>>();
+
+        factors.sort_by(|(a, _), (b, _)| {
+            a.len()
+                .cmp(&b.len())
+                .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+        });
+
+        let leading = factors
+            .iter()
+            .fold(Rational::one(), |product, (factor, multiplicity)| {
+                product * factor.last().unwrap().pow(*multiplicity as i32)
+            });
+
+        Some((
+            a.last().unwrap() / leading,
+            factors
+                .into_iter()
+                .map(|(factor, multiplicity)| {
+                    (Self::from_coefficients(&identifier, &factor), multiplicity)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Returns the content of the polynomial, which is the greatest (non-negative)
+    /// rational number of which all its coefficients are integer multiples.
+    pub fn content(&self) -> Rational {
+        self.terms()
+            .values()
+            .fold(Rational::zero(), |g, c| rational_gcd(&g, c))
+    }
+
+    /// Returns the greatest common divisor of the polynomials in (at most) one variable,
+    /// computed using the subresultant polynomial remainder sequence, or `None` if the
+    /// polynomials contain more than one variable. The result is the product of the
+    /// greatest common divisor of the contents of the polynomials (the greatest rational
+    /// number of which all their coefficients are integer multiples) and a polynomial with
+    /// coprime integer coefficients and a positive leading coefficient. The greatest common
+    /// divisor of two zero polynomials is zero.
+    pub fn gcd(&self, other: &Self) -> Option<Self> {
+        let variables = self
+            .variables()
+            .into_iter()
+            .chain(other.variables())
+            .collect::<BTreeSet<_>>();
+
+        if variables.len() > 1 {
+            return None;
+        }
+
+        let identifier = variables.into_iter().next().unwrap_or_default();
+
+        Some(Self::from_coefficients(
+            &identifier,
+            &subresultant_gcd(
+                &self.coefficients(&identifier)?,
+                &other.coefficients(&identifier)?,
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+    use crate::helpers::*;
+    use crate::polynomial::Polynomial;
+
+    #[track_caller]
+    fn f(polynomial: &str, factorization: &str) {
+        let (constant, factors) =
+            Polynomial::from_expression(&polynomial.parse::<Expression>().unwrap())
+                .unwrap()
+                .factor()
+                .unwrap();
+
+        let factors = factors
+            .into_iter()
+            .map(|(factor, multiplicity)| match multiplicity {
+                1 => format!("({})", factor.to_expression()),
+                _ => format!("({})^{}", factor.to_expression(), multiplicity),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        assert_eq!(
+            format!("{} {}", Expression::from(constant), factors),
+            factorization,
+        );
+    }
+
+    #[test]
+    fn factor() {
+        f("x ^ 2 - 1", "1 (x - 1) (x + 1)");
+        f("2 * x ^ 2 - 2", "2 (x - 1) (x + 1)");
+        f("x ^ 4 - 1", "1 (x - 1) (x + 1) (x ^ 2 + 1)");
+        f("(x - 1) ^ 3 * (x + 2)", "1 (x - 1)^3 (x + 2)");
+        f("x ^ 3 / 2 - x / 8", "1/8 (x) (2 * x - 1) (2 * x + 1)");
+        f("x ^ 4 + 4", "1 (x ^ 2 - 2 * x + 2) (x ^ 2 + 2 * x + 2)");
+        f(
+            "x ^ 6 - 1",
+            "1 (x - 1) (x + 1) (x ^ 2 - x + 1) (x ^ 2 + x + 1)",
+        );
+        f("x ^ 2 + 1", "1 (x ^ 2 + 1)");
+        f("-x ^ 2 + 4 * x - 4", "-1 (x - 2)^2");
+        f("3", "3 ");
+        f(
+            "x ^ 22 - 1",
+            "1 (x - 1) (x + 1) (x ^ 10 - x ^ 9 + x ^ 8 - x ^ 7 + x ^ 6 - x ^ 5 + x ^ 4 - x ^ 3 + x ^ 2 - x + 1) (x ^ 10 + x ^ 9 + x ^ 8 + x ^ 7 + x ^ 6 + x ^ 5 + x ^ 4 + x ^ 3 + x ^ 2 + x + 1)",
+        );
+        f(
+            "(x ^ 8 + 3 * x + 1) * (x ^ 8 + 5 * x ^ 3 + 2)",
+            "1 (x ^ 8 + 3 * x + 1) (x ^ 8 + 5 * x ^ 3 + 2)",
+        );
+        f("x ^ 4 - 10 * x ^ 2 + 1", "1 (x ^ 4 - 10 * x ^ 2 + 1)");
+        f(
+            "(2 * x ^ 3 - 5) * (3 * x ^ 2 + 7 * x - 1) * (6 * x ^ 4 + x + 11)",
+            "1 (3 * x ^ 2 + 7 * x - 1) (2 * x ^ 3 - 5) (6 * x ^ 4 + x + 11)",
+        );
+        f(
+            "x ^ 8 - 40 * x ^ 6 + 352 * x ^ 4 - 960 * x ^ 2 + 576",
+            "1 (x ^ 8 - 40 * x ^ 6 + 352 * x ^ 4 - 960 * x ^ 2 + 576)",
+        );
+        f(
+            "x ^ 12 - 1",
+            "1 (x - 1) (x + 1) (x ^ 2 - x + 1) (x ^ 2 + 1) (x ^ 2 + x + 1) (x ^ 4 - x ^ 2 + 1)",
+        );
+
+        assert_eq!(
+            Polynomial::from_expression(&(var("x") * var("y")))
+                .unwrap()
+                .factor(),
+            None,
+        );
+        assert_eq!(Polynomial::default().factor(), None);
+    }
+    #[track_caller]
+    fn g(a: &str, b: &str, gcd: Option<&str>) {
+        let p = |string: &str| {
+            Polynomial::from_expression(&string.parse::<Expression>().unwrap()).unwrap()
+        };
+
+        assert_eq!(
+            p(a).gcd(&p(b)).map(|gcd| gcd.to_expression().to_string()),
+            gcd.map(ToOwned::to_owned),
+        );
+    }
+
+    #[test]
+    fn gcd() {
+        g("x ^ 2 - 1", "x ^ 2 + 2 * x + 1", Some("x + 1"));
+        g("2 * x ^ 2 - 2", "4 * x - 4", Some("2 * x - 2"));
+        g("x ^ 2 / 2 - 1/2", "x / 3 + 1/3", Some("1/6 * x + 1/6"));
+        g(
+            "x ^ 8 + x ^ 6 - 3 * x ^ 4 - 3 * x ^ 3 + 8 * x ^ 2 + 2 * x - 5",
+            "3 * x ^ 6 + 5 * x ^ 4 - 4 * x ^ 2 - 9 * x + 21",
+            Some("1"),
+        );
+        g(
+            "(x - 1) ^ 3 * (x + 2)",
+            "(x - 1) ^ 2 * (x - 3)",
+            Some("x ^ 2 - 2 * x + 1"),
+        );
+        g("6", "4 * x + 2", Some("2"));
+        g("0", "-2 * x", Some("2 * x"));
+        g("0", "0", Some("0"));
+        g("x", "y", None);
+    }
+}
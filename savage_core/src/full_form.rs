@@ -0,0 +1,656 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::ops::Range;
+
+use chumsky::prelude::*;
+#[cfg(feature = "matrices")]
+use num::One;
+use num::Zero;
+
+use crate::{
+    expression::{Expression, Integer, Rational, RationalRepresentation},
+    helpers::*,
+    parse::{Error, ParseError},
+    print::{decimal_representation, quote_string},
+};
+
+#[cfg(feature = "matrices")]
+use crate::print::nested_list;
+
+/// Pairs of identifiers and the symbols representing them in FullForm,
+/// for constants and functions whose names differ between the two.
+const SYMBOLS: [(&str, &str); 16] = [
+    ("pi", "Pi"),
+    ("e", "E"),
+    ("i", "I"),
+    ("sin", "Sin"),
+    ("cos", "Cos"),
+    ("tan", "Tan"),
+    ("det", "Det"),
+    ("factorial", "Factorial"),
+    ("is_prime", "PrimeQ"),
+    ("nth_prime", "Prime"),
+    ("prime_pi", "PrimePi"),
+    ("divisors", "Divisors"),
+    ("euler_phi", "EulerPhi"),
+    ("moebius", "MoebiusMu"),
+    ("fibonacci", "Fibonacci"),
+    ("lucas", "LucasL"),
+];
+
+/// Returns the FullForm symbol representing the given identifier.
+fn identifier_to_symbol(identifier: &str) -> &str {
+    SYMBOLS
+        .iter()
+        .find(|(i, _)| *i == identifier)
+        .map_or(identifier, |(_, symbol)| symbol)
+}
+
+/// Returns the identifier represented by the given FullForm symbol.
+fn symbol_to_identifier(symbol: &str) -> &str {
+    SYMBOLS
+        .iter()
+        .find(|(_, s)| *s == symbol)
+        .map_or(symbol, |(identifier, _)| identifier)
+}
+
+/// Returns the FullForm of the given rational number, which is a decimal
+/// if the preferred representation is decimal and the number has a finite
+/// decimal expansion, and `Rational[n, d]` otherwise.
+fn rational_to_full_form(x: &Rational, representation: RationalRepresentation) -> String {
+    if representation == RationalRepresentation::Decimal && decimal_representation(x).is_some() {
+        Expression::Rational(x.clone(), representation).to_string()
+    } else if x.is_integer() {
+        x.to_integer().to_string()
+    } else {
+        format!("Rational[{}, {}]", x.numer(), x.denom())
+    }
+}
+
+/// Returns the FullForm of an index, converting it from 0-based to 1-based.
+#[cfg(feature = "matrices")]
+fn index_to_full_form(index: &Expression) -> String {
+    match index {
+        Expression::Integer(i) => (i + Integer::one()).to_string(),
+        Expression::Difference(a, b) if **b == int(1) => a.to_full_form(),
+        _ => format!("Plus[{}, 1]", index.to_full_form()),
+    }
+}
+
+/// Returns the index converted from 1-based to 0-based.
+#[cfg(feature = "matrices")]
+fn index_from_full_form(index: Expression) -> Expression {
+    match index {
+        Expression::Integer(i) => Expression::Integer(i - Integer::one()),
+        Expression::Sum(a, b) if *b == int(1) => *a,
+        _ => index - int(1),
+    }
+}
+
+impl Expression {
+    /// Appends the operands of the expression to `operands` as FullForm strings,
+    /// descending into left operands with the same operator, so that chains
+    /// such as `a + b - c` become a single `Plus` with three operands.
+    fn add_full_form_operands(&self, operands: &mut Vec<String>) {
+        use crate::expression::Expression::*;
+
+        let same_operator = |a: &Self| {
+            std::mem::discriminant(a) == std::mem::discriminant(self)
+                || matches!(
+                    (self, a),
+                    (Sum(_, _) | Difference(_, _), Sum(_, _) | Difference(_, _))
+                        | (
+                            Product(_, _) | Quotient(_, _),
+                            Product(_, _) | Quotient(_, _)
+                        )
+                )
+        };
+
+        match self {
+            Sum(a, b)
+            | Difference(a, b)
+            | Product(a, b)
+            | Quotient(a, b)
+            | And(a, b)
+            | Or(a, b) => {
+                if same_operator(a) {
+                    a.add_full_form_operands(operands);
+                } else {
+                    operands.push(a.to_full_form());
+                }
+
+                operands.push(match self {
+                    Difference(_, _) => format!("Times[-1, {}]", b.to_full_form()),
+                    Quotient(_, _) => format!("Power[{}, -1]", b.to_full_form()),
+                    _ => b.to_full_form(),
+                });
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the representation of the expression in Mathematica's FullForm,
+    /// e.g. `Plus[x, Power[y, 2]]` for `x + y ^ 2`.
+    ///
+    /// Differences and quotients are represented as in Mathematica, i.e. `a - b`
+    /// becomes `Plus[a, Times[-1, b]]` and `a / b` becomes `Times[a, Power[b, -1]]`.
+    /// Indices are converted to Mathematica's 1-based convention, remainders become `Mod`,
+    /// and residue classes, which have no equivalent in Mathematica, become `ResidueClass`.
+    pub fn to_full_form(&self) -> String {
+        use crate::expression::Expression::*;
+
+        let call = |head: &str, arguments: &[&Self]| {
+            format!(
+                "{}[{}]",
+                head,
+                arguments
+                    .iter()
+                    .map(|argument| argument.to_full_form())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        };
+
+        let chain = |head: &str| {
+            let mut operands = Vec::new();
+            self.add_full_form_operands(&mut operands);
+            format!("{}[{}]", head, operands.join(", "))
+        };
+
+        match self {
+            Variable(identifier) | Function(identifier, _) => {
+                identifier_to_symbol(identifier).to_owned()
+            }
+            FunctionValue(function, arguments) => call(
+                &function.to_full_form(),
+                &arguments.iter().collect::<Vec<_>>(),
+            ),
+            Integer(n) => n.to_string(),
+            Rational(x, representation) => rational_to_full_form(x, *representation),
+            Complex(z, representation) => {
+                if z.im.is_zero() {
+                    rational_to_full_form(&z.re, *representation)
+                } else {
+                    format!(
+                        "Complex[{}, {}]",
+                        rational_to_full_form(&z.re, *representation),
+                        rational_to_full_form(&z.im, *representation),
+                    )
+                }
+            }
+            #[cfg(feature = "matrices")]
+            Vector(v) => call("List", &v.iter().collect::<Vec<_>>()),
+            #[cfg(feature = "matrices")]
+            VectorElement(vector, i) => {
+                format!("Part[{}, {}]", vector.to_full_form(), index_to_full_form(i),)
+            }
+            #[cfg(feature = "matrices")]
+            Matrix(m) => format!(
+                "List[{}]",
+                m.row_iter()
+                    .map(|row| call("List", &row.iter().collect::<Vec<_>>()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            #[cfg(feature = "matrices")]
+            MatrixElement(matrix, i, j) => format!(
+                "Part[{}, {}, {}]",
+                matrix.to_full_form(),
+                index_to_full_form(i),
+                index_to_full_form(j),
+            ),
+            #[cfg(feature = "matrices")]
+            Tensor(t) => nested_list(t.shape(), t.elements(), "List[", "]", &|element| {
+                element.to_full_form()
+            }),
+            #[cfg(feature = "matrices")]
+            TensorElement(tensor, indices) => format!(
+                "Part[{}, {}]",
+                tensor.to_full_form(),
+                indices
+                    .iter()
+                    .map(index_to_full_form)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Quaternion(a, b, c, d) => call("Quaternion", &[a, b, c, d]),
+            Mod(n, m) => call("ResidueClass", &[n, m]),
+            Interval(a, b) => format!("Interval[{}]", call("List", &[a, b])),
+            Boolean(true) => "True".to_owned(),
+            Boolean(false) => "False".to_owned(),
+            String(string) => quote_string(string),
+            Negation(a) => format!("Times[-1, {}]", a.to_full_form()),
+            Not(a) => call("Not", &[a]),
+            Sum(_, _) | Difference(_, _) => chain("Plus"),
+            Product(_, _) | Quotient(_, _) => chain("Times"),
+            Remainder(a, b) => call("Mod", &[a, b]),
+            Power(a, b) => call("Power", &[a, b]),
+            Equal(a, b) => call("Equal", &[a, b]),
+            NotEqual(a, b) => call("Unequal", &[a, b]),
+            LessThan(a, b) => call("Less", &[a, b]),
+            LessThanOrEqual(a, b) => call("LessEqual", &[a, b]),
+            GreaterThan(a, b) => call("Greater", &[a, b]),
+            GreaterThanOrEqual(a, b) => call("GreaterEqual", &[a, b]),
+            And(_, _) => chain("And"),
+            Or(_, _) => chain("Or"),
+            Lambda(parameters, body) => format!(
+                "Function[List[{}], {}]",
+                parameters
+                    .iter()
+                    .map(|parameter| identifier_to_symbol(parameter))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                body.to_full_form(),
+            ),
+        }
+    }
+
+    /// Parses an expression written in Mathematica's FullForm, such as
+    /// `Plus[x, Power[y, 2]]`. This is the inverse of [`Expression::to_full_form`].
+    ///
+    /// Heads that have no equivalent in savage, such as `Sqrt`, are interpreted
+    /// as function values, so that the result can be evaluated if the context
+    /// defines a function with that name.
+    pub fn from_full_form(string: &str) -> Result<Self, ParseError> {
+        full_form_parser()
+            .then_ignore(end())
+            .parse(string)
+            .map_err(|errors| ParseError {
+                errors,
+                input: string.to_owned(),
+            })
+    }
+}
+
+/// Returns the elements of the given FullForm list, or `None` if it is not a list.
+fn list_elements(list: Expression) -> Option<Vec<Expression>> {
+    match list {
+        #[cfg(feature = "matrices")]
+        Expression::Vector(v) => Some(v.iter().cloned().collect()),
+        #[cfg(not(feature = "matrices"))]
+        Expression::FunctionValue(head, elements) if *head == var("List") => Some(elements),
+        _ => None,
+    }
+}
+
+/// Returns the expression represented by the given FullForm head and arguments,
+/// or an error message if the arguments are not valid for the head.
+fn from_head(head: Expression, arguments: Vec<Expression>) -> Result<Expression, String> {
+    use crate::expression::Expression::*;
+
+    let symbol = match &head {
+        Variable(identifier) => identifier.as_str(),
+        _ => return Ok(fun(head, arguments)),
+    };
+
+    let arity = match symbol {
+        "Plus" | "Times" | "And" | "Or" => None,
+        "Not" | "Interval" => Some(1),
+        // Without matrices, lists and parts are left as function values.
+        #[cfg(feature = "matrices")]
+        "List" => None,
+        #[cfg(feature = "matrices")]
+        "Part" => Some(arguments.len().max(2)),
+        "Quaternion" => Some(4),
+        "Rational" | "Complex" | "ResidueClass" | "Mod" | "Power" | "Equal" | "Unequal"
+        | "Less" | "LessEqual" | "Greater" | "GreaterEqual" | "Function" => Some(2),
+        _ => return Ok(fun(head, arguments)),
+    };
+
+    if let Some(arity) = arity {
+        if arguments.len() != arity {
+            return Err(format!(
+                "{} expects {} arguments, but {} were given",
+                symbol,
+                arity,
+                arguments.len(),
+            ));
+        }
+    }
+
+    let mut arguments = arguments.into_iter();
+
+    // Heads with any number of arguments.
+    match symbol {
+        "Plus" => {
+            return Ok(match arguments.next() {
+                Some(first) => arguments.fold(first, |sum, term| match term {
+                    Negation(a) => sum - *a,
+                    _ => sum + term,
+                }),
+                None => int(0),
+            });
+        }
+        "Times" => {
+            return Ok(match arguments.next() {
+                Some(Integer(n))
+                    if n == crate::expression::Integer::from(-1) && arguments.len() == 1 =>
+                {
+                    -arguments.next().unwrap()
+                }
+                Some(first) => arguments.fold(first, |product, factor| match factor {
+                    Power(a, b) if *b == int(-1) => product / *a,
+                    _ => product * factor,
+                }),
+                None => int(1),
+            });
+        }
+        "And" => return Ok(arguments.reduce(and).unwrap_or(Boolean(true))),
+        "Or" => return Ok(arguments.reduce(or).unwrap_or(Boolean(false))),
+        #[cfg(feature = "matrices")]
+        "List" => {
+            let elements = arguments.collect::<Vec<_>>();
+
+            return Ok(
+                if let Some(tensor) = crate::expression::Tensor::from_slices(&elements) {
+                    Tensor(tensor)
+                } else if let Some(Vector(v)) = elements.first() {
+                    // As in the regular syntax, a list of lists of the same length is a matrix.
+                    let row_size = v.len();
+                    let mut rows = Vec::new();
+
+                    for element in &elements {
+                        match element {
+                            Vector(v) if v.len() == row_size => rows.push(v.transpose()),
+                            _ => return Ok(Vector(crate::expression::Vector::from_vec(elements))),
+                        }
+                    }
+
+                    Matrix(crate::expression::Matrix::from_rows(&rows))
+                } else {
+                    Vector(crate::expression::Vector::from_vec(elements))
+                },
+            );
+        }
+        _ => (),
+    }
+
+    // Heads with a fixed number of arguments, which has already been checked.
+    let mut next = || arguments.next().unwrap();
+
+    Ok(match symbol {
+        "Not" => !next(),
+        #[cfg(feature = "matrices")]
+        "Part" => {
+            let (a, i) = (next(), index_from_full_form(next()));
+
+            match arity {
+                Some(2) => VectorElement(Box::new(a), Box::new(i)),
+                Some(3) => MatrixElement(
+                    Box::new(a),
+                    Box::new(i),
+                    Box::new(index_from_full_form(next())),
+                ),
+                _ => TensorElement(
+                    Box::new(a),
+                    std::iter::once(i)
+                        .chain(
+                            std::iter::repeat_with(|| index_from_full_form(next()))
+                                .take(arity.unwrap() - 2),
+                        )
+                        .collect(),
+                ),
+            }
+        }
+        "Quaternion" => quat(next(), next(), next(), next()),
+        "Function" => {
+            let parameters = match next() {
+                Variable(identifier) => vec![identifier],
+                list => match list_elements(list) {
+                    Some(elements) => elements
+                        .into_iter()
+                        .map(|parameter| match parameter {
+                            Variable(identifier) => Ok(identifier),
+                            _ => Err("Function expects symbols as parameters".to_owned()),
+                        })
+                        .collect::<Result<_, _>>()?,
+                    None => return Err("Function expects a symbol or a list of symbols".to_owned()),
+                },
+            };
+
+            Lambda(parameters, Box::new(next()))
+        }
+        "Rational" => match (next(), next()) {
+            (Integer(n), Integer(d)) if !d.is_zero() => Rational(
+                crate::expression::Rational::new(n, d),
+                RationalRepresentation::Fraction,
+            ),
+            _ => return Err("Rational expects an integer and a nonzero integer".to_owned()),
+        },
+        "Complex" => {
+            let part = |x: Expression| match x {
+                Integer(n) => Ok((crate::expression::Rational::from_integer(n), None)),
+                Rational(x, representation) => Ok((x, Some(representation))),
+                _ => Err("Complex expects real numbers".to_owned()),
+            };
+
+            let (re, re_representation) = part(next())?;
+            let (im, im_representation) = part(next())?;
+
+            let representation = match (re_representation, im_representation) {
+                (Some(a), Some(b)) => a.merge(b),
+                (Some(a), None) | (None, Some(a)) => a,
+                (None, None) => RationalRepresentation::Fraction,
+            };
+
+            Complex(crate::expression::Complex::new(re, im), representation)
+        }
+        "ResidueClass" => modulo(next(), next()),
+        "Interval" => match list_elements(next()).as_deref() {
+            Some([a, b]) => interval(a.clone(), b.clone()),
+            _ => return Err("Interval expects a list of two bounds".to_owned()),
+        },
+        "Mod" => next() % next(),
+        "Power" => pow(next(), next()),
+        "Equal" => eq(next(), next()),
+        "Unequal" => ne(next(), next()),
+        "Less" => lt(next(), next()),
+        "LessEqual" => le(next(), next()),
+        "Greater" => gt(next(), next()),
+        "GreaterEqual" => ge(next(), next()),
+        _ => unreachable!(),
+    })
+}
+
+/// Returns a parser that produces expressions from character streams in FullForm.
+fn full_form_parser() -> impl Parser<char, Expression, Error = Error> {
+    recursive(|expression| {
+        let symbol = filter(|c: &char| c.is_ascii_alphabetic() || *c == '$')
+            .chain(filter(|c: &char| c.is_ascii_alphanumeric() || *c == '$').repeated())
+            .collect::<String>()
+            .map(|symbol| match symbol.as_str() {
+                "True" => Expression::Boolean(true),
+                "False" => Expression::Boolean(false),
+                _ => var(symbol_to_identifier(&symbol)),
+            })
+            .labelled("symbol")
+            .boxed();
+
+        // Precision and accuracy marks (e.g. `1.5`20.`) are ignored,
+        // since all numbers are exact.
+        let precision = just('`')
+            .then(filter(|c: &char| c.is_ascii_digit() || *c == '.' || *c == '`').repeated())
+            .ignored();
+
+        let number = just('-')
+            .or_not()
+            .then(text::int(10))
+            .then(just('.').ignore_then(text::digits(10).or_not()).or_not())
+            .then_ignore(precision.or_not())
+            .map(|((sign, integer_part), fractional_part)| {
+                let sign = if sign.is_some() { "-" } else { "" };
+
+                match fractional_part {
+                    Some(fractional_part) => {
+                        let fractional_part = fractional_part.unwrap_or_default();
+                        let numerator = format!("{}{}{}", sign, integer_part, fractional_part);
+
+                        Expression::Rational(
+                            crate::expression::Rational::new(
+                                numerator.parse::<Integer>().unwrap(),
+                                num::pow(Integer::from(10), fractional_part.len()),
+                            ),
+                            RationalRepresentation::Decimal,
+                        )
+                    }
+                    None => int(format!("{}{}", sign, integer_part)
+                        .parse::<Integer>()
+                        .unwrap()),
+                }
+            })
+            .labelled("number")
+            .boxed();
+
+        let escape_sequence = just('\\').ignore_then(
+            just('\\')
+                .or(just('"'))
+                .or(just('n').to('\n'))
+                .or(just('t').to('\t')),
+        );
+
+        let string = filter(|c: &char| *c != '"' && *c != '\\')
+            .or(escape_sequence)
+            .repeated()
+            .collect::<String>()
+            .delimited_by(just('"'), just('"'))
+            .map(Expression::String)
+            .labelled("string")
+            .boxed();
+
+        let arguments = expression
+            .separated_by(just(','))
+            .padded()
+            .delimited_by(just('['), just(']'));
+
+        symbol
+            .or(number)
+            .or(string)
+            .padded()
+            .then(arguments.repeated())
+            .try_map(|(head, calls), span: Range<usize>| {
+                calls.into_iter().try_fold(head, |head, arguments| {
+                    from_head(head, arguments)
+                        .map_err(|message| Error::custom(span.clone(), message))
+                })
+            })
+            .padded()
+            .labelled("expression")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "matrices")]
+    use nalgebra::{dmatrix, dvector};
+
+    use crate::expression::Expression;
+    #[cfg(feature = "matrices")]
+    use crate::expression::Tensor;
+    use crate::helpers::*;
+
+    #[track_caller]
+    fn t(expression: Expression, full_form: &str) {
+        assert_eq!(expression.to_full_form(), full_form);
+        assert_eq!(Expression::from_full_form(full_form), Ok(expression));
+    }
+
+    #[test]
+    fn full_form() {
+        t(var("x") + pow(var("y"), int(2)), "Plus[x, Power[y, 2]]");
+        t(var("a") - var("b") + var("c"), "Plus[a, Times[-1, b], c]");
+        t(var("a") + (var("b") + var("c")), "Plus[a, Plus[b, c]]");
+        t(int(2) * var("x") / var("y"), "Times[2, x, Power[y, -1]]");
+        t(-var("x"), "Times[-1, x]");
+        t(var("x") % int(3), "Mod[x, 3]");
+        t(int(-12), "-12");
+        t(rat(-1, 3), "Rational[-1, 3]");
+        t(ratd(-5, 4), "-1.25");
+        t(com(1, 2, 3, 4), "Complex[Rational[1, 2], Rational[3, 4]]");
+        t(fun(var("sin"), [var("pi") * var("x")]), "Sin[Times[Pi, x]]");
+        t(fun(var("f"), [var("a"), var("e")]), "f[a, E]");
+        t(fun(fun(var("f"), [var("a")]), [var("b")]), "f[a][b]");
+        #[cfg(feature = "matrices")]
+        t(Expression::Vector(dvector![int(1), var("i")]), "List[1, I]");
+        #[cfg(feature = "matrices")]
+        t(
+            Expression::Matrix(dmatrix![int(1), int(2); int(3), int(4)]),
+            "List[List[1, 2], List[3, 4]]",
+        );
+        #[cfg(feature = "matrices")]
+        t(
+            Expression::VectorElement(Box::new(var("v")), Box::new(int(0))),
+            "Part[v, 1]",
+        );
+        #[cfg(feature = "matrices")]
+        t(
+            Expression::MatrixElement(
+                Box::new(var("m")),
+                Box::new(var("k") - int(1)),
+                Box::new(var("k")),
+            ),
+            "Part[m, k, Plus[k, 1]]",
+        );
+        #[cfg(feature = "matrices")]
+        t(
+            Expression::Tensor(
+                Tensor::new(vec![2, 1, 2], vec![int(1), int(2), int(3), int(4)]).unwrap(),
+            ),
+            "List[List[List[1, 2]], List[List[3, 4]]]",
+        );
+        #[cfg(feature = "matrices")]
+        t(
+            Expression::TensorElement(Box::new(var("t")), vec![int(0), int(1), var("k")]),
+            "Part[t, 1, 2, Plus[k, 1]]",
+        );
+        t(
+            quat(int(1), int(2), int(3), int(4)),
+            "Quaternion[1, 2, 3, 4]",
+        );
+        t(modulo(int(5), int(7)), "ResidueClass[5, 7]");
+        t(
+            interval(int(1), rat(5, 2)),
+            "Interval[List[1, Rational[5, 2]]]",
+        );
+        t(
+            and(lt(var("x"), int(1)), ne(var("y"), string("a\"b"))),
+            "And[Less[x, 1], Unequal[y, \"a\\\"b\"]]",
+        );
+        t(
+            or(
+                or(Expression::Boolean(true), !var("p")),
+                ge(var("z"), int(0)),
+            ),
+            "Or[True, Not[p], GreaterEqual[z, 0]]",
+        );
+        t(
+            Expression::Lambda(
+                vec!["x".to_owned(), "y".to_owned()],
+                Box::new(var("x") * var("y")),
+            ),
+            "Function[List[x, y], Times[x, y]]",
+        );
+
+        let f = |string: &str| Expression::from_full_form(string);
+
+        assert_eq!(f(" Plus[ 1.5`20. , x ] "), Ok(ratd(3, 2) + var("x")));
+        assert_eq!(f("Times[]"), Ok(int(1)));
+        assert_eq!(f("Sqrt[2]"), Ok(fun(var("Sqrt"), [int(2)])));
+        assert_eq!(
+            f("Function[x, Power[x, 2]]"),
+            Ok(Expression::Lambda(
+                vec!["x".to_owned()],
+                Box::new(pow(var("x"), int(2))),
+            )),
+        );
+
+        let e = |string: &str| f(string).unwrap_err().to_string();
+
+        assert_eq!(e("Power[x]"), "Power expects 2 arguments, but 1 were given");
+        assert_eq!(
+            e("Rational[1, 0]"),
+            "Rational expects an integer and a nonzero integer",
+        );
+        assert!(e("Plus[x,").starts_with("unexpected end of input at position 7"));
+    }
+}
@@ -0,0 +1,407 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::ToPrimitive;
+use savage_macros::function;
+
+use crate::{
+    compile::CompileError,
+    evaluate::{from_f64, Options},
+    expression::{Expression, RationalRepresentation, Type, Vector},
+    functions::{function_expression, Expressions, FunctionError, NonNegativeInteger},
+    helpers::*,
+};
+
+/// Error tolerance of `nintegrate` if none is given.
+const DEFAULT_TOLERANCE: f64 = 1e-10;
+
+/// Maximum number of times `nintegrate` bisects an interval,
+/// which bounds the number of integrand evaluations.
+const MAX_DEPTH: usize = 20;
+
+/// Returns the identifiers of the variables in the given vector,
+/// or the vector as an error if any of its elements is not a variable.
+#[allow(clippy::result_large_err)]
+fn identifiers(variables: &Vector) -> Result<Vec<String>, Expression> {
+    variables
+        .iter()
+        .map(|variable| match variable {
+            Expression::Variable(identifier) => Ok(identifier.clone()),
+            _ => Err(Expression::Vector(variables.clone())),
+        })
+        .collect()
+}
+
+#[function(
+    name = "diff",
+    description = "derivative of `f` with respect to the variable `x`",
+    examples = r#"[
+        ("diff(x^3 + 2*x, x)", "3 * x ^ 2 + 2"),
+        ("diff(x * sin(x), x)", "sin(x) + x * cos(x)"),
+        ("diff(1 / x, x)", "-1 / x ^ 2"),
+        ("diff(cos(2*x), x)", "-sin(2 * x) * 2"),
+        ("diff(e^(2*x), x)", "e ^ (2 * x) * 2"),
+        ("diff(2^x, x)", "2 ^ x * ln(2)"),
+        ("diff(x^x, x)", "x ^ x * (ln(x) + 1)"),
+        ("diff(x * y, y)", "x"),
+        ("diff([x, x^2], x)", "[1, 2 * x]"),
+    ]"#,
+    categories = r#"[
+        "calculus",
+    ]"#
+)]
+fn diff(f: Expression, x: Expression) -> Result<Expression, Expression> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x),
+    };
+
+    if let Type::Boolean(_) | Type::String(_) | Type::Function(_, _) = f.typ() {
+        return Err(f);
+    }
+
+    Ok(match f.derivative(identifier) {
+        Some(derivative) => derivative,
+        None => fun(function_expression("diff").unwrap(), [f, x]),
+    })
+}
+
+#[function(
+    name = "integrate",
+    description = "antiderivative of `f` with respect to the variable `x`, or the unevaluated integral if none can be found",
+    examples = r#"[
+        ("integrate(3*x^2 + 2*x + 1, x)", "x ^ 3 + x ^ 2 + x"),
+        ("integrate(1/x^2, x)", "-x ^ (-1)"),
+        ("integrate(sqrt(x), x)", "2/3 * x ^ (3/2)"),
+        ("integrate(2^x, x)", "2 ^ x / ln(2)"),
+        ("integrate(e^(3*x), x)", "1/3 * e ^ (3 * x)"),
+        ("integrate(sin(2*x) + cos(x), x)", "-1/2 * cos(2 * x) + sin(x)"),
+        ("integrate(x * cos(x^2), x)", "1/2 * sin(x ^ 2)"),
+        ("integrate(x * sin(x), x)", "integrate(x * sin(x), x)"),
+    ]"#,
+    categories = r#"[
+        "calculus",
+    ]"#
+)]
+fn integrate(f: Expression, x: Expression, options: &Options) -> Result<Expression, Expression> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x),
+    };
+
+    if let Type::Boolean(_) | Type::String(_) | Type::Function(_, _) = f.typ() {
+        return Err(f);
+    }
+
+    Ok(match f.antiderivative(identifier, options) {
+        Some(antiderivative) => antiderivative,
+        None => fun(function_expression("integrate").unwrap(), [f, x]),
+    })
+}
+
+#[function(
+    name = "series",
+    description = "Taylor polynomial of order `n` of `f` in the variable `x` around the point `a`, followed by the order term",
+    examples = r#"[
+        ("series(sin(x), x, 0, 5)", "x - 1/6 * x ^ 3 + 1/120 * x ^ 5 + O(x ^ 6)"),
+        ("series(1 / (1 - x), x, 0, 3)", "1 + x + x ^ 2 + x ^ 3 + O(x ^ 4)"),
+        ("series(sqrt(x), x, 1, 2)", "1 + 1/2 * (x - 1) - 1/8 * (x - 1) ^ 2 + O((x - 1) ^ 3)"),
+        ("series(x^2 + y, x, 0, 1)", "y + O(x ^ 2)"),
+    ]"#,
+    categories = r#"[
+        "calculus",
+    ]"#
+)]
+fn series(
+    f: Expression,
+    x: Expression,
+    a: Expression,
+    n: NonNegativeInteger,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x.into()),
+    };
+
+    if let Type::Boolean(_) | Type::String(_) | Type::Function(_, _) = f.typ() {
+        return Err(f.into());
+    }
+
+    if a.contains(&x) {
+        return Err(a.into());
+    }
+
+    let order = n.to_usize().ok_or_else(|| Expression::Integer(n.clone()))?;
+
+    Ok(match f.series(identifier, &a, order, options) {
+        Some(series) => series,
+        None => {
+            options.check_time()?;
+            fun(
+                function_expression("series").unwrap(),
+                [f, x, a, Expression::Integer(n)],
+            )
+        }
+    })
+}
+
+/// Returns a closure computing the value of the given expression as a floating point
+/// number from the values of the given variables, `Ok(None)` if the expression might
+/// still be given a value (e.g. because it contains other variables), and the
+/// expression as an error otherwise.
+#[allow(clippy::result_large_err)]
+fn numeric(
+    expression: &Expression,
+    variables: &[&str],
+) -> Result<Option<impl Fn(&[f64]) -> f64>, Expression> {
+    match expression.compile_f64(variables) {
+        Ok(f) => Ok(Some(f)),
+        Err(CompileError::UnknownVariable(_)) => Ok(None),
+        Err(CompileError::UnsupportedExpression(_)) => match expression.typ() {
+            Type::Number(_, _) | Type::Arithmetic | Type::Unknown => Ok(None),
+            _ => Err(expression.clone()),
+        },
+    }
+}
+
+/// Returns the integral of `f` over `[a, b]`, computed with adaptive Simpson quadrature
+/// given the values of `f` at `a`, `b`, and the midpoint, and the Simpson estimate `whole`,
+/// together with whether the estimated error is within `tolerance`. Subdivision stops
+/// early if the timeout in the given options is exceeded.
+#[allow(clippy::too_many_arguments)]
+fn adaptive_simpson(
+    f: &impl Fn(f64) -> f64,
+    options: &Options,
+    a: f64,
+    b: f64,
+    fa: f64,
+    fm: f64,
+    fb: f64,
+    whole: f64,
+    tolerance: f64,
+    depth: usize,
+) -> (f64, bool) {
+    let m = (a + b) / 2.0;
+    let (lm, rm) = ((a + m) / 2.0, (m + b) / 2.0);
+    let (flm, frm) = (f(lm), f(rm));
+
+    let left = (m - a) / 6.0 * (fa + 4.0 * flm + fm);
+    let right = (b - m) / 6.0 * (fm + 4.0 * frm + fb);
+    let delta = left + right - whole;
+
+    if delta.abs() <= 15.0 * tolerance {
+        // Richardson extrapolation of the two estimates.
+        (left + right + delta / 15.0, true)
+    } else if depth == 0 || !delta.is_finite() || options.check_time().is_err() {
+        (left + right, false)
+    } else {
+        let (left, left_converged) = adaptive_simpson(
+            f,
+            options,
+            a,
+            m,
+            fa,
+            flm,
+            fm,
+            left,
+            tolerance / 2.0,
+            depth - 1,
+        );
+        let (right, right_converged) = adaptive_simpson(
+            f,
+            options,
+            m,
+            b,
+            fm,
+            frm,
+            fb,
+            right,
+            tolerance / 2.0,
+            depth - 1,
+        );
+
+        (left + right, left_converged && right_converged)
+    }
+}
+
+#[function(
+    name = "nintegrate",
+    description = "numerical approximation of the integral of `f` with respect to the variable `x` from `a` to `b`, with the estimated error within the optional `tolerance` (default `10^(-10)`)",
+    examples = r#"[
+        ("nintegrate(x^2, x, 0, 3)", "9"),
+        ("nintegrate(sin(x), x, 0, pi)", "2"),
+        ("nintegrate(4 / (1 + x^2), x, 0, 1, 1/10^14)", "3.14159265358979"),
+        ("nintegrate(x^3, x, 1, 0, 0.001)", "-0.25"),
+    ]"#,
+    categories = r#"[
+        "calculus",
+    ]"#
+)]
+fn nintegrate(
+    f: Expression,
+    x: Expression,
+    a: Expression,
+    b: Expression,
+    tolerance: Expressions,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    let unevaluated = |tolerance: Expressions| {
+        Ok(fun(
+            function_expression("nintegrate").unwrap(),
+            [f.clone(), x.clone(), a.clone(), b.clone()]
+                .into_iter()
+                .chain(tolerance)
+                .collect::<Vec<_>>(),
+        ))
+    };
+
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x.into()),
+    };
+
+    let tolerance_value = match tolerance.as_slice() {
+        [] => DEFAULT_TOLERANCE,
+        [t] => match numeric(t, &[])? {
+            Some(t) => Some(t(&[]))
+                .filter(|t| *t > 0.0 && t.is_finite())
+                .ok_or_else(|| tolerance[0].clone())?,
+            None => return unevaluated(tolerance),
+        },
+        _ => return Err(tolerance[1].clone().into()),
+    };
+
+    let (f_closure, a_closure, b_closure) = match (
+        numeric(&f, &[identifier])?,
+        numeric(&a, &[])?,
+        numeric(&b, &[])?,
+    ) {
+        (Some(f), Some(a), Some(b)) => (f, a, b),
+        _ => return unevaluated(tolerance),
+    };
+
+    let g = |x: f64| f_closure(&[x]);
+    let (a_value, b_value) = (a_closure(&[]), b_closure(&[]));
+
+    if !a_value.is_finite() {
+        return Err(a.into());
+    }
+
+    if !b_value.is_finite() {
+        return Err(b.into());
+    }
+
+    let (fa, fm, fb) = (g(a_value), g((a_value + b_value) / 2.0), g(b_value));
+    let whole = (b_value - a_value) / 6.0 * (fa + 4.0 * fm + fb);
+
+    let (integral, converged) = adaptive_simpson(
+        &g,
+        options,
+        a_value,
+        b_value,
+        fa,
+        fm,
+        fb,
+        whole,
+        tolerance_value,
+        MAX_DEPTH,
+    );
+
+    if !integral.is_finite() {
+        return Err(FunctionError::Failed(
+            "the integrand is not finite on the interval of integration".to_owned(),
+        ));
+    }
+
+    if !converged {
+        options.check_time()?;
+
+        return Err(FunctionError::Failed(
+            "numerical integration failed to reach the requested tolerance".to_owned(),
+        ));
+    }
+
+    Ok(Expression::Rational(
+        from_f64(integral),
+        RationalRepresentation::Decimal,
+    ))
+}
+
+#[function(
+    name = "div",
+    description = "divergence of the vector field `f` with respect to the given variables",
+    examples = r#"[
+        ("div([x^2, x*y, z], [x, y, z])", "2 * x + x + 1"),
+        ("div([x*sin(y), cos(y)], [x, y])", "0"),
+        ("div([-y, x], [x, y])", "0"),
+    ]"#,
+    categories = r#"[
+        "calculus",
+        "vector calculus",
+    ]"#
+)]
+fn divergence(f: Vector, variables: Vector) -> Result<Expression, Expression> {
+    let identifiers = identifiers(&variables)?;
+
+    if f.len() != identifiers.len() {
+        return Err(Expression::Vector(variables));
+    }
+
+    Ok(f.iter()
+        .zip(&identifiers)
+        .map(|(component, identifier)| component.derivative(identifier))
+        .collect::<Option<Vec<_>>>()
+        .map_or_else(
+            || {
+                fun(
+                    function_expression("div").unwrap(),
+                    [Expression::Vector(f.clone()), Expression::Vector(variables)],
+                )
+            },
+            |terms| terms.into_iter().reduce(|a, b| a + b).unwrap_or(int(0)),
+        ))
+}
+
+#[function(
+    name = "curl",
+    description = "curl of the three-dimensional vector field `f` with respect to the given variables",
+    examples = r#"[
+        ("curl([-y, x, 0], [x, y, z])", "[0, 0, 2]"),
+        ("curl([x*y, y*z, z*x], [x, y, z])", "[-y, -z, -x]"),
+    ]"#,
+    categories = r#"[
+        "calculus",
+        "vector calculus",
+    ]"#
+)]
+fn curl(f: Vector, variables: Vector) -> Result<Expression, Expression> {
+    let identifiers = identifiers(&variables)?;
+
+    if identifiers.len() != 3 {
+        return Err(Expression::Vector(variables));
+    }
+
+    if f.len() != 3 {
+        return Err(Expression::Vector(f));
+    }
+
+    // Partial derivative of the `i`th component with respect to the `j`th variable.
+    let d = |i: usize, j: usize| f[i].derivative(&identifiers[j]);
+
+    let components = (|| {
+        Some([
+            d(2, 1)? - d(1, 2)?,
+            d(0, 2)? - d(2, 0)?,
+            d(1, 0)? - d(0, 1)?,
+        ])
+    })();
+
+    Ok(match components {
+        Some(components) => Expression::Vector(Vector::from_vec(components.to_vec())),
+        None => fun(
+            function_expression("curl").unwrap(),
+            [Expression::Vector(f), Expression::Vector(variables)],
+        ),
+    })
+}
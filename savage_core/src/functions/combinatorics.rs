@@ -1,24 +1,271 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
-use num::range_inclusive;
+use num::{range_inclusive, Integer as _, One, Signed, ToPrimitive, Zero};
 use savage_macros::function;
 
-use crate::{expression::Integer, functions::NonNegativeInteger};
+use crate::{
+    evaluate::{LimitExceeded, Options},
+    expression::{Expression, Integer, Rational, RationalRepresentation, Type},
+    functions::{
+        function_expression, inexact_value, Expressions, FunctionError, NonNegativeInteger,
+    },
+    helpers::*,
+};
+
+/// Checks that the number of digits of `x!` (or `1 / x!`) for a non-negative
+/// real number `x` does not exceed the limit set by the options.
+pub(super) fn check_factorial_digits(x: f64, options: &Options) -> Result<(), LimitExceeded> {
+    if x >= 2.0 {
+        // Stirling's approximation of log(x!)
+        let log = x * x.ln() - x + 0.5 * (2.0 * std::f64::consts::PI * x).ln();
+        options.check_digits(log / std::f64::consts::LN_10)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the factorial of the non-negative integer.
+fn integer_factorial(n: &Integer) -> Integer {
+    range_inclusive::<Integer>(1.into(), n.clone()).product()
+}
+
+/// Returns an upper bound for the number of digits of `binomial(n, k)`
+/// for integers `0 <= k <= n`.
+fn binomial_digits(n: &Integer, k: &Integer) -> f64 {
+    let m = k.min(&(n - k)).to_f64().unwrap_or(f64::INFINITY);
+
+    if m == 0.0 {
+        0.0
+    } else {
+        // binomial(n, k) <= (e * n / m) ^ m with m = min(k, n - k)
+        m * (std::f64::consts::E * n.to_f64().unwrap_or(f64::INFINITY) / m).log10()
+    }
+}
+
+/// Returns the binomial coefficient `binomial(n, k)` for integers `0 <= k <= n`.
+fn integer_binomial(n: &Integer, k: &Integer) -> Integer {
+    let k = k.min(&(n - k)).clone();
+    let mut result = Integer::one();
+
+    // After the `i`th iteration, the intermediate value is `binomial(n - k + i, i)`,
+    // so all divisions are exact.
+    for i in range_inclusive(Integer::one(), k.clone()) {
+        result = result * (n - &k + &i) / i;
+    }
+
+    result
+}
 
 #[function(
     name = "factorial",
-    description = "factorial of a non-negative integer",
+    description = "factorial of a non-negative integer, which can also be written as `n!`",
     examples = r#"[
         ("factorial(0)", "1"),
         ("factorial(1)", "1"),
         ("factorial(4)", "24"),
         ("factorial(10)", "3628800"),
+        ("5!", "120"),
+        ("(2 + 1)! ^ 2", "36"),
+    ]"#,
+    categories = r#"[
+        "combinatorics",
+    ]"#
+)]
+fn factorial(n: NonNegativeInteger, options: &Options) -> Result<Integer, LimitExceeded> {
+    check_factorial_digits(n.to_f64().unwrap_or(f64::INFINITY), options)?;
+
+    Ok(integer_factorial(&n))
+}
+
+#[function(
+    name = "binomial",
+    description = "binomial coefficient of `n` and `k`, i.e. the number of ways to choose `k` elements from a set of `n` elements, extended to negative integers `n` by `binomial(n, k) = (-1) ^ k * binomial(k - n - 1, k)`",
+    examples = r#"[
+        ("binomial(10, 3)", "120"),
+        ("binomial(50, 25)", "126410606437752"),
+        ("binomial(5, 7)", "0"),
+        ("binomial(5, -1)", "0"),
+        ("binomial(-3, 2)", "6"),
+        ("binomial(-3, 3)", "-10"),
+        ("binomial(n, 2)", "binomial(n, 2)"),
+    ]"#,
+    categories = r#"[
+        "combinatorics",
+    ]"#
+)]
+fn binomial(n: Integer, k: Integer, options: &Options) -> Result<Integer, LimitExceeded> {
+    if k.is_negative() || (!n.is_negative() && k > n) {
+        return Ok(Integer::zero());
+    }
+
+    let (n, negative) = if n.is_negative() {
+        (&k - n - 1, k.is_odd())
+    } else {
+        (n, false)
+    };
+
+    options.check_digits(binomial_digits(&n, &k))?;
+
+    let result = integer_binomial(&n, &k);
+
+    Ok(if negative { -result } else { result })
+}
+
+#[function(
+    name = "permutations",
+    description = "number of ways to arrange `k` elements from a set of `n` elements in order, i.e. `n! / (n - k)!`",
+    examples = r#"[
+        ("permutations(10, 3)", "720"),
+        ("permutations(5, 5)", "120"),
+        ("permutations(5, 0)", "1"),
+        ("permutations(3, 5)", "0"),
+    ]"#,
+    categories = r#"[
+        "combinatorics",
+    ]"#
+)]
+fn permutations(
+    n: NonNegativeInteger,
+    k: NonNegativeInteger,
+    options: &Options,
+) -> Result<Integer, LimitExceeded> {
+    if k > n {
+        return Ok(Integer::zero());
+    }
+
+    options.check_digits(
+        k.to_f64().unwrap_or(f64::INFINITY) * n.to_f64().unwrap_or(f64::INFINITY).log10(),
+    )?;
+
+    Ok(range_inclusive(&n - &k + 1, n).product())
+}
+
+#[function(
+    name = "multinomial",
+    description = "multinomial coefficient of the non-negative integers `k`, i.e. `(k_1 + ... + k_m)! / (k_1! * ... * k_m!)`",
+    examples = r#"[
+        ("multinomial(2, 3, 4)", "1260"),
+        ("multinomial(5, 2)", "21"),
+        ("multinomial(7)", "1"),
+        ("multinomial()", "1"),
+        ("multinomial(1, 1, 1, 1)", "24"),
+        ("multinomial(2, n)", "multinomial(2, n)"),
+    ]"#,
+    categories = r#"[
+        "combinatorics",
+    ]"#
+)]
+fn multinomial(k: Expressions, options: &Options) -> Result<Expression, FunctionError> {
+    let mut numbers = Vec::new();
+
+    for x in &k {
+        match Integer::try_from(x.clone()) {
+            Ok(n) if !n.is_negative() => numbers.push(n),
+            _ => match x.typ() {
+                Type::Arithmetic | Type::Unknown => {
+                    return Ok(fun(function_expression("multinomial").unwrap(), k));
+                }
+                _ => return Err(x.clone().into()),
+            },
+        }
+    }
+
+    // multinomial(k_1, ..., k_m) = binomial(k_1, k_1) * binomial(k_1 + k_2, k_2) * ...
+    //                              * binomial(k_1 + ... + k_m, k_m)
+    let mut sum = Integer::zero();
+    let mut digits = 0.0;
+
+    for n in &numbers {
+        sum += n;
+        digits += binomial_digits(&sum, n);
+    }
+
+    options.check_digits(digits)?;
+
+    let mut sum = Integer::zero();
+    let mut result = Integer::one();
+
+    for n in &numbers {
+        sum += n;
+        result *= integer_binomial(&sum, n);
+    }
+
+    Ok(int(result))
+}
+
+#[function(
+    name = "gamma",
+    description = "gamma function of `x`, which is evaluated exactly for integers and half-integers and approximated for decimal numbers",
+    examples = r#"[
+        ("gamma(1)", "1"),
+        ("gamma(6)", "120"),
+        ("gamma(1/2)", "sqrt(pi)"),
+        ("gamma(7/2)", "15/8 * sqrt(pi)"),
+        ("gamma(-3/2)", "4/3 * sqrt(pi)"),
+        ("gamma(1/3)", "gamma(1/3)"),
+        ("N(gamma(1/3), 20)", "2.6789385347077476337"),
+        ("gamma(2.5)", "1.32934038817914"),
+        ("gamma(x)", "gamma(x)"),
     ]"#,
     categories = r#"[
         "combinatorics",
     ]"#
 )]
-fn factorial(n: NonNegativeInteger) -> Integer {
-    range_inclusive::<Integer>(1.into(), n).product()
+fn gamma(x: Expression, options: &Options) -> Result<Expression, FunctionError> {
+    let (z, representation) = match x.typ() {
+        Type::Number(z, representation) if z.im.is_zero() => (z.re, representation),
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+            return Ok(fun(function_expression("gamma").unwrap(), [x]));
+        }
+        _ => return Err(x.into()),
+    };
+
+    // The gamma function has poles at the non-positive integers.
+    if z.is_integer() && !z.is_positive() {
+        return Err(x.into());
+    }
+
+    check_factorial_digits(z.abs().to_f64().unwrap_or(f64::INFINITY), options)?;
+
+    if z.is_integer() {
+        // gamma(n) = (n - 1)!
+        return Ok(int(integer_factorial(&(z.to_integer() - 1))));
+    }
+
+    if representation == RationalRepresentation::Fraction && (&z * Integer::from(2)).is_integer() {
+        // With `x = m + 1/2`, gamma(x) = (2 * m)! / (4 ^ m * m!) * sqrt(pi) for `m >= 0`,
+        // and gamma(x) = (-4) ^ (-m) * (-m)! / (-2 * m)! * sqrt(pi) for `m < 0`.
+        let m = z.floor().to_integer();
+        let k = m.abs();
+
+        let a = integer_factorial(&(&k * Integer::from(2)));
+        let b = integer_factorial(&k) * Integer::from(4).pow(k.to_u32().unwrap());
+
+        let coefficient = if m.is_negative() {
+            let coefficient = Rational::new(b, a);
+
+            if k.is_odd() {
+                -coefficient
+            } else {
+                coefficient
+            }
+        } else {
+            Rational::new(a, b)
+        };
+
+        let root = fun(function_expression("sqrt").unwrap(), [var("pi")]);
+
+        return Ok(if coefficient.is_one() {
+            root
+        } else {
+            Expression::Rational(coefficient, representation) * root
+        });
+    }
+
+    Ok(if representation == RationalRepresentation::Decimal {
+        inexact_value("gamma", [x])
+    } else {
+        fun(function_expression("gamma").unwrap(), [x])
+    })
 }
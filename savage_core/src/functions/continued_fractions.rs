@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::f64::consts::LOG10_2;
+
+use num::{Integer as _, One, Signed, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    evaluate::Options,
+    expression::{Expression, Integer, Rational, Type, Vector},
+    functions::{function_expression, FunctionError, PositiveInteger},
+    helpers::*,
+};
+
+/// Maximum number of times the precision of the approximation of a real number
+/// is doubled when fewer terms of its continued fraction expansion than requested
+/// could be determined.
+const MAX_DOUBLINGS: u32 = 4;
+
+/// Returns the first (at most `n`) terms that the continued fraction expansions
+/// of all numbers between `lower` and `upper` have in common. If `lower == upper`,
+/// these are the terms of the expansion of that number, which terminates
+/// after fewer than `n` terms if the remainder becomes zero.
+fn common_terms(lower: &Rational, upper: &Rational, n: usize) -> Vec<Integer> {
+    // The numbers are represented as pairs of numerators and denominators,
+    // which are not reduced because the Euclidean algorithm keeps them coprime.
+    let mut a = (lower.numer().clone(), lower.denom().clone());
+    let mut b = (upper.numer().clone(), upper.denom().clone());
+    let mut terms = Vec::new();
+
+    while terms.len() < n {
+        let term = a.0.div_floor(&a.1);
+
+        if term != b.0.div_floor(&b.1) {
+            break;
+        }
+
+        let remainder_a = &a.0 - &term * &a.1;
+        let remainder_b = &b.0 - &term * &b.1;
+        terms.push(term);
+
+        if remainder_a.is_zero() || remainder_b.is_zero() {
+            break;
+        }
+
+        a = (a.1, remainder_a);
+        b = (b.1, remainder_b);
+    }
+
+    terms
+}
+
+/// Returns the first `n` terms of the continued fraction expansion of the real number
+/// represented by the expression, determined from increasingly precise approximations,
+/// or `None` if the expression cannot be approximated.
+fn approximate_terms(x: &Expression, n: usize) -> Option<Vec<Integer>> {
+    // By Lévy's theorem, the denominators of the convergents of almost all
+    // real numbers grow by about 1.7 bits per term, and the approximation
+    // must be about twice as precise as the last convergent.
+    let mut precision = (n as u32).saturating_mul(4).saturating_add(64);
+    let mut terms = Vec::new();
+
+    for _ in 0..=MAX_DOUBLINGS {
+        let value = x.approximate_value(precision)?.to_rational();
+
+        // The last bits of the approximation might be incorrect
+        // due to accumulated rounding errors.
+        let error = (value.abs() + Rational::one())
+            / Rational::from(Integer::one() << (precision - 16) as usize);
+
+        terms = common_terms(&(&value - &error), &(&value + &error), n);
+
+        if terms.len() == n {
+            break;
+        }
+
+        precision = precision.saturating_mul(2);
+    }
+
+    Some(terms)
+}
+
+/// Returns the convergents of the simple continued fraction with the given terms,
+/// `Ok(None)` if any term is symbolic, or an error containing the first term
+/// that is not an integer (or, except for the first term, not positive).
+#[allow(clippy::result_large_err)]
+fn convergents_of(
+    terms: &Vector,
+    options: &Options,
+) -> Result<Option<Vec<Rational>>, FunctionError> {
+    let mut integers = Vec::new();
+
+    for (i, term) in terms.iter().enumerate() {
+        match Integer::try_from(term.clone()) {
+            Ok(a) if i == 0 || a.is_positive() => integers.push(a),
+            Ok(_) => return Err(term.clone().into()),
+            Err(_) => match term.typ() {
+                Type::Arithmetic | Type::Unknown => return Ok(None),
+                _ => return Err(term.clone().into()),
+            },
+        }
+    }
+
+    // The numerators and denominators are bounded by the product of `|a_k| + 1`.
+    let digits = integers
+        .iter()
+        .map(|a| (a.abs() + Integer::one()).bits() as f64 * LOG10_2)
+        .sum::<f64>();
+
+    options.check_digits(digits)?;
+
+    let (mut p_0, mut q_0, mut p_1, mut q_1) = (
+        Integer::from(0),
+        Integer::from(1),
+        Integer::from(1),
+        Integer::from(0),
+    );
+
+    let mut convergents = Vec::new();
+
+    for a in integers {
+        let p_2 = &p_0 + &a * &p_1;
+        let q_2 = &q_0 + &a * &q_1;
+        p_0 = p_1;
+        q_0 = q_1;
+        p_1 = p_2;
+        q_1 = q_2;
+
+        convergents.push(Rational::new(p_1.clone(), q_1.clone()));
+    }
+
+    Ok(Some(convergents))
+}
+
+#[function(
+    name = "cfrac",
+    description = "first `n` terms of the simple continued fraction expansion of the real number `x`, which has fewer terms if `x` is a rational number with a shorter expansion, and is computed from numerical approximations if `x` is not a rational number",
+    examples = r#"[
+        ("cfrac(415/93, 10)", "[4, 2, 6, 7]"),
+        ("cfrac(-7/3, 5)", "[-3, 1, 2]"),
+        ("cfrac(0.75, 5)", "[0, 1, 3]"),
+        ("cfrac(sqrt(2), 6)", "[1, 2, 2, 2, 2, 2]"),
+        ("cfrac(pi, 5)", "[3, 7, 15, 1, 292]"),
+        ("cfrac(e, 10)", "[2, 1, 2, 1, 1, 4, 1, 1, 6, 1]"),
+        ("cfrac(x, 3)", "cfrac(x, 3)"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "rational approximation",
+    ]"#
+)]
+fn cfrac(
+    x: Expression,
+    n: PositiveInteger,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    let count = n.to_usize().unwrap_or(usize::MAX);
+
+    let terms = match x.typ() {
+        Type::Number(z, _) if z.im.is_zero() => common_terms(&z.re, &z.re, count),
+        Type::Arithmetic | Type::Unknown => {
+            options.check_digits(n.to_f64().unwrap_or(f64::INFINITY))?;
+
+            match approximate_terms(&x, count) {
+                Some(terms) => terms,
+                None => return Ok(fun(function_expression("cfrac").unwrap(), [x, int(n)])),
+            }
+        }
+        _ => return Err(x.into()),
+    };
+
+    Ok(Expression::Vector(Vector::from_vec(
+        terms.into_iter().map(int).collect(),
+    )))
+}
+
+#[function(
+    name = "from_cfrac",
+    description = "value of the simple continued fraction with the given terms, i.e. `a_0 + 1 / (a_1 + 1 / (... + 1 / a_n))`",
+    examples = r#"[
+        ("from_cfrac([4, 2, 6, 7])", "415/93"),
+        ("from_cfrac([3, 7, 15, 1])", "355/113"),
+        ("from_cfrac([-3, 1, 2])", "-7/3"),
+        ("from_cfrac([5])", "5"),
+        ("from_cfrac([1, a])", "from_cfrac([1, a])"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "rational approximation",
+    ]"#
+)]
+fn from_cfrac(terms: Vector, options: &Options) -> Result<Expression, FunctionError> {
+    match convergents_of(&terms, options)? {
+        Some(convergents) => match convergents.last() {
+            Some(value) => Ok(value.clone().into()),
+            None => Err(Expression::Vector(terms).into()),
+        },
+        None => Ok(fun(
+            function_expression("from_cfrac").unwrap(),
+            [Expression::Vector(terms)],
+        )),
+    }
+}
+
+#[function(
+    name = "convergents",
+    description = "convergents of the simple continued fraction with the given terms, i.e. the values of its initial segments, which are the best rational approximations of its value",
+    examples = r#"[
+        ("convergents([3, 7, 15, 1, 292])", "[3, 22/7, 333/106, 355/113, 103993/33102]"),
+        ("convergents(cfrac(sqrt(2), 5))", "[1, 3/2, 7/5, 17/12, 41/29]"),
+        ("convergents([])", "[]"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "rational approximation",
+    ]"#
+)]
+fn convergents(terms: Vector, options: &Options) -> Result<Expression, FunctionError> {
+    match convergents_of(&terms, options)? {
+        Some(convergents) => Ok(Expression::Vector(Vector::from_vec(
+            convergents.into_iter().map(Expression::from).collect(),
+        ))),
+        None => Ok(fun(
+            function_expression("convergents").unwrap(),
+            [Expression::Vector(terms)],
+        )),
+    }
+}
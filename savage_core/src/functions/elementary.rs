@@ -0,0 +1,343 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{Integer as _, Signed, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    evaluate::{from_f64, Options, Sign, CONSTANTS},
+    expression::{Complex, Expression, Rational, RationalRepresentation, Type},
+    functions::function_expression,
+    helpers::*,
+    surd::Surd,
+};
+
+impl Sign {
+    /// Returns the sign with the given side of zero, which is
+    /// the positive side if `non_negative` is `true`, and
+    /// excluding zero if `strict` is `true`.
+    fn from_parts(non_negative: bool, strict: bool) -> Self {
+        match (non_negative, strict) {
+            (true, true) => Sign::Positive,
+            (true, false) => Sign::NonNegative,
+            (false, true) => Sign::Negative,
+            (false, false) => Sign::NonPositive,
+        }
+    }
+
+    /// Returns whether the sign is on the positive side of zero,
+    /// and whether it excludes zero.
+    fn parts(self) -> (bool, bool) {
+        match self {
+            Sign::Positive => (true, true),
+            Sign::NonNegative => (true, false),
+            Sign::Negative => (false, true),
+            Sign::NonPositive => (false, false),
+        }
+    }
+}
+
+/// Returns the identifier of the function applied by the given function value, if any.
+pub(super) fn function_name(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::FunctionValue(function, _) => match &**function {
+            Expression::Variable(identifier) | Expression::Function(identifier, _) => {
+                Some(identifier)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the sign of the given expression if it is a real number,
+/// or if it can be deduced from the signs assumed for its variables.
+pub(super) fn sign(expression: &Expression, options: &Options) -> Option<Sign> {
+    use crate::expression::Expression::*;
+
+    if let Type::Number(z, _) = expression.typ() {
+        return if !z.im.is_zero() {
+            None
+        } else if z.re.is_zero() {
+            // Zero is both non-negative and non-positive.
+            Some(Sign::NonNegative)
+        } else {
+            Some(Sign::from_parts(z.re.is_positive(), true))
+        };
+    }
+
+    match expression {
+        Variable(identifier) if CONSTANTS.contains(&identifier.as_str()) => Some(Sign::Positive),
+        Variable(identifier) => options.assumptions.get(identifier).copied(),
+        FunctionValue(_, _) => match function_name(expression) {
+            Some("abs") | Some("sqrt") => Some(Sign::NonNegative),
+            _ => None,
+        },
+        Negation(a) => {
+            let (non_negative, strict) = sign(a, options)?.parts();
+            Some(Sign::from_parts(!non_negative, strict))
+        }
+        Sum(a, b) => {
+            let (a_non_negative, a_strict) = sign(a, options)?.parts();
+            let (b_non_negative, b_strict) = sign(b, options)?.parts();
+
+            if a_non_negative == b_non_negative {
+                Some(Sign::from_parts(a_non_negative, a_strict || b_strict))
+            } else {
+                None
+            }
+        }
+        Product(a, b) | Quotient(a, b) => {
+            let (a_non_negative, a_strict) = sign(a, options)?.parts();
+            let (b_non_negative, b_strict) = sign(b, options)?.parts();
+
+            Some(Sign::from_parts(
+                a_non_negative == b_non_negative,
+                a_strict && b_strict,
+            ))
+        }
+        Power(a, b) => match (sign(a, options), &**b) {
+            (Some(Sign::Positive), _) => Some(Sign::Positive),
+            (_, Integer(n)) if n.is_even() => Some(Sign::NonNegative),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the absolute value of the given number.
+fn number_abs(z: Complex, representation: RationalRepresentation) -> Expression {
+    if z.im.is_zero() {
+        Expression::Rational(z.re.abs(), representation)
+    } else {
+        number_sqrt(z.norm_sqr(), representation)
+    }
+}
+
+/// Returns the principal square root of the given real number, simplified to a rational
+/// multiple of a square root of a square-free integer (times `i` if the number is negative)
+/// unless the number is a decimal whose square root is not rational.
+fn number_sqrt(x: Rational, representation: RationalRepresentation) -> Expression {
+    if x.is_zero() {
+        return Expression::Rational(x, representation);
+    }
+
+    let root = Surd::root(&x.abs(), 1, 2)
+        .filter(|root| root.is_rational() || representation == RationalRepresentation::Fraction);
+
+    match root {
+        Some(root) if x.is_negative() => {
+            let factor = Complex::new(Rational::zero(), root.coefficient().clone());
+
+            if root.is_rational() {
+                Expression::Complex(factor, representation)
+            } else {
+                Expression::Complex(factor, representation) * root.radical()
+            }
+        }
+        Some(root) => root.to_expression(representation),
+        None => fun(
+            function_expression("sqrt").unwrap(),
+            [Expression::Rational(x, representation)],
+        ),
+    }
+}
+
+/// Returns the absolute value of the given symbolic expression,
+/// simplified using the signs assumed for its variables.
+fn symbolic_abs(x: Expression, options: &Options) -> Expression {
+    match (sign(&x, options), x) {
+        (Some(Sign::Positive | Sign::NonNegative), x) => x,
+        (Some(Sign::Negative | Sign::NonPositive), x) => -x,
+        (None, Expression::Negation(a)) => symbolic_abs(*a, options),
+        (None, x) => fun(function_expression("abs").unwrap(), [x]),
+    }
+}
+
+#[function(
+    name = "abs",
+    description = "absolute value of `x`",
+    examples = r#"[
+        ("abs(-3/2)", "3/2"),
+        ("abs(3 + 4 * i)", "5"),
+        ("abs(1 + i)", "sqrt(2)"),
+        ("abs(-x)", "abs(x)"),
+        ("abs(x^2 + 1)", "x ^ 2 + 1"),
+    ]"#,
+    categories = r#"[
+        "elementary functions",
+    ]"#
+)]
+fn abs(x: Expression, options: &Options) -> Result<Expression, Expression> {
+    match x.typ() {
+        Type::Number(z, representation) => Ok(number_abs(z, representation)),
+        Type::Arithmetic | Type::Unknown => Ok(symbolic_abs(x, options)),
+        _ => Err(x),
+    }
+}
+
+#[function(
+    name = "sqrt",
+    description = "principal square root of `x`",
+    examples = r#"[
+        ("sqrt(16/9)", "4/3"),
+        ("sqrt(-4)", "2*i"),
+        ("sqrt(2)", "sqrt(2)"),
+        ("sqrt(8)", "2 * sqrt(2)"),
+        ("sqrt(-12)", "2*i * sqrt(3)"),
+        ("sqrt(2) * sqrt(6)", "2 * sqrt(3)"),
+        ("1 / sqrt(2)", "sqrt(2) / 2"),
+        ("sqrt(x^2)", "abs(x)"),
+        ("sqrt((x - 1)^2)", "abs(x - 1)"),
+        ("sqrt(pi^2)", "pi"),
+    ]"#,
+    categories = r#"[
+        "elementary functions",
+    ]"#
+)]
+fn sqrt(x: Expression, options: &Options) -> Result<Expression, Expression> {
+    match x.typ() {
+        Type::Number(z, representation) if z.im.is_zero() => Ok(number_sqrt(z.re, representation)),
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => Ok(match x {
+            // The square root of the square of a real number is its absolute value.
+            Expression::Power(a, b) if *b == int(2) => symbolic_abs(*a, options),
+            _ => fun(function_expression("sqrt").unwrap(), [x]),
+        }),
+        _ => Err(x),
+    }
+}
+
+/// Returns the principal argument of the given non-zero number, which is exact
+/// if the number lies on one of the axes or diagonals of the complex plane,
+/// or `None` if the number is zero.
+fn number_arg(z: Complex, representation: RationalRepresentation) -> Option<Expression> {
+    if z.is_zero() {
+        return None;
+    }
+
+    if z.im.is_zero() {
+        return Some(if z.re.is_positive() {
+            Expression::Rational(Rational::zero(), representation)
+        } else {
+            var("pi")
+        });
+    }
+
+    if z.re.is_zero() || z.re.abs() == z.im.abs() {
+        let angle = if z.re.is_zero() {
+            var("pi") / int(2)
+        } else if z.re.is_positive() {
+            var("pi") / int(4)
+        } else {
+            int(3) * var("pi") / int(4)
+        };
+
+        return Some(if z.im.is_negative() { -angle } else { angle });
+    }
+
+    // Scaling both parts to at most 1 keeps them within the range of `f64`.
+    let scale = z.re.abs().max(z.im.abs());
+    let re = (&z.re / &scale).to_f64()?;
+    let im = (&z.im / &scale).to_f64()?;
+
+    Some(Expression::Rational(
+        from_f64(im.atan2(re)),
+        RationalRepresentation::Decimal,
+    ))
+}
+
+#[function(
+    name = "re",
+    description = "real part of `z`",
+    examples = r#"[
+        ("re(3 - 4 * i)", "3"),
+        ("re(2.5 * i)", "0"),
+        ("re(-7/2)", "-7/2"),
+        ("re(pi)", "pi"),
+        ("re(z)", "re(z)"),
+    ]"#,
+    categories = r#"[
+        "complex numbers",
+    ]"#
+)]
+fn re(z: Expression, options: &Options) -> Result<Expression, Expression> {
+    match z.typ() {
+        Type::Number(z, representation) => Ok(Expression::Rational(z.re, representation)),
+        Type::Arithmetic | Type::Unknown if sign(&z, options).is_some() => Ok(z),
+        Type::Arithmetic | Type::Unknown => Ok(fun(function_expression("re").unwrap(), [z])),
+        _ => Err(z),
+    }
+}
+
+#[function(
+    name = "im",
+    description = "imaginary part of `z`",
+    examples = r#"[
+        ("im(3 - 4 * i)", "-4"),
+        ("im(2.5 * i)", "2.5"),
+        ("im(-7/2)", "0"),
+        ("im(pi)", "0"),
+        ("im(z)", "im(z)"),
+    ]"#,
+    categories = r#"[
+        "complex numbers",
+    ]"#
+)]
+fn im(z: Expression, options: &Options) -> Result<Expression, Expression> {
+    match z.typ() {
+        Type::Number(z, representation) => Ok(Expression::Rational(z.im, representation)),
+        Type::Arithmetic | Type::Unknown if sign(&z, options).is_some() => Ok(int(0)),
+        Type::Arithmetic | Type::Unknown => Ok(fun(function_expression("im").unwrap(), [z])),
+        _ => Err(z),
+    }
+}
+
+#[function(
+    name = "arg",
+    description = "principal argument of `z`, in the interval `(-pi, pi]`",
+    examples = r#"[
+        ("arg(2)", "0"),
+        ("arg(-2)", "pi"),
+        ("arg(3 * i)", "pi / 2"),
+        ("arg(-1 - i)", "-(3 * pi / 4)"),
+        ("arg(3 + 4 * i)", "0.927295218001612"),
+        ("arg(pi)", "0"),
+        ("arg(z)", "arg(z)"),
+    ]"#,
+    categories = r#"[
+        "complex numbers",
+    ]"#
+)]
+fn arg(z: Expression, options: &Options) -> Result<Expression, Expression> {
+    match z.typ() {
+        Type::Number(z_number, representation) => number_arg(z_number, representation).ok_or(z),
+        Type::Arithmetic | Type::Unknown => Ok(match sign(&z, options) {
+            Some(Sign::Positive) => int(0),
+            Some(Sign::Negative) => var("pi"),
+            _ => fun(function_expression("arg").unwrap(), [z]),
+        }),
+        _ => Err(z),
+    }
+}
+
+#[function(
+    name = "conj",
+    description = "complex conjugate of `z`",
+    examples = r#"[
+        ("conj(3 - 4 * i)", "3 + 4*i"),
+        ("conj(1/2)", "1/2"),
+        ("conj(pi)", "pi"),
+        ("conj(z)", "conj(z)"),
+    ]"#,
+    categories = r#"[
+        "complex numbers",
+    ]"#
+)]
+fn conj(z: Expression, options: &Options) -> Result<Expression, Expression> {
+    match z.typ() {
+        Type::Number(z, representation) => Ok(Expression::Complex(z.conj(), representation)),
+        Type::Arithmetic | Type::Unknown if sign(&z, options).is_some() => Ok(z),
+        Type::Arithmetic | Type::Unknown => Ok(fun(function_expression("conj").unwrap(), [z])),
+        _ => Err(z),
+    }
+}
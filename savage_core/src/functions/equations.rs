@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use savage_macros::function;
+
+use crate::{
+    evaluate::Options,
+    expression::{Expression, Type, Vector},
+    functions::{function_expression, FunctionError},
+    helpers::*,
+    solve::{solve_linear_system, LinearSystemError},
+};
+
+/// Returns the sides of the expression if it is an equation, `Ok(None)` if the
+/// expression might evaluate to an equation, and the expression as an error otherwise.
+#[allow(clippy::result_large_err)]
+fn sides(equation: &Expression) -> Result<Option<(Expression, Expression)>, Expression> {
+    match equation {
+        Expression::Equal(lhs, rhs) => Ok(Some(((**lhs).clone(), (**rhs).clone()))),
+        _ => match equation.typ() {
+            Type::Arithmetic | Type::Unknown => Ok(None),
+            _ => Err(equation.clone()),
+        },
+    }
+}
+
+#[function(
+    name = "lhs",
+    description = "left-hand side of an equation",
+    examples = r#"[
+        ("lhs(2 * x + 1 == 7)", "2 * x + 1"),
+    ]"#,
+    categories = r#"[
+        "equations",
+    ]"#
+)]
+fn lhs(equation: Expression) -> Result<Expression, Expression> {
+    Ok(match sides(&equation)? {
+        Some((lhs, _)) => lhs,
+        None => fun(function_expression("lhs").unwrap(), [equation]),
+    })
+}
+
+#[function(
+    name = "rhs",
+    description = "right-hand side of an equation",
+    examples = r#"[
+        ("rhs(2 * x + 1 == 7)", "7"),
+    ]"#,
+    categories = r#"[
+        "equations",
+    ]"#
+)]
+fn rhs(equation: Expression) -> Result<Expression, Expression> {
+    Ok(match sides(&equation)? {
+        Some((_, rhs)) => rhs,
+        None => fun(function_expression("rhs").unwrap(), [equation]),
+    })
+}
+
+#[function(
+    name = "swap",
+    description = "equation with the left-hand and right-hand sides exchanged",
+    examples = r#"[
+        ("swap(7 == 2 * x + 1)", "2 * x + 1 == 7"),
+    ]"#,
+    categories = r#"[
+        "equations",
+    ]"#
+)]
+fn swap(equation: Expression) -> Result<Expression, Expression> {
+    Ok(match sides(&equation)? {
+        Some((lhs, rhs)) => eq(rhs, lhs),
+        None => fun(function_expression("swap").unwrap(), [equation]),
+    })
+}
+
+#[function(
+    name = "solve",
+    description = "vector of the solutions of a linear or quadratic equation (or of `equation == 0` if `equation` is not an equation) for the variable `x`",
+    examples = r#"[
+        ("solve(2 * x + 1 == 7, x)", "[3]"),
+        ("solve(x^2 == 4, x)", "[-2, 2]"),
+        ("solve(x^2 + 2*x - 1, x)", "[-1 - sqrt(2), -1 + sqrt(2)]"),
+        ("solve(x^2 + 4, x)", "[-2*i, 2*i]"),
+        ("solve(x^2 + 2, x)", "[-i * sqrt(2), i * sqrt(2)]"),
+        ("solve(a * x == b, x)", "[b / a]"),
+        ("solve(x + 1 == x, x)", "[]"),
+    ]"#,
+    categories = r#"[
+        "equations",
+    ]"#
+)]
+fn solve(equation: Expression, x: Expression, options: &Options) -> Result<Expression, Expression> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x),
+    };
+
+    if let Type::Boolean(_) | Type::String(_) | Type::Function(_, _) = equation.typ() {
+        if !matches!(equation, Expression::Equal(_, _)) {
+            return Err(equation);
+        }
+    }
+
+    Ok(match equation.solve(identifier, options) {
+        Some(solutions) => Expression::Vector(Vector::from_vec(solutions)),
+        None => fun(function_expression("solve").unwrap(), [equation, x]),
+    })
+}
+
+#[function(
+    name = "linsolve",
+    description = "vector of the values of the given variables that solve the system of linear equations (or expressions that are equal to zero), if the solution is unique",
+    examples = r#"[
+        ("linsolve([x + y == 3, x - y == 1], [x, y])", "[2, 1]"),
+        ("linsolve([2*x + y - z == 8, -3*x - y + 2*z == -11, -2*x + y + 2*z == -3], [x, y, z])", "[2, 3, -1]"),
+        ("linsolve([x + 2*y == a, y == 1], [x, y])", "[a - 2, 1]"),
+    ]"#,
+    categories = r#"[
+        "equations",
+        "linear algebra",
+    ]"#
+)]
+fn linsolve(
+    equations: Vector,
+    variables: Vector,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    let identifiers = variables
+        .iter()
+        .map(|variable| match variable {
+            Expression::Variable(identifier) => Ok(identifier.as_str()),
+            _ => Err(variable.clone()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for equation in equations.iter() {
+        if let Type::Boolean(_) | Type::String(_) | Type::Function(_, _) = equation.typ() {
+            if !matches!(equation, Expression::Equal(_, _)) {
+                return Err(equation.clone().into());
+            }
+        }
+    }
+
+    match solve_linear_system(equations.as_slice(), &identifiers, options) {
+        Ok(Some(solution)) => Ok(Expression::Vector(Vector::from_vec(solution))),
+        Ok(None) => Ok(fun(
+            function_expression("linsolve").unwrap(),
+            [Expression::Vector(equations), Expression::Vector(variables)],
+        )),
+        Err(LinearSystemError::Inconsistent) => Err(FunctionError::Failed(
+            "the system of equations has no solution".to_owned(),
+        )),
+        Err(LinearSystemError::Underdetermined) => Err(FunctionError::Failed(
+            "the system of equations has infinitely many solutions".to_owned(),
+        )),
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{One, Signed, Zero};
+use savage_macros::function;
+
+use crate::{
+    evaluate::Options,
+    expression::{Expression, Integer, Rational, RationalRepresentation, Type},
+    functions::{
+        elementary::{function_name, sign},
+        function_expression, inexact_value,
+    },
+    helpers::*,
+};
+
+/// Maximum number of bits of the numerator and denominator of a number
+/// for which exact logarithms are searched.
+const MAX_BITS: u64 = 4096;
+
+/// Returns the exponent `k` if the expression is `e ^ k` (or `e`)
+/// for an exponent `k` that is known to be real, or `None` otherwise.
+fn exponent_of_e(expression: &Expression, options: &Options) -> Option<Expression> {
+    match expression {
+        Expression::Variable(identifier) if identifier == "e" => Some(int(1)),
+        Expression::Power(a, k) if **a == var("e") && sign(k, options).is_some() => {
+            Some((**k).clone())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the `n`th root of the non-negative integer if it is a perfect `n`th power.
+fn perfect_root(x: &Integer, n: u32) -> Option<Integer> {
+    let root = x.nth_root(n);
+
+    if root.pow(n) == *x {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+/// Returns the number `r` and the largest integer `m` such that `x == r ^ m`
+/// for the positive rational number `x`, or `None` if `x` is too large.
+fn primitive_root(x: &Rational) -> Option<(Rational, u32)> {
+    let bits = x.numer().bits().max(x.denom().bits());
+
+    if bits > MAX_BITS {
+        return None;
+    }
+
+    for m in (2..=bits as u32).rev() {
+        if let (Some(a), Some(b)) = (perfect_root(x.numer(), m), perfect_root(x.denom(), m)) {
+            return Some((Rational::new(a, b), m));
+        }
+    }
+
+    Some((x.clone(), 1))
+}
+
+/// Returns the rational number `q` such that `b ^ q == x` for the positive
+/// rational numbers `b != 1` and `x`, or `None` if there is no such number
+/// or the numbers are too large.
+fn rational_logarithm(b: &Rational, x: &Rational) -> Option<Rational> {
+    if x.is_one() {
+        return Some(Rational::zero());
+    }
+
+    // If `b == r ^ m` and `x == s ^ n` where `r` and `s` are not perfect powers,
+    // then `b ^ q == x` for some rational `q` if and only if `r == s` or `r == 1/s`.
+    let (r, m) = primitive_root(b)?;
+    let (s, n) = primitive_root(x)?;
+
+    let q = Rational::new(n.into(), m.into());
+
+    if r == s {
+        Some(q)
+    } else if r == s.recip() {
+        Some(-q)
+    } else {
+        None
+    }
+}
+
+#[function(
+    name = "exp",
+    description = "exponential function of `x`, which is the power `e ^ x`",
+    examples = r#"[
+        ("exp(0)", "1"),
+        ("exp(1)", "e"),
+        ("exp(2)", "e ^ 2"),
+        ("exp(0.5)", "1.64872127070013"),
+        ("exp(ln(x))", "x"),
+        ("exp(x)", "e ^ x"),
+    ]"#,
+    categories = r#"[
+        "elementary functions",
+    ]"#
+)]
+fn exp(x: Expression) -> Result<Expression, Expression> {
+    match x.typ() {
+        Type::Number(z, _) if z.is_zero() => Ok(int(1)),
+        Type::Number(z, _) if z.is_one() => Ok(var("e")),
+        Type::Number(z, RationalRepresentation::Decimal) if z.im.is_zero() => {
+            Ok(inexact_value("exp", [x]))
+        }
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => Ok(match x {
+            Expression::FunctionValue(_, arguments) if function_name(&x) == Some("ln") => {
+                arguments[0].clone()
+            }
+            _ => pow(var("e"), x),
+        }),
+        _ => Err(x),
+    }
+}
+
+#[function(
+    name = "ln",
+    description = "natural logarithm of `x`, which is evaluated exactly for powers of `e` and approximated for positive decimal numbers",
+    examples = r#"[
+        ("ln(1)", "0"),
+        ("ln(e)", "1"),
+        ("ln(e ^ 3)", "3"),
+        ("ln(e ^ (-1/2))", "-1/2"),
+        ("ln(2)", "ln(2)"),
+        ("ln(0.5)", "-0.693147180559945"),
+        ("ln(-1)", "ln(-1)"),
+        ("ln(x)", "ln(x)"),
+    ]"#,
+    categories = r#"[
+        "elementary functions",
+    ]"#
+)]
+fn ln(x: Expression, options: &Options) -> Result<Expression, Expression> {
+    if let Some(k) = exponent_of_e(&x, options) {
+        return Ok(k);
+    }
+
+    match x.typ() {
+        Type::Number(z, _) if z.is_zero() => Err(x),
+        Type::Number(z, representation) if z.is_one() => {
+            Ok(Expression::Rational(Rational::zero(), representation))
+        }
+        Type::Number(z, RationalRepresentation::Decimal)
+            if z.im.is_zero() && z.re.is_positive() =>
+        {
+            Ok(inexact_value("ln", [x]))
+        }
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+            Ok(fun(function_expression("ln").unwrap(), [x]))
+        }
+        _ => Err(x),
+    }
+}
+
+#[function(
+    name = "log",
+    description = "logarithm of `x` to the base `b`, which is evaluated exactly if `x` is a rational power of `b` and approximated for other positive decimal numbers",
+    examples = r#"[
+        ("log(10, 1000)", "3"),
+        ("log(2, 1/8)", "-3"),
+        ("log(8, 2)", "1/3"),
+        ("log(1/4, 32)", "-5/2"),
+        ("log(9, 1)", "0"),
+        ("log(10, 2)", "log(10, 2)"),
+        ("log(10, 0.5)", "-0.301029995663981"),
+        ("log(10, 0.001)", "-3"),
+        ("log(b, b ^ 2)", "2"),
+        ("log(2, x)", "log(2, x)"),
+    ]"#,
+    categories = r#"[
+        "elementary functions",
+    ]"#
+)]
+fn log(b: Expression, x: Expression, options: &Options) -> Result<Expression, Expression> {
+    match (b.typ(), x.typ()) {
+        (Type::Number(base, _), _)
+            if !base.im.is_zero() || !base.re.is_positive() || base.re.is_one() =>
+        {
+            Err(b)
+        }
+        (_, Type::Number(z, _)) if z.is_zero() => Err(x),
+        (Type::Number(base, b_representation), Type::Number(z, x_representation))
+            if z.im.is_zero() && z.re.is_positive() =>
+        {
+            let representation = b_representation.merge(x_representation);
+
+            Ok(match rational_logarithm(&base.re, &z.re) {
+                Some(q) => Expression::Rational(q, representation),
+                None if representation == RationalRepresentation::Decimal => {
+                    inexact_value("log", [b, x])
+                }
+                None => fun(function_expression("log").unwrap(), [b, x]),
+            })
+        }
+        (
+            Type::Number(_, _) | Type::Arithmetic | Type::Unknown,
+            Type::Number(_, _) | Type::Arithmetic | Type::Unknown,
+        ) => Ok(match x {
+            _ if x == b => int(1),
+            Expression::Power(a, k) if *a == b && sign(&k, options).is_some() => *k,
+            _ => fun(function_expression("log").unwrap(), [b, x]),
+        }),
+        (Type::Number(_, _) | Type::Arithmetic | Type::Unknown, _) => Err(x),
+        _ => Err(b),
+    }
+}
@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::Zero;
+use savage_macros::function;
+
+use crate::{
+    expression::{Expression, Type},
+    helpers::interval as interval_expression,
+};
+
+#[function(
+    name = "interval",
+    description = "closed interval of real numbers from `a` to `b`, on which arithmetic yields intervals containing all possible results",
+    examples = r#"[
+        ("interval(1, 2)", "interval(1, 2)"),
+        ("interval(1, 2) + interval(-3, 5)", "interval(-2, 7)"),
+        ("interval(1, 2) - interval(-3, 5)", "interval(-4, 5)"),
+        ("interval(-1, 2) * interval(3, 4)", "interval(-4, 8)"),
+        ("1 / interval(2, 4)", "interval(1/4, 1/2)"),
+        ("interval(-2, 3) ^ 2", "interval(0, 9)"),
+        ("interval(9.8, 9.82) * 2.5", "interval(24.5, 24.55)"),
+        ("interval(1, 2) < interval(3, 4)", "true"),
+        ("interval(1, 3) < interval(2, 4)", "interval(1, 3) < interval(2, 4)"),
+        ("interval(0, x)", "interval(0, x)"),
+    ]"#,
+    categories = r#"[
+        "intervals",
+    ]"#
+)]
+fn interval(a: Expression, b: Expression) -> Result<Expression, Expression> {
+    for bound in [&a, &b] {
+        match bound.typ() {
+            Type::Number(z, _) if z.im.is_zero() => (),
+            Type::Arithmetic | Type::Unknown => (),
+            _ => return Err(bound.clone()),
+        }
+    }
+
+    match (a.typ(), b.typ()) {
+        (Type::Number(x, _), Type::Number(y, _)) if x.re > y.re => Err(b),
+        _ => Ok(interval_expression(a, b)),
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::fs;
+
+use num::ToPrimitive;
+
+use savage_macros::function;
+
+use crate::{
+    csv::{CsvFormatOptions, CsvOptions, FromCsv, ToCsv},
+    expression::{Expression, Matrix},
+    functions::{FunctionError, NonNegativeInteger},
+};
+
+/// Returns the matrix read from the CSV file at the given path.
+fn read_csv_file(path: &str, options: &CsvOptions) -> Result<Matrix, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| format!("cannot read file '{}': {}", path, error))?;
+
+    Matrix::from_csv(&text, options).map_err(|error| format!("'{}': {}", path, error))
+}
+
+#[function(
+    name = "read_csv",
+    description = "matrix of the numbers in the CSV file at `path`, with the delimiter and header row detected automatically",
+    examples = r#"[]"#,
+    categories = r#"[
+        "input/output",
+    ]"#,
+    pure = false
+)]
+fn read_csv(path: String) -> Result<Matrix, String> {
+    read_csv_file(&path, &CsvOptions::default())
+}
+
+#[function(
+    name = "read_csv_with",
+    description = "matrix of the numbers in the CSV file at `path`, with cells separated by `delimiter` and the first row skipped if `header` is true",
+    examples = r#"[]"#,
+    categories = r#"[
+        "input/output",
+    ]"#,
+    pure = false
+)]
+fn read_csv_with(path: String, delimiter: String, header: bool) -> Result<Matrix, FunctionError> {
+    let mut characters = delimiter.chars();
+
+    let delimiter = match (characters.next(), characters.next()) {
+        (Some(delimiter), None) => delimiter,
+        _ => return Err(Expression::String(delimiter).into()),
+    };
+
+    read_csv_file(
+        &path,
+        &CsvOptions {
+            delimiter: Some(delimiter),
+            has_header: Some(header),
+        },
+    )
+    .map_err(FunctionError::from)
+}
+
+/// Writes the matrix to the CSV file at the given path, and returns the matrix.
+fn write_csv_file(m: Matrix, path: &str, options: &CsvFormatOptions) -> Result<Matrix, String> {
+    fs::write(path, m.to_csv(options))
+        .map_err(|error| format!("cannot write file '{}': {}", path, error))?;
+
+    Ok(m)
+}
+
+#[function(
+    name = "write_csv",
+    description = "writes the matrix `m` to the CSV file at `path`, with numbers written exactly, and returns `m`",
+    examples = r#"[]"#,
+    categories = r#"[
+        "input/output",
+    ]"#,
+    pure = false
+)]
+fn write_csv(m: Matrix, path: String) -> Result<Matrix, String> {
+    write_csv_file(m, &path, &CsvFormatOptions::default())
+}
+
+#[function(
+    name = "write_csv_with",
+    description = "writes the matrix `m` to the CSV file at `path`, with numbers rounded to `digits` decimal places, and returns `m`",
+    examples = r#"[]"#,
+    categories = r#"[
+        "input/output",
+    ]"#,
+    pure = false
+)]
+fn write_csv_with(
+    m: Matrix,
+    path: String,
+    digits: NonNegativeInteger,
+) -> Result<Matrix, FunctionError> {
+    let precision = match digits.to_usize() {
+        Some(precision) => precision,
+        None => return Err(Expression::Integer(digits).into()),
+    };
+
+    write_csv_file(
+        m,
+        &path,
+        &CsvFormatOptions {
+            precision: Some(precision),
+            ..CsvFormatOptions::default()
+        },
+    )
+    .map_err(FunctionError::from)
+}
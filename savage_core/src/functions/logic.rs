@@ -1,8 +1,15 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
+use std::collections::HashSet;
+
 use savage_macros::function;
 
+use crate::{
+    expression::{Expression, Type},
+    helpers::var,
+};
+
 #[function(
     name = "and",
     description = "logical conjunction",
@@ -20,3 +27,285 @@ use savage_macros::function;
 fn and(a: bool, b: bool) -> bool {
     a && b
 }
+
+/// Literal in a normal form, consisting of an atomic formula and its polarity.
+type Literal = (Expression, bool);
+
+/// Returns the clauses (if `conjunctive` is `true`) or terms (if `conjunctive`
+/// is `false`) of the conjunctive or disjunctive normal form of the expression,
+/// or of its negation if `positive` is `false`.
+fn normal_form(expression: &Expression, positive: bool, conjunctive: bool) -> Vec<Vec<Literal>> {
+    use crate::expression::Expression::{And, Boolean, Not, Or};
+
+    match expression {
+        // The empty conjunction is `true`, and the empty disjunction is `false`.
+        Boolean(value) => {
+            if (*value == positive) == conjunctive {
+                Vec::new()
+            } else {
+                vec![Vec::new()]
+            }
+        }
+        Not(a) => normal_form(a, !positive, conjunctive),
+        And(a, b) | Or(a, b) => {
+            let a = normal_form(a, positive, conjunctive);
+            let b = normal_form(b, positive, conjunctive);
+
+            if matches!(expression, And(_, _)) == (positive == conjunctive) {
+                a.into_iter().chain(b).collect()
+            } else {
+                // Distribute the inner operator over the outer one.
+                let mut groups = Vec::new();
+
+                for group_a in &a {
+                    for group_b in &b {
+                        groups.push(group_a.iter().chain(group_b).cloned().collect());
+                    }
+                }
+
+                groups
+            }
+        }
+        _ => vec![vec![(expression.clone(), positive)]],
+    }
+}
+
+/// Removes duplicate literals, complementary literal pairs, and groups
+/// subsumed by other groups from the given normal form.
+fn reduce(groups: Vec<Vec<Literal>>) -> Vec<Vec<Literal>> {
+    let mut reduced_groups: Vec<Vec<Literal>> = Vec::new();
+
+    for group in groups {
+        let mut reduced_group: Vec<Literal> = Vec::new();
+
+        for literal in group {
+            if !reduced_group.contains(&literal) {
+                reduced_group.push(literal);
+            }
+        }
+
+        // A clause containing complementary literals is always true,
+        // and a term containing complementary literals is always false.
+        // Either way, the group doesn't affect the value of the normal form.
+        if reduced_group
+            .iter()
+            .any(|(atom, positive)| reduced_group.contains(&(atom.clone(), !positive)))
+        {
+            continue;
+        }
+
+        reduced_groups.push(reduced_group);
+    }
+
+    let subsumes = |a: &Vec<Literal>, b: &Vec<Literal>| a.iter().all(|literal| b.contains(literal));
+
+    let mut result: Vec<Vec<Literal>> = Vec::new();
+
+    for (i, group) in reduced_groups.iter().enumerate() {
+        // Keep only the first of several equivalent groups.
+        let subsumed = reduced_groups.iter().enumerate().any(|(j, other)| {
+            j != i && subsumes(other, group) && (!subsumes(group, other) || j < i)
+        });
+
+        if !subsumed {
+            result.push(group.clone());
+        }
+    }
+
+    result
+}
+
+/// Returns the expression represented by the given normal form.
+fn expression(groups: Vec<Vec<Literal>>, conjunctive: bool) -> Expression {
+    use crate::expression::Expression::{And, Or};
+
+    let connect = |conjunction: bool, a: Expression, b: Expression| {
+        if conjunction {
+            And(Box::new(a), Box::new(b))
+        } else {
+            Or(Box::new(a), Box::new(b))
+        }
+    };
+
+    groups
+        .into_iter()
+        .map(|group| {
+            group
+                .into_iter()
+                .map(|(atom, positive)| if positive { atom } else { !atom })
+                .reduce(|a, b| connect(!conjunctive, a, b))
+                .unwrap_or(Expression::Boolean(!conjunctive))
+        })
+        .reduce(|a, b| connect(conjunctive, a, b))
+        .unwrap_or(Expression::Boolean(conjunctive))
+}
+
+/// Returns an error if the expression is known not to be a boolean expression.
+#[allow(clippy::result_large_err)]
+fn check_boolean(expression: &Expression) -> Result<(), Expression> {
+    match expression.typ() {
+        Type::Boolean(_) | Type::Unknown => Ok(()),
+        _ => Err(expression.clone()),
+    }
+}
+
+#[function(
+    name = "to_cnf",
+    description = "conjunctive normal form of a boolean expression",
+    examples = r#"[
+        ("to_cnf(a || b && c)", "(a || b) && (a || c)"),
+        ("to_cnf(!(a && b) || c)", "!a || !b || c"),
+        ("to_cnf(!(a || b) || a && c)", "(!a || c) && (!b || a) && (!b || c)"),
+        ("to_cnf((a || b) && (a || b || c))", "a || b"),
+        ("to_cnf(a || !a)", "true"),
+    ]"#,
+    categories = r#"[
+        "logic",
+        "normal forms",
+    ]"#
+)]
+fn to_cnf(expression: Expression) -> Result<Expression, Expression> {
+    check_boolean(&expression)?;
+
+    Ok(self::expression(
+        reduce(normal_form(&expression, true, true)),
+        true,
+    ))
+}
+
+#[function(
+    name = "to_dnf",
+    description = "disjunctive normal form of a boolean expression",
+    examples = r#"[
+        ("to_dnf((a || b) && c)", "a && c || b && c"),
+        ("to_dnf(!(a || b && c))", "!a && !b || !a && !c"),
+        ("to_dnf(a && !a || b)", "b"),
+        ("to_dnf(a && b || a)", "a"),
+    ]"#,
+    categories = r#"[
+        "logic",
+        "normal forms",
+    ]"#
+)]
+fn to_dnf(expression: Expression) -> Result<Expression, Expression> {
+    check_boolean(&expression)?;
+
+    Ok(self::expression(
+        reduce(normal_form(&expression, true, false)),
+        false,
+    ))
+}
+
+/// Collects the operands of the given (possibly nested) conjunction or
+/// disjunction, taking into account negations, into `operands`.
+fn collect_operands(
+    expression: &Expression,
+    positive: bool,
+    conjunctive: bool,
+    operands: &mut Vec<(Expression, bool)>,
+) {
+    use crate::expression::Expression::{And, Not, Or};
+
+    match expression {
+        Not(a) => collect_operands(a, !positive, conjunctive, operands),
+        And(a, b) | Or(a, b) if matches!(expression, And(_, _)) == (positive == conjunctive) => {
+            collect_operands(a, positive, conjunctive, operands);
+            collect_operands(b, positive, conjunctive, operands);
+        }
+        _ => operands.push((expression.clone(), positive)),
+    }
+}
+
+/// Returns a literal that is equivalent to the expression (or its negation
+/// if `positive` is `false`), given the definitions of newly introduced
+/// variables, which are added to `clauses`.
+fn tseitin_literal(
+    expression: &Expression,
+    positive: bool,
+    clauses: &mut Vec<Vec<Literal>>,
+    fresh_variable: &mut dyn FnMut() -> Expression,
+) -> Literal {
+    use crate::expression::Expression::{And, Not, Or};
+
+    match expression {
+        Not(a) => tseitin_literal(a, !positive, clauses, fresh_variable),
+        And(_, _) | Or(_, _) => {
+            let conjunctive = matches!(expression, And(_, _)) == positive;
+
+            let mut operands = Vec::new();
+            collect_operands(expression, positive, conjunctive, &mut operands);
+
+            let literals = operands
+                .iter()
+                .map(|(operand, positive)| {
+                    tseitin_literal(operand, *positive, clauses, fresh_variable)
+                })
+                .collect::<Vec<_>>();
+
+            let variable = fresh_variable();
+
+            // For a conjunction, `x` implies every operand, and all operands together imply `x`.
+            // For a disjunction, every operand implies `x`, and `x` implies some operand.
+            let mut defining_clause = vec![(variable.clone(), conjunctive)];
+
+            for (atom, positive) in literals {
+                clauses.push(vec![
+                    (variable.clone(), !conjunctive),
+                    (atom.clone(), if conjunctive { positive } else { !positive }),
+                ]);
+
+                defining_clause.push((atom, if conjunctive { !positive } else { positive }));
+            }
+
+            clauses.push(defining_clause);
+
+            (variable, true)
+        }
+        _ => (expression.clone(), positive),
+    }
+}
+
+#[function(
+    name = "tseitin_cnf",
+    description = "conjunctive normal form of a boolean expression obtained by the Tseitin transformation, which is equisatisfiable with the expression and grows only linearly with its size, introducing new variables `t_1`, `t_2`, ... (skipping names already in use)",
+    examples = r#"[
+        ("tseitin_cnf(a && b)", "(!t_1 || a) && (!t_1 || b) && (t_1 || !a || !b) && t_1"),
+        ("tseitin_cnf(!(a || t_1))", "(!t_2 || !a) && (!t_2 || !t_1) && (t_2 || a || t_1) && t_2"),
+        ("tseitin_cnf(!a)", "!a"),
+    ]"#,
+    categories = r#"[
+        "logic",
+        "normal forms",
+    ]"#
+)]
+fn tseitin_cnf(expression: Expression) -> Result<Expression, Expression> {
+    check_boolean(&expression)?;
+
+    // Identifiers are the only tokens in the string representation
+    // that consist of alphanumeric characters and underscores.
+    let string = expression.to_string();
+
+    let identifiers = string
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .collect::<HashSet<_>>();
+
+    let mut index = 0;
+
+    let mut fresh_variable = || loop {
+        index += 1;
+
+        let identifier = format!("t_{}", index);
+
+        if !identifiers.contains(identifier.as_str()) {
+            return var(identifier);
+        }
+    };
+
+    let mut clauses = Vec::new();
+
+    let literal = tseitin_literal(&expression, true, &mut clauses, &mut fresh_variable);
+
+    clauses.push(vec![literal]);
+
+    Ok(self::expression(clauses, true))
+}
@@ -1,17 +1,50 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
+mod calculus;
 mod combinatorics;
+mod continued_fractions;
+mod elementary;
+mod equations;
+mod exponential;
+mod intervals;
+mod io;
 mod linear_algebra;
 mod logic;
 mod number_theory;
+mod percentages;
+mod polynomials;
+mod quaternions;
+mod random;
+mod representation;
+mod rounding;
+mod sequences;
+mod special;
+mod strings;
+mod substitution;
+mod summation;
+mod tabulation;
+mod tensors;
+mod trigonometry;
+mod units;
 
 use std::rc::Rc;
 
-use num::Signed;
+use num::{Signed, Zero};
 use savage_macros::functions;
 
-use crate::expression::{Expression, Function as FunctionImplementation, Integer, Matrix};
+use crate::{
+    evaluate::{Error, LimitExceeded, Options},
+    expression::{
+        Expression, Function as FunctionImplementation, Integer, Matrix, RationalRepresentation,
+        Type,
+    },
+    helpers::fun,
+};
+
+/// Number of significant digits of the decimal approximations of function values
+/// at real numbers whose exact values are not known.
+const DIGITS: u32 = 15;
 
 /// Arbitrary-precision non-negative integer.
 /// This type alias is intended for use in function signatures
@@ -28,6 +61,11 @@ pub(crate) type PositiveInteger = Integer;
 /// to mark matrix parameters that must be square matrices.
 pub(crate) type SquareMatrix = Matrix;
 
+/// Arguments collected into a vector.
+/// This type alias is intended for use in function signatures
+/// to mark a final parameter that receives all remaining arguments.
+pub(crate) type Expressions = Vec<Expression>;
+
 /// Function parameter.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Parameter {
@@ -51,6 +89,10 @@ pub enum Parameter {
     SquareMatrix,
     /// Boolean expression, or an expression that can be interpreted as a boolean value.
     Boolean,
+    /// String expression.
+    String,
+    /// Any number of symbolic expressions. Only valid as the last parameter.
+    Expressions,
 }
 
 /// Metadata associated with a function.
@@ -66,6 +108,47 @@ pub struct Metadata {
     pub examples: &'static [(&'static str, &'static str)],
     /// Categories associated with the function.
     pub categories: &'static [&'static str],
+    /// Whether the result of the function depends only on its arguments.
+    pub pure: bool,
+}
+
+/// Error returned by a function proxy.
+pub(crate) enum FunctionError {
+    /// The function was invoked with an invalid argument.
+    InvalidArgument(Expression),
+    /// Evaluating the function would exceed a resource limit.
+    LimitExceeded(LimitExceeded),
+    /// A quantity could not be converted to a unit of a different dimension,
+    /// given by the dimensions of the quantity and the unit.
+    IncompatibleDimensions(Expression, Expression),
+    /// The function failed for another reason, described by the message.
+    Failed(String),
+    /// Evaluating an expression on behalf of the function failed with the error.
+    Evaluation(Box<Error>),
+}
+
+impl From<Expression> for FunctionError {
+    fn from(argument: Expression) -> Self {
+        FunctionError::InvalidArgument(argument)
+    }
+}
+
+impl From<String> for FunctionError {
+    fn from(message: String) -> Self {
+        FunctionError::Failed(message)
+    }
+}
+
+impl From<LimitExceeded> for FunctionError {
+    fn from(limit_exceeded: LimitExceeded) -> Self {
+        FunctionError::LimitExceeded(limit_exceeded)
+    }
+}
+
+impl From<Error> for FunctionError {
+    fn from(error: Error) -> Self {
+        FunctionError::Evaluation(Box::new(error))
+    }
 }
 
 /// Function definition.
@@ -78,27 +161,36 @@ pub struct Function {
 
 /// Returns a regular function implementation that type-checks its arguments
 /// based on the given `parameters` and then invokes the given function `proxy`.
+#[allow(clippy::result_large_err)]
 fn wrap_proxy(
     parameters: &'static [Parameter],
-    proxy: impl Fn(&[Expression]) -> Result<Expression, Expression> + 'static,
+    proxy: impl Fn(&[Expression], &Options) -> Result<Expression, FunctionError> + 'static,
 ) -> Rc<FunctionImplementation> {
     use crate::evaluate::Error::*;
     use crate::expression::Type::{Arithmetic, Boolean as Bool, Unknown};
     use Parameter::*;
 
-    Rc::new(move |expression, arguments, _| {
-        if arguments.len() != parameters.len() {
+    let variadic = parameters.last() == Some(&Expressions);
+
+    let (min_number, max_number) = if variadic {
+        (parameters.len() - 1, usize::MAX)
+    } else {
+        (parameters.len(), parameters.len())
+    };
+
+    Rc::new(move |expression, arguments, _, options| {
+        if arguments.len() < min_number || arguments.len() > max_number {
             return Err(InvalidNumberOfArguments {
                 expression: expression.clone(),
-                min_number: parameters.len(),
-                max_number: parameters.len(),
+                min_number,
+                max_number,
                 given_number: arguments.len(),
             });
         }
 
         for (argument, parameter) in arguments.iter().zip(parameters) {
             if let Bool(None) | Arithmetic | Unknown = argument.typ() {
-                if *parameter != Expression {
+                if *parameter != Expression && *parameter != Expressions {
                     return Ok(expression.clone());
                 }
             }
@@ -132,9 +224,24 @@ fn wrap_proxy(
             }
         }
 
-        proxy(arguments).map_err(|argument| InvalidArgument {
-            expression: expression.clone(),
-            argument,
+        proxy(arguments, options).map_err(|error| match error {
+            FunctionError::InvalidArgument(argument) => InvalidArgument {
+                expression: expression.clone(),
+                argument,
+            },
+            FunctionError::LimitExceeded(limit_exceeded) => limit_exceeded.into_error(expression),
+            FunctionError::IncompatibleDimensions(dimension_1, dimension_2) => {
+                IncompatibleDimensions {
+                    expression: expression.clone(),
+                    dimension_1,
+                    dimension_2,
+                }
+            }
+            FunctionError::Failed(message) => NativeFunctionFailed {
+                expression: expression.clone(),
+                message,
+            },
+            FunctionError::Evaluation(error) => *error,
         })
     })
 }
@@ -143,14 +250,123 @@ fn wrap_proxy(
 pub fn functions() -> Vec<Function> {
     functions!(
         logic::and,
+        logic::to_cnf,
+        logic::to_dnf,
+        logic::tseitin_cnf,
+        elementary::abs,
+        elementary::sqrt,
+        elementary::re,
+        elementary::im,
+        elementary::arg,
+        elementary::conj,
+        exponential::exp,
+        exponential::ln,
+        exponential::log,
+        equations::lhs,
+        equations::rhs,
+        equations::swap,
+        equations::solve,
+        equations::linsolve,
+        polynomials::roots,
+        polynomials::factor,
+        polynomials::root_of,
+        polynomials::apart,
+        polynomials::together,
+        polynomials::expand,
         combinatorics::factorial,
+        combinatorics::binomial,
+        combinatorics::permutations,
+        combinatorics::multinomial,
+        combinatorics::gamma,
+        special::erf,
+        special::erfc,
+        special::zeta,
+        special::exponential_integral,
         linear_algebra::determinant,
+        tensors::shape,
+        tensors::reshape,
+        tensors::tensordot,
+        calculus::diff,
+        calculus::integrate,
+        calculus::nintegrate,
+        calculus::series,
+        calculus::divergence,
+        calculus::curl,
         number_theory::is_prime,
+        number_theory::primality_method,
+        number_theory::prove_prime,
+        number_theory::is_probable_prime,
         number_theory::nth_prime,
         number_theory::prime_pi,
+        number_theory::next_prime,
+        number_theory::prev_prime,
+        number_theory::divisors,
+        number_theory::sigma,
+        number_theory::gcd,
+        number_theory::lcm,
+        number_theory::modulo,
+        number_theory::powmod,
+        number_theory::invmod,
+        number_theory::euler_phi,
+        number_theory::moebius,
+        number_theory::rationalize,
+        continued_fractions::cfrac,
+        continued_fractions::from_cfrac,
+        continued_fractions::convergents,
+        number_theory::to_base,
+        number_theory::to_base_string,
+        number_theory::from_base,
+        sequences::fibonacci,
+        sequences::lucas,
+        sequences::catalan,
+        sequences::bernoulli,
+        sequences::stirling,
+        trigonometry::sin,
+        trigonometry::cos,
+        trigonometry::tan,
+        quaternions::quaternion,
+        quaternions::quaternion_conjugate,
+        quaternions::quaternion_norm,
+        quaternions::quaternion_inverse,
+        quaternions::quaternion_to_matrix,
+        quaternions::matrix_to_quaternion,
+        intervals::interval,
+        representation::decimal,
+        representation::fraction,
+        representation::numeric,
+        representation::numeric_interval,
+        representation::numeric_down,
+        representation::numeric_up,
+        percentages::percent_of,
+        rounding::round_down,
+        rounding::round_up,
+        rounding::round,
+        rounding::floor,
+        rounding::ceil,
+        units::convert,
+        tabulation::table,
+        summation::sum,
+        summation::product,
+        substitution::subs,
+        strings::format,
+        random::random,
+        random::randint,
+        random::randrational,
+        io::read_csv,
+        io::read_csv_with,
+        io::write_csv,
+        io::write_csv_with,
     )
 }
 
+/// Returns whether the function library contains a function with the given name
+/// whose result depends on more than its arguments.
+pub(crate) fn is_impure(name: &str) -> bool {
+    functions()
+        .iter()
+        .any(|function| function.metadata.name == name && !function.metadata.pure)
+}
+
 /// Returns an expression representing the function with the given name,
 /// or `None` if the function library contains no function with that name.
 pub fn function_expression(name: &str) -> Option<Expression> {
@@ -166,12 +382,41 @@ pub fn function_expression(name: &str) -> Option<Expression> {
     None
 }
 
+/// Returns the value of the function with the given name at the given arguments,
+/// approximated as a decimal number if all arguments are real numbers and at least
+/// one of them is a decimal number, or left unevaluated otherwise. Values at exact
+/// arguments are approximated only by `N`, which supports any precision.
+fn inexact_value(name: &str, arguments: impl Into<Vec<Expression>>) -> Expression {
+    let arguments = arguments.into();
+
+    let real_numbers = arguments
+        .iter()
+        .all(|argument| matches!(argument.typ(), Type::Number(z, _) if z.im.is_zero()));
+
+    let decimal = arguments.iter().any(|argument| {
+        matches!(
+            argument.typ(),
+            Type::Number(_, RationalRepresentation::Decimal),
+        )
+    });
+
+    let value = fun(function_expression(name).unwrap(), arguments);
+
+    if real_numbers && decimal {
+        value.approximate(DIGITS)
+    } else {
+        value
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::{collections::HashMap, time::Duration};
 
-    use crate::expression::Expression;
+    use crate::evaluate::{Options, Sign};
+    use crate::expression::{Expression, RationalRepresentation};
     use crate::functions::functions;
+    use crate::helpers::string;
 
     #[track_caller]
     fn t(expression: &str, result: &str) {
@@ -194,4 +439,280 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn read_csv() {
+        let path = std::env::temp_dir().join(format!("savage-{}.csv", std::process::id()));
+        std::fs::write(&path, "x;y\n1;0.5\n2;-1/3\n").unwrap();
+        let escaped_path = path.to_str().unwrap().replace('\\', "\\\\");
+
+        t(
+            &format!("read_csv(\"{}\")", escaped_path),
+            "[[1, 0.5], [2, -1/3]]",
+        );
+        t(
+            &format!("read_csv_with(\"{}\", \";\", true)", escaped_path),
+            "[[1, 0.5], [2, -1/3]]",
+        );
+
+        let error = format!("read_csv_with(\"{}\", \";\", false)", escaped_path)
+            .parse::<Expression>()
+            .unwrap()
+            .evaluate(HashMap::new())
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .ends_with("cell 'x' in line 1, column 1 is not a number"));
+
+        assert!("read_csv(\"\")"
+            .parse::<Expression>()
+            .unwrap()
+            .evaluate(HashMap::new())
+            .is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_csv() {
+        let path = std::env::temp_dir().join(format!("savage-{}-out.csv", std::process::id()));
+        let escaped_path = path.to_str().unwrap().replace('\\', "\\\\");
+
+        t(
+            &format!("write_csv([[1, 1/3], [0.5, x]], \"{}\")", escaped_path),
+            "[[1, 1/3], [0.5, x]]",
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1,1/3\n0.5,x\n");
+
+        t(
+            &format!("write_csv_with([1/3, 2/3], \"{}\", 3)", escaped_path),
+            "[1/3, 2/3]",
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "0.333\n0.667\n");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn format() {
+        let mut options = Options::default();
+        options.format_options.max_decimal_digits = Some(3);
+        options.format_options.rational_representation = Some(RationalRepresentation::Decimal);
+
+        assert_eq!(
+            "format(\"{} ≈ {}\", \"2/3\", 2/3)"
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate_with_options(HashMap::new(), &options),
+            Ok(string("2/3 ≈ 0.667")),
+        );
+
+        let e = |expression: &str| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(HashMap::new())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            e("format(\"{}\")"),
+            "format(\"{}\") failed: format string contains more placeholders than values were given",
+        );
+        assert_eq!(
+            e("format(\"{\", 1)"),
+            "format(\"{\", 1) failed: unmatched '{' in format string",
+        );
+        assert_eq!(
+            e("format()"),
+            "format takes at least 1 argument, but 0 were given in format()",
+        );
+    }
+
+    #[test]
+    fn summation_errors() {
+        let e = |expression: &str, options: &Options| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate_with_options(HashMap::new(), options)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            e("sum(k, 1, 3, 1 / (k - 2))", &Options::default()),
+            "division by zero in 1 / (k - 2) (k - 2 is zero)",
+        );
+
+        let options = Options {
+            max_steps: 1000,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            e("sum(k, 1, 10 ^ 6, k ^ 2)", &options),
+            "evaluating sum(k, 1, 10 ^ 6, k ^ 2) exceeded the limit of 1000 evaluation steps",
+        );
+    }
+
+    #[test]
+    fn sequence_limits() {
+        let e = |expression: &str, options: &Options| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate_with_options(HashMap::new(), options)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let options = Options::default();
+
+        assert_eq!(
+            e("fibonacci(10 ^ 7)", &options),
+            "evaluating fibonacci(10 ^ 7) would require about 2089877 digits, exceeding the limit of 100000",
+        );
+        assert_eq!(
+            e("catalan(10 ^ 6)", &options),
+            "evaluating catalan(10 ^ 6) would require about 602060 digits, exceeding the limit of 100000",
+        );
+        assert_eq!(
+            e("bernoulli(10 ^ 5)", &options),
+            "evaluating bernoulli(10 ^ 5) would require about 1803386835 bytes of memory, exceeding the limit of 67108864",
+        );
+        assert_eq!(
+            e("stirling(10 ^ 5, 10 ^ 4)", &options),
+            "evaluating stirling(10 ^ 5, 10 ^ 4) would require about 400000 digits, exceeding the limit of 100000",
+        );
+        assert_eq!(
+            e("table(x, x, 0, 10 ^ 7, 1)", &options),
+            "evaluating table(x, x, 0, 10 ^ 7, 1) would require about 20000002 matrix elements, exceeding the limit of 1000000",
+        );
+        assert_eq!(
+            e("table(1 / (x - 1), x, 0, 2, 1)", &options),
+            "division by zero in 1 / (x - 1) (x - 1 is zero)",
+        );
+
+        let options = Options {
+            timeout: Some(Duration::from_millis(100)),
+            ..Options::default()
+        };
+
+        assert_eq!(
+            e("catalan(10 ^ 5)", &options),
+            "evaluating catalan(10 ^ 5) exceeded the limit of 100 milliseconds",
+        );
+    }
+
+    #[test]
+    fn exact_trigonometric_values() {
+        for n in -120..=120 {
+            for function in ["sin", "cos"] {
+                let expression = format!("{}({} * pi / 60)", function, n);
+
+                let exact = expression
+                    .parse::<Expression>()
+                    .unwrap()
+                    .evaluate(HashMap::new())
+                    .unwrap();
+
+                // Values that are not in the table remain unevaluated.
+                if exact.to_string().contains(function) {
+                    continue;
+                }
+
+                let approximate = format!("N({}, 12)", exact)
+                    .parse::<Expression>()
+                    .unwrap()
+                    .evaluate(HashMap::new())
+                    .unwrap()
+                    .to_string()
+                    .parse::<f64>()
+                    .unwrap();
+
+                let x = f64::from(n) * std::f64::consts::PI / 60.0;
+                let expected = if function == "sin" { x.sin() } else { x.cos() };
+
+                assert!(
+                    (approximate - expected).abs() < 1e-10,
+                    "{} evaluated to {}",
+                    expression,
+                    exact,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn base_conversion_errors() {
+        let e = |expression: &str| {
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(HashMap::new())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            e("from_base(\"fg\", 16)"),
+            "invalid argument \"fg\" in from_base(\"fg\", 16)",
+        );
+        assert_eq!(
+            e("from_base(\"\", 16)"),
+            "invalid argument \"\" in from_base(\"\", 16)",
+        );
+        assert_eq!(
+            e("from_base([1, 2], 2)"),
+            "invalid argument [1, 2] in from_base([1, 2], 2)",
+        );
+        assert_eq!(
+            e("from_base(\"10\", 1)"),
+            "invalid argument 1 in from_base(\"10\", 1)",
+        );
+        assert_eq!(
+            e("from_base([1, 0], 37)"),
+            "invalid argument 37 in from_base([1, 0], 37)",
+        );
+        assert_eq!(e("to_base(10, 1)"), "invalid argument 1 in to_base(10, 1)");
+        assert_eq!(
+            e("to_base_string(10, 37)"),
+            "invalid argument 37 in to_base_string(10, 37)",
+        );
+    }
+
+    #[test]
+    fn assumptions() {
+        let mut options = Options::default();
+        options
+            .assumptions
+            .insert("x".to_owned(), Sign::NonNegative);
+        options.assumptions.insert("y".to_owned(), Sign::Negative);
+
+        let t = |expression: &str, result: &str| {
+            assert_eq!(
+                expression
+                    .parse::<Expression>()
+                    .unwrap()
+                    .evaluate_with_options(HashMap::new(), &options)
+                    .unwrap()
+                    .to_string(),
+                result,
+            );
+        };
+
+        t("sqrt(x^2)", "x");
+        t("sqrt(y^2)", "-y");
+        t("sqrt(z^2)", "abs(z)");
+        t("abs(x)", "x");
+        t("abs(-x)", "x");
+        t("abs(y)", "-y");
+        t("abs(x * y)", "-(x * y)");
+        t("abs(x + 1)", "x + 1");
+        t("abs(x + y)", "abs(x + y)");
+        t("abs(y ^ 2)", "y ^ 2");
+    }
 }
@@ -1,19 +1,397 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
-use num::ToPrimitive;
+use std::collections::BTreeMap;
+
+use num::{bigint::Sign, Integer as _, One, Signed, ToPrimitive, Zero};
 use primal::StreamingSieve;
 use savage_macros::function;
 
 use crate::{
-    expression::Expression,
-    functions::{function_expression, NonNegativeInteger, PositiveInteger},
+    evaluate::{modular_inverse, Options, ZeroPowerConvention},
+    expression::{Expression, Integer, Rational, Type, Vector},
+    factor::rational_gcd,
+    functions::{
+        function_expression, random::random_below, Expressions, NonNegativeInteger, PositiveInteger,
+    },
     helpers::*,
+    polynomial::Polynomial,
 };
 
+/// Primes below this bound are found by trial division when factoring integers.
+const TRIAL_DIVISION_BOUND: u64 = 1 << 16;
+
+/// Maximum number of iterations of Pollard's rho algorithm performed
+/// when factoring a single integer.
+const MAX_RHO_ITERATIONS: u64 = 1 << 17;
+
+/// Returns a non-trivial factor of the odd composite integer `n`, which must not
+/// be a prime power, using Brent's variant of Pollard's rho algorithm, or `None`
+/// if none is found after the given number of remaining iterations, which is
+/// decreased by the number of iterations performed.
+fn pollard_rho(n: &Integer, iterations: &mut u64) -> Option<Integer> {
+    // https://en.wikipedia.org/wiki/Pollard%27s_rho_algorithm#Variants
+    const BATCH_SIZE: u64 = 128;
+
+    let mut c = Integer::one();
+
+    loop {
+        let f = |x: &Integer| (x * x + &c) % n;
+
+        let mut y = Integer::from(2);
+        let mut x = y.clone();
+        let mut saved_y = y.clone();
+        let mut q = Integer::one();
+        let mut g = Integer::one();
+        let mut r = 1;
+
+        while g.is_one() {
+            x = y.clone();
+
+            for _ in 0..r {
+                y = f(&y);
+            }
+
+            let mut k = 0;
+
+            while k < r && g.is_one() {
+                saved_y = y.clone();
+                let batch = BATCH_SIZE.min(r - k);
+                *iterations = iterations.checked_sub(batch)?;
+
+                // The differences are multiplied so that only one gcd
+                // has to be computed for each batch.
+                for _ in 0..batch {
+                    y = f(&y);
+                    q = q * (&x - &y).abs() % n;
+                }
+
+                g = q.gcd(n);
+                k += batch;
+            }
+
+            r *= 2;
+        }
+
+        if g == *n {
+            // The batch contained the factor, so its steps are repeated one by one.
+            loop {
+                saved_y = f(&saved_y);
+                g = (&x - &saved_y).abs().gcd(n);
+
+                if !g.is_one() {
+                    break;
+                }
+            }
+        }
+
+        if g != *n {
+            return Some(g);
+        }
+
+        // The cycles modulo all prime factors were detected simultaneously,
+        // so the algorithm is restarted with a different polynomial.
+        c += 1;
+    }
+}
+
+/// Returns the prime factors of the positive integer `n` that could be found within
+/// the work limit, with their multiplicities, and the product of the remaining factors,
+/// which is 1 if the factorization is complete.
+///
+/// Small prime factors are found by trial division, and the remaining cofactors
+/// are split using Pollard's rho algorithm until they pass the Baillie-PSW test.
+/// Prime factors greater than 2^64 are therefore not proven to be prime.
+fn partial_factorization(n: &Integer) -> (BTreeMap<Integer, u32>, Integer) {
+    let mut factors = BTreeMap::new();
+    let mut remainder = Integer::one();
+
+    let mut n = n.clone();
+
+    for prime in primal::Primes::all().take_while(|p| (*p as u64) < TRIAL_DIVISION_BOUND) {
+        let prime = Integer::from(prime);
+
+        if &prime * &prime > n {
+            break;
+        }
+
+        while n.is_multiple_of(&prime) {
+            n /= &prime;
+            *factors.entry(prime.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let mut iterations = MAX_RHO_ITERATIONS;
+    let mut cofactors = vec![n];
+
+    while let Some(n) = cofactors.pop() {
+        if n.is_one() {
+            continue;
+        }
+
+        if is_bpsw_probable_prime(&n) {
+            *factors.entry(n).or_insert(0) += 1;
+            continue;
+        }
+
+        // Pollard's rho algorithm cannot split prime powers. All prime factors
+        // are at least `TRIAL_DIVISION_BOUND`, which bounds the exponent.
+        let max_exponent = n.bits() / TRIAL_DIVISION_BOUND.trailing_zeros() as u64;
+
+        let perfect_power = primal::Primes::all()
+            .take_while(|k| *k as u64 <= max_exponent)
+            .find_map(|k| {
+                let k = k as u32;
+                let root = n.nth_root(k);
+                if num::pow(root.clone(), k as usize) == n {
+                    Some((root, k))
+                } else {
+                    None
+                }
+            });
+
+        if let Some((root, k)) = perfect_power {
+            cofactors.extend(vec![root; k as usize]);
+            continue;
+        }
+
+        match pollard_rho(&n, &mut iterations) {
+            Some(factor) => {
+                cofactors.push(&n / &factor);
+                cofactors.push(factor);
+            }
+            None => remainder *= n,
+        }
+    }
+
+    (factors, remainder)
+}
+
+/// Returns the prime factorization of the positive integer `n` as a list of
+/// pairs of prime factors and their multiplicities, ordered by prime factor,
+/// or `None` if the factorization could not be found within the work limit.
+pub(crate) fn factorize(n: &Integer) -> Option<Vec<(Integer, u32)>> {
+    let (factors, remainder) = partial_factorization(n);
+
+    remainder.is_one().then(|| factors.into_iter().collect())
+}
+
+/// Returns whether the odd integer `n > 2` is a strong probable prime to the given base.
+fn is_strong_probable_prime(n: &Integer, base: &Integer) -> bool {
+    // https://en.wikipedia.org/wiki/Miller%E2%80%93Rabin_primality_test
+    let n_minus_1: Integer = n - 1;
+
+    let s = n_minus_1.trailing_zeros().unwrap();
+    let d = &n_minus_1 >> s;
+
+    let mut x = base.modpow(&d, n);
+
+    if x.is_one() || x == n_minus_1 {
+        return true;
+    }
+
+    for _ in 1..s {
+        x = &x * &x % n;
+
+        if x == n_minus_1 {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Returns the Jacobi symbol `(a/n)` for odd positive `n`.
+fn jacobi_symbol(a: &Integer, n: &Integer) -> i32 {
+    // https://en.wikipedia.org/wiki/Jacobi_symbol#Calculating_the_Jacobi_symbol
+    let mut a = a.mod_floor(n);
+    let mut n = n.clone();
+    let mut result = 1;
+
+    while !a.is_zero() {
+        while a.is_even() {
+            a >>= 1;
+
+            let r = (&n % 8u32).to_u32().unwrap();
+
+            if r == 3 || r == 5 {
+                result = -result;
+            }
+        }
+
+        std::mem::swap(&mut a, &mut n);
+
+        if (&a % 4u32).to_u32().unwrap() == 3 && (&n % 4u32).to_u32().unwrap() == 3 {
+            result = -result;
+        }
+
+        a = a.mod_floor(&n);
+    }
+
+    if n.is_one() {
+        result
+    } else {
+        0
+    }
+}
+
+/// Returns whether the odd integer `n > 2`, which must not be a perfect square,
+/// is a strong Lucas probable prime with parameters chosen by Selfridge's method.
+fn is_strong_lucas_probable_prime(n: &Integer) -> bool {
+    // https://en.wikipedia.org/wiki/Lucas_pseudoprime#Strong_Lucas_pseudoprimes
+    let mut d = Integer::from(5);
+
+    while jacobi_symbol(&d, n) != -1 {
+        d = if d.is_positive() { -d - 2 } else { -d + 2 };
+    }
+
+    let p = Integer::one();
+    let q: Integer = (1 - &d) / 4;
+
+    let half = |x: Integer| {
+        let x: Integer = if x.is_odd() { x + n } else { x };
+        (x / 2u32).mod_floor(n)
+    };
+
+    let n_plus_1: Integer = n + 1;
+
+    let s = n_plus_1.trailing_zeros().unwrap();
+    let k = &n_plus_1 >> s;
+
+    let mut u = Integer::one();
+    let mut v = p.clone();
+    let mut q_k = q.mod_floor(n);
+
+    for i in (0..k.bits() - 1).rev() {
+        u = (&u * &v).mod_floor(n);
+        v = (&v * &v - &q_k * 2u32).mod_floor(n);
+        q_k = (&q_k * &q_k).mod_floor(n);
+
+        if k.bit(i) {
+            let new_u = half(&p * &u + &v);
+            v = half(&d * &u + &p * &v);
+            u = new_u;
+            q_k = (&q_k * &q).mod_floor(n);
+        }
+    }
+
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+
+    for _ in 1..s {
+        v = (&v * &v - &q_k * 2u32).mod_floor(n);
+        q_k = (&q_k * &q_k).mod_floor(n);
+
+        if v.is_zero() {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Returns whether the non-negative integer `n` passes the Baillie-PSW primality test.
+///
+/// The test is known to be correct for all `n < 2^64`. No composite numbers passing
+/// the test are known, but it has not been proven that none exist.
+fn is_bpsw_probable_prime(n: &Integer) -> bool {
+    // https://en.wikipedia.org/wiki/Baillie%E2%80%93PSW_primality_test
+    if let Some(n) = n.to_u64() {
+        if n < 1000 {
+            return primal::is_prime(n);
+        }
+    }
+
+    for p in primal::Primes::all().take_while(|p| *p < 1000) {
+        if n.is_multiple_of(&Integer::from(p)) {
+            return false;
+        }
+    }
+
+    if !is_strong_probable_prime(n, &Integer::from(2)) {
+        return false;
+    }
+
+    let root = n.sqrt();
+
+    if &root * &root == *n {
+        return false;
+    }
+
+    is_strong_lucas_probable_prime(n)
+}
+
+/// Maximum number of bases tried as witnesses for each prime factor
+/// when proving primality with Pocklington's test.
+const MAX_POCKLINGTON_WITNESSES: u32 = 1000;
+
+/// Returns whether the non-negative integer `n` is prime, decided deterministically,
+/// or `None` if primality could not be proven within the work limits.
+///
+/// Integers below 2^64 are decided by the Baillie-PSW test. Larger integers that pass
+/// the test are proven prime using Pocklington's test, which requires prime factors
+/// of `n - 1` whose product exceeds the square root of `n`. Those prime factors are
+/// in turn proven prime recursively.
+fn proven_prime(n: &Integer) -> Option<bool> {
+    // https://en.wikipedia.org/wiki/Pocklington_primality_test
+    if !is_bpsw_probable_prime(n) {
+        return Some(false);
+    }
+
+    if n.to_u64().is_some() {
+        return Some(true);
+    }
+
+    let n_minus_1: Integer = n - 1;
+
+    let mut factored_part = Integer::one();
+    let mut primes = Vec::new();
+
+    for (prime, multiplicity) in partial_factorization(&n_minus_1).0 {
+        if proven_prime(&prime) == Some(true) {
+            factored_part *= num::pow(prime.clone(), multiplicity as usize);
+            primes.push(prime);
+        }
+    }
+
+    if &factored_part * &factored_part <= *n {
+        return None;
+    }
+
+    for prime in primes {
+        let exponent = &n_minus_1 / prime;
+        let mut witness_found = false;
+
+        for base in 2..=MAX_POCKLINGTON_WITNESSES {
+            let base = Integer::from(base);
+
+            if !base.modpow(&n_minus_1, n).is_one() {
+                // `n` is not a Fermat probable prime to this base.
+                return Some(false);
+            }
+
+            let divisor = (base.modpow(&exponent, n) - Integer::one()).gcd(n);
+
+            if divisor.is_one() {
+                witness_found = true;
+                break;
+            } else if divisor != *n {
+                return Some(false);
+            }
+        }
+
+        if !witness_found {
+            return None;
+        }
+    }
+
+    Some(true)
+}
+
 #[function(
     name = "is_prime",
-    description = "whether the given non-negative integer is a prime number",
+    description = "whether the given non-negative integer is a prime number (deterministic for integers less than 2^64; larger integers are prime if they pass the Baillie-PSW test, which is not proven to be correct for them, see `primality_method` and `prove_prime`)",
     examples = r#"[
         ("is_prime(0)", "false"),
         ("is_prime(1)", "false"),
@@ -21,18 +399,110 @@ use crate::{
         ("is_prime(29)", "true"),
         ("is_prime(2^31)", "false"),
         ("is_prime(2^31 - 1)", "true"),
+        ("is_prime(3825123056546413051)", "false"),
+        ("is_prime(2^64 + 1)", "false"),
+        ("is_prime(2^127 - 1)", "true"),
+        ("is_prime(2^128 + 1)", "false"),
     ]"#,
     categories = r#"[
         "number theory",
         "prime numbers",
     ]"#
 )]
-fn is_prime(n: NonNegativeInteger) -> Expression {
-    if let Some(n) = n.to_u64() {
-        Expression::Boolean(primal::is_prime(n))
+fn is_prime(n: NonNegativeInteger) -> bool {
+    // `primal::is_prime` is not used for large integers because its Miller-Rabin
+    // witnesses are insufficient for some integers below 2^64.
+    is_bpsw_probable_prime(&n)
+}
+
+#[function(
+    name = "primality_method",
+    description = "how `is_prime` decides whether the given non-negative integer is prime: \"deterministic\" if the result is proven, and \"probabilistic\" if the integer is greater than 2^64 and passes the Baillie-PSW test",
+    examples = r#"[
+        ("primality_method(29)", "\"deterministic\""),
+        ("primality_method(2^64 + 1)", "\"deterministic\""),
+        ("primality_method(2^127 - 1)", "\"probabilistic\""),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "prime numbers",
+    ]"#
+)]
+fn primality_method(n: NonNegativeInteger) -> String {
+    // Composite integers are always detected with certainty.
+    if n.to_u64().is_some() || !is_bpsw_probable_prime(&n) {
+        "deterministic".to_owned()
     } else {
-        fun(function_expression("is_prime").unwrap(), [int(n)])
+        "probabilistic".to_owned()
+    }
+}
+
+#[function(
+    name = "prove_prime",
+    description = "whether the given non-negative integer is a prime number, proven for integers greater than 2^64 using Pocklington's test (left unevaluated if not enough prime factors of `n - 1` can be found quickly)",
+    examples = r#"[
+        ("prove_prime(29)", "true"),
+        ("prove_prime(2^64 + 1)", "false"),
+        ("prove_prime(2^127 - 1)", "true"),
+        ("prove_prime(next_prime(10^30))", "true"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "prime numbers",
+    ]"#
+)]
+fn prove_prime(n: NonNegativeInteger) -> Expression {
+    match proven_prime(&n) {
+        Some(prime) => Expression::Boolean(prime),
+        None => fun(function_expression("prove_prime").unwrap(), [int(n)]),
+    }
+}
+
+#[function(
+    name = "is_probable_prime",
+    description = "whether the given non-negative integer is a prime number (deterministic for integers less than 2^64; larger integers must pass the Baillie-PSW test followed by `rounds` Miller-Rabin tests with random bases, so the probability that a composite number is reported as prime is less than 4^(-`rounds`))",
+    examples = r#"[
+        ("is_probable_prime(1, 0)", "false"),
+        ("is_probable_prime(29, 0)", "true"),
+        ("is_probable_prime(561, 0)", "false"),
+        ("is_probable_prime(2^64 + 1, 10)", "false"),
+        ("is_probable_prime(2^127 - 1, 10)", "true"),
+        ("is_probable_prime(2^128 + 1, 10)", "false"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "prime numbers",
+    ]"#,
+    pure = false
+)]
+fn is_probable_prime(
+    n: NonNegativeInteger,
+    rounds: NonNegativeInteger,
+    options: &Options,
+) -> Result<bool, Expression> {
+    let rounds = rounds.to_usize().ok_or_else(|| int(rounds.clone()))?;
+
+    if !is_bpsw_probable_prime(&n) {
+        return Ok(false);
     }
+
+    // The Baillie-PSW test has no pseudoprimes below 2^64.
+    if n.to_u64().is_some() {
+        return Ok(true);
+    }
+
+    // For a composite number, at most a quarter of the bases in `[2, n - 2]`
+    // are strong liars, so each round with a uniformly random base detects
+    // it with probability at least 3/4.
+    for _ in 0..rounds {
+        let base = 2 + random_below(&(&n - 3), options);
+
+        if !is_strong_probable_prime(&n, &base) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
 }
 
 #[function(
@@ -79,3 +549,570 @@ fn prime_pi(n: NonNegativeInteger) -> Expression {
         fun(function_expression("prime_pi").unwrap(), [int(n)])
     }
 }
+
+#[function(
+    name = "next_prime",
+    description = "smallest prime number greater than the given integer (results greater than 2^64 pass the Baillie-PSW test but are not proven to be prime)",
+    examples = r#"[
+        ("next_prime(-5)", "2"),
+        ("next_prime(2)", "3"),
+        ("next_prime(100)", "101"),
+        ("next_prime(2^64)", "18446744073709551629"),
+        ("next_prime(10^30)", "1000000000000000000000000000057"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "prime numbers",
+    ]"#
+)]
+fn next_prime(n: Integer) -> Integer {
+    if n < Integer::from(2) {
+        return Integer::from(2);
+    }
+
+    let mut candidate: Integer = n + 1;
+
+    if candidate.is_even() {
+        candidate += 1;
+    }
+
+    while !is_bpsw_probable_prime(&candidate) {
+        candidate += 2;
+    }
+
+    candidate
+}
+
+#[function(
+    name = "prev_prime",
+    description = "largest prime number less than the given integer, which must be greater than 2 (results greater than 2^64 pass the Baillie-PSW test but are not proven to be prime)",
+    examples = r#"[
+        ("prev_prime(3)", "2"),
+        ("prev_prime(100)", "97"),
+        ("prev_prime(2^64)", "18446744073709551557"),
+        ("prev_prime(10^30)", "999999999999999999999999999989"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "prime numbers",
+    ]"#
+)]
+fn prev_prime(n: Integer) -> Result<Integer, Expression> {
+    if n <= Integer::from(2) {
+        return Err(int(n));
+    } else if n == Integer::from(3) {
+        return Ok(Integer::from(2));
+    }
+
+    let mut candidate: Integer = n - 1;
+
+    if candidate.is_even() {
+        candidate -= 1;
+    }
+
+    while !is_bpsw_probable_prime(&candidate) {
+        candidate -= 2;
+    }
+
+    Ok(candidate)
+}
+
+#[function(
+    name = "divisors",
+    description = "positive divisors of the positive integer `n`, in ascending order, which is left unevaluated if `n` has several prime factors too large to be found quickly",
+    examples = r#"[
+        ("divisors(1)", "[1]"),
+        ("divisors(12)", "[1, 2, 3, 4, 6, 12]"),
+        ("divisors(29)", "[1, 29]"),
+        ("divisors(2^31 - 1)", "[1, 2147483647]"),
+        ("divisors(2^67 - 1)", "[1, 193707721, 761838257287, 147573952589676412927]"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "arithmetic functions",
+    ]"#
+)]
+fn divisors(n: PositiveInteger) -> Expression {
+    let factors = match factorize(&n) {
+        Some(factors) => factors,
+        None => return fun(function_expression("divisors").unwrap(), [int(n)]),
+    };
+
+    let mut divisors = vec![Integer::one()];
+
+    for (prime, multiplicity) in factors {
+        let mut new_divisors = Vec::new();
+
+        for divisor in &divisors {
+            let mut power = Integer::one();
+
+            for _ in 0..=multiplicity {
+                new_divisors.push(divisor * &power);
+                power *= &prime;
+            }
+        }
+
+        divisors = new_divisors;
+    }
+
+    divisors.sort();
+
+    Expression::Vector(Vector::from_vec(divisors.into_iter().map(int).collect()))
+}
+
+#[function(
+    name = "sigma",
+    description = "sum of the positive divisors of the positive integer `n`, which is left unevaluated if `n` has several prime factors too large to be found quickly",
+    examples = r#"[
+        ("sigma(1)", "1"),
+        ("sigma(12)", "28"),
+        ("sigma(28)", "56"),
+        ("sigma(2^31 - 1)", "2147483648"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "arithmetic functions",
+    ]"#
+)]
+fn sigma(n: PositiveInteger) -> Expression {
+    match factorize(&n) {
+        Some(factors) => int(factors
+            .into_iter()
+            .map(|(prime, multiplicity)| {
+                // Sum of the geometric series 1 + p + p^2 + ... + p^k.
+                (num::pow(prime.clone(), multiplicity as usize + 1) - 1) / (prime - 1)
+            })
+            .product::<Integer>()),
+        None => fun(function_expression("sigma").unwrap(), [int(n)]),
+    }
+}
+
+/// Returns the least common multiple of the rational numbers,
+/// which is the smallest non-negative rational number that is an integer multiple of both.
+fn rational_lcm(a: &Rational, b: &Rational) -> Rational {
+    Rational::new(a.numer().lcm(b.numer()), a.denom().gcd(b.denom()))
+}
+
+#[function(
+    name = "gcd",
+    description = "greatest common divisor of `a`, `b`, and any further arguments `c`, which are either rational numbers (giving the greatest non-negative rational number of which all are integer multiples) or polynomials in the same variable with rational coefficients (giving a polynomial with coprime integer coefficients and a positive leading coefficient, multiplied by the greatest common divisor of the contents)",
+    examples = r#"[
+        ("gcd(12, 18)", "6"),
+        ("gcd(-4, 6)", "2"),
+        ("gcd(7, 0)", "7"),
+        ("gcd(0, 0)", "0"),
+        ("gcd(12, 18, 8)", "2"),
+        ("gcd(1/2, 3/4)", "1/4"),
+        ("gcd(2/3, 4, 10/9)", "2/9"),
+        ("gcd(x^2 - 1, x^2 + 2*x + 1)", "x + 1"),
+        ("gcd(2*x^2 - 2, 4*x - 4)", "2 * x - 2"),
+        ("gcd(x^2 + 1, x - 1)", "1"),
+        ("gcd(x^2 - 1, x^2 - 3*x + 2, x^3 - 1)", "x - 1"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "polynomials",
+    ]"#
+)]
+fn gcd(a: Expression, b: Expression, c: Expressions) -> Result<Expression, Expression> {
+    let arguments = [a, b].into_iter().chain(c).collect::<Vec<_>>();
+
+    let numbers = arguments
+        .iter()
+        .map(|x| Rational::try_from(x.clone()).ok())
+        .collect::<Option<Vec<_>>>();
+
+    if let Some(numbers) = numbers {
+        return Ok(if numbers.iter().all(|x| x.is_integer()) {
+            Expression::Integer(
+                numbers
+                    .iter()
+                    .fold(Integer::zero(), |gcd, x| gcd.gcd(x.numer())),
+            )
+        } else {
+            numbers
+                .iter()
+                .fold(Rational::zero(), |gcd, x| rational_gcd(&gcd, x))
+                .into()
+        });
+    }
+
+    for x in &arguments {
+        match x.typ() {
+            Type::Number(_, _) if Integer::try_from(x.clone()).is_err() => return Err(x.clone()),
+            Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {}
+            _ => return Err(x.clone()),
+        }
+    }
+
+    let polynomials = arguments
+        .iter()
+        .map(Polynomial::from_expression)
+        .collect::<Option<Vec<_>>>();
+
+    if let Some(polynomials) = polynomials {
+        let mut gcd = polynomials[0].clone();
+
+        for p in &polynomials[1..] {
+            match gcd.gcd(p) {
+                Some(result) => gcd = result,
+                None => return Ok(fun(function_expression("gcd").unwrap(), arguments)),
+            }
+        }
+
+        return Ok(gcd.to_expression());
+    }
+
+    Ok(fun(function_expression("gcd").unwrap(), arguments))
+}
+
+#[function(
+    name = "lcm",
+    description = "least common multiple of the rational numbers `a`, `b`, and any further arguments `c`, which is the smallest non-negative rational number that is an integer multiple of all of them",
+    examples = r#"[
+        ("lcm(4, 6)", "12"),
+        ("lcm(-3, 5)", "15"),
+        ("lcm(7, 0)", "0"),
+        ("lcm(4, 6, 10)", "60"),
+        ("lcm(1/2, 3/4)", "3/2"),
+        ("lcm(2/3, 4, 10/9)", "20"),
+        ("lcm(2, x)", "lcm(2, x)"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+    ]"#
+)]
+fn lcm(a: Rational, b: Rational, c: Expressions) -> Result<Expression, Expression> {
+    let mut lcm = rational_lcm(&a, &b);
+
+    for x in &c {
+        match Rational::try_from(x.clone()) {
+            Ok(y) => lcm = rational_lcm(&lcm, &y),
+            Err(_) => match x.typ() {
+                Type::Arithmetic | Type::Unknown => {
+                    let arguments = [a.into(), b.into()]
+                        .into_iter()
+                        .chain(c)
+                        .collect::<Vec<_>>();
+                    return Ok(fun(function_expression("lcm").unwrap(), arguments));
+                }
+                _ => return Err(x.clone()),
+            },
+        }
+    }
+
+    Ok(if lcm.is_integer() {
+        Expression::Integer(lcm.to_integer())
+    } else {
+        lcm.into()
+    })
+}
+
+#[function(
+    name = "mod",
+    description = "remainder of the division of `a` by `m` rounded down, which has the same sign as `m` (unlike `a % m`, which has the same sign as `a`)",
+    examples = r#"[
+        ("mod(17, 5)", "2"),
+        ("mod(-17, 5)", "3"),
+        ("mod(17, -5)", "-3"),
+        ("mod(7/2, 1)", "1/2"),
+        ("mod(-3/4, 1/2)", "1/4"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+    ]"#
+)]
+fn modulo(a: Rational, m: Rational) -> Result<Rational, Expression> {
+    if m.is_zero() {
+        return Err(m.into());
+    }
+
+    Ok(&a - (&a / &m).floor() * &m)
+}
+
+#[function(
+    name = "powmod",
+    description = "`b` raised to the power `e` modulo `m`, computed by repeated squaring without computing `b ^ e` (negative exponents require `b` to be invertible modulo `m`)",
+    examples = r#"[
+        ("powmod(4, 13, 497)", "445"),
+        ("powmod(-2, 3, 7)", "6"),
+        ("powmod(3, -1, 7)", "5"),
+        ("powmod(2, 10^30, 10^9 + 7)", "312267046"),
+        ("powmod(2^127 - 2, 2^127 - 2, 2^127 - 1)", "1"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+    ]"#
+)]
+fn powmod(
+    b: Integer,
+    e: Integer,
+    m: PositiveInteger,
+    options: &Options,
+) -> Result<Integer, Expression> {
+    let b = b.mod_floor(&m);
+
+    if b.is_zero()
+        && e.is_zero()
+        && options.zero_to_the_power_of_zero == ZeroPowerConvention::Undefined
+    {
+        return Err(int(e));
+    }
+
+    let b = if e.is_negative() {
+        modular_inverse(&b, &m).ok_or_else(|| int(b.clone()))?
+    } else {
+        b
+    };
+
+    Ok(b.modpow(&e.abs(), &m))
+}
+
+#[function(
+    name = "invmod",
+    description = "multiplicative inverse of `a` modulo `m` in the range `[0, m)`, computed with the extended Euclidean algorithm (`a` must be coprime to `m`)",
+    examples = r#"[
+        ("invmod(3, 7)", "5"),
+        ("invmod(-3, 7)", "2"),
+        ("invmod(10, 17)", "12"),
+        ("invmod(2, 2^127 - 1)", "85070591730234615865843651857942052864"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+    ]"#
+)]
+fn invmod(a: Integer, m: PositiveInteger) -> Result<Integer, Expression> {
+    modular_inverse(&a, &m).ok_or_else(|| int(a))
+}
+
+#[function(
+    name = "euler_phi",
+    description = "number of integers between 1 and `n` that are coprime to the positive integer `n` (Euler's totient function), which is left unevaluated if `n` has several prime factors too large to be found quickly",
+    examples = r#"[
+        ("euler_phi(1)", "1"),
+        ("euler_phi(12)", "4"),
+        ("euler_phi(29)", "28"),
+        ("euler_phi(2^10)", "512"),
+        ("euler_phi(2^89 - 1)", "618970019642690137449562110"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "arithmetic functions",
+    ]"#
+)]
+fn euler_phi(n: PositiveInteger) -> Expression {
+    match factorize(&n) {
+        Some(factors) => int(factors
+            .into_iter()
+            .map(|(prime, multiplicity)| {
+                num::pow(prime.clone(), multiplicity as usize - 1) * (prime - 1)
+            })
+            .product::<Integer>()),
+        None => fun(function_expression("euler_phi").unwrap(), [int(n)]),
+    }
+}
+
+#[function(
+    name = "moebius",
+    description = "Möbius function of the positive integer `n`, which is left unevaluated if `n` has several prime factors too large to be found quickly",
+    examples = r#"[
+        ("moebius(1)", "1"),
+        ("moebius(2)", "-1"),
+        ("moebius(6)", "1"),
+        ("moebius(12)", "0"),
+        ("moebius(30)", "-1"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "arithmetic functions",
+    ]"#
+)]
+fn moebius(n: PositiveInteger) -> Expression {
+    let factors = match factorize(&n) {
+        Some(factors) => factors,
+        None => return fun(function_expression("moebius").unwrap(), [int(n)]),
+    };
+
+    if factors.iter().any(|(_, multiplicity)| *multiplicity > 1) {
+        int(0)
+    } else if factors.len().is_even() {
+        int(1)
+    } else {
+        int(-1)
+    }
+}
+
+#[function(
+    name = "rationalize",
+    description = "closest rational number to `x` whose denominator does not exceed `max_denominator`",
+    examples = r#"[
+        ("rationalize(3.14159, 1000)", "355/113"),
+        ("rationalize(0.333, 10)", "1/3"),
+        ("rationalize(-1.414, 100)", "-140/99"),
+        ("rationalize(2.5, 100)", "5/2"),
+        ("rationalize(0.999, 100)", "1"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "rational approximation",
+    ]"#
+)]
+fn rationalize(x: Rational, max_denominator: PositiveInteger) -> Rational {
+    if x.denom() <= &max_denominator {
+        return x;
+    }
+
+    // https://en.wikipedia.org/wiki/Continued_fraction#Best_rational_approximations
+    // The convergents of the continued fraction expansion of `x` are computed
+    // until their denominators exceed the limit. The best approximation is then
+    // either the last convergent within the limit, or the largest semiconvergent
+    // that lies between it and the previous convergent.
+    let (mut p_0, mut q_0, mut p_1, mut q_1) = (
+        Integer::from(0),
+        Integer::from(1),
+        Integer::from(1),
+        Integer::from(0),
+    );
+
+    let mut numerator = x.numer().clone();
+    let mut denominator = x.denom().clone();
+
+    loop {
+        let a = numerator.div_floor(&denominator);
+        let q_2 = &q_0 + &a * &q_1;
+
+        if q_2 > max_denominator {
+            break;
+        }
+
+        let p_2 = &p_0 + &a * &p_1;
+        p_0 = p_1;
+        q_0 = q_1;
+        p_1 = p_2;
+        q_1 = q_2;
+
+        let remainder = &numerator - &a * &denominator;
+        numerator = denominator;
+        denominator = remainder;
+    }
+
+    let k = (&max_denominator - &q_0) / &q_1;
+
+    let semiconvergent = Rational::new(&p_0 + &k * &p_1, &q_0 + &k * &q_1);
+    let convergent = Rational::new(p_1, q_1);
+
+    if (&convergent - &x).abs() <= (&semiconvergent - &x).abs() {
+        convergent
+    } else {
+        semiconvergent
+    }
+}
+
+/// Returns the given base as a radix suitable for digit conversion,
+/// or an error if the base is outside the supported range.
+#[allow(clippy::result_large_err)]
+fn radix(b: Integer) -> Result<u32, Expression> {
+    match b.to_u32() {
+        Some(radix) if (2..=36).contains(&radix) => Ok(radix),
+        _ => Err(int(b)),
+    }
+}
+
+#[function(
+    name = "to_base",
+    description = "digits of the non-negative integer `n` in base `b` (between 2 and 36), most significant digit first",
+    examples = r#"[
+        ("to_base(0, 2)", "[0]"),
+        ("to_base(10, 2)", "[1, 0, 1, 0]"),
+        ("to_base(255, 16)", "[15, 15]"),
+        ("to_base(2^64, 36)", "[3, 32, 5, 14, 1, 1, 2, 6, 4, 28, 16, 28, 16]"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "base conversion",
+    ]"#
+)]
+fn to_base(n: NonNegativeInteger, b: Integer) -> Result<Vector, Expression> {
+    let radix = radix(b)?;
+
+    Ok(Vector::from_vec(
+        n.to_radix_be(radix).1.into_iter().map(int).collect(),
+    ))
+}
+
+#[function(
+    name = "to_base_string",
+    description = "string of the digits of the non-negative integer `n` in base `b` (between 2 and 36), using the letters `a` to `z` for digits from 10 to 35",
+    examples = r#"[
+        ("to_base_string(0, 2)", "\"0\""),
+        ("to_base_string(10, 2)", "\"1010\""),
+        ("to_base_string(255, 16)", "\"ff\""),
+        ("to_base_string(2^64, 36)", "\"3w5e11264sgsg\""),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "base conversion",
+    ]"#
+)]
+fn to_base_string(n: NonNegativeInteger, b: Integer) -> Result<String, Expression> {
+    let radix = radix(b)?;
+
+    Ok(n.to_str_radix(radix))
+}
+
+#[function(
+    name = "from_base",
+    description = "non-negative integer with the given digits in base `b` (between 2 and 36), most significant digit first, where the digits are either a vector of integers or a string using the letters `a` to `z` (or `A` to `Z`) for digits from 10 to 35",
+    examples = r#"[
+        ("from_base([0], 2)", "0"),
+        ("from_base([1, 0, 1, 0], 2)", "10"),
+        ("from_base([15, 15], 16)", "255"),
+        ("from_base([3, 32, 5, 14, 1, 1, 2, 6, 4, 28, 16, 28, 16], 36)", "18446744073709551616"),
+        ("from_base(\"1010\", 2)", "10"),
+        ("from_base(\"ff\", 16)", "255"),
+        ("from_base(\"FF\", 16)", "255"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "base conversion",
+    ]"#
+)]
+fn from_base(digits: Expression, b: Integer) -> Result<Expression, Expression> {
+    let radix = radix(b.clone())?;
+
+    let mut digit_values = Vec::new();
+
+    match &digits {
+        Expression::Vector(vector) => {
+            for digit in vector.iter() {
+                if let Ok(digit) = Integer::try_from(digit.clone()) {
+                    match digit.to_u8() {
+                        Some(value) if u32::from(value) < radix => digit_values.push(value),
+                        _ => return Err(digits),
+                    }
+                } else {
+                    // Symbolic digits cannot be combined into an integer.
+                    return Ok(fun(
+                        function_expression("from_base").unwrap(),
+                        [digits, int(b)],
+                    ));
+                }
+            }
+        }
+        Expression::String(string) if !string.is_empty() => {
+            for character in string.chars() {
+                match character.to_digit(radix) {
+                    Some(value) => digit_values.push(value as u8),
+                    None => return Err(digits),
+                }
+            }
+        }
+        _ => return Err(digits),
+    }
+
+    Ok(int(if digit_values.is_empty() {
+        Integer::zero()
+    } else {
+        Integer::from_radix_be(Sign::Plus, &digit_values, radix).unwrap()
+    }))
+}
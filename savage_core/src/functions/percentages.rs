@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use savage_macros::function;
+
+use crate::{expression::Expression, helpers::int};
+
+#[function(
+    name = "percent_of",
+    description = "`p` percent of `x`",
+    examples = r#"[
+        ("percent_of(15, 80)", "12"),
+        ("percent_of(12.5, 10)", "1.25"),
+        ("percent_of(1/3, 300)", "1"),
+    ]"#,
+    categories = r#"[
+        "percentages",
+    ]"#
+)]
+fn percent_of(p: Expression, x: Expression) -> Expression {
+    p * x / int(100)
+}
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{One, ToPrimitive};
+use savage_macros::function;
+
+use crate::{
+    algebraic::AlgebraicNumber,
+    evaluate::Options,
+    expression::{Expression, Type, Vector},
+    functions::{function_expression, FunctionError, PositiveInteger},
+    helpers::*,
+    polynomial::Polynomial,
+};
+
+#[function(
+    name = "roots",
+    description = "vector of the roots of the polynomial `p` with rational coefficients in the variable `x`, repeated according to their multiplicity, with the rational roots given exactly and the others approximated",
+    examples = r#"[
+        ("roots(x^2 - 1, x)", "[-1, 1]"),
+        ("roots(2*x^3 - 3*x^2 - 11*x + 6, x)", "[-2, 1/2, 3]"),
+        ("roots((x - 1)^2 * (x^2 + 1), x)", "[1, 1, -i, i]"),
+        ("roots(x^3 - 2*x - 5, x)", "[-1.04727574077116 - 1.13593988908893*i, 1.13593988908893*i - 1.04727574077116, 2.09455148154233]"),
+    ]"#,
+    categories = r#"[
+        "polynomials",
+    ]"#
+)]
+fn roots(p: Expression, x: Expression, options: &Options) -> Result<Expression, FunctionError> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x.into()),
+    };
+
+    if let Type::Boolean(_) | Type::String(_) | Type::Function(_, _) = p.typ() {
+        if !matches!(p, Expression::Equal(_, _)) {
+            return Err(p.into());
+        }
+    }
+
+    Ok(match p.roots(identifier, options) {
+        Some(roots) => Expression::Vector(Vector::from_vec(roots)),
+        None => {
+            options.check_time()?;
+            fun(function_expression("roots").unwrap(), [p, x])
+        }
+    })
+}
+
+#[function(
+    name = "root_of",
+    description = "`k`th smallest real root of the polynomial `p` with rational coefficients in the variable `x`, as an exact algebraic number given by its minimal polynomial, which can be used in arithmetic and comparisons",
+    examples = r#"[
+        ("root_of(x^2 - x - 1, x, 2)", "root_of(x ^ 2 - x - 1, x, 2)"),
+        ("root_of(2*x^3 - 3*x^2 - 11*x + 6, x, 2)", "1/2"),
+        ("root_of(x^4 - 4, x, 1)", "root_of(x ^ 2 - 2, x, 1)"),
+        ("root_of(x^2 - x - 1, x, 2) ^ 2 - root_of(x^2 - x - 1, x, 2)", "1"),
+        ("1 / root_of(x^2 - x - 1, x, 2)", "root_of(x ^ 2 + x - 1, x, 2)"),
+        ("root_of(x^3 - 2, x, 1) * sqrt(2)", "root_of(x ^ 6 - 32, x, 2)"),
+        ("root_of(x^2 - 2, x, 2) == sqrt(2)", "true"),
+        ("root_of(x^3 - x - 1, x, 1) < 4/3", "true"),
+    ]"#,
+    categories = r#"[
+        "polynomials",
+    ]"#
+)]
+fn root_of(p: Expression, x: Expression, k: PositiveInteger) -> Result<Expression, Expression> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x),
+    };
+
+    let index = k.to_usize().ok_or_else(|| Expression::Integer(k.clone()))?;
+
+    match Polynomial::from_expression(&p) {
+        Some(polynomial)
+            if !polynomial.is_zero() && polynomial.variables().iter().all(|x| x == identifier) =>
+        {
+            AlgebraicNumber::root_of(&p, identifier, index)
+                .map(|root| root.to_expression())
+                .ok_or(Expression::Integer(k))
+        }
+        Some(_) => Err(p),
+        None => match p.typ() {
+            Type::Number(_, _) | Type::Arithmetic | Type::Unknown => Ok(fun(
+                function_expression("root_of").unwrap(),
+                [p, x, Expression::Integer(k)],
+            )),
+            _ => Err(p),
+        },
+    }
+}
+
+#[function(
+    name = "factor",
+    description = "factorization of the polynomial `p` in one variable into irreducible factors over the rational numbers",
+    examples = r#"[
+        ("factor(x^2 - 1)", "(x - 1) * (x + 1)"),
+        ("factor(2*x^3 - 2*x)", "2 * (x - 1) * x * (x + 1)"),
+        ("factor(x^4 + 4)", "(x ^ 2 - 2 * x + 2) * (x ^ 2 + 2 * x + 2)"),
+        ("factor(x^3 - 3*x^2 + 3*x - 1)", "(x - 1) ^ 3"),
+        ("factor(x^2/4 - 1)", "1/4 * (x - 2) * (x + 2)"),
+        ("factor(1 - x^2)", "-(x - 1) * (x + 1)"),
+        ("factor(x^2 + 1)", "x ^ 2 + 1"),
+    ]"#,
+    categories = r#"[
+        "polynomials",
+    ]"#
+)]
+fn factor(p: Expression) -> Result<Expression, Expression> {
+    if let Type::Number(_, _) = p.typ() {
+        return Ok(p);
+    }
+
+    let factorization = match Polynomial::from_expression(&p) {
+        Some(polynomial) if polynomial.is_zero() => return Ok(int(0)),
+        Some(polynomial) => polynomial.factor(),
+        None => match p.typ() {
+            Type::Arithmetic | Type::Unknown => None,
+            _ => return Err(p),
+        },
+    };
+
+    let (constant, factors) = match factorization {
+        Some(factorization) => factorization,
+        None => return Ok(fun(function_expression("factor").unwrap(), [p])),
+    };
+
+    let mut factors = factors
+        .into_iter()
+        .map(|(factor, multiplicity)| match multiplicity {
+            1 => factor.to_expression(),
+            _ => pow(factor.to_expression(), int(multiplicity)),
+        })
+        .collect::<Vec<_>>();
+
+    // A constant of `-1` is written as the negation of the first factor,
+    // e.g. `-(x - 1) * (x + 1)` rather than `-((x - 1) * (x + 1))`.
+    if (-&constant).is_one() {
+        if let Some(first) = factors.first_mut() {
+            *first = -first.clone();
+        }
+    }
+
+    Ok(match factors.into_iter().reduce(|a, b| a * b) {
+        Some(product) if constant.is_one() || (-&constant).is_one() => product,
+        Some(product) => Expression::from(constant) * product,
+        None => Expression::from(constant),
+    })
+}
+
+#[function(
+    name = "apart",
+    description = "partial fraction decomposition of the rational function `f` with rational coefficients in the variable `x`, over the irreducible factors of its denominator",
+    examples = r#"[
+        ("apart(1 / (x^2 - 1), x)", "1 / (2 * (x - 1)) - 1 / (2 * (x + 1))"),
+        ("apart((x^3 + 1) / (x^2 + x), x)", "x - 1 + 1 / x"),
+        ("apart(1 / ((x - 1)^2 * (x^2 + 1)), x)", "-1 / (2 * (x - 1)) + 1 / (2 * (x - 1) ^ 2) + x / (2 * (x ^ 2 + 1))"),
+    ]"#,
+    categories = r#"[
+        "polynomials",
+    ]"#
+)]
+fn apart(f: Expression, x: Expression) -> Result<Expression, Expression> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(x),
+    };
+
+    match f.apart(identifier) {
+        Some(result) => Ok(result),
+        None => match f.typ() {
+            Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+                Ok(fun(function_expression("apart").unwrap(), [f, x]))
+            }
+            _ => Err(f),
+        },
+    }
+}
+
+#[function(
+    name = "together",
+    description = "rational function `f` with rational coefficients written as a single quotient of polynomials over a common denominator, with common factors cancelled if there is at most one variable",
+    examples = r#"[
+        ("together(1 / (x - 1) + 1 / (x + 1))", "2 * x / (x ^ 2 - 1)"),
+        ("together(x / 2 + 1 / 3)", "(3 * x + 2) / 6"),
+        ("together((x^2 - 1) / (x + 1))", "x - 1"),
+        ("together(1 / x + 1 / y)", "(x + y) / (x * y)"),
+    ]"#,
+    categories = r#"[
+        "polynomials",
+    ]"#
+)]
+fn together(f: Expression) -> Result<Expression, Expression> {
+    match f.together() {
+        Some(result) => Ok(result),
+        None => match f.typ() {
+            Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+                Ok(fun(function_expression("together").unwrap(), [f]))
+            }
+            _ => Err(f),
+        },
+    }
+}
+
+#[function(
+    name = "expand",
+    description = "expansion of `f` obtained by distributing products over sums and multiplying out integer powers of sums, with like terms combined",
+    examples = r#"[
+        ("expand((x + 1)^2)", "x ^ 2 + 2 * x + 1"),
+        ("expand((x + y) * (x - y))", "x ^ 2 - y ^ 2"),
+        ("expand((a + b + c)^2)", "a ^ 2 + 2 * a * b + 2 * a * c + b ^ 2 + 2 * b * c + c ^ 2"),
+        ("expand(2 * (sin(x) + 1) * x)", "2 * x * sin(x) + 2 * x"),
+        ("expand((x + 1) / x)", "1 / x + 1"),
+        ("expand((x + 1)^2 == 4)", "x ^ 2 + 2 * x + 1 == 4"),
+    ]"#,
+    categories = r#"[
+        "polynomials",
+    ]"#
+)]
+fn expand(f: Expression) -> Result<Expression, Expression> {
+    match f.typ() {
+        Type::Boolean(_) if matches!(f, Expression::Equal(_, _)) => Ok(f.expand()),
+        Type::Boolean(_) | Type::String(_) | Type::Function(_, _) => Err(f),
+        _ => Ok(f.expand()),
+    }
+}
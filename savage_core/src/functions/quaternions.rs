@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{One, Signed, Zero};
+use savage_macros::function;
+
+use crate::{
+    expression::{Expression, Matrix, Rational, Type},
+    functions::{function_expression, SquareMatrix},
+    helpers::*,
+};
+
+/// Returns the real part and `i`, `j`, and `k` components of the expression
+/// if it is a quaternion or a number, `Ok(None)` if the expression might
+/// evaluate to a quaternion, and the expression as an error otherwise.
+#[allow(clippy::result_large_err)]
+fn components(q: &Expression) -> Result<Option<[Expression; 4]>, Expression> {
+    match q.typ() {
+        Type::Quaternion(a, b, c, d) => Ok(Some([*a, *b, *c, *d])),
+        Type::Number(z, representation) => Ok(Some([
+            Expression::Rational(z.re, representation),
+            Expression::Rational(z.im, representation),
+            int(0),
+            int(0),
+        ])),
+        Type::Arithmetic | Type::Unknown => Ok(None),
+        _ => Err(q.clone()),
+    }
+}
+
+/// Returns the components of the expression if they are all rational numbers.
+fn rational_components(q: &[Expression; 4]) -> Option<[Rational; 4]> {
+    let [a, b, c, d] = q;
+
+    Some([
+        Rational::try_from(a.clone()).ok()?,
+        Rational::try_from(b.clone()).ok()?,
+        Rational::try_from(c.clone()).ok()?,
+        Rational::try_from(d.clone()).ok()?,
+    ])
+}
+
+/// Returns the square root of the given non-negative rational number,
+/// as a rational number if it is a perfect square and as a power otherwise.
+fn sqrt(x: Rational) -> Expression {
+    let numerator = x.numer().sqrt();
+    let denominator = x.denom().sqrt();
+
+    if &numerator * &numerator == *x.numer() && &denominator * &denominator == *x.denom() {
+        Rational::new(numerator, denominator).into()
+    } else {
+        pow(x, rat(1, 2))
+    }
+}
+
+/// Returns the product of the given rational number and expression,
+/// evaluating it directly if the expression is a rational number.
+fn scale(k: Rational, x: &Expression) -> Expression {
+    if k.is_zero() {
+        int(0)
+    } else if let Ok(x) = Rational::try_from(x.clone()) {
+        (k * x).into()
+    } else {
+        Expression::from(k) * x.clone()
+    }
+}
+
+#[function(
+    name = "quaternion",
+    description = "quaternion `a + b i + c j + d k` with real components",
+    examples = r#"[
+        ("quaternion(1, 2, 3, 4)", "quaternion(1, 2, 3, 4)"),
+        ("quaternion(0, 1, 0, 0) * quaternion(0, 0, 1, 0)", "quaternion(0, 0, 0, 1)"),
+        ("quaternion(0, 0, 1, 0) * quaternion(0, 1, 0, 0)", "quaternion(0, 0, 0, -1)"),
+        ("quaternion(0, 0, 0, 1) ^ 2", "quaternion(-1, 0, 0, 0)"),
+        ("quaternion(1, 2, 3, 4) / quaternion(0, 1, 0, 0)", "quaternion(2, -1, -4, 3)"),
+        ("quaternion(1, 2, 3, 4) + i", "quaternion(1, 3, 3, 4)"),
+    ]"#,
+    categories = r#"[
+        "quaternions",
+    ]"#
+)]
+fn quaternion(
+    a: Expression,
+    b: Expression,
+    c: Expression,
+    d: Expression,
+) -> Result<Expression, Expression> {
+    for component in [&a, &b, &c, &d] {
+        match component.typ() {
+            Type::Number(z, _) if z.im.is_zero() => (),
+            Type::Arithmetic | Type::Unknown => (),
+            _ => return Err(component.clone()),
+        }
+    }
+
+    Ok(quat(a, b, c, d))
+}
+
+#[function(
+    name = "quaternion_conjugate",
+    description = "conjugate of a quaternion",
+    examples = r#"[
+        ("quaternion_conjugate(quaternion(1, 2, 3, 4))", "quaternion(1, -2, -3, -4)"),
+        ("quaternion_conjugate(quaternion(a, b, c, d))", "quaternion(a, -b, -c, -d)"),
+    ]"#,
+    categories = r#"[
+        "quaternions",
+    ]"#
+)]
+fn quaternion_conjugate(q: Expression) -> Result<Expression, Expression> {
+    Ok(match components(&q)? {
+        Some([a, b, c, d]) => quat(a, -b, -c, -d),
+        None => fun(function_expression("quaternion_conjugate").unwrap(), [q]),
+    })
+}
+
+#[function(
+    name = "quaternion_norm",
+    description = "norm (absolute value) of a quaternion",
+    examples = r#"[
+        ("quaternion_norm(quaternion(1, 2, 2, 4))", "5"),
+        ("quaternion_norm(quaternion(1, 1, 1, 1/2))", "sqrt(13) / 2"),
+        ("quaternion_norm(quaternion(a, b, c, d))", "(a ^ 2 + b ^ 2 + c ^ 2 + d ^ 2) ^ (1/2)"),
+    ]"#,
+    categories = r#"[
+        "quaternions",
+    ]"#
+)]
+fn quaternion_norm(q: Expression) -> Result<Expression, Expression> {
+    Ok(match components(&q)? {
+        Some(components) => match rational_components(&components) {
+            Some([a, b, c, d]) => sqrt(&a * &a + &b * &b + &c * &c + &d * &d),
+            None => {
+                let [a, b, c, d] = components;
+
+                pow(
+                    a.clone() * a + b.clone() * b + c.clone() * c + d.clone() * d,
+                    rat(1, 2),
+                )
+            }
+        },
+        None => fun(function_expression("quaternion_norm").unwrap(), [q]),
+    })
+}
+
+#[function(
+    name = "quaternion_inverse",
+    description = "multiplicative inverse of a non-zero quaternion",
+    examples = r#"[
+        ("quaternion_inverse(quaternion(1, 1, 1, 1))", "quaternion(1/4, -1/4, -1/4, -1/4)"),
+        ("quaternion_inverse(quaternion(0, 0, 2, 0))", "quaternion(0, 0, -1/2, 0)"),
+    ]"#,
+    categories = r#"[
+        "quaternions",
+    ]"#
+)]
+fn quaternion_inverse(q: Expression) -> Result<Expression, Expression> {
+    Ok(match components(&q)? {
+        Some(components) => {
+            if let Some(components) = rational_components(&components) {
+                if components.iter().all(Zero::is_zero) {
+                    return Err(q);
+                }
+            }
+
+            int(1) / q
+        }
+        None => fun(function_expression("quaternion_inverse").unwrap(), [q]),
+    })
+}
+
+#[function(
+    name = "quaternion_to_matrix",
+    description = "3x3 matrix of the rotation represented by a non-zero quaternion",
+    examples = r#"[
+        ("quaternion_to_matrix(quaternion(1, 0, 0, 0))", "[[1, 0, 0], [0, 1, 0], [0, 0, 1]]"),
+        ("quaternion_to_matrix(quaternion(0, 1, 0, 0))", "[[1, 0, 0], [0, -1, 0], [0, 0, -1]]"),
+        ("quaternion_to_matrix(quaternion(1, 0, 0, 1))", "[[0, -1, 0], [1, 0, 0], [0, 0, 1]]"),
+    ]"#,
+    categories = r#"[
+        "quaternions",
+        "linear algebra",
+    ]"#
+)]
+fn quaternion_to_matrix(q: Expression) -> Result<Expression, Expression> {
+    let [a, b, c, d] = match components(&q)? {
+        Some(components) => components,
+        None => {
+            return Ok(fun(
+                function_expression("quaternion_to_matrix").unwrap(),
+                [q],
+            ))
+        }
+    };
+
+    if let Some(components) = rational_components(&[a.clone(), b.clone(), c.clone(), d.clone()]) {
+        if components.iter().all(Zero::is_zero) {
+            return Err(q);
+        }
+    }
+
+    // Dividing by the squared norm makes the result a rotation matrix
+    // even if the quaternion is not a unit quaternion.
+    let s = int(2)
+        / (a.clone() * a.clone()
+            + b.clone() * b.clone()
+            + c.clone() * c.clone()
+            + d.clone() * d.clone());
+
+    let p = |x: &Expression, y: &Expression| x.clone() * y.clone();
+
+    Ok(Matrix::from_row_slice(
+        3,
+        3,
+        &[
+            int(1) - s.clone() * (p(&c, &c) + p(&d, &d)),
+            s.clone() * (p(&b, &c) - p(&d, &a)),
+            s.clone() * (p(&b, &d) + p(&c, &a)),
+            s.clone() * (p(&b, &c) + p(&d, &a)),
+            int(1) - s.clone() * (p(&b, &b) + p(&d, &d)),
+            s.clone() * (p(&c, &d) - p(&b, &a)),
+            s.clone() * (p(&b, &d) - p(&c, &a)),
+            s.clone() * (p(&c, &d) + p(&b, &a)),
+            int(1) - s * (p(&b, &b) + p(&c, &c)),
+        ],
+    )
+    .into())
+}
+
+#[function(
+    name = "matrix_to_quaternion",
+    description = "unit quaternion representing the rotation given by a rational 3x3 rotation matrix",
+    examples = r#"[
+        ("matrix_to_quaternion([[1, 0, 0], [0, 1, 0], [0, 0, 1]])", "quaternion(1, 0, 0, 0)"),
+        ("matrix_to_quaternion([[1, 0, 0], [0, -1, 0], [0, 0, -1]])", "quaternion(0, 1, 0, 0)"),
+        ("matrix_to_quaternion([[0, -1, 0], [1, 0, 0], [0, 0, 1]])", "quaternion(sqrt(2) / 2, 0, 0, sqrt(2) / 2)"),
+        ("matrix_to_quaternion([[3/5, -4/5, 0], [4/5, 3/5, 0], [0, 0, 1]])", "quaternion(2 * sqrt(5) / 5, 0, 0, sqrt(5) / 5)"),
+    ]"#,
+    categories = r#"[
+        "quaternions",
+        "linear algebra",
+    ]"#
+)]
+fn matrix_to_quaternion(matrix: SquareMatrix) -> Result<Expression, Expression> {
+    if matrix.nrows() != 3 {
+        return Err(matrix.into());
+    }
+
+    let mut m = Vec::new();
+
+    for element in matrix.iter() {
+        match Rational::try_from(element.clone()) {
+            Ok(x) => m.push(x),
+            Err(_) => {
+                return Ok(fun(
+                    function_expression("matrix_to_quaternion").unwrap(),
+                    [matrix.into()],
+                ))
+            }
+        }
+    }
+
+    let m = nalgebra::DMatrix::from_vec(3, 3, m);
+
+    // The matrix must be orthogonal with determinant 1.
+    let determinant = &m[(0, 0)] * (&m[(1, 1)] * &m[(2, 2)] - &m[(1, 2)] * &m[(2, 1)])
+        - &m[(0, 1)] * (&m[(1, 0)] * &m[(2, 2)] - &m[(1, 2)] * &m[(2, 0)])
+        + &m[(0, 2)] * (&m[(1, 0)] * &m[(2, 1)] - &m[(1, 1)] * &m[(2, 0)]);
+
+    let orthogonal = (0..3).all(|i| {
+        (0..3).all(|j| {
+            let dot = (0..3)
+                .map(|k| &m[(k, i)] * &m[(k, j)])
+                .fold(Rational::zero(), |a, b| a + b);
+
+            dot == if i == j {
+                Rational::one()
+            } else {
+                Rational::zero()
+            }
+        })
+    });
+
+    if !orthogonal || determinant != Rational::one() {
+        return Err(matrix.into());
+    }
+
+    let trace = &m[(0, 0)] + &m[(1, 1)] + &m[(2, 2)];
+
+    // Shepperd's method: Compute the component with the largest absolute value
+    // from the diagonal, and the others from the off-diagonal elements.
+    let candidates = [
+        trace.clone(),
+        m[(0, 0)].clone(),
+        m[(1, 1)].clone(),
+        m[(2, 2)].clone(),
+    ];
+
+    let largest = (1..4).fold(0, |largest, i| {
+        if candidates[i] > candidates[largest] {
+            i
+        } else {
+            largest
+        }
+    });
+
+    let one = Rational::one();
+
+    let radicand = match largest {
+        0 => &one + &trace,
+        1 => &one + &m[(0, 0)] - &m[(1, 1)] - &m[(2, 2)],
+        2 => &one - &m[(0, 0)] + &m[(1, 1)] - &m[(2, 2)],
+        _ => &one - &m[(0, 0)] - &m[(1, 1)] + &m[(2, 2)],
+    };
+
+    // With `r = sqrt(radicand)`, the largest component is `r / 2`,
+    // and every other component `x / (2 * r)` equals `(x / (2 * radicand)) * r`.
+    let half = Rational::new(1.into(), 2.into());
+    let f = |x: Rational| x / (Rational::from_integer(2.into()) * &radicand);
+
+    let coefficients = match largest {
+        0 => [
+            half,
+            f(&m[(2, 1)] - &m[(1, 2)]),
+            f(&m[(0, 2)] - &m[(2, 0)]),
+            f(&m[(1, 0)] - &m[(0, 1)]),
+        ],
+        1 => [
+            f(&m[(2, 1)] - &m[(1, 2)]),
+            half,
+            f(&m[(0, 1)] + &m[(1, 0)]),
+            f(&m[(0, 2)] + &m[(2, 0)]),
+        ],
+        2 => [
+            f(&m[(0, 2)] - &m[(2, 0)]),
+            f(&m[(0, 1)] + &m[(1, 0)]),
+            half,
+            f(&m[(1, 2)] + &m[(2, 1)]),
+        ],
+        _ => [
+            f(&m[(1, 0)] - &m[(0, 1)]),
+            f(&m[(0, 2)] + &m[(2, 0)]),
+            f(&m[(1, 2)] + &m[(2, 1)]),
+            half,
+        ],
+    };
+
+    let r = sqrt(radicand.clone());
+
+    // The quaternions `q` and `-q` represent the same rotation.
+    // Choose the one with non-negative real part.
+    let [a, b, c, d] = if coefficients[0].is_negative() {
+        coefficients.map(|x| -x)
+    } else {
+        coefficients
+    };
+
+    Ok(quat(scale(a, &r), scale(b, &r), scale(c, &r), scale(d, &r)))
+}
@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::f64::consts::LOG10_2;
+
+use num::{One, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    evaluate::{LimitExceeded, Options},
+    expression::{Expression, Integer, Rational},
+    functions::NonNegativeInteger,
+    helpers::ratd,
+};
+
+/// Number of decimal places of the numbers returned by `random`.
+const RANDOM_DIGITS: u32 = 15;
+
+#[function(
+    name = "random",
+    description = "random number between 0 (inclusive) and 1 (exclusive) with 15 decimal places",
+    examples = r#"[
+        ("random() >= 0 && random() < 1", "true"),
+    ]"#,
+    categories = r#"[
+        "random",
+    ]"#,
+    pure = false
+)]
+fn random(options: &Options) -> Expression {
+    let scale = 10u64.pow(RANDOM_DIGITS);
+
+    // The bias from the modulo operation is negligible, since 2^64 is
+    // more than 10^4 times as large as the scale.
+    ratd(options.generator.next_u64() % scale, scale)
+}
+
+/// Returns a uniformly distributed random non-negative integer less than `2 ^ bits`.
+fn random_bits(bits: u64, options: &Options) -> Integer {
+    let mut n = Integer::zero();
+    let mut generated_bits = 0;
+
+    while generated_bits < bits {
+        n = (n << 64) | Integer::from(options.generator.next_u64());
+        generated_bits += 64;
+    }
+
+    n >> (generated_bits - bits) as usize
+}
+
+/// Returns a uniformly distributed random non-negative integer less than the positive integer `n`.
+pub(crate) fn random_below(n: &Integer, options: &Options) -> Integer {
+    let bits = n.bits();
+
+    // Rejection sampling, which succeeds with probability greater than 1/2
+    // in each iteration and avoids the bias of reducing modulo `n`.
+    loop {
+        let k = random_bits(bits, options);
+
+        if k < *n {
+            return k;
+        }
+    }
+}
+
+#[function(
+    name = "randint",
+    description = "random integer between `a` and `b` (both inclusive), each of which is equally likely",
+    examples = r#"[
+        ("randint(5, 5)", "5"),
+        ("randint(1, 6) >= 1 && randint(1, 6) <= 6", "true"),
+        ("abs(randint(-10^30, 10^30)) <= 10^30", "true"),
+    ]"#,
+    categories = r#"[
+        "random",
+    ]"#,
+    pure = false
+)]
+fn randint(a: Integer, b: Integer, options: &Options) -> Result<Integer, Expression> {
+    if a > b {
+        return Err(Expression::Integer(b));
+    }
+
+    let range = &b - &a + Integer::one();
+
+    Ok(&a + random_below(&range, options))
+}
+
+#[function(
+    name = "randrational",
+    description = "random rational number between 0 (inclusive) and 1 (exclusive) of the form `k / 2^bits`, where each integer `0 <= k < 2^bits` is equally likely",
+    examples = r#"[
+        ("randrational(0)", "0"),
+        ("randrational(64) >= 0 && randrational(64) < 1", "true"),
+        ("randrational(3) * 8 % 1 == 0", "true"),
+    ]"#,
+    categories = r#"[
+        "random",
+    ]"#,
+    pure = false
+)]
+fn randrational(bits: NonNegativeInteger, options: &Options) -> Result<Rational, LimitExceeded> {
+    let bits = bits.to_u64().unwrap_or(u64::MAX);
+    options.check_digits(bits as f64 * LOG10_2)?;
+
+    Ok(Rational::new(
+        random_bits(bits, options),
+        Integer::one() << bits as usize,
+    ))
+}
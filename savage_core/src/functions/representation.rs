@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::ToPrimitive;
+use savage_macros::function;
+
+use crate::{
+    bigfloat::Rounding,
+    evaluate::{LimitExceeded, Options},
+    expression::{Expression, RationalRepresentation},
+    functions::{function_expression, PositiveInteger},
+    helpers::fun,
+};
+
+/// Returns the expression with the preferred representation of all numbers it contains
+/// (including the components of vectors and matrices) set to `representation`,
+/// or `None` if the expression is neither a number nor a vector or matrix of numbers.
+fn with_representation(
+    expression: &Expression,
+    representation: RationalRepresentation,
+) -> Option<Expression> {
+    use crate::expression::Expression::*;
+
+    match expression {
+        Integer(_) => Some(expression.clone()),
+        Rational(x, _) => Some(Rational(x.clone(), representation)),
+        Complex(z, _) => Some(Complex(z.clone(), representation)),
+        Vector(v) => v
+            .iter()
+            .map(|x| with_representation(x, representation))
+            .collect::<Option<Vec<_>>>()
+            .map(|elements| Vector(crate::expression::Vector::from_vec(elements))),
+        Matrix(m) => m
+            .iter()
+            .map(|x| with_representation(x, representation))
+            .collect::<Option<Vec<_>>>()
+            .map(|elements| {
+                Matrix(crate::expression::Matrix::from_vec(
+                    m.nrows(),
+                    m.ncols(),
+                    elements,
+                ))
+            }),
+        Tensor(t) => t
+            .elements()
+            .iter()
+            .map(|x| with_representation(x, representation))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|elements| crate::expression::Tensor::new(t.shape().to_vec(), elements))
+            .map(Tensor),
+        _ => None,
+    }
+}
+
+#[function(
+    name = "decimal",
+    description = "`x` with all numbers in decimal representation where they have a finite decimal expansion",
+    examples = r#"[
+        ("decimal(1/8)", "0.125"),
+        ("decimal(5/2 + 1/4 * i)", "2.5 + 0.25*i"),
+        ("decimal([1/2, 3/4])", "[0.5, 0.75]"),
+        ("decimal(1/3)", "1/3"),
+        ("decimal(x)", "decimal(x)"),
+    ]"#,
+    categories = r#"[
+        "number representation",
+    ]"#
+)]
+fn decimal(x: Expression) -> Expression {
+    with_representation(&x, RationalRepresentation::Decimal)
+        .unwrap_or_else(|| fun(function_expression("decimal").unwrap(), [x]))
+}
+
+#[function(
+    name = "fraction",
+    description = "`x` with all numbers in fraction representation",
+    examples = r#"[
+        ("fraction(0.125)", "1/8"),
+        ("fraction(1.5 * i)", "3/2*i"),
+        ("fraction([[0.5, 2], [0.2, 1]])", "[[1/2, 2], [1/5, 1]]"),
+        ("fraction(0.5) + 0.25", "0.75"),
+    ]"#,
+    categories = r#"[
+        "number representation",
+    ]"#
+)]
+fn fraction(x: Expression) -> Expression {
+    with_representation(&x, RationalRepresentation::Fraction)
+        .unwrap_or_else(|| fun(function_expression("fraction").unwrap(), [x]))
+}
+
+#[function(
+    name = "N",
+    description = "`x` with all real numerical subexpressions approximated to `digits` significant digits",
+    examples = r#"[
+        ("N(pi, 10)", "3.141592654"),
+        ("N(sqrt(2), 30)", "1.41421356237309504880168872421"),
+        ("N(e ^ 2 - 1/3, 8)", "7.0557228"),
+        ("N(sin(1) * cos(1), 6)", "0.454649"),
+        ("N(root_of(x^5 - x - 1, x, 1), 12)", "1.16730397826"),
+        ("N(1/3 + 2/3 * i, 3)", "0.333 + 0.667*i"),
+        ("N(i ^ i, 10)", "0.2078795764"),
+        ("N(x + pi, 4)", "x + 3.142"),
+        ("N(1000000, 2)", "1000000"),
+    ]"#,
+    categories = r#"[
+        "number representation",
+    ]"#
+)]
+fn numeric(
+    x: Expression,
+    digits: PositiveInteger,
+    options: &Options,
+) -> Result<Expression, LimitExceeded> {
+    let digits = digits.to_f64().unwrap_or(f64::INFINITY);
+    options.check_digits(digits)?;
+
+    x.approximate_within_limits(digits as u32, options)
+}
+
+#[function(
+    name = "N_interval",
+    description = "`x` with all real numerical subexpressions that are operands of arithmetic operations replaced with intervals certified to contain their values, with bounds rounded outward to `digits` significant digits",
+    examples = r#"[
+        ("N_interval(pi, 10)", "interval(3.141592653, 3.141592654)"),
+        ("N_interval(sqrt(2), 30)", "interval(1.4142135623730950488016887242, 1.41421356237309504880168872421)"),
+        ("N_interval(e ^ 2 - 1/3, 8)", "interval(7.0557227, 7.0557228)"),
+        ("N_interval(1/3, 3)", "interval(0.333, 0.334)"),
+        ("N_interval(x + pi, 4)", "x + interval(3.141, 3.142)"),
+        ("N_interval(gamma(1/3), 4)", "gamma(1/3)"),
+    ]"#,
+    categories = r#"[
+        "number representation",
+        "intervals",
+    ]"#
+)]
+fn numeric_interval(
+    x: Expression,
+    digits: PositiveInteger,
+    options: &Options,
+) -> Result<Expression, LimitExceeded> {
+    let digits = digits.to_f64().unwrap_or(f64::INFINITY);
+    options.check_digits(digits)?;
+
+    x.enclose_subexpressions(digits as u32, options)
+}
+
+/// Returns the bound of an interval certified to contain the value of `x`
+/// (see `numeric_interval`), rounded in the given direction to `digits`
+/// significant digits, or the unevaluated function value if `x` is not
+/// a real number for which such an interval can be computed.
+fn numeric_bound(
+    name: &str,
+    x: Expression,
+    digits: PositiveInteger,
+    rounding: Rounding,
+    options: &Options,
+) -> Result<Expression, LimitExceeded> {
+    let digits_original = Expression::Integer(digits.clone());
+    let digits = digits.to_f64().unwrap_or(f64::INFINITY);
+    options.check_digits(digits)?;
+
+    Ok(match x.enclose(digits as u32, options)? {
+        Some(interval) => Expression::Rational(
+            match rounding {
+                Rounding::Up => interval.upper().clone(),
+                _ => interval.lower().clone(),
+            },
+            RationalRepresentation::Decimal,
+        ),
+        None => fun(function_expression(name).unwrap(), [x, digits_original]),
+    })
+}
+
+#[function(
+    name = "N_down",
+    description = "number with `digits` significant digits that is certified to be less than or equal to `x`, computed with directed rounding",
+    examples = r#"[
+        ("N_down(pi, 10)", "3.141592653"),
+        ("N_down(-sqrt(2), 5)", "-1.4143"),
+        ("N_down(1/3, 3)", "0.333"),
+        ("N_down(x, 3)", "N_down(x, 3)"),
+    ]"#,
+    categories = r#"[
+        "number representation",
+        "rounding",
+    ]"#
+)]
+fn numeric_down(
+    x: Expression,
+    digits: PositiveInteger,
+    options: &Options,
+) -> Result<Expression, LimitExceeded> {
+    numeric_bound("N_down", x, digits, Rounding::Down, options)
+}
+
+#[function(
+    name = "N_up",
+    description = "number with `digits` significant digits that is certified to be greater than or equal to `x`, computed with directed rounding",
+    examples = r#"[
+        ("N_up(pi, 10)", "3.141592654"),
+        ("N_up(-sqrt(2), 5)", "-1.4142"),
+        ("N_up(1/3, 3)", "0.334"),
+        ("N_up(x, 3)", "N_up(x, 3)"),
+    ]"#,
+    categories = r#"[
+        "number representation",
+        "rounding",
+    ]"#
+)]
+fn numeric_up(
+    x: Expression,
+    digits: PositiveInteger,
+    options: &Options,
+) -> Result<Expression, LimitExceeded> {
+    numeric_bound("N_up", x, digits, Rounding::Up, options)
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{Signed, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    expression::{Expression, Integer, Rational, RationalRepresentation, Type},
+    functions::{function_expression, Expressions},
+    helpers::*,
+};
+
+/// Returns `x` rounded to `digits` decimal places in the direction given by `round`,
+/// which is applied to `x` scaled such that the digits to be kept are integral.
+/// Negative values of `digits` round to the corresponding power of ten.
+#[allow(clippy::result_large_err)]
+fn round_to_digits(
+    x: Rational,
+    digits: Integer,
+    round: fn(&Rational) -> Rational,
+) -> Result<Expression, Expression> {
+    let exponent = match digits.abs().to_usize() {
+        Some(exponent) => exponent,
+        None => return Err(digits.into()),
+    };
+
+    let scale = Rational::from_integer(num::pow(Integer::from(10), exponent));
+
+    let result = if digits.is_negative() {
+        round(&(x / &scale)) * scale
+    } else {
+        round(&(x * &scale)) / scale
+    };
+
+    Ok(Expression::Rational(
+        result,
+        RationalRepresentation::Decimal,
+    ))
+}
+
+#[function(
+    name = "round_down",
+    description = "largest number with at most `digits` decimal places that is less than or equal to `x`",
+    examples = r#"[
+        ("round_down(1/3, 3)", "0.333"),
+        ("round_down(-1/3, 3)", "-0.334"),
+        ("round_down(2/3, 0)", "0"),
+        ("round_down(12345, -2)", "12300"),
+    ]"#,
+    categories = r#"[
+        "rounding",
+    ]"#
+)]
+fn round_down(x: Rational, digits: Integer) -> Result<Expression, Expression> {
+    round_to_digits(x, digits, Rational::floor)
+}
+
+#[function(
+    name = "round_up",
+    description = "smallest number with at most `digits` decimal places that is greater than or equal to `x`",
+    examples = r#"[
+        ("round_up(1/3, 3)", "0.334"),
+        ("round_up(-1/3, 3)", "-0.333"),
+        ("round_up(2/3, 0)", "1"),
+        ("round_up(12345, -2)", "12400"),
+    ]"#,
+    categories = r#"[
+        "rounding",
+    ]"#
+)]
+fn round_up(x: Rational, digits: Integer) -> Result<Expression, Expression> {
+    round_to_digits(x, digits, Rational::ceil)
+}
+
+#[function(
+    name = "round",
+    description = "number with at most `digits` (default 0) decimal places that is closest to `x`, rounding half away from zero",
+    examples = r#"[
+        ("round(2.5)", "3"),
+        ("round(2/3, 4)", "0.6667"),
+        ("round(-2.5, 0)", "-3"),
+        ("round(1/8, 2)", "0.13"),
+        ("round(12345, -2)", "12300"),
+    ]"#,
+    categories = r#"[
+        "rounding",
+    ]"#
+)]
+fn round(x: Expression, digits: Expressions) -> Result<Expression, Expression> {
+    let is_symbolic = |expression: &Expression| {
+        matches!(
+            expression.typ(),
+            Type::Arithmetic | Type::Unknown | Type::Boolean(None),
+        )
+    };
+
+    if is_symbolic(&x) || digits.iter().any(is_symbolic) {
+        return Ok(fun(
+            function_expression("round").unwrap(),
+            [x].into_iter().chain(digits).collect::<Vec<_>>(),
+        ));
+    }
+
+    let digits = match digits.as_slice() {
+        [] => Integer::zero(),
+        [digits] => digits.clone().try_into()?,
+        [_, extra, ..] => return Err(extra.clone()),
+    };
+
+    round_to_digits(x.try_into()?, digits, Rational::round)
+}
+
+#[function(
+    name = "floor",
+    description = "largest integer less than or equal to `x`",
+    examples = r#"[
+        ("floor(7/2)", "3"),
+        ("floor(-7/2)", "-4"),
+        ("floor(2.5)", "2"),
+        ("floor(5)", "5"),
+    ]"#,
+    categories = r#"[
+        "rounding",
+    ]"#
+)]
+fn floor(x: Rational) -> Integer {
+    x.floor().to_integer()
+}
+
+#[function(
+    name = "ceil",
+    description = "smallest integer greater than or equal to `x`",
+    examples = r#"[
+        ("ceil(7/2)", "4"),
+        ("ceil(-7/2)", "-3"),
+        ("ceil(2.5)", "3"),
+        ("ceil(5)", "5"),
+    ]"#,
+    categories = r#"[
+        "rounding",
+    ]"#
+)]
+fn ceil(x: Rational) -> Integer {
+    x.ceil().to_integer()
+}
@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::f64::consts::{LOG10_E, LOG2_10};
+
+use num::{range_inclusive, One, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    evaluate::{LimitExceeded, Options, Resource},
+    expression::{Expression, Integer, Rational},
+    functions::{function_expression, NonNegativeInteger},
+    helpers::*,
+};
+
+/// Checks that the number of digits of the `n`th Fibonacci or Lucas number,
+/// which is about `n * log10(phi)`, does not exceed the limit set by the options.
+fn check_fibonacci_digits(n: &Integer, options: &Options) -> Result<(), LimitExceeded> {
+    let phi = (1.0 + 5_f64.sqrt()) / 2.0;
+    options.check_digits(n.to_f64().unwrap_or(f64::INFINITY) * phi.log10())
+}
+
+/// Returns the pair of Fibonacci numbers `(F(n), F(n + 1))`.
+fn fibonacci_pair(n: &Integer) -> (Integer, Integer) {
+    // https://www.nayuki.io/page/fast-fibonacci-algorithms
+    let mut a = Integer::zero();
+    let mut b = Integer::one();
+
+    for i in (0..n.bits()).rev() {
+        // (F(2k), F(2k + 1)) from (F(k), F(k + 1)).
+        let c = &a * (&b * 2 - &a);
+        let d = &a * &a + &b * &b;
+
+        if n.bit(i) {
+            a = d;
+            b = c + &a;
+        } else {
+            a = c;
+            b = d;
+        }
+    }
+
+    (a, b)
+}
+
+#[function(
+    name = "fibonacci",
+    description = "`n`th Fibonacci number",
+    examples = r#"[
+        ("fibonacci(0)", "0"),
+        ("fibonacci(1)", "1"),
+        ("fibonacci(10)", "55"),
+        ("fibonacci(100)", "354224848179261915075"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "integer sequences",
+    ]"#
+)]
+fn fibonacci(n: NonNegativeInteger, options: &Options) -> Result<Integer, LimitExceeded> {
+    check_fibonacci_digits(&n, options)?;
+
+    Ok(fibonacci_pair(&n).0)
+}
+
+#[function(
+    name = "lucas",
+    description = "`n`th Lucas number",
+    examples = r#"[
+        ("lucas(0)", "2"),
+        ("lucas(1)", "1"),
+        ("lucas(10)", "123"),
+        ("lucas(100)", "792070839848372253127"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "integer sequences",
+    ]"#
+)]
+fn lucas(n: NonNegativeInteger, options: &Options) -> Result<Integer, LimitExceeded> {
+    check_fibonacci_digits(&n, options)?;
+
+    let (a, b) = fibonacci_pair(&n);
+
+    // L(n) = F(n - 1) + F(n + 1) = 2 * F(n + 1) - F(n).
+    Ok(b * 2 - a)
+}
+
+#[function(
+    name = "catalan",
+    description = "`n`th Catalan number",
+    examples = r#"[
+        ("catalan(0)", "1"),
+        ("catalan(1)", "1"),
+        ("catalan(10)", "16796"),
+        ("catalan(50)", "1978261657756160653623774456"),
+    ]"#,
+    categories = r#"[
+        "combinatorics",
+        "integer sequences",
+    ]"#
+)]
+fn catalan(n: NonNegativeInteger, options: &Options) -> Result<Integer, LimitExceeded> {
+    // C(n) < 4 ^ n
+    options.check_digits(n.to_f64().unwrap_or(f64::INFINITY) * 4_f64.log10())?;
+
+    // C(n) = binomial(2n, n) / (n + 1). After the `k`th iteration,
+    // the intermediate value is binomial(n + k, k), so all divisions are exact.
+    let mut binomial = Integer::one();
+
+    for k in range_inclusive(Integer::one(), n.clone()) {
+        options.check_time()?;
+
+        binomial = binomial * (&n + &k) / k;
+    }
+
+    Ok(binomial / (n + 1))
+}
+
+#[function(
+    name = "bernoulli",
+    description = "`n`th Bernoulli number, with the convention B(1) = -1/2",
+    examples = r#"[
+        ("bernoulli(0)", "1"),
+        ("bernoulli(1)", "-1/2"),
+        ("bernoulli(2)", "1/6"),
+        ("bernoulli(3)", "0"),
+        ("bernoulli(12)", "-691/2730"),
+        ("bernoulli(30)", "8615841276005/14322"),
+    ]"#,
+    categories = r#"[
+        "number theory",
+        "rational sequences",
+    ]"#
+)]
+fn bernoulli(n: NonNegativeInteger, options: &Options) -> Result<Expression, LimitExceeded> {
+    let m = n.to_f64().unwrap_or(f64::INFINITY);
+
+    // The algorithm keeps `n + 1` intermediate values, whose denominators
+    // are divisors of `lcm(1, ..., n + 1)`, which has about `n * log10(e)` digits.
+    options.check(Resource::Memory, (m + 1.0) * m * LOG10_E * LOG2_10 / 8.0)?;
+
+    Ok(match n.to_usize() {
+        Some(n) => bernoulli_number(n, options)?.into(),
+        None => fun(function_expression("bernoulli").unwrap(), [int(n)]),
+    })
+}
+
+/// Returns the `n`th Bernoulli number, with the convention B(1) = -1/2,
+/// or an error if the computation exceeds the timeout set by the options.
+pub(super) fn bernoulli_number(n: usize, options: &Options) -> Result<Rational, LimitExceeded> {
+    if n == 1 {
+        return Ok(Rational::new(Integer::from(-1), Integer::from(2)));
+    } else if n % 2 == 1 {
+        return Ok(Rational::zero());
+    }
+
+    // https://en.wikipedia.org/wiki/Bernoulli_number#Algorithmic_description
+    // (Akiyama-Tanigawa algorithm, which yields B(1) = +1/2,
+    // a case that has already been handled above).
+    let mut a = Vec::new();
+
+    for m in 0..=n {
+        options.check_time()?;
+
+        a.push(Rational::new(Integer::one(), Integer::from(m + 1)));
+
+        for j in (1..=m).rev() {
+            a[j - 1] = (&a[j - 1] - &a[j]) * Integer::from(j);
+        }
+    }
+
+    Ok(a.swap_remove(0))
+}
+
+#[function(
+    name = "stirling",
+    description = "Stirling number of the second kind, i.e. the number of ways to partition a set of `n` elements into `k` non-empty subsets",
+    examples = r#"[
+        ("stirling(0, 0)", "1"),
+        ("stirling(4, 2)", "7"),
+        ("stirling(3, 5)", "0"),
+        ("stirling(10, 3)", "9330"),
+        ("stirling(20, 10)", "5917584964655"),
+    ]"#,
+    categories = r#"[
+        "combinatorics",
+        "integer sequences",
+    ]"#
+)]
+fn stirling(
+    n: NonNegativeInteger,
+    k: NonNegativeInteger,
+    options: &Options,
+) -> Result<Expression, LimitExceeded> {
+    if k > n {
+        return Ok(int(0));
+    }
+
+    // S(n, k) <= k ^ n / k!, and the algorithm keeps `k + 1` such values.
+    let digits = n.to_f64().unwrap_or(f64::INFINITY) * k.to_f64().unwrap_or(f64::INFINITY).log10();
+    options.check_digits(digits)?;
+    options.check(
+        Resource::Memory,
+        (k.to_f64().unwrap_or(f64::INFINITY) + 1.0) * digits * LOG2_10 / 8.0,
+    )?;
+
+    let (n_usize, k_usize) = match (n.to_usize(), k.to_usize()) {
+        (Some(n), Some(k)) => (n, k),
+        _ => {
+            return Ok(fun(
+                function_expression("stirling").unwrap(),
+                [int(n), int(k)],
+            ))
+        }
+    };
+
+    // S(n, k) = k * S(n - 1, k) + S(n - 1, k - 1), computed row by row.
+    let mut row = vec![Integer::zero(); k_usize + 1];
+    row[0] = Integer::one();
+
+    for i in 1..=n_usize {
+        options.check_time()?;
+
+        for j in (1..=k_usize.min(i)).rev() {
+            row[j] = &row[j] * Integer::from(j) + &row[j - 1];
+        }
+
+        row[0] = Integer::zero();
+    }
+
+    Ok(int(row.swap_remove(k_usize)))
+}
@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::f64::consts::LOG10_E;
+
+use num::{Integer as _, One, Signed, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    evaluate::{LimitExceeded, Options},
+    expression::{Expression, Integer, Rational, RationalRepresentation::Decimal, Type},
+    functions::{
+        combinatorics::check_factorial_digits, function_expression, inexact_value,
+        sequences::bernoulli_number, FunctionError,
+    },
+    helpers::*,
+};
+
+/// Maximum absolute value of integers at which `zeta` is evaluated exactly.
+/// The Bernoulli numbers required for larger integers are expensive to compute,
+/// and the exact values are unwieldy.
+const MAX_EXACT_ZETA: i64 = 256;
+
+/// Returns the exact value of the Riemann zeta function at the integer,
+/// `None` if it is not known or too expensive to compute, or an error
+/// if computing it exceeds the timeout set by the options.
+fn integer_zeta(n: &Integer, options: &Options) -> Result<Option<Expression>, LimitExceeded> {
+    let n = match n.to_i64().filter(|n| n.abs() <= MAX_EXACT_ZETA) {
+        Some(n) => n,
+        None => return Ok(None),
+    };
+
+    if n <= 0 {
+        // zeta(-m) = (-1) ^ m * B(m + 1) / (m + 1)
+        let m = -n as usize;
+        let value = bernoulli_number(m + 1, options)? / Integer::from(m + 1);
+
+        return Ok(Some(if m % 2 == 1 { -value } else { value }.into()));
+    } else if n % 2 == 1 {
+        return Ok(None);
+    }
+
+    // zeta(2 * m) = |B(2 * m)| * 2 ^ (2 * m - 1) / (2 * m)! * pi ^ (2 * m)
+    let factorial: Integer = (1..=n).map(Integer::from).product();
+    let coefficient = bernoulli_number(n as usize, options)?.abs()
+        * (Integer::one() << (n - 1) as usize)
+        / factorial;
+
+    let power = pow(var("pi"), int(n));
+    let numerator = if coefficient.numer().is_one() {
+        power
+    } else {
+        int(coefficient.numer().clone()) * power
+    };
+
+    Ok(Some(numerator / int(coefficient.denom().clone())))
+}
+
+#[function(
+    name = "erf",
+    description = "error function of `x`, which is approximated for decimal numbers other than 0",
+    examples = r#"[
+        ("erf(0)", "0"),
+        ("erf(1)", "erf(1)"),
+        ("erf(-0.5)", "-0.520499877813047"),
+        ("erf(10.5)", "1"),
+        ("N(erf(1), 30)", "0.842700792949714869341220635083"),
+        ("erf(x)", "erf(x)"),
+    ]"#,
+    categories = r#"[
+        "special functions",
+    ]"#
+)]
+fn erf(x: Expression) -> Result<Expression, Expression> {
+    match x.typ() {
+        Type::Number(z, representation) if z.is_zero() => {
+            Ok(Expression::Rational(Rational::zero(), representation))
+        }
+        Type::Number(z, Decimal) if z.im.is_zero() => Ok(inexact_value("erf", [x])),
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+            Ok(fun(function_expression("erf").unwrap(), [x]))
+        }
+        _ => Err(x),
+    }
+}
+
+#[function(
+    name = "erfc",
+    description = "complementary error function `1 - erf(x)` of `x`, which is approximated for decimal numbers other than 0",
+    examples = r#"[
+        ("erfc(0)", "1"),
+        ("erfc(1)", "erfc(1)"),
+        ("erfc(-2.5)", "1.99959304798256"),
+        ("erfc(5.5)", "0.0000000000000073578479179744"),
+        ("N(erfc(1), 20)", "0.15729920705028513066"),
+        ("erfc(x)", "erfc(x)"),
+    ]"#,
+    categories = r#"[
+        "special functions",
+    ]"#
+)]
+fn erfc(x: Expression, options: &Options) -> Result<Expression, FunctionError> {
+    match x.typ() {
+        Type::Number(z, representation) if z.is_zero() => {
+            Ok(Expression::Rational(Rational::one(), representation))
+        }
+        Type::Number(z, Decimal) if z.im.is_zero() => {
+            if z.re.is_positive() {
+                // erfc(x) < exp(-x ^ 2) for positive `x`.
+                let x = z.re.to_f64().unwrap_or(f64::INFINITY);
+                options.check_digits(x * x * LOG10_E)?;
+            }
+
+            Ok(inexact_value("erfc", [x]))
+        }
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+            Ok(fun(function_expression("erfc").unwrap(), [x]))
+        }
+        _ => Err(x.into()),
+    }
+}
+
+#[function(
+    name = "zeta",
+    description = "Riemann zeta function of `s`, which is evaluated exactly for even and non-positive integers and approximated for other decimal numbers",
+    examples = r#"[
+        ("zeta(2)", "pi ^ 2 / 6"),
+        ("zeta(4)", "pi ^ 4 / 90"),
+        ("zeta(12)", "691 * pi ^ 12 / 638512875"),
+        ("zeta(0)", "-1/2"),
+        ("zeta(-1)", "-1/12"),
+        ("zeta(-2)", "0"),
+        ("zeta(3)", "zeta(3)"),
+        ("zeta(0.5)", "-1.46035450880959"),
+        ("N(zeta(3), 20)", "1.2020569031595942854"),
+        ("zeta(s)", "zeta(s)"),
+    ]"#,
+    categories = r#"[
+        "special functions",
+    ]"#
+)]
+fn zeta(s: Expression, options: &Options) -> Result<Expression, FunctionError> {
+    match s.typ() {
+        Type::Number(z, representation) if z.im.is_zero() => {
+            // The zeta function has a pole at 1.
+            if z.re.is_one() {
+                return Err(s.into());
+            }
+
+            if z.re.is_integer() {
+                let n = z.re.to_integer();
+
+                // Trivial zeros
+                if n.is_negative() && n.is_even() {
+                    return Ok(int(0));
+                }
+
+                if let Some(value) = integer_zeta(&n, options)? {
+                    return Ok(value);
+                }
+            }
+
+            if representation == Decimal && z.re.is_negative() {
+                // The magnitude of zeta(s) for negative `s` grows like `gamma(1 - s)`.
+                check_factorial_digits(z.re.abs().to_f64().unwrap_or(f64::INFINITY), options)?;
+            }
+
+            Ok(if representation == Decimal {
+                inexact_value("zeta", [s])
+            } else {
+                fun(function_expression("zeta").unwrap(), [s])
+            })
+        }
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+            Ok(fun(function_expression("zeta").unwrap(), [s]))
+        }
+        _ => Err(s.into()),
+    }
+}
+
+#[function(
+    name = "Ei",
+    description = "exponential integral of `x`, which is approximated for decimal numbers other than 0",
+    examples = r#"[
+        ("Ei(1)", "Ei(1)"),
+        ("Ei(-1.5)", "-0.100019582406633"),
+        ("N(Ei(1), 20)", "1.8951178163559367555"),
+        ("Ei(0.01)", "-4.01792946542667"),
+        ("Ei(x)", "Ei(x)"),
+    ]"#,
+    categories = r#"[
+        "special functions",
+    ]"#
+)]
+fn exponential_integral(x: Expression, options: &Options) -> Result<Expression, FunctionError> {
+    match x.typ() {
+        // The exponential integral has a logarithmic singularity at 0.
+        Type::Number(z, _) if z.is_zero() => Err(x.into()),
+        Type::Number(z, Decimal) if z.im.is_zero() => {
+            // |Ei(x)| is about exp(x) / |x| for large `|x|`, so the decimal approximation
+            // has about `|x| * log10(e)` digits.
+            let x_abs = z.re.abs().to_f64().unwrap_or(f64::INFINITY);
+            options.check_digits(x_abs * LOG10_E)?;
+
+            Ok(inexact_value("Ei", [x]))
+        }
+        Type::Number(_, _) | Type::Arithmetic | Type::Unknown => {
+            Ok(fun(function_expression("Ei").unwrap(), [x]))
+        }
+        _ => Err(x.into()),
+    }
+}
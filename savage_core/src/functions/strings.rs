@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use savage_macros::function;
+
+use crate::{evaluate::Options, expression::Expression, functions::Expressions};
+
+#[function(
+    name = "format",
+    description = "`template` with each `{}` replaced by the next of the `values`, formatted using the format options, and `{{` and `}}` replaced by `{` and `}`",
+    examples = r#"[
+        ("format(\"x = {}, x^2 = {}\", 1/2, (1/2)^2)", "\"x = 1/2, x^2 = 1/4\""),
+        ("format(\"{} + {}\", \"a\", 0.5 * x)", "\"a + 0.5 * x\""),
+        ("format(\"{{}}\")", "\"{}\""),
+    ]"#,
+    categories = r#"[
+        "strings",
+    ]"#
+)]
+fn format(template: String, values: Expressions, options: &Options) -> Result<String, String> {
+    let mut result = String::new();
+    let mut values = values.iter();
+    let mut characters = template.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match (character, characters.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                result.push(character);
+                characters.next();
+            }
+            ('{', Some('}')) => {
+                let value = values.next().ok_or_else(|| {
+                    "format string contains more placeholders than values were given".to_owned()
+                })?;
+
+                match value {
+                    Expression::String(string) => result.push_str(string),
+                    _ => result.push_str(&value.display_with(&options.format_options).to_string()),
+                }
+
+                characters.next();
+            }
+            ('{' | '}', _) => {
+                return Err(format!("unmatched '{}' in format string", character));
+            }
+            _ => result.push(character),
+        }
+    }
+
+    if values.next().is_some() {
+        return Err("format string contains fewer placeholders than values were given".to_owned());
+    }
+
+    Ok(result)
+}
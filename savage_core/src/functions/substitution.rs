@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::collections::HashMap;
+
+use savage_macros::function;
+
+use crate::{evaluate::Options, expression::Expression, functions::FunctionError};
+
+#[function(
+    name = "subs",
+    description = "value of `f` with each occurrence of the sub-expression `x` replaced by `value`",
+    examples = r#"[
+        ("subs(x^2 + 1, x, 3)", "10"),
+        ("subs(x^2 + 1, x, y + 1)", "(y + 1) ^ 2 + 1"),
+        ("subs(sin(x) + x, sin(x), 1/2)", "1/2 + x"),
+        ("subs([x, y], y, x)", "[x, x]"),
+    ]"#,
+    categories = r#"[
+        "substitution",
+    ]"#
+)]
+fn subs(
+    f: Expression,
+    x: Expression,
+    value: Expression,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    f.substitute(&x, &value)
+        .evaluate_with_options(HashMap::new(), options)
+        .map_err(|error| FunctionError::Failed(error.to_string()))
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::collections::HashMap;
+
+use savage_macros::function;
+
+use crate::{
+    evaluate::{complete_context, Options},
+    expression::{Expression, Integer},
+    functions::FunctionError,
+    helpers::*,
+};
+
+/// Returns the result of combining the values of `f` for each integer value
+/// of the variable `k` from `a` to `b` using `combine`, or `identity` if the range is empty.
+#[allow(clippy::result_large_err)]
+fn fold(
+    k: Expression,
+    a: Integer,
+    b: Integer,
+    f: Expression,
+    identity: Expression,
+    combine: fn(Expression, Expression) -> Expression,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    let identifier = match &k {
+        Expression::Variable(identifier) => identifier,
+        _ => return Err(k.into()),
+    };
+
+    let mut context = complete_context(HashMap::new(), options);
+    let mut result = None;
+    let mut value = a;
+
+    while value <= b {
+        context.insert(identifier.clone(), Expression::Integer(value.clone()));
+
+        let term = f.evaluate_in_complete_context(&context, options)?;
+
+        result = Some(match result {
+            Some(result) => {
+                combine(result, term).evaluate_in_complete_context(&context, options)?
+            }
+            None => term,
+        });
+
+        value += 1;
+    }
+
+    Ok(result.unwrap_or(identity))
+}
+
+#[function(
+    name = "sum",
+    description = "sum of the values of `f` for each integer value of the variable `k` from `a` to `b`",
+    examples = r#"[
+        ("sum(k, 1, 100, k)", "5050"),
+        ("sum(k, 1, 4, 1 / k)", "25/12"),
+        ("sum(k, 1, 3, x ^ k)", "x + x ^ 2 + x ^ 3"),
+        ("sum(k, 1, 3, [k, k ^ 2])", "[6, 14]"),
+        ("sum(k, 1, 0, k)", "0"),
+        ("sum(k, 1, n, k)", "sum(k, 1, n, k)"),
+    ]"#,
+    categories = r#"[
+        "summation",
+    ]"#
+)]
+fn sum(
+    k: Expression,
+    a: Integer,
+    b: Integer,
+    f: Expression,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    fold(k, a, b, f, int(0), |a, b| a + b, options)
+}
+
+#[function(
+    name = "product",
+    description = "product of the values of `f` for each integer value of the variable `k` from `a` to `b`",
+    examples = r#"[
+        ("product(k, 1, 10, k)", "3628800"),
+        ("product(k, 2, 4, 1 - 1 / k^2)", "5/8"),
+        ("product(k, 1, 3, x + k)", "(x + 1) * (x + 2) * (x + 3)"),
+        ("product(k, 1, 0, k)", "1"),
+    ]"#,
+    categories = r#"[
+        "summation",
+    ]"#
+)]
+fn product(
+    k: Expression,
+    a: Integer,
+    b: Integer,
+    f: Expression,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    fold(k, a, b, f, int(1), |a, b| a * b, options)
+}
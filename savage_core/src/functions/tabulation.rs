@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::collections::HashMap;
+
+use num::{Signed, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    evaluate::{complete_context, Options, Resource},
+    expression::{Expression, Matrix, Rational, RationalRepresentation, Type},
+    functions::{function_expression, FunctionError},
+    helpers::*,
+};
+
+/// Returns the value and preferred representation of the expression if it is
+/// a real number, `Ok(None)` if the expression might evaluate to a real number,
+/// and the expression as an error otherwise.
+#[allow(clippy::result_large_err)]
+fn real(x: &Expression) -> Result<Option<(Rational, RationalRepresentation)>, Expression> {
+    match x.typ() {
+        Type::Number(z, representation) if z.im.is_zero() => Ok(Some((z.re, representation))),
+        Type::Arithmetic | Type::Unknown => Ok(None),
+        _ => Err(x.clone()),
+    }
+}
+
+#[function(
+    name = "table",
+    description = "matrix whose rows contain the values of `x` from `a` to `b` in increments of `step`, and the corresponding values of `f`",
+    examples = r#"[
+        ("table(x ^ 2, x, 0, 3, 1)", "[[0, 0], [1, 1], [2, 4], [3, 9]]"),
+        ("table(1 / x, x, 1, 2, 1/4)", "[[1, 1], [5/4, 4/5], [3/2, 2/3], [7/4, 4/7], [2, 1/2]]"),
+        ("table(2 * n + 1, n, 0, 1, 0.25)", "[[0, 1], [0.25, 1.5], [0.5, 2], [0.75, 2.5], [1, 3]]"),
+        ("table(x + y, x, 1, 2, 1)", "[[1, 1 + y], [2, 2 + y]]"),
+    ]"#,
+    categories = r#"[
+        "tabulation",
+    ]"#
+)]
+fn table(
+    f: Expression,
+    x: Expression,
+    a: Expression,
+    b: Expression,
+    step: Expression,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    let identifier = match &x {
+        Expression::Variable(identifier) => identifier.clone(),
+        _ => return Err(x.into()),
+    };
+
+    let (a, a_representation, b, b_representation, step, step_representation) =
+        match (real(&a)?, real(&b)?, real(&step)?) {
+            (Some((a, a_r)), Some((b, b_r)), Some((step, step_r))) => {
+                (a, a_r, b, b_r, step, step_r)
+            }
+            _ => {
+                return Ok(fun(
+                    function_expression("table").unwrap(),
+                    [f, x, a, b, step],
+                ))
+            }
+        };
+
+    if !step.is_positive() {
+        return Err(Expression::from(step).into());
+    }
+
+    // Each row contains two elements.
+    if b >= a {
+        let rows = ((&b - &a) / &step)
+            .floor()
+            .to_f64()
+            .unwrap_or(f64::INFINITY)
+            + 1.0;
+        options.check(Resource::MatrixElements, 2.0 * rows)?;
+    }
+
+    let representation = a_representation
+        .merge(b_representation)
+        .merge(step_representation);
+
+    let mut elements = Vec::new();
+
+    let mut context = complete_context(HashMap::new(), options);
+    let mut value = a;
+
+    while value <= b {
+        let x = Expression::Rational(value.clone(), representation);
+
+        context.insert(identifier.clone(), x.clone());
+
+        let y = f.evaluate_in_complete_context(&context, options)?;
+
+        elements.push(x);
+        elements.push(y);
+
+        value += &step;
+    }
+
+    Ok(Matrix::from_row_slice(elements.len() / 2, 2, &elements).into())
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::ToPrimitive;
+use savage_macros::function;
+
+use crate::{
+    evaluate::{Options, Resource},
+    expression::{Expression, Integer, Tensor, Type, Vector},
+    functions::{function_expression, FunctionError, NonNegativeInteger},
+    helpers::*,
+};
+
+/// Returns the expression as a tensor if it is a vector, matrix, or tensor,
+/// `Ok(None)` if the expression might evaluate to one, and the expression
+/// as an error otherwise.
+#[allow(clippy::result_large_err)]
+fn tensor(x: &Expression) -> Result<Option<Tensor>, Expression> {
+    match x.typ() {
+        Type::Arithmetic | Type::Unknown => Ok(None),
+        _ => Tensor::from_expression(x)
+            .map(Some)
+            .ok_or_else(|| x.clone()),
+    }
+}
+
+#[function(
+    name = "shape",
+    description = "vector containing the size of each dimension of a vector, matrix, or tensor",
+    examples = r#"[
+        ("shape([1, 2, 3])", "[3]"),
+        ("shape([[1, 2, 3], [4, 5, 6]])", "[2, 3]"),
+        ("shape([[[1, 2], [3, 4]], [[5, 6], [7, 8]]])", "[2, 2, 2]"),
+    ]"#,
+    categories = r#"[
+        "linear algebra",
+    ]"#
+)]
+fn shape(x: Expression) -> Result<Expression, Expression> {
+    Ok(match tensor(&x)? {
+        Some(x) => Expression::Vector(Vector::from_iterator(
+            x.rank(),
+            x.shape().iter().map(|n| Integer::from(*n).into()),
+        )),
+        None => fun(function_expression("shape").unwrap(), [x]),
+    })
+}
+
+#[function(
+    name = "reshape",
+    description = "vector, matrix, or tensor with the elements of `x` in row-major order and the given shape",
+    examples = r#"[
+        ("reshape([1, 2, 3, 4, 5, 6], [2, 3])", "[[1, 2, 3], [4, 5, 6]]"),
+        ("reshape([[1, 2], [3, 4]], [4])", "[1, 2, 3, 4]"),
+        ("reshape([1, 2, 3, 4, 5, 6, 7, 8], [2, 2, 2])", "[[[1, 2], [3, 4]], [[5, 6], [7, 8]]]"),
+    ]"#,
+    categories = r#"[
+        "linear algebra",
+    ]"#
+)]
+fn reshape(x: Expression, shape: Vector) -> Result<Expression, Expression> {
+    let sizes = shape
+        .iter()
+        .map(|size| Integer::try_from(size.clone()).ok()?.to_usize())
+        .collect::<Option<Vec<_>>>();
+
+    Ok(match tensor(&x)? {
+        Some(x) => sizes
+            .filter(|sizes| !sizes.is_empty())
+            .and_then(|sizes| x.reshape(sizes))
+            .ok_or_else(|| Expression::Vector(shape))?
+            .into_expression(),
+        None => fun(
+            function_expression("reshape").unwrap(),
+            [x, Expression::Vector(shape)],
+        ),
+    })
+}
+
+#[function(
+    name = "tensordot",
+    description = "contraction of `a` and `b` over the last `n` dimensions of `a` and the first `n` dimensions of `b`",
+    examples = r#"[
+        ("tensordot([[1, 2], [3, 4]], [5, 6], 1)", "[17, 39]"),
+        ("tensordot([1, 2], [3, 4], 0)", "[[3, 4], [6, 8]]"),
+        ("tensordot([[1, 2], [3, 4]], [[1, 0], [0, 1]], 2)", "5"),
+        ("tensordot([[[1, 2], [3, 4]], [[5, 6], [7, 8]]], [1, 1], 1)", "[[3, 7], [11, 15]]"),
+    ]"#,
+    categories = r#"[
+        "linear algebra",
+    ]"#
+)]
+fn tensordot(
+    a: Expression,
+    b: Expression,
+    n: NonNegativeInteger,
+    options: &Options,
+) -> Result<Expression, FunctionError> {
+    Ok(match (tensor(&a)?, tensor(&b)?) {
+        (Some(a), Some(b)) => {
+            let n = n.to_usize().ok_or_else(|| Expression::Integer(n.clone()))?;
+
+            // Each element of the contraction is a sum of products of elements
+            // of the factors, consisting of about `4 * inner_length` expression nodes.
+            let inner_length = b.shape().iter().take(n).product::<usize>().max(1) as f64;
+            let elements = (a.elements().len() as f64) * (b.elements().len() as f64)
+                / (inner_length * inner_length);
+
+            options.check(Resource::MatrixElements, elements)?;
+            options.check(
+                Resource::Memory,
+                elements * 4.0 * inner_length * (std::mem::size_of::<Expression>() as f64),
+            )?;
+
+            a.contract(&b, n)
+                .ok_or_else(|| Expression::Integer(n.into()))?
+                .into_expression()
+        }
+        _ => fun(
+            function_expression("tensordot").unwrap(),
+            [a, b, Expression::Integer(n)],
+        ),
+    })
+}
@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{One, ToPrimitive, Zero};
+use savage_macros::function;
+
+use crate::{
+    expression::{Expression, Integer, Rational},
+    functions::{function_expression, inexact_value},
+    helpers::*,
+};
+
+/// Returns the rational number `q` such that the expression equals `q * pi`,
+/// or `None` if the expression is not recognizably of that form.
+fn pi_multiple(expression: &Expression) -> Option<Rational> {
+    use crate::expression::Expression::{Difference, Negation, Product, Quotient, Sum, Variable};
+
+    match expression {
+        Variable(identifier) if identifier == "pi" => Some(Rational::one()),
+        Negation(a) => pi_multiple(a).map(|q| -q),
+        Sum(a, b) => Some(pi_multiple(a)? + pi_multiple(b)?),
+        Difference(a, b) => Some(pi_multiple(a)? - pi_multiple(b)?),
+        Product(a, b) => {
+            if let Ok(x) = Rational::try_from((**a).clone()) {
+                Some(x * pi_multiple(b)?)
+            } else if let Ok(x) = Rational::try_from((**b).clone()) {
+                Some(pi_multiple(a)? * x)
+            } else {
+                None
+            }
+        }
+        Quotient(a, b) => {
+            let x = Rational::try_from((**b).clone()).ok()?;
+
+            if x.is_zero() {
+                None
+            } else {
+                Some(pi_multiple(a)? / x)
+            }
+        }
+        _ => Rational::try_from(expression.clone())
+            .ok()
+            .filter(|x| x.is_zero()),
+    }
+}
+
+/// Returns the square root of the given expression.
+fn sqrt_of(x: Expression) -> Expression {
+    fun(function_expression("sqrt").unwrap(), [x])
+}
+
+/// Returns the square root of the given positive integer as an expression.
+fn sqrt(n: i32) -> Expression {
+    sqrt_of(int(n))
+}
+
+/// Returns the exact value of `sin(q * pi)` for `0 <= q <= 1/2`,
+/// or `None` if `q` is not in the table of known values.
+fn sin_first_quadrant(q: &Rational) -> Option<Expression> {
+    let n = q * Integer::from(60);
+
+    if !n.is_integer() {
+        return None;
+    }
+
+    // https://en.wikipedia.org/wiki/Exact_trigonometric_values#Common_angles
+    Some(match n.to_integer().to_u32() {
+        Some(0) => int(0),
+        Some(5) => (sqrt(6) - sqrt(2)) / int(4),
+        Some(6) => (sqrt(5) - int(1)) / int(4),
+        Some(10) => rat(1, 2),
+        Some(12) => sqrt_of(int(10) - int(2) * sqrt(5)) / int(4),
+        Some(15) => sqrt(2) / int(2),
+        Some(18) => (sqrt(5) + int(1)) / int(4),
+        Some(20) => sqrt(3) / int(2),
+        Some(24) => sqrt_of(int(10) + int(2) * sqrt(5)) / int(4),
+        Some(25) => (sqrt(6) + sqrt(2)) / int(4),
+        Some(30) => int(1),
+        _ => return None,
+    })
+}
+
+/// Returns the exact value of `sin(q * pi)`, or `None` if it is not known.
+fn sin_pi_multiple(q: &Rational) -> Option<Expression> {
+    let two = Rational::from_integer(Integer::from(2));
+    let one = Rational::one();
+    let half = Rational::new(Integer::one(), Integer::from(2));
+
+    // Reduce to the interval [0, 2) using periodicity...
+    let q = q - (q / &two).floor() * &two;
+
+    // ... then to [0, 1] using sin(x + pi) = -sin(x)...
+    let (q, negative) = if q > one {
+        (q - &one, true)
+    } else {
+        (q, false)
+    };
+
+    // ... and finally to [0, 1/2] using sin(pi - x) = sin(x).
+    let q = if q > half { one - q } else { q };
+
+    let value = sin_first_quadrant(&q)?;
+
+    Some(if negative && value != int(0) {
+        -value
+    } else {
+        value
+    })
+}
+
+#[function(
+    name = "sin",
+    description = "sine of `x` (in radians), which is evaluated exactly for rational multiples of `pi` with known values and approximated for decimal numbers",
+    examples = r#"[
+        ("sin(0)", "0"),
+        ("sin(pi / 6)", "1/2"),
+        ("sin(pi / 4)", "sqrt(2) / 2"),
+        ("sin(-pi / 3)", "-sqrt(3) / 2"),
+        ("sin(pi / 5)", "sqrt(10 - 2 * sqrt(5)) / 4"),
+        ("sin(2 * pi / 5)", "sqrt(10 + 2 * sqrt(5)) / 4"),
+        ("sin(5 * pi / 2)", "1"),
+        ("sin(pi / 7)", "sin(pi / 7)"),
+        ("sin(1)", "sin(1)"),
+        ("sin(0.5)", "0.479425538604203"),
+        ("N(sin(1), 20)", "0.84147098480789650665"),
+        ("N(sin(10 ^ 30), 15)", "-0.0901169019121381"),
+        ("sin(x)", "sin(x)"),
+    ]"#,
+    categories = r#"[
+        "trigonometry",
+    ]"#
+)]
+fn sin(x: Expression) -> Expression {
+    pi_multiple(&x)
+        .and_then(|q| sin_pi_multiple(&q))
+        .unwrap_or_else(|| inexact_value("sin", [x]))
+}
+
+#[function(
+    name = "cos",
+    description = "cosine of `x` (in radians), which is evaluated exactly for rational multiples of `pi` with known values and approximated for decimal numbers",
+    examples = r#"[
+        ("cos(0)", "1"),
+        ("cos(pi / 3)", "1/2"),
+        ("cos(3 * pi / 4)", "-sqrt(2) / 2"),
+        ("cos(pi)", "-1"),
+        ("cos(pi / 5)", "(sqrt(5) + 1) / 4"),
+        ("cos(pi / 10)", "sqrt(10 + 2 * sqrt(5)) / 4"),
+        ("cos(2)", "cos(2)"),
+        ("cos(0.5)", "0.877582561890373"),
+        ("cos(x)", "cos(x)"),
+    ]"#,
+    categories = r#"[
+        "trigonometry",
+    ]"#
+)]
+fn cos(x: Expression) -> Expression {
+    // cos(x) = sin(pi/2 - x).
+    pi_multiple(&x)
+        .and_then(|q| sin_pi_multiple(&(Rational::new(Integer::one(), Integer::from(2)) - q)))
+        .unwrap_or_else(|| inexact_value("cos", [x]))
+}
+
+#[function(
+    name = "tan",
+    description = "tangent of `x` (in radians), which is evaluated exactly for rational multiples of `pi` with known values and approximated for decimal numbers",
+    examples = r#"[
+        ("tan(0)", "0"),
+        ("tan(pi / 3)", "sqrt(3)"),
+        ("tan(pi / 6)", "sqrt(3) / 3"),
+        ("tan(3 * pi / 4)", "-1"),
+        ("tan(pi / 12)", "2 - sqrt(3)"),
+        ("tan(-2)", "tan(-2)"),
+        ("tan(-2.5)", "0.74702229723866"),
+        ("tan(i)", "tan(i)"),
+        ("tan(x)", "tan(x)"),
+    ]"#,
+    categories = r#"[
+        "trigonometry",
+    ]"#
+)]
+fn tan(x: Expression) -> Result<Expression, Expression> {
+    let q = if let Some(q) = pi_multiple(&x) {
+        q
+    } else {
+        return Ok(inexact_value("tan", [x]));
+    };
+
+    let one = Rational::one();
+    let half = Rational::new(Integer::one(), Integer::from(2));
+
+    // Reduce to the interval [0, 1) using periodicity...
+    let q = &q - q.floor();
+
+    // ... then to [0, 1/2] using tan(pi - x) = -tan(x).
+    let (q, negative) = if q > half {
+        (one - q, true)
+    } else {
+        (q, false)
+    };
+
+    let n = &q * Integer::from(12);
+
+    let value = if n.is_integer() {
+        match n.to_integer().to_u32() {
+            Some(0) => int(0),
+            Some(1) => int(2) - sqrt(3),
+            Some(2) => sqrt(3) / int(3),
+            Some(3) => int(1),
+            Some(4) => sqrt(3),
+            Some(5) => int(2) + sqrt(3),
+            // The tangent has a pole at pi/2.
+            Some(6) => return Err(x),
+            _ => unreachable!(),
+        }
+    } else {
+        return Ok(inexact_value("tan", [x]));
+    };
+
+    Ok(if negative && value != int(0) {
+        -value
+    } else {
+        value
+    })
+}
@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{One, ToPrimitive};
+use savage_macros::function;
+
+use crate::{
+    expression::{Expression, RationalRepresentation::Fraction},
+    functions::FunctionError,
+    helpers::*,
+};
+
+/// Physical dimension, given by the exponents of the SI base units
+/// (metre, kilogram, second, ampere, kelvin, mole, and candela).
+type Dimension = [i32; 7];
+
+/// Identifiers of the SI base units, in the order used by `Dimension`.
+const BASE_UNITS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// Identifiers and definitions of derived units.
+/// Units may be defined in terms of other units from this table.
+const DERIVED_UNITS: &[(&str, &str)] = &[
+    // Length
+    ("km", "1000 * m"),
+    ("cm", "m / 100"),
+    ("mm", "m / 1000"),
+    ("um", "m / 1000000"),
+    ("nm", "m / 1000000000"),
+    ("inch", "0.0254 * m"),
+    ("ft", "0.3048 * m"),
+    ("yd", "0.9144 * m"),
+    ("mi", "1609.344 * m"),
+    ("nmi", "1852 * m"),
+    // Area
+    ("ha", "10000 * m ^ 2"),
+    ("acre", "4046.8564224 * m ^ 2"),
+    // Volume
+    ("L", "m ^ 3 / 1000"),
+    ("mL", "L / 1000"),
+    ("gal", "3.785411784 * L"),
+    // Mass
+    ("g", "kg / 1000"),
+    ("mg", "g / 1000"),
+    ("t", "1000 * kg"),
+    ("lb", "0.45359237 * kg"),
+    ("oz", "lb / 16"),
+    // Time
+    ("ms", "s / 1000"),
+    ("min", "60 * s"),
+    ("h", "60 * min"),
+    ("day", "24 * h"),
+    ("week", "7 * day"),
+    // Velocity
+    ("mph", "mi / h"),
+    ("kn", "nmi / h"),
+    // Force
+    ("N", "kg * m / s ^ 2"),
+    ("lbf", "4.4482216152605 * N"),
+    // Pressure
+    ("Pa", "N / m ^ 2"),
+    ("kPa", "1000 * Pa"),
+    ("bar", "100000 * Pa"),
+    ("atm", "101325 * Pa"),
+    ("psi", "lbf / inch ^ 2"),
+    // Energy
+    ("J", "N * m"),
+    ("kJ", "1000 * J"),
+    ("cal", "4.184 * J"),
+    ("kcal", "1000 * cal"),
+    ("Wh", "3600 * J"),
+    ("kWh", "1000 * Wh"),
+    // Power
+    ("W", "J / s"),
+    ("kW", "1000 * W"),
+    ("hp", "550 * ft * lbf / s"),
+    // Electromagnetism
+    ("C", "A * s"),
+    ("V", "W / A"),
+    ("ohm", "V / A"),
+];
+
+/// Returns the numerical coefficient (relative to SI base units) and the dimension
+/// of the given quantity expression, or `None` if the expression combines
+/// quantities of different dimensions in a way that is not meaningful.
+/// Identifiers that are not units are treated as dimensionless.
+fn quantity(expression: &Expression) -> Option<(Expression, Dimension)> {
+    use crate::expression::Expression::*;
+
+    let dimensionless = [0; 7];
+
+    match expression {
+        Variable(identifier) => {
+            if let Some(index) = BASE_UNITS.iter().position(|unit| unit == identifier) {
+                let mut dimension = dimensionless;
+                dimension[index] = 1;
+                Some((int(1), dimension))
+            } else if let Some((_, definition)) =
+                DERIVED_UNITS.iter().find(|(unit, _)| unit == identifier)
+            {
+                quantity(&definition.parse().unwrap())
+            } else {
+                Some((expression.clone(), dimensionless))
+            }
+        }
+        Negation(a) => {
+            let (a, dimension) = quantity(a)?;
+            Some((-a, dimension))
+        }
+        Sum(a, b) | Difference(a, b) => {
+            let (a, a_dimension) = quantity(a)?;
+            let (b, b_dimension) = quantity(b)?;
+
+            if a_dimension != b_dimension {
+                None
+            } else if let Sum(_, _) = expression {
+                Some((a + b, a_dimension))
+            } else {
+                Some((a - b, a_dimension))
+            }
+        }
+        Product(a, b) | Quotient(a, b) => {
+            let (a, a_dimension) = quantity(a)?;
+            let (b, b_dimension) = quantity(b)?;
+
+            let mut dimension = a_dimension;
+
+            for (x, y) in dimension.iter_mut().zip(b_dimension) {
+                if let Product(_, _) = expression {
+                    *x += y;
+                } else {
+                    *x -= y;
+                }
+            }
+
+            if let Product(_, _) = expression {
+                Some((a * b, dimension))
+            } else {
+                Some((a / b, dimension))
+            }
+        }
+        Power(a, b) => {
+            let (a, mut dimension) = quantity(a)?;
+
+            if dimension != dimensionless {
+                let exponent = crate::expression::Integer::try_from((**b).clone())
+                    .ok()?
+                    .to_i32()?;
+
+                for x in dimension.iter_mut() {
+                    *x = x.checked_mul(exponent)?;
+                }
+            } else if quantity(b)?.1 != dimensionless {
+                return None;
+            }
+
+            Some((pow(a, (**b).clone()), dimension))
+        }
+        Vector(_) | Matrix(_) | Tensor(_) | Boolean(_) | String(_) => None,
+        _ => Some((expression.clone(), dimensionless)),
+    }
+}
+
+/// Returns the normalized form of the coefficient of a converted quantity,
+/// with a fractional numerical factor that is not a decimal number written as a quotient, e.g. `x / 1000`
+/// rather than `1/1000 * x`.
+fn coefficient(expression: Expression) -> Expression {
+    use crate::expression::Expression::*;
+
+    match expression.normalize() {
+        Product(a, b) => match *a {
+            Rational(a, Fraction) if !a.is_integer() => {
+                let numerator = if a.numer().is_one() {
+                    *b
+                } else {
+                    int(a.numer().clone()) * *b
+                };
+
+                numerator / int(a.denom().clone())
+            }
+            a => a * *b,
+        },
+        expression => expression,
+    }
+}
+
+/// Returns the dimension as a product and quotient of powers of SI base units,
+/// e.g. `m * kg / s ^ 2`, or `1` if it is dimensionless.
+fn dimension_expression(dimension: Dimension) -> Expression {
+    let power = |unit: &str, exponent: i32| {
+        if exponent == 1 {
+            var(unit)
+        } else {
+            pow(var(unit), int(exponent))
+        }
+    };
+
+    let product = |factors: Vec<Expression>| factors.into_iter().reduce(|a, b| a * b);
+
+    let numerator = product(
+        BASE_UNITS
+            .iter()
+            .zip(dimension)
+            .filter(|(_, exponent)| *exponent > 0)
+            .map(|(unit, exponent)| power(unit, exponent))
+            .collect(),
+    );
+
+    let denominator = product(
+        BASE_UNITS
+            .iter()
+            .zip(dimension)
+            .filter(|(_, exponent)| *exponent < 0)
+            .map(|(unit, exponent)| power(unit, -exponent))
+            .collect(),
+    );
+
+    match denominator {
+        Some(denominator) => numerator.unwrap_or_else(|| int(1)) / denominator,
+        None => numerator.unwrap_or_else(|| int(1)),
+    }
+}
+
+#[function(
+    name = "convert",
+    description = "quantity expressed as a multiple of the given unit, with units from the SI, imperial, and US customary systems written as products and quotients of unit identifiers",
+    examples = r#"[
+        ("convert(90 * km / h, m / s)", "25 * m / s"),
+        ("convert(1 * acre, m ^ 2)", "4046.8564224 * m ^ 2"),
+        ("convert(60 * mph, km / h)", "96.56064 * km / h"),
+        ("convert(1 * kWh, J)", "3600000 * J"),
+        ("convert(2 * ft + 6 * inch, cm)", "76.2 * cm"),
+        ("convert(x * L, m ^ 3)", "x / 1000 * m ^ 3"),
+        ("convert(2 * x * km, cm)", "200000 * x * cm"),
+    ]"#,
+    categories = r#"[
+        "units",
+    ]"#
+)]
+fn convert(quantity: Expression, unit: Expression) -> Result<Expression, FunctionError> {
+    let (quantity_coefficient, quantity_dimension) =
+        self::quantity(&quantity).ok_or_else(|| quantity.clone())?;
+
+    let (unit_coefficient, unit_dimension) = self::quantity(&unit).ok_or_else(|| unit.clone())?;
+
+    if quantity_dimension == unit_dimension {
+        Ok(coefficient(quantity_coefficient / unit_coefficient) * unit)
+    } else {
+        Err(FunctionError::IncompatibleDimensions(
+            dimension_expression(quantity_dimension),
+            dimension_expression(unit_dimension),
+        ))
+    }
+}
@@ -9,9 +9,10 @@ use std::ops::{
 
 use num::{One, Zero};
 
-use crate::expression::{
-    Complex, Expression, Integer, Matrix, Rational, RationalRepresentation, Type, Vector,
-};
+use crate::expression::{Complex, Expression, Integer, Rational, RationalRepresentation, Type};
+
+#[cfg(feature = "matrices")]
+use crate::expression::{Matrix, Vector};
 
 impl Neg for Expression {
     type Output = Self;
@@ -167,12 +168,14 @@ impl TryFrom<Expression> for Complex {
     }
 }
 
+#[cfg(feature = "matrices")]
 impl From<Vector> for Expression {
     fn from(vector: Vector) -> Self {
         Expression::Vector(vector)
     }
 }
 
+#[cfg(feature = "matrices")]
 impl TryFrom<Expression> for Vector {
     type Error = Expression;
 
@@ -189,12 +192,14 @@ impl TryFrom<Expression> for Vector {
     }
 }
 
+#[cfg(feature = "matrices")]
 impl From<Matrix> for Expression {
     fn from(matrix: Matrix) -> Self {
         Expression::Matrix(matrix)
     }
 }
 
+#[cfg(feature = "matrices")]
 impl TryFrom<Expression> for Matrix {
     type Error = Expression;
 
@@ -225,11 +230,81 @@ impl TryFrom<Expression> for bool {
     }
 }
 
+impl From<String> for Expression {
+    fn from(string: String) -> Self {
+        Expression::String(string)
+    }
+}
+
+impl TryFrom<Expression> for String {
+    type Error = Expression;
+
+    fn try_from(expression: Expression) -> Result<Self, Self::Error> {
+        if let Expression::String(string) = expression {
+            Ok(string)
+        } else {
+            Err(expression)
+        }
+    }
+}
+
+impl Expression {
+    /// Returns the value of the expression if it is a boolean value.
+    pub fn as_bool(&self) -> Option<bool> {
+        bool::try_from(self.clone()).ok()
+    }
+
+    /// Returns the value of the expression if it is a number that is an integer,
+    /// regardless of whether it is represented as an integer, rational, or complex number.
+    pub fn as_integer(&self) -> Option<Integer> {
+        Integer::try_from(self.clone()).ok()
+    }
+
+    /// Returns the value of the expression if it is a number that is a rational number,
+    /// including integers and complex numbers with zero imaginary part.
+    pub fn as_rational(&self) -> Option<Rational> {
+        Rational::try_from(self.clone()).ok()
+    }
+
+    /// Returns the value of the expression if it is a number,
+    /// including integers and rational numbers.
+    pub fn as_complex(&self) -> Option<Complex> {
+        Complex::try_from(self.clone()).ok()
+    }
+
+    /// Returns the value of the expression if it is a vector,
+    /// or a matrix with a single column.
+    #[cfg(feature = "matrices")]
+    pub fn as_vector(&self) -> Option<Vector> {
+        Vector::try_from(self.clone()).ok()
+    }
+
+    /// Returns the value of the expression if it is a matrix,
+    /// or a vector (as a matrix with a single column).
+    #[cfg(feature = "matrices")]
+    pub fn as_matrix(&self) -> Option<Matrix> {
+        Matrix::try_from(self.clone()).ok()
+    }
+
+    /// Returns the value of the expression if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Expression::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
 /// Returns an expression representing the variable with the given identifier.
 pub fn var(identifier: impl Into<String>) -> Expression {
     Expression::Variable(identifier.into())
 }
 
+/// Returns an expression representing the given string.
+pub fn string(string: impl Into<String>) -> Expression {
+    Expression::String(string.into())
+}
+
 /// Returns an expression representing the value of the given function at the given arguments.
 pub fn fun(function: impl Into<Expression>, arguments: impl Into<Vec<Expression>>) -> Expression {
     Expression::FunctionValue(Box::new(function.into()), arguments.into())
@@ -298,6 +373,33 @@ pub fn comd(
     )
 }
 
+/// Returns an expression representing the quaternion with the given real part
+/// and `i`, `j`, and `k` components.
+pub fn quat(
+    a: impl Into<Expression>,
+    b: impl Into<Expression>,
+    c: impl Into<Expression>,
+    d: impl Into<Expression>,
+) -> Expression {
+    Expression::Quaternion(
+        Box::new(a.into()),
+        Box::new(b.into()),
+        Box::new(c.into()),
+        Box::new(d.into()),
+    )
+}
+
+/// Returns an expression representing the residue class of the first expression
+/// modulo the second.
+pub fn modulo(n: impl Into<Expression>, m: impl Into<Expression>) -> Expression {
+    Expression::Mod(Box::new(n.into()), Box::new(m.into()))
+}
+
+/// Returns an expression representing the closed interval between the given bounds.
+pub fn interval(lower: impl Into<Expression>, upper: impl Into<Expression>) -> Expression {
+    Expression::Interval(Box::new(lower.into()), Box::new(upper.into()))
+}
+
 /// Returns an expression representing the first expression raised to the power of the second.
 pub fn pow(base: impl Into<Expression>, exponent: impl Into<Expression>) -> Expression {
     Expression::Power(Box::new(base.into()), Box::new(exponent.into()))
@@ -342,3 +444,72 @@ pub fn and(a: impl Into<Expression>, b: impl Into<Expression>) -> Expression {
 pub fn or(a: impl Into<Expression>, b: impl Into<Expression>) -> Expression {
     Expression::Or(Box::new(a.into()), Box::new(b.into()))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        expression::{Complex, Expression, Integer, Rational},
+        helpers::*,
+    };
+
+    #[cfg(feature = "matrices")]
+    use crate::expression::{Matrix, Vector};
+
+    #[test]
+    fn accessors() {
+        let e = |string: &str| string.parse::<Expression>().unwrap();
+
+        assert_eq!(e("true").as_bool(), Some(true));
+        assert_eq!(e("1").as_bool(), None);
+
+        assert_eq!(int(3).as_integer(), Some(Integer::from(3)));
+        assert_eq!(rat(6, 2).as_integer(), Some(Integer::from(3)));
+        assert_eq!(com(3, 1, 0, 1).as_integer(), Some(Integer::from(3)));
+        assert_eq!(rat(1, 2).as_integer(), None);
+        assert_eq!(e("x").as_integer(), None);
+
+        assert_eq!(int(3).as_rational(), Some(Rational::from_integer(3.into())));
+        assert_eq!(
+            ratd(1, 2).as_rational(),
+            Some(Rational::new(1.into(), 2.into()))
+        );
+        assert_eq!(com(1, 1, 1, 1).as_rational(), None);
+
+        assert_eq!(
+            rat(1, 2).as_complex(),
+            Some(Complex::new(
+                Rational::new(1.into(), 2.into()),
+                Rational::from_integer(0.into())
+            )),
+        );
+        assert_eq!(e("true").as_complex(), None);
+
+        #[cfg(feature = "matrices")]
+        assert_eq!(
+            e("[1, 2]").as_vector(),
+            Some(Vector::from_vec(vec![int(1), int(2)]))
+        );
+        #[cfg(feature = "matrices")]
+        assert_eq!(e("[[1, 2]]").as_vector(), None);
+
+        #[cfg(feature = "matrices")]
+        assert_eq!(
+            e("[[1, 2], [3, 4]]").as_matrix(),
+            Some(Matrix::from_row_slice(
+                2,
+                2,
+                &[int(1), int(2), int(3), int(4)]
+            )),
+        );
+        #[cfg(feature = "matrices")]
+        assert_eq!(
+            e("[1, 2]").as_matrix(),
+            Some(Matrix::from_vec(2, 1, vec![int(1), int(2)]))
+        );
+        #[cfg(feature = "matrices")]
+        assert_eq!(e("1").as_matrix(), None);
+
+        assert_eq!(e("\"text\"").as_str(), Some("text"));
+        assert_eq!(e("text").as_str(), None);
+    }
+}
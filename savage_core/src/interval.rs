@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{One, Signed, Zero};
+
+use crate::expression::{Expression, Rational, RationalRepresentation, Type};
+
+/// Closed interval of real numbers with rational bounds.
+///
+/// Arithmetic on intervals is exact, so the result of an operation contains
+/// the results of the operation on all numbers in the operands, and nothing else
+/// (except for products and quotients involving the same interval more than once,
+/// which are computed as if the operands were independent).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) struct Interval {
+    lower: Rational,
+    upper: Rational,
+}
+
+impl Interval {
+    /// Returns the interval with the given bounds, or `None` if `lower > upper`.
+    pub(crate) fn new(lower: Rational, upper: Rational) -> Option<Self> {
+        if lower <= upper {
+            Some(Self { lower, upper })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the interval containing only the given number.
+    fn point(x: Rational) -> Self {
+        Self {
+            lower: x.clone(),
+            upper: x,
+        }
+    }
+
+    /// Returns the interval and the preferred representation of its bounds if the type
+    /// is an interval or a real number, which is treated as an interval containing
+    /// only that number.
+    pub(crate) fn from_type(typ: Type) -> Option<(Self, RationalRepresentation)> {
+        match typ {
+            Type::Interval(interval, representation) => Some((interval, representation)),
+            Type::Number(z, representation) if z.im.is_zero() => {
+                Some((Self::point(z.re), representation))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the lower bound of the interval.
+    pub(crate) fn lower(&self) -> &Rational {
+        &self.lower
+    }
+
+    /// Returns the upper bound of the interval.
+    pub(crate) fn upper(&self) -> &Rational {
+        &self.upper
+    }
+
+    /// Returns whether the interval contains the given number.
+    pub(crate) fn contains(&self, x: &Rational) -> bool {
+        self.lower <= *x && *x <= self.upper
+    }
+
+    /// Returns the interval containing the negations of the numbers in the interval.
+    pub(crate) fn negate(&self) -> Self {
+        Self {
+            lower: -&self.upper,
+            upper: -&self.lower,
+        }
+    }
+
+    /// Returns the smallest interval containing the given numbers.
+    fn hull(numbers: [Rational; 4]) -> Self {
+        let [a, b, c, d] = numbers;
+        let lower = a.clone().min(b.clone()).min(c.clone()).min(d.clone());
+        let upper = a.max(b).max(c).max(d);
+
+        Self { lower, upper }
+    }
+
+    /// Returns the interval containing the sums of the numbers in the intervals.
+    pub(crate) fn add(&self, other: &Self) -> Self {
+        Self {
+            lower: &self.lower + &other.lower,
+            upper: &self.upper + &other.upper,
+        }
+    }
+
+    /// Returns the interval containing the differences of the numbers in the intervals.
+    pub(crate) fn subtract(&self, other: &Self) -> Self {
+        self.add(&other.negate())
+    }
+
+    /// Returns the interval containing the products of the numbers in the intervals.
+    pub(crate) fn multiply(&self, other: &Self) -> Self {
+        Self::hull([
+            &self.lower * &other.lower,
+            &self.lower * &other.upper,
+            &self.upper * &other.lower,
+            &self.upper * &other.upper,
+        ])
+    }
+
+    /// Returns the interval containing the reciprocals of the numbers in the interval,
+    /// or `None` if the interval contains zero.
+    pub(crate) fn reciprocal(&self) -> Option<Self> {
+        if self.contains(&Rational::zero()) {
+            None
+        } else {
+            Some(Self {
+                lower: self.upper.recip(),
+                upper: self.lower.recip(),
+            })
+        }
+    }
+
+    /// Returns the interval containing the quotients of the numbers in the intervals,
+    /// or `None` if `other` contains zero.
+    pub(crate) fn divide(&self, other: &Self) -> Option<Self> {
+        Some(self.multiply(&other.reciprocal()?))
+    }
+
+    /// Returns the interval containing the `n`th powers of the numbers in the interval,
+    /// or `None` if `n` is negative and the interval contains zero.
+    pub(crate) fn power(&self, n: i32) -> Option<Self> {
+        if n < 0 {
+            return self.reciprocal()?.power(-n);
+        } else if n == 0 {
+            return Some(Self::point(Rational::one()));
+        }
+
+        let (a, b) = (self.lower.pow(n), self.upper.pow(n));
+
+        Some(if n % 2 == 1 || !self.lower.is_negative() {
+            Self { lower: a, upper: b }
+        } else if !self.upper.is_positive() {
+            Self { lower: b, upper: a }
+        } else {
+            Self {
+                lower: Rational::zero(),
+                upper: a.max(b),
+            }
+        })
+    }
+
+    /// Returns `Some(true)` if every number in the interval is less than every number
+    /// in `other`, `Some(false)` if no number in the interval is less than any number
+    /// in `other`, and `None` otherwise.
+    pub(crate) fn less_than(&self, other: &Self) -> Option<bool> {
+        if self.upper < other.lower {
+            Some(true)
+        } else if self.lower >= other.upper {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `Some(true)` if every number in the interval is less than or equal to
+    /// every number in `other`, `Some(false)` if no number in the interval is less than
+    /// or equal to any number in `other`, and `None` otherwise.
+    pub(crate) fn less_than_or_equal(&self, other: &Self) -> Option<bool> {
+        if self.upper <= other.lower {
+            Some(true)
+        } else if self.lower > other.upper {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the expression representing the interval,
+    /// with bounds in the given representation.
+    pub(crate) fn to_expression(&self, representation: RationalRepresentation) -> Expression {
+        Expression::Interval(
+            Box::new(Expression::Complex(
+                self.lower.clone().into(),
+                representation,
+            )),
+            Box::new(Expression::Complex(
+                self.upper.clone().into(),
+                representation,
+            )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::{Integer, Rational};
+    use crate::interval::Interval;
+
+    fn i(lower: i64, upper: i64) -> Interval {
+        Interval::new(Integer::from(lower).into(), Integer::from(upper).into()).unwrap()
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(i(1, 2).add(&i(-3, 5)), i(-2, 7));
+        assert_eq!(i(1, 2).subtract(&i(-3, 5)), i(-4, 5));
+        assert_eq!(i(-1, 2).multiply(&i(-3, 5)), i(-6, 10));
+        assert_eq!(i(-2, -1).multiply(&i(3, 5)), i(-10, -3));
+        assert_eq!(
+            i(1, 2).divide(&i(4, 8)),
+            Interval::new(
+                Rational::new(1.into(), 8.into()),
+                Rational::new(1.into(), 2.into())
+            ),
+        );
+        assert_eq!(i(1, 2).divide(&i(-1, 1)), None);
+        assert_eq!(i(-2, 3).power(2), Some(i(0, 9)));
+        assert_eq!(i(-3, -2).power(2), Some(i(4, 9)));
+        assert_eq!(i(-3, 2).power(3), Some(i(-27, 8)));
+        assert_eq!(i(-1, 1).power(-1), None);
+        assert_eq!(i(-1, 1).power(0), Some(i(1, 1)));
+        assert_eq!(
+            Interval::new(Integer::from(2).into(), Integer::from(1).into()),
+            None
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(i(1, 2).less_than(&i(3, 4)), Some(true));
+        assert_eq!(i(1, 3).less_than(&i(3, 4)), None);
+        assert_eq!(i(3, 4).less_than(&i(1, 3)), Some(false));
+        assert_eq!(i(1, 3).less_than_or_equal(&i(3, 4)), Some(true));
+        assert_eq!(i(1, 4).less_than_or_equal(&i(2, 3)), None);
+    }
+}
@@ -1,10 +1,172 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
-mod evaluate;
+// Allows the assertion macros, which refer to this crate by name, to be used in its own tests.
+extern crate self as savage_core;
+
+pub mod algebraic;
+mod approximate;
+pub mod bigfloat;
+mod calculus;
+mod compile;
+#[cfg(feature = "matrices")]
+pub mod csv;
+#[cfg(feature = "functions")]
+mod enclosure;
+pub mod engine;
+pub mod evaluate;
 pub mod expression;
+mod factor;
+mod full_form;
+#[cfg(feature = "functions")]
 pub mod functions;
 pub mod helpers;
+mod interval;
+mod normalize;
 pub mod parse;
+mod partial_fractions;
+pub mod polynomial;
 mod print;
+mod random;
+pub mod rewrite;
 mod simplify;
+mod solve;
+mod surd;
+#[cfg(feature = "matrices")]
+mod tensor;
+
+use std::{collections::HashMap, fmt};
+
+pub use savage_macros::{assert_evaluates_to, assert_evaluation_error};
+
+pub use crate::compile::CompileError;
+pub use crate::print::{
+    FormatOptions, Locale, MatrixLayout, Notation, RepeatingDecimals, MAX_REPEATING_DECIMAL_DIGITS,
+};
+
+use crate::{expression::Expression, parse::ParseError};
+
+/// Error that occurred while trying to parse or evaluate a string.
+#[allow(clippy::large_enum_variant)]
+#[derive(PartialEq, Clone, Debug)]
+pub enum SavageError {
+    /// The string is not a valid expression.
+    Parse(ParseError),
+    /// The expression cannot be evaluated.
+    Evaluate(evaluate::Error),
+}
+
+impl SavageError {
+    /// Returns a stable, machine-readable code identifying the kind of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SavageError::Parse(error) => error.code(),
+            SavageError::Evaluate(error) => error.code(),
+        }
+    }
+
+    /// Returns a suggestion for fixing the error, if one is available.
+    pub fn suggestion(&self) -> Option<String> {
+        match self {
+            SavageError::Parse(error) => error.suggestion(),
+            SavageError::Evaluate(error) => error.suggestion(),
+        }
+    }
+}
+
+impl From<ParseError> for SavageError {
+    fn from(error: ParseError) -> Self {
+        SavageError::Parse(error)
+    }
+}
+
+impl From<evaluate::Error> for SavageError {
+    fn from(error: evaluate::Error) -> Self {
+        SavageError::Evaluate(error)
+    }
+}
+
+impl fmt::Display for SavageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SavageError::Parse(error) => write!(f, "{}", error),
+            SavageError::Evaluate(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SavageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SavageError::Parse(error) => Some(error),
+            SavageError::Evaluate(error) => Some(error),
+        }
+    }
+}
+
+/// Returns the result of parsing the given string as an expression
+/// and evaluating it, or an error if either step fails.
+#[allow(clippy::result_large_err)]
+pub fn eval(string: &str) -> Result<Expression, SavageError> {
+    Ok(string.parse::<Expression>()?.evaluate(HashMap::new())?)
+}
+
+/// Implementation details of the assertion macros. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use std::collections::HashMap;
+
+    use crate::{evaluate::Error, expression::Expression};
+
+    /// Returns the result of parsing the given string as an expression and evaluating it.
+    /// Panics if the string is not a valid expression.
+    #[allow(clippy::result_large_err)]
+    #[track_caller]
+    pub fn evaluate_str(string: &str) -> Result<Expression, Error> {
+        match string.parse::<Expression>() {
+            Ok(expression) => expression.evaluate(HashMap::new()),
+            Err(error) => panic!("`{}` is not a valid expression: {}", string, error),
+        }
+    }
+}
+
+#[cfg(test)]
+// Tests that use built-in functions are skipped if those are disabled.
+#[cfg_attr(not(feature = "functions"), allow(unused_imports, dead_code))]
+mod tests {
+    use crate::{eval, helpers::*, SavageError};
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn eval_string() {
+        assert_eq!(eval("1/3 + 1/6"), Ok(rat(1, 2)));
+        assert_eq!(
+            eval("det([[a, 2], [3, a]])"),
+            Ok(pow(var("a"), int(2)) - int(6))
+        );
+
+        assert!(matches!(eval("1 +"), Err(SavageError::Parse(_))));
+        assert!(matches!(eval("1 / 0"), Err(SavageError::Evaluate(_))));
+
+        let error = eval("1 / 0").unwrap_err();
+
+        assert_eq!(error.code(), "E0204");
+        assert_eq!(error.to_string(), "division by zero in 1 / 0 (0 is zero)");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "evaluating `1/2 + 0.5` returned an unexpected result\n  expected: 2\n  actual:   1"
+    )]
+    fn assert_evaluates_to_failure() {
+        crate::assert_evaluates_to!("1/2 + 0.5", "2");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "evaluating `1 / 0` failed with an unexpected error\n  expected: InvalidOperand { .. }"
+    )]
+    fn assert_evaluation_error_failure() {
+        crate::assert_evaluation_error!("1 / 0", InvalidOperand);
+    }
+}
@@ -0,0 +1,571 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use num::{Signed, ToPrimitive, Zero};
+
+use crate::{
+    expression::{Expression, Rational, TraversalOrder},
+    helpers::*,
+};
+
+/// Maximum number of terms produced when expanding a product or power,
+/// beyond which it is kept unexpanded.
+const MAX_TERMS: usize = 10_000;
+
+/// Maximum exponent of a power of a sum that is expanded.
+const MAX_EXPONENT: u32 = 64;
+
+/// Product of a number and powers of distinct bases.
+#[derive(Clone, Debug)]
+struct Term {
+    coefficient: Expression,
+    factors: Vec<(Expression, Expression)>,
+}
+
+/// Returns whether the expression is a number literal.
+fn is_number(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Integer(_) | Expression::Rational(_, _) | Expression::Complex(_, _),
+    )
+}
+
+/// Returns the value of the expression if it is a real number literal.
+fn real_value(expression: &Expression) -> Option<Rational> {
+    match expression {
+        Expression::Integer(n) => Some(Rational::from(n.clone())),
+        Expression::Rational(x, _) => Some(x.clone()),
+        _ => None,
+    }
+}
+
+/// Returns whether the expression is a negative real number literal.
+fn is_negative(expression: &Expression) -> bool {
+    matches!(real_value(expression), Some(x) if x.is_negative())
+}
+
+/// Returns whether the expression is a zero number literal.
+fn is_zero(expression: &Expression) -> bool {
+    matches!(real_value(expression), Some(x) if x.is_zero())
+}
+
+/// Returns whether the expression contains vectors, matrices, tensors, or quaternions,
+/// whose products are not commutative in general.
+fn is_non_commutative(expression: &Expression) -> bool {
+    use crate::expression::Expression::*;
+
+    expression
+        .iter_subexpressions(TraversalOrder::PreOrder)
+        .any(|x| x.is_array() || matches!(x, Quaternion(_, _, _, _)))
+}
+
+/// Returns the result of evaluating the arithmetic operation on numbers,
+/// or the operation itself if it cannot be evaluated (e.g. for division by zero).
+fn arithmetic(expression: Expression) -> Expression {
+    expression.evaluate(HashMap::new()).unwrap_or(expression)
+}
+
+/// Returns the sum of the exponents, which are normalized expressions.
+fn add_exponents(a: Expression, b: Expression) -> Expression {
+    if is_number(&a) && is_number(&b) {
+        arithmetic(a + b)
+    } else {
+        (a + b).normalize()
+    }
+}
+
+/// Returns the rank of the kind of the expression in the canonical order.
+fn rank(expression: &Expression) -> u8 {
+    use crate::expression::Expression::*;
+
+    match expression {
+        Integer(_) | Rational(_, _) | Complex(_, _) => 0,
+        Variable(_) => 1,
+        Function(_, _) => 2,
+        FunctionValue(_, _) => 3,
+        Power(_, _) => 4,
+        Product(_, _) => 5,
+        Quotient(_, _) => 6,
+        Negation(_) => 7,
+        Sum(_, _) => 8,
+        Difference(_, _) => 9,
+        _ => 10,
+    }
+}
+
+/// Returns the ordering of the expressions in the canonical order, in which numbers
+/// come first (ordered by value if they are real), followed by variables (ordered by
+/// identifier), function values (ordered by function name and then by arguments),
+/// and compound expressions (ordered by their sub-expressions).
+fn compare(a: &Expression, b: &Expression) -> Ordering {
+    use crate::expression::Expression::*;
+
+    rank(a).cmp(&rank(b)).then_with(|| {
+        match (a, b) {
+            (Variable(x), Variable(y)) => return x.cmp(y),
+            (FunctionValue(f, x), FunctionValue(g, y)) => {
+                return f
+                    .to_string()
+                    .cmp(&g.to_string())
+                    .then_with(|| compare_all(x.iter(), y.iter()))
+            }
+            _ => {}
+        }
+
+        match (real_value(a), real_value(b)) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => compare_all(a.children().into_iter(), b.children().into_iter()),
+        }
+        .then_with(|| a.to_string().cmp(&b.to_string()))
+    })
+}
+
+/// Returns the lexicographic ordering of the sequences of expressions in the canonical order.
+fn compare_all<'a>(
+    a: impl Iterator<Item = &'a Expression>,
+    b: impl Iterator<Item = &'a Expression>,
+) -> Ordering {
+    let (a, b) = (a.collect::<Vec<_>>(), b.collect::<Vec<_>>());
+
+    a.iter()
+        .zip(&b)
+        .map(|(x, y)| compare(x, y))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+/// Returns the ordering of the terms in a normalized sum, which orders them by their
+/// factors in the canonical order, with greater exponents first for equal bases,
+/// so that terms with more factors come first and the constant term comes last.
+fn compare_terms(a: &Term, b: &Term) -> Ordering {
+    for ((x, m), (y, n)) in a.factors.iter().zip(&b.factors) {
+        let ordering = compare(x, y).then_with(|| match (real_value(m), real_value(n)) {
+            (Some(m), Some(n)) => n.cmp(&m),
+            _ => compare(m, n),
+        });
+
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+
+    b.factors.len().cmp(&a.factors.len())
+}
+
+impl Term {
+    /// Returns the term representing the (normalized) expression.
+    fn new(expression: &Expression) -> Self {
+        use crate::expression::Expression::*;
+
+        let opaque = || Self {
+            coefficient: int(1),
+            factors: vec![(expression.clone(), int(1))],
+        };
+
+        match expression {
+            _ if is_number(expression) => Self {
+                coefficient: expression.clone(),
+                factors: Vec::new(),
+            },
+            Negation(a) => Self::new(a).negate(),
+            // Factors of products of vectors, matrices, tensors,
+            // and quaternions must not be reordered.
+            Product(_, _) if is_non_commutative(expression) => opaque(),
+            Product(a, b) => Self::new(a).multiply(Self::new(b)),
+            Quotient(a, b) => {
+                let b = Self::new(b);
+
+                if is_zero(&b.coefficient) {
+                    opaque()
+                } else {
+                    Self::new(a).multiply(b.power(&int(-1)))
+                }
+            }
+            Power(a, b) => match &**b {
+                Integer(_) => {
+                    let a = Self::new(a);
+
+                    if is_zero(&a.coefficient) && is_negative(b) {
+                        opaque()
+                    } else {
+                        a.power(b)
+                    }
+                }
+                _ => Self {
+                    coefficient: int(1),
+                    factors: vec![(*a.clone(), *b.clone())],
+                },
+            },
+            _ => opaque(),
+        }
+    }
+
+    /// Returns the negation of the term.
+    fn negate(mut self) -> Self {
+        self.coefficient = arithmetic(-self.coefficient);
+        self
+    }
+
+    /// Returns the product of the terms, with the exponents of equal bases added.
+    fn multiply(mut self, other: Self) -> Self {
+        self.coefficient = arithmetic(self.coefficient * other.coefficient);
+
+        for (base, exponent) in other.factors {
+            match self.factors.iter_mut().find(|(b, _)| *b == base) {
+                Some((_, e)) => *e = add_exponents(e.clone(), exponent),
+                None => self.factors.push((base, exponent)),
+            }
+        }
+
+        let mut factors = Vec::new();
+
+        for (base, exponent) in self.factors {
+            if is_zero(&exponent) {
+                continue;
+            }
+
+            // Integer powers of numbers are merged into the coefficient.
+            if is_number(&base) && matches!(exponent, Expression::Integer(_)) {
+                let power = arithmetic(pow(base.clone(), exponent.clone()));
+
+                if is_number(&power) {
+                    self.coefficient = arithmetic(self.coefficient * power);
+                    continue;
+                }
+            }
+
+            factors.push((base, exponent));
+        }
+
+        factors.sort_by(|(a, _), (b, _)| compare(a, b));
+        self.factors = factors;
+
+        self
+    }
+
+    /// Returns the term raised to the power of the integer `n`.
+    fn power(self, n: &Expression) -> Self {
+        Self {
+            coefficient: arithmetic(pow(self.coefficient, n.clone())),
+            factors: Vec::new(),
+        }
+        .multiply(Self {
+            coefficient: int(1),
+            factors: self
+                .factors
+                .into_iter()
+                .map(|(base, exponent)| {
+                    let exponent = if is_number(&exponent) {
+                        arithmetic(exponent * n.clone())
+                    } else {
+                        (exponent * n.clone()).normalize()
+                    };
+
+                    (base, exponent)
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the expression representing the term, with factors that have
+    /// negative exponents in the denominator of a quotient.
+    fn to_expression(&self) -> Expression {
+        if is_zero(&self.coefficient) {
+            return int(0);
+        }
+
+        let power = |base: &Expression, exponent: Expression| {
+            if exponent == int(1) {
+                base.clone()
+            } else {
+                pow(base.clone(), exponent)
+            }
+        };
+
+        let mut numerator = None;
+        let mut denominator = None;
+
+        for (base, exponent) in &self.factors {
+            let (product, factor) = if is_negative(exponent) {
+                (&mut denominator, power(base, arithmetic(-exponent.clone())))
+            } else {
+                (&mut numerator, power(base, exponent.clone()))
+            };
+
+            *product = Some(match product.take() {
+                Some(product) => product * factor,
+                None => factor,
+            });
+        }
+
+        let numerator = match numerator {
+            Some(numerator) if self.coefficient == int(1) => numerator,
+            Some(numerator) if self.coefficient == int(-1) => -numerator,
+            Some(numerator) => self.coefficient.clone() * numerator,
+            None => self.coefficient.clone(),
+        };
+
+        match denominator {
+            Some(denominator) => numerator / denominator,
+            None => numerator,
+        }
+    }
+}
+
+/// Adds the terms of the (normalized) expression to `terms`, negated if `negate` is `true`.
+fn collect_terms(expression: &Expression, negate: bool, terms: &mut Vec<Term>) {
+    use crate::expression::Expression::*;
+
+    match expression {
+        Sum(a, b) => {
+            collect_terms(a, negate, terms);
+            collect_terms(b, negate, terms);
+        }
+        Difference(a, b) => {
+            collect_terms(a, negate, terms);
+            collect_terms(b, !negate, terms);
+        }
+        Negation(a) => collect_terms(a, !negate, terms),
+        _ => {
+            let term = Term::new(expression);
+            terms.push(if negate { term.negate() } else { term });
+        }
+    }
+}
+
+/// Returns the terms with terms that have equal factors merged,
+/// without zero terms, and in the order of a normalized sum.
+fn merge_terms(terms: Vec<Term>) -> Vec<Term> {
+    let mut merged: Vec<Term> = Vec::new();
+
+    for term in terms {
+        match merged.iter_mut().find(|t| t.factors == term.factors) {
+            Some(t) => t.coefficient = arithmetic(t.coefficient.clone() + term.coefficient),
+            None => merged.push(term),
+        }
+    }
+
+    merged.retain(|term| !is_zero(&term.coefficient));
+    merged.sort_by(compare_terms);
+
+    merged
+}
+
+/// Returns the expression representing the sum of the terms, with terms that have
+/// equal factors merged, and negative terms (except the first) subtracted.
+fn sum_expression(terms: Vec<Term>) -> Expression {
+    let mut result: Option<Expression> = None;
+
+    for term in merge_terms(terms) {
+        result = Some(match result {
+            Some(result) if is_negative(&term.coefficient) => {
+                result - term.negate().to_expression()
+            }
+            Some(result) => result + term.to_expression(),
+            None => term.to_expression(),
+        });
+    }
+
+    result.unwrap_or_else(|| int(0))
+}
+
+/// Returns the products of each term of `a` with each term of `b`,
+/// or `None` if there would be too many of them.
+fn multiply_terms(a: &[Term], b: &[Term]) -> Option<Vec<Term>> {
+    if a.len() * b.len() > MAX_TERMS {
+        return None;
+    }
+
+    Some(
+        a.iter()
+            .flat_map(|x| b.iter().map(move |y| x.clone().multiply(y.clone())))
+            .collect(),
+    )
+}
+
+/// Returns the terms of the sum obtained by expanding the expression,
+/// whose sub-expressions have already been expanded.
+fn expanded_terms(expression: &Expression) -> Vec<Term> {
+    use crate::expression::Expression::*;
+
+    let terms = || match expression {
+        Sum(a, b) => Some(
+            expanded_terms(a)
+                .into_iter()
+                .chain(expanded_terms(b))
+                .collect(),
+        ),
+        Difference(a, b) => Some(
+            expanded_terms(a)
+                .into_iter()
+                .chain(expanded_terms(b).into_iter().map(Term::negate))
+                .collect(),
+        ),
+        Negation(a) => Some(expanded_terms(a).into_iter().map(Term::negate).collect()),
+        Product(a, b) if !is_non_commutative(expression) => {
+            multiply_terms(&expanded_terms(a), &expanded_terms(b))
+        }
+        Quotient(a, b) => {
+            let b = Term::new(b);
+
+            if is_zero(&b.coefficient) {
+                None
+            } else {
+                multiply_terms(&expanded_terms(a), &[b.power(&int(-1))])
+            }
+        }
+        Power(a, n) => match (&**n, expanded_terms(a)) {
+            (Integer(n), terms) if terms.len() > 1 => {
+                let n = n.to_u32().filter(|n| *n <= MAX_EXPONENT)?;
+                let mut result = vec![Term::new(&int(1))];
+
+                for _ in 0..n {
+                    // Like terms are merged after each step to keep the number of terms small.
+                    result = merge_terms(multiply_terms(&result, &terms)?);
+                }
+
+                Some(result)
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+
+    terms().unwrap_or_else(|| vec![Term::new(expression)])
+}
+
+impl Expression {
+    /// Returns the normal form of the expression, in which nested sums and products
+    /// are flattened, their operands are sorted in a canonical order, numbers are
+    /// combined, terms that differ only in their numerical coefficients are merged,
+    /// and powers of equal bases are merged by adding their exponents. Expressions
+    /// that are equal as polynomials or rational functions with numerical coefficients
+    /// have equal normal forms (e.g. `x + 1 + x` and `1 + 2 * x`), but products and
+    /// powers of sums are not expanded.
+    ///
+    /// Variables are assumed to be scalars, and products of vectors, matrices, tensors,
+    /// and quaternions are kept in their original order. Note that merging powers can
+    /// remove divisions by zero, e.g. `x / x` is normalized to `1`.
+    pub fn normalize(&self) -> Self {
+        use crate::expression::Expression::*;
+
+        let expression = self.map_children(Self::normalize);
+
+        match expression {
+            Sum(_, _) | Difference(_, _) | Negation(_) => {
+                let mut terms = Vec::new();
+                collect_terms(&expression, false, &mut terms);
+                sum_expression(terms)
+            }
+            Product(_, _) | Quotient(_, _) | Power(_, _) => Term::new(&expression).to_expression(),
+            _ => expression,
+        }
+    }
+
+    /// Returns the expansion of the expression, in which products are distributed
+    /// over sums and non-negative integer powers of sums are multiplied out,
+    /// in normal form (see `normalize`). Numerators of quotients are distributed
+    /// over their denominators, which are expanded but otherwise kept as they are.
+    /// Products and powers that would have too many terms are not expanded.
+    pub fn expand(&self) -> Self {
+        use crate::expression::Expression::*;
+
+        let expression = self.map_children(Self::expand);
+
+        match expression {
+            Sum(_, _)
+            | Difference(_, _)
+            | Negation(_)
+            | Product(_, _)
+            | Quotient(_, _)
+            | Power(_, _) => sum_expression(expanded_terms(&expression)),
+            _ => expression,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+
+    #[track_caller]
+    fn n(expression: &str, result: &str) {
+        let normalized = expression.parse::<Expression>().unwrap().normalize();
+
+        assert_eq!(normalized.to_string(), result);
+
+        // Normal forms are already normalized.
+        assert_eq!(normalized.normalize(), normalized);
+    }
+
+    #[test]
+    fn normalize() {
+        n("x + 1 + x", "2 * x + 1");
+        n("1 + 2 * x", "2 * x + 1");
+        n("b + a + c", "a + b + c");
+        n("x - x", "0");
+        n("2 * 3 + 4", "10");
+        n("1 - x", "-x + 1");
+        n("-(x - y)", "-x + y");
+        n("x * y - y * x", "0");
+        n("x ^ 2 + x + x ^ 3 + 1", "x ^ 3 + x ^ 2 + x + 1");
+        n("x * y + x", "x * y + x");
+        n("x * 2 * x * 3", "6 * x ^ 2");
+        n("x / 2", "1/2 * x");
+        n("x / x", "1");
+        n("x ^ 2 / x", "x");
+        n("2 * x / (4 * y)", "1/2 * x / y");
+        n("-x / y", "-x / y");
+        n("(2 * x) ^ 2", "4 * x ^ 2");
+        n("(x ^ 2) ^ (1/2)", "(x ^ 2) ^ (1/2)");
+        n("x ^ n * x", "x ^ (n + 1)");
+        n("sqrt(x) * sqrt(x)", "sqrt(x) ^ 2");
+        n("f(b, a) + f(a, b)", "f(a, b) + f(b, a)");
+        n("sin(x) + (x + 1) * 2", "sin(x) + 2 * (x + 1)");
+        #[cfg(feature = "matrices")]
+        n("[x, x + x]", "[x, 2 * x]");
+        #[cfg(feature = "matrices")]
+        n(
+            "[[1, 2], [3, 4]] * [[a, b], [c, d]]",
+            "[[1, 2], [3, 4]] * [[a, b], [c, d]]",
+        );
+        n("x == 1 + x", "x == x + 1");
+    }
+    #[track_caller]
+    fn e(expression: &str, result: &str) {
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .expand()
+                .to_string(),
+            result,
+        );
+    }
+
+    #[test]
+    fn expand() {
+        e("(x + 1) ^ 2", "x ^ 2 + 2 * x + 1");
+        e("(x + 1) * (x - 1)", "x ^ 2 - 1");
+        e("(2 * x - 1) ^ 3", "8 * x ^ 3 - 12 * x ^ 2 + 6 * x - 1");
+        e("(x + y) ^ 2 - (x - y) ^ 2", "4 * x * y");
+        e("x * (y + z) + y * (x - z)", "2 * x * y + x * z - y * z");
+        e("-(x + 1) * 2", "-2 * x - 2");
+        e("(x + 1) / x", "1 / x + 1");
+        e("x / (x + 1) ^ 2", "x / (x ^ 2 + 2 * x + 1)");
+        e(
+            "f((x + 1) ^ 2) * (y + 1)",
+            "y * f(x ^ 2 + 2 * x + 1) + f(x ^ 2 + 2 * x + 1)",
+        );
+        e("(x + 1) ^ (1/2) * x", "x * (x + 1) ^ (1/2)");
+        e("(x + 1) ^ 1000", "(x + 1) ^ 1000");
+        #[cfg(feature = "matrices")]
+        e(
+            "[[1, 2], [3, 4]] * ([[a]] + [[b]])",
+            "[[1, 2], [3, 4]] * ([[a]] + [[b]])",
+        );
+        e("2 * 3", "6");
+    }
+}
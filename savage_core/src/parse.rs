@@ -1,40 +1,193 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
-use std::{ops::Range, str::FromStr};
+use std::{fmt, ops::Range, str::FromStr};
 
 use chumsky::prelude::*;
 
 use crate::{
-    expression::{Expression, Integer, Matrix, Vector},
+    expression::{Expression, Integer},
     helpers::*,
+    print::Locale,
 };
 
+#[cfg(feature = "matrices")]
+use crate::expression::{Matrix, Tensor, Vector};
+
 /// Error that occurred while trying to parse a character stream into an expression.
 pub type Error = chumsky::error::Simple<char, Range<usize>>;
 
 /// Reason why a parse error occurred.
 pub type ErrorReason = chumsky::error::SimpleReason<char, Range<usize>>;
 
+/// Returns a stable, machine-readable code identifying the kind of the given parse error.
+pub fn error_code(error: &Error) -> &'static str {
+    match error.reason() {
+        ErrorReason::Unexpected => {
+            if error.found().is_some() {
+                "E0101"
+            } else {
+                "E0102"
+            }
+        }
+        ErrorReason::Unclosed { .. } => "E0103",
+        ErrorReason::Custom(_) => "E0104",
+    }
+}
+
+/// Returns a suggestion for fixing the given parse error in the given input,
+/// if one is available.
+pub fn error_suggestion(error: &Error, input: &str) -> Option<String> {
+    let expected = |c| error.expected().any(|expected| *expected == Some(c));
+
+    // The character preceding the error, which for errors in the middle
+    // of a multi-character operator is the first character of the operator.
+    let previous = input
+        .get(..error.span().start)
+        .and_then(|before| before.trim_end().chars().last());
+
+    match (previous, error.found()) {
+        (Some('='), _) if expected('=') => Some("did you mean `==` instead of `=`?".to_owned()),
+        (Some('&'), _) if expected('&') => Some("did you mean `&&` instead of `&`?".to_owned()),
+        (Some('|'), _) if expected('|') => Some("did you mean `||` instead of `|`?".to_owned()),
+        (_, None) if expected(')') => Some("did you forget a closing `)`?".to_owned()),
+        (_, None) if expected(']') => Some("did you forget a closing `]`?".to_owned()),
+        _ => match error.reason() {
+            ErrorReason::Unclosed { delimiter, .. } => {
+                Some(format!("did you forget to close the `{}`?", delimiter))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Errors that occurred while trying to parse a string into an expression.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ParseError {
+    /// Individual errors, in the order in which they were found.
+    pub errors: Vec<Error>,
+    /// The string that was parsed.
+    pub input: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+
+            if let ErrorReason::Custom(message) = error.reason() {
+                write!(f, "{}", message)?;
+            } else {
+                match error.found() {
+                    Some(found) => write!(f, "unexpected character '{}'", found)?,
+                    None => write!(f, "unexpected end of input")?,
+                }
+
+                write!(f, " at position {}", error.span().start)?;
+
+                let mut expected = error
+                    .expected()
+                    .map(|expected| match expected {
+                        Some(expected) => format!("'{}'", expected),
+                        None => "end of input".to_owned(),
+                    })
+                    .collect::<Vec<_>>();
+
+                // The set of expected tokens is unordered.
+                expected.sort();
+
+                if !expected.is_empty() {
+                    write!(f, ", expected {}", expected.join(", "))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ParseError {
+    /// Returns a stable, machine-readable code identifying the kind of the first error.
+    pub fn code(&self) -> &'static str {
+        self.errors.first().map_or("E0100", error_code)
+    }
+
+    /// Returns a suggestion for fixing the first error, if one is available.
+    pub fn suggestion(&self) -> Option<String> {
+        self.errors
+            .first()
+            .and_then(|error| error_suggestion(error, &self.input))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 /// Returns a parser that produces expressions from character streams.
 ///
 /// The purpose of this function is to be a building block for parsers that parse
 /// expressions as parts of a more complex input language. If you simply want
 /// to turn strings into expressions, use `"a + b".parse::<Expression>()`.
-#[allow(clippy::let_and_return)]
 pub fn parser() -> impl Parser<char, Expression, Error = Error> {
-    recursive(|expression| {
+    parser_with_locale(Locale::default())
+}
+
+/// Returns a parser that produces expressions from character streams,
+/// reading numbers written with the decimal separator and digit grouping
+/// of the given locale (e.g. `1.234,56` with a decimal comma).
+///
+/// If the decimal separator is a comma, it is only recognized between digits,
+/// so arguments and vector components must be separated by a comma followed by whitespace.
+#[allow(clippy::let_and_return)]
+pub fn parser_with_locale(locale: Locale) -> impl Parser<char, Expression, Error = Error> {
+    recursive(move |expression| {
         let identifier = text::ident()
             .map(|identifier: String| match identifier.as_str() {
                 "true" => Expression::Boolean(true),
                 "false" => Expression::Boolean(false),
                 _ => var(identifier),
             })
+            .or(just('π').to(var("pi")))
             .labelled("identifier")
             .boxed();
 
+        let digit_groups = filter(move |c: &char| Some(*c) == locale.digit_group_separator)
+            .ignore_then(
+                filter(char::is_ascii_digit)
+                    .repeated()
+                    .exactly(3)
+                    .collect::<String>(),
+            )
+            .repeated();
+
+        // A percent sign directly following a number denotes a percentage rather than
+        // the remainder operator, unless it is followed by something that can start
+        // an operand. So `15%` and `(1 + 15%) * 2` contain percentages, while `7%3`,
+        // `7%x`, and `10%-3` are remainders.
+        let percent_sign = just('%').then_ignore(
+            text::whitespace()
+                .then(
+                    filter(|c: &char| !(c.is_alphanumeric() || "_([\"!¬√-−".contains(*c)))
+                        .ignored()
+                        .or(end()),
+                )
+                .rewind(),
+        );
+
+        // An exclamation mark directly following an operand denotes the factorial,
+        // unless it is the start of the `!=` operator.
+        let factorial_sign =
+            just('!').then_ignore(filter(|c: &char| *c != '=').ignored().or(end()).rewind());
+
         let number = text::int(10)
-            .chain(just('.').ignore_then(text::digits(10)).or_not())
+            .then(digit_groups)
+            .map(|(integer, groups)| format!("{}{}", integer, groups.concat()))
+            .chain(
+                just(locale.decimal_separator)
+                    .ignore_then(text::digits(10))
+                    .or_not(),
+            )
             .map(|parts: Vec<String>| match parts.as_slice() {
                 [integer] => int(integer.parse::<Integer>().unwrap()),
                 [integer_part, fractional_part] => {
@@ -47,45 +200,49 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
                 }
                 _ => unreachable!(),
             })
+            .then(percent_sign.or_not())
+            .map(|(number, percent_sign)| match percent_sign {
+                Some(_) => number / int(100),
+                None => number,
+            })
             .labelled("number")
             .boxed();
 
+        let escape_sequence = just('\\').ignore_then(
+            just('\\')
+                .or(just('"'))
+                .or(just('n').to('\n'))
+                .or(just('t').to('\t')),
+        );
+
+        let string = filter(|c: &char| *c != '"' && *c != '\\')
+            .or(escape_sequence)
+            .repeated()
+            .collect::<String>()
+            .delimited_by(just('"'), just('"'))
+            .map(Expression::String)
+            .labelled("string")
+            .boxed();
+
         let vector_or_matrix = expression
             .clone()
             .separated_by(just(','))
             .padded()
             .delimited_by(just('['), just(']'))
-            .map(|elements| {
-                if let Some(Expression::Vector(v)) = elements.first() {
-                    // If all elements of the vector are themselves vectors, and have the same size,
-                    // they are interpreted as the rows of a rectangular matrix.
-                    let row_size = v.len();
-                    let mut rows = Vec::new();
-
-                    for element in &elements {
-                        match element {
-                            Expression::Vector(v) if v.len() == row_size => {
-                                rows.push(v.transpose());
-                            }
-                            _ => return Expression::Vector(Vector::from_vec(elements)),
-                        }
-                    }
-
-                    Expression::Matrix(Matrix::from_rows(&rows))
-                } else {
-                    Expression::Vector(Vector::from_vec(elements))
-                }
-            })
+            .try_map(vector_or_matrix)
             .labelled("vector_or_matrix")
             .boxed();
 
         let atomic_expression = identifier
             .or(number)
+            .or(string)
             .or(vector_or_matrix)
             .or(expression.clone().delimited_by(just('('), just(')')))
             .padded()
             .boxed();
 
+        // Any atomic expression can be followed by any number of argument lists
+        // and index lists, e.g. `f(x)(y)`, `v[0][1]`, or `reshape(v, [2, 2])[0]`.
         let function_or_element = atomic_expression
             .then(
                 expression
@@ -98,32 +255,52 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
                         .clone()
                         .separated_by(just(','))
                         .at_least(1)
-                        .at_most(2)
                         .delimited_by(just('['), just(']'))
-                        .map(|indices| (None, Some(indices))))
-                    .or_not(),
-            )
-            .map(
-                |(expression, arguments_or_indices)| match arguments_or_indices {
-                    Some((Some(arguments), None)) => fun(expression, arguments),
-                    Some((None, Some(indices))) => {
-                        if indices.len() == 1 {
-                            Expression::VectorElement(
-                                Box::new(expression),
-                                Box::new(indices[0].clone()),
-                            )
-                        } else {
-                            Expression::MatrixElement(
-                                Box::new(expression),
-                                Box::new(indices[0].clone()),
-                                Box::new(indices[1].clone()),
-                            )
-                        }
-                    }
-                    None => expression,
-                    _ => unreachable!(),
-                },
+                        .try_map(|indices, span| {
+                            if cfg!(feature = "matrices") {
+                                Ok((None, Some(indices)))
+                            } else {
+                                Err(Error::custom(
+                                    span,
+                                    "indexing is not supported without the `matrices` feature",
+                                ))
+                            }
+                        }))
+                    .repeated(),
             )
+            .map(|(expression, postfixes)| {
+                postfixes
+                    .into_iter()
+                    .fold(
+                        expression,
+                        |expression, arguments_or_indices| match arguments_or_indices {
+                            (Some(arguments), None) => fun(expression, arguments),
+                            #[cfg(feature = "matrices")]
+                            (None, Some(indices)) => {
+                                if indices.len() == 1 {
+                                    Expression::VectorElement(
+                                        Box::new(expression),
+                                        Box::new(indices[0].clone()),
+                                    )
+                                } else if indices.len() == 2 {
+                                    Expression::MatrixElement(
+                                        Box::new(expression),
+                                        Box::new(indices[0].clone()),
+                                        Box::new(indices[1].clone()),
+                                    )
+                                } else {
+                                    Expression::TensorElement(Box::new(expression), indices)
+                                }
+                            }
+                            _ => unreachable!(),
+                        },
+                    )
+            })
+            .then(factorial_sign.padded().or_not())
+            .map(|(expression, factorial_sign)| match factorial_sign {
+                Some(_) => fun(var("factorial"), [expression]),
+                None => expression,
+            })
             .padded()
             .boxed();
 
@@ -141,9 +318,16 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
             .boxed();
 
         let negation = just('-')
+            .or(just('−'))
             .ignore_then(power.clone())
             .map(|a| -a)
-            .or(just('!').ignore_then(power.clone()).map(|a| !a))
+            .or(just('!')
+                .or(just('¬'))
+                .ignore_then(power.clone())
+                .map(|a| !a))
+            .or(just('√')
+                .ignore_then(power.clone())
+                .map(|a| pow(a, rat(1, 2))))
             .labelled("negation")
             .or(power)
             .padded()
@@ -153,7 +337,10 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
             .clone()
             .then(
                 just('*')
+                    .or(just('·').to('*'))
+                    .or(just('×').to('*'))
                     .or(just('/'))
+                    .or(just('÷').to('/'))
                     .or(just('%'))
                     .then(negation)
                     .repeated(),
@@ -172,6 +359,7 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
             .then(
                 just('+')
                     .or(just('-'))
+                    .or(just('−').to('-'))
                     .then(product_or_quotient_or_remainder)
                     .repeated(),
             )
@@ -183,7 +371,19 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
             .labelled("sum_or_difference")
             .boxed();
 
-        let comparison = sum_or_difference
+        let modulo = sum_or_difference
+            .clone()
+            .then(
+                text::keyword("mod")
+                    .padded()
+                    .ignore_then(sum_or_difference)
+                    .repeated(),
+            )
+            .foldl(modulo)
+            .labelled("modulo")
+            .boxed();
+
+        let comparison = modulo
             .clone()
             .then(
                 just('=')
@@ -193,8 +393,11 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
                     .or(just('<').to(vec!['<']))
                     .or(just('>').chain(just('=')))
                     .or(just('>').to(vec!['>']))
+                    .or(just('≠').to(vec!['!', '=']))
+                    .or(just('≤').to(vec!['<', '=']))
+                    .or(just('≥').to(vec!['>', '=']))
                     .collect::<String>()
-                    .then(sum_or_difference)
+                    .then(modulo)
                     .repeated(),
             )
             .foldl(|a, (operator, b)| match operator.as_str() {
@@ -214,6 +417,7 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
             .then(
                 just('&')
                     .ignore_then(just('&'))
+                    .or(just('∧'))
                     .ignore_then(comparison)
                     .repeated(),
             )
@@ -226,6 +430,7 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
             .then(
                 just('|')
                     .ignore_then(just('|'))
+                    .or(just('∨'))
                     .ignore_then(conjunction)
                     .repeated(),
             )
@@ -233,20 +438,115 @@ pub fn parser() -> impl Parser<char, Expression, Error = Error> {
             .labelled("disjunction")
             .boxed();
 
-        disjunction
+        // The body of a lambda expression extends as far to the right as possible,
+        // so `x -> x + 1` is the function that increments its argument.
+        let lambda = text::ident()
+            .map(|parameter| vec![parameter])
+            .or(text::ident()
+                .padded()
+                .separated_by(just(','))
+                .delimited_by(just('('), just(')')))
+            .padded()
+            .then_ignore(just("->").ignored().or(just('→').ignored()))
+            .then(expression)
+            .map(|(parameters, body)| Expression::Lambda(parameters, Box::new(body)))
+            .labelled("lambda")
+            .boxed();
+
+        lambda.or(disjunction)
     })
 }
 
+/// Builds a vector, matrix, or tensor from the elements of a list literal.
+#[cfg(feature = "matrices")]
+fn vector_or_matrix(elements: Vec<Expression>, span: Range<usize>) -> Result<Expression, Error> {
+    let shapes = elements
+        .iter()
+        .map(|element| Tensor::from_expression(element).map(|t| t.shape().to_vec()))
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default();
+
+    // Nested lists containing matrices (or tensors) must have the same shape
+    // at every level, so that they can be stacked into a tensor.
+    if shapes.iter().any(|shape| shape.len() > 1) {
+        if let Some(shape) = shapes.iter().find(|shape| **shape != shapes[0]) {
+            let describe = |shape: &[usize]| {
+                shape
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join("x")
+            };
+
+            return Err(Error::custom(
+                span,
+                format!(
+                    "ragged nested list with elements of shapes {} and {}",
+                    describe(&shapes[0]),
+                    describe(shape),
+                ),
+            ));
+        }
+    }
+
+    Ok(if let Some(tensor) = Tensor::from_slices(&elements) {
+        // Matrices (or tensors) of the same shape are stacked into a tensor
+        // with an additional dimension.
+        Expression::Tensor(tensor)
+    } else if let Some(Expression::Vector(v)) = elements.first() {
+        // If all elements of the vector are themselves vectors, and have the same size,
+        // they are interpreted as the rows of a rectangular matrix.
+        let row_size = v.len();
+        let mut rows = Vec::new();
+
+        for element in &elements {
+            match element {
+                Expression::Vector(v) if v.len() == row_size => {
+                    rows.push(v.transpose());
+                }
+                _ => return Ok(Expression::Vector(Vector::from_vec(elements))),
+            }
+        }
+
+        Expression::Matrix(Matrix::from_rows(&rows))
+    } else {
+        Expression::Vector(Vector::from_vec(elements))
+    })
+}
+
+/// Rejects list literals, which require the `matrices` feature.
+#[cfg(not(feature = "matrices"))]
+fn vector_or_matrix(_: Vec<Expression>, span: Range<usize>) -> Result<Expression, Error> {
+    Err(Error::custom(
+        span,
+        "vectors and matrices are not supported without the `matrices` feature",
+    ))
+}
+
+/// Parses the given string as an expression, reading numbers written
+/// with the decimal separator and digit grouping of the given locale.
+/// See [`parser_with_locale`] for details.
+pub fn parse_with_locale(string: &str, locale: Locale) -> Result<Expression, ParseError> {
+    parser_with_locale(locale)
+        .then_ignore(end())
+        .parse(string)
+        .map_err(|errors| ParseError {
+            errors,
+            input: string.to_owned(),
+        })
+}
+
 impl FromStr for Expression {
-    type Err = Vec<Error>;
+    type Err = ParseError;
 
     fn from_str(string: &str) -> Result<Self, Self::Err> {
-        parser().then_ignore(end()).parse(string)
+        parse_with_locale(string, Locale::default())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "matrices")]
     use nalgebra::{dmatrix, dvector};
 
     use crate::expression::{Expression, Expression::*};
@@ -257,6 +557,47 @@ mod tests {
         assert_eq!(string.parse(), Ok(expression));
     }
 
+    #[track_caller]
+    fn c(string: &str, code: &str, suggestion: Option<&str>) {
+        let error = string.parse::<Expression>().unwrap_err();
+
+        assert_eq!(error.code(), code);
+        assert_eq!(error.suggestion().as_deref(), suggestion);
+    }
+
+    #[test]
+    fn error_codes() {
+        c("a $ b", "E0101", None);
+        c("a = b", "E0101", Some("did you mean `==` instead of `=`?"));
+        c("a & b", "E0101", Some("did you mean `&&` instead of `&`?"));
+        c("a | b", "E0101", Some("did you mean `||` instead of `|`?"));
+        c("a +", "E0102", None);
+        c("(a + b", "E0102", Some("did you forget a closing `)`?"));
+        c("[a, b", "E0102", Some("did you forget a closing `]`?"));
+    }
+
+    #[test]
+    fn errors() {
+        let e = |string: &str| string.parse::<Expression>().unwrap_err().to_string();
+
+        assert!(
+            e("a +").starts_with("unexpected end of input at position 3, expected '!', '\"', '('")
+        );
+        assert!(e("a $ b").starts_with("unexpected character '$' at position 2, expected "));
+
+        #[cfg(not(feature = "matrices"))]
+        {
+            assert_eq!(
+                e("[1, 2]"),
+                "vectors and matrices are not supported without the `matrices` feature",
+            );
+            assert_eq!(
+                e("v[0]"),
+                "indexing is not supported without the `matrices` feature",
+            );
+        }
+    }
+
     #[test]
     fn variables() {
         t("a   ", var("a"));
@@ -299,6 +640,7 @@ mod tests {
         t(" 100.000 ", ratd(100, 1));
     }
 
+    #[cfg(feature = "matrices")]
     #[test]
     fn vectors() {
         t(" [ ] ", Vector(dvector![]));
@@ -314,6 +656,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "matrices")]
     #[test]
     fn matrices() {
         t("[[1   ]   ]   ", Matrix(dmatrix![int(1)]));
@@ -334,12 +677,122 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "matrices")]
+    #[test]
+    fn tensors() {
+        t(
+            "[[[1, 2]], [[3, 4]]]",
+            Tensor(
+                crate::expression::Tensor::new(vec![2, 1, 2], vec![int(1), int(2), int(3), int(4)])
+                    .unwrap(),
+            ),
+        );
+        t(
+            "t[1, 2, k]",
+            TensorElement(Box::new(var("t")), vec![int(1), int(2), var("k")]),
+        );
+        t(
+            "reshape(v, [2, 2])[0]",
+            VectorElement(
+                Box::new(fun(
+                    var("reshape"),
+                    [var("v"), Vector(dvector![int(2), int(2)])],
+                )),
+                Box::new(int(0)),
+            ),
+        );
+        t(
+            "v[0][1, 2]",
+            MatrixElement(
+                Box::new(VectorElement(Box::new(var("v")), Box::new(int(0)))),
+                Box::new(int(1)),
+                Box::new(int(2)),
+            ),
+        );
+        t("f(x)(y)", fun(fun(var("f"), [var("x")]), [var("y")]));
+
+        let e = |string: &str| string.parse::<Expression>().unwrap_err().to_string();
+
+        assert_eq!(
+            e("[[[1, 2], [3, 4]], [[5, 6]]]"),
+            "ragged nested list with elements of shapes 2x2 and 1x2",
+        );
+        assert_eq!(
+            e("[[[1, 2]], [3, 4]]"),
+            "ragged nested list with elements of shapes 1x2 and 2",
+        );
+    }
+
+    #[test]
+    fn lambdas() {
+        let l = |parameters: &[&str], body| {
+            Lambda(
+                parameters.iter().map(|p| p.to_string()).collect(),
+                Box::new(body),
+            )
+        };
+
+        t("x -> x ^ 2", l(&["x"], pow(var("x"), int(2))));
+        t("(x, y)->x*y", l(&["x", "y"], var("x") * var("y")));
+        t("() -> 1", l(&[], int(1)));
+        t("x → y -> x + y", l(&["x"], l(&["y"], var("x") + var("y"))));
+        t(
+            "map(x -> x + 1, v)",
+            fun(var("map"), [l(&["x"], var("x") + int(1)), var("v")]),
+        );
+        t(
+            "(x -> x)(1) - 1",
+            fun(l(&["x"], var("x")), [int(1)]) - int(1),
+        );
+    }
+
     #[test]
     fn booleans() {
         t("   true", Boolean(true));
         t("false   ", Boolean(false));
     }
 
+    #[test]
+    fn percentages() {
+        t("15%", int(15) / int(100));
+        t("2.5% ", ratd(25, 10) / int(100));
+        t("120 * (1 + 15%)", int(120) * (int(1) + int(15) / int(100)));
+        t("50% + 10", int(50) / int(100) + int(10));
+        t("15% * 200", int(15) / int(100) * int(200));
+        t("f(10%, x)", fun(var("f"), [int(10) / int(100), var("x")]));
+        t("7%3", int(7) % int(3));
+        t("7%x", int(7) % var("x"));
+        t("7 % -3", int(7) % -int(3));
+        t("10%-3", int(10) % -int(3));
+        t("10% -3", int(10) % -int(3));
+        t("a%-b", var("a") % -var("b"));
+        t("7%-x", int(7) % -var("x"));
+    }
+
+    #[test]
+    fn factorials() {
+        let factorial = |a| fun(var("factorial"), [a]);
+
+        t("5!", factorial(int(5)));
+        t(" n ! ", factorial(var("n")));
+        t("(n + 1)!", factorial(var("n") + int(1)));
+        t("-n!", -factorial(var("n")));
+        t("2^n!", pow(int(2), factorial(var("n"))));
+        t("n! ^ 2", pow(factorial(var("n")), int(2)));
+        t("n!/k!", factorial(var("n")) / factorial(var("k")));
+        t("!n!", !factorial(var("n")));
+        t("n != 5", ne(var("n"), int(5)));
+        t("n! != 5", ne(factorial(var("n")), int(5)));
+    }
+
+    #[test]
+    fn strings() {
+        t("\"\"", string(""));
+        t(" \"data.csv\" ", string("data.csv"));
+        t(r#""a \"b\" \\ c\n\td""#, string("a \"b\" \\ c\n\td"));
+        t("f(\"x\", x)", fun(var("f"), [string("x"), var("x")]));
+    }
+
     #[test]
     fn operators() {
         t("  - 1 ", -int(1));
@@ -374,6 +827,12 @@ mod tests {
         t(" (1^2)  ^  3", pow(pow(int(1), int(2)), int(3)));
         t("1 ^2 ^3 ", pow(int(1), pow(int(2), int(3))));
 
+        t("5 mod 7", modulo(int(5), int(7)));
+        t(" 1+2  mod 3 * 4", modulo(int(1) + int(2), int(3) * int(4)));
+        t("1 mod 2 mod 3", modulo(modulo(int(1), int(2)), int(3)));
+        t("A mod(B)", modulo(var("A"), var("B")));
+        t("modulus mod 2", modulo(var("modulus"), int(2)));
+
         // TODO: Comparison operators!
 
         t("A&&B&&C", and(and(var("A"), var("B")), var("C")));
@@ -384,6 +843,53 @@ mod tests {
         t("   A|| B &&C", or(var("A"), and(var("B"), var("C"))));
     }
 
+    #[test]
+    fn unicode_operators() {
+        t("2 · π × r", int(2) * var("pi") * var("r"));
+        t("a ÷ b − c", var("a") / var("b") - var("c"));
+        t("−a", -var("a"));
+        t("√x ^ 2 + 1", pow(pow(var("x"), int(2)), rat(1, 2)) + int(1));
+        t("¬A ∧ B ∨ C", or(and(!var("A"), var("B")), var("C")));
+        t("a ≤ b", le(var("a"), var("b")));
+        t("a ≥ b", ge(var("a"), var("b")));
+        t("a ≠ b", ne(var("a"), var("b")));
+    }
+
+    #[test]
+    fn locale() {
+        use crate::parse::parse_with_locale;
+        use crate::print::Locale;
+
+        let l = |string: &str, locale: Locale, expression: Expression| {
+            assert_eq!(parse_with_locale(string, locale), Ok(expression));
+        };
+
+        let european = Locale {
+            decimal_separator: ',',
+            digit_group_separator: Some('.'),
+        };
+
+        l("1.234,56", european, ratd(123456, 100));
+        l("-1.234.567", european, -int(1234567));
+        l("0,5 * x", european, ratd(5, 10) * var("x"));
+        l("f(1,5, 2)", european, fun(var("f"), [ratd(15, 10), int(2)]));
+        #[cfg(feature = "matrices")]
+        l("[1, 2]", european, Vector(dvector![int(1), int(2)]));
+        l("1.000 + 2", european, int(1000) + int(2));
+        assert!(parse_with_locale("1.5", european).is_err());
+        assert!(parse_with_locale("1.2345", european).is_err());
+
+        l(
+            "1,234.5",
+            Locale {
+                decimal_separator: '.',
+                digit_group_separator: Some(','),
+            },
+            ratd(12345, 10),
+        );
+        assert!(parse_with_locale("1,5", Locale::default()).is_err());
+    }
+
     // TODO: Replace with a real benchmark once `#[bench]` is stable.
     #[test]
     fn benchmark() {
@@ -0,0 +1,357 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{One, Signed, ToPrimitive, Zero};
+
+use crate::{
+    expression::{Expression, Rational},
+    helpers::*,
+    polynomial::Polynomial,
+};
+
+/// Maximum absolute value of an integer exponent that is expanded
+/// when converting an expression to a rational function.
+const MAX_EXPONENT: u32 = 64;
+
+/// Quotient of two polynomials, with a non-zero denominator.
+#[derive(Clone, Debug)]
+struct RationalFunction {
+    numerator: Polynomial,
+    denominator: Polynomial,
+}
+
+impl RationalFunction {
+    /// Returns the quotient of the polynomials, with common factors cancelled
+    /// if the polynomials contain at most one variable.
+    fn new(numerator: Polynomial, denominator: Polynomial) -> Self {
+        if numerator.is_zero() {
+            return Self {
+                numerator,
+                denominator: Polynomial::constant(Rational::one()),
+            };
+        }
+
+        match numerator.gcd(&denominator) {
+            Some(gcd) if gcd.constant_value().is_none() => Self {
+                numerator: numerator.div_rem(&gcd).unwrap().0,
+                denominator: denominator.div_rem(&gcd).unwrap().0,
+            },
+            _ => Self {
+                numerator,
+                denominator,
+            },
+        }
+    }
+
+    /// Returns the rational function represented by the expression, or `None` if the
+    /// expression is not built from variables and rational numbers using arithmetic
+    /// operations and integer powers, or contains a division by zero.
+    fn from_expression(expression: &Expression) -> Option<Self> {
+        use crate::expression::Expression::*;
+
+        match expression {
+            Negation(a) => {
+                let a = Self::from_expression(a)?;
+                Some(Self::new(-a.numerator, a.denominator))
+            }
+            Sum(a, b) | Difference(a, b) => {
+                let (a, b) = (Self::from_expression(a)?, Self::from_expression(b)?);
+
+                let left = &a.numerator * &b.denominator;
+                let right = &b.numerator * &a.denominator;
+
+                Some(Self::new(
+                    if let Sum(_, _) = expression {
+                        left + right
+                    } else {
+                        left - right
+                    },
+                    a.denominator * b.denominator,
+                ))
+            }
+            Product(a, b) => {
+                let (a, b) = (Self::from_expression(a)?, Self::from_expression(b)?);
+                Some(Self::new(
+                    a.numerator * b.numerator,
+                    a.denominator * b.denominator,
+                ))
+            }
+            Quotient(a, b) => {
+                let (a, b) = (Self::from_expression(a)?, Self::from_expression(b)?);
+                a.divide(b)
+            }
+            Power(a, b) => {
+                let n = Polynomial::from_expression(b)?
+                    .constant_value()
+                    .filter(|n| n.is_integer())?
+                    .to_integer();
+
+                let exponent = n.abs().to_u32().filter(|n| *n <= MAX_EXPONENT)?;
+                let a = Self::from_expression(a)?;
+                let power = Self::new(a.numerator.pow(exponent), a.denominator.pow(exponent));
+
+                if n.is_negative() {
+                    Self::one().divide(power)
+                } else {
+                    Some(power)
+                }
+            }
+            _ => Polynomial::from_expression(expression)
+                .map(|p| Self::new(p, Polynomial::constant(crate::expression::Rational::one()))),
+        }
+    }
+
+    /// Returns the rational function `1`.
+    fn one() -> Self {
+        Self::new(
+            Polynomial::constant(Rational::one()),
+            Polynomial::constant(Rational::one()),
+        )
+    }
+
+    /// Returns the quotient of the rational functions, or `None` if `other` is zero.
+    fn divide(self, other: Self) -> Option<Self> {
+        if other.numerator.is_zero() {
+            return None;
+        }
+
+        Some(Self::new(
+            self.numerator * other.denominator,
+            self.denominator * other.numerator,
+        ))
+    }
+
+    /// Returns the expression representing the rational function as a single quotient,
+    /// with coprime integer coefficients in the numerator and in the denominator, and
+    /// a positive leading coefficient in the denominator.
+    fn to_expression(&self) -> Expression {
+        let (sign, denominator) = normalize(&self.denominator);
+        let content = self.numerator.content();
+
+        if content.is_zero() {
+            return int(0);
+        }
+
+        let ratio = &content / sign;
+
+        let numerator = self
+            .numerator
+            .scale(&(Rational::from(ratio.numer().clone()) / content));
+        let denominator = denominator.scale(&Rational::from(ratio.denom().clone()));
+
+        if matches!(denominator.constant_value(), Some(c) if c.is_one()) {
+            numerator.to_expression()
+        } else {
+            numerator.to_expression() / denominator.to_expression()
+        }
+    }
+}
+
+/// Returns the content of the polynomial, with the sign of its leading coefficient,
+/// and the polynomial divided by it, which has coprime integer coefficients and
+/// a positive leading coefficient.
+fn normalize(p: &Polynomial) -> (Rational, Polynomial) {
+    let content = match p.leading_term() {
+        Some((_, c)) if c.is_negative() => -p.content(),
+        Some(_) => p.content(),
+        None => return (Rational::one(), p.clone()),
+    };
+
+    (content.clone(), p.scale(&content.recip()))
+}
+
+/// Returns the inverse of the polynomial `a` modulo the polynomial `m`
+/// in one variable, or `None` if they are not coprime.
+fn inverse(a: &Polynomial, m: &Polynomial) -> Option<Polynomial> {
+    // https://en.wikipedia.org/wiki/Polynomial_greatest_common_divisor#B%C3%A9zout's_identity_and_extended_GCD_algorithm
+    let (mut r0, mut r1) = (m.clone(), a.div_rem(m)?.1);
+    let (mut s0, mut s1) = (Polynomial::default(), Polynomial::constant(Rational::one()));
+
+    while !r1.is_zero() {
+        let (q, r) = r0.div_rem(&r1)?;
+        let s = s0 - &q * &s1;
+
+        r0 = r1;
+        r1 = r;
+        s0 = s1;
+        s1 = s;
+    }
+
+    match r0.constant_value() {
+        Some(c) if !c.is_zero() => Some(s0.scale(&c.recip())),
+        _ => None,
+    }
+}
+
+impl Expression {
+    /// Returns the partial fraction decomposition of the rational function in the
+    /// variable with the given identifier, or `None` if the expression is not a quotient
+    /// of polynomials in that variable with rational coefficients. The decomposition
+    /// consists of a polynomial followed by fractions whose denominators are powers of
+    /// the irreducible factors of the denominator over the rational numbers, and whose
+    /// numerators have a smaller degree than those factors.
+    pub fn apart(&self, variable: &str) -> Option<Self> {
+        let function = RationalFunction::from_expression(self)?;
+
+        if function
+            .numerator
+            .variables()
+            .iter()
+            .chain(function.denominator.variables().iter())
+            .any(|identifier| identifier != variable)
+        {
+            return None;
+        }
+
+        let (_, factors) = function.denominator.factor()?;
+        let (quotient, remainder) = function.numerator.div_rem(&function.denominator)?;
+
+        let mut terms = Vec::new();
+
+        for (factor, multiplicity) in factors {
+            let power = factor.pow(multiplicity);
+            let cofactor = function.denominator.div_rem(&power)?.0;
+
+            // The numerator over the power of the factor is congruent to the remainder
+            // divided by the rest of the denominator, modulo the power of the factor.
+            let mut numerator = (&remainder * &inverse(&cofactor, &power)?)
+                .div_rem(&power)?
+                .1;
+
+            // The digits of the numerator in base `factor` are the numerators
+            // of the fractions over the powers of the factor.
+            let mut digits = Vec::new();
+
+            for exponent in (1..=multiplicity).rev() {
+                let (q, r) = numerator.div_rem(&factor)?;
+                digits.push((r, exponent));
+                numerator = q;
+            }
+
+            let base = factor.to_expression();
+
+            for (digit, exponent) in digits.into_iter().rev() {
+                if digit.is_zero() {
+                    continue;
+                }
+
+                let (content, digit) = normalize(&digit);
+
+                let power = match exponent {
+                    1 => base.clone(),
+                    _ => pow(base.clone(), int(exponent)),
+                };
+
+                let denominator = if content.denom().is_one() {
+                    power
+                } else {
+                    Expression::Integer(content.denom().clone()) * power
+                };
+
+                terms.push((Rational::from(content.numer().clone()), digit, denominator));
+            }
+        }
+
+        let mut result = if quotient.is_zero() {
+            None
+        } else {
+            Some(quotient.to_expression())
+        };
+
+        for (numerator, digit, denominator) in terms {
+            // Negative terms are subtracted, except for the first term,
+            // whose sign is kept in its numerator.
+            result = Some(match result {
+                Some(result) if numerator.is_negative() => {
+                    result - digit.scale(&-numerator).to_expression() / denominator
+                }
+                Some(result) => result + digit.scale(&numerator).to_expression() / denominator,
+                None => digit.scale(&numerator).to_expression() / denominator,
+            });
+        }
+
+        Some(result.unwrap_or_else(|| int(0)))
+    }
+
+    /// Returns the rational function written as a single quotient of polynomials with
+    /// coprime integer coefficients, with common factors cancelled if the polynomials
+    /// contain at most one variable, or `None` if the expression is not built from
+    /// variables and rational numbers using arithmetic operations and integer powers.
+    pub fn together(&self) -> Option<Self> {
+        RationalFunction::from_expression(self).map(|function| function.to_expression())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+
+    #[track_caller]
+    fn a(function: &str, decomposition: Option<&str>) {
+        let function = function.parse::<Expression>().unwrap();
+        let result = function.apart("x");
+
+        assert_eq!(
+            result.as_ref().map(ToString::to_string),
+            decomposition.map(ToOwned::to_owned),
+        );
+
+        // Combining the partial fractions gives back the original function.
+        if let Some(result) = result {
+            assert_eq!(result.together(), function.together());
+        }
+    }
+
+    #[test]
+    fn apart() {
+        a(
+            "1 / (x ^ 2 - 1)",
+            Some("1 / (2 * (x - 1)) - 1 / (2 * (x + 1))"),
+        );
+        a("(x ^ 3 + 1) / (x ^ 2 + x)", Some("x - 1 + 1 / x"));
+        a(
+            "(3 * x + 5) / (2 * x ^ 2 + 2 * x)",
+            Some("5 / (2 * x) - 1 / (x + 1)"),
+        );
+        a(
+            "1 / ((x - 1) ^ 2 * (x ^ 2 + 1))",
+            Some("-1 / (2 * (x - 1)) + 1 / (2 * (x - 1) ^ 2) + x / (2 * (x ^ 2 + 1))"),
+        );
+        a(
+            "(x ^ 2 + 2) / (x ^ 3 - 2 * x)",
+            Some("-1 / x + 2 * x / (x ^ 2 - 2)"),
+        );
+        a("(x + 1) / (x + 1) ^ 3", Some("1 / (x + 1) ^ 2"));
+        a("x ^ 2 - 1", Some("x ^ 2 - 1"));
+        a("0", Some("0"));
+        a("x / y", None);
+        a("1 / (x - x)", None);
+        a("x ^ (1/2)", None);
+    }
+
+    #[track_caller]
+    fn t(function: &str, quotient: Option<&str>) {
+        assert_eq!(
+            function
+                .parse::<Expression>()
+                .unwrap()
+                .together()
+                .map(|quotient| quotient.to_string()),
+            quotient.map(ToOwned::to_owned),
+        );
+    }
+
+    #[test]
+    fn together() {
+        t("1 / (x - 1) + 1 / (x + 1)", Some("2 * x / (x ^ 2 - 1)"));
+        t("x / 2 + 1 / 3", Some("(3 * x + 2) / 6"));
+        t("(x ^ 2 - 1) / (x + 1)", Some("x - 1"));
+        t("1 / (1 - x)", Some("-1 / (x - 1)"));
+        t("x ^ (-2) - 1", Some("(-x ^ 2 + 1) / x ^ 2"));
+        t("(x / 3) / (2 / x)", Some("x ^ 2 / 6"));
+        t("1 / x + 1 / y", Some("(x + y) / (x * y)"));
+        t("x - x", Some("0"));
+        t("1 / (x - x)", None);
+        t("sin(x)", None);
+    }
+}
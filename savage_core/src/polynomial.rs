@@ -0,0 +1,547 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use num::{One, Signed, ToPrimitive, Zero};
+
+use crate::{
+    expression::{Expression, Rational, Type},
+    helpers::*,
+};
+
+/// Product of variables raised to positive integer powers,
+/// stored as the exponents of the variables by their identifiers.
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct Monomial(BTreeMap<String, u32>);
+
+impl Monomial {
+    /// Returns the monomial consisting of the variable with the given identifier.
+    pub fn variable(identifier: &str) -> Self {
+        Monomial(BTreeMap::from([(identifier.to_owned(), 1)]))
+    }
+
+    /// Returns the exponents of the variables in the monomial, by their identifiers.
+    pub fn exponents(&self) -> &BTreeMap<String, u32> {
+        &self.0
+    }
+
+    /// Returns the exponent of the variable with the given identifier,
+    /// which is zero if the monomial doesn't contain the variable.
+    pub fn exponent(&self, identifier: &str) -> u32 {
+        self.0.get(identifier).copied().unwrap_or(0)
+    }
+
+    /// Returns the sum of the exponents of all variables.
+    pub fn degree(&self) -> u32 {
+        self.0.values().sum()
+    }
+
+    /// Returns the quotient of the monomial and `divisor`,
+    /// or `None` if the monomial is not divisible by `divisor`.
+    pub fn divide(&self, divisor: &Self) -> Option<Self> {
+        let mut exponents = self.0.clone();
+
+        for (identifier, exponent) in &divisor.0 {
+            let remaining = exponents.get(identifier).copied().unwrap_or(0);
+
+            match remaining.cmp(exponent) {
+                Ordering::Less => return None,
+                Ordering::Equal => {
+                    exponents.remove(identifier);
+                }
+                Ordering::Greater => {
+                    exponents.insert(identifier.clone(), remaining - exponent);
+                }
+            }
+        }
+
+        Some(Monomial(exponents))
+    }
+}
+
+impl Mul for &Monomial {
+    type Output = Monomial;
+
+    // Multiplying monomials adds their exponents.
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, other: Self) -> Monomial {
+        let mut exponents = self.0.clone();
+
+        for (identifier, exponent) in &other.0 {
+            *exponents.entry(identifier.clone()).or_insert(0) += exponent;
+        }
+
+        Monomial(exponents)
+    }
+}
+
+impl Ord for Monomial {
+    /// Graded lexicographic order: monomials of higher degree are greater, and
+    /// monomials of the same degree are ordered by the exponents of the variables,
+    /// with variables whose identifiers come first alphabetically compared first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.degree().cmp(&other.degree()).then_with(|| {
+            let identifiers = self.0.keys().chain(other.0.keys()).collect::<BTreeSet<_>>();
+
+            identifiers
+                .into_iter()
+                .map(|identifier| self.exponent(identifier).cmp(&other.exponent(identifier)))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+}
+
+impl PartialOrd for Monomial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Polynomial with rational coefficients in any number of variables, stored
+/// as the non-zero coefficients of its terms by their monomials. Because zero
+/// coefficients are never stored, equal polynomials have equal representations.
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct Polynomial {
+    terms: BTreeMap<Monomial, Rational>,
+}
+
+impl Polynomial {
+    /// Returns the polynomial whose only term has the given coefficient and monomial.
+    pub fn term(coefficient: Rational, monomial: Monomial) -> Self {
+        let mut terms = BTreeMap::new();
+
+        if !coefficient.is_zero() {
+            terms.insert(monomial, coefficient);
+        }
+
+        Polynomial { terms }
+    }
+
+    /// Returns the constant polynomial with the given value.
+    pub fn constant(value: Rational) -> Self {
+        Self::term(value, Monomial::default())
+    }
+
+    /// Returns the polynomial consisting of the variable with the given identifier.
+    pub fn variable(identifier: &str) -> Self {
+        Self::term(Rational::one(), Monomial::variable(identifier))
+    }
+
+    /// Returns the polynomial represented by the given expression, or `None` if the
+    /// expression is not built from rational numbers and variables using addition,
+    /// subtraction, multiplication, division by non-zero numbers, and powers with
+    /// non-negative integer exponents.
+    pub fn from_expression(expression: &Expression) -> Option<Self> {
+        use crate::expression::Expression::*;
+
+        match expression {
+            Variable(identifier) => Some(Self::variable(identifier)),
+            Negation(a) => Some(-Self::from_expression(a)?),
+            Sum(a, b) => Some(Self::from_expression(a)? + Self::from_expression(b)?),
+            Difference(a, b) => Some(Self::from_expression(a)? - Self::from_expression(b)?),
+            Product(a, b) => Some(Self::from_expression(a)? * Self::from_expression(b)?),
+            Quotient(a, b) => {
+                let b = Self::from_expression(b)?.constant_value()?;
+
+                if b.is_zero() {
+                    None
+                } else {
+                    Some(Self::from_expression(a)?.scale(&b.recip()))
+                }
+            }
+            Power(a, b) => {
+                let n = match &**b {
+                    Integer(n) => n.to_u32()?,
+                    _ => return None,
+                };
+
+                Some(Self::from_expression(a)?.pow(n))
+            }
+            _ => match expression.typ() {
+                Type::Number(z, _) if z.im.is_zero() => Some(Self::constant(z.re)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Returns the expression representing the polynomial, with the terms ordered
+    /// from the greatest to the smallest monomial in graded lexicographic order.
+    pub fn to_expression(&self) -> Expression {
+        let mut result: Option<Expression> = None;
+
+        for (monomial, coefficient) in self.terms.iter().rev() {
+            let factors = monomial
+                .exponents()
+                .iter()
+                .map(|(identifier, exponent)| match exponent {
+                    1 => var(identifier),
+                    _ => pow(var(identifier), int(*exponent)),
+                })
+                .reduce(|a, b| a * b);
+
+            let negative = result.is_some() && coefficient.is_negative();
+            let magnitude = if negative {
+                coefficient.abs()
+            } else {
+                coefficient.clone()
+            };
+
+            let term = match factors {
+                Some(factors) if magnitude.is_one() => factors,
+                Some(factors) if (-&magnitude).is_one() => -factors,
+                Some(factors) => Expression::from(magnitude) * factors,
+                None => Expression::from(magnitude),
+            };
+
+            result = Some(match result {
+                Some(result) if negative => result - term,
+                Some(result) => result + term,
+                None => term,
+            });
+        }
+
+        result.unwrap_or_else(|| int(0))
+    }
+
+    /// Returns the non-zero coefficients of the polynomial by their monomials.
+    pub fn terms(&self) -> &BTreeMap<Monomial, Rational> {
+        &self.terms
+    }
+
+    /// Returns whether the polynomial is zero.
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Returns the value of the polynomial if it is constant.
+    pub fn constant_value(&self) -> Option<Rational> {
+        match self.terms.iter().next() {
+            None => Some(Rational::zero()),
+            Some((monomial, coefficient)) if self.terms.len() == 1 && monomial.degree() == 0 => {
+                Some(coefficient.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the identifiers of the variables occurring in the polynomial.
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.terms
+            .keys()
+            .flat_map(|monomial| monomial.exponents().keys().cloned())
+            .collect()
+    }
+
+    /// Returns the highest exponent of the variable with the given identifier
+    /// in the polynomial, or `None` if the polynomial is zero.
+    pub fn degree(&self, identifier: &str) -> Option<u32> {
+        self.terms
+            .keys()
+            .map(|monomial| monomial.exponent(identifier))
+            .max()
+    }
+
+    /// Returns the greatest degree of the terms of the polynomial,
+    /// or `None` if the polynomial is zero.
+    pub fn total_degree(&self) -> Option<u32> {
+        self.terms.keys().map(Monomial::degree).max()
+    }
+
+    /// Returns the coefficients of the polynomial as a polynomial in the variable with
+    /// the given identifier, ordered by increasing degree and without trailing zeros,
+    /// or `None` if the polynomial contains other variables.
+    pub fn coefficients(&self, identifier: &str) -> Option<Vec<Rational>> {
+        let mut coefficients =
+            vec![Rational::zero(); self.degree(identifier).map_or(0, |d| d + 1) as usize];
+
+        for (monomial, coefficient) in &self.terms {
+            if monomial.exponents().len() > 1
+                || (monomial.degree() > 0 && monomial.exponent(identifier) == 0)
+            {
+                return None;
+            }
+
+            coefficients[monomial.exponent(identifier) as usize] = coefficient.clone();
+        }
+
+        Some(coefficients)
+    }
+
+    /// Returns the polynomial in the variable with the given identifier
+    /// with the given coefficients, ordered by increasing degree.
+    pub fn from_coefficients(identifier: &str, coefficients: &[Rational]) -> Self {
+        let mut result = Self::default();
+
+        for (degree, coefficient) in coefficients.iter().enumerate() {
+            let monomial = if degree == 0 {
+                Monomial::default()
+            } else {
+                Monomial(BTreeMap::from([(identifier.to_owned(), degree as u32)]))
+            };
+
+            result = result + Self::term(coefficient.clone(), monomial);
+        }
+
+        result
+    }
+
+    /// Returns the term with the greatest monomial in graded lexicographic order,
+    /// or `None` if the polynomial is zero.
+    pub fn leading_term(&self) -> Option<(&Monomial, &Rational)> {
+        self.terms.iter().next_back()
+    }
+
+    /// Returns the product of the polynomial and the rational number `c`.
+    pub fn scale(&self, c: &Rational) -> Self {
+        if c.is_zero() {
+            return Self::default();
+        }
+
+        Polynomial {
+            terms: self
+                .terms
+                .iter()
+                .map(|(monomial, coefficient)| (monomial.clone(), coefficient * c))
+                .collect(),
+        }
+    }
+
+    /// Returns the polynomial raised to the power of `n`.
+    pub fn pow(&self, mut n: u32) -> Self {
+        let mut result = Self::constant(Rational::one());
+        let mut base = self.clone();
+
+        while n > 0 {
+            if n % 2 == 1 {
+                result = &result * &base;
+            }
+
+            n /= 2;
+
+            if n > 0 {
+                base = &base * &base;
+            }
+        }
+
+        result
+    }
+
+    /// Returns the quotient and remainder of dividing the polynomial by `divisor`,
+    /// such that `self == quotient * divisor + remainder` and no term of the remainder
+    /// is divisible by the leading term of `divisor`, or `None` if `divisor` is zero.
+    /// For polynomials in one variable, this is ordinary polynomial long division.
+    pub fn div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        let (leading_monomial, leading_coefficient) = divisor.leading_term()?;
+
+        let mut quotient = Self::default();
+        let mut remainder = Self::default();
+        let mut dividend = self.clone();
+
+        while let Some((monomial, coefficient)) = dividend.leading_term() {
+            let term = Self::term(coefficient.clone(), monomial.clone());
+
+            match monomial.divide(leading_monomial) {
+                Some(factor) => {
+                    let factor = Self::term(coefficient / leading_coefficient, factor);
+                    dividend = dividend - &factor * divisor;
+                    quotient = quotient + factor;
+                }
+                None => {
+                    dividend = dividend - term.clone();
+                    remainder = remainder + term;
+                }
+            }
+        }
+
+        Some((quotient, remainder))
+    }
+
+    /// Returns the polynomial obtained by replacing the variables with the values
+    /// given for them by their identifiers. Variables without a value are kept,
+    /// so the result is constant if values are given for all variables.
+    pub fn evaluate(&self, values: &HashMap<String, Rational>) -> Self {
+        let mut result = Self::default();
+
+        for (monomial, coefficient) in &self.terms {
+            let mut coefficient = coefficient.clone();
+            let mut exponents = BTreeMap::new();
+
+            for (identifier, exponent) in monomial.exponents() {
+                match values.get(identifier) {
+                    Some(value) => coefficient *= value.pow(*exponent as i32),
+                    None => {
+                        exponents.insert(identifier.clone(), *exponent);
+                    }
+                }
+            }
+
+            result = result + Self::term(coefficient, Monomial(exponents));
+        }
+
+        result
+    }
+}
+
+impl Neg for Polynomial {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.scale(&-Rational::one())
+    }
+}
+
+impl Add for Polynomial {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        for (monomial, coefficient) in other.terms {
+            let sum = self.terms.remove(&monomial).unwrap_or_else(Rational::zero) + coefficient;
+
+            if !sum.is_zero() {
+                self.terms.insert(monomial, sum);
+            }
+        }
+
+        self
+    }
+}
+
+impl Sub for Polynomial {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, other: Self) -> Polynomial {
+        let mut result = Polynomial::default();
+
+        for (a_monomial, a_coefficient) in &self.terms {
+            for (b_monomial, b_coefficient) in &other.terms {
+                result = result
+                    + Polynomial::term(a_coefficient * b_coefficient, a_monomial * b_monomial);
+            }
+        }
+
+        result
+    }
+}
+
+impl Mul for Polynomial {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        &self * &other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::expression::{Expression, Rational};
+    use crate::polynomial::Polynomial;
+
+    fn p(string: &str) -> Polynomial {
+        Polynomial::from_expression(&string.parse::<Expression>().unwrap()).unwrap()
+    }
+
+    #[track_caller]
+    fn t(polynomial: Polynomial, string: &str) {
+        assert_eq!(polynomial.to_expression().to_string(), string);
+    }
+
+    #[test]
+    fn polynomial() {
+        t(p("(x + 1) ^ 2"), "x ^ 2 + 2 * x + 1");
+        t(p("x + 1 + x"), "2 * x + 1");
+        t(p("y * x - x * y + 3/2"), "3/2");
+        t(p("(x - y) * (x + y) / 2"), "1/2 * x ^ 2 - 1/2 * y ^ 2");
+        t(p("-x ^ 3 + y ^ 2 * x"), "-x ^ 3 + x * y ^ 2");
+        t(p("0 * x"), "0");
+
+        assert_eq!(p("x + 1 + x"), p("1 + 2 * x"));
+        assert_eq!(p("(x + y) ^ 2").degree("x"), Some(2));
+        assert_eq!(p("(x + y) ^ 2 * y").total_degree(), Some(3));
+        assert_eq!(p("0").total_degree(), None);
+        assert_eq!(
+            p("x * y + z").variables().into_iter().collect::<Vec<_>>(),
+            ["x", "y", "z"],
+        );
+
+        assert_eq!(Polynomial::from_expression(&"x ^ y".parse().unwrap()), None);
+        assert_eq!(
+            Polynomial::from_expression(&"sin(x) + 1".parse().unwrap()),
+            None,
+        );
+        assert_eq!(Polynomial::from_expression(&"x / 0".parse().unwrap()), None);
+        assert_eq!(Polynomial::from_expression(&"1 / x".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        t(p("x + 1") + p("x - 1"), "2 * x");
+        t(p("x + 1") - p("x - 1"), "2");
+        t(p("x + 1") * p("x - 1"), "x ^ 2 - 1");
+        t(-p("x - y"), "-x + y");
+        t(
+            p("x + y").pow(3),
+            "x ^ 3 + 3 * x ^ 2 * y + 3 * x * y ^ 2 + y ^ 3",
+        );
+    }
+
+    #[track_caller]
+    fn d(dividend: &str, divisor: &str, quotient: &str, remainder: &str) {
+        let (q, r) = p(dividend).div_rem(&p(divisor)).unwrap();
+
+        t(q.clone(), quotient);
+        t(r.clone(), remainder);
+        assert_eq!(&q * &p(divisor) + r, p(dividend));
+    }
+
+    #[test]
+    fn division() {
+        d("x ^ 3 - 1", "x - 1", "x ^ 2 + x + 1", "0");
+        d("x ^ 2 + 1", "2 * x", "1/2 * x", "1");
+        d("x ^ 2 + 3 * x + 5", "x + 1", "x + 2", "3");
+        d("x ^ 2 * y + y", "x * y", "x", "y");
+        d("1", "x", "0", "1");
+
+        assert_eq!(p("x").div_rem(&p("0")), None);
+    }
+
+    #[test]
+    fn coefficients() {
+        let coefficients = p("2 * x ^ 3 - x + 1/2").coefficients("x").unwrap();
+
+        t(
+            Polynomial::from_coefficients("x", &coefficients),
+            "2 * x ^ 3 - x + 1/2",
+        );
+        assert_eq!(coefficients.len(), 4);
+        assert_eq!(p("0").coefficients("x"), Some(Vec::new()));
+        assert_eq!(p("x * y").coefficients("x"), None);
+        assert_eq!(p("y + 1").coefficients("x"), None);
+    }
+
+    #[test]
+    fn evaluation() {
+        let values = HashMap::from([("x".to_owned(), Rational::new(1.into(), 2.into()))]);
+
+        t(p("4 * x ^ 2 + x * y").evaluate(&values), "1/2 * y + 1");
+        assert_eq!(
+            p("4 * x ^ 2 - x").evaluate(&values).constant_value(),
+            Some(Rational::new(1.into(), 2.into())),
+        );
+    }
+}
@@ -2,15 +2,115 @@
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
 use std::cmp::max;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter, Result};
 
 use num::{One, Signed, Zero};
 
-use crate::expression::{Expression, Integer, Rational};
+use crate::evaluate::operator;
+use crate::expression::{Expression, Integer, Rational, RationalRepresentation};
+
+/// Layout used when formatting matrices.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MatrixLayout {
+    /// All rows on a single line, e.g. `[[1, 2], [3, 4]]`.
+    Inline,
+    /// Each row on its own line, with the columns aligned.
+    Rows,
+}
+
+/// Notation used for decimals that repeat infinitely.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RepeatingDecimals {
+    /// Fall back to fraction representation, e.g. `1/3`,
+    /// or round the decimal if a maximum number of digits is set.
+    Fraction,
+    /// Repeating digits enclosed in parentheses, e.g. `0.1(6)`.
+    Parentheses,
+    /// Repeating digits marked with a combining overline, e.g. `0.16\u{305}`.
+    Overline,
+}
+
+/// Notation used for numbers in decimal representation.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Notation {
+    /// Positional notation, e.g. `12500`.
+    Positional,
+    /// Scientific notation, with a single nonzero digit
+    /// before the decimal separator, e.g. `1.25e4`.
+    Scientific,
+    /// Engineering notation, with exponents that are multiples of 3
+    /// and one to three digits before the decimal separator, e.g. `12.5e3`.
+    Engineering,
+}
+
+/// Conventions for writing numbers, used when formatting and parsing expressions.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Locale {
+    /// Character separating the integer part of a decimal number from the fractional part.
+    pub decimal_separator: char,
+    /// Character separating groups of three digits in the integer part of a number, if any.
+    pub digit_group_separator: Option<char>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            decimal_separator: '.',
+            digit_group_separator: None,
+        }
+    }
+}
+
+/// Options controlling how an expression is formatted by [`Expression::display_with`].
+/// The default options produce the same output as the `Display` implementation.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub struct FormatOptions {
+    /// Representation to use for all rational and complex numbers,
+    /// or `None` to use the representation stored in each number.
+    pub rational_representation: Option<RationalRepresentation>,
+    /// Maximum number of digits after the decimal separator when formatting
+    /// numbers in decimal representation. Numbers that have more digits,
+    /// or that cannot be represented as a finite decimal, are rounded
+    /// to that many digits. If `None`, finite decimals are printed in full
+    /// and all other numbers fall back to fraction representation.
+    pub max_decimal_digits: Option<usize>,
+    /// Notation to use for numbers in decimal representation whose decimal
+    /// expansion repeats infinitely. Repeating decimals are only printed
+    /// if they fit into `max_decimal_digits` digits after the decimal separator,
+    /// or into [`MAX_REPEATING_DECIMAL_DIGITS`] digits if no maximum is set.
+    pub repeating_decimals: RepeatingDecimals,
+    /// Notation to use for numbers in decimal representation.
+    /// In scientific and engineering notation, `max_decimal_digits`
+    /// applies to the digits of the mantissa.
+    pub notation: Notation,
+    /// Decimal separator and digit grouping to use for numbers.
+    pub locale: Locale,
+    /// Whether to omit the multiplication operator between a numeric coefficient
+    /// and a variable, function value, or power of a variable (e.g. `2x`).
+    pub implicit_multiplication: bool,
+    /// Layout to use for matrices.
+    pub matrix_layout: MatrixLayout,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            rational_representation: None,
+            max_decimal_digits: None,
+            repeating_decimals: RepeatingDecimals::Fraction,
+            notation: Notation::Positional,
+            locale: Locale::default(),
+            implicit_multiplication: false,
+            matrix_layout: MatrixLayout::Inline,
+        }
+    }
+}
 
 /// Returns a pair of integers `(n, m)` such that `x = n / 10^m`,
 /// or `None` if no such integers exist.
-fn decimal_representation(x: &Rational) -> Option<(Integer, usize)> {
+pub(crate) fn decimal_representation(x: &Rational) -> Option<(Integer, usize)> {
     // https://en.wikipedia.org/wiki/Decimal_representation#Finite_decimal_representations
     let mut denominator = x.denom().clone();
 
@@ -38,206 +138,984 @@ fn decimal_representation(x: &Rational) -> Option<(Integer, usize)> {
     }
 }
 
+/// Maximum number of digits after the decimal separator in a repeating decimal
+/// if [`FormatOptions::max_decimal_digits`] is not set.
+pub const MAX_REPEATING_DECIMAL_DIGITS: usize = 100;
+
+/// Returns the decimal expansion of the absolute value of `x` as a triple
+/// of the integer part, the non-repeating digits after the decimal separator,
+/// and the repeating digits (which are empty if the expansion is finite),
+/// or `None` if more than `max_digits` digits after the decimal separator are needed.
+fn repeating_decimal_representation(
+    x: &Rational,
+    max_digits: usize,
+) -> Option<(Integer, String, String)> {
+    // https://en.wikipedia.org/wiki/Repeating_decimal#Every_rational_number_is_either_a_terminating_or_repeating_decimal
+    let denominator = x.denom().abs();
+    let numerator = x.numer().abs();
+
+    let integer_part = &numerator / &denominator;
+    let mut remainder = numerator % &denominator;
+
+    let mut digits = String::new();
+    // Positions in the digits at which each remainder was first encountered.
+    // The expansion starts repeating as soon as a remainder occurs again.
+    let mut positions = HashMap::new();
+
+    while !remainder.is_zero() {
+        if let Some(&position) = positions.get(&remainder) {
+            let repeating_digits = digits.split_off(position);
+            return Some((integer_part, digits, repeating_digits));
+        }
+
+        if digits.len() == max_digits {
+            return None;
+        }
+
+        positions.insert(remainder.clone(), digits.len());
+
+        remainder *= 10;
+        digits.push_str(&(&remainder / &denominator).to_string());
+        remainder %= &denominator;
+    }
+
+    Some((integer_part, digits, String::new()))
+}
+
+/// Returns a pair of integers `(n, m)` such that `n / 10^m` is `x` rounded
+/// to `digits` digits after the decimal separator, and `m` is minimal.
+fn rounded_decimal_representation(x: &Rational, digits: usize) -> (Integer, usize) {
+    let scale = Integer::from(10).pow(digits as u32);
+
+    let mut mantissa = (x * Rational::from_integer(scale)).round().to_integer();
+    let mut separator_position = digits;
+
+    while separator_position > 0 && (mantissa.clone() % Integer::from(10)).is_zero() {
+        mantissa /= 10;
+        separator_position -= 1;
+    }
+
+    (mantissa, separator_position)
+}
+
+/// Returns the decimal string `n / 10^m` for the pair `(n, m)`.
+fn decimal_to_string(mantissa: &Integer, separator_position: usize) -> String {
+    let mut string = mantissa.abs().to_string();
+
+    if separator_position > 0 {
+        if separator_position > string.len() - 1 {
+            // Left-pad the string with enough zeros to be able
+            // to insert the decimal separator at the indicated position.
+            string = format!(
+                "{}{}",
+                "0".repeat(separator_position - (string.len() - 1)),
+                string,
+            );
+        }
+
+        string.insert(string.len() - separator_position, '.');
+    }
+
+    format!(
+        "{}{}",
+        if mantissa.is_negative() { "-" } else { "" },
+        string
+    )
+}
+
+/// Returns the decimal string representing the given rational number
+/// in positional notation, or `None` if the number cannot be represented
+/// as a decimal subject to the options.
+fn positional_decimal_to_string(x: &Rational, options: &FormatOptions) -> Option<String> {
+    match decimal_representation(x) {
+        Some((mantissa, separator_position))
+            if separator_position <= options.max_decimal_digits.unwrap_or(usize::MAX) =>
+        {
+            Some(decimal_to_string(&mantissa, separator_position))
+        }
+        _ => {
+            let repeating_decimal = if options.repeating_decimals == RepeatingDecimals::Fraction {
+                None
+            } else {
+                repeating_decimal_representation(
+                    x,
+                    options
+                        .max_decimal_digits
+                        .unwrap_or(MAX_REPEATING_DECIMAL_DIGITS),
+                )
+            };
+
+            match (repeating_decimal, options.max_decimal_digits) {
+                (Some((integer_part, digits, repeating_digits)), _)
+                    if !repeating_digits.is_empty() =>
+                {
+                    Some(format!(
+                        "{}{}.{}{}",
+                        if x.is_negative() { "-" } else { "" },
+                        integer_part,
+                        digits,
+                        if options.repeating_decimals == RepeatingDecimals::Parentheses {
+                            format!("({})", repeating_digits)
+                        } else {
+                            repeating_digits
+                                .chars()
+                                .flat_map(|digit| [digit, '\u{305}'])
+                                .collect()
+                        },
+                    ))
+                }
+                (_, Some(max_digits)) => {
+                    let (mantissa, separator_position) =
+                        rounded_decimal_representation(x, max_digits);
+                    Some(decimal_to_string(&mantissa, separator_position))
+                }
+                (_, None) => None,
+            }
+        }
+    }
+}
+
+/// Returns `10^exponent` as a rational number.
+fn power_of_10(exponent: isize) -> Rational {
+    let power = Rational::from_integer(Integer::from(10).pow(exponent.unsigned_abs() as u32));
+
+    if exponent < 0 {
+        power.recip()
+    } else {
+        power
+    }
+}
+
+/// Returns the decimal string representing the given nonzero rational number
+/// in scientific notation, or in engineering notation if `engineering` is `true`,
+/// or `None` if the number cannot be represented as a decimal subject to the options.
+fn exponential_decimal_to_string(
+    x: &Rational,
+    engineering: bool,
+    options: &FormatOptions,
+) -> Option<String> {
+    let (step, limit) = if engineering { (3, 1000) } else { (1, 10) };
+
+    // The number of digits in the numerator minus the number of digits in the denominator
+    // is either the exponent of the leading digit of the number, or one more than that.
+    let mut exponent =
+        x.numer().abs().to_string().len() as isize - x.denom().to_string().len() as isize;
+
+    if x.abs() < power_of_10(exponent) {
+        exponent -= 1;
+    }
+
+    exponent = exponent.div_euclid(step) * step;
+
+    let mut mantissa = x / power_of_10(exponent);
+
+    if let Some(max_digits) = options.max_decimal_digits {
+        // Rounding can carry over into the next power of the base,
+        // e.g. 9.9996 rounded to 3 digits is 10.000.
+        let (rounded_mantissa, separator_position) =
+            rounded_decimal_representation(&mantissa.abs(), max_digits);
+
+        if Rational::new(
+            rounded_mantissa,
+            Integer::from(10).pow(separator_position as u32),
+        ) >= Rational::from_integer(Integer::from(limit))
+        {
+            exponent += step;
+            mantissa = x / power_of_10(exponent);
+        }
+    }
+
+    positional_decimal_to_string(&mantissa, options).map(|mantissa| {
+        if exponent == 0 {
+            mantissa
+        } else {
+            format!("{}e{}", mantissa, exponent)
+        }
+    })
+}
+
+/// Returns the string representation of the given rational number,
+/// using the given representation unless overridden by the options.
+fn rational_to_string(
+    x: &Rational,
+    representation: RationalRepresentation,
+    options: &FormatOptions,
+) -> String {
+    use crate::expression::RationalRepresentation::*;
+
+    let decimal = match options.rational_representation.unwrap_or(representation) {
+        Fraction => None,
+        Decimal => match options.notation {
+            Notation::Positional => positional_decimal_to_string(x, options),
+            _ if x.is_zero() => Some("0".to_owned()),
+            Notation::Scientific => exponential_decimal_to_string(x, false, options),
+            Notation::Engineering => exponential_decimal_to_string(x, true, options),
+        },
+    };
+
+    // Fall back to fraction representation.
+    localize_number(&decimal.unwrap_or_else(|| x.to_string()), &options.locale)
+}
+
+/// Returns the given string of digits, with the given separator
+/// inserted between groups of three digits counted from the right.
+fn group_digits(digits: &str, separator: char) -> String {
+    let reversed_digits = digits.chars().rev().collect::<Vec<_>>();
+
+    reversed_digits
+        .chunks(3)
+        .rev()
+        .map(|group| group.iter().rev().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
+
+/// Returns the given number string (an integer, fraction, or decimal)
+/// with the decimal separator and digit grouping of the given locale.
+fn localize_number(string: &str, locale: &Locale) -> String {
+    let mut localized_string = String::new();
+    let mut integer_digits = String::new();
+    // Digits after the decimal separator or in an exponent are not grouped.
+    let mut in_integer_part = true;
+
+    let mut characters = string.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        if character.is_ascii_digit() && in_integer_part {
+            integer_digits.push(character);
+
+            if !matches!(characters.peek(), Some(c) if c.is_ascii_digit()) {
+                localized_string.push_str(&match locale.digit_group_separator {
+                    Some(separator) => group_digits(&integer_digits, separator),
+                    None => integer_digits.clone(),
+                });
+
+                integer_digits.clear();
+            }
+
+            continue;
+        }
+
+        localized_string.push(if character == '.' {
+            locale.decimal_separator
+        } else {
+            character
+        });
+
+        in_integer_part = match character {
+            '.' | 'e' => false,
+            '/' => true,
+            _ => in_integer_part,
+        };
+    }
+
+    localized_string
+}
+
+/// Returns the given string as a string literal,
+/// enclosed in double quotes and with special characters escaped.
+pub(crate) fn quote_string(string: &str) -> String {
+    let mut literal = "\"".to_owned();
+
+    for character in string.chars() {
+        match character {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            _ => literal.push(character),
+        }
+    }
+
+    literal.push('"');
+    literal
+}
+
+/// Returns the representation of a tensor with the given shape and row-major
+/// elements as nested lists enclosed by `open` and `close`, such as
+/// `[[[1, 2], [3, 4]], [[5, 6], [7, 8]]]`, with the elements formatted by `format`.
+#[cfg(feature = "matrices")]
+pub(crate) fn nested_list(
+    shape: &[usize],
+    elements: &[Expression],
+    open: &str,
+    close: &str,
+    format: &dyn Fn(&Expression) -> String,
+) -> String {
+    match shape.split_first() {
+        Some((size, shape)) => {
+            let length = shape.iter().product::<usize>();
+
+            format!(
+                "{}{}{}",
+                open,
+                (0..*size)
+                    .map(|i| nested_list(
+                        shape,
+                        &elements[i * length..(i + 1) * length],
+                        open,
+                        close,
+                        format,
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                close,
+            )
+        }
+        None => format(&elements[0]),
+    }
+}
+
+/// Expression formatted with specific options, as returned by [`Expression::display_with`].
+struct DisplayWith<'a> {
+    expression: &'a Expression,
+    options: &'a FormatOptions,
+}
+
+impl Display for DisplayWith<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.expression.fmt_with(f, self.options)
+    }
+}
+
 impl Expression {
+    /// Returns an object that formats the expression using the given options
+    /// instead of the defaults used by the `Display` implementation.
+    pub fn display_with<'a>(&'a self, options: &'a FormatOptions) -> impl Display + 'a {
+        DisplayWith {
+            expression: self,
+            options,
+        }
+    }
+
+    /// Returns `true` if the expression is a number that, when formatted
+    /// with the given options, can precede another factor without a
+    /// multiplication operator in between.
+    fn is_coefficient(&self, options: &FormatOptions) -> bool {
+        use crate::expression::Expression::*;
+
+        matches!(self, Integer(_) | Rational(_, _))
+            && self.precedence_and_associativity_with(options).0 == isize::MAX
+    }
+
+    /// Returns `true` if the expression can follow a coefficient
+    /// without a multiplication operator in between.
+    fn is_implicit_factor(&self) -> bool {
+        use crate::expression::Expression::*;
+
+        match self {
+            Variable(_) => true,
+            FunctionValue(function, _) | Power(function, _) => {
+                matches!(**function, Variable(_))
+            }
+            _ => false,
+        }
+    }
+
     /// Formats the expression as a unary prefix operator with the minimally necessary parentheses.
-    fn fmt_prefix(&self, f: &mut Formatter<'_>, symbol: &str, a: &Self) -> Result {
-        let a_needs_parentheses = a.precedence() <= self.precedence();
+    fn fmt_prefix(
+        &self,
+        f: &mut Formatter<'_>,
+        options: &FormatOptions,
+        symbol: &str,
+        a: &Self,
+    ) -> Result {
+        let precedence =
+            |expression: &Self| expression.precedence_and_associativity_with(options).0;
+
+        let a_needs_parentheses = precedence(a) <= precedence(self);
 
         write!(
             f,
             "{}{}{}{}",
             symbol,
             if a_needs_parentheses { "(" } else { "" },
-            a,
+            a.display_with(options),
             if a_needs_parentheses { ")" } else { "" },
         )
     }
 
     /// Formats the expression as a binary infix operator with the minimally necessary parentheses.
-    fn fmt_infix(&self, f: &mut Formatter<'_>, symbol: &str, a: &Self, b: &Self) -> Result {
+    fn fmt_infix(
+        &self,
+        f: &mut Formatter<'_>,
+        options: &FormatOptions,
+        symbol: &str,
+        a: &Self,
+        b: &Self,
+    ) -> Result {
         use crate::expression::Associativity::*;
 
-        let a_needs_parentheses = (a.precedence() < self.precedence())
-            || ((a.precedence() == self.precedence())
-                && (self.associativity() == RightAssociative));
+        let (precedence, associativity) = self.precedence_and_associativity_with(options);
+        let a_precedence = a.precedence_and_associativity_with(options).0;
+        let b_precedence = b.precedence_and_associativity_with(options).0;
 
-        let b_needs_parentheses = (b.precedence() < self.precedence())
-            || ((b.precedence() == self.precedence()) && (self.associativity() == LeftAssociative));
+        let a_needs_parentheses = (a_precedence < precedence)
+            || ((a_precedence == precedence) && (associativity == RightAssociative));
+
+        let b_needs_parentheses = (b_precedence < precedence)
+            || ((b_precedence == precedence) && (associativity == LeftAssociative));
 
         write!(
             f,
             "{}{}{} {} {}{}{}",
             if a_needs_parentheses { "(" } else { "" },
-            a,
+            a.display_with(options),
             if a_needs_parentheses { ")" } else { "" },
             symbol,
             if b_needs_parentheses { "(" } else { "" },
-            b,
+            b.display_with(options),
             if b_needs_parentheses { ")" } else { "" },
         )
     }
-}
 
-impl Display for Expression {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        use crate::expression::{Expression::*, RationalRepresentation::*};
+    /// Formats the expression using the given options.
+    fn fmt_with(&self, f: &mut Formatter<'_>, options: &FormatOptions) -> Result {
+        use crate::expression::Expression::*;
+
+        let join = |expressions: &mut dyn Iterator<Item = &Self>| {
+            expressions
+                .map(|expression| expression.display_with(options).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
 
         match self {
             Variable(identifier) => write!(f, "{}", identifier),
             Function(identifier, _) => write!(f, "{}", identifier),
             FunctionValue(function, arguments) => {
-                let function_needs_parentheses = function.precedence() < isize::MAX;
+                let function_needs_parentheses =
+                    function.precedence_and_associativity_with(options).0 < isize::MAX;
 
                 write!(
                     f,
                     "{}{}{}({})",
                     if function_needs_parentheses { "(" } else { "" },
-                    function,
+                    function.display_with(options),
                     if function_needs_parentheses { ")" } else { "" },
-                    arguments
-                        .iter()
-                        .map(|a| a.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", "),
+                    join(&mut arguments.iter()),
                 )
             }
-            Integer(n) => write!(f, "{}", n),
+            Integer(n) => write!(f, "{}", localize_number(&n.to_string(), &options.locale)),
             Rational(x, representation) => {
-                match representation {
-                    Fraction => write!(f, "{}", x),
-                    Decimal => {
-                        if let Some((mantissa, separator_position)) = decimal_representation(x) {
-                            let mut string = mantissa.abs().to_string();
-
-                            if separator_position > 0 {
-                                if separator_position > string.len() - 1 {
-                                    // Left-pad the string with enough zeros to be able
-                                    // to insert the decimal separator at the indicated position.
-                                    string = format!(
-                                        "{}{}",
-                                        "0".repeat(separator_position - (string.len() - 1)),
-                                        string,
-                                    );
-                                }
-
-                                string.insert(string.len() - separator_position, '.');
-                            }
-
-                            write!(f, "{}{}", if x.is_negative() { "-" } else { "" }, string)
-                        } else {
-                            // Fall back to fraction representation.
-                            write!(f, "{}", x)
-                        }
-                    }
-                }
+                write!(f, "{}", rational_to_string(x, *representation, options))
             }
             Complex(z, representation) => {
+                let r = |x: &crate::expression::Rational| {
+                    rational_to_string(x, *representation, options)
+                };
+
                 if z.im.is_zero() {
-                    write!(f, "{}", Rational(z.re.clone(), *representation))
+                    write!(f, "{}", r(&z.re))
                 } else if z.re.is_zero() {
                     if z.im.abs().is_one() {
                         write!(f, "{}i", if z.im.is_negative() { "-" } else { "" })
                     } else {
-                        write!(f, "{}*i", Rational(z.im.clone(), *representation))
+                        write!(f, "{}*i", r(&z.im))
                     }
                 } else if z.re.is_negative() && z.im.is_positive() {
                     if z.im.is_one() {
-                        write!(f, "i - {}", Rational(z.re.abs(), *representation))
+                        write!(f, "i - {}", r(&z.re.abs()))
                     } else {
-                        write!(
-                            f,
-                            "{}*i - {}",
-                            Rational(z.im.clone(), *representation),
-                            Rational(z.re.abs(), *representation),
-                        )
+                        write!(f, "{}*i - {}", r(&z.im), r(&z.re.abs()))
                     }
                 } else if z.im.abs().is_one() {
                     write!(
                         f,
                         "{} {} i",
-                        Rational(z.re.clone(), *representation),
+                        r(&z.re),
                         if z.im.is_negative() { "-" } else { "+" },
                     )
                 } else {
                     write!(
                         f,
                         "{} {} {}*i",
-                        Rational(z.re.clone(), *representation),
+                        r(&z.re),
                         if z.im.is_negative() { "-" } else { "+" },
-                        Rational(z.im.abs(), *representation),
+                        r(&z.im.abs()),
                     )
                 }
             }
-            Vector(v) => write!(
-                f,
-                "[{}]",
-                v.iter()
-                    .map(|element| element.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", "),
-            ),
+            #[cfg(feature = "matrices")]
+            Vector(v) => write!(f, "[{}]", join(&mut v.iter())),
+            #[cfg(feature = "matrices")]
             VectorElement(vector, i) => {
-                let vector_needs_parentheses = vector.precedence() < isize::MAX;
+                let vector_needs_parentheses =
+                    vector.precedence_and_associativity_with(options).0 < isize::MAX;
 
                 write!(
                     f,
                     "{}{}{}[{}]",
                     if vector_needs_parentheses { "(" } else { "" },
-                    vector,
+                    vector.display_with(options),
                     if vector_needs_parentheses { ")" } else { "" },
-                    i,
+                    i.display_with(options),
                 )
             }
-            Matrix(m) => write!(
-                f,
-                "[{}]",
-                m.row_iter()
-                    .map(|row| format!(
+            #[cfg(feature = "matrices")]
+            Matrix(m) => match options.matrix_layout {
+                MatrixLayout::Inline => write!(
+                    f,
+                    "[{}]",
+                    m.row_iter()
+                        .map(|row| format!("[{}]", join(&mut row.iter())))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                MatrixLayout::Rows => {
+                    let elements = m.map(|element| element.display_with(options).to_string());
+
+                    let widths = elements
+                        .column_iter()
+                        .map(|column| column.iter().map(|e| e.chars().count()).max().unwrap_or(0))
+                        .collect::<Vec<_>>();
+
+                    write!(
+                        f,
                         "[{}]",
-                        row.iter()
-                            .map(|element| element.to_string())
+                        elements
+                            .row_iter()
+                            .map(|row| format!(
+                                "[{}]",
+                                row.iter()
+                                    .zip(&widths)
+                                    .map(|(element, width)| format!(
+                                        "{:>width$}",
+                                        element,
+                                        width = width,
+                                    ))
+                                    .collect::<Vec<_>>()
+                                    .join(", "),
+                            ))
                             .collect::<Vec<_>>()
-                            .join(", "),
-                    ))
-                    .collect::<Vec<_>>()
-                    .join(", "),
-            ),
+                            .join(",\n "),
+                    )
+                }
+            },
+            #[cfg(feature = "matrices")]
             MatrixElement(matrix, i, j) => {
-                let matrix_needs_parentheses = matrix.precedence() < isize::MAX;
+                let matrix_needs_parentheses =
+                    matrix.precedence_and_associativity_with(options).0 < isize::MAX;
 
                 write!(
                     f,
                     "{}{}{}[{}, {}]",
                     if matrix_needs_parentheses { "(" } else { "" },
-                    matrix,
+                    matrix.display_with(options),
                     if matrix_needs_parentheses { ")" } else { "" },
-                    i,
-                    j,
+                    i.display_with(options),
+                    j.display_with(options),
+                )
+            }
+            #[cfg(feature = "matrices")]
+            Tensor(t) => write!(
+                f,
+                "{}",
+                nested_list(t.shape(), t.elements(), "[", "]", &|element| element
+                    .display_with(options)
+                    .to_string()),
+            ),
+            #[cfg(feature = "matrices")]
+            TensorElement(tensor, indices) => {
+                let tensor_needs_parentheses =
+                    tensor.precedence_and_associativity_with(options).0 < isize::MAX;
+
+                write!(
+                    f,
+                    "{}{}{}[{}]",
+                    if tensor_needs_parentheses { "(" } else { "" },
+                    tensor.display_with(options),
+                    if tensor_needs_parentheses { ")" } else { "" },
+                    join(&mut indices.iter()),
                 )
             }
+            Quaternion(a, b, c, d) => write!(
+                f,
+                "quaternion({})",
+                join(&mut [a, b, c, d].iter().map(|e| &***e)),
+            ),
+            Mod(n, m) => self.fmt_infix(f, options, "mod", n, m),
+            Interval(a, b) => write!(f, "interval({})", join(&mut [a, b].iter().map(|e| &***e))),
             Boolean(boolean) => write!(f, "{}", boolean),
-            Negation(a) => self.fmt_prefix(f, "-", a),
-            Not(a) => self.fmt_prefix(f, "!", a),
-            Sum(a, b) => self.fmt_infix(f, "+", a, b),
-            Difference(a, b) => self.fmt_infix(f, "-", a, b),
-            Product(a, b) => self.fmt_infix(f, "*", a, b),
-            Quotient(a, b) => self.fmt_infix(f, "/", a, b),
-            Remainder(a, b) => self.fmt_infix(f, "%", a, b),
-            Power(a, b) => self.fmt_infix(f, "^", a, b),
-            Equal(a, b) => self.fmt_infix(f, "==", a, b),
-            NotEqual(a, b) => self.fmt_infix(f, "!=", a, b),
-            LessThan(a, b) => self.fmt_infix(f, "<", a, b),
-            LessThanOrEqual(a, b) => self.fmt_infix(f, "<=", a, b),
-            GreaterThan(a, b) => self.fmt_infix(f, ">", a, b),
-            GreaterThanOrEqual(a, b) => self.fmt_infix(f, ">=", a, b),
-            And(a, b) => self.fmt_infix(f, "&&", a, b),
-            Or(a, b) => self.fmt_infix(f, "||", a, b),
+            String(string) => write!(f, "{}", quote_string(string)),
+            Negation(a) => self.fmt_prefix(f, options, "-", a),
+            Not(a) => self.fmt_prefix(f, options, "!", a),
+            Sum(a, b) => self.fmt_infix(f, options, "+", a, b),
+            Difference(a, b) => self.fmt_infix(f, options, "-", a, b),
+            Product(a, b)
+                if options.implicit_multiplication
+                    && a.is_coefficient(options)
+                    && b.is_implicit_factor() =>
+            {
+                write!(f, "{}{}", a.display_with(options), b.display_with(options))
+            }
+            Product(a, b) => self.fmt_infix(f, options, "*", a, b),
+            Quotient(a, b) => self.fmt_infix(f, options, "/", a, b),
+            Remainder(a, b) => self.fmt_infix(f, options, "%", a, b),
+            Power(a, b) => self.fmt_infix(f, options, "^", a, b),
+            Equal(a, b) => self.fmt_infix(f, options, "==", a, b),
+            NotEqual(a, b) => self.fmt_infix(f, options, "!=", a, b),
+            LessThan(a, b) => self.fmt_infix(f, options, "<", a, b),
+            LessThanOrEqual(a, b) => self.fmt_infix(f, options, "<=", a, b),
+            GreaterThan(a, b) => self.fmt_infix(f, options, ">", a, b),
+            GreaterThanOrEqual(a, b) => self.fmt_infix(f, options, ">=", a, b),
+            And(a, b) => self.fmt_infix(f, options, "&&", a, b),
+            Or(a, b) => self.fmt_infix(f, options, "||", a, b),
+            Lambda(parameters, body) => write!(
+                f,
+                "{} -> {}",
+                parameters_to_string(parameters),
+                body.display_with(options),
+            ),
         }
     }
 }
 
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.fmt_with(f, &FormatOptions::default())
+    }
+}
+
+/// Returns the parameter list of a lambda expression as it is written before the arrow,
+/// which is the identifier itself for a single parameter and a parenthesized list otherwise.
+fn parameters_to_string(parameters: &[String]) -> String {
+    match parameters {
+        [parameter] => parameter.clone(),
+        _ => format!("({})", parameters.join(", ")),
+    }
+}
+
+/// Returns the LaTeX representation of the given identifier.
+fn identifier_to_latex(identifier: &str) -> String {
+    if identifier == "pi" {
+        "\\pi".to_owned()
+    } else if identifier.chars().count() == 1 {
+        identifier.to_owned()
+    } else {
+        format!("\\mathrm{{{}}}", identifier.replace('_', "\\_"))
+    }
+}
+
+/// Returns the LaTeX representation of the given rational number,
+/// using the given representation.
+fn rational_to_latex(x: &Rational, representation: RationalRepresentation) -> String {
+    let string = Expression::Rational(x.clone(), representation).to_string();
+
+    if string.contains('/') {
+        format!(
+            "{}\\frac{{{}}}{{{}}}",
+            if x.is_negative() { "-" } else { "" },
+            x.numer().abs(),
+            x.denom(),
+        )
+    } else {
+        string
+    }
+}
+
+/// Returns the LaTeX representation of the given expression,
+/// enclosed in parentheses if `parenthesize` is `true`.
+fn parenthesize_latex(expression: &Expression, parenthesize: bool) -> String {
+    if parenthesize {
+        format!("\\left({}\\right)", expression.to_latex())
+    } else {
+        expression.to_latex()
+    }
+}
+
+impl Expression {
+    /// Returns the expression formatted as a unary prefix operator
+    /// in LaTeX, with the minimally necessary parentheses.
+    fn prefix_to_latex(&self, symbol: &str, a: &Self) -> String {
+        if a.precedence() <= self.precedence() {
+            format!("{}\\left({}\\right)", symbol, a.to_latex())
+        } else {
+            format!("{}{}", symbol, a.to_latex())
+        }
+    }
+
+    /// Returns the expression formatted as a binary infix operator
+    /// in LaTeX, with the minimally necessary parentheses.
+    fn infix_to_latex(&self, symbol: &str, a: &Self, b: &Self) -> String {
+        use crate::expression::Associativity::*;
+
+        let a_needs_parentheses = (a.precedence() < self.precedence())
+            || ((a.precedence() == self.precedence())
+                && (self.associativity() == RightAssociative));
+
+        let b_needs_parentheses = (b.precedence() < self.precedence())
+            || ((b.precedence() == self.precedence()) && (self.associativity() == LeftAssociative));
+
+        format!(
+            "{} {} {}",
+            parenthesize_latex(a, a_needs_parentheses),
+            symbol,
+            parenthesize_latex(b, b_needs_parentheses),
+        )
+    }
+
+    /// Returns the LaTeX representation of the expression, for use in math mode.
+    pub fn to_latex(&self) -> String {
+        use crate::expression::Expression::*;
+
+        let arguments_to_latex = |arguments: &[Self]| {
+            format!(
+                "\\left({}\\right)",
+                arguments
+                    .iter()
+                    .map(Self::to_latex)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        };
+
+        match self {
+            Variable(identifier) => identifier_to_latex(identifier),
+            Function(identifier, _) => {
+                format!("\\operatorname{{{}}}", identifier.replace('_', "\\_"))
+            }
+            FunctionValue(function, arguments) => {
+                let function = match &**function {
+                    Variable(identifier) if identifier.chars().count() > 1 => {
+                        format!("\\operatorname{{{}}}", identifier.replace('_', "\\_"))
+                    }
+                    function => parenthesize_latex(function, function.precedence() < isize::MAX),
+                };
+
+                format!("{}{}", function, arguments_to_latex(arguments))
+            }
+            Integer(n) => n.to_string(),
+            Rational(x, representation) => rational_to_latex(x, *representation),
+            Complex(z, representation) => {
+                let re = rational_to_latex(&z.re, *representation);
+                let im = |x: &crate::expression::Rational| {
+                    if x.is_one() {
+                        "i".to_owned()
+                    } else {
+                        format!("{} i", rational_to_latex(x, *representation))
+                    }
+                };
+
+                if z.im.is_zero() {
+                    re
+                } else if z.re.is_zero() {
+                    format!(
+                        "{}{}",
+                        if z.im.is_negative() { "-" } else { "" },
+                        im(&z.im.abs())
+                    )
+                } else if z.re.is_negative() && z.im.is_positive() {
+                    format!(
+                        "{} - {}",
+                        im(&z.im),
+                        rational_to_latex(&z.re.abs(), *representation)
+                    )
+                } else {
+                    format!(
+                        "{} {} {}",
+                        re,
+                        if z.im.is_negative() { "-" } else { "+" },
+                        im(&z.im.abs()),
+                    )
+                }
+            }
+            #[cfg(feature = "matrices")]
+            Vector(v) => format!(
+                "\\begin{{pmatrix}} {} \\end{{pmatrix}}",
+                v.iter()
+                    .map(Self::to_latex)
+                    .collect::<Vec<_>>()
+                    .join(" \\\\ "),
+            ),
+            #[cfg(feature = "matrices")]
+            VectorElement(vector, i) => format!(
+                "{}_{{{}}}",
+                parenthesize_latex(vector, vector.precedence() < isize::MAX),
+                i.to_latex(),
+            ),
+            #[cfg(feature = "matrices")]
+            Matrix(m) => format!(
+                "\\begin{{pmatrix}} {} \\end{{pmatrix}}",
+                m.row_iter()
+                    .map(|row| row
+                        .iter()
+                        .map(Self::to_latex)
+                        .collect::<Vec<_>>()
+                        .join(" & "))
+                    .collect::<Vec<_>>()
+                    .join(" \\\\ "),
+            ),
+            #[cfg(feature = "matrices")]
+            MatrixElement(matrix, i, j) => format!(
+                "{}_{{{}, {}}}",
+                parenthesize_latex(matrix, matrix.precedence() < isize::MAX),
+                i.to_latex(),
+                j.to_latex(),
+            ),
+            #[cfg(feature = "matrices")]
+            Tensor(t) => nested_list(
+                t.shape(),
+                t.elements(),
+                "\\left[",
+                "\\right]",
+                &Self::to_latex,
+            ),
+            #[cfg(feature = "matrices")]
+            TensorElement(tensor, indices) => format!(
+                "{}_{{{}}}",
+                parenthesize_latex(tensor, tensor.precedence() < isize::MAX),
+                indices
+                    .iter()
+                    .map(Self::to_latex)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Quaternion(a, b, c, d) => format!(
+                "\\operatorname{{quaternion}}{}",
+                arguments_to_latex(&[(**a).clone(), (**b).clone(), (**c).clone(), (**d).clone()]),
+            ),
+            Mod(n, m) => self.infix_to_latex("\\bmod", n, m),
+            Interval(a, b) => format!("\\left[{}, {}\\right]", a.to_latex(), b.to_latex()),
+            Boolean(boolean) => format!("\\mathrm{{{}}}", boolean),
+            String(string) => format!(
+                "\\text{{``{}''}}",
+                string
+                    .chars()
+                    .map(|c| match c {
+                        '\\' => "\\textbackslash{}".to_owned(),
+                        '~' => "\\textasciitilde{}".to_owned(),
+                        '^' => "\\textasciicircum{}".to_owned(),
+                        '{' | '}' | '$' | '&' | '#' | '_' | '%' => format!("\\{}", c),
+                        c => c.to_string(),
+                    })
+                    .collect::<std::string::String>(),
+            ),
+            Negation(a) => self.prefix_to_latex("-", a),
+            Not(a) => self.prefix_to_latex("\\neg ", a),
+            Sum(a, b) => self.infix_to_latex("+", a, b),
+            Difference(a, b) => self.infix_to_latex("-", a, b),
+            Product(a, b) => self.infix_to_latex("\\cdot", a, b),
+            Quotient(a, b) => format!("\\frac{{{}}}{{{}}}", a.to_latex(), b.to_latex()),
+            Remainder(a, b) => self.infix_to_latex("\\mathbin{\\%}", a, b),
+            Power(a, b) => format!(
+                "{{{}}}^{{{}}}",
+                parenthesize_latex(a, a.precedence() <= self.precedence()),
+                b.to_latex(),
+            ),
+            Equal(a, b) => self.infix_to_latex("=", a, b),
+            NotEqual(a, b) => self.infix_to_latex("\\neq", a, b),
+            LessThan(a, b) => self.infix_to_latex("<", a, b),
+            LessThanOrEqual(a, b) => self.infix_to_latex("\\leq", a, b),
+            GreaterThan(a, b) => self.infix_to_latex(">", a, b),
+            GreaterThanOrEqual(a, b) => self.infix_to_latex("\\geq", a, b),
+            And(a, b) => self.infix_to_latex("\\land", a, b),
+            Or(a, b) => self.infix_to_latex("\\lor", a, b),
+            Lambda(parameters, body) => format!(
+                "{} \\mapsto {}",
+                match parameters.as_slice() {
+                    [parameter] => identifier_to_latex(parameter),
+                    _ => format!(
+                        "\\left({}\\right)",
+                        parameters
+                            .iter()
+                            .map(|parameter| identifier_to_latex(parameter))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                },
+                body.to_latex(),
+            ),
+        }
+    }
+}
+
+impl Expression {
+    /// Returns the label of the node representing the expression
+    /// in the DOT representation of an expression tree.
+    fn dot_label(&self) -> String {
+        use crate::expression::Expression::*;
+
+        match self {
+            Variable(identifier) | Function(identifier, _) => identifier.clone(),
+            FunctionValue(_, _) => "call".to_owned(),
+            Integer(_) | Rational(_, _) | Complex(_, _) | Boolean(_) | String(_) => {
+                self.to_string()
+            }
+            #[cfg(feature = "matrices")]
+            Vector(v) => format!("vector ({})", v.len()),
+            #[cfg(feature = "matrices")]
+            VectorElement(_, _) => "[i]".to_owned(),
+            #[cfg(feature = "matrices")]
+            Matrix(m) => format!("matrix ({}x{})", m.nrows(), m.ncols()),
+            #[cfg(feature = "matrices")]
+            MatrixElement(_, _, _) => "[i, j]".to_owned(),
+            #[cfg(feature = "matrices")]
+            Tensor(t) => format!(
+                "tensor ({})",
+                t.shape()
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join("x"),
+            ),
+            #[cfg(feature = "matrices")]
+            TensorElement(_, indices) => format!("[{}]", vec!["i"; indices.len()].join(", ")),
+            Quaternion(_, _, _, _) => "quaternion".to_owned(),
+            Interval(_, _) => "interval".to_owned(),
+            Lambda(parameters, _) => format!("{} ->", parameters_to_string(parameters)),
+            _ => operator(self).unwrap().to_owned(),
+        }
+    }
+
+    /// Appends the DOT statements describing the nodes and edges of the expression tree
+    /// to `statements`, numbering nodes starting at `next_id`, and returns the ID
+    /// of the node representing the expression.
+    fn add_dot_statements(&self, statements: &mut Vec<String>, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        statements.push(format!(
+            "n{} [label=\"{}\"];",
+            id,
+            self.dot_label().replace('\\', "\\\\").replace('"', "\\\""),
+        ));
+
+        let children = match self {
+            // Matrix elements are stored in column-major order,
+            // but reading them row by row is more natural.
+            #[cfg(feature = "matrices")]
+            Expression::Matrix(m) => m.transpose().iter().cloned().collect::<Vec<_>>(),
+            _ => self.children().into_iter().cloned().collect::<Vec<_>>(),
+        };
+
+        for child in &children {
+            let child_id = child.add_dot_statements(statements, next_id);
+            statements.push(format!("n{} -> n{};", id, child_id));
+        }
+
+        id
+    }
+
+    /// Returns a description of the expression tree in the DOT language
+    /// used by Graphviz, with one node for each subexpression.
+    pub fn to_dot(&self) -> String {
+        let mut statements = Vec::new();
+        self.add_dot_statements(&mut statements, &mut 0);
+
+        format!(
+            "digraph expression {{\n{}}}\n",
+            statements
+                .iter()
+                .map(|statement| format!("    {}\n", statement))
+                .collect::<String>(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "matrices")]
     use nalgebra::{dmatrix, dvector};
 
     use crate::expression::{Expression, Expression::*};
@@ -269,6 +1147,21 @@ mod tests {
         );
         t(fun(fun(var("f"), [var("a")]), [var("b")]), "(f(a))(b)");
         t(fun(var("f") + var("g"), [var("a")]), "(f + g)(a)");
+
+        let l = |parameters: &[&str], body| {
+            Lambda(
+                parameters.iter().map(|p| p.to_string()).collect(),
+                Box::new(body),
+            )
+        };
+
+        t(l(&["x"], pow(var("x"), int(2))), "x -> x ^ 2");
+        t(
+            l(&["x", "y"], l(&[], var("x") * var("y"))),
+            "(x, y) -> () -> x * y",
+        );
+        t(fun(l(&["x"], var("x")), [int(1)]), "(x -> x)(1)");
+        t(l(&["x"], var("x")) + int(1), "(x -> x) + 1");
     }
 
     #[test]
@@ -332,6 +1225,7 @@ mod tests {
         t(comd(1, 3, 123, 40), "1/3 + 3.075*i");
     }
 
+    #[cfg(feature = "matrices")]
     #[test]
     fn vectors() {
         t(Vector(dvector![]), "[]");
@@ -347,6 +1241,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "matrices")]
     #[test]
     fn matrices() {
         t(Matrix(dmatrix![]), "[]");
@@ -368,12 +1263,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn quaternions() {
+        t(
+            quat(int(1), int(2), int(3), int(4)),
+            "quaternion(1, 2, 3, 4)",
+        );
+        t(
+            quat(var("a"), -var("b"), rat(1, 2), int(0) + int(1)),
+            "quaternion(a, -b, 1/2, 0 + 1)",
+        );
+    }
+
     #[test]
     fn booleans() {
         t(Boolean(true), "true");
         t(Boolean(false), "false");
     }
 
+    #[test]
+    fn strings() {
+        t(string(""), "\"\"");
+        t(string("data.csv"), "\"data.csv\"");
+        t(string("a \"b\" \\ c\n\td"), r#""a \"b\" \\ c\n\td""#);
+    }
+
     #[test]
     fn operators() {
         t(-int(1), "-1");
@@ -421,6 +1335,13 @@ mod tests {
         t(com(1, 1, -1, 1) - int(2), "1 - i - 2");
         t(int(2) - com(1, 1, -1, 1), "2 - (1 - i)");
 
+        t(modulo(int(5), int(7)), "5 mod 7");
+        t(modulo(int(1) + int(2), int(3) * int(4)), "1 + 2 mod 3 * 4");
+        t(modulo(modulo(int(1), int(2)), int(3)), "1 mod 2 mod 3");
+        t(modulo(int(1), modulo(int(2), int(3))), "1 mod (2 mod 3)");
+        t(eq(modulo(var("A"), int(2)), int(1)), "A mod 2 == 1");
+        t(modulo(eq(var("A"), int(2)), int(1)), "(A == 2) mod 1");
+
         // TODO: Comparison operators!
 
         t(and(and(var("A"), var("B")), var("C")), "A && B && C");
@@ -432,4 +1353,265 @@ mod tests {
         t(or(var("A"), and(var("B"), var("C"))), "A || B && C");
         t(or(var("A"), or(var("B"), var("C"))), "A || B || C");
     }
+
+    #[test]
+    fn format_options() {
+        use crate::expression::RationalRepresentation::*;
+        use crate::print::{FormatOptions, Locale, Notation, RepeatingDecimals};
+
+        let f = |expression: Expression, options: &FormatOptions, string: &str| {
+            assert_eq!(expression.display_with(options).to_string(), string);
+        };
+
+        let default = FormatOptions::default();
+
+        f(ratd(1, 3) * var("x"), &default, "1/3 * x");
+        #[cfg(feature = "matrices")]
+        f(
+            Matrix(dmatrix![int(1), int(2); int(3), int(4)]),
+            &default,
+            "[[1, 2], [3, 4]]",
+        );
+
+        let options = FormatOptions {
+            rational_representation: Some(Decimal),
+            ..FormatOptions::default()
+        };
+
+        f(rat(3, 2), &options, "1.5");
+        f(com(1, 2, -1, 4), &options, "0.5 - 0.25*i");
+        f(pow(rat(1, 2), int(3)), &options, "0.5 ^ 3");
+
+        let options = FormatOptions {
+            rational_representation: Some(Fraction),
+            ..FormatOptions::default()
+        };
+
+        f(ratd(3, 2), &options, "3/2");
+        f(pow(ratd(1, 2), int(3)), &options, "(1/2) ^ 3");
+
+        let options = FormatOptions {
+            max_decimal_digits: Some(3),
+            ..FormatOptions::default()
+        };
+
+        f(ratd(1, 3), &options, "0.333");
+        f(ratd(-2, 3), &options, "-0.667");
+        f(ratd(1, 8), &options, "0.125");
+        f(ratd(1, 16), &options, "0.063");
+        f(ratd(1, 4000), &options, "0");
+        f(ratd(2001, 2000), &options, "1.001");
+        f(ratd(20001, 20000), &options, "1");
+        f(rat(1, 3), &options, "1/3");
+
+        let options = FormatOptions {
+            repeating_decimals: RepeatingDecimals::Parentheses,
+            ..FormatOptions::default()
+        };
+
+        f(ratd(1, 3), &options, "0.(3)");
+        f(ratd(-1, 6), &options, "-0.1(6)");
+        f(ratd(22, 7), &options, "3.(142857)");
+        f(ratd(1, 8), &options, "0.125");
+        f(rat(1, 3), &options, "1/3");
+        f(comd(0, 1, 1, 3), &options, "0.(3)*i");
+        f(ratd(1, 7919), &options, "1/7919");
+
+        let options = FormatOptions {
+            max_decimal_digits: Some(4),
+            repeating_decimals: RepeatingDecimals::Parentheses,
+            ..FormatOptions::default()
+        };
+
+        f(ratd(1, 12), &options, "0.08(3)");
+        f(ratd(22, 7), &options, "3.1429");
+
+        let options = FormatOptions {
+            repeating_decimals: RepeatingDecimals::Overline,
+            ..FormatOptions::default()
+        };
+
+        f(ratd(5, 6), &options, "0.83\u{305}");
+        f(ratd(1, 11), &options, "0.0\u{305}9\u{305}");
+
+        let options = FormatOptions {
+            notation: Notation::Scientific,
+            ..FormatOptions::default()
+        };
+
+        f(ratd(12500, 1), &options, "1.25e4");
+        f(ratd(-3, 2), &options, "-1.5");
+        f(ratd(3, 400), &options, "7.5e-3");
+        f(ratd(0, 1), &options, "0");
+        f(ratd(1, 3), &options, "1/3");
+        f(rat(12500, 1), &options, "12500");
+        f(int(12500), &options, "12500");
+
+        let options = FormatOptions {
+            notation: Notation::Engineering,
+            ..FormatOptions::default()
+        };
+
+        f(ratd(12500, 1), &options, "12.5e3");
+        f(ratd(125, 1), &options, "125");
+        f(ratd(1000, 1), &options, "1e3");
+        f(ratd(1, 2), &options, "500e-3");
+        f(ratd(-1, 20000), &options, "-50e-6");
+        f(comd(1500, 1, 1, 1000), &options, "1.5e3 + 1e-3*i");
+
+        let options = FormatOptions {
+            max_decimal_digits: Some(2),
+            notation: Notation::Engineering,
+            ..FormatOptions::default()
+        };
+
+        f(ratd(1, 3), &options, "333.33e-3");
+        f(ratd(999999, 1000), &options, "1e3");
+        f(ratd(123456789, 1), &options, "123.46e6");
+
+        let options = FormatOptions {
+            locale: Locale {
+                decimal_separator: ',',
+                digit_group_separator: Some('.'),
+            },
+            ..FormatOptions::default()
+        };
+
+        f(int(123), &options, "123");
+        f(int(-1234567), &options, "-1.234.567");
+        f(ratd(123456, 100), &options, "1.234,56");
+        f(ratd(1, 20000), &options, "0,00005");
+        f(rat(-12345, 1001), &options, "-12.345/1.001");
+        f(
+            fun(var("f"), [ratd(3, 2), int(1000)]),
+            &options,
+            "f(1,5, 1.000)",
+        );
+
+        let options = FormatOptions {
+            notation: Notation::Scientific,
+            repeating_decimals: RepeatingDecimals::Parentheses,
+            locale: Locale {
+                decimal_separator: ',',
+                digit_group_separator: Some(' '),
+            },
+            ..FormatOptions::default()
+        };
+
+        f(ratd(12345, 1), &options, "1,2345e4");
+        f(ratd(10000, 3), &options, "3,(3)e3");
+
+        let options = FormatOptions {
+            implicit_multiplication: true,
+            ..FormatOptions::default()
+        };
+
+        f(int(2) * var("x"), &options, "2x");
+        f(ratd(3, 2) * fun(var("f"), [var("x")]), &options, "1.5f(x)");
+        f(
+            int(3) * pow(var("x"), int(2)) + int(1),
+            &options,
+            "3x ^ 2 + 1",
+        );
+        f(pow(int(2) * var("x"), int(2)), &options, "(2x) ^ 2");
+        f(int(-2) * var("x"), &options, "-2 * x");
+        f(rat(1, 2) * var("x"), &options, "1/2 * x");
+        f(var("x") * int(2), &options, "x * 2");
+        f(int(2) * (var("x") + int(1)), &options, "2 * (x + 1)");
+
+        #[cfg(feature = "matrices")]
+        {
+            let options = FormatOptions {
+                matrix_layout: crate::print::MatrixLayout::Rows,
+                ..FormatOptions::default()
+            };
+
+            f(Matrix(dmatrix![]), &options, "[]");
+            f(
+                Matrix(dmatrix![int(1), int(-20); int(300), var("x")]),
+                &options,
+                "[[  1, -20],\n [300,   x]]",
+            );
+        }
+    }
+
+    #[test]
+    fn dot() {
+        assert_eq!(
+            (-var("x") + fun(var("f"), [rat(1, 2)])).to_dot(),
+            "digraph expression {
+    n0 [label=\"+\"];
+    n1 [label=\"-\"];
+    n2 [label=\"x\"];
+    n1 -> n2;
+    n0 -> n1;
+    n3 [label=\"call\"];
+    n4 [label=\"f\"];
+    n3 -> n4;
+    n5 [label=\"1/2\"];
+    n3 -> n5;
+    n0 -> n3;
+}
+",
+        );
+
+        #[cfg(feature = "matrices")]
+        {
+            let dot = Matrix(dmatrix![int(1), int(2); int(3), int(4)]).to_dot();
+
+            assert!(dot.contains("n0 [label=\"matrix (2x2)\"];"));
+            assert!(dot.contains("n2 [label=\"2\"];"));
+            assert!(dot.contains("n0 -> n4;"));
+        }
+    }
+
+    #[test]
+    fn latex() {
+        let l = |expression: Expression, string: &str| assert_eq!(expression.to_latex(), string);
+
+        l(var("x"), "x");
+        l(var("pi"), "\\pi");
+        l(var("Named_Variable"), "\\mathrm{Named\\_Variable}");
+        l(fun(var("f"), [var("a"), int(1)]), "f\\left(a, 1\\right)");
+        l(
+            fun(var("det"), [var("M")]),
+            "\\operatorname{det}\\left(M\\right)",
+        );
+        l(rat(-1, 2), "-\\frac{1}{2}");
+        l(ratd(3, 2), "1.5");
+        l(com(1, 1, -2, 3), "1 - \\frac{2}{3} i");
+        l(com(0, 1, 1, 1), "i");
+        #[cfg(feature = "matrices")]
+        l(
+            Expression::Vector(dvector![int(1), var("x")]),
+            "\\begin{pmatrix} 1 \\\\ x \\end{pmatrix}",
+        );
+        #[cfg(feature = "matrices")]
+        l(
+            Expression::Matrix(dmatrix![int(1), int(2); int(3), int(4)]),
+            "\\begin{pmatrix} 1 & 2 \\\\ 3 & 4 \\end{pmatrix}",
+        );
+        l((int(1) + var("x")) / int(2), "\\frac{1 + x}{2}");
+        l(
+            pow(var("a") + var("b"), int(2) * var("n")),
+            "{\\left(a + b\\right)}^{2 \\cdot n}",
+        );
+        l(-(var("a") + var("b")), "-\\left(a + b\\right)");
+        l(
+            and(!var("A"), le(var("x"), int(1))),
+            "\\neg A \\land x \\leq 1",
+        );
+        l(modulo(int(5), int(7)), "5 \\bmod 7");
+        l(
+            Lambda(vec!["x".to_owned()], Box::new(pow(var("x"), int(2)))),
+            "x \\mapsto {x}^{2}",
+        );
+        l(
+            Lambda(
+                vec!["x".to_owned(), "y".to_owned()],
+                Box::new(var("x") * var("y")),
+            ),
+            "\\left(x, y\\right) \\mapsto x \\cdot y",
+        );
+    }
 }
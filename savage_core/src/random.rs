@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// Pseudorandom number generator (SplitMix64) behind the random built-in functions.
+/// Not suitable for cryptographic purposes.
+pub(crate) struct Generator {
+    state: Cell<u64>,
+}
+
+impl Generator {
+    /// Returns a generator that produces the sequence determined by the given seed.
+    pub(crate) fn new(seed: u64) -> Self {
+        Generator {
+            state: Cell::new(seed),
+        }
+    }
+
+    /// Returns a generator seeded with a value that is different for each generator.
+    pub(crate) fn from_entropy() -> Self {
+        // The standard library seeds the keys of each `RandomState` randomly.
+        Self::new(RandomState::new().build_hasher().finish())
+    }
+
+    /// Returns the next number in the sequence.
+    #[cfg_attr(not(feature = "functions"), allow(dead_code))]
+    pub(crate) fn next_u64(&self) -> u64 {
+        let state = self.state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        self.state.set(state);
+
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::Generator;
+
+    #[test]
+    fn sequence() {
+        // Reference values from the SplitMix64 reference implementation.
+        let generator = Generator::new(1234567);
+        assert_eq!(generator.next_u64(), 6457827717110365317);
+        assert_eq!(generator.next_u64(), 3203168211198807973);
+
+        let a = Generator::new(42);
+        let b = Generator::new(42);
+        assert_eq!(
+            (0..10).map(|_| a.next_u64()).collect::<Vec<_>>(),
+            (0..10).map(|_| b.next_u64()).collect::<Vec<_>>(),
+        );
+
+        assert_ne!(
+            Generator::from_entropy().next_u64(),
+            Generator::from_entropy().next_u64(),
+        );
+    }
+}
@@ -0,0 +1,412 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+//! Rewriting of expressions using rules with pattern variables.
+
+use std::{collections::HashMap, mem::discriminant, str::FromStr};
+
+use crate::{
+    evaluate::substitute_variables,
+    expression::Expression,
+    parse::{Error, ParseError},
+};
+
+/// Maximum number of passes over an expression when rewriting it,
+/// which bounds the work done for rules that never stop applying.
+const MAX_PASSES: usize = 1000;
+
+/// Values of the pattern variables of a rule, by identifier.
+type Bindings = HashMap<String, Expression>;
+
+/// Rule that replaces sub-expressions matching a pattern with a replacement.
+///
+/// All variables in the pattern (except the names of called functions)
+/// are pattern variables, which match any expression. A pattern variable
+/// that occurs more than once must match equal expressions everywhere,
+/// and its occurrences in the replacement are replaced by the matched expression.
+///
+/// Sums and products are matched regardless of the order of their terms and factors.
+/// A sum or product in a pattern also matches some of the terms or factors of a larger
+/// sum or product, which are then replaced while the others are kept.
+///
+/// Rules can be parsed from strings of the form `pattern => replacement`:
+///
+/// ```
+/// use savage_core::{expression::Expression, rewrite::Rule};
+///
+/// let rule = "sin(x) ^ 2 + cos(x) ^ 2 => 1".parse::<Rule>().unwrap();
+/// let expression = "a + cos(b) ^ 2 + sin(b) ^ 2".parse::<Expression>().unwrap();
+///
+/// assert_eq!(expression.rewrite(&[rule]).to_string(), "a + 1");
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Rule {
+    /// Expression matched by the rule.
+    pub pattern: Expression,
+    /// Expression that replaces the matched expression.
+    pub replacement: Expression,
+}
+
+impl Rule {
+    /// Creates a rule that replaces expressions matching `pattern` with `replacement`.
+    pub fn new(pattern: Expression, replacement: Expression) -> Self {
+        Self {
+            pattern,
+            replacement,
+        }
+    }
+
+    /// Returns the result of applying the rule to the expression itself
+    /// (but not to its sub-expressions), or `None` if the pattern doesn't match it.
+    fn apply(&self, expression: &Expression) -> Option<Expression> {
+        if let Some(operands) = associative_operands(&self.pattern) {
+            // Patterns for sums and products can match some of the operands
+            // of the expression, in which case the other operands are kept.
+            let patterns = operands;
+            let operands = associative_operands(expression)
+                .filter(|_| discriminant(&self.pattern) == discriminant(expression))?;
+
+            let (bindings, matched) = match_operands(&patterns, &operands, &Bindings::new(), true)?;
+
+            let replacement = self.instantiate(&bindings);
+            let first = matched.iter().position(|m| *m).unwrap();
+
+            return operands
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| *i == first || !matched[*i])
+                .map(|(i, operand)| {
+                    if i == first {
+                        replacement.clone()
+                    } else {
+                        operand.clone()
+                    }
+                })
+                .reduce(|a, b| combine(expression, a, b));
+        }
+
+        let mut bindings = Bindings::new();
+
+        if match_pattern(&self.pattern, expression, &mut bindings) {
+            Some(self.instantiate(&bindings))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the replacement with the pattern variables replaced by their values.
+    fn instantiate(&self, bindings: &Bindings) -> Expression {
+        substitute_variables(
+            &self.replacement,
+            &bindings
+                .iter()
+                .map(|(identifier, value)| (identifier.as_str(), value))
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.split_once("=>") {
+            Some((pattern, replacement)) => Ok(Self::new(pattern.parse()?, replacement.parse()?)),
+            None => Err(ParseError {
+                errors: vec![Error::custom(
+                    string.len()..string.len(),
+                    "expected `=>` between pattern and replacement",
+                )],
+                input: string.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Returns the terms of the expression if it is a sum, or its factors if it is a product,
+/// flattening nested sums or products, or `None` if it is neither.
+fn associative_operands(expression: &Expression) -> Option<Vec<&Expression>> {
+    fn collect<'a>(
+        expression: &'a Expression,
+        root: &Expression,
+        operands: &mut Vec<&'a Expression>,
+    ) {
+        match expression {
+            Expression::Sum(a, b) | Expression::Product(a, b)
+                if discriminant(expression) == discriminant(root) =>
+            {
+                collect(a, root, operands);
+                collect(b, root, operands);
+            }
+            _ => operands.push(expression),
+        }
+    }
+
+    match expression {
+        Expression::Sum(_, _) | Expression::Product(_, _) => {
+            let mut operands = Vec::new();
+            collect(expression, expression, &mut operands);
+            Some(operands)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the sum or product (like `operation`) of the expressions.
+fn combine(operation: &Expression, a: Expression, b: Expression) -> Expression {
+    match operation {
+        Expression::Product(_, _) => Expression::Product(Box::new(a), Box::new(b)),
+        _ => Expression::Sum(Box::new(a), Box::new(b)),
+    }
+}
+
+/// Returns the name of the function if the expression is a function
+/// or a variable naming a function.
+fn function_name(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Variable(identifier) | Expression::Function(identifier, _) => Some(identifier),
+        _ => None,
+    }
+}
+
+/// Returns whether the expression matches the pattern, given the values of the pattern
+/// variables that have already been matched, adding the values of newly matched variables.
+/// If the expression doesn't match, the bindings may contain partial results.
+fn match_pattern(pattern: &Expression, expression: &Expression, bindings: &mut Bindings) -> bool {
+    use crate::expression::Expression::*;
+
+    match (pattern, expression) {
+        (Variable(identifier), _) => match bindings.get(identifier) {
+            Some(value) => value == expression,
+            None => {
+                bindings.insert(identifier.clone(), expression.clone());
+                true
+            }
+        },
+        (FunctionValue(f, a), FunctionValue(g, b)) => {
+            let same_function = match (function_name(f), function_name(g)) {
+                (Some(f), Some(g)) => f == g,
+                _ => f == g,
+            };
+
+            same_function
+                && a.len() == b.len()
+                && a.iter().zip(b).all(|(a, b)| match_pattern(a, b, bindings))
+        }
+        (Sum(_, _), Sum(_, _)) | (Product(_, _), Product(_, _)) => {
+            let patterns = associative_operands(pattern).unwrap();
+            let operands = associative_operands(expression).unwrap();
+
+            // If the operands can't be matched one by one, the operands of the pattern
+            // are matched with the two operands of the expression instead, so for example
+            // `x * y` matches `a * b * c` with `x = a * b` and `y = c`.
+            let result = match_operands(&patterns, &operands, bindings, false).or_else(|| {
+                let (a, b) = (pattern.children(), expression.children());
+
+                [(b[0], b[1]), (b[1], b[0])].into_iter().find_map(|(c, d)| {
+                    let mut candidate = bindings.clone();
+
+                    if match_pattern(a[0], c, &mut candidate)
+                        && match_pattern(a[1], d, &mut candidate)
+                    {
+                        Some((candidate, Vec::new()))
+                    } else {
+                        None
+                    }
+                })
+            });
+
+            match result {
+                Some((result, _)) => {
+                    // Only the bindings from the successful assignment are kept,
+                    // as earlier attempts may have bound variables differently.
+                    *bindings = result;
+                    true
+                }
+                None => false,
+            }
+        }
+        _ => {
+            let children = pattern.children();
+
+            if children.is_empty() || matches!(pattern, Lambda(_, _)) {
+                pattern == expression
+            } else {
+                discriminant(pattern) == discriminant(expression)
+                    && children.len() == expression.children().len()
+                    && children
+                        .iter()
+                        .zip(expression.children())
+                        .all(|(a, b)| match_pattern(a, b, bindings))
+            }
+        }
+    }
+}
+
+/// Returns the bindings (extending the given bindings) of a match of each pattern
+/// with a different operand, and which operands were matched, or `None` if there
+/// is no such match. All operands must be matched unless `partial` is `true`.
+fn match_operands(
+    patterns: &[&Expression],
+    operands: &[&Expression],
+    bindings: &Bindings,
+    partial: bool,
+) -> Option<(Bindings, Vec<bool>)> {
+    fn search(
+        patterns: &[&Expression],
+        operands: &[&Expression],
+        used: &mut Vec<bool>,
+        bindings: &Bindings,
+        partial: bool,
+    ) -> Option<(Bindings, Vec<bool>)> {
+        let (pattern, rest) = match patterns.split_first() {
+            Some(split) => split,
+            None if partial || used.iter().all(|u| *u) => {
+                return Some((bindings.clone(), used.clone()))
+            }
+            None => return None,
+        };
+
+        for i in 0..operands.len() {
+            if used[i] {
+                continue;
+            }
+
+            let mut candidate = bindings.clone();
+
+            if match_pattern(pattern, operands[i], &mut candidate) {
+                used[i] = true;
+
+                if let Some(result) = search(rest, operands, used, &candidate, partial) {
+                    return Some(result);
+                }
+
+                used[i] = false;
+            }
+        }
+
+        None
+    }
+
+    search(
+        patterns,
+        operands,
+        &mut vec![false; operands.len()],
+        bindings,
+        partial,
+    )
+}
+
+/// Returns the result of applying the first matching rule to each sub-expression
+/// (from the innermost to the outermost), or `None` if no rule matches.
+fn rewrite_once(expression: &Expression, rules: &[Rule]) -> Option<Expression> {
+    let mut changed = false;
+
+    let expression = expression.map_children(|child| match rewrite_once(child, rules) {
+        Some(child) => {
+            changed = true;
+            child
+        }
+        None => child.clone(),
+    });
+
+    match rules.iter().find_map(|rule| rule.apply(&expression)) {
+        Some(result) if result != expression => Some(result),
+        _ if changed => Some(expression),
+        _ => None,
+    }
+}
+
+impl Expression {
+    /// Returns the result of repeatedly applying the rules to the expression
+    /// and its sub-expressions until none of them match anymore, without evaluating it.
+    /// Rules are tried in the given order, and sub-expressions are rewritten
+    /// before the expressions that contain them. Rules that never stop applying
+    /// (e.g. `x + y => y + x`) are stopped after a fixed number of passes.
+    pub fn rewrite(&self, rules: &[Rule]) -> Self {
+        let mut expression = self.clone();
+
+        for _ in 0..MAX_PASSES {
+            match rewrite_once(&expression, rules) {
+                Some(result) => expression = result,
+                None => break,
+            }
+        }
+
+        expression
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expression::Expression, rewrite::Rule};
+
+    #[track_caller]
+    fn r(rules: &[&str], expression: &str, result: &str) {
+        let rules = rules
+            .iter()
+            .map(|rule| rule.parse::<Rule>().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .rewrite(&rules)
+                .to_string(),
+            result,
+        );
+    }
+
+    #[test]
+    fn rewrite() {
+        let pythagoras = "sin(x) ^ 2 + cos(x) ^ 2 => 1";
+
+        r(&[pythagoras], "sin(a) ^ 2 + cos(a) ^ 2", "1");
+        r(&[pythagoras], "cos(a + b) ^ 2 + sin(a + b) ^ 2", "1");
+        r(
+            &[pythagoras],
+            "c + sin(a) ^ 2 + d + cos(a) ^ 2",
+            "c + 1 + d",
+        );
+        r(
+            &[pythagoras],
+            "sin(a) ^ 2 + cos(b) ^ 2",
+            "sin(a) ^ 2 + cos(b) ^ 2",
+        );
+        r(&[pythagoras], "f(sin(a) ^ 2 + cos(a) ^ 2) * 2", "f(1) * 2");
+
+        r(&["x * 0 => 0", "x + 0 => x"], "a * (b * 0) + 0", "0");
+        r(&["x + x => 2 * x"], "a + b + a", "2 * a + b");
+        r(
+            &["log(x * y) => log(x) + log(y)"],
+            "log(a * b * c)",
+            "log(a) + log(b) + log(c)",
+        );
+        r(&["f(x, x) => x"], "f(a, b) + f(c, c)", "f(a, b) + c");
+        r(
+            &["f(x, y + x) => y"],
+            "f(a, a + b) * f(a, b + c)",
+            "b * f(a, b + c)",
+        );
+        r(&["(a -> a) => 1"], "(a -> a) + (a -> b)", "1 + (a -> b)");
+        r(&["x - y => x + -y"], "(a - b) - c", "a + -b + -c");
+
+        // Rules that always apply are stopped.
+        r(&["x + y => y + x"], "a + b", "a + b");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "x ^ 2 => x * x".parse::<Rule>(),
+            Ok(Rule::new(
+                "x ^ 2".parse().unwrap(),
+                "x * x".parse().unwrap(),
+            )),
+        );
+
+        assert!("x ^ 2".parse::<Rule>().is_err());
+        assert!("x ^ => x".parse::<Rule>().is_err());
+    }
+}
@@ -1,18 +1,108 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 // Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
 
+use std::collections::HashMap;
+
 use crate::{expression::Expression, helpers::*};
 
+#[cfg(feature = "functions")]
+use crate::{expression::Type, functions::function_expression};
+
 impl Expression {
+    /// Returns the number of nodes in the expression tree.
+    pub fn size(&self) -> usize {
+        1 + self.children().into_iter().map(Self::size).sum::<usize>()
+    }
+
+    /// Returns the number of nodes on the longest path
+    /// from the root of the expression tree to a leaf.
+    pub fn depth(&self) -> usize {
+        1 + self
+            .children()
+            .into_iter()
+            .map(Self::depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of operators and function applications in the expression.
+    /// Vectors, matrices, tensors, and quaternions are values rather than operations,
+    /// and only their components are counted.
+    pub fn count_ops(&self) -> usize {
+        use crate::expression::Expression::*;
+
+        let own = match self {
+            _ if self.is_array() => 0,
+            Quaternion(_, _, _, _) => 0,
+            _ if self.children().is_empty() => 0,
+            _ => 1,
+        };
+
+        own + self
+            .children()
+            .into_iter()
+            .map(Self::count_ops)
+            .sum::<usize>()
+    }
+
+    /// Returns the simplest form of the expression, as measured by `size`.
+    /// See `simplify_with_cost` for the forms that are considered.
+    pub fn simplify(&self) -> Self {
+        self.simplify_with_cost(Self::size)
+    }
+
+    /// Returns the form of the expression that minimizes the given cost function.
+    ///
+    /// The forms considered are the expression itself, the result of evaluating it
+    /// (which applies algebraic simplification rules throughout the expression tree),
+    /// and, for boolean expressions, the conjunctive and disjunctive normal forms
+    /// of both. If several forms have the same cost, the earliest one is returned,
+    /// so the expression is only rewritten if that actually reduces its cost.
+    pub fn simplify_with_cost(&self, cost: impl Fn(&Self) -> usize) -> Self {
+        let mut candidates = vec![self.clone()];
+
+        if let Ok(expression) = self.evaluate(HashMap::new()) {
+            candidates.push(expression);
+        }
+
+        #[cfg(feature = "functions")]
+        for expression in candidates.clone() {
+            if let Type::Boolean(_) = expression.typ() {
+                for name in ["to_cnf", "to_dnf"] {
+                    let normal_form = fun(function_expression(name).unwrap(), [expression.clone()]);
+
+                    if let Ok(normal_form) = normal_form.evaluate(HashMap::new()) {
+                        candidates.push(normal_form);
+                    }
+                }
+            }
+        }
+
+        candidates.into_iter().min_by_key(|e| cost(e)).unwrap()
+    }
+
     /// Applies standard algebraic simplification rules to the expression,
     /// and returns the result.
     ///
     /// Note that this function does not itself recurse into sub-expressions;
     /// but since it is called from `evaluate_step`, which *does* recurse,
     /// simplifications are applied to the entire expression tree during evaluation.
-    pub(crate) fn simplify(&self) -> Self {
+    pub(crate) fn simplify_step(&self) -> Self {
         use crate::expression::Expression::*;
 
+        if let Sum(a, b)
+        | Difference(a, b)
+        | Product(a, b)
+        | Quotient(a, b)
+        | Remainder(a, b)
+        | Power(a, b) = self
+        {
+            // The rules below are only valid for scalars.
+            if [a, b].iter().any(|x| x.is_array()) {
+                return self.clone();
+            }
+        }
+
         match self {
             Negation(a) => {
                 if let Negation(a) = &**a {
@@ -113,6 +203,9 @@ impl Expression {
                     int(1)
                 } else if b == int(1) {
                     a
+                } else if a == int(0) && b == int(0) {
+                    // The value depends on the evaluation options.
+                    self.clone()
                 } else if a == int(0) {
                     // FIXME: This is incorrect if `b` evaluates to zero!
                     int(0)
@@ -179,6 +272,8 @@ impl Expression {
 }
 
 #[cfg(test)]
+// Tests that use built-in functions are skipped if those are disabled.
+#[cfg_attr(not(feature = "functions"), allow(unused_imports, dead_code))]
 mod tests {
     use crate::expression::Expression;
 
@@ -188,7 +283,7 @@ mod tests {
             expression
                 .parse::<Expression>()
                 .unwrap()
-                .simplify()
+                .simplify_step()
                 .to_string(),
             result,
         );
@@ -229,6 +324,7 @@ mod tests {
         t("a ^ 1", "a");
         t("0 ^ a", "0");
         t("a ^ 0", "1");
+        t("0 ^ 0", "0 ^ 0");
     }
 
     #[test]
@@ -261,4 +357,49 @@ mod tests {
         t("a > a", "false");
         t("a >= a", "true");
     }
+
+    #[track_caller]
+    fn c(expression: &str, size: usize, depth: usize, count_ops: usize) {
+        let expression = expression.parse::<Expression>().unwrap();
+
+        assert_eq!(expression.size(), size);
+        assert_eq!(expression.depth(), depth);
+        assert_eq!(expression.count_ops(), count_ops);
+    }
+
+    #[test]
+    fn complexity() {
+        c("a", 1, 1, 0);
+        c("a + b * c", 5, 3, 2);
+        c("-(a ^ 2)", 4, 3, 2);
+        c("f(x, 2)", 4, 2, 1);
+        #[cfg(feature = "matrices")]
+        c("[1, 2] * a", 5, 3, 1);
+        #[cfg(feature = "matrices")]
+        c("[[1, 2], [3, a + b]]", 7, 3, 1);
+        c("a && !b || c", 6, 4, 3);
+    }
+
+    #[track_caller]
+    fn s(expression: &str, result: &str) {
+        assert_eq!(
+            expression
+                .parse::<Expression>()
+                .unwrap()
+                .simplify()
+                .to_string(),
+            result,
+        );
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn cost_based() {
+        s("a", "a");
+        s("2 * 3 + a", "6 + a");
+        s("(a + 0) * 1", "a");
+        s("(a || b) && (a || c)", "a || b && c");
+        s("!(a && b) && !a", "!a");
+        s("1 / 0", "1 / 0");
+    }
 }
@@ -0,0 +1,693 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use num::{complex::Complex64, Integer as _, One, Signed, ToPrimitive, Zero};
+
+use crate::{
+    evaluate::{from_f64, Options},
+    expression::{Complex, Expression, Integer, Rational, RationalRepresentation, Type},
+    helpers::*,
+};
+
+/// Maximum exponent of a power that is expanded when determining the coefficients
+/// of a polynomial, bounding the number of products of coefficients.
+const MAX_EXPONENT: usize = 64;
+
+/// Maximum number of candidates tested for rational roots of a polynomial.
+const MAX_CANDIDATES: usize = 10_000;
+
+/// Maximum number of iterations of the Durand-Kerner method.
+const MAX_ITERATIONS: usize = 1000;
+
+/// Reason why a system of linear equations has no unique solution.
+#[cfg(feature = "functions")]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(crate) enum LinearSystemError {
+    /// The equations contradict each other, so there is no solution.
+    Inconsistent,
+    /// There are fewer independent equations than variables,
+    /// so there are infinitely many solutions.
+    Underdetermined,
+}
+
+/// Returns the result of evaluating the expression,
+/// or the expression itself if evaluation fails.
+fn evaluate(expression: Expression, options: &Options) -> Expression {
+    match expression.evaluate_with_options(HashMap::new(), options) {
+        Ok(result) => result,
+        Err(_) => expression,
+    }
+}
+
+/// Returns the sum of the polynomials with the given coefficients.
+fn add(a: Vec<Expression>, b: Vec<Expression>) -> Vec<Expression> {
+    let (mut longer, shorter) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+
+    for (i, coefficient) in shorter.into_iter().enumerate() {
+        longer[i] = longer[i].clone() + coefficient;
+    }
+
+    longer
+}
+
+/// Returns the product of the polynomials with the given coefficients,
+/// with the coefficients of the product evaluated using the given options.
+fn multiply(a: &[Expression], b: &[Expression], options: &Options) -> Vec<Expression> {
+    let mut product = vec![int(0); a.len() + b.len() - 1];
+
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            // Evaluating the coefficients while accumulating them keeps them
+            // from growing exponentially with the exponent when expanding powers.
+            product[i + j] = evaluate(product[i + j].clone() + x.clone() * y.clone(), options);
+        }
+    }
+
+    product
+}
+
+/// Returns whether the expression is a real number less than zero.
+fn is_negative(expression: &Expression) -> bool {
+    matches!(expression.typ(), Type::Number(z, _) if z.im.is_zero() && z.re.is_negative())
+}
+
+/// Returns the value of the polynomial with the given coefficients at `x`.
+fn horner(coefficients: &[Rational], x: &Rational) -> Rational {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Rational::zero(), |value, coefficient| {
+            value * x + coefficient
+        })
+}
+
+/// Returns the quotient of the polynomial with the given coefficients
+/// and `x - r`, assuming that `r` is a root of the polynomial.
+fn deflate(coefficients: &[Rational], r: &Rational) -> Vec<Rational> {
+    let mut quotient = vec![Rational::zero(); coefficients.len() - 1];
+    let mut carry = Rational::zero();
+
+    for i in (1..coefficients.len()).rev() {
+        carry = carry * r + &coefficients[i];
+        quotient[i - 1] = carry.clone();
+    }
+
+    quotient
+}
+
+/// Returns the positive divisors of the non-zero integer `n`,
+/// or `None` if finding them would take too long.
+fn divisors(n: &Integer) -> Option<Vec<Integer>> {
+    let n = n.abs().to_u64()?;
+
+    if n > 1 << 40 {
+        return None;
+    }
+
+    let mut divisors = Vec::new();
+    let mut d = 1;
+
+    while d * d <= n {
+        if n % d == 0 {
+            divisors.push(Integer::from(d));
+
+            if d * d != n {
+                divisors.push(Integer::from(n / d));
+            }
+        }
+
+        d += 1;
+    }
+
+    Some(divisors)
+}
+
+/// Returns approximations of all complex roots of the polynomial with the given
+/// coefficients and a degree of at least one, computed using the Durand-Kerner method,
+/// or `None` if the method fails to produce finite approximations or exceeds the timeout
+/// in the given options.
+fn numeric_roots(coefficients: &[Rational], options: &Options) -> Option<Vec<Complex64>> {
+    let degree = coefficients.len() - 1;
+    let leading = coefficients[degree].to_f64()?;
+
+    let monic = coefficients
+        .iter()
+        .map(|c| Some(c.to_f64()? / leading))
+        .collect::<Option<Vec<_>>>()?;
+
+    let p = |z: Complex64| {
+        monic
+            .iter()
+            .rev()
+            .fold(Complex64::zero(), |value, c| value * z + c)
+    };
+
+    let seed = Complex64::new(0.4, 0.9);
+    let mut roots = (0..degree).map(|k| seed.powu(k as u32)).collect::<Vec<_>>();
+
+    for _ in 0..MAX_ITERATIONS {
+        options.check_time().ok()?;
+
+        let mut change = 0.0_f64;
+
+        for i in 0..degree {
+            let denominator = (0..degree)
+                .filter(|j| *j != i)
+                .fold(Complex64::one(), |product, j| {
+                    product * (roots[i] - roots[j])
+                });
+
+            let delta = p(roots[i]) / denominator;
+
+            if delta.is_finite() {
+                roots[i] -= delta;
+                change = change.max(delta.norm() / roots[i].norm().max(1.0));
+            }
+        }
+
+        if change < 1e-15 {
+            break;
+        }
+    }
+
+    // Polishing the approximations with Newton's method
+    // removes most of the accumulated rounding error.
+    let derivative = monic
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(k, c)| c * k as f64)
+        .collect::<Vec<_>>();
+
+    for root in &mut roots {
+        for _ in 0..2 {
+            let slope = derivative
+                .iter()
+                .rev()
+                .fold(Complex64::zero(), |value, c| value * *root + c);
+
+            let delta = p(*root) / slope;
+
+            if delta.is_finite() {
+                *root -= delta;
+            }
+        }
+    }
+
+    if roots.iter().all(|z| z.is_finite()) {
+        Some(roots)
+    } else {
+        None
+    }
+}
+
+/// Returns the decimal number approximated by the given complex number,
+/// with parts that are negligible relative to its absolute value set to zero.
+fn decimal(z: Complex64) -> Expression {
+    let part = |x: f64| {
+        if x.abs() < z.norm() * 1e-12 {
+            Rational::zero()
+        } else {
+            from_f64(x)
+        }
+    };
+
+    if part(z.im).is_zero() {
+        Expression::Rational(part(z.re), RationalRepresentation::Decimal)
+    } else {
+        Expression::Complex(
+            Complex::new(part(z.re), part(z.im)),
+            RationalRepresentation::Decimal,
+        )
+    }
+}
+
+impl Expression {
+    /// Returns the expression `lhs - rhs` if the expression is an equation,
+    /// and the expression itself otherwise.
+    fn polynomial(&self) -> Self {
+        match self {
+            Expression::Equal(lhs, rhs) => *lhs.clone() - *rhs.clone(),
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns the coefficients of the expression as a polynomial in `x`,
+    /// ordered by increasing degree, with the arithmetic on them unevaluated
+    /// except for products, which are evaluated using the given options.
+    fn raw_coefficients(&self, x: &Self, options: &Options) -> Option<Vec<Self>> {
+        use crate::expression::Expression::*;
+
+        if !self.contains(x) {
+            return match self.typ() {
+                Type::Number(_, _) | Type::Arithmetic | Type::Unknown => Some(vec![self.clone()]),
+                _ => None,
+            };
+        }
+
+        match self {
+            Variable(_) => Some(vec![int(0), int(1)]),
+            Negation(a) => Some(
+                a.raw_coefficients(x, options)?
+                    .into_iter()
+                    .map(|c| -c)
+                    .collect(),
+            ),
+            Sum(a, b) => Some(add(
+                a.raw_coefficients(x, options)?,
+                b.raw_coefficients(x, options)?,
+            )),
+            Difference(a, b) => Some(add(
+                a.raw_coefficients(x, options)?,
+                b.raw_coefficients(x, options)?
+                    .into_iter()
+                    .map(|c| -c)
+                    .collect(),
+            )),
+            Product(a, b) => Some(multiply(
+                &a.raw_coefficients(x, options)?,
+                &b.raw_coefficients(x, options)?,
+                options,
+            )),
+            Quotient(a, b) if !b.contains(x) => Some(
+                a.raw_coefficients(x, options)?
+                    .into_iter()
+                    .map(|c| c / *b.clone())
+                    .collect(),
+            ),
+            Power(a, b) => {
+                let mut n = match &**b {
+                    Integer(n) => n.to_usize().filter(|n| *n <= MAX_EXPONENT)?,
+                    _ => return None,
+                };
+
+                let mut base = a.raw_coefficients(x, options)?;
+                let mut power = vec![int(1)];
+
+                // Exponentiation by squaring
+                while n > 0 {
+                    if n % 2 == 1 {
+                        power = multiply(&power, &base, options);
+                    }
+
+                    n /= 2;
+
+                    if n > 0 {
+                        base = multiply(&base, &base, options);
+                    }
+                }
+
+                Some(power)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the coefficients of the expression as a polynomial in the
+    /// variable with the given identifier, ordered by increasing degree and
+    /// without trailing zeros, or `None` if the expression is not a polynomial
+    /// in the variable. The options are used when evaluating the coefficients.
+    pub(crate) fn coefficients(&self, variable: &str, options: &Options) -> Option<Vec<Self>> {
+        let mut coefficients = self
+            .raw_coefficients(&var(variable), options)?
+            .into_iter()
+            .map(|c| evaluate(c, options))
+            .collect::<Vec<_>>();
+
+        while coefficients.last() == Some(&int(0)) {
+            coefficients.pop();
+        }
+
+        Some(coefficients)
+    }
+
+    /// Returns the solutions of the equation (or the equation `self == 0` if the
+    /// expression is not an equation) for the variable with the given identifier,
+    /// or `None` if the equation is not a linear or quadratic equation in the
+    /// variable or is satisfied by all values of the variable. Solutions of
+    /// quadratic equations are given exactly, using square roots if necessary.
+    /// The options are used when evaluating the solutions.
+    pub fn solve(&self, variable: &str, options: &Options) -> Option<Vec<Self>> {
+        let coefficients = self.polynomial().coefficients(variable, options)?;
+
+        match coefficients.as_slice() {
+            [] => None,
+            [_] => Some(Vec::new()),
+            [c, b] => Some(vec![evaluate(-c.clone() / b.clone(), options)]),
+            [c, b, a] => {
+                let (p, q) = (
+                    evaluate(-b.clone() / (int(2) * a.clone()), options),
+                    evaluate(
+                        (pow(b.clone(), int(2)) - int(4) * a.clone() * c.clone())
+                            / (int(4) * pow(a.clone(), int(2))),
+                        options,
+                    ),
+                );
+
+                if q == int(0) {
+                    return Some(vec![p]);
+                }
+
+                // The square root of a negative number is written as a multiple of `i`.
+                let (minus, plus) = if is_negative(&q) {
+                    let root = fun(var("sqrt"), [evaluate(-q, options)]);
+                    (-var("i") * root.clone(), var("i") * root)
+                } else {
+                    let root = fun(var("sqrt"), [q]);
+                    (-root.clone(), root)
+                };
+
+                Some(if p == int(0) {
+                    vec![evaluate(minus, options), evaluate(plus, options)]
+                } else {
+                    vec![
+                        evaluate(p.clone() - plus.clone(), options),
+                        evaluate(p + plus, options),
+                    ]
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns all roots of the expression (or of `lhs - rhs` if the expression is an
+    /// equation) as a polynomial with rational coefficients in the variable with the
+    /// given identifier, repeated according to their multiplicity, or `None` if the
+    /// expression is not such a polynomial or is zero. Rational roots are found
+    /// exactly using the rational root theorem and come first, in ascending order.
+    /// The remaining roots are approximated numerically and given as decimal numbers.
+    /// The options are used when evaluating the coefficients, and `None` is also
+    /// returned if finding the roots exceeds the timeout in the options.
+    pub fn roots(&self, variable: &str, options: &Options) -> Option<Vec<Self>> {
+        let mut coefficients = self
+            .polynomial()
+            .coefficients(variable, options)?
+            .into_iter()
+            .map(|c| Rational::try_from(c).ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        if coefficients.is_empty() {
+            return None;
+        }
+
+        let mut exact = Vec::new();
+
+        while coefficients[0].is_zero() {
+            exact.push(Rational::zero());
+            coefficients.remove(0);
+        }
+
+        if coefficients.len() > 1 {
+            // Multiplying by the least common multiple of the denominators
+            // yields integer coefficients with the same roots.
+            let denominator = coefficients
+                .iter()
+                .fold(Integer::one(), |m, c| m.lcm(c.denom()));
+
+            let constant = (&coefficients[0] * &denominator).to_integer();
+            let leading = (coefficients.last().unwrap() * &denominator).to_integer();
+
+            if let (Some(p), Some(q)) = (divisors(&constant), divisors(&leading)) {
+                if p.len() * q.len() <= MAX_CANDIDATES {
+                    let mut candidates = p
+                        .iter()
+                        .flat_map(|p| q.iter().map(move |q| Rational::new(p.clone(), q.clone())))
+                        .flat_map(|r| [-r.clone(), r])
+                        .collect::<Vec<_>>();
+
+                    candidates.sort();
+                    candidates.dedup();
+
+                    for r in candidates {
+                        options.check_time().ok()?;
+
+                        while coefficients.len() > 1 && horner(&coefficients, &r).is_zero() {
+                            coefficients = deflate(&coefficients, &r);
+                            exact.push(r.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        exact.sort();
+
+        let mut roots = exact.into_iter().map(Expression::from).collect::<Vec<_>>();
+
+        if coefficients.len() > 1 {
+            let mut approximations = numeric_roots(&coefficients, options)?;
+
+            // The approximations are finite, so they can be compared.
+            let compare = |a: f64, b: f64| a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+
+            approximations.sort_by(|a, b| compare(a.re, b.re).then(compare(a.im, b.im)));
+
+            roots.extend(approximations.into_iter().map(decimal));
+        }
+
+        Some(roots)
+    }
+}
+
+/// Returns the coefficients of the given variables and the constant term of the
+/// expression as a linear expression in the variables, or `None` if it is not one.
+#[cfg(feature = "functions")]
+fn linear_coefficients(
+    expression: &Expression,
+    variables: &[&str],
+    options: &Options,
+) -> Option<(Vec<Expression>, Expression)> {
+    let mut coefficients = Vec::new();
+
+    for variable in variables {
+        coefficients.push(
+            match expression.coefficients(variable, options)?.as_slice() {
+                [] | [_] => int(0),
+                [_, a] if !variables.iter().any(|x| a.contains(&var(*x))) => a.clone(),
+                _ => return None,
+            },
+        );
+    }
+
+    let zeros = variables
+        .iter()
+        .map(|x| ((*x).to_owned(), int(0)))
+        .collect::<HashMap<_, _>>();
+
+    let constant = expression.evaluate_with_options(zeros, options).ok()?;
+
+    Some((coefficients, constant))
+}
+
+/// Returns the unique solution of the system of linear equations (or expressions
+/// that are equal to zero) for the given variables, in the order of the variables,
+/// computed exactly by Gaussian elimination. Returns `Ok(None)` if the equations are
+/// not linear in the variables, or if the system cannot be solved because whether it
+/// is consistent depends on the values of other variables. Symbolic pivots are assumed
+/// to be non-zero. The options are used when evaluating intermediate results.
+#[cfg(feature = "functions")]
+pub(crate) fn solve_linear_system(
+    equations: &[Expression],
+    variables: &[&str],
+    options: &Options,
+) -> Result<Option<Vec<Expression>>, LinearSystemError> {
+    let n = variables.len();
+
+    // Augmented matrix of the system, with the constant terms moved to the right.
+    let mut rows = Vec::new();
+
+    for equation in equations {
+        let (mut row, constant) =
+            match linear_coefficients(&equation.polynomial(), variables, options) {
+                Some(linear) => linear,
+                None => return Ok(None),
+            };
+
+        row.push(evaluate(-constant, options));
+        rows.push(row);
+    }
+
+    let mut rank = 0;
+    let mut pivot_columns = Vec::new();
+
+    for column in 0..n {
+        // Numeric pivots are preferred, as symbolic ones might be zero.
+        let pivot = (rank..rows.len())
+            .filter(|i| rows[*i][column] != int(0))
+            .min_by_key(|i| !matches!(rows[*i][column].typ(), Type::Number(_, _)));
+
+        let pivot = match pivot {
+            Some(pivot) => pivot,
+            None => continue,
+        };
+
+        rows.swap(rank, pivot);
+
+        let pivot_row = rows[rank].clone();
+
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i == rank || row[column] == int(0) {
+                continue;
+            }
+
+            let factor = evaluate(row[column].clone() / pivot_row[column].clone(), options);
+
+            for (entry, pivot_entry) in row.iter_mut().zip(&pivot_row).skip(column) {
+                *entry = evaluate(
+                    entry.clone() - factor.clone() * pivot_entry.clone(),
+                    options,
+                );
+            }
+        }
+
+        pivot_columns.push(column);
+        rank += 1;
+    }
+
+    for row in &rows[rank..] {
+        match row[n].typ() {
+            Type::Number(z, _) if z.is_zero() => (),
+            Type::Number(_, _) => return Err(LinearSystemError::Inconsistent),
+            _ => return Ok(None),
+        }
+    }
+
+    if rank < n {
+        return Err(LinearSystemError::Underdetermined);
+    }
+
+    Ok(Some(
+        rows.iter()
+            .zip(pivot_columns)
+            .map(|(row, column)| evaluate(row[n].clone() / row[column].clone(), options))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+// Tests that use built-in functions are skipped if those are disabled.
+#[cfg_attr(not(feature = "functions"), allow(unused_imports, dead_code))]
+mod tests {
+    use crate::evaluate::Options;
+    use crate::expression::Expression;
+
+    #[cfg(feature = "functions")]
+    use crate::solve::{solve_linear_system, LinearSystemError, LinearSystemError::*};
+
+    #[track_caller]
+    fn s(equation: &str, solutions: Option<&[&str]>) {
+        assert_eq!(
+            equation
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(Default::default())
+                .unwrap()
+                .solve("x", &Options::default())
+                .map(|solutions| solutions.iter().map(|x| x.to_string()).collect::<Vec<_>>()),
+            solutions.map(|solutions| solutions.iter().map(|x| x.to_string()).collect::<Vec<_>>()),
+        );
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn solve() {
+        s("2 * x + 1 == 7", Some(&["3"]));
+        s("x / 2 == x - 1", Some(&["2"]));
+        s("x ^ 2 == 4", Some(&["-2", "2"]));
+        s("(x - 1) * (x - 3)", Some(&["1", "3"]));
+        s("x ^ 2 - 2 * x + 1", Some(&["1"]));
+        s("x ^ 2 + 1", Some(&["-i", "i"]));
+        s("x ^ 2 == 3", Some(&["-sqrt(3)", "sqrt(3)"]));
+        s(
+            "x ^ 2 + 2 * x + 3",
+            Some(&["-1 - i * sqrt(2)", "-1 + i * sqrt(2)"]),
+        );
+        s("x + 1 == x", Some(&[]));
+        s("x == x", None);
+        s("x ^ 3 == 1", None);
+        s("x ^ 22 == 1", None);
+        s("sin(x) == 0", None);
+    }
+
+    #[track_caller]
+    fn r(polynomial: &str, roots: Option<&[&str]>) {
+        assert_eq!(
+            polynomial
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(Default::default())
+                .unwrap()
+                .roots("x", &Options::default())
+                .map(|roots| roots.iter().map(|x| x.to_string()).collect::<Vec<_>>()),
+            roots.map(|roots| roots.iter().map(|x| x.to_string()).collect::<Vec<_>>()),
+        );
+    }
+
+    #[test]
+    fn roots() {
+        r("x ^ 2 - 1", Some(&["-1", "1"]));
+        r("(x - 1/2) ^ 2 * x", Some(&["0", "1/2", "1/2"]));
+        r(
+            "2 * x ^ 3 - 3 * x ^ 2 - 11 * x + 6",
+            Some(&["-2", "1/2", "3"]),
+        );
+        r(
+            "x ^ 2 - 3",
+            Some(&["-1.73205080756888", "1.73205080756888"]),
+        );
+        r("x ^ 3 - x ^ 2 + x - 1", Some(&["1", "-i", "i"]));
+        r("x ^ 2 == 1", Some(&["-1", "1"]));
+        r("x ^ 64", Some(&["0"; 64]));
+        r("(2 * x - 1) ^ 40", Some(&["1/2"; 40]));
+        r(
+            "(x - 1) ^ 10 * (x + 2) ^ 3",
+            Some(&[&["-2"; 3][..], &["1"; 10]].concat()),
+        );
+        r("3", Some(&[]));
+        r("0", None);
+        r("a * x", None);
+        r("sqrt(x)", None);
+    }
+
+    #[cfg(feature = "functions")]
+    #[track_caller]
+    fn l(equations: &[&str], solution: Result<Option<&[&str]>, LinearSystemError>) {
+        let equations = equations
+            .iter()
+            .map(|e| {
+                e.parse::<Expression>()
+                    .unwrap()
+                    .evaluate(Default::default())
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            solve_linear_system(&equations, &["x", "y"], &Options::default()).map(|solution| {
+                solution.map(|solution| solution.iter().map(|x| x.to_string()).collect::<Vec<_>>())
+            }),
+            solution.map(|solution| {
+                solution.map(|solution| solution.iter().map(|x| x.to_string()).collect::<Vec<_>>())
+            }),
+        );
+    }
+
+    #[cfg(feature = "functions")]
+    #[test]
+    fn linear_system() {
+        l(&["x + y == 3", "x - y == 1"], Ok(Some(&["2", "1"])));
+        l(
+            &["2 * y == 1", "x / 3 + y == 0"],
+            Ok(Some(&["-3/2", "1/2"])),
+        );
+        l(
+            &["x + y == a", "2 * y == b"],
+            Ok(Some(&["a - 1/2 * b", "b / 2"])),
+        );
+        l(&["x + y == 1", "2 * x + 2 * y == 3"], Err(Inconsistent));
+        l(&["x + y == 1", "2 * x + 2 * y == 2"], Err(Underdetermined));
+        l(&["x * y == 1", "x == 1"], Ok(None));
+    }
+}
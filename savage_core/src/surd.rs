@@ -0,0 +1,397 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use num::{Integer as _, One, Signed, ToPrimitive, Zero};
+
+use crate::{
+    expression::{Expression, Integer, Rational, RationalRepresentation},
+    helpers::*,
+};
+
+#[cfg(feature = "functions")]
+use crate::functions::function_expression;
+
+/// Maximum index of a radical, i.e. the maximum `n` for which `n`th roots are kept exact.
+const MAX_INDEX: u32 = 64;
+
+/// Maximum number of bits of a radicand before its perfect powers are extracted.
+const MAX_BITS: u64 = 4096;
+
+/// Maximum divisor tried when extracting perfect powers from a radicand.
+/// Radicands with large repeated prime factors might therefore not be fully simplified.
+const MAX_DIVISOR: u32 = 10_000;
+
+/// Exact real number of the form `coefficient * radicand ^ (1/index)`, where the radicand
+/// is a positive integer that is not divisible by the `index`th power of any integer
+/// greater than 1 (as far as such powers can be found), and the index is the smallest
+/// possible. Rational numbers have the radicand 1 and the index 1.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) struct Surd {
+    coefficient: Rational,
+    radicand: Integer,
+    index: u32,
+}
+
+impl Surd {
+    /// Returns the surd representing the rational number.
+    fn rational(x: Rational) -> Self {
+        Self {
+            coefficient: x,
+            radicand: Integer::one(),
+            index: 1,
+        }
+    }
+
+    /// Returns whether the surd is a rational number.
+    pub(crate) fn is_rational(&self) -> bool {
+        self.index == 1
+    }
+
+    /// Returns the rational coefficient of the surd.
+    pub(crate) fn coefficient(&self) -> &Rational {
+        &self.coefficient
+    }
+
+    /// Returns the radicand of the surd.
+    #[cfg(feature = "functions")]
+    pub(crate) fn radicand(&self) -> &Integer {
+        &self.radicand
+    }
+
+    /// Returns the index of the radical of the surd.
+    #[cfg(feature = "functions")]
+    pub(crate) fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns `x ^ (p/q)` for the positive rational number `x`, or `None` if
+    /// the index or the radicand of the result would exceed the internal limits.
+    pub(crate) fn root(x: &Rational, p: i32, q: u32) -> Option<Self> {
+        let gcd = (p.unsigned_abs()).gcd(&q).max(1);
+        let (p, q) = (p / gcd as i32, q / gcd);
+
+        if q > MAX_INDEX || !x.is_positive() {
+            return None;
+        }
+
+        let (k, r) = (p.div_euclid(q as i32), p.rem_euclid(q as i32) as u32);
+
+        let mut coefficient = x.pow(k);
+
+        if r == 0 {
+            return Some(Self::rational(coefficient));
+        }
+
+        // (a / b) ^ (r/q) = (a ^ r * b ^ (q - r)) ^ (1/q) / b
+        let (a, b) = (x.numer(), x.denom());
+
+        if a.bits() * u64::from(r) + b.bits() * u64::from(q - r) > MAX_BITS {
+            return None;
+        }
+
+        let mut radicand = a.pow(r) * b.pow(q - r);
+        coefficient /= Rational::from(b.clone());
+
+        // Perfect `q`th powers are moved from the radicand into the coefficient.
+        let mut divisor = Integer::from(2);
+
+        while divisor <= Integer::from(MAX_DIVISOR) && divisor.pow(q) <= radicand {
+            let power = divisor.pow(q);
+
+            while radicand.is_multiple_of(&power) {
+                radicand /= &power;
+                coefficient *= Rational::from(divisor.clone());
+            }
+
+            divisor += 1;
+        }
+
+        let root = radicand.nth_root(q);
+
+        if root.pow(q) == radicand {
+            return Some(Self::rational(coefficient * Rational::from(root)));
+        }
+
+        // If the radicand is a perfect `d`th power for a divisor `d` of the index,
+        // the index can be reduced, e.g. `4 ^ (1/4) == 2 ^ (1/2)`.
+        let mut index = q;
+
+        if let Some(d) = (2..q).rev().find(|d| {
+            q % d == 0 && {
+                let root = radicand.nth_root(*d);
+                root.pow(*d) == radicand
+            }
+        }) {
+            radicand = radicand.nth_root(d);
+            index = q / d;
+        }
+
+        Some(Self {
+            coefficient,
+            radicand,
+            index,
+        })
+    }
+
+    /// Returns the surd represented by the expression, or `None` if the expression is not
+    /// built from rational numbers, square roots and rational powers of positive rational
+    /// numbers, and products, quotients, and powers of those that are surds themselves.
+    pub(crate) fn from_expression(expression: &Expression) -> Option<Self> {
+        use crate::expression::Expression::*;
+
+        match expression {
+            Integer(n) => Some(Self::rational(n.clone().into())),
+            // Decimal numbers are approximations, so their radicals are not kept exact.
+            Rational(x, RationalRepresentation::Fraction) => Some(Self::rational(x.clone())),
+            Complex(z, RationalRepresentation::Fraction) if z.im.is_zero() => {
+                Some(Self::rational(z.re.clone()))
+            }
+            FunctionValue(f, arguments) if arguments.len() == 1 => match &**f {
+                Function(identifier, _) | Variable(identifier) if identifier == "sqrt" => {
+                    let x = Self::from_expression(&arguments[0])?;
+
+                    if x.is_rational() {
+                        Self::root(&x.coefficient, 1, 2)
+                    } else {
+                        x.power(&crate::expression::Rational::new(1.into(), 2.into()))
+                    }
+                }
+                _ => None,
+            },
+            Negation(a) => Some(Self::from_expression(a)?.negate()),
+            Product(a, b) => Self::from_expression(a)?.multiply(&Self::from_expression(b)?),
+            Quotient(a, b) => {
+                Self::from_expression(a)?.multiply(&Self::from_expression(b)?.reciprocal()?)
+            }
+            Power(a, b) => {
+                let b = Self::from_expression(b)?;
+
+                if b.is_rational() {
+                    Self::from_expression(a)?.power(&b.coefficient)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the negation of the surd.
+    pub(crate) fn negate(mut self) -> Self {
+        self.coefficient = -self.coefficient;
+        self
+    }
+
+    /// Returns the product of the surds, or `None` if it would exceed the internal limits.
+    fn multiply(&self, other: &Self) -> Option<Self> {
+        let index = self.index.lcm(&other.index);
+
+        if index > MAX_INDEX {
+            return None;
+        }
+
+        let radicand =
+            self.radicand.pow(index / self.index) * other.radicand.pow(index / other.index);
+
+        let mut result = Self::root(&Rational::from(radicand), 1, index)?;
+        result.coefficient *= &self.coefficient * &other.coefficient;
+
+        Some(result)
+    }
+
+    /// Returns the reciprocal of the surd, which has a rational denominator,
+    /// or `None` if the surd is zero.
+    fn reciprocal(&self) -> Option<Self> {
+        if self.coefficient.is_zero() {
+            return None;
+        }
+
+        let mut result = Self::root(&Rational::from(self.radicand.clone()), -1, self.index)?;
+        result.coefficient *= self.coefficient.recip();
+
+        Some(result)
+    }
+
+    /// Returns the surd raised to the rational power `exponent`, or `None` if the result
+    /// is not a (real) surd or would exceed the internal limits.
+    fn power(&self, exponent: &Rational) -> Option<Self> {
+        let p = exponent.numer().to_i32()?;
+        let q = exponent.denom().to_u32()?;
+
+        let coefficient = if q == 1 {
+            if self.coefficient.is_zero() && p < 0 {
+                return None;
+            }
+
+            Self::rational(self.coefficient.pow(p))
+        } else if self.coefficient.is_positive() {
+            Self::root(&self.coefficient, p, q)?
+        } else {
+            return None;
+        };
+
+        coefficient.multiply(&Self::root(
+            &Rational::from(self.radicand.clone()),
+            p,
+            q.checked_mul(self.index)?,
+        )?)
+    }
+
+    /// Returns the sum of the surds, or `None` if it is not a surd
+    /// because they have different radicals.
+    fn add(&self, other: &Self) -> Option<Self> {
+        if self.radicand != other.radicand || self.index != other.index {
+            return None;
+        }
+
+        let coefficient = &self.coefficient + &other.coefficient;
+
+        Some(if coefficient.is_zero() {
+            Self::rational(coefficient)
+        } else {
+            Self {
+                coefficient,
+                ..self.clone()
+            }
+        })
+    }
+
+    /// Returns the expression representing the radical (without the coefficient),
+    /// which is a square root for the index 2 and a power otherwise.
+    pub(crate) fn radical(&self) -> Expression {
+        let radicand = Expression::Integer(self.radicand.clone());
+
+        #[cfg(feature = "functions")]
+        if self.index == 2 {
+            return fun(function_expression("sqrt").unwrap(), [radicand]);
+        }
+
+        pow(
+            radicand,
+            Expression::Rational(
+                Rational::new(Integer::one(), Integer::from(self.index)),
+                RationalRepresentation::Fraction,
+            ),
+        )
+    }
+
+    /// Returns the expression representing the surd, as a quotient
+    /// with an integer denominator if the coefficient is not an integer.
+    pub(crate) fn to_expression(&self, representation: RationalRepresentation) -> Expression {
+        if self.is_rational() || self.coefficient.is_zero() {
+            return Expression::Complex(self.coefficient.clone().into(), representation);
+        }
+
+        let radical = self.radical();
+        let numerator = self.coefficient.numer();
+
+        let numerator = if numerator.is_one() {
+            radical
+        } else if (-numerator).is_one() {
+            -radical
+        } else {
+            Expression::Integer(numerator.clone()) * radical
+        };
+
+        if self.coefficient.denom().is_one() {
+            numerator
+        } else {
+            numerator / Expression::Integer(self.coefficient.denom().clone())
+        }
+    }
+}
+
+/// Returns whether the expression is a radical, i.e. a square root
+/// or a non-integer power of a number.
+fn is_radical(expression: &Expression) -> bool {
+    use crate::expression::Expression::*;
+
+    match expression {
+        FunctionValue(f, _) => {
+            matches!(&**f, Function(identifier, _) | Variable(identifier) if identifier == "sqrt")
+        }
+        Power(a, b) => {
+            matches!(**a, Integer(_) | Rational(_, _) | Complex(_, _))
+                && matches!(**b, Rational(_, _) | Complex(_, _))
+        }
+        Negation(a) => is_radical(a),
+        Product(a, b) | Quotient(a, b) => is_radical(a) || is_radical(b),
+        _ => false,
+    }
+}
+
+/// Returns the negation of the evaluated operand if it is a radical
+/// or a product or quotient involving one, or `None` otherwise.
+pub(crate) fn negate(a: &Expression) -> Option<Expression> {
+    if !is_radical(a) {
+        return None;
+    }
+
+    Some(
+        Surd::from_expression(a)?
+            .negate()
+            .to_expression(RationalRepresentation::Fraction),
+    )
+}
+
+/// Returns the result of the arithmetic operation (`self` is the operation)
+/// on the evaluated operands if at least one of them is a radical and the result
+/// is a surd, or `None` otherwise. Sums and differences are only combined
+/// if the radicals of the operands are equal.
+pub(crate) fn evaluate(
+    operation: &Expression,
+    a: &Expression,
+    b: &Expression,
+) -> Option<Expression> {
+    use crate::expression::Expression::*;
+
+    if !is_radical(a) && !is_radical(b) {
+        return None;
+    }
+
+    let x = Surd::from_expression(a)?;
+    let y = Surd::from_expression(b)?;
+
+    let result = match operation {
+        Sum(_, _) => x.add(&y)?,
+        Difference(_, _) => x.add(&y.negate())?,
+        Product(_, _) => x.multiply(&y)?,
+        Quotient(_, _) => x.multiply(&y.reciprocal()?)?,
+        Power(_, _) if y.is_rational() => x.power(&y.coefficient)?,
+        _ => return None,
+    };
+
+    Some(result.to_expression(RationalRepresentation::Fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expression::Rational, surd::Surd};
+
+    #[track_caller]
+    fn r(x: (i64, i64), p: i32, q: u32, result: Option<(i64, i64, i64, u32)>) {
+        assert_eq!(
+            Surd::root(&Rational::new(x.0.into(), x.1.into()), p, q),
+            result.map(|(numerator, denominator, radicand, index)| Surd {
+                coefficient: Rational::new(numerator.into(), denominator.into()),
+                radicand: radicand.into(),
+                index,
+            }),
+        );
+    }
+
+    #[test]
+    fn root() {
+        r((8, 1), 1, 2, Some((2, 1, 2, 2)));
+        r((4, 1), 1, 2, Some((2, 1, 1, 1)));
+        r((1, 8), 1, 2, Some((1, 4, 2, 2)));
+        r((16, 1), 1, 3, Some((2, 1, 2, 3)));
+        r((4, 1), 1, 4, Some((1, 1, 2, 2)));
+        r((2, 1), 3, 2, Some((2, 1, 2, 2)));
+        r((2, 1), -1, 2, Some((1, 2, 2, 2)));
+        r((9, 4), 3, 2, Some((27, 8, 1, 1)));
+        r((72, 1), 2, 4, Some((6, 1, 2, 2)));
+        r((2, 1), 1, 100, None);
+        r((-2, 1), 1, 2, None);
+    }
+}
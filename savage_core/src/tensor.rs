@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2021-2022  Philipp Emanuel Weidmann <pew@worldwidemann.com>
+
+use crate::expression::{Expression, Matrix, Vector};
+
+/// Array of expressions with an arbitrary number of dimensions,
+/// with the elements stored in row-major order (the last index varies fastest).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Tensor {
+    shape: Vec<usize>,
+    elements: Vec<Expression>,
+}
+
+impl Tensor {
+    /// Returns a tensor with the given shape and elements in row-major order,
+    /// or `None` if the number of elements doesn't match the shape.
+    pub fn new(shape: Vec<usize>, elements: Vec<Expression>) -> Option<Self> {
+        if shape.iter().product::<usize>() == elements.len() {
+            Some(Tensor { shape, elements })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the tensor with the same shape and elements as the given
+    /// vector, matrix, or tensor expression, or `None` if the expression
+    /// is none of these.
+    pub fn from_expression(expression: &Expression) -> Option<Self> {
+        match expression {
+            Expression::Vector(v) => Some(Tensor {
+                shape: vec![v.len()],
+                elements: v.iter().cloned().collect(),
+            }),
+            Expression::Matrix(m) => Some(Tensor {
+                shape: vec![m.nrows(), m.ncols()],
+                elements: m.transpose().iter().cloned().collect(),
+            }),
+            Expression::Tensor(t) => Some(t.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the tensor whose subtensors along the first axis are the given tensors,
+    /// or `None` if the tensors don't all have the same shape.
+    pub fn stack(tensors: &[Self]) -> Option<Self> {
+        let mut shape = vec![tensors.len()];
+        let mut elements = Vec::new();
+
+        if let Some(first) = tensors.first() {
+            shape.extend(&first.shape);
+        }
+
+        for tensor in tensors {
+            if tensor.shape != shape[1..] {
+                return None;
+            }
+
+            elements.extend(tensor.elements.iter().cloned());
+        }
+
+        Some(Tensor { shape, elements })
+    }
+
+    /// Returns the tensor whose subtensors along the first axis are the given
+    /// matrix or tensor expressions, or `None` if the expressions are not all
+    /// matrices or tensors of the same shape.
+    pub(crate) fn from_slices(expressions: &[Expression]) -> Option<Self> {
+        let tensors = expressions
+            .iter()
+            .map(|expression| match expression {
+                Expression::Matrix(_) | Expression::Tensor(_) => Self::from_expression(expression),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if tensors.is_empty() {
+            None
+        } else {
+            Self::stack(&tensors)
+        }
+    }
+
+    /// Returns the size of each dimension of the tensor.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Returns the number of dimensions of the tensor.
+    pub fn rank(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// Returns the elements of the tensor in row-major order.
+    pub fn elements(&self) -> &[Expression] {
+        &self.elements
+    }
+
+    /// Returns the tensor obtained by applying `f` to each element.
+    pub fn map(&self, f: impl FnMut(&Expression) -> Expression) -> Self {
+        Tensor {
+            shape: self.shape.clone(),
+            elements: self.elements.iter().map(f).collect(),
+        }
+    }
+
+    /// Returns the tensor obtained by applying `f` to each pair of corresponding elements
+    /// of `self` and `other`, or `None` if the tensors have different shapes.
+    pub fn zip_map(
+        &self,
+        other: &Self,
+        mut f: impl FnMut(&Expression, &Expression) -> Expression,
+    ) -> Option<Self> {
+        if self.shape != other.shape {
+            return None;
+        }
+
+        Some(Tensor {
+            shape: self.shape.clone(),
+            elements: self
+                .elements
+                .iter()
+                .zip(&other.elements)
+                .map(|(a, b)| f(a, b))
+                .collect(),
+        })
+    }
+
+    /// Returns the tensor with the same elements in row-major order and the given shape,
+    /// or `None` if the number of elements doesn't match the shape.
+    pub fn reshape(&self, shape: Vec<usize>) -> Option<Self> {
+        Self::new(shape, self.elements.clone())
+    }
+
+    /// Returns the subtensor obtained by fixing the leading indices to the given values,
+    /// or `None` if there are more indices than dimensions or an index is out of bounds.
+    pub fn slice(&self, indices: &[usize]) -> Option<Self> {
+        if indices.len() > self.rank() {
+            return None;
+        }
+
+        let mut offset = 0;
+
+        for (index, size) in indices.iter().zip(&self.shape) {
+            if index >= size {
+                return None;
+            }
+
+            offset = offset * size + index;
+        }
+
+        let shape = self.shape[indices.len()..].to_vec();
+        let length = shape.iter().product::<usize>();
+
+        Some(Tensor {
+            shape,
+            elements: self.elements[offset * length..(offset + 1) * length].to_vec(),
+        })
+    }
+
+    /// Returns the contraction of `self` and `other` over the last `axes` dimensions
+    /// of `self` and the first `axes` dimensions of `other`, with the elements given as
+    /// unevaluated sums of products, or `None` if those dimensions don't match.
+    /// Contracting over one axis of two matrices yields their matrix product,
+    /// and contracting over no axes yields their outer product.
+    pub fn contract(&self, other: &Self, axes: usize) -> Option<Self> {
+        if axes > self.rank() || axes > other.rank() {
+            return None;
+        }
+
+        let (outer_a, inner_a) = self.shape.split_at(self.rank() - axes);
+        let (inner_b, outer_b) = other.shape.split_at(axes);
+
+        if inner_a != inner_b {
+            return None;
+        }
+
+        let inner_length = inner_a.iter().product::<usize>();
+        let outer_length_a = outer_a.iter().product::<usize>();
+        let outer_length_b = outer_b.iter().product::<usize>();
+
+        let mut elements = Vec::new();
+
+        for i in 0..outer_length_a {
+            for j in 0..outer_length_b {
+                let sum = (0..inner_length)
+                    .map(|k| {
+                        self.elements[i * inner_length + k].clone()
+                            * other.elements[k * outer_length_b + j].clone()
+                    })
+                    .reduce(|a, b| a + b);
+
+                elements.push(sum.unwrap_or_else(|| Expression::Integer(0.into())));
+            }
+        }
+
+        Some(Tensor {
+            shape: outer_a.iter().chain(outer_b).copied().collect(),
+            elements,
+        })
+    }
+
+    /// Returns the expression representing the tensor, which is a single element
+    /// for tensors without dimensions, a vector for one-dimensional tensors,
+    /// and a matrix for two-dimensional tensors.
+    pub fn into_expression(self) -> Expression {
+        match self.shape.as_slice() {
+            [] => self.elements.into_iter().next().unwrap(),
+            [_] => Expression::Vector(Vector::from_vec(self.elements)),
+            [rows, columns] => {
+                Expression::Matrix(Matrix::from_row_slice(*rows, *columns, &self.elements))
+            }
+            _ => Expression::Tensor(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, dvector};
+
+    use crate::expression::Expression;
+    use crate::helpers::*;
+    use crate::tensor::Tensor;
+
+    fn t(shape: &[usize], elements: &[i32]) -> Tensor {
+        Tensor::new(shape.to_vec(), elements.iter().map(|n| int(*n)).collect()).unwrap()
+    }
+
+    #[test]
+    fn tensor() {
+        let a = t(&[2, 2, 2], &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert_eq!(Tensor::new(vec![2, 3], vec![int(1)]), None);
+        assert_eq!(a.rank(), 3);
+        assert_eq!(
+            a.slice(&[1]).unwrap().into_expression(),
+            Expression::Matrix(dmatrix![int(5), int(6); int(7), int(8)]),
+        );
+        assert_eq!(
+            a.slice(&[0, 1]).unwrap().into_expression(),
+            Expression::Vector(dvector![int(3), int(4)]),
+        );
+        assert_eq!(a.slice(&[1, 1, 0]).unwrap().into_expression(), int(7));
+        assert_eq!(a.slice(&[2]), None);
+        assert_eq!(
+            a.reshape(vec![4, 2]).unwrap().into_expression(),
+            Expression::Matrix(
+                dmatrix![int(1), int(2); int(3), int(4); int(5), int(6); int(7), int(8)]
+            ),
+        );
+        assert_eq!(
+            Tensor::from_expression(&Expression::Matrix(
+                dmatrix![int(1), int(2); int(3), int(4)]
+            )),
+            Some(t(&[2, 2], &[1, 2, 3, 4])),
+        );
+        assert_eq!(
+            Tensor::stack(&[t(&[2], &[1, 2]), t(&[2], &[3, 4])]),
+            Some(t(&[2, 2], &[1, 2, 3, 4])),
+        );
+        assert_eq!(Tensor::stack(&[t(&[2], &[1, 2]), t(&[1], &[3])]), None);
+
+        let m = t(&[2, 2], &[1, 2, 3, 4]);
+        let v = t(&[2], &[5, 6]);
+
+        assert_eq!(
+            m.contract(&v, 1).unwrap().elements(),
+            &[
+                int(1) * int(5) + int(2) * int(6),
+                int(3) * int(5) + int(4) * int(6),
+            ],
+        );
+        assert_eq!(m.contract(&v, 0).unwrap().shape(), &[2, 2, 2]);
+        assert_eq!(
+            m.contract(&m, 2).unwrap().into_expression(),
+            int(1) * int(1) + int(2) * int(2) + int(3) * int(3) + int(4) * int(4),
+        );
+        assert_eq!(m.contract(&t(&[3], &[1, 2, 3]), 1), None);
+    }
+}
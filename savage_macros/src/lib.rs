@@ -5,8 +5,11 @@ use darling::FromMeta;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
-    parse_macro_input, punctuated::Punctuated, token::Comma, AttributeArgs, ExprArray, FnArg,
-    ItemFn, Path, Type,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    token::Comma,
+    AttributeArgs, ExprArray, FnArg, ItemFn, LitStr, Pat, Path, ReturnType, Token, Type,
 };
 
 #[derive(FromMeta)]
@@ -15,10 +18,28 @@ struct Arguments {
     description: String,
     examples: ExprArray,
     categories: ExprArray,
+    #[darling(default = "pure_default")]
+    pure: bool,
+}
+
+fn pure_default() -> bool {
+    true
 }
 
 /// Generates code required for the marked function to be usable in a function expression.
 /// Function metadata is generated from the provided attribute arguments.
+///
+/// If the marked function returns a `Result`, the error value is interpreted
+/// as the argument that caused the function invocation to fail, unless it is
+/// a `LimitExceeded` error indicating that a resource limit would be exceeded,
+/// or a `String` describing a failure unrelated to the arguments.
+///
+/// A parameter of type `&Options` receives the options used for evaluation,
+/// and does not correspond to an argument of the function expression.
+///
+/// Functions whose results depend on more than their arguments (random numbers,
+/// file contents, ...) must be marked with `pure = false`, which prevents
+/// their results from being cached.
 #[proc_macro_attribute]
 pub fn function(attr: TokenStream, item: TokenStream) -> TokenStream {
     let arguments = match Arguments::from_list(&parse_macro_input!(attr as AttributeArgs)) {
@@ -30,6 +51,7 @@ pub fn function(attr: TokenStream, item: TokenStream) -> TokenStream {
     let description_argument = arguments.description;
     let examples_argument = arguments.examples;
     let categories_argument = arguments.categories;
+    let pure_argument = arguments.pure;
 
     let item_fn = parse_macro_input!(item as ItemFn);
 
@@ -37,38 +59,106 @@ pub fn function(attr: TokenStream, item: TokenStream) -> TokenStream {
     let metadata_name = format_ident!("{}_METADATA", name.to_string().to_uppercase());
     let proxy_name = format_ident!("{}_proxy", name);
 
-    let parameters = item_fn.sig.inputs.iter().map(|fn_arg| {
+    let is_options = |fn_arg: &FnArg| {
         if let FnArg::Typed(pat_type) = fn_arg {
-            if let Type::Path(type_path) = &*pat_type.ty {
-                match type_path.path.get_ident().unwrap().to_string().as_str() {
-                    "Expression" => quote! { crate::functions::Parameter::Expression },
-                    "Integer" => quote! { crate::functions::Parameter::Integer },
-                    "NonNegativeInteger" => {
-                        quote! { crate::functions::Parameter::NonNegativeInteger }
-                    }
-                    "PositiveInteger" => {
-                        quote! { crate::functions::Parameter::PositiveInteger }
+            if let Type::Reference(type_reference) = &*pat_type.ty {
+                if let Type::Path(type_path) = &*type_reference.elem {
+                    return type_path.path.is_ident("Options");
+                }
+            }
+        }
+
+        false
+    };
+
+    let parameters = item_fn
+        .sig
+        .inputs
+        .iter()
+        .filter(|fn_arg| !is_options(fn_arg))
+        .map(|fn_arg| {
+            if let FnArg::Typed(pat_type) = fn_arg {
+                if let Type::Path(type_path) = &*pat_type.ty {
+                    match type_path.path.get_ident().unwrap().to_string().as_str() {
+                        "Expression" => quote! { crate::functions::Parameter::Expression },
+                        "Integer" => quote! { crate::functions::Parameter::Integer },
+                        "NonNegativeInteger" => {
+                            quote! { crate::functions::Parameter::NonNegativeInteger }
+                        }
+                        "PositiveInteger" => {
+                            quote! { crate::functions::Parameter::PositiveInteger }
+                        }
+                        "Rational" => quote! { crate::functions::Parameter::Rational },
+                        "Complex" => quote! { crate::functions::Parameter::Complex },
+                        "Vector" => quote! { crate::functions::Parameter::Vector },
+                        "Matrix" => quote! { crate::functions::Parameter::Matrix },
+                        "SquareMatrix" => quote! { crate::functions::Parameter::SquareMatrix },
+                        "bool" => quote! { crate::functions::Parameter::Boolean },
+                        "String" => quote! { crate::functions::Parameter::String },
+                        "Expressions" => quote! { crate::functions::Parameter::Expressions },
+                        _ => unimplemented!(),
                     }
-                    "Rational" => quote! { crate::functions::Parameter::Rational },
-                    "Complex" => quote! { crate::functions::Parameter::Complex },
-                    "Vector" => quote! { crate::functions::Parameter::Vector },
-                    "Matrix" => quote! { crate::functions::Parameter::Matrix },
-                    "SquareMatrix" => quote! { crate::functions::Parameter::SquareMatrix },
-                    "bool" => quote! { crate::functions::Parameter::Boolean },
-                    _ => unimplemented!(),
+                } else {
+                    unreachable!();
                 }
             } else {
                 unreachable!();
             }
+        });
+
+    let mut index = 0usize;
+
+    let arguments = item_fn
+        .sig
+        .inputs
+        .iter()
+        .map(|fn_arg| {
+            if is_options(fn_arg) {
+                return quote! { options };
+            }
+
+            let i = index;
+            index += 1;
+
+            if let FnArg::Typed(pat_type) = fn_arg {
+                if let Type::Path(type_path) = &*pat_type.ty {
+                    if type_path.path.is_ident("Expression") {
+                        // Expressions don't require conversion (and the conversion
+                        // would be infallible, which the `?` operator cannot handle).
+                        return quote! { arguments[#i].clone() };
+                    }
+
+                    if type_path.path.is_ident("Expressions") {
+                        // The final parameter receives all remaining arguments.
+                        return quote! { arguments[#i..].to_vec() };
+                    }
+                }
+            }
+
+            quote! { arguments[#i].clone().try_into()? }
+        })
+        .collect::<Vec<_>>();
+
+    let returns_result = if let ReturnType::Type(_, ty) = &item_fn.sig.output {
+        if let Type::Path(type_path) = &**ty {
+            type_path.path.segments.last().unwrap().ident == "Result"
         } else {
-            unreachable!();
+            false
         }
-    });
+    } else {
+        false
+    };
 
-    let arguments =
-        (0..item_fn.sig.inputs.len()).map(|i| quote! { arguments[#i].clone().try_into()? });
+    let value = if returns_result {
+        quote! { #name(#(#arguments),*)? }
+    } else {
+        quote! { #name(#(#arguments),*) }
+    };
 
+    // Functions report invalid arguments by returning them as errors,
+    // which are expressions and therefore larger than clippy's threshold.
     let tokens = quote! {
+        #[allow(clippy::result_large_err)]
         #item_fn
 
         pub(crate) const #metadata_name: crate::functions::Metadata = crate::functions::Metadata {
@@ -77,11 +167,14 @@ pub fn function(attr: TokenStream, item: TokenStream) -> TokenStream {
             parameters: &[#(#parameters),*],
             examples: &#examples_argument,
             categories: &#categories_argument,
+            pure: #pure_argument,
         };
 
-        pub(crate) fn #proxy_name(arguments: &[crate::expression::Expression]) ->
-            ::std::result::Result<crate::expression::Expression, crate::expression::Expression> {
-            ::std::result::Result::Ok(#name(#(#arguments),*).into())
+        pub(crate) fn #proxy_name(
+            arguments: &[crate::expression::Expression],
+            options: &crate::evaluate::Options,
+        ) -> ::std::result::Result<crate::expression::Expression, crate::functions::FunctionError> {
+            ::std::result::Result::Ok(#value.into())
         }
     };
 
@@ -121,3 +214,132 @@ pub fn functions(input: TokenStream) -> TokenStream {
 
     tokens.into()
 }
+
+/// Arguments of `assert_evaluates_to`.
+struct EvaluatesTo {
+    expression: LitStr,
+    result: LitStr,
+}
+
+impl Parse for EvaluatesTo {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expression = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let result = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        Ok(EvaluatesTo { expression, result })
+    }
+}
+
+/// Arguments of `assert_evaluation_error`.
+struct EvaluationError {
+    expression: LitStr,
+    pattern: Pat,
+}
+
+impl Parse for EvaluationError {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expression = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let pattern = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        Ok(EvaluationError {
+            expression,
+            pattern,
+        })
+    }
+}
+
+/// Asserts that evaluating the expression given as the first argument
+/// (a string literal) without any variables set results in an expression
+/// whose string representation is the second argument (a string literal).
+///
+/// On failure, the panic message shows the expression along with
+/// the expected and the actual result.
+///
+/// ```ignore
+/// assert_evaluates_to!("1/2 + 0.5", "1");
+/// ```
+#[proc_macro]
+pub fn assert_evaluates_to(input: TokenStream) -> TokenStream {
+    let EvaluatesTo { expression, result } = parse_macro_input!(input as EvaluatesTo);
+
+    let tokens = quote! {{
+        let expression: &str = #expression;
+        let expected: &str = #result;
+
+        match ::savage_core::__private::evaluate_str(expression) {
+            ::std::result::Result::Ok(result) => {
+                let result = result.to_string();
+
+                if result != expected {
+                    ::std::panic!(
+                        "evaluating `{}` returned an unexpected result\n  expected: {}\n  actual:   {}",
+                        expression,
+                        expected,
+                        result,
+                    );
+                }
+            }
+            ::std::result::Result::Err(error) => ::std::panic!(
+                "evaluating `{}` failed\n  expected: {}\n  error:    {:?}",
+                expression,
+                expected,
+                error,
+            ),
+        }
+    }};
+
+    tokens.into()
+}
+
+/// Asserts that evaluating the expression given as the first argument
+/// (a string literal) without any variables set fails with an error matching
+/// the second argument, which is either the name of a variant of
+/// `savage_core::evaluate::Error` or a pattern for such a variant.
+///
+/// On failure, the panic message shows the expression along with
+/// the expected error and the actual outcome.
+///
+/// ```ignore
+/// assert_evaluation_error!("1 / 0", DivisionByZero);
+/// assert_evaluation_error!("[1, 2] + [1]", IncompatibleShapes { shape_1: (2, 1), .. });
+/// ```
+#[proc_macro]
+pub fn assert_evaluation_error(input: TokenStream) -> TokenStream {
+    let EvaluationError {
+        expression,
+        pattern,
+    } = parse_macro_input!(input as EvaluationError);
+
+    let pattern = match &pattern {
+        Pat::Ident(pat_ident) if pat_ident.subpat.is_none() => quote! { #pattern { .. } },
+        _ => quote! { #pattern },
+    };
+
+    let pattern_string = pattern.to_string();
+
+    let tokens = quote! {{
+        let expression: &str = #expression;
+
+        match ::savage_core::__private::evaluate_str(expression) {
+            ::std::result::Result::Err(::savage_core::evaluate::Error::#pattern) => (),
+            ::std::result::Result::Err(error) => ::std::panic!(
+                "evaluating `{}` failed with an unexpected error\n  expected: {}\n  actual:   {:?}",
+                expression,
+                #pattern_string,
+                error,
+            ),
+            ::std::result::Result::Ok(result) => ::std::panic!(
+                "evaluating `{}` succeeded unexpectedly\n  expected: {}\n  result:   {}",
+                expression,
+                #pattern_string,
+                result,
+            ),
+        }
+    }};
+
+    tokens.into()
+}